@@ -1,10 +1,11 @@
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use http_body::Body as HttpBody;
 use http_body_util::combinators::BoxBody;
 //use sync_wrapper::SyncWrapper;
@@ -47,6 +48,69 @@ pin_project! {
     }
 }
 
+pin_project! {
+    /// A body that sends a trailers frame after the inner body.
+    struct WithTrailers<B> {
+        #[pin]
+        inner: B,
+        trailers: Option<http::HeaderMap>,
+    }
+}
+
+pin_project! {
+    /// A body that keeps a copy of the bytes it sends.
+    struct Recorded<B> {
+        #[pin]
+        inner: B,
+        replay: Replay,
+    }
+}
+
+pin_project! {
+    /// A body that isn't sent until the server answers `100 Continue`, or a
+    /// timeout elapses.
+    struct Continued<B> {
+        #[pin]
+        inner: B,
+        gate: ContinueGate,
+        #[pin]
+        sleep: Option<Sleep>,
+        timeout: Duration,
+    }
+}
+
+/// Decides whether a body sent with `Expect: 100-continue` is sent.
+#[derive(Clone)]
+pub(crate) struct ContinueGate {
+    state: Arc<Mutex<GateState>>,
+}
+
+struct GateState {
+    status: GateStatus,
+    waker: Option<Waker>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GateStatus {
+    Waiting,
+    Open,
+    Aborted,
+}
+
+/// The bytes sent by a streaming body, kept up to a limit so that the body
+/// can be sent again.
+#[derive(Clone)]
+pub(crate) struct Replay {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+struct ReplayState {
+    // `None` once the limit was exceeded
+    buf: Option<BytesMut>,
+    limit: usize,
+    complete: bool,
+}
+
 /// Converts any `impl Body` into a `impl Stream` of just its DATA frames.
 #[cfg(any(feature = "stream", feature = "multipart",))]
 pub(crate) struct DataStream<B>(pub(crate) B);
@@ -117,6 +181,47 @@ impl Body {
         }
     }
 
+    /// Send `trailers` after this body.
+    ///
+    /// If the body already ends with trailers, they are merged.
+    pub(crate) fn with_trailers(self, trailers: http::HeaderMap) -> Body {
+        use http_body_util::BodyExt;
+
+        let body = match self.inner {
+            Inner::Reusable(bytes) => WithTrailers {
+                inner: http_body_util::Full::new(bytes).map_err(|never| match never {}),
+                trailers: Some(trailers),
+            }
+            .boxed(),
+            Inner::Streaming(body) => WithTrailers {
+                inner: body,
+                trailers: Some(trailers),
+            }
+            .boxed(),
+        };
+        Body {
+            inner: Inner::Streaming(body),
+        }
+    }
+
+    /// Encode this body with a custom content coding.
+    pub(crate) fn transform(
+        self,
+        transform: Box<dyn crate::codec::Transform>,
+    ) -> std::io::Result<Body> {
+        use http_body_util::BodyExt;
+
+        let inner = match self.inner {
+            Inner::Reusable(bytes) => {
+                Inner::Reusable(crate::codec::transform_bytes(transform, &bytes)?)
+            }
+            Inner::Streaming(body) => {
+                Inner::Streaming(crate::codec::transformed(body, transform).boxed())
+            }
+        };
+        Ok(Body { inner })
+    }
+
     pub(crate) fn empty() -> Body {
         Body::reusable(Bytes::new())
     }
@@ -155,6 +260,16 @@ impl Body {
         }
     }
 
+    /// Like `wrap`, for bodies that are `Send` but not `Sync`.
+    pub(crate) fn wrap_send<B>(inner: B) -> Body
+    where
+        B: HttpBody + Send + 'static,
+        B::Data: Into<Bytes>,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Body::wrap(SyncBody::new(inner))
+    }
+
     pub(crate) fn try_reuse(self) -> (Option<Bytes>, Self) {
         let reuse = match self.inner {
             Inner::Reusable(ref chunk) => Some(chunk.clone()),
@@ -164,6 +279,74 @@ impl Body {
         (reuse, self)
     }
 
+    /// Keeps a copy of the bytes of a streaming body as they are sent, up to
+    /// `limit`, so that it can be replayed.
+    pub(crate) fn recorded(self, limit: usize) -> (Body, Option<Replay>) {
+        use http_body_util::BodyExt;
+
+        match self.inner {
+            Inner::Reusable(_) => (self, None),
+            Inner::Streaming(inner) => {
+                let replay = Replay {
+                    state: Arc::new(Mutex::new(ReplayState {
+                        buf: Some(BytesMut::new()),
+                        limit,
+                        complete: false,
+                    })),
+                };
+                let body = Recorded {
+                    inner,
+                    replay: replay.clone(),
+                };
+                let body = Body {
+                    inner: Inner::Streaming(body.boxed()),
+                };
+                (body, Some(replay))
+            }
+        }
+    }
+
+    /// Holds back this body until the returned gate is opened, or `timeout`
+    /// elapses after the body is first polled.
+    ///
+    /// An empty body isn't held back, and has no gate.
+    pub(crate) fn expect_continue(self, timeout: Duration) -> (Body, Option<ContinueGate>) {
+        use http_body_util::BodyExt;
+
+        if self.is_end_stream() {
+            return (self, None);
+        }
+        let gate = ContinueGate {
+            state: Arc::new(Mutex::new(GateState {
+                status: GateStatus::Waiting,
+                waker: None,
+            })),
+        };
+        let body = Continued {
+            inner: self,
+            gate: gate.clone(),
+            sleep: None,
+            timeout,
+        };
+        let body = Body {
+            inner: Inner::Streaming(body.map_err(Into::into).boxed()),
+        };
+        (body, Some(gate))
+    }
+
+    /// Sends this body no faster than `rate` bytes per second.
+    pub(crate) fn throttled(self, rate: u64) -> Body {
+        use http_body_util::BodyExt;
+
+        if self.is_end_stream() {
+            return self;
+        }
+        let body = super::throttle::Throttled::new(self, rate);
+        Body {
+            inner: Inner::Streaming(body.map_err(Into::into).boxed()),
+        }
+    }
+
     pub(crate) fn try_clone(&self) -> Option<Body> {
         match self.inner {
             Inner::Reusable(ref chunk) => Some(Body::reusable(chunk.clone())),
@@ -294,6 +477,195 @@ impl HttpBody for Body {
     }
 }
 
+// ===== impl WithTrailers =====
+
+impl<B> HttpBody for WithTrailers<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        match futures_core::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => match frame.into_trailers() {
+                Ok(mut trailers) => {
+                    if let Some(extra) = this.trailers.take() {
+                        trailers.extend(extra);
+                    }
+                    Poll::Ready(Some(Ok(hyper::body::Frame::trailers(trailers))))
+                }
+                Err(frame) => Poll::Ready(Some(Ok(frame))),
+            },
+            Some(Err(err)) => Poll::Ready(Some(Err(err))),
+            None => Poll::Ready(
+                this.trailers
+                    .take()
+                    .map(|trailers| Ok(hyper::body::Frame::trailers(trailers))),
+            ),
+        }
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        // Never report an exact size, so HTTP/1.1 uses chunked encoding,
+        // which is required to send trailers.
+        let mut hint = http_body::SizeHint::new();
+        hint.set_lower(self.inner.size_hint().lower());
+        hint
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.trailers.is_none() && self.inner.is_end_stream()
+    }
+}
+
+// ===== impl Recorded =====
+
+impl<B> HttpBody for Recorded<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let frame = futures_core::ready!(this.inner.as_mut().poll_frame(cx));
+        let mut state = this.replay.state.lock().expect("lock poisoned");
+        match frame {
+            Some(Ok(ref frame)) => {
+                if let Some(data) = frame.data_ref() {
+                    let limit = state.limit;
+                    let fits = state
+                        .buf
+                        .as_ref()
+                        .map_or(false, |buf| buf.len() + data.len() <= limit);
+                    if fits {
+                        state.buf.as_mut().expect("fits").extend_from_slice(data);
+                    } else {
+                        state.buf = None;
+                    }
+                }
+            }
+            Some(Err(_)) => state.buf = None,
+            None => state.complete = true,
+        }
+        // a body with a known length isn't polled after its last frame
+        if this.inner.is_end_stream() {
+            state.complete = true;
+        }
+        Poll::Ready(frame)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+// ===== impl Continued =====
+
+impl<B> HttpBody for Continued<B>
+where
+    B: HttpBody<Data = Bytes>,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        match this.gate.poll(cx) {
+            GateStatus::Open => (),
+            GateStatus::Aborted => {
+                return Poll::Ready(Some(Err(
+                    "request body not sent, a final response arrived first".into(),
+                )));
+            }
+            GateStatus::Waiting => {
+                if this.sleep.is_none() {
+                    this.sleep.set(Some(tokio::time::sleep(*this.timeout)));
+                }
+                let sleep = this.sleep.as_mut().as_pin_mut().expect("sleep was set");
+                futures_core::ready!(sleep.poll(cx));
+                log::debug!("no 100 Continue response, sending the request body");
+                this.gate.open();
+            }
+        }
+        this.inner.poll_frame(cx).map_err(Into::into)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+// ===== impl ContinueGate =====
+
+impl ContinueGate {
+    /// Sends the body, after a `100 Continue` response.
+    pub(crate) fn open(&self) {
+        self.set(GateStatus::Open);
+    }
+
+    /// Never sends the body, since a final response arrived before it was
+    /// sent.
+    pub(crate) fn abort(&self) {
+        self.set(GateStatus::Aborted);
+    }
+
+    fn set(&self, status: GateStatus) {
+        let mut state = self.state.lock().expect("lock poisoned");
+        if state.status == GateStatus::Waiting {
+            state.status = status;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn poll(&self, cx: &mut Context) -> GateStatus {
+        let mut state = self.state.lock().expect("lock poisoned");
+        if state.status == GateStatus::Waiting {
+            state.waker = Some(cx.waker().clone());
+        }
+        state.status
+    }
+}
+
+// ===== impl Replay =====
+
+impl Replay {
+    /// Takes the bytes of the body, if all of it was sent, and it is no
+    /// larger than `limit`.
+    pub(crate) fn take(&self, limit: usize) -> Option<Bytes> {
+        let mut state = self.state.lock().expect("lock poisoned");
+        match state.buf {
+            Some(ref buf) if state.complete && buf.len() <= limit => {
+                state.buf.take().map(BytesMut::freeze)
+            }
+            _ => None,
+        }
+    }
+}
+
 // ===== impl TotalTimeoutBody =====
 
 pub(crate) fn total_timeout<B>(body: B, timeout: Pin<Box<Sleep>>) -> TotalTimeoutBody<B> {
@@ -499,6 +871,56 @@ where
     }
 }
 
+// ===== impl SyncBody =====
+
+pin_project! {
+    /// Makes a `Send` body `Sync`, by only touching it through `&mut`.
+    ///
+    /// The size hint and end of stream are copied out after every frame, so
+    /// they can be read through `&self`.
+    struct SyncBody<B> {
+        #[pin]
+        inner: sync_wrapper::SyncWrapper<B>,
+        size_hint: http_body::SizeHint,
+        is_end_stream: bool,
+    }
+}
+
+impl<B: HttpBody> SyncBody<B> {
+    fn new(inner: B) -> Self {
+        SyncBody {
+            size_hint: inner.size_hint(),
+            is_end_stream: inner.is_end_stream(),
+            inner: sync_wrapper::SyncWrapper::new(inner),
+        }
+    }
+}
+
+impl<B: HttpBody> HttpBody for SyncBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let mut inner = this.inner.get_pin_mut();
+        let frame = futures_core::ready!(inner.as_mut().poll_frame(cx));
+        *this.size_hint = inner.size_hint();
+        *this.is_end_stream = inner.is_end_stream();
+        Poll::Ready(frame)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.size_hint
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.is_end_stream
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use http_body::Body as _;
@@ -3,32 +3,54 @@ use std::any::Any;
 use std::future::Future;
 use std::net::IpAddr;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::Duration;
 use std::{collections::HashMap, convert::TryInto, net::SocketAddr};
 use std::{fmt, str};
 
 use super::decoder::Accepts;
-use super::request::{Request, RequestBuilder};
-use super::response::Response;
+#[cfg(feature = "http2")]
+use super::h2_fallback::H2Fallback;
+use super::hedge::Hedge;
+#[cfg(feature = "http2")]
+use super::lanes::{self, Lane, Lanes};
+#[cfg(feature = "http3")]
+use super::race::{Race, Winner};
+use super::limit::{Limits, Queued};
+use super::request::{OnInformational, Request, RequestBuilder};
+use super::response::{
+    ExpectedContentType, RequestCounts, RequestHeaders, Response, RetryAfterWaits,
+};
+use super::shutdown::{InFlight, Shutdown};
+use super::throttle::Throttled;
+use super::body::{ContinueGate, Replay, ResponseBody};
 use super::Body;
 #[cfg(feature = "http3")]
 use crate::async_impl::h3_client::connect::H3Connector;
 #[cfg(feature = "http3")]
-use crate::async_impl::h3_client::{H3Client, H3ResponseFuture};
+use crate::async_impl::h3_client::{H3Client, H3ResponseFuture, Http3ZeroRtt};
+use crate::circuit::{Breakers, CircuitBreaker, Permit};
 use crate::connect::{
     sealed::{Conn, Unnameable},
     BoxedConnectorLayer, BoxedConnectorService, Connector, ConnectorBuilder,
 };
 #[cfg(feature = "cookies")]
 use crate::cookie;
+use crate::hsts;
 #[cfg(feature = "hickory-dns")]
 use crate::dns::hickory::HickoryDnsResolver;
-use crate::dns::{gai::GaiResolver, DnsResolverWithOverrides, DynResolver, Resolve};
+use crate::dns::{
+    gai::GaiResolver, DnsResolverWithFallback, DnsResolverWithOverrides, DynResolver, Resolve,
+};
 use crate::error::{self, BoxError};
 use crate::into_url::try_uri;
 use crate::redirect::{self, remove_sensitive_headers};
+use crate::interceptor::{Interceptor, RequestParts, ResponseParts};
+#[cfg(feature = "http2")]
+use crate::http2::{Http2Event, Http2EventCallback};
+use crate::pool::{PoolEvent, PoolEventCallback, Slots};
+use crate::trace_context::ContextSource;
 #[cfg(feature = "__rustls")]
 use crate::tls::CertificateRevocationList;
 #[cfg(feature = "__tls")]
@@ -41,12 +63,15 @@ use crate::{IntoUrl, Method, Proxy, StatusCode, Url};
 use bytes::Bytes;
 use http::header::{
     Entry, HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH,
-    CONTENT_TYPE, LOCATION, PROXY_AUTHORIZATION, RANGE, REFERER, TRANSFER_ENCODING, USER_AGENT,
+    CONTENT_TYPE, EXPECT, HOST, LOCATION, PROXY_AUTHORIZATION, RANGE, REFERER, RETRY_AFTER,
+    TRANSFER_ENCODING, USER_AGENT,
 };
 use http::uri::Scheme;
-use http::Uri;
+use http::{Request as HttpRequest, Uri};
+use http_body::Body as HttpBody;
 use hyper_util::client::legacy::connect::HttpConnector;
 use log::debug;
+use mime::Mime;
 #[cfg(feature = "default-tls")]
 use native_tls_crate::TlsConnector;
 use pin_project_lite::pin_project;
@@ -54,12 +79,26 @@ use pin_project_lite::pin_project;
 use quinn::TransportConfig;
 #[cfg(feature = "http3")]
 use quinn::VarInt;
+#[cfg(feature = "http3")]
+use quinn::congestion::ControllerFactory;
 use tokio::time::Sleep;
-use tower::util::BoxCloneSyncServiceLayer;
-use tower::{Layer, Service};
+use tower::util::{BoxCloneSyncService, BoxCloneSyncServiceLayer, Oneshot};
+use tower::{Layer, Service, ServiceExt};
 
 type HyperResponseFuture = hyper_util::client::legacy::ResponseFuture;
 
+/// The service sending the requests of a `Client`, which
+/// [`ClientBuilder::request_layer()`] wraps.
+pub type RequestService =
+    BoxCloneSyncService<HttpRequest<Body>, http::Response<Body>, BoxError>;
+
+type BoxedRequestLayer = BoxCloneSyncServiceLayer<
+    RequestService,
+    HttpRequest<Body>,
+    http::Response<Body>,
+    BoxError,
+>;
+
 /// An asynchronous `Client` to make Requests with.
 ///
 /// The Client has various configuration values to tweak, but the defaults
@@ -84,6 +123,12 @@ pub struct ClientBuilder {
     config: Config,
 }
 
+enum HeaderValueValidation {
+    Permissive,
+    Strict,
+    Sanitize,
+}
+
 enum HttpVersionPref {
     Http1,
     #[cfg(feature = "http2")]
@@ -93,9 +138,15 @@ enum HttpVersionPref {
     All,
 }
 
+const CAPABILITIES_TTL: Duration = Duration::from_secs(5 * 60);
+
+type DeprecationCallback = Arc<dyn Fn(&Url, &crate::notice::Deprecation) + Send + Sync>;
+
 struct Config {
     // NOTE: When adding a new field, update `fmt::Debug for ClientBuilder`
     accepts: Accepts,
+    codecs: Vec<(String, Arc<dyn crate::codec::Codec>)>,
+    accept_encoding: Option<Vec<crate::codec::Preference>>,
     headers: HeaderMap,
     #[cfg(feature = "__tls")]
     hostname_verification: bool,
@@ -107,6 +158,9 @@ struct Config {
     connection_verbose: bool,
     pool_idle_timeout: Option<Duration>,
     pool_max_idle_per_host: usize,
+    max_connections_per_host: Option<usize>,
+    pool_acquire_timeout: Option<Duration>,
+    pool_events: Option<PoolEventCallback>,
     tcp_keepalive: Option<Duration>,
     #[cfg(any(feature = "native-tls", feature = "__rustls"))]
     identity: Option<Identity>,
@@ -114,8 +168,30 @@ struct Config {
     auto_sys_proxy: bool,
     redirect_policy: redirect::Policy,
     referer: bool,
+    redirect_body_buffer_limit: Option<usize>,
+    retry_body_buffer_limit: Option<usize>,
+    retry_after: Option<Duration>,
+    capabilities_ttl: Duration,
+    on_deprecation: Option<DeprecationCallback>,
+    expected_content_type: Option<Mime>,
+    #[cfg(feature = "json")]
+    canonical_json: bool,
+    #[cfg(feature = "digest")]
+    verify_content_digest: bool,
+    track_upgrades: bool,
+    close_connections_on_shutdown: bool,
+    hedge_after: Option<Duration>,
+    max_upload_rate: Option<u64>,
+    max_download_rate: Option<u64>,
+    max_concurrent_requests: Option<usize>,
+    max_concurrent_requests_per_host: Option<usize>,
+    concurrency_queue_timeout: Option<Duration>,
+    circuit_breaker: Option<CircuitBreaker>,
+    trace_context: Option<Arc<dyn ContextSource>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
     read_timeout: Option<Duration>,
     timeout: Option<Duration>,
+    method_timeouts: MethodTimeouts,
     #[cfg(feature = "__tls")]
     root_certs: Vec<Certificate>,
     #[cfg(feature = "__tls")]
@@ -126,21 +202,40 @@ struct Config {
     tls_built_in_certs_native: bool,
     #[cfg(feature = "__rustls")]
     crls: Vec<CertificateRevocationList>,
+    #[cfg(feature = "rustls-tls-ocsp")]
+    tls_ocsp: Option<tls::OcspPolicy>,
+    #[cfg(feature = "__rustls")]
+    tls_ct_policy: Option<tls::CtPolicy>,
     #[cfg(feature = "__tls")]
     min_tls_version: Option<tls::Version>,
     #[cfg(feature = "__tls")]
     max_tls_version: Option<tls::Version>,
     #[cfg(feature = "__tls")]
+    tls_cipher_suites: Option<Vec<tls::CipherSuite>>,
+    #[cfg(feature = "__tls")]
+    tls_host_options: HashMap<String, tls::TlsOptions>,
+    #[cfg(feature = "__tls")]
+    tls_key_exchange_groups: Option<Vec<tls::KeyExchangeGroup>>,
+    #[cfg(feature = "__tls")]
+    tls_server_names: HashMap<String, String>,
+    #[cfg(feature = "__tls")]
     tls_info: bool,
     #[cfg(feature = "__tls")]
     tls: TlsBackend,
     connector_layers: Vec<BoxedConnectorLayer>,
+    request_layers: Vec<BoxedRequestLayer>,
     http_version_pref: HttpVersionPref,
+    header_value_validation: HeaderValueValidation,
     http09_responses: bool,
     http1_title_case_headers: bool,
     http1_allow_obsolete_multiline_headers_in_responses: bool,
     http1_ignore_invalid_headers_in_responses: bool,
     http1_allow_spaces_after_header_name_in_responses: bool,
+    http1_probe_idle_connections: bool,
+    http1_preserve_raw_headers: bool,
+    allowed_ports: Option<Vec<u16>>,
+    #[cfg(feature = "http2")]
+    http2_cleartext_prior_knowledge: bool,
     #[cfg(feature = "http2")]
     http2_initial_stream_window_size: Option<u32>,
     #[cfg(feature = "http2")]
@@ -157,18 +252,35 @@ struct Config {
     http2_keep_alive_timeout: Option<Duration>,
     #[cfg(feature = "http2")]
     http2_keep_alive_while_idle: bool,
+    #[cfg(feature = "http2")]
+    http2_fallback: Option<Duration>,
+    #[cfg(feature = "http2")]
+    http2_events: Option<Http2EventCallback>,
+    #[cfg(feature = "http2")]
+    http2_max_concurrent_streams: Option<u32>,
     local_address: Option<IpAddr>,
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
     interface: Option<String>,
     nodelay: bool,
     #[cfg(feature = "cookies")]
     cookie_store: Option<Arc<dyn cookie::CookieStore>>,
+    #[cfg(feature = "cookies")]
+    cookie_observer: Option<cookie::CookieObserver>,
+    hsts_store: Option<Arc<dyn hsts::HstsStore>>,
     hickory_dns: bool,
     error: Option<crate::Error>,
     https_only: bool,
     #[cfg(feature = "http3")]
     tls_enable_early_data: bool,
     #[cfg(feature = "http3")]
+    http3_zero_rtt: Http3ZeroRtt,
+    #[cfg(feature = "http3")]
+    http3_race_tcp: bool,
+    #[cfg(feature = "http3")]
+    http3_pool_max_idle_per_host: usize,
+    #[cfg(feature = "http3")]
+    http3_max_streams_per_connection: Option<u32>,
+    #[cfg(feature = "http3")]
     quic_max_idle_timeout: Option<Duration>,
     #[cfg(feature = "http3")]
     quic_stream_receive_window: Option<VarInt>,
@@ -176,8 +288,12 @@ struct Config {
     quic_receive_window: Option<VarInt>,
     #[cfg(feature = "http3")]
     quic_send_window: Option<u64>,
+    #[cfg(feature = "http3")]
+    quic_congestion_controller: Option<Arc<dyn ControllerFactory + Send + Sync>>,
     dns_overrides: HashMap<String, Vec<SocketAddr>>,
     dns_resolver: Option<Arc<dyn Resolve>>,
+    dns_fallback: Option<Arc<dyn Resolve>>,
+    dns_timeout: Option<Duration>,
 }
 
 impl Default for ClientBuilder {
@@ -198,6 +314,8 @@ impl ClientBuilder {
             config: Config {
                 error: None,
                 accepts: Accepts::default(),
+                codecs: Vec::new(),
+                accept_encoding: None,
                 headers,
                 #[cfg(feature = "__tls")]
                 hostname_verification: true,
@@ -209,15 +327,40 @@ impl ClientBuilder {
                 connection_verbose: false,
                 pool_idle_timeout: Some(Duration::from_secs(90)),
                 pool_max_idle_per_host: usize::MAX,
+                max_connections_per_host: None,
+                pool_acquire_timeout: None,
+                pool_events: None,
                 // TODO: Re-enable default duration once hyper's HttpConnector is fixed
                 // to no longer error when an option fails.
                 tcp_keepalive: None, //Some(Duration::from_secs(60)),
                 proxies: Vec::new(),
                 auto_sys_proxy: true,
                 redirect_policy: redirect::Policy::default(),
+                capabilities_ttl: CAPABILITIES_TTL,
+                on_deprecation: None,
+                expected_content_type: None,
+                #[cfg(feature = "json")]
+                canonical_json: false,
+                #[cfg(feature = "digest")]
+                verify_content_digest: false,
+                track_upgrades: false,
+                close_connections_on_shutdown: false,
+                hedge_after: None,
+                max_upload_rate: None,
+                max_download_rate: None,
+                max_concurrent_requests: None,
+                max_concurrent_requests_per_host: None,
+                concurrency_queue_timeout: None,
+                circuit_breaker: None,
+                trace_context: None,
+                interceptors: Vec::new(),
                 referer: true,
+                redirect_body_buffer_limit: None,
+                retry_body_buffer_limit: None,
+                retry_after: None,
                 read_timeout: None,
                 timeout: None,
+                method_timeouts: MethodTimeouts::default(),
                 #[cfg(feature = "__tls")]
                 root_certs: Vec::new(),
                 #[cfg(feature = "__tls")]
@@ -230,21 +373,40 @@ impl ClientBuilder {
                 identity: None,
                 #[cfg(feature = "__rustls")]
                 crls: vec![],
+                #[cfg(feature = "rustls-tls-ocsp")]
+                tls_ocsp: None,
+                #[cfg(feature = "__rustls")]
+                tls_ct_policy: None,
                 #[cfg(feature = "__tls")]
                 min_tls_version: None,
                 #[cfg(feature = "__tls")]
                 max_tls_version: None,
                 #[cfg(feature = "__tls")]
+                tls_cipher_suites: None,
+                #[cfg(feature = "__tls")]
+                tls_host_options: HashMap::new(),
+                #[cfg(feature = "__tls")]
+                tls_key_exchange_groups: None,
+                #[cfg(feature = "__tls")]
+                tls_server_names: HashMap::new(),
+                #[cfg(feature = "__tls")]
                 tls_info: false,
                 #[cfg(feature = "__tls")]
                 tls: TlsBackend::default(),
                 connector_layers: Vec::new(),
+                request_layers: Vec::new(),
                 http_version_pref: HttpVersionPref::All,
+                header_value_validation: HeaderValueValidation::Permissive,
                 http09_responses: false,
                 http1_title_case_headers: false,
                 http1_allow_obsolete_multiline_headers_in_responses: false,
                 http1_ignore_invalid_headers_in_responses: false,
                 http1_allow_spaces_after_header_name_in_responses: false,
+                http1_probe_idle_connections: false,
+                http1_preserve_raw_headers: false,
+                allowed_ports: None,
+                #[cfg(feature = "http2")]
+                http2_cleartext_prior_knowledge: false,
                 #[cfg(feature = "http2")]
                 http2_initial_stream_window_size: None,
                 #[cfg(feature = "http2")]
@@ -261,6 +423,12 @@ impl ClientBuilder {
                 http2_keep_alive_timeout: None,
                 #[cfg(feature = "http2")]
                 http2_keep_alive_while_idle: false,
+                #[cfg(feature = "http2")]
+                http2_fallback: None,
+                #[cfg(feature = "http2")]
+                http2_events: None,
+                #[cfg(feature = "http2")]
+                http2_max_concurrent_streams: None,
                 local_address: None,
                 #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
                 interface: None,
@@ -268,11 +436,22 @@ impl ClientBuilder {
                 hickory_dns: cfg!(feature = "hickory-dns"),
                 #[cfg(feature = "cookies")]
                 cookie_store: None,
+                #[cfg(feature = "cookies")]
+                cookie_observer: None,
+                hsts_store: None,
                 https_only: false,
                 dns_overrides: HashMap::new(),
                 #[cfg(feature = "http3")]
                 tls_enable_early_data: false,
                 #[cfg(feature = "http3")]
+                http3_zero_rtt: Http3ZeroRtt::Disabled,
+                #[cfg(feature = "http3")]
+                http3_race_tcp: false,
+                #[cfg(feature = "http3")]
+                http3_pool_max_idle_per_host: usize::MAX,
+                #[cfg(feature = "http3")]
+                http3_max_streams_per_connection: None,
+                #[cfg(feature = "http3")]
                 quic_max_idle_timeout: None,
                 #[cfg(feature = "http3")]
                 quic_stream_receive_window: None,
@@ -280,7 +459,11 @@ impl ClientBuilder {
                 quic_receive_window: None,
                 #[cfg(feature = "http3")]
                 quic_send_window: None,
+                #[cfg(feature = "http3")]
+                quic_congestion_controller: None,
                 dns_resolver: None,
+                dns_fallback: None,
+                dns_timeout: None,
             },
         }
     }
@@ -293,6 +476,10 @@ impl ClientBuilder {
     ///
     /// This method fails if a TLS backend cannot be initialized, or the resolver
     /// cannot load the system configuration.
+    ///
+    /// It also fails if options that can't be used together were set, such
+    /// as an [`Identity`] that the TLS backend can't use. The error lists
+    /// each conflict, in [`Error::conflicts()`](crate::Error::conflicts).
     pub fn build(self) -> crate::Result<Client> {
         let config = self.config;
 
@@ -300,6 +487,11 @@ impl ClientBuilder {
             return Err(err);
         }
 
+        let conflicts = config_conflicts(&config);
+        if !conflicts.is_empty() {
+            return Err(crate::error::conflicts(conflicts));
+        }
+
         let mut proxies = config.proxies;
         if config.auto_sys_proxy {
             proxies.push(Proxy::system());
@@ -310,6 +502,12 @@ impl ClientBuilder {
         #[cfg(feature = "http3")]
         let mut h3_connector = None;
 
+        #[cfg(feature = "http2")]
+        let h2_fallback = config.http2_fallback.map(|ttl| Arc::new(H2Fallback::new(ttl)));
+        #[cfg(all(feature = "http2", feature = "default-tls"))]
+        #[cfg_attr(not(feature = "native-tls-alpn"), allow(unused_mut))]
+        let mut native_http1 = None;
+
         let mut connector_builder = {
             #[cfg(feature = "__tls")]
             fn user_agent(headers: &HeaderMap) -> Option<HeaderValue> {
@@ -326,6 +524,13 @@ impl ClientBuilder {
             if let Some(dns_resolver) = config.dns_resolver {
                 resolver = dns_resolver;
             }
+            if config.dns_fallback.is_some() || config.dns_timeout.is_some() {
+                resolver = Arc::new(DnsResolverWithFallback::new(
+                    resolver,
+                    config.dns_fallback,
+                    config.dns_timeout,
+                ));
+            }
             if !config.dns_overrides.is_empty() {
                 resolver = Arc::new(DnsResolverWithOverrides::new(
                     resolver,
@@ -338,11 +543,12 @@ impl ClientBuilder {
             #[cfg(all(feature = "http3", feature = "__rustls"))]
             let build_h3_connector =
                 |resolver,
-                 tls,
+                 mut tls: rustls::ClientConfig,
                  quic_max_idle_timeout: Option<Duration>,
                  quic_stream_receive_window,
                  quic_receive_window,
                  quic_send_window,
+                 quic_congestion_controller,
                  local_address,
                  http_version_pref: &HttpVersionPref| {
                     let mut transport_config = TransportConfig::default();
@@ -365,6 +571,13 @@ impl ClientBuilder {
                         transport_config.send_window(send_window);
                     }
 
+                    if let Some(congestion_controller) = quic_congestion_controller {
+                        transport_config.congestion_controller_factory(congestion_controller);
+                    }
+
+                    // QUIC only speaks HTTP/3, whatever TCP connections offer
+                    tls.alpn_protocols = vec!["h3".into()];
+
                     let res = H3Connector::new(
                         DynResolver::new(resolver),
                         tls,
@@ -384,76 +597,119 @@ impl ClientBuilder {
                     }
                 };
 
+            #[cfg(feature = "__tls")]
+            let proxy_identities = proxies
+                .iter()
+                .filter_map(|proxy| proxy.tls_identity().transpose())
+                .map(|res| res.map(|(host, identity)| (host.clone(), identity.clone())))
+                .collect::<crate::Result<Vec<_>>>()?;
+
             #[cfg(feature = "__tls")]
             match config.tls {
                 #[cfg(feature = "default-tls")]
                 TlsBackend::Default => {
-                    let mut tls = TlsConnector::builder();
-
-                    #[cfg(all(feature = "native-tls-alpn", not(feature = "http3")))]
-                    {
-                        match config.http_version_pref {
-                            HttpVersionPref::Http1 => {
-                                tls.request_alpns(&["http/1.1"]);
-                            }
-                            #[cfg(feature = "http2")]
-                            HttpVersionPref::Http2 => {
-                                tls.request_alpns(&["h2"]);
-                            }
-                            HttpVersionPref::All => {
-                                tls.request_alpns(&["h2", "http/1.1"]);
+                    #[cfg_attr(
+                        not(any(feature = "native-tls", feature = "__rustls")),
+                        allow(unused_variables)
+                    )]
+                    let native_tls = |identity: Option<tls::Identity>| -> crate::Result<_> {
+                        let mut tls = TlsConnector::builder();
+
+                        #[cfg(all(feature = "native-tls-alpn", not(feature = "http3")))]
+                        {
+                            match config.http_version_pref {
+                                HttpVersionPref::Http1 => {
+                                    tls.request_alpns(&["http/1.1"]);
+                                }
+                                #[cfg(feature = "http2")]
+                                HttpVersionPref::Http2 => {
+                                    tls.request_alpns(&["h2"]);
+                                }
+                                HttpVersionPref::All => {
+                                    tls.request_alpns(&["h2", "http/1.1"]);
+                                }
                             }
                         }
-                    }
 
-                    tls.danger_accept_invalid_hostnames(!config.hostname_verification);
+                        tls.danger_accept_invalid_hostnames(!config.hostname_verification);
 
-                    tls.danger_accept_invalid_certs(!config.certs_verification);
+                        tls.danger_accept_invalid_certs(!config.certs_verification);
 
-                    tls.use_sni(config.tls_sni);
+                        tls.use_sni(config.tls_sni);
 
-                    tls.disable_built_in_roots(!config.tls_built_in_root_certs);
+                        tls.disable_built_in_roots(!config.tls_built_in_root_certs);
 
-                    for cert in config.root_certs {
-                        cert.add_to_native_tls(&mut tls);
-                    }
+                        for cert in &config.root_certs {
+                            cert.clone().add_to_native_tls(&mut tls);
+                        }
 
-                    #[cfg(feature = "native-tls")]
-                    {
-                        if let Some(id) = config.identity {
-                            id.add_to_native_tls(&mut tls)?;
+                        #[cfg(feature = "native-tls")]
+                        {
+                            if let Some(id) = identity {
+                                id.add_to_native_tls(&mut tls)?;
+                            }
                         }
-                    }
-                    #[cfg(all(feature = "__rustls", not(feature = "native-tls")))]
-                    {
-                        // Default backend + rustls Identity doesn't work.
-                        if let Some(_id) = config.identity {
-                            return Err(crate::error::builder("incompatible TLS identity type"));
+                        #[cfg(all(feature = "__rustls", not(feature = "native-tls")))]
+                        {
+                            // Default backend + rustls Identity doesn't work.
+                            if let Some(_id) = identity {
+                                return Err(crate::error::builder("incompatible TLS identity type"));
+                            }
+                        }
+
+                        if let Some(min_tls_version) = config.min_tls_version {
+                            let protocol = min_tls_version.to_native_tls().ok_or_else(|| {
+                                // TLS v1.3. This would be entirely reasonable,
+                                // native-tls just doesn't support it.
+                                // https://github.com/sfackler/rust-native-tls/issues/140
+                                crate::error::builder("invalid minimum TLS version for backend")
+                            })?;
+                            tls.min_protocol_version(Some(protocol));
                         }
+
+                        if let Some(max_tls_version) = config.max_tls_version {
+                            let protocol = max_tls_version.to_native_tls().ok_or_else(|| {
+                                // TLS v1.3.
+                                // We could arguably do max_protocol_version(None), given
+                                // that 1.4 does not exist yet, but that'd get messy in the
+                                // future.
+                                crate::error::builder("invalid maximum TLS version for backend")
+                            })?;
+                            tls.max_protocol_version(Some(protocol));
+                        }
+
+                        Ok(tls)
+                    };
+
+                    let mut proxy_tls = HashMap::new();
+                    for (host, identity) in proxy_identities {
+                        let tls = native_tls(Some(identity))?
+                            .build()
+                            .map_err(crate::error::builder)?;
+                        proxy_tls.insert(host, tls);
                     }
 
-                    if let Some(min_tls_version) = config.min_tls_version {
-                        let protocol = min_tls_version.to_native_tls().ok_or_else(|| {
-                            // TLS v1.3. This would be entirely reasonable,
-                            // native-tls just doesn't support it.
-                            // https://github.com/sfackler/rust-native-tls/issues/140
-                            crate::error::builder("invalid minimum TLS version for backend")
-                        })?;
-                        tls.min_protocol_version(Some(protocol));
+                    #[cfg(any(feature = "native-tls", feature = "__rustls"))]
+                    let identity = config.identity;
+                    #[cfg(not(any(feature = "native-tls", feature = "__rustls")))]
+                    let identity = None;
+
+                    let mut host_tls = HashMap::new();
+                    for (host, mut options) in config.tls_host_options {
+                        let mut tls = native_tls(options.take_identity().or(identity.clone()))?;
+                        options.add_to_native_tls(&mut tls);
+                        host_tls.insert(host, tls.build().map_err(crate::error::builder)?);
                     }
 
-                    if let Some(max_tls_version) = config.max_tls_version {
-                        let protocol = max_tls_version.to_native_tls().ok_or_else(|| {
-                            // TLS v1.3.
-                            // We could arguably do max_protocol_version(None), given
-                            // that 1.4 does not exist yet, but that'd get messy in the
-                            // future.
-                            crate::error::builder("invalid maximum TLS version for backend")
-                        })?;
-                        tls.max_protocol_version(Some(protocol));
+                    #[cfg(all(feature = "http2", feature = "native-tls-alpn"))]
+                    if h2_fallback.is_some() {
+                        let mut tls = native_tls(identity.clone())?;
+                        tls.request_alpns(&["http/1.1"]);
+                        native_http1 = Some(tls.build().map_err(crate::error::builder)?);
                     }
 
-                    ConnectorBuilder::new_default_tls(
+                    let tls = native_tls(identity)?;
+                    let mut connector = ConnectorBuilder::new_default_tls(
                         http,
                         tls,
                         proxies.clone(),
@@ -467,22 +723,38 @@ impl ClientBuilder {
                         config.interface.as_deref(),
                         config.nodelay,
                         config.tls_info,
-                    )?
+                    )?;
+                    connector.set_native_tls_proxies(proxy_tls);
+                    connector.set_native_tls_hosts(host_tls);
+                    connector
                 }
                 #[cfg(feature = "native-tls")]
-                TlsBackend::BuiltNativeTls(conn) => ConnectorBuilder::from_built_default_tls(
-                    http,
-                    conn,
-                    proxies.clone(),
-                    user_agent(&config.headers),
-                    config.local_address,
-                    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
-                    config.interface.as_deref(),
-                    config.nodelay,
-                    config.tls_info,
-                ),
+                TlsBackend::BuiltNativeTls(conn) => {
+                    if !proxy_identities.is_empty() {
+                        return Err(preconfigured_proxy_identity());
+                    }
+                    ConnectorBuilder::from_built_default_tls(
+                        http,
+                        conn,
+                        proxies.clone(),
+                        user_agent(&config.headers),
+                        config.local_address,
+                        #[cfg(any(
+                            target_os = "android",
+                            target_os = "fuchsia",
+                            target_os = "linux"
+                        ))]
+                        config.interface.as_deref(),
+                        config.nodelay,
+                        config.tls_info,
+                    )
+                }
                 #[cfg(feature = "__rustls")]
                 TlsBackend::BuiltRustls(conn) => {
+                    if !proxy_identities.is_empty() {
+                        return Err(preconfigured_proxy_identity());
+                    }
+
                     #[cfg(feature = "http3")]
                     {
                         h3_connector = build_h3_connector(
@@ -492,6 +764,7 @@ impl ClientBuilder {
                             config.quic_stream_receive_window,
                             config.quic_receive_window,
                             config.quic_send_window,
+                            config.quic_congestion_controller.clone(),
                             config.local_address,
                             &config.http_version_pref,
                         )?;
@@ -603,9 +876,46 @@ impl ClientBuilder {
                             #[cfg(feature = "__rustls-ring")]
                             Arc::new(rustls::crypto::ring::default_provider())
                         });
+                    let provider = if config.tls_cipher_suites.is_some()
+                        || config.tls_key_exchange_groups.is_some()
+                    {
+                        Arc::new(tls::restrict_provider(
+                            &provider,
+                            config.tls_cipher_suites.as_deref(),
+                            config.tls_key_exchange_groups.as_deref(),
+                        )?)
+                    } else {
+                        provider
+                    };
+
+                    // OCSP responders are queried like the origins are reached
+                    #[cfg(feature = "rustls-tls-ocsp")]
+                    let ocsp_client = match config.tls_ocsp {
+                        Some(ref policy) if policy.fetch_timeout.is_some() => {
+                            let mut builder = ClientBuilder::new();
+                            builder.config.proxies = proxies.to_vec();
+                            builder.config.auto_sys_proxy = false;
+                            builder.config.dns_resolver = Some(resolver.clone());
+                            builder.config.allowed_ports = config.allowed_ports.clone();
+                            builder.config.local_address = config.local_address;
+                            builder.config.connect_timeout = config.connect_timeout;
+                            builder.config.timeout = policy.fetch_timeout;
+                            Some(builder.build()?)
+                        }
+                        _ => None,
+                    };
+                    let checks = tls::CertChecks {
+                        #[cfg(feature = "rustls-tls-ocsp")]
+                        ocsp: config.tls_ocsp,
+                        #[cfg(feature = "rustls-tls-ocsp")]
+                        ocsp_client,
+                        ct: config.tls_ct_policy,
+                    };
 
                     // Build TLS config
+                    let host_provider = provider.clone();
                     let signature_algorithms = provider.signature_verification_algorithms;
+                    let key_provider = provider.key_provider;
                     let config_builder =
                         rustls::ClientConfig::builder_with_provider(provider.clone())
                             .with_protocol_versions(&versions)
@@ -616,14 +926,18 @@ impl ClientBuilder {
                             .dangerous()
                             .with_custom_certificate_verifier(Arc::new(NoVerifier))
                     } else if !config.hostname_verification {
+                        let roots = Arc::new(root_cert_store);
+                        let verifier = tls::add_checks(
+                            Arc::new(IgnoreHostname::new(roots.clone(), signature_algorithms)),
+                            roots,
+                            &checks,
+                            &host_provider,
+                        )?;
                         config_builder
                             .dangerous()
-                            .with_custom_certificate_verifier(Arc::new(IgnoreHostname::new(
-                                root_cert_store,
-                                signature_algorithms,
-                            )))
+                            .with_custom_certificate_verifier(verifier)
                     } else {
-                        if config.crls.is_empty() {
+                        if config.crls.is_empty() && checks.is_empty() {
                             config_builder.with_root_certificates(root_cert_store)
                         } else {
                             let crls = config
@@ -631,9 +945,10 @@ impl ClientBuilder {
                                 .iter()
                                 .map(|e| e.as_rustls_crl())
                                 .collect::<Vec<_>>();
+                            let roots = Arc::new(root_cert_store);
                             let verifier =
                                 rustls::client::WebPkiServerVerifier::builder_with_provider(
-                                    Arc::new(root_cert_store),
+                                    roots.clone(),
                                     provider,
                                 )
                                 .with_crls(crls)
@@ -641,7 +956,15 @@ impl ClientBuilder {
                                 .map_err(|_| {
                                     crate::error::builder("invalid TLS verification settings")
                                 })?;
-                            config_builder.with_webpki_verifier(verifier)
+                            if !checks.is_empty() {
+                                let verifier =
+                                    tls::add_checks(verifier, roots, &checks, &host_provider)?;
+                                config_builder
+                                    .dangerous()
+                                    .with_custom_certificate_verifier(verifier)
+                            } else {
+                                config_builder.with_webpki_verifier(verifier)
+                            }
                         }
                     };
 
@@ -676,6 +999,28 @@ impl ClientBuilder {
                         }
                     }
 
+                    let mut proxy_tls = HashMap::new();
+                    for (host, identity) in proxy_identities {
+                        let mut proxy_config = tls.clone();
+                        proxy_config.alpn_protocols.clear();
+                        identity.replace_in_rustls(&mut proxy_config, key_provider)?;
+                        proxy_tls.insert(host, proxy_config);
+                    }
+
+                    let mut host_tls = HashMap::new();
+                    for (host, mut options) in config.tls_host_options {
+                        let mut host_config = tls.clone();
+                        if let Some(identity) = options.take_identity() {
+                            identity.replace_in_rustls(&mut host_config, key_provider)?;
+                        }
+                        options.add_to_rustls(
+                            &mut host_config,
+                            &host_provider,
+                            &checks,
+                        )?;
+                        host_tls.insert(host, host_config);
+                    }
+
                     #[cfg(feature = "http3")]
                     {
                         tls.enable_early_data = config.tls_enable_early_data;
@@ -687,12 +1032,13 @@ impl ClientBuilder {
                             config.quic_stream_receive_window,
                             config.quic_receive_window,
                             config.quic_send_window,
+                            config.quic_congestion_controller.clone(),
                             config.local_address,
                             &config.http_version_pref,
                         )?;
                     }
 
-                    ConnectorBuilder::new_rustls_tls(
+                    let mut connector = ConnectorBuilder::new_rustls_tls(
                         http,
                         tls,
                         proxies.clone(),
@@ -706,7 +1052,10 @@ impl ClientBuilder {
                         config.interface.as_deref(),
                         config.nodelay,
                         config.tls_info,
-                    )
+                    );
+                    connector.set_rustls_proxies(proxy_tls);
+                    connector.set_rustls_hosts(host_tls);
+                    connector
                 }
                 #[cfg(any(feature = "native-tls", feature = "__rustls",))]
                 TlsBackend::UnknownPreconfigured => {
@@ -729,7 +1078,42 @@ impl ClientBuilder {
 
         connector_builder.set_timeout(config.connect_timeout);
         connector_builder.set_verbose(config.connection_verbose);
+        let shutdown = Arc::new(Shutdown::default());
+        if config.close_connections_on_shutdown {
+            connector_builder.set_shutdown(shutdown.clone());
+        }
+        connector_builder.set_probe_idle(config.http1_probe_idle_connections);
+        #[cfg(feature = "http2")]
+        let http2_only = matches!(config.http_version_pref, HttpVersionPref::Http2);
+        #[cfg(not(feature = "http2"))]
+        let http2_only = false;
+        connector_builder.set_raw_headers(config.http1_preserve_raw_headers && !http2_only);
+        #[cfg(feature = "http2")]
+        connector_builder.set_h2c(config.http2_cleartext_prior_knowledge);
+        connector_builder.set_allowed_ports(config.allowed_ports);
         connector_builder.set_keepalive(config.tcp_keepalive);
+        #[cfg(feature = "__tls")]
+        connector_builder.set_tls_server_names(config.tls_server_names);
+        #[cfg(all(feature = "http2", feature = "__tls"))]
+        if let Some(ref h2_fallback) = h2_fallback {
+            connector_builder.set_h2_fallback(
+                h2_fallback.clone(),
+                #[cfg(feature = "default-tls")]
+                native_http1,
+            );
+        }
+        if let Some(callback) = config.pool_events {
+            connector_builder.set_pool_events(callback, http2_only);
+        }
+        #[cfg(feature = "http2")]
+        if let Some(callback) = config.http2_events {
+            connector_builder.set_http2_events(callback, http2_only);
+        }
+        connector_builder.set_slots(
+            config
+                .max_connections_per_host
+                .map(|max| Arc::new(Slots::new(max, config.pool_acquire_timeout))),
+        );
 
         let mut builder =
             hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new());
@@ -793,31 +1177,145 @@ impl ClientBuilder {
             builder.http1_allow_spaces_after_header_name_in_responses(true);
         }
 
-        let proxies_maybe_http_auth = proxies.iter().any(|p| p.maybe_has_http_auth());
+        // the system proxies may have credentials once reloaded
+        let proxies_maybe_http_auth = proxies
+            .iter()
+            .any(|p| p.maybe_has_http_auth() || p.is_system());
+
+        let mut accepts = config.accepts;
+        if let Some(ref preferences) = config.accept_encoding {
+            let value = crate::codec::format_preferences(preferences)?;
+            accepts.advertised = Some(HeaderValue::try_from(value).map_err(crate::error::builder)?);
+        }
+        if !config.codecs.is_empty() {
+            let codecs = crate::codec::Registry::new(config.codecs, accepts.builtin())?;
+            accepts.codecs = Some(Arc::new(codecs));
+        }
+
+        if config.max_concurrent_requests == Some(0)
+            || config.max_concurrent_requests_per_host == Some(0)
+        {
+            return Err(crate::error::builder("concurrency limits must be greater than zero"));
+        }
+        if config.max_connections_per_host == Some(0) {
+            return Err(crate::error::builder("connection limits must be greater than zero"));
+        }
+        #[cfg(feature = "http2")]
+        if config.http2_max_concurrent_streams == Some(0) {
+            return Err(crate::error::builder("stream limits must be greater than zero"));
+        }
+        #[cfg(feature = "http3")]
+        if config.http3_max_streams_per_connection == Some(0) {
+            return Err(crate::error::builder("stream limits must be greater than zero"));
+        }
+        let limits = Limits::new(
+            config.max_concurrent_requests,
+            config.max_concurrent_requests_per_host,
+            config.concurrency_queue_timeout,
+        );
+
+        let scoped_dns = ScopedDns {
+            builder: builder.clone(),
+            connector: connector_builder.clone(),
+            layers: config.connector_layers.clone(),
+            clients: Mutex::default(),
+        };
+        #[cfg(feature = "http2")]
+        let lanes = config.http2_max_concurrent_streams.map(|max| {
+            Arc::new(Lanes::new(
+                max as usize,
+                builder.clone(),
+                connector_builder.clone(),
+                config.connector_layers.clone(),
+            ))
+        });
+        #[cfg(feature = "http3")]
+        let race = if config.http3_race_tcp && h3_connector.is_some() {
+            let connector = connector_builder.clone().build(config.connector_layers.clone());
+            Some(Arc::new(Race::new(connector)))
+        } else {
+            None
+        };
+        let hyper = builder.build(connector_builder.build(config.connector_layers));
+        let request_service = if config.request_layers.is_empty() {
+            None
+        } else {
+            let service = RequestService::new(SendRequest(hyper.clone()));
+            Some(
+                config
+                    .request_layers
+                    .into_iter()
+                    .fold(service, |service, layer| layer.layer(service)),
+            )
+        };
 
         Ok(Client {
             inner: Arc::new(ClientRef {
-                accepts: config.accepts,
+                accepts,
                 #[cfg(feature = "cookies")]
                 cookie_store: config.cookie_store,
+                #[cfg(feature = "cookies")]
+                cookie_observer: config.cookie_observer,
+                hsts_store: config.hsts_store,
                 // Use match instead of map since config is partially moved,
                 // and it cannot be used in closure
                 #[cfg(feature = "http3")]
                 h3_client: match h3_connector {
                     Some(h3_connector) => {
-                        Some(H3Client::new(h3_connector, config.pool_idle_timeout))
+                        Some(H3Client::new(
+                            h3_connector,
+                            config.pool_idle_timeout,
+                            config.http3_pool_max_idle_per_host,
+                            config
+                                .http3_max_streams_per_connection
+                                .map(|max| max as usize),
+                            config.http3_zero_rtt,
+                        ))
                     }
                     None => None,
                 },
-                hyper: builder.build(connector_builder.build(config.connector_layers)),
+                #[cfg(feature = "http3")]
+                race,
+                scoped_dns,
+                hyper,
+                request_service,
                 headers: config.headers,
+                header_value_validation: config.header_value_validation,
                 redirect_policy: config.redirect_policy,
                 referer: config.referer,
+                redirect_body_buffer_limit: config.redirect_body_buffer_limit,
+                retry_body_buffer_limit: config.retry_body_buffer_limit,
+                retry_after: config.retry_after,
+                capabilities: crate::capabilities::Cache::new(config.capabilities_ttl),
+                on_deprecation: config.on_deprecation,
+                expected_content_type: config.expected_content_type.map(Arc::new),
+                #[cfg(feature = "json")]
+                canonical_json: config.canonical_json,
+                #[cfg(feature = "digest")]
+                verify_content_digest: config.verify_content_digest,
+                #[cfg(feature = "http2")]
+                h2_fallback,
+                upgrades: if config.track_upgrades {
+                    Some(Arc::new(super::upgrade::Registry::default()))
+                } else {
+                    None
+                },
                 read_timeout: config.read_timeout,
                 request_timeout: config.timeout,
+                method_timeouts: config.method_timeouts,
+                hedge_after: config.hedge_after,
+                max_upload_rate: config.max_upload_rate,
+                max_download_rate: config.max_download_rate,
+                limits,
+                #[cfg(feature = "http2")]
+                lanes,
+                circuit_breakers: config.circuit_breaker.map(|c| Arc::new(Breakers::new(c))),
+                trace_context: config.trace_context,
+                interceptors: config.interceptors,
                 proxies,
                 proxies_maybe_http_auth,
                 https_only: config.https_only,
+                shutdown,
             }),
         })
     }
@@ -889,6 +1387,55 @@ impl ClientBuilder {
         self
     }
 
+    /// Don't send the default `Accept: */*` header.
+    ///
+    /// By default, requests without an `Accept` header are sent with
+    /// `Accept: */*`. Some APIs treat that differently from a missing
+    /// header, for instance when negotiating the content type.
+    pub fn no_default_accept(mut self) -> ClientBuilder {
+        self.config.headers.remove(ACCEPT);
+        self
+    }
+
+    /// Removes all the default headers, including the default `Accept`
+    /// header, and any set with `default_headers()` or `user_agent()` so
+    /// far.
+    ///
+    /// Call it before `default_headers()` to start from an empty set of
+    /// default headers.
+    pub fn clear_default_headers(mut self) -> ClientBuilder {
+        self.config.headers.clear();
+        self
+    }
+
+    /// Reject requests with header values that aren't visible ASCII.
+    ///
+    /// Header values may contain arbitrary bytes other than control
+    /// characters, but many servers and intermediaries only accept visible
+    /// ASCII (and spaces and tabs). With this option, sending a request
+    /// with any other bytes in a header value fails with a builder error,
+    /// whose [`header_name()`](crate::Error::header_name) identifies the
+    /// offending header.
+    ///
+    /// By default, such header values are sent as they are.
+    pub fn strict_header_values(mut self) -> ClientBuilder {
+        self.config.header_value_validation = HeaderValueValidation::Strict;
+        self
+    }
+
+    /// Replace characters in header values that aren't visible ASCII with `?`.
+    ///
+    /// This is the lossy alternative to
+    /// [`strict_header_values()`](ClientBuilder::strict_header_values):
+    /// instead of failing the request, each offending character is replaced
+    /// before the request is sent.
+    ///
+    /// By default, such header values are sent as they are.
+    pub fn sanitize_header_values(mut self) -> ClientBuilder {
+        self.config.header_value_validation = HeaderValueValidation::Sanitize;
+        self
+    }
+
     /// Enable a persistent cookie store for the client.
     ///
     /// Cookies received in responses will be preserved and included in
@@ -937,6 +1484,77 @@ impl ClientBuilder {
         self
     }
 
+    /// Set a function to call when a response changes the cookie store.
+    ///
+    /// The function is told when a cookie is stored, updated, or expired by
+    /// a `Set-Cookie` header, for instance to persist the cookies right
+    /// away, or audit them. Cookies the store rejects aren't reported.
+    ///
+    /// This applies to the cookie store of the `Client`, and to those set
+    /// with [`RequestBuilder::cookie_jar()`](crate::RequestBuilder::cookie_jar).
+    /// The function is called before the response is returned, so it should
+    /// be quick.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reqwest::cookie::CookieEventKind;
+    ///
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .cookie_store(true)
+    ///     .cookie_observer(|event| {
+    ///         if event.kind() == CookieEventKind::Expired {
+    ///             println!("{} logged us out", event.url());
+    ///         }
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cookies` feature to be enabled.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    pub fn cookie_observer<F>(mut self, f: F) -> ClientBuilder
+    where
+        F: Fn(&cookie::CookieEvent<'_>) + Send + Sync + 'static,
+    {
+        self.config.cookie_observer = Some(Arc::new(f));
+        self
+    }
+
+    /// Enable HTTP Strict Transport Security for the client.
+    ///
+    /// Hosts sending a `Strict-Transport-Security` header over HTTPS are
+    /// remembered, and later `http://` requests and redirects to them are
+    /// upgraded to `https://` before connecting.
+    ///
+    /// By default, HSTS isn't used. Like with
+    /// [`cookie_store()`](ClientBuilder::cookie_store), calling `hsts(true)`
+    /// after [`hsts_provider()`](ClientBuilder::hsts_provider) replaces the
+    /// provided store with a default [`HstsCache`](crate::hsts::HstsCache).
+    pub fn hsts(mut self, enable: bool) -> ClientBuilder {
+        if enable {
+            self.hsts_provider(Arc::new(hsts::HstsCache::default()))
+        } else {
+            self.config.hsts_store = None;
+            self
+        }
+    }
+
+    /// Set the HSTS store for the client.
+    ///
+    /// Policies received in responses are passed to this store, and the
+    /// URL of each request and redirect is checked against it. This can be
+    /// used to preload hosts, or to persist them.
+    pub fn hsts_provider<S: hsts::HstsStore + 'static>(mut self, store: Arc<S>) -> ClientBuilder {
+        self.config.hsts_store = Some(store as _);
+        self
+    }
+
     /// Enable auto gzip decompression by checking the `Content-Encoding` response header.
     ///
     /// If auto gzip decompression is turned on:
@@ -1063,6 +1681,43 @@ impl ClientBuilder {
         }
     }
 
+    /// Set the largest zstd window size accepted when decompressing, as a
+    /// power of 2.
+    ///
+    /// The window is the memory a zstd decoder needs, chosen by the server
+    /// when compressing. Responses that need a larger window fail to
+    /// decode, instead of using up to 2 GiB of memory.
+    ///
+    /// Default is zstd's limit, 27 (128 MiB).
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `zstd` feature to be enabled
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    pub fn zstd_window_log_max(mut self, log: u32) -> ClientBuilder {
+        self.config.accepts.zstd_window_log_max = Some(log);
+        self
+    }
+
+    /// Decompress response bodies on the blocking thread pool, if they're
+    /// larger than `threshold` bytes.
+    ///
+    /// Decompressing large bodies can keep a worker thread of the runtime
+    /// busy, delaying other tasks. With this option, each chunk of a
+    /// compressed body that's larger than `threshold`, or of unknown
+    /// length, is decompressed with `tokio::task::spawn_blocking`. This
+    /// also applies to the decoders of custom content codings.
+    ///
+    /// Offloading has a cost for each chunk, so it's only worth it for
+    /// bodies that take a while to decompress.
+    ///
+    /// Default is to decompress on the task reading the body.
+    pub fn decompression_offload_threshold(mut self, threshold: u64) -> ClientBuilder {
+        self.config.accepts.offload_threshold = Some(threshold);
+        self
+    }
+
     /// Disable auto response body zstd decompression.
     ///
     /// This method exists even if the optional `zstd` feature is not enabled.
@@ -1097,6 +1752,62 @@ impl ClientBuilder {
         }
     }
 
+    /// Set the content codings advertised in the `Accept-Encoding` header,
+    /// in order of preference.
+    ///
+    /// By default, all the enabled built-in encodings are advertised, like
+    /// `gzip, br, zstd, deflate`. This lists only some of them, in another
+    /// order, or with [quality values](crate::codec::Encoding::quality).
+    /// Custom codecs registered with [`content_codec()`] are still listed
+    /// after these, and an empty list advertises only them.
+    ///
+    /// Responses are still decoded like before, so each listed encoding
+    /// must be enabled, or [`build()`] fails.
+    ///
+    /// The header isn't set when a request already has one, or a `Range`
+    /// header, or was built with [`RequestBuilder::no_accept_encoding()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reqwest::codec::Encoding;
+    ///
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// // Accept-Encoding: zstd, gzip;q=0.5
+    /// let client = reqwest::Client::builder()
+    ///     .accept_encoding([Encoding::Zstd.into(), Encoding::Gzip.quality(0.5)])
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`content_codec()`]: ClientBuilder::content_codec
+    /// [`build()`]: ClientBuilder::build
+    pub fn accept_encoding<I>(mut self, encodings: I) -> ClientBuilder
+    where
+        I: IntoIterator,
+        I::Item: Into<crate::codec::Preference>,
+    {
+        self.config.accept_encoding = Some(encodings.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Register a custom content coding.
+    ///
+    /// The coding is advertised in the `Accept-Encoding` header, after the
+    /// enabled built-in encodings, and responses with a `Content-Encoding` of
+    /// `name` are decoded with the codec. Request bodies can be encoded with
+    /// it using [`RequestBuilder::encode_body()`].
+    ///
+    /// Enabled built-in encodings take precedence over a codec of the same
+    /// name.
+    ///
+    /// See the [`codec`](crate::codec) module for an example.
+    pub fn content_codec<C: crate::codec::Codec>(mut self, name: &str, codec: C) -> ClientBuilder {
+        self.config.codecs.push((name.to_owned(), Arc::new(codec)));
+        self
+    }
+
     // Redirect options
 
     /// Set a `RedirectPolicy` for this client.
@@ -1115,22 +1826,76 @@ impl ClientBuilder {
         self
     }
 
-    // Proxy options
-
-    /// Add a `Proxy` to the list of proxies the `Client` will use.
+    /// Buffer streaming request bodies, so that they can be sent again when
+    /// following a `307 Temporary Redirect` or `308 Permanent Redirect`.
     ///
-    /// # Note
+    /// A streaming body can't be replayed, so by default the 307 or 308
+    /// response is returned instead of following it. With a limit, the bytes
+    /// of a streaming body are kept as they are sent, and the redirect is
+    /// followed if the whole body was sent before the redirect response
+    /// arrived, and was no larger than `limit` bytes.
     ///
-    /// Adding a proxy will disable the automatic usage of the "system" proxy.
-    pub fn proxy(mut self, proxy: Proxy) -> ClientBuilder {
-        self.config.proxies.push(proxy);
-        self.config.auto_sys_proxy = false;
+    /// Default is no buffering.
+    pub fn redirect_body_buffer_limit(mut self, limit: usize) -> ClientBuilder {
+        self.config.redirect_body_buffer_limit = Some(limit);
         self
     }
 
-    /// Clear all `Proxies`, so `Client` will use no proxy anymore.
+    /// Buffer streaming request bodies, so that they can be sent again when
+    /// a request is retried.
     ///
-    /// # Note
+    /// Requests are retried automatically when the server refuses an HTTP/2
+    /// stream, or closes the connection, before processing them. A streaming
+    /// body can't be replayed, so by default such a request fails instead.
+    /// With a limit, the bytes of a streaming body are kept as they are sent,
+    /// and the request is retried if the whole body was sent, and was no
+    /// larger than `limit` bytes.
+    ///
+    /// The same buffer is used for
+    /// [`redirect_body_buffer_limit()`](ClientBuilder::redirect_body_buffer_limit),
+    /// up to the larger of the two limits.
+    ///
+    /// Default is no buffering.
+    pub fn retry_body_buffer_limit(mut self, limit: usize) -> ClientBuilder {
+        self.config.retry_body_buffer_limit = Some(limit);
+        self
+    }
+
+    /// Honor `Retry-After` headers, waiting up to `max` each time.
+    ///
+    /// When a redirect that will be followed has a `Retry-After` header, the
+    /// client waits for its delay before sending the next request. A
+    /// `429 Too Many Requests` or `503 Service Unavailable` response with a
+    /// `Retry-After` header is retried after the delay, at most twice, if
+    /// the request body can be sent again.
+    ///
+    /// A response asking to wait longer than `max` is returned as is. The
+    /// waits count against the [`timeout()`](ClientBuilder::timeout), and
+    /// are listed by
+    /// [`Response::retry_after_waits()`](crate::Response::retry_after_waits).
+    ///
+    /// Default is to ignore `Retry-After`.
+    pub fn retry_after(mut self, max: Duration) -> ClientBuilder {
+        self.config.retry_after = Some(max);
+        self
+    }
+
+    // Proxy options
+
+    /// Add a `Proxy` to the list of proxies the `Client` will use.
+    ///
+    /// # Note
+    ///
+    /// Adding a proxy will disable the automatic usage of the "system" proxy.
+    pub fn proxy(mut self, proxy: Proxy) -> ClientBuilder {
+        self.config.proxies.push(proxy);
+        self.config.auto_sys_proxy = false;
+        self
+    }
+
+    /// Clear all `Proxies`, so `Client` will use no proxy anymore.
+    ///
+    /// # Note
     /// To add a proxy exclusion list, use [crate::proxy::Proxy::no_proxy()]
     /// on all desired proxies instead.
     ///
@@ -1154,6 +1919,53 @@ impl ClientBuilder {
         self
     }
 
+    /// Enables a total request timeout for requests with `method`.
+    ///
+    /// This takes precedence over the timeouts of
+    /// [`safe_methods_timeout()`](ClientBuilder::safe_methods_timeout),
+    /// [`unsafe_methods_timeout()`](ClientBuilder::unsafe_methods_timeout) and
+    /// [`timeout()`](ClientBuilder::timeout), while
+    /// [`RequestBuilder::timeout()`](crate::RequestBuilder::timeout) takes
+    /// precedence over it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use reqwest::Method;
+    ///
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// // uploads are slow
+    /// let client = reqwest::Client::builder()
+    ///     .timeout(Duration::from_secs(10))
+    ///     .timeout_for(Method::POST, Duration::from_secs(300))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timeout_for(mut self, method: Method, timeout: Duration) -> ClientBuilder {
+        self.config.method_timeouts.methods.insert(method, timeout);
+        self
+    }
+
+    /// Enables a total request timeout for requests with a safe method:
+    /// `GET`, `HEAD`, `OPTIONS` and `TRACE`.
+    ///
+    /// This takes precedence over [`timeout()`](ClientBuilder::timeout).
+    pub fn safe_methods_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.method_timeouts.safe = Some(timeout);
+        self
+    }
+
+    /// Enables a total request timeout for requests with a method that
+    /// isn't safe, such as `POST`, `PUT` or `DELETE`.
+    ///
+    /// This takes precedence over [`timeout()`](ClientBuilder::timeout).
+    pub fn unsafe_methods_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.method_timeouts.unsafe_ = Some(timeout);
+        self
+    }
+
     /// Enables a read timeout.
     ///
     /// The timeout applies to each read operation, and resets after a
@@ -1166,6 +1978,185 @@ impl ClientBuilder {
         self
     }
 
+    /// Limits the rate at which request bodies are sent, in bytes per
+    /// second.
+    ///
+    /// Each request is limited on its own, with a token bucket that lets
+    /// the first second's worth of bytes through at once. This is useful
+    /// for background work that must not saturate a link. Requests can
+    /// override it with
+    /// [`RequestBuilder::max_upload_rate()`](crate::RequestBuilder::max_upload_rate).
+    ///
+    /// Default is no limit, as is a rate of 0.
+    pub fn max_upload_rate(mut self, bytes_per_sec: u64) -> ClientBuilder {
+        self.config.max_upload_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Limits the rate at which response bodies are received, in bytes per
+    /// second.
+    ///
+    /// The rate applies to the bytes on the wire, before they're decoded,
+    /// and it's enforced by reading the body slower, so the server is held
+    /// back by flow control. Requests can override it with
+    /// [`RequestBuilder::max_download_rate()`](crate::RequestBuilder::max_download_rate).
+    ///
+    /// Default is no limit, as is a rate of 0.
+    pub fn max_download_rate(mut self, bytes_per_sec: u64) -> ClientBuilder {
+        self.config.max_download_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Hedge `GET` and `HEAD` requests: if no response arrived after
+    /// `delay`, send a second, identical request, and use whichever response
+    /// arrives first. The other request is cancelled.
+    ///
+    /// This trades extra load on the server for lower tail latency. Only
+    /// `GET` and `HEAD` requests are hedged, since sending other requests
+    /// twice may not be safe; use [`RequestBuilder::hedge_after()`] to opt
+    /// in for other requests that are idempotent. Requests with a streaming
+    /// body are never hedged.
+    ///
+    /// A request that fails before `delay` elapses isn't retried.
+    ///
+    /// Default is no hedging.
+    pub fn hedge_after(mut self, delay: Duration) -> ClientBuilder {
+        self.config.hedge_after = Some(delay);
+        self
+    }
+
+    /// Limits how many requests of the client are in flight at once.
+    ///
+    /// Requests over the limit wait in line until an earlier request got
+    /// its response, or failed. The body of a response doesn't count, so
+    /// responses that are streamed slowly don't hold back other requests.
+    /// See [`concurrency_queue_timeout()`] to bound the wait.
+    ///
+    /// Clones of the client share the limit. It must be greater than zero.
+    ///
+    /// Default is no limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .max_concurrent_requests(64)
+    ///     .max_concurrent_requests_per_host(8)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`concurrency_queue_timeout()`]: ClientBuilder::concurrency_queue_timeout
+    pub fn max_concurrent_requests(mut self, max: usize) -> ClientBuilder {
+        self.config.max_concurrent_requests = Some(max);
+        self
+    }
+
+    /// Limits how many requests of the client to each host are in flight
+    /// at once.
+    ///
+    /// Hosts are told apart by their origin, the scheme, host and port of
+    /// the URL. This works like
+    /// [`max_concurrent_requests()`](ClientBuilder::max_concurrent_requests),
+    /// and both limits can be combined.
+    ///
+    /// Default is no limit.
+    pub fn max_concurrent_requests_per_host(mut self, max: usize) -> ClientBuilder {
+        self.config.max_concurrent_requests_per_host = Some(max);
+        self
+    }
+
+    /// Sets how long a request waits in line for the concurrency limits,
+    /// before it fails with a timeout error.
+    ///
+    /// Default is to wait as long as it takes.
+    pub fn concurrency_queue_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.concurrency_queue_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables a circuit breaker for each origin.
+    ///
+    /// Once an origin failed too many times in a row, requests to it fail
+    /// immediately, without being sent, until the breaker lets probe
+    /// requests through again. See the [`circuit`](crate::circuit) module
+    /// for details.
+    ///
+    /// Default is no circuit breaker.
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> ClientBuilder {
+        self.config.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Propagates the current trace context with requests.
+    ///
+    /// When a request is sent, `source` is asked for the current
+    /// [`TraceContext`](crate::trace_context::TraceContext), whose
+    /// `traceparent`, `tracestate` and `baggage` headers are added to the
+    /// request, and to the requests of any redirects and retries. See the
+    /// [`trace_context`](crate::trace_context) module for details.
+    ///
+    /// Default is no propagation.
+    pub fn trace_context<S: ContextSource>(mut self, source: S) -> ClientBuilder {
+        self.config.trace_context = Some(Arc::new(source));
+        self
+    }
+
+    /// Adds an interceptor, told about the requests, responses and errors of
+    /// the `Client`.
+    ///
+    /// Interceptors are called in the order they were added. See the
+    /// [`interceptor`](crate::interceptor) module for details.
+    pub fn interceptor<I: Interceptor>(mut self, interceptor: I) -> ClientBuilder {
+        self.config.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    // The blocking client reads the trace context on the calling thread.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn trace_context_source(&self) -> Option<Arc<dyn ContextSource>> {
+        self.config.trace_context.clone()
+    }
+
+    // The blocking client serializes JSON bodies on the calling thread.
+    #[cfg(all(feature = "blocking", feature = "json"))]
+    pub(crate) fn is_canonical_json(&self) -> bool {
+        self.config.canonical_json
+    }
+
+    /// Only connect to the given destination ports.
+    ///
+    /// Before dialing, the connector checks the port of the destination,
+    /// or the default port of its scheme. Connecting to any other port
+    /// fails with an error for which
+    /// [`Error::is_port_not_allowed()`](crate::Error::is_port_not_allowed)
+    /// returns true. This applies to redirects as well, and when connecting
+    /// through a proxy it's the port of the destination that is checked.
+    ///
+    /// This is a cheap hardening measure for services fetching URLs that
+    /// users control, such as webhooks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "rustls-tls-no-provider"))]
+    /// let client = reqwest::Client::builder()
+    ///     .allowed_ports([80, 443, 8443])
+    ///     .build()?;
+    /// # Ok::<(), reqwest::Error>(())
+    /// ```
+    ///
+    /// Default is all ports.
+    pub fn allowed_ports<I>(mut self, ports: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        self.config.allowed_ports = Some(ports.into_iter().collect());
+        self
+    }
+
     /// Set a timeout for only the connect phase of a `Client`.
     ///
     /// Default is `None`.
@@ -1211,6 +2202,77 @@ impl ClientBuilder {
         self
     }
 
+    /// Limits how many connections to each host are open at once, whether
+    /// they're in use or idle.
+    ///
+    /// Once the limit is reached, new requests to the host wait until a
+    /// connection is free again, or one is closed and a new one may be
+    /// opened. See [`pool_acquire_timeout()`] to bound the wait. Hosts are
+    /// told apart by their host and port. This matters most with HTTP/1,
+    /// where a connection carries only one request at a time.
+    ///
+    /// It must be greater than zero. Default is no limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .max_connections_per_host(4)
+    ///     .pool_acquire_timeout(Duration::from_secs(10))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`pool_acquire_timeout()`]: ClientBuilder::pool_acquire_timeout
+    pub fn max_connections_per_host(mut self, max: usize) -> ClientBuilder {
+        self.config.max_connections_per_host = Some(max);
+        self
+    }
+
+    /// Sets how long a request waits for a connection when the pool of its
+    /// host is saturated, for instance because of
+    /// [`max_connections_per_host()`](ClientBuilder::max_connections_per_host).
+    ///
+    /// When it elapses, the request fails with an error for which
+    /// [`Error::is_pool_timeout()`](crate::Error::is_pool_timeout) returns
+    /// true. This is separate from the
+    /// [`connect_timeout()`](ClientBuilder::connect_timeout), which starts
+    /// once a connection may be opened.
+    ///
+    /// Default is to wait as long as it takes.
+    pub fn pool_acquire_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.pool_acquire_timeout = Some(timeout);
+        self
+    }
+
+    /// Set a function to call when a connection of the pool is created,
+    /// reused or closed.
+    ///
+    /// See the [`pool`](crate::pool) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .pool_events(|event| {
+    ///         eprintln!("{:?} {}:{}", event.kind(), event.host(), event.port());
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pool_events<F>(mut self, f: F) -> ClientBuilder
+    where
+        F: Fn(&PoolEvent<'_>) + Send + Sync + 'static,
+    {
+        self.config.pool_events = Some(Arc::new(f));
+        self
+    }
+
     /// Send headers as title case instead of lowercase.
     pub fn http1_title_case_headers(mut self) -> ClientBuilder {
         self.config.http1_title_case_headers = true;
@@ -1251,6 +2313,37 @@ impl ClientBuilder {
         self
     }
 
+    /// Set whether to check if the server closed an idle HTTP/1 connection
+    /// before reusing it.
+    ///
+    /// Servers with short keep-alive timeouts may close a pooled connection
+    /// just as it is picked for a new request, which then fails with a
+    /// "connection closed before message completed" error. With this
+    /// enabled, the socket is peeked at before writing, and a request on a
+    /// connection that the server already closed is retried on a new one.
+    ///
+    /// This costs an extra system call whenever a read would block, and a
+    /// duplicated socket handle per connection.
+    ///
+    /// Default is `false`.
+    pub fn http1_probe_idle_connections(mut self, enabled: bool) -> ClientBuilder {
+        self.config.http1_probe_idle_connections = enabled;
+        self
+    }
+
+    /// Set whether to record the header lines of HTTP/1 responses in the
+    /// order and casing they were received.
+    ///
+    /// They are available from
+    /// [`Response::raw_headers()`](crate::Response::raw_headers). See the
+    /// [`raw_headers`](crate::raw_headers) module for more.
+    ///
+    /// Default is `false`.
+    pub fn http1_preserve_raw_headers(mut self, enabled: bool) -> ClientBuilder {
+        self.config.http1_preserve_raw_headers = enabled;
+        self
+    }
+
     /// Only use HTTP/1.
     pub fn http1_only(mut self) -> ClientBuilder {
         self.config.http_version_pref = HttpVersionPref::Http1;
@@ -1264,6 +2357,13 @@ impl ClientBuilder {
     }
 
     /// Only use HTTP/2.
+    ///
+    /// Connections to `http://` URLs speak HTTP/2 from the start, without
+    /// TLS (h2c), and connections to `https://` URLs only offer HTTP/2 with
+    /// ALPN. See [`http2_cleartext_prior_knowledge()`] to keep negotiating
+    /// the version over TLS.
+    ///
+    /// [`http2_cleartext_prior_knowledge()`]: ClientBuilder::http2_cleartext_prior_knowledge
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_prior_knowledge(mut self) -> ClientBuilder {
@@ -1271,7 +2371,37 @@ impl ClientBuilder {
         self
     }
 
+    /// Use HTTP/2 with prior knowledge for `http://` URLs only.
+    ///
+    /// Connections to `http://` URLs speak HTTP/2 from the start, without
+    /// TLS (h2c), such as to internal gRPC services. Connections to
+    /// `https://` URLs still negotiate HTTP/1.1 or HTTP/2 with ALPN. Requests
+    /// forwarded through an HTTP proxy keep using HTTP/1.1 to the proxy.
+    ///
+    /// The `Upgrade: h2c` handshake isn't supported, so the servers need to
+    /// accept HTTP/2 without it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .http2_cleartext_prior_knowledge()
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_cleartext_prior_knowledge(mut self) -> ClientBuilder {
+        self.config.http2_cleartext_prior_knowledge = true;
+        self
+    }
+
     /// Only use HTTP/3.
+    ///
+    /// HTTP/3 requests to hosts that a configured proxy applies to fail,
+    /// since QUIC can't be sent through HTTP or SOCKS proxies.
     #[cfg(feature = "http3")]
     #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
     pub fn http3_prior_knowledge(mut self) -> ClientBuilder {
@@ -1372,6 +2502,74 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the maximum number of requests in flight on each HTTP/2
+    /// connection.
+    ///
+    /// All the requests to an origin normally share a single HTTP/2
+    /// connection, up to the limit of the server. With this option, a
+    /// request that would go over `max` opens another connection instead,
+    /// so that large or slow responses hold up fewer requests. A request is
+    /// in flight until its response body was read, or dropped.
+    ///
+    /// This is a limit of the client, which doesn't raise the
+    /// `SETTINGS_MAX_CONCURRENT_STREAMS` of the server.
+    ///
+    /// Default is no limit.
+    ///
+    /// # Errors
+    ///
+    /// [`build()`](ClientBuilder::build) fails if `max` is zero.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_concurrent_streams(mut self, max: u32) -> ClientBuilder {
+        self.config.http2_max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Falls back to HTTP/1.1 for origins whose HTTP/2 connections keep
+    /// failing with protocol errors, for instance because of a buggy
+    /// middlebox.
+    ///
+    /// After 2 consecutive HTTP/2 protocol errors with an origin, new
+    /// connections to it only offer HTTP/1.1 with ALPN, until `ttl` has
+    /// elapsed. Like browsers, this only remembers origins in memory.
+    ///
+    /// Connections that are already open, and HTTP/2 with prior knowledge
+    /// or through a proxy, aren't affected.
+    ///
+    /// Default is disabled.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_fallback(mut self, ttl: Duration) -> ClientBuilder {
+        self.config.http2_fallback = Some(ttl);
+        self
+    }
+
+    /// Set a function to call when an HTTP/2 connection receives a `GOAWAY`,
+    /// a stream reset, or the acknowledgement of a ping.
+    ///
+    /// See the [`http2`](crate::http2) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .http2_events(|event| eprintln!("{event:?}"))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_events<F>(mut self, f: F) -> ClientBuilder
+    where
+        F: Fn(&Http2Event<'_>) + Send + Sync + 'static,
+    {
+        self.config.http2_events = Some(Arc::new(f));
+        self
+    }
+
     // TCP options
 
     /// Set whether sockets have `TCP_NODELAY` enabled.
@@ -1486,32 +2684,123 @@ impl ClientBuilder {
         self
     }
 
-    /// Controls the use of built-in/preloaded certificates during certificate validation.
+    /// Checks the revocation status of server certificates with OCSP.
     ///
-    /// Defaults to `true` -- built-in system certs will be used.
+    /// The status comes from the OCSP response stapled by the server, which
+    /// must be signed by the issuer of the certificate, or by a responder it
+    /// delegated. The issuer must be one of the intermediate certificates
+    /// sent by the server, or a root. A revoked certificate fails the
+    /// connection with an error for which [`Error::is_certificate_revoked()`](crate::Error::is_certificate_revoked)
+    /// is `true`; the [`OcspPolicy`](tls::OcspPolicy) decides about
+    /// certificates whose status is unknown.
     ///
-    /// # Bulk Option
+    /// OCSP responders are only queried when the policy allows it, see
+    /// [`OcspPolicy::fetch_responses()`](tls::OcspPolicy::fetch_responses).
+    /// Otherwise, servers that don't staple responses are only accepted by a
+    /// soft-fail policy. Building the `Client` fails if certificates aren't
+    /// verified at all, see
+    /// [`danger_accept_invalid_certs()`](ClientBuilder::danger_accept_invalid_certs).
     ///
-    /// If this value is `true`, _all_ enabled system certs configured with Cargo
-    /// features will be loaded.
+    /// # Example
     ///
-    /// You can set this to `false`, and enable only a specific source with
-    /// individual methods. Do that will prevent other sources from being loaded
-    /// even if their feature Cargo feature is enabled.
+    /// ```
+    /// # fn doc() -> Result<(), reqwest::Error> {
+    /// use reqwest::tls::OcspPolicy;
+    /// use std::time::Duration;
+    ///
+    /// let policy = OcspPolicy::hard_fail().fetch_responses(Duration::from_secs(5));
+    /// let client = reqwest::Client::builder()
+    ///     .use_rustls_tls()
+    ///     .tls_ocsp_check(policy)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
     ///
     /// # Optional
     ///
-    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
-    /// feature to be enabled.
-    #[cfg(feature = "__tls")]
-    #[cfg_attr(
-        docsrs,
-        doc(cfg(any(
-            feature = "default-tls",
-            feature = "native-tls",
-            feature = "rustls-tls"
-        )))
-    )]
+    /// This requires the `rustls-tls-ocsp` Cargo feature enabled, along
+    /// with one of the `rustls-tls(-...)` features.
+    #[cfg(feature = "rustls-tls-ocsp")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls-ocsp")))]
+    pub fn tls_ocsp_check(mut self, policy: tls::OcspPolicy) -> ClientBuilder {
+        self.config.tls_ocsp = Some(policy);
+        self
+    }
+
+    /// Requires server certificates to satisfy a Certificate Transparency
+    /// policy.
+    ///
+    /// Certificates must embed signed certificate timestamps (SCTs) from
+    /// enough of the logs of the [`CtPolicy`](tls::CtPolicy), or the
+    /// connection fails. The policy also applies with
+    /// [`danger_accept_invalid_hostnames()`](ClientBuilder::danger_accept_invalid_hostnames),
+    /// while building the `Client` fails if certificates aren't verified at
+    /// all, see [`danger_accept_invalid_certs()`](ClientBuilder::danger_accept_invalid_certs).
+    ///
+    /// Only the SCTs embedded in certificates are checked. Servers that
+    /// deliver theirs in stapled OCSP responses or in the
+    /// `signed_certificate_timestamp` TLS extension, which rustls doesn't
+    /// request, fail the policy.
+    ///
+    /// Certificates from private certificate authorities usually aren't
+    /// logged, so this is meant for clients that only connect to hosts with
+    /// publicly trusted certificates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn doc(log_keys: Vec<Vec<u8>>) -> Result<(), reqwest::Error> {
+    /// use reqwest::tls::{CtLog, CtPolicy};
+    ///
+    /// let logs = log_keys
+    ///     .iter()
+    ///     .map(|key| CtLog::from_der(key))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// let client = reqwest::Client::builder()
+    ///     .use_rustls_tls()
+    ///     .tls_ct_policy(CtPolicy::new(logs).min_scts(2))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the `rustls-tls(-...)` Cargo feature enabled.
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn tls_ct_policy(mut self, policy: tls::CtPolicy) -> ClientBuilder {
+        self.config.tls_ct_policy = Some(policy);
+        self
+    }
+
+    /// Controls the use of built-in/preloaded certificates during certificate validation.
+    ///
+    /// Defaults to `true` -- built-in system certs will be used.
+    ///
+    /// # Bulk Option
+    ///
+    /// If this value is `true`, _all_ enabled system certs configured with Cargo
+    /// features will be loaded.
+    ///
+    /// You can set this to `false`, and enable only a specific source with
+    /// individual methods. Do that will prevent other sources from being loaded
+    /// even if their feature Cargo feature is enabled.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
     pub fn tls_built_in_root_certs(mut self, tls_built_in_root_certs: bool) -> ClientBuilder {
         self.config.tls_built_in_root_certs = tls_built_in_root_certs;
 
@@ -1623,6 +2912,52 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets TLS options for the connections to a host.
+    ///
+    /// The root certificates, client identity and certificate validation
+    /// set in `options` only apply to `host`, and replace those of the
+    /// `Client` for it. The host is matched exactly, without its port.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn doc(cert: reqwest::Certificate) -> Result<(), reqwest::Error> {
+    /// use reqwest::tls::TlsOptions;
+    ///
+    /// let client = reqwest::Client::builder()
+    ///     .tls_config_for_host(
+    ///         "internal.example",
+    ///         TlsOptions::new().add_root_certificate(cert),
+    ///     )
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if it uses a preconfigured TLS backend.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_config_for_host(mut self, host: &str, options: tls::TlsOptions) -> ClientBuilder {
+        self.config
+            .tls_host_options
+            .insert(host.to_ascii_lowercase(), options);
+        self
+    }
+
     /// Controls the use of TLS server name indication.
     ///
     /// Defaults to `true`.
@@ -1645,6 +2980,47 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the name sent in the TLS server name indication to a host,
+    /// in place of the host itself.
+    ///
+    /// The `Host` header and the HTTP/2 `:authority` of requests keep the
+    /// host of the URL; only the TLS handshake uses `name`, and the server's
+    /// certificate is verified against it. The host is matched exactly,
+    /// without its port.
+    ///
+    /// This applies to connections over TCP, not to HTTP/3.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn doc() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .tls_sni_hostname("origin.internal", "front.example.com")
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_sni_hostname(mut self, host: &str, name: &str) -> ClientBuilder {
+        self.config
+            .tls_server_names
+            .insert(host.to_ascii_lowercase(), name.to_owned());
+        self
+    }
+
     /// Set the minimum required TLS version for connections.
     ///
     /// By default, the TLS backend's own default is used.
@@ -1706,6 +3082,65 @@ impl ClientBuilder {
         self
     }
 
+    /// Restrict the TLS cipher suites of connections, in order of preference.
+    ///
+    /// By default, the TLS backend's own suites are used.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if a suite isn't supported by the rustls
+    /// crypto provider, or if the list is empty. The `native-tls` and
+    /// `default-tls` backends can't restrict the cipher suites, so building a
+    /// `Client` with them fails too.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_cipher_suites(mut self, suites: &[tls::CipherSuite]) -> ClientBuilder {
+        self.config.tls_cipher_suites = Some(suites.to_vec());
+        self
+    }
+
+    /// Restrict the TLS key exchange groups of connections, in order of
+    /// preference.
+    ///
+    /// By default, the TLS backend's own groups are used.
+    ///
+    /// # Errors
+    ///
+    /// Building the `Client` fails if a group isn't supported by the rustls
+    /// crypto provider, or if the list is empty. The `native-tls` and
+    /// `default-tls` backends can't restrict the key exchange groups, so
+    /// building a `Client` with them fails too.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `default-tls`, `native-tls`, or `rustls-tls(-...)`
+    /// feature to be enabled.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_key_exchange_groups(mut self, groups: &[tls::KeyExchangeGroup]) -> ClientBuilder {
+        self.config.tls_key_exchange_groups = Some(groups.to_vec());
+        self
+    }
+
     /// Force using the native TLS backend.
     ///
     /// Since multiple TLS backends can be optionally enabled, this option will
@@ -1804,6 +3239,148 @@ impl ClientBuilder {
         self
     }
 
+    /// Set how long the result of [`Client::capabilities()`] is cached for
+    /// an origin.
+    ///
+    /// Default is 5 minutes.
+    pub fn capabilities_ttl(mut self, ttl: Duration) -> ClientBuilder {
+        self.config.capabilities_ttl = ttl;
+        self
+    }
+
+    /// Set a function to call when a response announces a deprecation with
+    /// the `Deprecation` or `Sunset` headers.
+    ///
+    /// The function is called with the URL of the response, before the
+    /// response is returned. This makes it possible to notice, for instance
+    /// by logging them, deprecations of all the endpoints an application
+    /// uses, without checking [`Response::deprecation()`] everywhere.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .on_deprecation(|url, deprecation| {
+    ///         eprintln!("{url} is deprecated, sunset: {:?}", deprecation.sunset());
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_deprecation<F>(mut self, f: F) -> ClientBuilder
+    where
+        F: Fn(&Url, &crate::notice::Deprecation) + Send + Sync + 'static,
+    {
+        self.config.on_deprecation = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the content type that [`Response::json()`] and
+    /// [`Response::text()`] expect responses to have.
+    ///
+    /// When set, these methods fail without reading the body if a response
+    /// has another `Content-Type`, like
+    /// [`Response::ensure_content_type()`] does. This protects against
+    /// parsing an HTML error page returned by a proxy as data.
+    ///
+    /// The expected type is matched like in `ensure_content_type()`, so
+    /// wildcards like `text/*` can be used. An invalid type makes
+    /// [`ClientBuilder::build()`] fail.
+    ///
+    /// Default is no expectation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::builder()
+    ///     .expect_content_type("application/json")
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn expect_content_type(mut self, content_type: &str) -> ClientBuilder {
+        match content_type.parse::<Mime>() {
+            Ok(mime) => self.config.expected_content_type = Some(mime),
+            Err(e) => self.config.error = Some(crate::error::builder(e)),
+        }
+        self
+    }
+
+    /// Serialize the bodies set with [`RequestBuilder::json()`] in the
+    /// canonical form of the JSON Canonicalization Scheme (RFC 8785).
+    ///
+    /// Object members are sorted and numbers are written in a single way,
+    /// so that the same value always gives the same bytes, whatever the
+    /// order of the fields of a map. This keeps signatures computed over
+    /// the body, for instance by an [interceptor](crate::interceptor),
+    /// stable.
+    ///
+    /// Integers beyond the range of exact doubles (±2^53) can't be
+    /// represented, and make `json()` fail.
+    ///
+    /// Default is `false`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    ///
+    /// [`RequestBuilder::json()`]: crate::RequestBuilder::json
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn canonical_json(mut self, enable: bool) -> ClientBuilder {
+        self.config.canonical_json = enable;
+        self
+    }
+
+    /// Verify response bodies against their `Content-Digest` or
+    /// `Repr-Digest` header, while they're read.
+    ///
+    /// If a response has one of these headers with a SHA-256 or SHA-512
+    /// digest, reading its body fails at the end when the digest doesn't
+    /// match, with an error for which
+    /// [`Error::is_digest_mismatch()`](crate::Error::is_digest_mismatch)
+    /// returns true. The digests are checked before the body is
+    /// decompressed, since they're computed over the encoded content.
+    /// `Repr-Digest` isn't checked for `206 Partial Content` responses,
+    /// since it covers the whole representation.
+    ///
+    /// Default is `false`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `digest` feature enabled.
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    pub fn verify_content_digest(mut self, enable: bool) -> ClientBuilder {
+        self.config.verify_content_digest = enable;
+        self
+    }
+
+    /// Keep track of the connections upgraded with [`Response::upgrade()`].
+    ///
+    /// The open connections, such as tunnels or websockets, can then be
+    /// listed with [`Client::upgrades()`], and aborted through the returned
+    /// handles.
+    ///
+    /// Default is `false`.
+    pub fn track_upgrades(mut self, enabled: bool) -> ClientBuilder {
+        self.config.track_upgrades = enabled;
+        self
+    }
+
+    /// Close the pooled connections when [`Client::shutdown()`] is called.
+    ///
+    /// Every connection is then watched for the shutdown, which adds a
+    /// small cost to each read and write.
+    ///
+    /// Default is `false`.
+    pub fn close_connections_on_shutdown(mut self, enabled: bool) -> ClientBuilder {
+        self.config.close_connections_on_shutdown = enabled;
+        self
+    }
+
     /// Restrict the Client to be used with HTTPS only requests.
     ///
     /// Defaults to false.
@@ -1893,6 +3470,34 @@ impl ClientBuilder {
         self
     }
 
+    /// Set a timeout for resolving the name of a host.
+    ///
+    /// The connect timeout only starts once the name is resolved. Without
+    /// a DNS timeout, a resolver that hangs makes the request wait until
+    /// its total timeout, if any. A timeout of the fallback resolver set
+    /// with `dns_fallback()` is also bounded by this.
+    ///
+    /// Default is no timeout.
+    pub fn dns_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.dns_timeout = Some(timeout);
+        self
+    }
+
+    /// Set a resolver used when the DNS resolver fails or times out.
+    ///
+    /// For instance, a client using `hickory_dns` can fall back to the
+    /// system resolver. If the fallback fails too, the error of the
+    /// primary resolver is included in the error chain.
+    ///
+    /// Overrides for specific names passed to `resolve` and
+    /// `resolve_to_addrs` are applied before either resolver.
+    ///
+    /// Default is no fallback.
+    pub fn dns_fallback<R: Resolve + 'static>(mut self, resolver: Arc<R>) -> ClientBuilder {
+        self.config.dns_fallback = Some(resolver as _);
+        self
+    }
+
     /// Whether to send data on the first flight ("early data") in TLS 1.3 handshakes
     /// for HTTP/3 connections.
     ///
@@ -1904,43 +3509,111 @@ impl ClientBuilder {
         self
     }
 
-    /// Maximum duration of inactivity to accept before timing out the QUIC connection.
+    /// Which requests may be sent as 0-RTT data on resumed HTTP/3 connections.
     ///
-    /// Please see docs in [`TransportConfig`] in [`quinn`].
+    /// A request sent in 0-RTT data goes out before the handshake completes,
+    /// and can be replayed by an attacker, so only requests that are safe to
+    /// repeat should be allowed. Requests that aren't wait for the handshake.
+    /// If the server rejects the early data, the request is sent again on a
+    /// new connection. Requests whose body can't be cloned, like streams,
+    /// couldn't be sent again, so they always wait for the handshake.
     ///
-    /// [`TransportConfig`]: https://docs.rs/quinn/latest/quinn/struct.TransportConfig.html
+    /// This requires `tls_early_data(true)`, and a session ticket from an
+    /// earlier connection to the server.
+    ///
+    /// The default is [`Http3ZeroRtt::Disabled`].
     #[cfg(feature = "http3")]
     #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
-    pub fn http3_max_idle_timeout(mut self, value: Duration) -> ClientBuilder {
-        self.config.quic_max_idle_timeout = Some(value);
+    pub fn http3_zero_rtt(mut self, policy: Http3ZeroRtt) -> ClientBuilder {
+        self.config.http3_zero_rtt = policy;
         self
     }
 
-    /// Maximum number of bytes the peer may transmit without acknowledgement on any one stream
-    /// before becoming blocked.
-    ///
-    /// Please see docs in [`TransportConfig`] in [`quinn`].
+    /// Race HTTP/3 against HTTP/1.1 and HTTP/2 for new origins.
     ///
-    /// [`TransportConfig`]: https://docs.rs/quinn/latest/quinn/struct.TransportConfig.html
+    /// The first request to an `https` origin opens a QUIC connection and a
+    /// TCP connection at once, and is sent over whichever completes its
+    /// handshake first. The winning protocol is remembered for the origin,
+    /// until an HTTP/3 request to it fails.
     ///
-    /// # Panics
+    /// Only requests that leave their version unset take part, and not
+    /// those sent through a proxy or with a `resolve_map`.
     ///
-    /// Panics if the value is over 2^62.
+    /// The default is false.
     #[cfg(feature = "http3")]
     #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
-    pub fn http3_stream_receive_window(mut self, value: u64) -> ClientBuilder {
-        self.config.quic_stream_receive_window = Some(value.try_into().unwrap());
+    pub fn http3_race_tcp(mut self, enabled: bool) -> ClientBuilder {
+        self.config.http3_race_tcp = enabled;
         self
     }
 
-    /// Maximum number of bytes the peer may transmit across all streams of a connection before
-    /// becoming blocked.
-    ///
-    /// Please see docs in [`TransportConfig`] in [`quinn`].
-    ///
-    /// [`TransportConfig`]: https://docs.rs/quinn/latest/quinn/struct.TransportConfig.html
+    /// Sets the maximum idle HTTP/3 connections kept per host.
     ///
-    /// # Panics
+    /// This is the HTTP/3 counterpart of
+    /// [`pool_max_idle_per_host()`](ClientBuilder::pool_max_idle_per_host).
+    /// The idle timeout is shared with the other connections, see
+    /// [`pool_idle_timeout()`](ClientBuilder::pool_idle_timeout).
+    ///
+    /// Default is `usize::MAX` (no limit).
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
+    pub fn http3_pool_max_idle_per_host(mut self, max: usize) -> ClientBuilder {
+        self.config.http3_pool_max_idle_per_host = max;
+        self
+    }
+
+    /// Sets the number of requests in flight on an HTTP/3 connection before
+    /// another connection to the host is opened.
+    ///
+    /// While the new connection is being opened, requests share the least
+    /// busy of the existing ones.
+    ///
+    /// Default is no limit, so all the requests to a host share one
+    /// connection. Building the `Client` fails if the limit is zero.
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
+    pub fn http3_max_streams_per_connection(mut self, max: u32) -> ClientBuilder {
+        self.config.http3_max_streams_per_connection = Some(max);
+        self
+    }
+
+    /// Maximum duration of inactivity to accept before timing out the QUIC connection.
+    ///
+    /// Please see docs in [`TransportConfig`] in [`quinn`].
+    ///
+    /// [`TransportConfig`]: https://docs.rs/quinn/latest/quinn/struct.TransportConfig.html
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
+    pub fn http3_max_idle_timeout(mut self, value: Duration) -> ClientBuilder {
+        self.config.quic_max_idle_timeout = Some(value);
+        self
+    }
+
+    /// Maximum number of bytes the peer may transmit without acknowledgement on any one stream
+    /// before becoming blocked.
+    ///
+    /// Please see docs in [`TransportConfig`] in [`quinn`].
+    ///
+    /// [`TransportConfig`]: https://docs.rs/quinn/latest/quinn/struct.TransportConfig.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is over 2^62.
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
+    pub fn http3_stream_receive_window(mut self, value: u64) -> ClientBuilder {
+        self.config.quic_stream_receive_window = Some(value.try_into().unwrap());
+        self
+    }
+
+    /// Maximum number of bytes the peer may transmit across all streams of a connection before
+    /// becoming blocked.
+    ///
+    /// Please see docs in [`TransportConfig`] in [`quinn`].
+    ///
+    /// [`TransportConfig`]: https://docs.rs/quinn/latest/quinn/struct.TransportConfig.html
+    ///
+    /// # Panics
     ///
     /// Panics if the value is over 2^62.
     #[cfg(feature = "http3")]
@@ -1962,6 +3635,22 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the congestion controller of QUIC connections.
+    ///
+    /// Please see docs in [`ControllerFactory`] in [`quinn`], which also
+    /// provides the Cubic, NewReno and BBR controllers. The default is Cubic.
+    ///
+    /// [`ControllerFactory`]: https://docs.rs/quinn/latest/quinn/congestion/trait.ControllerFactory.html
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
+    pub fn http3_congestion_controller(
+        mut self,
+        factory: Arc<dyn ControllerFactory + Send + Sync>,
+    ) -> ClientBuilder {
+        self.config.quic_congestion_controller = Some(factory);
+        self
+    }
+
     /// Adds a new Tower [`Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html) to the
     /// base connector [`Service`](https://docs.rs/tower/latest/tower/trait.Service.html) which
     /// is responsible for connection establishment.
@@ -1998,9 +3687,60 @@ impl ClientBuilder {
 
         self
     }
+
+    /// Adds a new Tower [`Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html) to the
+    /// [`RequestService`] which sends requests, for instance to sign or cache
+    /// them.
+    ///
+    /// Each subsequent invocation of this function will wrap previous layers.
+    ///
+    /// The layers are called for every request sent, so once per redirect
+    /// and retry. The requests they receive are final: they have an absolute
+    /// URI, and the default headers, cookies and proxy authorization are
+    /// already added. The responses they return have their body not
+    /// decompressed yet, and their cookies, redirects and `Retry-After` are
+    /// handled after them. The [`timeout`](ClientBuilder::timeout) includes
+    /// the time spent in the layers.
+    ///
+    /// Errors returned by the layers are reported as request errors. HTTP/3
+    /// requests aren't sent through the layers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reqwest::header::HeaderValue;
+    ///
+    /// # #[cfg(not(feature = "rustls-tls-no-provider"))]
+    /// let client = reqwest::Client::builder()
+    ///     .request_layer(tower::util::MapRequestLayer::new(
+    ///         |mut req: http::Request<reqwest::Body>| {
+    ///             req.headers_mut()
+    ///                 .insert("x-signature", HeaderValue::from_static("..."));
+    ///             req
+    ///         },
+    ///     ))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn request_layer<L>(mut self, layer: L) -> ClientBuilder
+    where
+        L: Layer<RequestService> + Clone + Send + Sync + 'static,
+        L::Service: Service<HttpRequest<Body>, Response = http::Response<Body>, Error = BoxError>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as Service<HttpRequest<Body>>>::Future: Send + 'static,
+    {
+        let layer = BoxCloneSyncServiceLayer::new(layer);
+
+        self.config.request_layers.push(layer);
+
+        self
+    }
 }
 
-type HyperClient = hyper_util::client::legacy::Client<Connector, super::Body>;
+pub(super) type HyperClient = hyper_util::client::legacy::Client<Connector, super::Body>;
 
 impl Default for Client {
     fn default() -> Self {
@@ -2083,6 +3823,187 @@ impl Client {
         self.request(Method::HEAD, url)
     }
 
+    /// Gracefully shut down this `Client`.
+    ///
+    /// The client, and all of its clones, stop accepting new requests: sending
+    /// one fails with a request error. Requests already in flight can finish,
+    /// including reading their response bodies, until `deadline` elapses.
+    ///
+    /// If [`ClientBuilder::close_connections_on_shutdown()`] is enabled, all
+    /// pooled connections are then closed, which aborts any requests still
+    /// in flight. Otherwise, idle connections are only closed once every
+    /// clone of the `Client` is dropped, or when they time out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let client = reqwest::Client::builder()
+    ///     .close_connections_on_shutdown(true)
+    ///     .build()
+    ///     .unwrap();
+    /// // ... use the client ...
+    /// client.shutdown(std::time::Duration::from_secs(5)).await;
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, deadline: Duration) {
+        self.inner.shutdown.shutdown(deadline).await
+    }
+
+    /// Probe what an origin supports, with an `OPTIONS` request.
+    ///
+    /// The request is sent to the root of the origin of `origin`, and the
+    /// `Allow`, `Accept-Patch` and `Accept-Ranges` headers of the response
+    /// are parsed. A `405 Method Not Allowed` response is accepted, since it
+    /// lists the allowed methods too.
+    ///
+    /// The result is cached per origin, for the duration set with
+    /// [`ClientBuilder::capabilities_ttl()`], so this can be called before
+    /// every request.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let url = "https://example.com/items/1";
+    ///
+    /// let caps = client.capabilities(url).await?;
+    /// let req = if caps.accepts_patch("application/merge-patch+json") {
+    ///     client
+    ///         .patch(url)
+    ///         .header("content-type", "application/merge-patch+json")
+    /// } else {
+    ///     client.put(url)
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if there was an error while sending the request, or
+    /// if the server responded with an error status other than 405.
+    pub async fn capabilities<U: IntoUrl>(
+        &self,
+        origin: U,
+    ) -> crate::Result<crate::capabilities::Capabilities> {
+        crate::capabilities::capabilities(self, origin).await
+    }
+
+    /// List the open connections upgraded with [`Response::upgrade()`].
+    ///
+    /// This is always empty, unless [`ClientBuilder::track_upgrades()`] is
+    /// enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run(client: &reqwest::Client) {
+    /// // abort the tunnels that have been idle for too long
+    /// for upgrade in client.upgrades() {
+    ///     if upgrade.idle() > std::time::Duration::from_secs(300) {
+    ///         upgrade.abort();
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn upgrades(&self) -> Vec<super::upgrade::UpgradeHandle> {
+        match self.inner.upgrades {
+            Some(ref registry) => registry.list(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Reads the system proxy settings again.
+    ///
+    /// The system settings, from the environment variables like
+    /// `HTTP_PROXY` and `NO_PROXY`, and from the OS on Windows and macOS, are
+    /// read when the `Client` is built. This makes the `Client`, and all its
+    /// clones, use the current settings for new connections instead, for
+    /// instance after a VPN connected or disconnected.
+    ///
+    /// This does nothing if the `Client` was built with
+    /// [`ClientBuilder::no_proxy()`], or with its own proxies. Connections
+    /// that are already pooled keep being used.
+    pub fn reload_system_proxy(&self) {
+        for proxy in self.inner.proxies.iter() {
+            proxy.reload_system();
+        }
+    }
+
+    /// Closes the pooled HTTP/3 connections.
+    ///
+    /// Requests in flight finish on their connection, which is closed once
+    /// they're done. New requests open new connections.
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(all(reqwest_unstable, feature = "http3",))))]
+    pub fn flush_http3_pool(&self) {
+        if let Some(ref h3_client) = self.inner.h3_client {
+            h3_client.flush();
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn proxies(&self) -> Arc<Vec<Proxy>> {
+        self.inner.proxies.clone()
+    }
+
+    pub(crate) fn capabilities_cache(&self) -> &crate::capabilities::Cache {
+        &self.inner.capabilities
+    }
+
+    pub(crate) fn codec(&self, encoding: &str) -> Option<&dyn crate::codec::Codec> {
+        self.inner.accepts.codecs.as_ref()?.get(encoding)
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn canonical_json(&self) -> bool {
+        self.inner.canonical_json
+    }
+
+    /// Download a resource, unless it hasn't changed since a previous download.
+    ///
+    /// The validators in `meta`, from the previous download, are sent as
+    /// `If-None-Match` and `If-Modified-Since` headers. If the server responds
+    /// with `304 Not Modified`, this returns [`Download::NotModified`].
+    /// Otherwise, the new content can be streamed from the returned response,
+    /// and the returned `CacheMeta` should be stored with it for next time.
+    ///
+    /// [`Download::NotModified`]: crate::conditional::Download::NotModified
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use reqwest::conditional::{CacheMeta, Download};
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let meta = CacheMeta::new(Some("\"33a64df5\"".into()), None);
+    ///
+    /// match client.download_if_changed("https://example.com/index.json", &meta).await? {
+    ///     Download::NotModified => println!("up to date"),
+    ///     Download::Modified { response, meta } => {
+    ///         let content = response.bytes().await?;
+    ///         println!("downloaded {} bytes, new etag: {:?}", content.len(), meta.etag());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if there was an error while sending the request, or
+    /// if the server responded with an error status.
+    pub async fn download_if_changed<U: IntoUrl>(
+        &self,
+        url: U,
+        meta: &crate::conditional::CacheMeta,
+    ) -> crate::Result<crate::conditional::Download> {
+        crate::conditional::download_if_changed(self, url, meta).await
+    }
+
     /// Start building a `Request` with the `Method` and `Url`.
     ///
     /// Returns a `RequestBuilder`, which will allow setting headers and
@@ -2115,17 +4036,91 @@ impl Client {
         self.execute_request(request)
     }
 
-    pub(super) fn execute_request(&self, req: Request) -> Pending {
-        let (method, url, mut headers, body, timeout, version) = req.pieces();
+    pub(crate) fn execute_request(&self, req: Request) -> Pending {
+        if let Some(ref limits) = self.inner.limits {
+            return Pending {
+                inner: PendingInner::Queued(Box::new(Queued::new(self.clone(), limits, req))),
+            };
+        }
+        self.execute_unlimited(req)
+    }
+
+    /// Sends the request, without waiting for the concurrency limits.
+    pub(super) fn execute_unlimited(&self, req: Request) -> Pending {
+        let delay = req.hedge_after().or_else(|| match *req.method() {
+            Method::GET | Method::HEAD => self.inner.hedge_after,
+            _ => None,
+        });
+        if let Some(delay) = delay {
+            if let Some(hedge) = req.try_clone() {
+                let primary = self.execute_once(req);
+                return Pending {
+                    inner: PendingInner::Hedged(Box::new(Hedge::new(
+                        self.clone(),
+                        primary,
+                        hedge,
+                        delay,
+                    ))),
+                };
+            }
+        }
+        self.execute_once(req)
+    }
+
+    /// Sends the request, without hedging.
+    pub(super) fn execute_once(&self, mut req: Request) -> Pending {
+        let resolve_map = req.resolve_map_mut().take();
+        #[cfg(feature = "cookies")]
+        let cookie_store = req
+            .cookie_store_mut()
+            .take()
+            .or_else(|| self.inner.cookie_store.clone());
+        #[cfg(not(feature = "cookies"))]
+        let cookie_store = ();
+        let on_informational = req.on_informational_mut().take();
+        let expect_continue = req.expect_continue();
+        let advertise_encodings = req.accept_encoding();
+        #[cfg(feature = "http2")]
+        let protocol = req.protocol_mut().take();
+        let upload_rate = req
+            .max_upload_rate()
+            .or(self.inner.max_upload_rate)
+            .filter(|&rate| rate > 0);
+        let download_rate = req
+            .max_download_rate()
+            .or(self.inner.max_download_rate)
+            .filter(|&rate| rate > 0);
+        let (method, mut url, mut headers, body, timeout, max_redirects, version) = req.pieces();
         if url.scheme() != "http" && url.scheme() != "https" {
             return Pending::new_err(error::url_bad_scheme(url));
         }
 
+        if let Some(ref hsts_store) = self.inner.hsts_store {
+            hsts::upgrade(&**hsts_store, &mut url);
+        }
+
         // check if we're in https_only mode and check the scheme of the current URL
         if self.inner.https_only && url.scheme() != "https" {
             return Pending::new_err(error::url_bad_scheme(url));
         }
 
+        let circuit_permit = match self.inner.circuit_breakers {
+            Some(ref breakers) => match breakers.acquire(&url) {
+                Some(permit) => Some(permit),
+                None => return Pending::new_err(error::circuit_open(url)),
+            },
+            None => None,
+        };
+
+        let shutdown_guard = match self.inner.shutdown.start_request() {
+            Some(guard) => guard,
+            None => {
+                return Pending::new_err(
+                    error::request("client has been shut down").with_url(url),
+                )
+            }
+        };
+
         // insert default headers in the request headers
         // without overwriting already appended headers.
         for (key, value) in &self.inner.headers {
@@ -2134,24 +4129,50 @@ impl Client {
             }
         }
 
+        if let Some(ref source) = self.inner.trace_context {
+            if let Some(context) = source.current() {
+                context.inject(&mut headers);
+            }
+        }
+
         // Add cookies from the cookie store.
         #[cfg(feature = "cookies")]
         {
-            if let Some(cookie_store) = self.inner.cookie_store.as_ref() {
+            if let Some(cookie_store) = cookie_store.as_ref() {
                 if headers.get(crate::header::COOKIE).is_none() {
                     add_cookie_header(&mut headers, &**cookie_store, &url);
                 }
             }
         }
 
-        let accept_encoding = self.inner.accepts.as_str();
+        let accept_encoding = self
+            .inner
+            .accepts
+            .header_value()
+            .filter(|_| advertise_encodings);
 
         if let Some(accept_encoding) = accept_encoding {
             if !headers.contains_key(ACCEPT_ENCODING) && !headers.contains_key(RANGE) {
-                headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(accept_encoding));
+                headers.insert(ACCEPT_ENCODING, accept_encoding);
             }
         }
 
+        match self.inner.header_value_validation {
+            HeaderValueValidation::Permissive => (),
+            HeaderValueValidation::Strict => {
+                if let Some(name) = crate::util::find_non_visible_ascii_header(&headers) {
+                    return Pending::new_err(
+                        error::invalid_header_value(
+                            name.clone(),
+                            "header value contains bytes that aren't visible ASCII",
+                        )
+                        .with_url(url),
+                    );
+                }
+            }
+            HeaderValueValidation::Sanitize => crate::util::sanitize_header_values(&mut headers),
+        }
+
         let uri = match try_uri(&url) {
             Ok(uri) => uri,
             _ => return Pending::new_err(error::url_invalid_uri(url)),
@@ -2165,14 +4186,111 @@ impl Client {
             None => (None, Body::empty()),
         };
 
+        let buffer_limit = std::cmp::max(
+            self.inner.redirect_body_buffer_limit,
+            self.inner.retry_body_buffer_limit,
+        );
+        let (body, replay) = match buffer_limit {
+            Some(limit) => body.recorded(limit),
+            None => (body, None),
+        };
+
+        let body = match upload_rate {
+            Some(rate) => body.throttled(rate),
+            None => body,
+        };
+
+        let (body, continue_gate) = match expect_continue {
+            Some(timeout) => body.expect_continue(timeout),
+            None => (body, None),
+        };
+        let on_informational = match continue_gate {
+            Some(ref gate) => {
+                headers.insert(EXPECT, HeaderValue::from_static("100-continue"));
+                Some(open_on_continue(gate.clone(), on_informational))
+            }
+            None => on_informational,
+        };
+
+        #[cfg(feature = "http3")]
+        {
+            if version == http::Version::HTTP_3
+                && self.inner.h3_client.is_some()
+                && self.inner.is_proxied(&uri)
+            {
+                return Pending::new_err(h3_proxied(url));
+            }
+            if version == http::Version::HTTP_3
+                && self.inner.h3_client.is_some()
+                && resolve_map.is_some()
+            {
+                return Pending::new_err(
+                    error::request("a resolve_map can't be used with HTTP/3").with_url(url),
+                );
+            }
+        }
+
+        // a request without a version either goes over the protocol that won
+        // the race to its origin, or races for it
+        #[cfg(feature = "http3")]
+        let (version, racing) = match self.inner.race {
+            Some(ref race)
+                if version == http::Version::default()
+                    && url.scheme() == "https"
+                    && resolve_map.is_none()
+                    && !self.inner.is_proxied(&uri) =>
+            {
+                match race.winner(&url) {
+                    Some(Winner::Http3) => (http::Version::HTTP_3, false),
+                    Some(Winner::Tcp) => (version, false),
+                    None => (version, true),
+                }
+            }
+            _ => (version, false),
+        };
+
+        if !self.inner.interceptors.is_empty() {
+            let parts = RequestParts::new(&method, &url, version, &headers);
+            for interceptor in &self.inner.interceptors {
+                interceptor.on_request(&parts);
+            }
+        }
+
         self.proxy_auth(&uri, &mut headers);
 
+        #[cfg(feature = "http2")]
+        let (hyper, lane) = match (resolve_map, &self.inner.lanes) {
+            (Some(overrides), _) => (self.inner.scoped_dns.client(overrides), None),
+            (None, Some(lanes)) => {
+                let (hyper, lane) = lanes.acquire(&url, &self.inner.hyper);
+                (hyper, Some(lane))
+            }
+            (None, None) => (self.inner.hyper.clone(), None),
+        };
+        #[cfg(not(feature = "http2"))]
+        let (hyper, lane) = match resolve_map {
+            Some(overrides) => (self.inner.scoped_dns.client(overrides), None),
+            None => (self.inner.hyper.clone(), None),
+        };
+
         let builder = hyper::Request::builder()
             .method(method.clone())
             .uri(uri)
             .version(version);
 
         let in_flight = match version {
+            #[cfg(feature = "http3")]
+            _ if racing => {
+                let mut req = builder.body(body).expect("valid request parts");
+                *req.headers_mut() = headers.clone();
+                set_on_informational(&mut req, on_informational.as_ref());
+                let race = self.inner.race.as_ref().expect("racing needs a race");
+                let h3_client = self.inner.h3_client.as_ref().expect("racing needs HTTP/3");
+                ResponseFuture::Racing(
+                    Box::pin(race.run(h3_client, &url, req.uri().clone())),
+                    Some(Box::new(req)),
+                )
+            }
             #[cfg(feature = "http3")]
             http::Version::HTTP_3 if self.inner.h3_client.is_some() => {
                 let mut req = builder.body(body).expect("valid request parts");
@@ -2182,11 +4300,18 @@ impl Client {
             _ => {
                 let mut req = builder.body(body).expect("valid request parts");
                 *req.headers_mut() = headers.clone();
-                ResponseFuture::Default(self.inner.hyper.request(req))
+                set_on_informational(&mut req, on_informational.as_ref());
+                #[cfg(feature = "http2")]
+                if let Some(ref protocol) = protocol {
+                    req.extensions_mut()
+                        .insert(hyper::ext::Protocol::from(protocol.as_str()));
+                }
+                self.inner.send(&hyper, req)
             }
         };
 
         let total_timeout = timeout
+            .or_else(|| self.inner.method_timeouts.get(&method))
             .or(self.inner.request_timeout)
             .map(tokio::time::sleep)
             .map(Box::pin);
@@ -2197,18 +4322,53 @@ impl Client {
             .map(tokio::time::sleep)
             .map(Box::pin);
 
+        #[cfg(feature = "tracing")]
+        let span = {
+            // don't leak a password into traces
+            let mut url = url.clone();
+            let _ = url.set_password(None);
+            tracing::info_span!(
+                "request",
+                method = %method,
+                url = %url,
+                status = tracing::field::Empty,
+            )
+        };
+
         Pending {
             inner: PendingInner::Request(PendingRequest {
                 method,
                 url,
                 headers,
                 body: reusable,
+                replay,
 
                 urls: Vec::new(),
+                hops: Vec::new(),
+                max_redirects,
+                redirect: None,
+                retry_after: None,
+                waits: Vec::new(),
 
                 retry_count: 0,
+                redirect_count: 0,
 
                 client: self.inner.clone(),
+                hyper,
+                cookie_store,
+                on_informational,
+                continue_gate,
+                upload_rate,
+                download_rate,
+                shutdown_guard: Some(shutdown_guard),
+                lane,
+                circuit_permit,
+                spans: Box::new(RequestSpans {
+                    #[cfg(feature = "tracing")]
+                    request: span,
+                    #[cfg(feature = "tracing")]
+                    attempt: tracing::Span::none(),
+                }),
 
                 in_flight,
                 total_timeout,
@@ -2282,6 +4442,50 @@ impl tower_service::Service<Request> for &'_ Client {
     }
 }
 
+/// Sends `http::Request`s, for use with crates built on `tower` and `http`,
+/// such as gRPC clients.
+///
+/// The request body is streamed as is, and the response body is returned
+/// without buffering, so DATA frame boundaries and trailers are preserved
+/// in both directions.
+impl<B> tower_service::Service<HttpRequest<B>> for Client
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Into<Bytes>,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<Body>;
+    type Error = crate::Error;
+    type Future = HttpPending;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: HttpRequest<B>) -> Self::Future {
+        HttpPending::new(self, req)
+    }
+}
+
+impl<B> tower_service::Service<HttpRequest<B>> for &'_ Client
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Into<Bytes>,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<Body>;
+    type Error = crate::Error;
+    type Future = HttpPending;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: HttpRequest<B>) -> Self::Future {
+        HttpPending::new(self, req)
+    }
+}
+
 impl fmt::Debug for ClientBuilder {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut builder = f.debug_struct("ClientBuilder");
@@ -2300,10 +4504,27 @@ impl Config {
             if let Some(_) = self.cookie_store {
                 f.field("cookie_store", &true);
             }
+
+            if self.cookie_observer.is_some() {
+                f.field("cookie_observer", &true);
+            }
+        }
+
+        if self.hsts_store.is_some() {
+            f.field("hsts_store", &true);
         }
 
         f.field("accepts", &self.accepts);
 
+        if !self.codecs.is_empty() {
+            let names = self.codecs.iter().map(|(name, _)| name).collect::<Vec<_>>();
+            f.field("content_codecs", &names);
+        }
+
+        if let Some(ref accept_encoding) = self.accept_encoding {
+            f.field("accept_encoding", accept_encoding);
+        }
+
         if !self.proxies.is_empty() {
             f.field("proxies", &self.proxies);
         }
@@ -2316,33 +4537,129 @@ impl Config {
             f.field("referer", &true);
         }
 
-        f.field("default_headers", &self.headers);
+        if let Some(limit) = self.redirect_body_buffer_limit {
+            f.field("redirect_body_buffer_limit", &limit);
+        }
 
-        if self.http1_title_case_headers {
-            f.field("http1_title_case_headers", &true);
+        if let Some(limit) = self.retry_body_buffer_limit {
+            f.field("retry_body_buffer_limit", &limit);
         }
 
-        if self.http1_allow_obsolete_multiline_headers_in_responses {
-            f.field("http1_allow_obsolete_multiline_headers_in_responses", &true);
+        if let Some(max) = self.retry_after {
+            f.field("retry_after", &max);
         }
 
-        if self.http1_ignore_invalid_headers_in_responses {
-            f.field("http1_ignore_invalid_headers_in_responses", &true);
+        if self.capabilities_ttl != CAPABILITIES_TTL {
+            f.field("capabilities_ttl", &self.capabilities_ttl);
+        }
+
+        if self.on_deprecation.is_some() {
+            f.field("on_deprecation", &true);
+        }
+
+        if let Some(ref content_type) = self.expected_content_type {
+            f.field("expected_content_type", content_type);
+        }
+
+        #[cfg(feature = "json")]
+        if self.canonical_json {
+            f.field("canonical_json", &true);
+        }
+
+        #[cfg(feature = "digest")]
+        if self.verify_content_digest {
+            f.field("verify_content_digest", &true);
+        }
+
+        if self.pool_events.is_some() {
+            f.field("pool_events", &true);
+        }
+
+        if let Some(max) = self.max_connections_per_host {
+            f.field("max_connections_per_host", &max);
+        }
+
+        if let Some(ref d) = self.pool_acquire_timeout {
+            f.field("pool_acquire_timeout", d);
+        }
+
+        if self.track_upgrades {
+            f.field("track_upgrades", &true);
+        }
+
+        if self.close_connections_on_shutdown {
+            f.field("close_connections_on_shutdown", &true);
+        }
+
+        f.field("default_headers", &self.headers);
+
+        if self.http1_title_case_headers {
+            f.field("http1_title_case_headers", &true);
+        }
+
+        if self.http1_allow_obsolete_multiline_headers_in_responses {
+            f.field("http1_allow_obsolete_multiline_headers_in_responses", &true);
+        }
+
+        if self.http1_ignore_invalid_headers_in_responses {
+            f.field("http1_ignore_invalid_headers_in_responses", &true);
         }
 
         if self.http1_allow_spaces_after_header_name_in_responses {
             f.field("http1_allow_spaces_after_header_name_in_responses", &true);
         }
 
+        if self.http1_probe_idle_connections {
+            f.field("http1_probe_idle_connections", &true);
+        }
+
+        if self.http1_preserve_raw_headers {
+            f.field("http1_preserve_raw_headers", &true);
+        }
+
+        if let Some(ref v) = self.allowed_ports {
+            f.field("allowed_ports", v);
+        }
+
         if matches!(self.http_version_pref, HttpVersionPref::Http1) {
             f.field("http1_only", &true);
         }
 
+        match self.header_value_validation {
+            HeaderValueValidation::Permissive => (),
+            HeaderValueValidation::Strict => {
+                f.field("strict_header_values", &true);
+            }
+            HeaderValueValidation::Sanitize => {
+                f.field("sanitize_header_values", &true);
+            }
+        }
+
         #[cfg(feature = "http2")]
         if matches!(self.http_version_pref, HttpVersionPref::Http2) {
             f.field("http2_prior_knowledge", &true);
         }
 
+        #[cfg(feature = "http2")]
+        if self.http2_cleartext_prior_knowledge {
+            f.field("http2_cleartext_prior_knowledge", &true);
+        }
+
+        #[cfg(feature = "http2")]
+        if let Some(ref ttl) = self.http2_fallback {
+            f.field("http2_fallback", ttl);
+        }
+
+        #[cfg(feature = "http2")]
+        if self.http2_events.is_some() {
+            f.field("http2_events", &true);
+        }
+
+        #[cfg(feature = "http2")]
+        if let Some(max) = self.http2_max_concurrent_streams {
+            f.field("http2_max_concurrent_streams", &max);
+        }
+
         if let Some(ref d) = self.connect_timeout {
             f.field("connect_timeout", d);
         }
@@ -2351,6 +4668,46 @@ impl Config {
             f.field("timeout", d);
         }
 
+        if !self.method_timeouts.is_empty() {
+            f.field("method_timeouts", &self.method_timeouts);
+        }
+
+        if let Some(ref d) = self.hedge_after {
+            f.field("hedge_after", d);
+        }
+
+        if let Some(rate) = self.max_upload_rate {
+            f.field("max_upload_rate", &rate);
+        }
+
+        if let Some(rate) = self.max_download_rate {
+            f.field("max_download_rate", &rate);
+        }
+
+        if let Some(max) = self.max_concurrent_requests {
+            f.field("max_concurrent_requests", &max);
+        }
+
+        if let Some(max) = self.max_concurrent_requests_per_host {
+            f.field("max_concurrent_requests_per_host", &max);
+        }
+
+        if let Some(ref d) = self.concurrency_queue_timeout {
+            f.field("concurrency_queue_timeout", d);
+        }
+
+        if let Some(ref breaker) = self.circuit_breaker {
+            f.field("circuit_breaker", breaker);
+        }
+
+        if self.trace_context.is_some() {
+            f.field("trace_context", &true);
+        }
+
+        if !self.interceptors.is_empty() {
+            f.field("interceptors", &self.interceptors.len());
+        }
+
         if let Some(ref v) = self.local_address {
             f.field("local_address", v);
         }
@@ -2385,6 +4742,32 @@ impl Config {
                 f.field("max_tls_version", max_tls_version);
             }
 
+            if let Some(ref tls_cipher_suites) = self.tls_cipher_suites {
+                f.field("tls_cipher_suites", tls_cipher_suites);
+            }
+
+            if !self.tls_host_options.is_empty() {
+                f.field("tls_host_options", &self.tls_host_options);
+            }
+
+            if !self.tls_server_names.is_empty() {
+                f.field("tls_server_names", &self.tls_server_names);
+            }
+
+            if let Some(ref tls_key_exchange_groups) = self.tls_key_exchange_groups {
+                f.field("tls_key_exchange_groups", tls_key_exchange_groups);
+            }
+
+            #[cfg(feature = "rustls-tls-ocsp")]
+            if let Some(ref tls_ocsp) = self.tls_ocsp {
+                f.field("tls_ocsp", tls_ocsp);
+            }
+
+            #[cfg(feature = "__rustls")]
+            if let Some(ref tls_ct_policy) = self.tls_ct_policy {
+                f.field("tls_ct_policy", tls_ct_policy);
+            }
+
             f.field("tls_sni", &self.tls_sni);
 
             f.field("tls_info", &self.tls_info);
@@ -2399,11 +4782,38 @@ impl Config {
             f.field("dns_overrides", &self.dns_overrides);
         }
 
+        if let Some(ref v) = self.dns_timeout {
+            f.field("dns_timeout", v);
+        }
+
+        if self.dns_fallback.is_some() {
+            f.field("dns_fallback", &true);
+        }
+
         #[cfg(feature = "http3")]
         {
             if self.tls_enable_early_data {
                 f.field("tls_enable_early_data", &true);
             }
+
+            if self.http3_zero_rtt != Http3ZeroRtt::Disabled {
+                f.field("http3_zero_rtt", &self.http3_zero_rtt);
+            }
+
+            if self.http3_race_tcp {
+                f.field("http3_race_tcp", &true);
+            }
+
+            if self.http3_pool_max_idle_per_host != usize::MAX {
+                f.field(
+                    "http3_pool_max_idle_per_host",
+                    &self.http3_pool_max_idle_per_host,
+                );
+            }
+
+            if let Some(max) = self.http3_max_streams_per_connection {
+                f.field("http3_max_streams_per_connection", &max);
+            }
         }
     }
 }
@@ -2412,20 +4822,69 @@ struct ClientRef {
     accepts: Accepts,
     #[cfg(feature = "cookies")]
     cookie_store: Option<Arc<dyn cookie::CookieStore>>,
+    #[cfg(feature = "cookies")]
+    cookie_observer: Option<cookie::CookieObserver>,
+    hsts_store: Option<Arc<dyn hsts::HstsStore>>,
     headers: HeaderMap,
+    header_value_validation: HeaderValueValidation,
     hyper: HyperClient,
+    request_service: Option<RequestService>,
+    scoped_dns: ScopedDns,
     #[cfg(feature = "http3")]
     h3_client: Option<H3Client>,
+    #[cfg(feature = "http3")]
+    race: Option<Arc<Race>>,
     redirect_policy: redirect::Policy,
     referer: bool,
+    redirect_body_buffer_limit: Option<usize>,
+    retry_body_buffer_limit: Option<usize>,
+    retry_after: Option<Duration>,
     request_timeout: Option<Duration>,
+    method_timeouts: MethodTimeouts,
+    hedge_after: Option<Duration>,
+    max_upload_rate: Option<u64>,
+    max_download_rate: Option<u64>,
+    limits: Option<Limits>,
+    #[cfg(feature = "http2")]
+    lanes: Option<Arc<Lanes>>,
+    circuit_breakers: Option<Arc<Breakers>>,
+    trace_context: Option<Arc<dyn ContextSource>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
     read_timeout: Option<Duration>,
     proxies: Arc<Vec<Proxy>>,
     proxies_maybe_http_auth: bool,
     https_only: bool,
+    shutdown: Arc<Shutdown>,
+    capabilities: crate::capabilities::Cache,
+    on_deprecation: Option<DeprecationCallback>,
+    expected_content_type: Option<Arc<Mime>>,
+    #[cfg(feature = "json")]
+    canonical_json: bool,
+    #[cfg(feature = "digest")]
+    verify_content_digest: bool,
+    upgrades: Option<Arc<super::upgrade::Registry>>,
+    #[cfg(feature = "http2")]
+    h2_fallback: Option<Arc<H2Fallback>>,
 }
 
 impl ClientRef {
+    /// Sends a request with `hyper`, through the request layers if any.
+    fn send(&self, hyper: &HyperClient, mut req: HttpRequest<Body>) -> ResponseFuture {
+        match self.request_service {
+            Some(ref service) => {
+                req.extensions_mut().insert(SendWith(hyper.clone()));
+                ResponseFuture::Layered(Box::pin(service.clone().oneshot(req)))
+            }
+            None => ResponseFuture::Default(hyper.request(req)),
+        }
+    }
+
+    /// Returns true if a proxy would be used to connect to `uri`.
+    #[cfg(feature = "http3")]
+    fn is_proxied(&self, uri: &Uri) -> bool {
+        self.proxies.iter().any(|proxy| proxy.intercept(uri).is_some())
+    }
+
     fn fmt_fields(&self, f: &mut fmt::DebugStruct<'_, '_>) {
         // Instead of deriving Debug, only print fields when their output
         // would provide relevant or interesting data.
@@ -2435,6 +4894,14 @@ impl ClientRef {
             if let Some(_) = self.cookie_store {
                 f.field("cookie_store", &true);
             }
+
+            if self.cookie_observer.is_some() {
+                f.field("cookie_observer", &true);
+            }
+        }
+
+        if self.hsts_store.is_some() {
+            f.field("hsts_store", &true);
         }
 
         f.field("accepts", &self.accepts);
@@ -2451,15 +4918,81 @@ impl ClientRef {
             f.field("referer", &true);
         }
 
+        if let Some(limit) = self.redirect_body_buffer_limit {
+            f.field("redirect_body_buffer_limit", &limit);
+        }
+
+        if let Some(limit) = self.retry_body_buffer_limit {
+            f.field("retry_body_buffer_limit", &limit);
+        }
+
+        if let Some(max) = self.retry_after {
+            f.field("retry_after", &max);
+        }
+
+        if self.on_deprecation.is_some() {
+            f.field("on_deprecation", &true);
+        }
+
+        if let Some(ref content_type) = self.expected_content_type {
+            f.field("expected_content_type", content_type);
+        }
+
+        #[cfg(feature = "json")]
+        if self.canonical_json {
+            f.field("canonical_json", &true);
+        }
+
+        #[cfg(feature = "digest")]
+        if self.verify_content_digest {
+            f.field("verify_content_digest", &true);
+        }
+
+        if self.upgrades.is_some() {
+            f.field("track_upgrades", &true);
+        }
+
         f.field("default_headers", &self.headers);
 
         if let Some(ref d) = self.request_timeout {
             f.field("timeout", d);
         }
 
+        if !self.method_timeouts.is_empty() {
+            f.field("method_timeouts", &self.method_timeouts);
+        }
+
         if let Some(ref d) = self.read_timeout {
             f.field("read_timeout", d);
         }
+
+        if let Some(ref d) = self.hedge_after {
+            f.field("hedge_after", d);
+        }
+
+        if let Some(rate) = self.max_upload_rate {
+            f.field("max_upload_rate", &rate);
+        }
+
+        if let Some(rate) = self.max_download_rate {
+            f.field("max_download_rate", &rate);
+        }
+
+        if let Some(ref limits) = self.limits {
+            f.field("limits", limits);
+        }
+
+        if let Some(ref breakers) = self.circuit_breakers {
+            f.field("circuit_breaker", breakers);
+        }
+
+        if self.trace_context.is_some() {
+            f.field("trace_context", &true);
+        }
+
+        if !self.interceptors.is_empty() {
+            f.field("interceptors", &self.interceptors.len());
+        }
     }
 }
 
@@ -2470,8 +5003,16 @@ pin_project! {
     }
 }
 
+/// A `Future` resolving to an `http::Response`, from the `tower::Service`
+/// impl for `http::Request`s.
+pub struct HttpPending {
+    inner: Pending,
+}
+
 enum PendingInner {
     Request(PendingRequest),
+    Hedged(Box<Hedge>),
+    Queued(Box<Queued>),
     Error(Option<crate::Error>),
 }
 
@@ -2481,12 +5022,33 @@ pin_project! {
         url: Url,
         headers: HeaderMap,
         body: Option<Option<Bytes>>,
+        replay: Option<Replay>,
 
         urls: Vec<Url>,
+        hops: Vec<redirect::Hop>,
+        max_redirects: Option<usize>,
+        redirect: Option<PendingRedirect>,
+        retry_after: Option<RetryAfter>,
+        waits: Vec<redirect::Wait>,
 
         retry_count: usize,
+        redirect_count: usize,
 
         client: Arc<ClientRef>,
+        // the client of the `resolve_map()` of the request, if any
+        hyper: HyperClient,
+        // the cookie store of the request, or of the client
+        cookie_store: RequestCookieStore,
+        on_informational: Option<OnInformational>,
+        // holds back the body until `100 Continue`
+        continue_gate: Option<ContinueGate>,
+        upload_rate: Option<u64>,
+        download_rate: Option<u64>,
+        shutdown_guard: Option<InFlight>,
+        // the lane of `http2_max_concurrent_streams()`, if any
+        lane: Option<Lane>,
+        circuit_permit: Option<Permit>,
+        spans: Box<RequestSpans>,
 
         #[pin]
         in_flight: ResponseFuture,
@@ -2498,10 +5060,148 @@ pin_project! {
     }
 }
 
+/// The timeouts set with `timeout_for()`, `safe_methods_timeout()` and
+/// `unsafe_methods_timeout()`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MethodTimeouts {
+    pub(crate) methods: HashMap<Method, Duration>,
+    pub(crate) safe: Option<Duration>,
+    pub(crate) unsafe_: Option<Duration>,
+}
+
+impl MethodTimeouts {
+    /// Returns the timeout of requests with `method`, if any.
+    pub(crate) fn get(&self, method: &Method) -> Option<Duration> {
+        if let Some(timeout) = self.methods.get(method) {
+            return Some(*timeout);
+        }
+        match *method {
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE => self.safe,
+            _ => self.unsafe_,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.methods.is_empty() && self.safe.is_none() && self.unsafe_.is_none()
+    }
+}
+
+/// The pooled clients of the requests with a `resolve_map()`, one for each
+/// set of DNS overrides, so that their connections aren't shared with other
+/// requests.
+struct ScopedDns {
+    builder: hyper_util::client::legacy::Builder,
+    connector: ConnectorBuilder,
+    layers: Vec<BoxedConnectorLayer>,
+    clients: Mutex<HashMap<ScopedDnsKey, HyperClient>>,
+}
+
+/// The sorted overrides of a `resolve_map()`.
+type ScopedDnsKey = Vec<(String, Vec<SocketAddr>)>;
+
+impl ScopedDns {
+    fn client(&self, overrides: HashMap<String, Vec<SocketAddr>>) -> HyperClient {
+        let mut key = overrides
+            .iter()
+            .map(|(domain, addrs)| (domain.clone(), addrs.clone()))
+            .collect::<Vec<_>>();
+        key.sort();
+        let mut clients = self.clients.lock().unwrap();
+        clients
+            .entry(key)
+            .or_insert_with(|| {
+                let mut connector = self.connector.clone();
+                connector.set_dns_overrides(Arc::new(overrides));
+                self.builder.build(connector.build(self.layers.clone()))
+            })
+            .clone()
+    }
+}
+
+// `#[cfg]` can't be used on the fields of `PendingRequest`
+#[cfg(not(feature = "http2"))]
+type Lane = ();
+
+#[cfg(feature = "cookies")]
+type RequestCookieStore = Option<Arc<dyn cookie::CookieStore>>;
+#[cfg(not(feature = "cookies"))]
+type RequestCookieStore = ();
+
+/// The tracing spans of a request, and of its current redirect or retry.
+struct RequestSpans {
+    #[cfg(feature = "tracing")]
+    request: tracing::Span,
+    #[cfg(feature = "tracing")]
+    attempt: tracing::Span,
+}
+
+/// A redirect waiting on an async `redirect::Policy`.
+struct PendingRedirect {
+    res: hyper::Response<ResponseBody>,
+    loc: Url,
+    headers: HeaderMap,
+    action: redirect::PendingAction,
+}
+
+/// A `Retry-After` delay, and what to do once it elapsed.
+struct RetryAfter {
+    delay: Pin<Box<Sleep>>,
+    then: AfterDelay,
+}
+
+enum AfterDelay {
+    Redirect(Box<DelayedRedirect>),
+    Resend(Body),
+}
+
+struct DelayedRedirect {
+    res: hyper::Response<ResponseBody>,
+    loc: Url,
+    headers: HeaderMap,
+}
+
+/// The innermost `RequestService`.
+#[derive(Clone)]
+struct SendRequest(HyperClient);
+
+/// The client of the `resolve_map()` of a request sent through the request
+/// layers.
+#[derive(Clone)]
+struct SendWith(HyperClient);
+
+impl Service<HttpRequest<Body>> for SendRequest {
+    type Response = http::Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut req: HttpRequest<Body>) -> Self::Future {
+        let hyper = match req.extensions_mut().remove::<SendWith>() {
+            Some(SendWith(hyper)) => hyper,
+            None => self.0.clone(),
+        };
+        let fut = hyper.request(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res.map(Body::wrap))
+        })
+    }
+}
+
 enum ResponseFuture {
     Default(HyperResponseFuture),
+    Layered(Pin<Box<Oneshot<RequestService, HttpRequest<Body>>>>),
     #[cfg(feature = "http3")]
     H3(H3ResponseFuture),
+    // the request waits for the race to its origin
+    #[cfg(feature = "http3")]
+    Racing(
+        Pin<Box<dyn Future<Output = Winner> + Send>>,
+        Option<Box<HttpRequest<Body>>>,
+    ),
 }
 
 impl PendingRequest {
@@ -2525,6 +5225,166 @@ impl PendingRequest {
         self.project().headers
     }
 
+    /// Acts on the redirect policy, returning the redirect response if it
+    /// isn't followed.
+    fn follow_redirect(
+        mut self: Pin<&mut Self>,
+        res: hyper::Response<ResponseBody>,
+        loc: Url,
+        mut headers: HeaderMap,
+        action: redirect::ActionKind,
+    ) -> Result<Option<hyper::Response<ResponseBody>>, crate::Error> {
+        match action {
+            redirect::ActionKind::Follow => {
+                debug!("redirecting '{}' to '{}'", self.url, loc);
+
+                if loc.scheme() != "http" && loc.scheme() != "https" {
+                    return Err(error::url_bad_scheme(loc));
+                }
+
+                if self.client.https_only && loc.scheme() != "https" {
+                    return Err(error::redirect(error::url_bad_scheme(loc.clone()), loc));
+                }
+
+                #[cfg(feature = "tracing")]
+                {
+                    self.spans.attempt = tracing::debug_span!(
+                        parent: &self.spans.request,
+                        "redirect",
+                        status = res.status().as_u16(),
+                        location = %loc,
+                    );
+                }
+                self.url = loc;
+                self.redirect_count += 1;
+                let uri = try_uri(&self.url)?;
+                #[cfg(feature = "http3")]
+                {
+                    if let ResponseFuture::H3(_) = *self.as_mut().in_flight().as_ref() {
+                        if self.client.is_proxied(&uri) {
+                            return Err(h3_proxied(self.url.clone()));
+                        }
+                    }
+                }
+                let parts = RequestParts::new(&self.method, &self.url, http::Version::default(), &headers);
+                for interceptor in &self.client.interceptors {
+                    interceptor.on_request(&parts);
+                }
+                let body = match self.body {
+                    Some(Some(ref body)) => Body::reusable(body.clone()),
+                    _ => Body::empty(),
+                };
+                let body = match self.upload_rate {
+                    Some(rate) => body.throttled(rate),
+                    None => body,
+                };
+
+                *self.as_mut().in_flight().get_mut() = match *self.as_mut().in_flight().as_ref() {
+                    #[cfg(feature = "http3")]
+                    ResponseFuture::H3(_) => {
+                        let mut req = hyper::Request::builder()
+                            .method(self.method.clone())
+                            .uri(uri.clone())
+                            .body(body)
+                            .expect("valid request parts");
+                        *req.headers_mut() = headers.clone();
+                        std::mem::swap(self.as_mut().headers(), &mut headers);
+                        ResponseFuture::H3(
+                            self.client
+                                .h3_client
+                                .as_ref()
+                                .expect("H3 client must exists, otherwise we can't have a h3 request here")
+                                .request(req),
+                        )
+                    }
+                    _ => {
+                        let mut req = hyper::Request::builder()
+                            .method(self.method.clone())
+                            .uri(uri.clone())
+                            .body(body)
+                            .expect("valid request parts");
+                        *req.headers_mut() = headers.clone();
+                        set_on_informational(&mut req, self.on_informational.as_ref());
+                        std::mem::swap(self.as_mut().headers(), &mut headers);
+                        self.client.send(&self.hyper, req)
+                    }
+                };
+
+                Ok(None)
+            }
+            redirect::ActionKind::Stop => {
+                debug!("redirect policy disallowed redirection to '{loc}'");
+                Ok(Some(res))
+            }
+            redirect::ActionKind::Error(err) => {
+                Err(crate::error::redirect(err, self.url.clone()))
+            }
+        }
+    }
+
+    fn respond(mut self: Pin<&mut Self>, mut res: hyper::Response<ResponseBody>) -> Response {
+        #[cfg(feature = "tracing")]
+        self.spans.request.record("status", res.status().as_u16());
+        if let Some(ref on_deprecation) = self.client.on_deprecation {
+            if let Some(deprecation) = crate::notice::Deprecation::from_headers(res.headers()) {
+                on_deprecation(&self.url, &deprecation);
+            }
+        }
+        res.extensions_mut().insert(RequestCounts {
+            retries: self.retry_count,
+            redirects: self.redirect_count,
+        });
+        if !self.waits.is_empty() {
+            let waits = std::mem::take(&mut self.waits);
+            res.extensions_mut().insert(RetryAfterWaits(waits));
+        }
+        if let Some(ref registry) = self.client.upgrades {
+            res.extensions_mut()
+                .insert(super::upgrade::Tracker(registry.clone()));
+        }
+        if let Some(ref content_type) = self.client.expected_content_type {
+            res.extensions_mut()
+                .insert(ExpectedContentType(content_type.clone()));
+        }
+        let mut headers = std::mem::take(&mut self.headers);
+        if res.version() <= http::Version::HTTP_11 {
+            add_http1_headers(&mut headers, &self.url, &self.body);
+        }
+        res.extensions_mut().insert(RequestHeaders(headers));
+        let guard = self.shutdown_guard.take();
+        let res = res.map(|body| super::body::boxed(super::shutdown::guarded(body, guard)));
+        #[cfg(feature = "http2")]
+        let res = match self.lane.take() {
+            Some(lane) => res.map(|body| super::body::boxed(lanes::hold(body, Some(lane)))),
+            None => res,
+        };
+        let res = match self.download_rate {
+            Some(rate) => res.map(|body| super::body::boxed(Throttled::new(body, rate))),
+            None => res,
+        };
+        #[cfg(feature = "digest")]
+        let res = if self.client.verify_content_digest {
+            crate::digest::verify_headers(res, self.method == Method::HEAD)
+        } else {
+            res
+        };
+        Response::new(
+            res,
+            self.url.clone(),
+            self.client.accepts.clone(),
+            self.total_timeout.take(),
+            self.read_timeout,
+        )
+    }
+
+    /// Takes the buffered bytes of a streaming body, if they are complete
+    /// and within `limit`.
+    fn replay(&mut self, limit: Option<usize>) -> Option<Bytes> {
+        let bytes = self.replay.as_ref()?.take(limit?)?;
+        self.replay = None;
+        Some(bytes)
+    }
+
     #[cfg(any(feature = "http2", feature = "http3"))]
     fn retry_error(mut self: Pin<&mut Self>, err: &(dyn std::error::Error + 'static)) -> bool {
         use log::trace;
@@ -2535,6 +5395,14 @@ impl PendingRequest {
 
         trace!("can retry {err:?}");
 
+        if let Some(None) = self.body {
+            // replay a streaming body, if it was buffered
+            let limit = self.client.retry_body_buffer_limit;
+            if let Some(bytes) = self.replay(limit) {
+                self.body = Some(Some(bytes));
+            }
+        }
+
         let body = match self.body {
             Some(Some(ref body)) => Body::reusable(body.clone()),
             Some(None) => {
@@ -2549,9 +5417,27 @@ impl PendingRequest {
             return false;
         }
         self.retry_count += 1;
+        #[cfg(feature = "tracing")]
+        {
+            self.spans.attempt = tracing::debug_span!(
+                parent: &self.spans.request,
+                "retry",
+                attempt = self.retry_count,
+            );
+        }
 
+        self.resend(body);
+        true
+    }
+
+    /// Sends the request again, to the same URL.
+    fn resend(mut self: Pin<&mut Self>, body: Body) {
         // If it parsed once, it should parse again
         let uri = try_uri(&self.url).expect("URL was already validated as URI");
+        let body = match self.upload_rate {
+            Some(rate) => body.throttled(rate),
+            None => body,
+        };
 
         *self.as_mut().in_flight().get_mut() = match *self.as_mut().in_flight().as_ref() {
             #[cfg(feature = "http3")]
@@ -2577,14 +5463,87 @@ impl PendingRequest {
                     .body(body)
                     .expect("valid request parts");
                 *req.headers_mut() = self.headers.clone();
-                ResponseFuture::Default(self.client.hyper.request(req))
+                set_on_informational(&mut req, self.on_informational.as_ref());
+                self.client.send(&self.hyper, req)
             }
         };
+    }
 
-        true
+    /// Returns the delay to wait and the body to send again after a `429` or
+    /// `503` response, if its `Retry-After` is honored and the request can
+    /// be retried.
+    fn retry_after_resend(
+        mut self: Pin<&mut Self>,
+        headers: &HeaderMap,
+    ) -> Option<(Duration, Body)> {
+        let delay = self.retry_after_delay(headers)?;
+        if self.retry_count >= 2 {
+            debug!("retry count too high to honor Retry-After");
+            return None;
+        }
+        if let Some(None) = self.body {
+            // replay a streaming body, if it was buffered
+            let limit = self.client.retry_body_buffer_limit;
+            if let Some(bytes) = self.replay(limit) {
+                self.body = Some(Some(bytes));
+            }
+        }
+        let body = match self.body {
+            Some(Some(ref body)) => Body::reusable(body.clone()),
+            Some(None) => {
+                debug!("Retry-After was honored, but body not reusable");
+                return None;
+            }
+            None => Body::empty(),
+        };
+        Some((delay, body))
+    }
+
+    /// Returns the `Retry-After` delay of a response, if the client honors
+    /// it and it isn't longer than the configured maximum.
+    fn retry_after_delay(&self, headers: &HeaderMap) -> Option<Duration> {
+        let max = self.client.retry_after?;
+        let delay = redirect::parse_retry_after(headers.get(RETRY_AFTER)?)?;
+        if delay > max {
+            debug!("Retry-After of {delay:?} is longer than {max:?}");
+            return None;
+        }
+        Some(delay)
+    }
+
+    /// Waits for `delay`, recording it, before doing `then`.
+    fn wait_for(mut self: Pin<&mut Self>, status: StatusCode, delay: Duration, then: AfterDelay) {
+        debug!("waiting {delay:?} for Retry-After from '{}'", self.url);
+        let wait = redirect::Wait::new(self.url.clone(), status, delay);
+        self.waits.push(wait);
+        *self.as_mut().project().retry_after = Some(RetryAfter {
+            delay: Box::pin(tokio::time::sleep(delay)),
+            then,
+        });
     }
 }
 
+/// Returns true if `err` is an HTTP/2 error a server, or a middlebox,
+/// caused by not speaking the protocol correctly.
+#[cfg(feature = "http2")]
+fn is_h2_protocol_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<h2::Error>() {
+            return matches!(
+                err.reason(),
+                Some(
+                    h2::Reason::PROTOCOL_ERROR
+                        | h2::Reason::FRAME_SIZE_ERROR
+                        | h2::Reason::COMPRESSION_ERROR
+                )
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
 #[cfg(any(feature = "http2", feature = "http3"))]
 fn is_retryable_error(err: &(dyn std::error::Error + 'static)) -> bool {
     // pop the legacy::Error
@@ -2641,6 +5600,8 @@ impl Future for Pending {
         let inner = self.inner();
         match inner.get_mut() {
             PendingInner::Request(ref mut req) => Pin::new(req).poll(cx),
+            PendingInner::Hedged(ref mut hedge) => Pin::new(&mut **hedge).poll(cx),
+            PendingInner::Queued(ref mut queued) => Pin::new(&mut **queued).poll(cx),
             PendingInner::Error(ref mut err) => Poll::Ready(Err(err
                 .take()
                 .expect("Pending error polled more than once"))),
@@ -2648,12 +5609,70 @@ impl Future for Pending {
     }
 }
 
+impl HttpPending {
+    fn new<B>(client: &Client, req: HttpRequest<B>) -> HttpPending
+    where
+        B: HttpBody + Send + 'static,
+        B::Data: Into<Bytes>,
+        B::Error: Into<BoxError>,
+    {
+        let (parts, body) = req.into_parts();
+        let body = Body::wrap_send(body);
+        let inner = match Request::try_from(HttpRequest::from_parts(parts, body)) {
+            Ok(req) => client.execute_request(req),
+            Err(err) => Pending::new_err(err),
+        };
+        HttpPending { inner }
+    }
+}
+
+impl Future for HttpPending {
+    type Output = Result<http::Response<Body>, crate::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.inner).poll(cx).map_ok(Into::into)
+    }
+}
+
+impl fmt::Debug for HttpPending {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
 impl Future for PendingRequest {
     type Output = Result<Response, crate::Error>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = futures_core::ready!(self.as_mut().poll_response(cx));
+        for interceptor in &self.client.interceptors {
+            match result {
+                Ok(ref res) => interceptor.on_response(&ResponseParts::new(
+                    res.url(),
+                    res.status(),
+                    res.version(),
+                    res.headers(),
+                )),
+                Err(ref err) => interceptor.on_error(err),
+            }
+        }
+        Poll::Ready(result)
+    }
+}
+
+impl PendingRequest {
+    fn poll_response(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Response, crate::Error>> {
+        #[cfg(feature = "tracing")]
+        let _request = self.spans.request.clone().entered();
+
         if let Some(delay) = self.as_mut().total_timeout().as_mut().as_pin_mut() {
             if let Poll::Ready(()) = delay.poll(cx) {
+                if let Some(permit) = self.circuit_permit.take() {
+                    permit.error();
+                }
                 return Poll::Ready(Err(
                     crate::error::request(crate::error::TimedOut).with_url(self.url.clone())
                 ));
@@ -2662,6 +5681,9 @@ impl Future for PendingRequest {
 
         if let Some(delay) = self.as_mut().read_timeout().as_mut().as_pin_mut() {
             if let Poll::Ready(()) = delay.poll(cx) {
+                if let Some(permit) = self.circuit_permit.take() {
+                    permit.error();
+                }
                 return Poll::Ready(Err(
                     crate::error::request(crate::error::TimedOut).with_url(self.url.clone())
                 ));
@@ -2669,45 +5691,205 @@ impl Future for PendingRequest {
         }
 
         loop {
-            let res = match self.as_mut().in_flight().get_mut() {
-                ResponseFuture::Default(r) => match Pin::new(r).poll(cx) {
-                    Poll::Ready(Err(e)) => {
-                        #[cfg(feature = "http2")]
-                        if self.as_mut().retry_error(&e) {
-                            continue;
+            #[cfg(feature = "tracing")]
+            let _attempt = self.spans.attempt.clone().entered();
+
+            if let Some(retry_after) = self.as_mut().project().retry_after.as_mut() {
+                if retry_after.delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                let retry_after = self
+                    .as_mut()
+                    .project()
+                    .retry_after
+                    .take()
+                    .expect("pending Retry-After");
+                match retry_after.then {
+                    AfterDelay::Redirect(redirect) => {
+                        let DelayedRedirect { res, loc, headers } = *redirect;
+                        match self.as_mut().follow_redirect(
+                            res,
+                            loc,
+                            headers,
+                            redirect::ActionKind::Follow,
+                        ) {
+                            Ok(None) => continue,
+                            Ok(Some(res)) => return Poll::Ready(Ok(self.as_mut().respond(res))),
+                            Err(err) => return Poll::Ready(Err(err)),
                         }
-                        return Poll::Ready(Err(
-                            crate::error::request(e).with_url(self.url.clone())
-                        ));
                     }
-                    Poll::Ready(Ok(res)) => res.map(super::body::boxed),
+                    AfterDelay::Resend(body) => {
+                        self.as_mut().resend(body);
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(redirect) = self.as_mut().project().redirect.as_mut() {
+                let action = match redirect.action.as_mut().poll(cx) {
+                    Poll::Ready(action) => action,
+                    Poll::Pending => return Poll::Pending,
+                };
+                let redirect = self
+                    .as_mut()
+                    .project()
+                    .redirect
+                    .take()
+                    .expect("pending redirect");
+                let (action, headers) = action.into_parts();
+                let headers = headers.unwrap_or(redirect.headers);
+                if let redirect::ActionKind::Follow = action {
+                    if let Some(delay) = self.retry_after_delay(redirect.res.headers()) {
+                        let status = redirect.res.status();
+                        let then = AfterDelay::Redirect(Box::new(DelayedRedirect {
+                            res: redirect.res,
+                            loc: redirect.loc,
+                            headers,
+                        }));
+                        self.as_mut().wait_for(status, delay, then);
+                        continue;
+                    }
+                }
+                match self
+                    .as_mut()
+                    .follow_redirect(redirect.res, redirect.loc, headers, action)
+                {
+                    Ok(None) => continue,
+                    Ok(Some(res)) => return Poll::Ready(Ok(self.as_mut().respond(res))),
+                    Err(err) => return Poll::Ready(Err(err)),
+                }
+            }
+
+            #[cfg(feature = "http3")]
+            if let ResponseFuture::Racing(ref mut race, ref mut req) =
+                *self.as_mut().in_flight().get_mut()
+            {
+                let winner = match race.as_mut().poll(cx) {
+                    Poll::Ready(winner) => winner,
+                    Poll::Pending => return Poll::Pending,
+                };
+                let mut req = *req.take().expect("a raced request is sent once");
+                let in_flight = match winner {
+                    Winner::Http3 => {
+                        *req.version_mut() = http::Version::HTTP_3;
+                        ResponseFuture::H3(
+                            self.client
+                                .h3_client
+                                .as_ref()
+                                .expect("racing needs HTTP/3")
+                                .request(req),
+                        )
+                    }
+                    Winner::Tcp => self.client.send(&self.hyper, req),
+                };
+                *self.as_mut().in_flight().get_mut() = in_flight;
+            }
+
+            let res = match self.as_mut().in_flight().get_mut() {
+                ResponseFuture::Default(r) => match Pin::new(r).poll(cx) {
+                    Poll::Ready(res) => res
+                        .map(|res| res.map(super::body::boxed))
+                        .map_err(BoxError::from),
+                    Poll::Pending => return Poll::Pending,
+                },
+                ResponseFuture::Layered(r) => match r.as_mut().poll(cx) {
+                    Poll::Ready(res) => res.map(|res| res.map(super::body::boxed)),
                     Poll::Pending => return Poll::Pending,
                 },
                 #[cfg(feature = "http3")]
                 ResponseFuture::H3(r) => match Pin::new(r).poll(cx) {
                     Poll::Ready(Err(e)) => {
+                        if let Some(ref race) = self.client.race {
+                            race.forget(&self.url);
+                        }
                         if self.as_mut().retry_error(&e) {
                             continue;
                         }
+                        if let Some(permit) = self.circuit_permit.take() {
+                            permit.error();
+                        }
                         return Poll::Ready(Err(
                             crate::error::request(e).with_url(self.url.clone())
                         ));
                     }
-                    Poll::Ready(Ok(res)) => res,
+                    Poll::Ready(Ok(res)) => Ok(res),
                     Poll::Pending => return Poll::Pending,
                 },
+                #[cfg(feature = "http3")]
+                ResponseFuture::Racing(..) => unreachable!("the race was decided above"),
             };
 
+            let res = match res {
+                Err(e) => {
+                    #[cfg(feature = "http2")]
+                    if let Some(ref h2_fallback) = self.client.h2_fallback {
+                        if is_h2_protocol_error(&*e) {
+                            h2_fallback.error(&self.url);
+                        }
+                    }
+                    #[cfg(feature = "http2")]
+                    if self.as_mut().retry_error(&*e) {
+                        continue;
+                    }
+                    if let Some(permit) = self.circuit_permit.take() {
+                        permit.error();
+                    }
+                    return Poll::Ready(Err(crate::error::request(e).with_url(self.url.clone())));
+                }
+                Ok(mut res) => {
+                    if let Some(gate) = self.continue_gate.take() {
+                        gate.abort();
+                    }
+                    crate::pool::record_response(&mut res);
+                    crate::raw_headers::record_response(&mut res);
+                    #[cfg(feature = "http2")]
+                    if let Some(ref h2_fallback) = self.client.h2_fallback {
+                        if res.version() == http::Version::HTTP_2 {
+                            h2_fallback.success(&self.url);
+                        }
+                    }
+                    res
+                }
+            };
+
+            if let Some(permit) = self.circuit_permit.take() {
+                permit.response(res.status());
+            }
+
             #[cfg(feature = "cookies")]
             {
-                if let Some(ref cookie_store) = self.client.cookie_store {
-                    let mut cookies =
-                        cookie::extract_response_cookie_headers(&res.headers()).peekable();
-                    if cookies.peek().is_some() {
-                        cookie_store.set_cookies(&mut cookies, &self.url);
+                if let Some(ref cookie_store) = self.cookie_store {
+                    cookie::store_response_cookies(
+                        &**cookie_store,
+                        res.headers(),
+                        &self.url,
+                        self.client.cookie_observer.as_ref(),
+                    );
+                }
+            }
+            if let Some(ref hsts_store) = self.client.hsts_store {
+                hsts::record(&**hsts_store, res.headers(), &self.url);
+            }
+            if let StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE = res.status() {
+                if let Some((delay, body)) = self.as_mut().retry_after_resend(res.headers()) {
+                    self.retry_count += 1;
+                    #[cfg(feature = "tracing")]
+                    {
+                        self.spans.attempt = tracing::debug_span!(
+                            parent: &self.spans.request,
+                            "retry",
+                            attempt = self.retry_count,
+                            status = res.status().as_u16(),
+                        );
                     }
+                    self.as_mut()
+                        .wait_for(res.status(), delay, AfterDelay::Resend(body));
+                    continue;
                 }
             }
+
+            // the method might be changed for the next request below
+            let method = self.method.clone();
             let should_redirect = match res.status() {
                 StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER => {
                     self.body = None;
@@ -2729,6 +5911,13 @@ impl Future for PendingRequest {
                     true
                 }
                 StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => {
+                    if let Some(None) = self.body {
+                        // replay a streaming body, if it was buffered
+                        let limit = self.client.redirect_body_buffer_limit;
+                        if let Some(bytes) = self.replay(limit) {
+                            self.body = Some(Some(bytes));
+                        }
+                    }
                     match self.body {
                         Some(Some(_)) | None => true,
                         Some(None) => false,
@@ -2761,101 +5950,85 @@ impl Future for PendingRequest {
                     }
                     loc
                 });
-                if let Some(loc) = loc {
+                if let Some(mut loc) = loc {
+                    if let Some(ref hsts_store) = self.client.hsts_store {
+                        hsts::upgrade(&**hsts_store, &mut loc);
+                    }
                     if self.client.referer {
                         if let Some(referer) = make_referer(&loc, &self.url) {
                             self.headers.insert(REFERER, referer);
                         }
                     }
                     let url = self.url.clone();
-                    self.as_mut().urls().push(url);
-                    let action = self
-                        .client
-                        .redirect_policy
-                        .check(res.status(), &loc, &self.urls);
-
-                    match action {
-                        redirect::ActionKind::Follow => {
-                            debug!("redirecting '{}' to '{}'", self.url, loc);
-
-                            if loc.scheme() != "http" && loc.scheme() != "https" {
-                                return Poll::Ready(Err(error::url_bad_scheme(loc)));
-                            }
-
-                            if self.client.https_only && loc.scheme() != "https" {
-                                return Poll::Ready(Err(error::redirect(
-                                    error::url_bad_scheme(loc.clone()),
-                                    loc,
-                                )));
-                            }
-
-                            self.url = loc;
-                            let mut headers =
-                                std::mem::replace(self.as_mut().headers(), HeaderMap::new());
-
-                            remove_sensitive_headers(&mut headers, &self.url, &self.urls);
-                            let uri = try_uri(&self.url)?;
-                            let body = match self.body {
-                                Some(Some(ref body)) => Body::reusable(body.clone()),
-                                _ => Body::empty(),
-                            };
-
-                            // Add cookies from the cookie store.
-                            #[cfg(feature = "cookies")]
-                            {
-                                if let Some(ref cookie_store) = self.client.cookie_store {
-                                    add_cookie_header(&mut headers, &**cookie_store, &self.url);
-                                }
-                            }
-
-                            *self.as_mut().in_flight().get_mut() =
-                                match *self.as_mut().in_flight().as_ref() {
-                                    #[cfg(feature = "http3")]
-                                    ResponseFuture::H3(_) => {
-                                        let mut req = hyper::Request::builder()
-                                            .method(self.method.clone())
-                                            .uri(uri.clone())
-                                            .body(body)
-                                            .expect("valid request parts");
-                                        *req.headers_mut() = headers.clone();
-                                        std::mem::swap(self.as_mut().headers(), &mut headers);
-                                        ResponseFuture::H3(self.client.h3_client
-                        .as_ref()
-                        .expect("H3 client must exists, otherwise we can't have a h3 request here")
-                                            .request(req))
-                                    }
-                                    _ => {
-                                        let mut req = hyper::Request::builder()
-                                            .method(self.method.clone())
-                                            .uri(uri.clone())
-                                            .body(body)
-                                            .expect("valid request parts");
-                                        *req.headers_mut() = headers.clone();
-                                        std::mem::swap(self.as_mut().headers(), &mut headers);
-                                        ResponseFuture::Default(self.client.hyper.request(req))
-                                    }
-                                };
+                    self.as_mut().urls().push(url.clone());
+                    let hop = redirect::Hop::new(method, url, res.status(), &self.headers);
+                    self.hops.push(hop);
+
+                    let mut headers = self.headers.clone();
+                    remove_sensitive_headers(&mut headers, &loc, &self.urls);
+                    // Add cookies from the cookie store.
+                    #[cfg(feature = "cookies")]
+                    {
+                        if let Some(ref cookie_store) = self.cookie_store {
+                            add_cookie_header(&mut headers, &**cookie_store, &loc);
+                        }
+                    }
 
+                    let max_reached = self
+                        .max_redirects
+                        .map_or(false, |max| self.urls.len() >= max);
+                    if !max_reached {
+                        let attempt = || {
+                            redirect::AsyncAttempt::new(
+                                res.status(),
+                                loc.clone(),
+                                self.urls.clone(),
+                                self.method.clone(),
+                                res.headers().clone(),
+                                headers.clone(),
+                            )
+                        };
+                        if let Some(action) = self.client.redirect_policy.check_async(attempt) {
+                            *self.as_mut().project().redirect = Some(PendingRedirect {
+                                res,
+                                loc,
+                                headers,
+                                action,
+                            });
                             continue;
                         }
-                        redirect::ActionKind::Stop => {
-                            debug!("redirect policy disallowed redirection to '{loc}'");
-                        }
-                        redirect::ActionKind::Error(err) => {
-                            return Poll::Ready(Err(crate::error::redirect(err, self.url.clone())));
+                    }
+
+                    let chain = redirect::Chain {
+                        hops: &self.hops,
+                        method: &self.method,
+                        cookie: redirect::cookie_fingerprint(&headers),
+                        max: self.max_redirects,
+                    };
+                    let action =
+                        self.client
+                            .redirect_policy
+                            .check(res.status(), &loc, &self.urls, Some(chain));
+
+                    if let redirect::ActionKind::Follow = action {
+                        if let Some(delay) = self.retry_after_delay(res.headers()) {
+                            let status = res.status();
+                            let then =
+                                AfterDelay::Redirect(Box::new(DelayedRedirect { res, loc, headers }));
+                            self.as_mut().wait_for(status, delay, then);
+                            continue;
                         }
                     }
+
+                    match self.as_mut().follow_redirect(res, loc, headers, action) {
+                        Ok(None) => continue,
+                        Ok(Some(res)) => return Poll::Ready(Ok(self.as_mut().respond(res))),
+                        Err(err) => return Poll::Ready(Err(err)),
+                    }
                 }
             }
 
-            let res = Response::new(
-                res,
-                self.url.clone(),
-                self.client.accepts,
-                self.total_timeout.take(),
-                self.read_timeout,
-            );
-            return Poll::Ready(Ok(res));
+            return Poll::Ready(Ok(self.as_mut().respond(res)));
         }
     }
 }
@@ -2868,11 +6041,180 @@ impl fmt::Debug for Pending {
                 .field("method", &req.method)
                 .field("url", &req.url)
                 .finish(),
+            PendingInner::Hedged(ref hedge) => hedge.fmt(f),
+            PendingInner::Queued(ref queued) => queued.fmt(f),
             PendingInner::Error(ref err) => f.debug_struct("Pending").field("error", err).finish(),
         }
     }
 }
 
+/// Passes the informational responses to `req` to the function set with
+/// `RequestBuilder::on_informational()`.
+fn set_on_informational<B>(req: &mut hyper::Request<B>, f: Option<&OnInformational>) {
+    if let Some(f) = f {
+        let f = f.clone();
+        hyper::ext::on_informational(req, move |res| f(res.status(), res.headers()));
+    }
+}
+
+/// The options of `config` that can't be used together.
+fn config_conflicts(config: &Config) -> Vec<&'static str> {
+    let mut conflicts = Vec::new();
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    if config.interface.is_some() && config.local_address.is_some() {
+        conflicts.push("`interface()` can't be combined with `local_address()`");
+    }
+
+    #[cfg(any(feature = "native-tls", feature = "__rustls"))]
+    if let Some(ref identity) = config.identity {
+        match config.tls {
+            #[cfg(feature = "default-tls")]
+            TlsBackend::Default => {
+                if !identity.is_native_tls() {
+                    conflicts.push("a PEM `Identity` requires the rustls TLS backend");
+                }
+            }
+            #[cfg(feature = "__rustls")]
+            TlsBackend::Rustls => {
+                if identity.is_native_tls() {
+                    conflicts.push("a PKCS #8 or PKCS #12 `Identity` requires the native-tls backend");
+                }
+            }
+            #[allow(unreachable_patterns)]
+            _ => conflicts.push("an `Identity` can't be used with a preconfigured TLS backend"),
+        }
+    }
+
+    if let Some(ref preferences) = config.accept_encoding {
+        // a quality value of 0 means the encoding isn't acceptable
+        if preferences.iter().any(|preference| {
+            preference.quality_value() != Some(0.0)
+                && !config.accepts.decodes(preference.encoding())
+        }) {
+            conflicts.push("`accept_encoding()` lists an encoding that isn't enabled");
+        }
+    }
+
+    #[cfg(feature = "http2")]
+    if config.http2_cleartext_prior_knowledge
+        && matches!(config.http_version_pref, HttpVersionPref::Http1)
+    {
+        conflicts.push("`http2_cleartext_prior_knowledge()` can't be combined with `http1_only()`");
+    }
+
+    #[cfg(feature = "__tls")]
+    {
+        #[cfg(feature = "__rustls")]
+        let rustls = matches!(config.tls, TlsBackend::Rustls);
+        #[cfg(not(feature = "__rustls"))]
+        let rustls = false;
+        if !rustls && config.tls_cipher_suites.is_some() {
+            conflicts.push("`tls_cipher_suites()` requires the rustls TLS backend");
+        }
+        if !rustls && config.tls_key_exchange_groups.is_some() {
+            conflicts.push("`tls_key_exchange_groups()` requires the rustls TLS backend");
+        }
+        #[cfg(feature = "rustls-tls-ocsp")]
+        if !rustls && config.tls_ocsp.is_some() {
+            conflicts.push("`tls_ocsp_check()` requires the rustls TLS backend");
+        }
+        #[cfg(feature = "rustls-tls-ocsp")]
+        if !config.certs_verification && config.tls_ocsp.is_some() {
+            conflicts.push("`tls_ocsp_check()` can't be combined with `danger_accept_invalid_certs()`");
+        }
+        #[cfg(feature = "__rustls")]
+        if !rustls && config.tls_ct_policy.is_some() {
+            conflicts.push("`tls_ct_policy()` requires the rustls TLS backend");
+        }
+        #[cfg(feature = "__rustls")]
+        if !config.certs_verification && config.tls_ct_policy.is_some() {
+            conflicts.push("`tls_ct_policy()` can't be combined with `danger_accept_invalid_certs()`");
+        }
+
+        #[cfg(feature = "default-tls")]
+        let builtin = rustls || matches!(config.tls, TlsBackend::Default);
+        #[cfg(not(feature = "default-tls"))]
+        let builtin = rustls;
+        if !builtin && !config.tls_host_options.is_empty() {
+            conflicts.push("`tls_config_for_host()` can't be used with a preconfigured TLS backend");
+        }
+    }
+
+    #[cfg(feature = "http3")]
+    if let HttpVersionPref::Http3 = config.http_version_pref {
+        if !matches!(config.tls, TlsBackend::Rustls | TlsBackend::BuiltRustls(_)) {
+            conflicts.push("`http3_prior_knowledge()` requires the rustls TLS backend");
+        }
+    }
+
+    #[cfg(feature = "http3")]
+    if config.http3_zero_rtt != Http3ZeroRtt::Disabled && !config.tls_enable_early_data {
+        conflicts.push("`http3_zero_rtt()` requires `tls_early_data(true)`");
+    }
+
+    #[cfg(feature = "http3")]
+    if config.http3_race_tcp && !matches!(config.tls, TlsBackend::Rustls | TlsBackend::BuiltRustls(_)) {
+        conflicts.push("`http3_race_tcp()` requires the rustls TLS backend");
+    }
+
+    conflicts
+}
+
+/// Opens `gate` on a `100 Continue` response, and passes the informational
+/// responses on to `f`.
+fn open_on_continue(gate: ContinueGate, f: Option<OnInformational>) -> OnInformational {
+    Arc::new(move |status, headers| {
+        if status == StatusCode::CONTINUE {
+            gate.open();
+        }
+        if let Some(ref f) = f {
+            f(status, headers);
+        }
+    })
+}
+
+/// QUIC can't be tunneled through HTTP or SOCKS proxies, so instead of
+/// connecting around a configured proxy, HTTP/3 requests fail.
+#[cfg(feature = "http3")]
+fn h3_proxied(url: Url) -> crate::Error {
+    error::request("HTTP/3 requests can't be sent through a proxy").with_url(url)
+}
+
+#[cfg(any(feature = "native-tls", feature = "__rustls"))]
+fn preconfigured_proxy_identity() -> crate::Error {
+    crate::error::builder("a proxy identity can't be used with a preconfigured TLS backend")
+}
+
+/// Adds the headers that hyper adds to HTTP/1 requests, for
+/// `Response::request_headers()`.
+fn add_http1_headers(headers: &mut HeaderMap, url: &Url, body: &Option<Option<Bytes>>) {
+    if let Entry::Vacant(entry) = headers.entry(HOST) {
+        let host = url.host_str().unwrap_or_default();
+        let host = match url.port() {
+            Some(port) => HeaderValue::try_from(format!("{host}:{port}")),
+            None => HeaderValue::try_from(host),
+        };
+        if let Ok(host) = host {
+            entry.insert(host);
+        }
+    }
+
+    if headers.contains_key(CONTENT_LENGTH) || headers.contains_key(TRANSFER_ENCODING) {
+        return;
+    }
+    match body {
+        Some(Some(bytes)) if !bytes.is_empty() => {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+        }
+        // streaming bodies without a known length
+        Some(None) => {
+            headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+        }
+        _ => (),
+    }
+}
+
 fn make_referer(next: &Url, previous: &Url) -> Option<HeaderValue> {
     if next.scheme() == "http" && previous.scheme() == "https" {
         return None;
@@ -1,12 +1,7 @@
 use std::fmt;
-#[cfg(any(
-    feature = "gzip",
-    feature = "zstd",
-    feature = "brotli",
-    feature = "deflate"
-))]
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 #[cfg(any(
@@ -39,7 +34,7 @@ use async_compression::tokio::bufread::ZlibDecoder;
 use futures_core::Stream;
 
 use bytes::Bytes;
-use http::HeaderMap;
+use http::{HeaderMap, HeaderValue};
 use hyper::body::Body as HttpBody;
 use hyper::body::Frame;
 
@@ -60,7 +55,7 @@ use tokio_util::io::StreamReader;
 
 use super::body::ResponseBody;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub(super) struct Accepts {
     #[cfg(feature = "gzip")]
     pub(super) gzip: bool,
@@ -70,6 +65,13 @@ pub(super) struct Accepts {
     pub(super) zstd: bool,
     #[cfg(feature = "deflate")]
     pub(super) deflate: bool,
+    pub(super) codecs: Option<Arc<crate::codec::Registry>>,
+    /// The `Accept-Encoding` set with `ClientBuilder::accept_encoding()`,
+    /// empty to advertise nothing.
+    pub(super) advertised: Option<HeaderValue>,
+    #[cfg(feature = "zstd")]
+    pub(super) zstd_window_log_max: Option<u32>,
+    pub(super) offload_threshold: Option<u64>,
 }
 
 impl Accepts {
@@ -83,6 +85,11 @@ impl Accepts {
             zstd: false,
             #[cfg(feature = "deflate")]
             deflate: false,
+            codecs: None,
+            advertised: None,
+            #[cfg(feature = "zstd")]
+            zstd_window_log_max: None,
+            offload_threshold: None,
         }
     }
 }
@@ -130,6 +137,12 @@ enum Inner {
     #[cfg(feature = "deflate")]
     Deflate(Pin<Box<Fuse<FramedRead<ZlibDecoder<PeekableIoStreamReader>, BytesCodec>>>>),
 
+    /// A `Custom` decoder will decode the response content with a registered codec.
+    Custom(Pin<Box<crate::codec::Transformed<ResponseBody>>>),
+
+    /// An `Offloaded` decoder runs another decoder on the blocking thread pool.
+    Offloaded(Box<Offload>),
+
     /// A decoder that doesn't have a value yet.
     #[cfg(any(
         feature = "brotli",
@@ -140,6 +153,16 @@ enum Inner {
     Pending(Pin<Box<Pending>>),
 }
 
+/// Decodes a frame at a time with `spawn_blocking`, so that decompressing
+/// doesn't block the worker threads of the runtime.
+struct Offload {
+    handle: tokio::runtime::Handle,
+    decoder: Option<Box<Decoder>>,
+    running: Option<tokio::task::JoinHandle<(Box<Decoder>, Option<DecodedFrame>)>>,
+}
+
+type DecodedFrame = Result<Frame<Bytes>, crate::Error>;
+
 #[cfg(any(
     feature = "gzip",
     feature = "zstd",
@@ -170,7 +193,7 @@ enum DecoderType {
     #[cfg(feature = "brotli")]
     Brotli,
     #[cfg(feature = "zstd")]
-    Zstd,
+    Zstd(Option<u32>),
     #[cfg(feature = "deflate")]
     Deflate,
 }
@@ -197,7 +220,7 @@ impl Decoder {
     /// A plain text decoder.
     ///
     /// This decoder will emit the underlying chunks as-is.
-    fn plain_text(body: ResponseBody) -> Decoder {
+    pub(super) fn plain_text(body: ResponseBody) -> Decoder {
         Decoder {
             inner: Inner::PlainText(body),
         }
@@ -237,13 +260,13 @@ impl Decoder {
     ///
     /// This decoder will buffer and decompress chunks that are zstd compressed.
     #[cfg(feature = "zstd")]
-    fn zstd(body: ResponseBody) -> Decoder {
+    fn zstd(body: ResponseBody, window_log_max: Option<u32>) -> Decoder {
         use futures_util::StreamExt;
 
         Decoder {
             inner: Inner::Pending(Box::pin(Pending(
                 IoStream(body).peekable(),
-                DecoderType::Zstd,
+                DecoderType::Zstd(window_log_max),
             ))),
         }
     }
@@ -263,12 +286,36 @@ impl Decoder {
         }
     }
 
-    #[cfg(any(
-        feature = "brotli",
-        feature = "zstd",
-        feature = "gzip",
-        feature = "deflate"
-    ))]
+    /// Runs `self` on the blocking thread pool, if the body is larger than
+    /// `threshold`, or of unknown length, and there's a runtime to run it.
+    fn offloaded(self, threshold: Option<u64>, body_len: Option<u64>) -> Decoder {
+        let threshold = match threshold {
+            Some(threshold) => threshold,
+            None => return self,
+        };
+        if body_len.map_or(false, |len| len < threshold) {
+            return self;
+        }
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => return self,
+        };
+        Decoder {
+            inner: Inner::Offloaded(Box::new(Offload {
+                handle,
+                decoder: Some(Box::new(self)),
+                running: None,
+            })),
+        }
+    }
+
+    /// A decoder of a custom content coding.
+    fn custom(body: ResponseBody, transform: Box<dyn crate::codec::Transform>) -> Decoder {
+        Decoder {
+            inner: Inner::Custom(Box::pin(crate::codec::transformed(body, transform))),
+        }
+    }
+
     fn detect_encoding(headers: &mut HeaderMap, encoding_str: &str) -> bool {
         use http::header::{CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING};
         use log::warn;
@@ -304,40 +351,59 @@ impl Decoder {
     /// how to decode the content body of the request.
     ///
     /// Uses the correct variant by inspecting the Content-Encoding header.
-    pub(super) fn detect(
+    pub(super) fn detect(headers: &mut HeaderMap, body: ResponseBody, accepts: Accepts) -> Decoder {
+        let body_len = HttpBody::size_hint(&body).exact();
+        match Decoder::detect_decoder(headers, body, &accepts) {
+            Ok(decoder) => decoder.offloaded(accepts.offload_threshold, body_len),
+            Err(plain_text) => plain_text,
+        }
+    }
+
+    /// Returns the decoder of the content coding, or a plain text decoder
+    /// as the error if the body isn't encoded.
+    fn detect_decoder(
         _headers: &mut HeaderMap,
         body: ResponseBody,
-        _accepts: Accepts,
-    ) -> Decoder {
+        _accepts: &Accepts,
+    ) -> Result<Decoder, Decoder> {
         #[cfg(feature = "gzip")]
         {
             if _accepts.gzip && Decoder::detect_encoding(_headers, "gzip") {
-                return Decoder::gzip(body);
+                return Ok(Decoder::gzip(body));
             }
         }
 
         #[cfg(feature = "brotli")]
         {
             if _accepts.brotli && Decoder::detect_encoding(_headers, "br") {
-                return Decoder::brotli(body);
+                return Ok(Decoder::brotli(body));
             }
         }
 
         #[cfg(feature = "zstd")]
         {
             if _accepts.zstd && Decoder::detect_encoding(_headers, "zstd") {
-                return Decoder::zstd(body);
+                return Ok(Decoder::zstd(body, _accepts.zstd_window_log_max));
             }
         }
 
         #[cfg(feature = "deflate")]
         {
             if _accepts.deflate && Decoder::detect_encoding(_headers, "deflate") {
-                return Decoder::deflate(body);
+                return Ok(Decoder::deflate(body));
+            }
+        }
+
+        if let Some(ref codecs) = _accepts.codecs {
+            for name in codecs.names() {
+                if Decoder::detect_encoding(_headers, name) {
+                    let codec = codecs.get(name).expect("name of a registered codec");
+                    return Ok(Decoder::custom(body, codec.decoder()));
+                }
             }
         }
 
-        Decoder::plain_text(body)
+        Err(Decoder::plain_text(body))
     }
 }
 
@@ -371,6 +437,14 @@ impl HttpBody for Decoder {
                     None => Poll::Ready(None),
                 }
             }
+            Inner::Custom(ref mut body) => {
+                match futures_core::ready!(body.as_mut().poll_frame(cx)) {
+                    Some(Ok(frame)) => Poll::Ready(Some(Ok(frame))),
+                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::decode(err)))),
+                    None => Poll::Ready(None),
+                }
+            }
+            Inner::Offloaded(ref mut offload) => offload.poll_frame(cx),
             #[cfg(feature = "gzip")]
             Inner::Gzip(ref mut decoder) => {
                 match futures_core::ready!(Pin::new(&mut *decoder).poll_next(cx)) {
@@ -434,17 +508,43 @@ impl HttpBody for Decoder {
         match self.inner {
             Inner::PlainText(ref body) => HttpBody::size_hint(body),
             // the rest are "unknown", so default
-            #[cfg(any(
-                feature = "brotli",
-                feature = "zstd",
-                feature = "gzip",
-                feature = "deflate"
-            ))]
             _ => http_body::SizeHint::default(),
         }
     }
 }
 
+impl Offload {
+    fn poll_frame(&mut self, cx: &mut Context) -> Poll<Option<DecodedFrame>> {
+        if self.running.is_none() {
+            let mut decoder = match self.decoder.take() {
+                Some(decoder) => decoder,
+                None => return Poll::Ready(None),
+            };
+            let handle = self.handle.clone();
+            self.running = Some(self.handle.spawn_blocking(move || {
+                let frame =
+                    handle.block_on(std::future::poll_fn(|cx| {
+                        Pin::new(&mut *decoder).poll_frame(cx)
+                    }));
+                (decoder, frame)
+            }));
+        }
+
+        let running = self.running.as_mut().expect("spawned above");
+        let result = futures_core::ready!(Pin::new(running).poll(cx));
+        self.running = None;
+        match result {
+            Ok((decoder, frame)) => {
+                if frame.is_some() {
+                    self.decoder = Some(decoder);
+                }
+                Poll::Ready(frame)
+            }
+            Err(err) => Poll::Ready(Some(Err(crate::error::decode(err)))),
+        }
+    }
+}
+
 #[cfg(any(
     feature = "gzip",
     feature = "zstd",
@@ -524,13 +624,19 @@ impl Future for Pending {
                 .fuse(),
             )))),
             #[cfg(feature = "zstd")]
-            DecoderType::Zstd => Poll::Ready(Ok(Inner::Zstd(Box::pin(
-                FramedRead::new(
-                    ZstdDecoder::new(StreamReader::new(_body)),
-                    BytesCodec::new(),
-                )
-                .fuse(),
-            )))),
+            DecoderType::Zstd(window_log_max) => {
+                let reader = StreamReader::new(_body);
+                let decoder = match window_log_max {
+                    Some(log) => ZstdDecoder::with_params(
+                        reader,
+                        &[async_compression::zstd::DParameter::window_log_max(log)],
+                    ),
+                    None => ZstdDecoder::new(reader),
+                };
+                Poll::Ready(Ok(Inner::Zstd(Box::pin(
+                    FramedRead::new(decoder, BytesCodec::new()).fuse(),
+                ))))
+            }
             #[cfg(feature = "gzip")]
             DecoderType::Gzip => Poll::Ready(Ok(Inner::Gzip(Box::pin(
                 FramedRead::new(
@@ -627,6 +733,38 @@ impl Accepts {
         }
     }
 
+    /// The value of the `Accept-Encoding` header, including custom codecs.
+    pub(super) fn header_value(&self) -> Option<HeaderValue> {
+        match (&self.codecs, &self.advertised) {
+            (Some(codecs), _) => Some(codecs.accept_encoding().clone()),
+            (None, Some(advertised)) if advertised.is_empty() => None,
+            (None, Some(advertised)) => Some(advertised.clone()),
+            (None, None) => self.as_str().map(HeaderValue::from_static),
+        }
+    }
+
+    /// The built-in encodings to advertise, before custom codecs.
+    pub(super) fn builtin(&self) -> Option<&str> {
+        match self.advertised {
+            Some(ref advertised) if advertised.is_empty() => None,
+            Some(ref advertised) => advertised.to_str().ok(),
+            None => self.as_str(),
+        }
+    }
+
+    /// Returns true if responses with this built-in coding are decoded.
+    pub(super) fn decodes(&self, encoding: crate::codec::Encoding) -> bool {
+        use crate::codec::Encoding;
+
+        match encoding {
+            Encoding::Gzip => self.is_gzip(),
+            Encoding::Brotli => self.is_brotli(),
+            Encoding::Zstd => self.is_zstd(),
+            Encoding::Deflate => self.is_deflate(),
+            Encoding::Identity => true,
+        }
+    }
+
     fn is_gzip(&self) -> bool {
         #[cfg(feature = "gzip")]
         {
@@ -687,6 +825,11 @@ impl Default for Accepts {
             zstd: true,
             #[cfg(feature = "deflate")]
             deflate: true,
+            codecs: None,
+            advertised: None,
+            #[cfg(feature = "zstd")]
+            zstd_window_log_max: None,
+            offload_threshold: None,
         }
     }
 }
@@ -731,6 +874,11 @@ mod tests {
                             zstd,
                             #[cfg(feature = "deflate")]
                             deflate,
+                            codecs: None,
+                            advertised: None,
+                            #[cfg(feature = "zstd")]
+                            zstd_window_log_max: None,
+                            offload_threshold: None,
                         });
                     }
                 }
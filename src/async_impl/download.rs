@@ -0,0 +1,296 @@
+use std::fmt;
+use std::io::SeekFrom;
+use std::path::Path;
+
+use bytes::Bytes;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use http::header::{
+    HeaderMap, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, RANGE,
+};
+use http::StatusCode;
+use log::debug;
+use tokio::io::{AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use url::Url;
+
+use super::client::Client;
+use super::response::Response;
+use crate::IntoUrl;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+const DEFAULT_SEGMENT_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Downloads a large resource in segments, with concurrent `Range` requests.
+///
+/// The resource is probed with a `HEAD` request first. If the server accepts
+/// byte ranges and the resource is larger than one segment, the segments are
+/// requested concurrently and reassembled in order. Otherwise, the resource
+/// is downloaded with a single `GET`.
+///
+/// Every segment must have the length and `ETag` that the probe reported, so
+/// that segments of different versions of the resource aren't mixed.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run() -> Result<(), reqwest::Error> {
+/// let client = reqwest::Client::new();
+/// let len = reqwest::Downloader::new(&client, "https://example.com/large.iso")
+///     .concurrency(8)
+///     .to_file("large.iso")
+///     .await?;
+/// println!("downloaded {len} bytes");
+/// # Ok(())
+/// # }
+/// ```
+pub struct Downloader {
+    client: Client,
+    url: crate::Result<Url>,
+    headers: HeaderMap,
+    concurrency: usize,
+    segment_size: u64,
+}
+
+/// What the `HEAD` request reported about the resource.
+struct Probe {
+    url: Url,
+    len: u64,
+    etag: Option<HeaderValue>,
+}
+
+impl Downloader {
+    /// Creates a `Downloader` for the resource at `url`, sending its requests
+    /// with `client`.
+    pub fn new<U: IntoUrl>(client: &Client, url: U) -> Downloader {
+        Downloader {
+            client: client.clone(),
+            url: url.into_url(),
+            headers: HeaderMap::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+        }
+    }
+
+    /// Adds headers to every request, like an `Authorization` header.
+    pub fn headers(mut self, headers: HeaderMap) -> Downloader {
+        crate::util::replace_headers(&mut self.headers, headers);
+        self
+    }
+
+    /// Sets how many segments are downloaded at once.
+    ///
+    /// Default is 4. A value of 0 is treated as 1.
+    pub fn concurrency(mut self, concurrency: usize) -> Downloader {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the size of each segment, in bytes.
+    ///
+    /// Writing to an `AsyncWrite` keeps up to `concurrency` segments in
+    /// memory, to write them in order.
+    ///
+    /// Default is 8 MiB. A value of 0 is treated as 1.
+    pub fn segment_size(mut self, segment_size: u64) -> Downloader {
+        self.segment_size = segment_size.max(1);
+        self
+    }
+
+    /// Downloads the resource to the file at `path`, creating or truncating
+    /// it. Each segment is written at its offset as it's received.
+    ///
+    /// If the download fails, the file is left partially written.
+    ///
+    /// Returns the length of the resource.
+    pub async fn to_file<P: AsRef<Path>>(self, path: P) -> crate::Result<u64> {
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .map_err(crate::error::body)?;
+
+        let url = match self.url {
+            Ok(ref url) => url.clone(),
+            Err(err) => return Err(err),
+        };
+        let probe = match self.probe(url).await? {
+            Ok(probe) => probe,
+            Err(res) => return copy(res, &mut file).await,
+        };
+
+        file.set_len(probe.len).await.map_err(crate::error::body)?;
+        drop(file);
+
+        let (this, probe) = (&self, &probe);
+        stream::iter(self.segments(probe))
+            .map(|(start, end)| async move {
+                let mut res = this.segment(probe, start, end).await?;
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .await
+                    .map_err(crate::error::body)?;
+                file.seek(SeekFrom::Start(start))
+                    .await
+                    .map_err(crate::error::body)?;
+                let mut written = 0;
+                while let Some(chunk) = res.chunk().await? {
+                    written += chunk.len() as u64;
+                    file.write_all(&chunk).await.map_err(crate::error::body)?;
+                }
+                file.flush().await.map_err(crate::error::body)?;
+                check_len(&probe.url, written, start, end)
+            })
+            .buffer_unordered(self.concurrency)
+            .try_for_each(|()| async { Ok(()) })
+            .await?;
+
+        Ok(probe.len)
+    }
+
+    /// Downloads the resource into `writer`, in order.
+    ///
+    /// Returns the length of the resource.
+    pub async fn to_writer<W>(self, writer: &mut W) -> crate::Result<u64>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let url = match self.url {
+            Ok(ref url) => url.clone(),
+            Err(err) => return Err(err),
+        };
+        let probe = match self.probe(url).await? {
+            Ok(probe) => probe,
+            Err(res) => return copy(res, writer).await,
+        };
+
+        let (this, probe) = (&self, &probe);
+        let mut segments = stream::iter(self.segments(probe))
+            .map(|(start, end)| async move {
+                let res = this.segment(probe, start, end).await?;
+                let bytes = res.bytes().await?;
+                check_len(&probe.url, bytes.len() as u64, start, end)?;
+                Ok::<Bytes, crate::Error>(bytes)
+            })
+            .buffered(self.concurrency);
+
+        while let Some(bytes) = segments.try_next().await? {
+            writer.write_all(&bytes).await.map_err(crate::error::body)?;
+        }
+        writer.flush().await.map_err(crate::error::body)?;
+        Ok(probe.len)
+    }
+
+    /// Sends the `HEAD` request. If the resource can't be downloaded in
+    /// segments, the response to a single `GET` is returned instead.
+    async fn probe(&self, url: Url) -> crate::Result<Result<Probe, Response>> {
+        let head = self
+            .client
+            .head(url.clone())
+            .headers(self.headers.clone())
+            .send()
+            .await?;
+
+        let accepts_ranges = head
+            .headers()
+            .get_all(ACCEPT_RANGES)
+            .iter()
+            .any(|value| value.as_bytes().eq_ignore_ascii_case(b"bytes"));
+        // the body of a response to HEAD is empty, whatever its Content-Length
+        let len = head
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|_| head.status().is_success());
+
+        match len {
+            Some(len) if accepts_ranges && len > self.segment_size => {
+                debug!("downloading {url} in segments of {} bytes", self.segment_size);
+                Ok(Ok(Probe {
+                    etag: head.headers().get(ETAG).cloned(),
+                    url,
+                    len,
+                }))
+            }
+            _ => {
+                debug!("downloading {url} with a single request");
+                let res = self
+                    .client
+                    .get(url)
+                    .headers(self.headers.clone())
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(Err(res))
+            }
+        }
+    }
+
+    /// The inclusive byte ranges of the segments.
+    fn segments(&self, probe: &Probe) -> impl Iterator<Item = (u64, u64)> {
+        let (len, size) = (probe.len, self.segment_size);
+        (0..(len + size - 1) / size).map(move |i| (i * size, std::cmp::min((i + 1) * size, len) - 1))
+    }
+
+    /// Requests a segment, and checks that the response is that segment of
+    /// the probed resource.
+    async fn segment(&self, probe: &Probe, start: u64, end: u64) -> crate::Result<Response> {
+        let mut req = self
+            .client
+            .get(probe.url.clone())
+            .headers(self.headers.clone())
+            .header(RANGE, format!("bytes={start}-{end}"));
+        // a weak ETag can't be used with If-Range
+        if let Some(etag) = probe.etag.as_ref().filter(|etag| !etag.as_bytes().starts_with(b"W/")) {
+            req = req.header(IF_RANGE, etag.clone());
+        }
+        let res = req.send().await?.error_for_status()?;
+
+        if res.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(inconsistent(&probe.url, "server didn't return the requested range"));
+        }
+        let expected = format!("bytes {start}-{end}/{}", probe.len);
+        if res.headers().get(CONTENT_RANGE).map(HeaderValue::as_bytes) != Some(expected.as_bytes()) {
+            return Err(inconsistent(&probe.url, "segment has an unexpected Content-Range"));
+        }
+        if probe.etag.is_some() && res.headers().get(ETAG) != probe.etag.as_ref() {
+            return Err(inconsistent(&probe.url, "resource changed during download"));
+        }
+        Ok(res)
+    }
+}
+
+impl fmt::Debug for Downloader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Downloader")
+            .field("url", &self.url.as_ref().ok())
+            .field("concurrency", &self.concurrency)
+            .field("segment_size", &self.segment_size)
+            .finish()
+    }
+}
+
+/// Writes the whole body of `res` into `writer`.
+async fn copy<W>(mut res: Response, writer: &mut W) -> crate::Result<u64>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut len = 0;
+    while let Some(chunk) = res.chunk().await? {
+        len += chunk.len() as u64;
+        writer.write_all(&chunk).await.map_err(crate::error::body)?;
+    }
+    writer.flush().await.map_err(crate::error::body)?;
+    Ok(len)
+}
+
+fn check_len(url: &Url, len: u64, start: u64, end: u64) -> crate::Result<()> {
+    if len == end - start + 1 {
+        Ok(())
+    } else {
+        Err(inconsistent(url, "segment has an unexpected length"))
+    }
+}
+
+fn inconsistent(url: &Url, msg: &'static str) -> crate::Error {
+    crate::error::decode(msg).with_url(url.clone())
+}
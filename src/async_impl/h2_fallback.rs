@@ -0,0 +1,131 @@
+//! Falling back to HTTP/1.1 for origins whose HTTP/2 connections keep
+//! failing, like browsers do.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use http::Uri;
+use url::Url;
+
+/// How many consecutive HTTP/2 protocol errors downgrade an origin.
+const MAX_ERRORS: u32 = 2;
+
+/// The origins that are downgraded to HTTP/1.1.
+pub(crate) struct H2Fallback {
+    ttl: Duration,
+    origins: Mutex<HashMap<String, Origin>>,
+}
+
+#[derive(Default)]
+struct Origin {
+    errors: u32,
+    until: Option<Instant>,
+}
+
+impl H2Fallback {
+    pub(crate) fn new(ttl: Duration) -> H2Fallback {
+        H2Fallback {
+            ttl,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records an HTTP/2 protocol error of a request to `url`.
+    pub(crate) fn error(&self, url: &Url) {
+        let key = match url_key(url) {
+            Some(key) => key,
+            None => return,
+        };
+        let mut origins = self.origins.lock().unwrap();
+        let origin = origins.entry(key).or_default();
+        origin.errors += 1;
+        if origin.errors >= MAX_ERRORS && origin.until.is_none() {
+            log::debug!("downgrading {url} to HTTP/1.1 for {:?}", self.ttl);
+            origin.until = Instant::now().checked_add(self.ttl);
+        }
+    }
+
+    /// Records a response received over HTTP/2 from `url`.
+    pub(crate) fn success(&self, url: &Url) {
+        let key = match url_key(url) {
+            Some(key) => key,
+            None => return,
+        };
+        let mut origins = self.origins.lock().unwrap();
+        if let Some(origin) = origins.get(&key) {
+            if origin.until.is_none() {
+                origins.remove(&key);
+            }
+        }
+    }
+
+    /// Returns true if new connections to `dst` must only offer HTTP/1.1.
+    ///
+    /// Only TLS connections are downgraded.
+    #[cfg_attr(not(feature = "__tls"), allow(dead_code))]
+    pub(crate) fn is_downgraded(&self, dst: &Uri) -> bool {
+        let key = match uri_key(dst) {
+            Some(key) => key,
+            None => return false,
+        };
+        let mut origins = self.origins.lock().unwrap();
+        match origins.get(&key).and_then(|origin| origin.until) {
+            Some(until) if until <= Instant::now() => {
+                origins.remove(&key);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+}
+
+fn url_key(url: &Url) -> Option<String> {
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
+
+#[cfg_attr(not(feature = "__tls"), allow(dead_code))]
+fn uri_key(uri: &Uri) -> Option<String> {
+    let port = match uri.port_u16() {
+        Some(port) => port,
+        None if uri.scheme_str() == Some("https") => 443,
+        None => 80,
+    };
+    Some(format!("{}:{}", uri.host()?, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downgrades_after_repeated_errors() {
+        let fallback = H2Fallback::new(Duration::from_secs(60));
+        let url = Url::parse("https://example.com/a").unwrap();
+        let dst = Uri::from_static("https://example.com");
+
+        fallback.error(&url);
+        assert!(!fallback.is_downgraded(&dst));
+        fallback.success(&url);
+        fallback.error(&url);
+        assert!(!fallback.is_downgraded(&dst));
+        fallback.error(&url);
+        assert!(fallback.is_downgraded(&dst));
+        assert!(fallback.is_downgraded(&Uri::from_static("https://example.com:443/b")));
+        assert!(!fallback.is_downgraded(&Uri::from_static("https://example.com:8443")));
+
+        // a success over HTTP/2 doesn't end the downgrade
+        fallback.success(&url);
+        assert!(fallback.is_downgraded(&dst));
+    }
+
+    #[test]
+    fn downgrade_expires() {
+        let fallback = H2Fallback::new(Duration::ZERO);
+        let url = Url::parse("https://example.com/").unwrap();
+        fallback.error(&url);
+        fallback.error(&url);
+        assert!(!fallback.is_downgraded(&Uri::from_static("https://example.com/")));
+    }
+}
@@ -7,7 +7,7 @@ use h3_quinn::{Connection, OpenStreams};
 use http::Uri;
 use hyper_util::client::legacy::connect::dns::Name;
 use quinn::crypto::rustls::QuicClientConfig;
-use quinn::{ClientConfig, Endpoint, TransportConfig};
+use quinn::{ClientConfig, Endpoint, TransportConfig, ZeroRttAccepted};
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -15,6 +15,8 @@ use std::sync::Arc;
 type H3Connection = (
     h3::client::Connection<Connection, Bytes>,
     SendRequest<OpenStreams, Bytes>,
+    // set if the connection was resumed with 0-RTT
+    Option<ZeroRttAccepted>,
 );
 
 #[derive(Clone)]
@@ -46,7 +48,9 @@ impl H3Connector {
         Ok(Self { resolver, endpoint })
     }
 
-    pub async fn connect(&mut self, dest: Uri) -> Result<H3Connection, BoxError> {
+    /// Connects to `dest`, and with `early`, returns before the handshake
+    /// completes if the connection can be resumed with 0-RTT.
+    pub async fn connect(&mut self, dest: Uri, early: bool) -> Result<H3Connection, BoxError> {
         let host = dest
             .host()
             .ok_or("destination must have a host")?
@@ -66,20 +70,37 @@ impl H3Connector {
             addrs.collect()
         };
 
-        self.remote_connect(addrs, host).await
+        self.remote_connect(addrs, host, early).await
     }
 
     async fn remote_connect(
         &mut self,
         addrs: Vec<SocketAddr>,
         server_name: &str,
+        early: bool,
     ) -> Result<H3Connection, BoxError> {
         let mut err = None;
         for addr in addrs {
-            match self.endpoint.connect(addr, server_name)?.await {
+            let connecting = self.endpoint.connect(addr, server_name)?;
+            let connecting = if early {
+                // without a session ticket of the server, this falls back
+                // to a full handshake
+                match connecting.into_0rtt() {
+                    Ok((new_conn, accepted)) => {
+                        let quinn_conn = Connection::new(new_conn);
+                        let (driver, tx) = h3::client::new(quinn_conn).await?;
+                        return Ok((driver, tx, Some(accepted)));
+                    }
+                    Err(connecting) => connecting,
+                }
+            } else {
+                connecting
+            };
+            match connecting.await {
                 Ok(new_conn) => {
                     let quinn_conn = Connection::new(new_conn);
-                    return Ok(h3::client::new(quinn_conn).await?);
+                    let (driver, tx) = h3::client::new(quinn_conn).await?;
+                    return Ok((driver, tx, None));
                 }
                 Err(e) => err = Some(e),
             }
@@ -10,28 +10,66 @@ use crate::error::{BoxError, Error, Kind};
 use crate::{error, Body};
 use connect::H3Connector;
 use futures_util::future;
-use http::{Request, Response};
+use http::{Method, Request, Response, Uri};
 use log::trace;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
+/// Which requests are sent in 0-RTT early data, before the handshake of a
+/// resumed HTTP/3 connection completes.
+///
+/// Early data saves a round-trip, but an attacker can replay it, so a server
+/// may process such a request more than once. See
+/// [`ClientBuilder::http3_zero_rtt()`](crate::ClientBuilder::http3_zero_rtt).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Http3ZeroRtt {
+    /// Requests wait for the handshake to complete.
+    #[default]
+    Disabled,
+    /// Only `GET`, `HEAD` and `OPTIONS` requests are sent in early data.
+    SafeMethodsOnly,
+    /// All requests are sent in early data.
+    All,
+}
+
+impl Http3ZeroRtt {
+    fn allows(self, method: &Method) -> bool {
+        match self {
+            Http3ZeroRtt::Disabled => false,
+            Http3ZeroRtt::SafeMethodsOnly => {
+                matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+            }
+            Http3ZeroRtt::All => true,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct H3Client {
     pool: Pool,
     connector: H3Connector,
+    zero_rtt: Http3ZeroRtt,
 }
 
 impl H3Client {
-    pub fn new(connector: H3Connector, pool_timeout: Option<Duration>) -> Self {
+    pub fn new(
+        connector: H3Connector,
+        pool_timeout: Option<Duration>,
+        pool_max_idle: usize,
+        max_streams: Option<usize>,
+        zero_rtt: Http3ZeroRtt,
+    ) -> Self {
         H3Client {
-            pool: Pool::new(pool_timeout),
+            pool: Pool::new(pool_timeout, pool_max_idle, max_streams),
             connector,
+            zero_rtt,
         }
     }
 
-    async fn get_pooled_client(&mut self, key: Key) -> Result<PoolClient, BoxError> {
+    async fn get_pooled_client(&mut self, key: Key, early: bool) -> Result<PoolClient, BoxError> {
         if let Some(client) = self.pool.try_pool(&key) {
             trace!("getting client from pool with key {key:?}");
             return Ok(client);
@@ -40,9 +78,13 @@ impl H3Client {
         trace!("did not find connection {key:?} in pool so connecting...");
 
         let dest = pool::domain_as_uri(key.clone());
-        self.pool.connecting(key.clone())?;
-        let (driver, tx) = self.connector.connect(dest).await?;
-        Ok(self.pool.new_connection(key, driver, tx))
+        let _connecting = match self.pool.connecting(key.clone()) {
+            Ok(connecting) => connecting,
+            // share a busy connection until the new one is open
+            Err(e) => return self.pool.try_busy(&key).ok_or(e),
+        };
+        let (driver, tx, accepted) = self.connector.connect(dest, early).await?;
+        Ok(self.pool.new_connection(key, driver, tx, accepted))
     }
 
     async fn send_request(
@@ -50,14 +92,68 @@ impl H3Client {
         key: Key,
         req: Request<Body>,
     ) -> Result<Response<ResponseBody>, Error> {
-        let mut pooled = match self.get_pooled_client(key).await {
+        // only a request that can be sent again if the server rejects the
+        // early data is sent in it
+        let mut replay = if self.zero_rtt.allows(req.method()) {
+            try_clone_request(&req)
+        } else {
+            None
+        };
+        let mut pooled = match self.get_pooled_client(key.clone(), replay.is_some()).await {
             Ok(client) => client,
             Err(e) => return Err(error::request(e)),
         };
-        pooled
-            .send_request(req)
+
+        if !pooled.is_handshaking() {
+            replay = None;
+        } else if replay.is_none() && !pooled.handshake().await {
+            pooled = self.reconnect(key.clone()).await?;
+        }
+
+        let res = pooled.send_request(req).await;
+        match (res, replay) {
+            (Err(e), Some(replay)) => {
+                if pooled.handshake().await {
+                    return Err(Error::new(Kind::Request, Some(e)));
+                }
+                trace!("0-RTT data was rejected, sending the request again");
+                self.reconnect(key)
+                    .await?
+                    .send_request(replay)
+                    .await
+                    .map_err(|e| Error::new(Kind::Request, Some(e)))
+            }
+            (res, _) => res.map_err(|e| Error::new(Kind::Request, Some(e))),
+        }
+    }
+
+    /// Returns a connection to replace one whose early data was rejected.
+    ///
+    /// The streams of the HTTP/3 session that were opened in the early data,
+    /// like its control stream, are lost, so the connection is dropped from
+    /// the pool and a new one is opened without early data.
+    async fn reconnect(&mut self, key: Key) -> Result<PoolClient, Error> {
+        self.get_pooled_client(key, false)
             .await
-            .map_err(|e| Error::new(Kind::Request, Some(e)))
+            .map_err(error::request)
+    }
+
+    /// Opens a pooled connection to the origin of `uri`, unless there is one.
+    pub(crate) fn connect(
+        &self,
+        mut uri: Uri,
+    ) -> impl Future<Output = Result<(), BoxError>> + Send + 'static {
+        let mut client = self.clone();
+        async move {
+            let key = pool::extract_domain(&mut uri)?;
+            client.get_pooled_client(key, false).await?;
+            Ok(())
+        }
+    }
+
+    /// Closes the pooled connections once their requests in flight are done.
+    pub(crate) fn flush(&self) {
+        self.pool.flush();
     }
 
     pub fn request(&self, mut req: Request<Body>) -> H3ResponseFuture {
@@ -75,6 +171,15 @@ impl H3Client {
     }
 }
 
+fn try_clone_request(req: &Request<Body>) -> Option<Request<Body>> {
+    let mut clone = Request::new(req.body().try_clone()?);
+    *clone.method_mut() = req.method().clone();
+    *clone.uri_mut() = req.uri().clone();
+    *clone.version_mut() = req.version();
+    *clone.headers_mut() = req.headers().clone();
+    Some(clone)
+}
+
 pub(crate) struct H3ResponseFuture {
     inner: Pin<Box<dyn Future<Output = Result<Response<ResponseBody>, Error>> + Send>>,
 }
@@ -1,5 +1,6 @@
 use bytes::Bytes;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{Receiver, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -15,6 +16,8 @@ use h3_quinn::{Connection, OpenStreams};
 use http::uri::{Authority, Scheme};
 use http::{Request, Response, Uri};
 use log::trace;
+use quinn::ZeroRttAccepted;
+use tokio::sync::watch;
 
 pub(super) type Key = (Scheme, Authority);
 
@@ -24,48 +27,39 @@ pub struct Pool {
 }
 
 impl Pool {
-    pub fn new(timeout: Option<Duration>) -> Self {
+    pub fn new(timeout: Option<Duration>, max_idle: usize, max_streams: Option<usize>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(PoolInner {
-                connecting: HashSet::new(),
-                idle_conns: HashMap::new(),
-                timeout,
-            })),
+            inner: Arc::new(Mutex::new(PoolInner::new(timeout, max_idle, max_streams))),
         }
     }
 
-    pub fn connecting(&self, key: Key) -> Result<(), BoxError> {
+    /// Marks `key` as connecting until the returned guard is dropped, so
+    /// that a connection that fails or whose future is dropped, like the
+    /// loser of a race against TCP, doesn't block later ones.
+    pub fn connecting(&self, key: Key) -> Result<Connecting, BoxError> {
         let mut inner = self.inner.lock().unwrap();
         if !inner.connecting.insert(key.clone()) {
             return Err(format!("HTTP/3 connecting already in progress for {key:?}").into());
         }
-        return Ok(());
+        Ok(Connecting {
+            pool: self.clone(),
+            key,
+        })
     }
 
     pub fn try_pool(&self, key: &Key) -> Option<PoolClient> {
-        let mut inner = self.inner.lock().unwrap();
-        let timeout = inner.timeout;
-        if let Some(conn) = inner.idle_conns.get(&key) {
-            // We check first if the connection still valid
-            // and if not, we remove it from the pool.
-            if conn.is_invalid() {
-                trace!("pooled HTTP/3 connection is invalid so removing it...");
-                inner.idle_conns.remove(&key);
-                return None;
-            }
+        self.inner.lock().unwrap().try_pool(key)
+    }
 
-            if let Some(duration) = timeout {
-                if Instant::now().saturating_duration_since(conn.idle_timeout) > duration {
-                    trace!("pooled connection expired");
-                    return None;
-                }
-            }
-        }
+    /// Returns the least busy connection to `key`, even if it carries the
+    /// maximum number of streams.
+    pub fn try_busy(&self, key: &Key) -> Option<PoolClient> {
+        self.inner.lock().unwrap().try_busy(key)
+    }
 
-        inner
-            .idle_conns
-            .get_mut(&key)
-            .and_then(|conn| Some(conn.pool()))
+    /// Closes the pooled connections once their requests in flight are done.
+    pub fn flush(&self) {
+        self.inner.lock().unwrap().flush();
     }
 
     pub fn new_connection(
@@ -73,6 +67,7 @@ impl Pool {
         key: Key,
         mut driver: h3::client::Connection<Connection, Bytes>,
         tx: SendRequest<OpenStreams, Bytes>,
+        accepted: Option<ZeroRttAccepted>,
     ) -> PoolClient {
         let (close_tx, close_rx) = std::sync::mpsc::channel();
         tokio::spawn(async move {
@@ -82,44 +77,179 @@ impl Pool {
             }
         });
 
-        let mut inner = self.inner.lock().unwrap();
+        // learn whether the early data of a 0-RTT connection was accepted
+        let handshake = accepted.map(|accepted| {
+            let (handshake_tx, handshake_rx) = watch::channel(None);
+            tokio::spawn(async move {
+                let _ = handshake_tx.send(Some(accepted.await));
+            });
+            handshake_rx
+        });
 
-        let client = PoolClient::new(tx);
-        let conn = PoolConnection::new(client.clone(), close_rx);
-        inner.insert(key.clone(), conn);
+        let mut inner = self.inner.lock().unwrap();
 
-        // We clean up "connecting" here so we don't have to acquire the lock again.
-        let existed = inner.connecting.remove(&key);
-        debug_assert!(existed, "key not in connecting set");
+        let client = PoolClient::new(tx, handshake.clone());
+        let conn = PoolConnection::new(
+            client.clone(),
+            client.streams.clone(),
+            handshake,
+            close_rx,
+        );
+        inner.insert(key, conn);
 
         client
     }
 }
 
-struct PoolInner {
+/// A connection being opened, see [`Pool::connecting()`].
+pub struct Connecting {
+    pool: Pool,
+    key: Key,
+}
+
+impl Drop for Connecting {
+    fn drop(&mut self) {
+        let mut inner = self.pool.inner.lock().unwrap();
+        let existed = inner.connecting.remove(&self.key);
+        debug_assert!(existed, "key not in connecting set");
+    }
+}
+
+struct PoolInner<C = PoolClient> {
     connecting: HashSet<Key>,
-    idle_conns: HashMap<Key, PoolConnection>,
+    idle_conns: HashMap<Key, Vec<PoolConnection<C>>>,
     timeout: Option<Duration>,
+    // idle connections kept per key
+    max_idle: usize,
+    // requests in flight on a connection before another one is opened
+    max_streams: Option<usize>,
 }
 
-impl PoolInner {
-    fn insert(&mut self, key: Key, conn: PoolConnection) {
-        if self.idle_conns.contains_key(&key) {
-            trace!("connection already exists for key {key:?}");
+impl<C: Clone> PoolInner<C> {
+    fn new(timeout: Option<Duration>, max_idle: usize, max_streams: Option<usize>) -> Self {
+        PoolInner {
+            connecting: HashSet::new(),
+            idle_conns: HashMap::new(),
+            timeout,
+            max_idle,
+            max_streams,
         }
+    }
 
-        self.idle_conns.insert(key, conn);
+    fn insert(&mut self, key: Key, conn: PoolConnection<C>) {
+        let conns = self.idle_conns.entry(key).or_default();
+        if !conns.is_empty() {
+            trace!("opened another HTTP/3 connection, {} in pool", conns.len() + 1);
+        }
+        conns.push(conn);
+    }
+
+    /// Returns the usable connections to `key`, after removing the invalid
+    /// and expired ones, and the idle ones over the limit.
+    fn conns(&mut self, key: &Key) -> Option<&mut Vec<PoolConnection<C>>> {
+        let timeout = self.timeout;
+        let max_idle = self.max_idle;
+        let conns = self.idle_conns.get_mut(key)?;
+
+        // We check first if the connections are still valid
+        // and if not, we remove them from the pool.
+        conns.retain(|conn| {
+            if conn.is_invalid() {
+                trace!("pooled HTTP/3 connection is invalid so removing it...");
+                return false;
+            }
+            if let Some(duration) = timeout {
+                if conn.streams() == 0
+                    && Instant::now().saturating_duration_since(conn.idle_timeout) > duration
+                {
+                    trace!("pooled connection expired");
+                    return false;
+                }
+            }
+            true
+        });
+
+        // keep the most recently used idle connections
+        conns.sort_by_key(|conn| std::cmp::Reverse(conn.idle_timeout));
+        let mut idle = 0;
+        conns.retain(|conn| {
+            if conn.streams() > 0 {
+                return true;
+            }
+            idle += 1;
+            idle <= max_idle
+        });
+
+        if conns.is_empty() {
+            self.idle_conns.remove(key);
+            return None;
+        }
+        self.idle_conns.get_mut(key)
+    }
+
+    fn try_pool(&mut self, key: &Key) -> Option<C> {
+        let max_streams = self.max_streams.unwrap_or(usize::MAX);
+        let conns = self.conns(key)?;
+        conns
+            .iter_mut()
+            .find(|conn| conn.streams() < max_streams)
+            .map(PoolConnection::pool)
+    }
+
+    fn try_busy(&mut self, key: &Key) -> Option<C> {
+        let conns = self.conns(key)?;
+        conns
+            .iter_mut()
+            .min_by_key(|conn| conn.streams())
+            .map(PoolConnection::pool)
+    }
+
+    fn flush(&mut self) {
+        trace!("flushing {} HTTP/3 pool entries", self.idle_conns.len());
+        self.idle_conns.clear();
     }
 }
 
 #[derive(Clone)]
 pub struct PoolClient {
     inner: SendRequest<OpenStreams, Bytes>,
+    // whether the early data of a 0-RTT connection was accepted, once known
+    handshake: Option<watch::Receiver<Option<bool>>>,
+    // the requests in flight on the connection
+    streams: Arc<AtomicUsize>,
 }
 
 impl PoolClient {
-    pub fn new(tx: SendRequest<OpenStreams, Bytes>) -> Self {
-        Self { inner: tx }
+    pub fn new(
+        tx: SendRequest<OpenStreams, Bytes>,
+        handshake: Option<watch::Receiver<Option<bool>>>,
+    ) -> Self {
+        Self {
+            inner: tx,
+            handshake,
+            streams: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns true while requests are sent in 0-RTT early data.
+    pub fn is_handshaking(&self) -> bool {
+        match self.handshake {
+            Some(ref handshake) => handshake.borrow().is_none(),
+            None => false,
+        }
+    }
+
+    /// Waits for the handshake to complete, and returns false if the early
+    /// data was rejected.
+    pub async fn handshake(&mut self) -> bool {
+        match self.handshake {
+            Some(ref mut handshake) => match handshake.wait_for(Option::is_some).await {
+                Ok(accepted) => accepted.unwrap_or(true),
+                // the connection is gone
+                Err(_) => true,
+            },
+            None => true,
+        }
     }
 
     pub async fn send_request(
@@ -129,6 +259,8 @@ impl PoolClient {
         use http_body_util::{BodyExt, Full};
         use hyper::body::Body as _;
 
+        let _stream = StreamGuard::new(&self.streams);
+
         let (head, req_body) = req.into_parts();
         let mut req = Request::from_parts(head, ());
 
@@ -165,28 +297,65 @@ impl PoolClient {
     }
 }
 
-pub struct PoolConnection {
+/// Counts a request in flight on a connection, until dropped.
+struct StreamGuard(Arc<AtomicUsize>);
+
+impl StreamGuard {
+    fn new(streams: &Arc<AtomicUsize>) -> StreamGuard {
+        streams.fetch_add(1, Ordering::Relaxed);
+        StreamGuard(streams.clone())
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub struct PoolConnection<C = PoolClient> {
     // This receives errors from polling h3 driver.
     close_rx: Receiver<h3::Error>,
-    client: PoolClient,
+    client: C,
+    // the requests in flight on the connection, shared with its clients
+    streams: Arc<AtomicUsize>,
+    // whether the early data of a 0-RTT connection was accepted, once known
+    handshake: Option<watch::Receiver<Option<bool>>>,
     idle_timeout: Instant,
 }
 
-impl PoolConnection {
-    pub fn new(client: PoolClient, close_rx: Receiver<h3::Error>) -> Self {
+impl<C: Clone> PoolConnection<C> {
+    pub fn new(
+        client: C,
+        streams: Arc<AtomicUsize>,
+        handshake: Option<watch::Receiver<Option<bool>>>,
+        close_rx: Receiver<h3::Error>,
+    ) -> Self {
         Self {
             close_rx,
             client,
+            streams,
+            handshake,
             idle_timeout: Instant::now(),
         }
     }
 
-    pub fn pool(&mut self) -> PoolClient {
+    pub fn pool(&mut self) -> C {
         self.idle_timeout = Instant::now();
         self.client.clone()
     }
 
+    fn streams(&self) -> usize {
+        self.streams.load(Ordering::Relaxed)
+    }
+
     pub fn is_invalid(&self) -> bool {
+        // the early data was rejected, which breaks the HTTP/3 session
+        if let Some(ref handshake) = self.handshake {
+            if *handshake.borrow() == Some(false) {
+                return true;
+            }
+        }
         match self.close_rx.try_recv() {
             Err(TryRecvError::Empty) => false,
             Err(TryRecvError::Disconnected) => true,
@@ -211,3 +380,121 @@ pub(crate) fn domain_as_uri((scheme, auth): Key) -> Uri {
         .build()
         .expect("domain is valid Uri")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Sender;
+
+    fn key() -> Key {
+        (Scheme::HTTPS, Authority::from_static("example.com"))
+    }
+
+    // a connection identified by `id`, with the sender of its driver errors
+    // and its count of requests in flight
+    fn conn(id: usize) -> (PoolConnection<usize>, Sender<h3::Error>, Arc<AtomicUsize>) {
+        let (close_tx, close_rx) = std::sync::mpsc::channel();
+        let streams = Arc::new(AtomicUsize::new(0));
+        let conn = PoolConnection::new(id, streams.clone(), None, close_rx);
+        (conn, close_tx, streams)
+    }
+
+    #[test]
+    fn max_streams() {
+        let mut pool = PoolInner::new(None, usize::MAX, Some(2));
+        let (conn1, _tx1, streams1) = conn(1);
+        let (conn2, _tx2, streams2) = conn(2);
+        pool.insert(key(), conn1);
+        pool.insert(key(), conn2);
+
+        streams1.store(2, Ordering::Relaxed);
+        assert_eq!(pool.try_pool(&key()), Some(2));
+
+        streams2.store(3, Ordering::Relaxed);
+        assert_eq!(pool.try_pool(&key()), None);
+        // the least busy connection is shared while another one opens
+        assert_eq!(pool.try_busy(&key()), Some(1));
+    }
+
+    #[test]
+    fn max_idle_keeps_most_recent() {
+        let mut pool = PoolInner::new(None, 1, None);
+        let now = Instant::now();
+        let mut streams = Vec::new();
+        for id in 1..=3 {
+            let (mut conn, tx, s) = conn(id);
+            conn.idle_timeout = now - Duration::from_secs(10 - id as u64);
+            pool.insert(key(), conn);
+            streams.push((tx, s));
+        }
+        // busy connections don't count as idle
+        streams[0].1.store(1, Ordering::Relaxed);
+
+        assert_eq!(pool.try_pool(&key()), Some(3));
+        let ids: Vec<_> = pool.idle_conns[&key()].iter().map(|c| c.client).collect();
+        assert_eq!(ids, [3, 1]);
+    }
+
+    #[test]
+    fn expired() {
+        let mut pool = PoolInner::new(Some(Duration::from_secs(1)), usize::MAX, None);
+        let (mut conn1, _tx1, streams1) = conn(1);
+        let (mut conn2, _tx2, _) = conn(2);
+        conn1.idle_timeout = Instant::now() - Duration::from_secs(2);
+        conn2.idle_timeout = Instant::now() - Duration::from_secs(2);
+        pool.insert(key(), conn1);
+        pool.insert(key(), conn2);
+
+        // a connection with requests in flight isn't idle
+        streams1.store(1, Ordering::Relaxed);
+        assert_eq!(pool.try_pool(&key()), Some(1));
+
+        streams1.store(0, Ordering::Relaxed);
+        pool.idle_conns.get_mut(&key()).unwrap()[0].idle_timeout =
+            Instant::now() - Duration::from_secs(2);
+        assert_eq!(pool.try_pool(&key()), None);
+        assert!(pool.idle_conns.is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut pool = PoolInner::new(None, usize::MAX, None);
+        let (conn1, tx1, _) = conn(1);
+        pool.insert(key(), conn1);
+        assert_eq!(pool.try_pool(&key()), Some(1));
+
+        // the driver is gone
+        drop(tx1);
+        assert_eq!(pool.try_pool(&key()), None);
+
+        // the early data was rejected
+        let (handshake_tx, handshake_rx) = watch::channel(None);
+        let (_close_tx, close_rx) = std::sync::mpsc::channel();
+        let conn2 = PoolConnection::new(2, Default::default(), Some(handshake_rx), close_rx);
+        pool.insert(key(), conn2);
+        assert_eq!(pool.try_pool(&key()), Some(2));
+        handshake_tx.send(Some(false)).unwrap();
+        assert_eq!(pool.try_busy(&key()), None);
+    }
+
+    #[test]
+    fn flush() {
+        let mut pool = PoolInner::new(None, usize::MAX, None);
+        let (conn1, _tx1, _) = conn(1);
+        pool.insert(key(), conn1);
+        pool.flush();
+        assert_eq!(pool.try_pool(&key()), None);
+        assert_eq!(pool.try_busy(&key()), None);
+    }
+
+    #[test]
+    fn connecting() {
+        let pool = Pool::new(None, usize::MAX, None);
+        let connecting = pool.connecting(key()).unwrap();
+        assert!(pool.connecting(key()).is_err());
+
+        // a failed or dropped connect lets the next one through
+        drop(connecting);
+        assert!(pool.connecting(key()).is_ok());
+    }
+}
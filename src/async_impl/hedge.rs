@@ -0,0 +1,85 @@
+//! Hedged requests, see `ClientBuilder::hedge_after()`.
+//!
+//! The request is sent once, and a clone of it is kept. If no response
+//! arrived when the delay elapses, the clone is sent too, and the first
+//! successful response wins. Dropping the other `Pending` cancels it.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use log::debug;
+use tokio::time::Sleep;
+
+use super::client::{Client, Pending};
+use super::request::Request;
+use super::response::Response;
+
+pub(crate) struct Hedge {
+    client: Client,
+    primary: Option<Pending>,
+    hedge: Option<Pending>,
+    // sent once `delay` elapses
+    request: Option<Request>,
+    delay: Pin<Box<Sleep>>,
+    error: Option<crate::Error>,
+}
+
+impl Hedge {
+    pub(crate) fn new(client: Client, primary: Pending, request: Request, delay: Duration) -> Hedge {
+        Hedge {
+            client,
+            primary: Some(primary),
+            hedge: None,
+            request: Some(request),
+            delay: Box::pin(tokio::time::sleep(delay)),
+            error: None,
+        }
+    }
+}
+
+impl Future for Hedge {
+    type Output = Result<Response, crate::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        if this.request.is_some() && this.delay.as_mut().poll(cx).is_ready() {
+            let request = this.request.take().expect("checked above");
+            debug!("sending hedged request to {}", request.url());
+            this.hedge = Some(this.client.execute_once(request));
+        }
+
+        for pending in [&mut this.primary, &mut this.hedge] {
+            if let Some(fut) = pending {
+                match Pin::new(fut).poll(cx) {
+                    Poll::Ready(Ok(res)) => return Poll::Ready(Ok(res)),
+                    Poll::Ready(Err(err)) => {
+                        *pending = None;
+                        this.error.get_or_insert(err);
+                    }
+                    Poll::Pending => {}
+                }
+            }
+        }
+
+        // a request that failed before the delay isn't retried
+        if this.primary.is_none() && this.hedge.is_none() {
+            this.request = None;
+            let err = this.error.take().expect("Hedge polled after completion");
+            return Poll::Ready(Err(err));
+        }
+        Poll::Pending
+    }
+}
+
+impl fmt::Debug for Hedge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hedge")
+            .field("primary", &self.primary)
+            .field("hedge", &self.hedge)
+            .finish()
+    }
+}
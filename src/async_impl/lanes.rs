@@ -0,0 +1,162 @@
+//! Stream limits, see `ClientBuilder::http2_max_concurrent_streams()`.
+//!
+//! The connections of a pooled client are shared by all the requests to an
+//! origin, so the client is split in lanes, each with its own pool. A
+//! request takes the first lane carrying fewer than the maximum number of
+//! streams to its origin, which opens another connection once all of them
+//! are busy. A request holds its lane until its response body is done.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http_body::Body as HttpBody;
+use pin_project_lite::pin_project;
+use url::Url;
+
+use super::client::HyperClient;
+use crate::connect::{BoxedConnectorLayer, ConnectorBuilder};
+
+pub(crate) struct Lanes {
+    max_streams: usize,
+    builder: hyper_util::client::legacy::Builder,
+    connector: ConnectorBuilder,
+    layers: Vec<BoxedConnectorLayer>,
+    // the lanes after the first one, which is the client's own
+    clients: Mutex<Vec<HyperClient>>,
+    // the requests in flight in each lane, per origin
+    streams: Mutex<HashMap<String, Vec<usize>>>,
+}
+
+/// A request's place in a lane, released when dropped.
+pub(crate) struct Lane {
+    lanes: Arc<Lanes>,
+    origin: String,
+    index: usize,
+}
+
+pin_project! {
+    /// A response body that holds its lane until it ends.
+    pub(crate) struct LaneBody<B> {
+        #[pin]
+        inner: B,
+        lane: Option<Lane>,
+    }
+}
+
+// ===== impl Lanes =====
+
+impl Lanes {
+    pub(crate) fn new(
+        max_streams: usize,
+        builder: hyper_util::client::legacy::Builder,
+        connector: ConnectorBuilder,
+        layers: Vec<BoxedConnectorLayer>,
+    ) -> Lanes {
+        Lanes {
+            max_streams,
+            builder,
+            connector,
+            layers,
+            clients: Mutex::default(),
+            streams: Mutex::default(),
+        }
+    }
+
+    /// Picks the lane of a request to `url`, where `first` is the client of
+    /// the first lane.
+    pub(crate) fn acquire(self: &Arc<Self>, url: &Url, first: &HyperClient) -> (HyperClient, Lane) {
+        let origin = url.origin().ascii_serialization();
+        let index = {
+            let mut streams = self.streams.lock().unwrap();
+            let counts = streams.entry(origin.clone()).or_default();
+            let index = counts
+                .iter()
+                .position(|&count| count < self.max_streams)
+                .unwrap_or(counts.len());
+            if index == counts.len() {
+                counts.push(0);
+            }
+            counts[index] += 1;
+            index
+        };
+        let client = if index == 0 {
+            first.clone()
+        } else {
+            let mut clients = self.clients.lock().unwrap();
+            while clients.len() < index {
+                let connector = self.connector.clone().build(self.layers.clone());
+                clients.push(self.builder.build(connector));
+            }
+            clients[index - 1].clone()
+        };
+        let lane = Lane {
+            lanes: self.clone(),
+            origin,
+            index,
+        };
+        (client, lane)
+    }
+}
+
+impl fmt::Debug for Lanes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Lanes")
+            .field("max_streams", &self.max_streams)
+            .finish()
+    }
+}
+
+// ===== impl Lane =====
+
+impl Drop for Lane {
+    fn drop(&mut self) {
+        let mut streams = self.lanes.streams.lock().unwrap();
+        if let Some(counts) = streams.get_mut(&self.origin) {
+            counts[self.index] -= 1;
+            while counts.last() == Some(&0) {
+                counts.pop();
+            }
+            if counts.is_empty() {
+                streams.remove(&self.origin);
+            }
+        }
+    }
+}
+
+// ===== impl LaneBody =====
+
+pub(crate) fn hold<B>(inner: B, lane: Option<Lane>) -> LaneBody<B> {
+    LaneBody { inner, lane }
+}
+
+impl<B> HttpBody for LaneBody<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = futures_core::ready!(this.inner.poll_frame(cx));
+        if !matches!(frame, Some(Ok(_))) {
+            this.lane.take();
+        }
+        Poll::Ready(frame)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
@@ -0,0 +1,172 @@
+//! Concurrency limits, see `ClientBuilder::max_concurrent_requests()`.
+//!
+//! A request waits for a permit of the client, and one of its origin, before
+//! it's sent. The permits are held until the response arrived, or the
+//! request failed, so that the body of a response isn't counted.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use log::debug;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Sleep;
+use url::Url;
+
+use super::client::{Client, Pending};
+use super::request::Request;
+use super::response::Response;
+
+/// The concurrency limits of a `Client`.
+pub(crate) struct Limits {
+    global: Option<Arc<Semaphore>>,
+    per_origin: Option<usize>,
+    origins: Mutex<HashMap<String, Arc<Semaphore>>>,
+    queue_timeout: Option<Duration>,
+}
+
+/// A request waiting for its permits.
+pub(crate) struct Queued {
+    client: Client,
+    request: Option<Request>,
+    acquire: Pin<Box<dyn Future<Output = Permits> + Send + Sync>>,
+    timeout: Option<Pin<Box<Sleep>>>,
+    pending: Option<Pending>,
+    permits: Option<Permits>,
+}
+
+struct Permits {
+    _global: Option<OwnedSemaphorePermit>,
+    _origin: Option<OwnedSemaphorePermit>,
+}
+
+// ===== impl Limits =====
+
+impl Limits {
+    pub(crate) fn new(
+        global: Option<usize>,
+        per_origin: Option<usize>,
+        queue_timeout: Option<Duration>,
+    ) -> Option<Limits> {
+        if global.is_none() && per_origin.is_none() {
+            return None;
+        }
+        Some(Limits {
+            global: global.map(|n| Arc::new(Semaphore::new(n))),
+            per_origin,
+            origins: Mutex::new(HashMap::new()),
+            queue_timeout,
+        })
+    }
+
+    fn origin(&self, url: &Url) -> Option<Arc<Semaphore>> {
+        let max = self.per_origin?;
+        let mut origins = self.origins.lock().unwrap();
+        // forget the origins without requests
+        origins.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+        let semaphore = origins
+            .entry(url.origin().ascii_serialization())
+            .or_insert_with(|| Arc::new(Semaphore::new(max)));
+        Some(semaphore.clone())
+    }
+}
+
+impl fmt::Debug for Limits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Limits")
+            .field(
+                "global",
+                &self.global.as_ref().map(|s| s.available_permits()),
+            )
+            .field("per_origin", &self.per_origin)
+            .field("queue_timeout", &self.queue_timeout)
+            .finish()
+    }
+}
+
+// ===== impl Queued =====
+
+impl Queued {
+    pub(crate) fn new(client: Client, limits: &Limits, request: Request) -> Queued {
+        let global = limits.global.clone();
+        let origin = limits.origin(request.url());
+        let acquire = async move {
+            // the semaphores are never closed
+            let global = match global {
+                Some(semaphore) => semaphore.acquire_owned().await.ok(),
+                None => None,
+            };
+            let origin = match origin {
+                Some(semaphore) => semaphore.acquire_owned().await.ok(),
+                None => None,
+            };
+            Permits {
+                _global: global,
+                _origin: origin,
+            }
+        };
+        Queued {
+            client,
+            request: Some(request),
+            acquire: Box::pin(acquire),
+            timeout: limits
+                .queue_timeout
+                .map(|timeout| Box::pin(tokio::time::sleep(timeout))),
+            pending: None,
+            permits: None,
+        }
+    }
+}
+
+impl Future for Queued {
+    type Output = Result<Response, crate::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        if let Some(ref mut request) = this.request {
+            match this.acquire.as_mut().poll(cx) {
+                Poll::Ready(permits) => {
+                    let request = this.request.take().expect("checked above");
+                    this.permits = Some(permits);
+                    this.pending = Some(this.client.execute_unlimited(request));
+                }
+                Poll::Pending => {
+                    if let Some(ref mut timeout) = this.timeout {
+                        if timeout.as_mut().poll(cx).is_ready() {
+                            debug!("no free request slot for {}", request.url());
+                            let url = request.url().clone();
+                            this.request = None;
+                            return Poll::Ready(Err(
+                                crate::error::request(crate::error::TimedOut).with_url(url)
+                            ));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        let pending = this.pending.as_mut().expect("Queued polled after completion");
+        let res = futures_core::ready!(Pin::new(pending).poll(cx));
+        this.pending = None;
+        this.permits = None;
+        Poll::Ready(res)
+    }
+}
+
+impl fmt::Debug for Queued {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.pending {
+            Some(ref pending) => pending.fmt(f),
+            None => f
+                .debug_struct("Queued")
+                .field("url", &self.request.as_ref().map(Request::url))
+                .finish(),
+        }
+    }
+}
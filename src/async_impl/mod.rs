@@ -1,8 +1,14 @@
 pub use self::body::Body;
-pub use self::client::{Client, ClientBuilder};
+pub use self::client::{Client, ClientBuilder, HttpPending, RequestService};
+#[cfg(feature = "stream")]
+pub use self::download::Downloader;
+#[cfg(feature = "http3")]
+pub use self::h3_client::Http3ZeroRtt;
 pub use self::request::{Request, RequestBuilder};
 pub use self::response::Response;
-pub use self::upgrade::Upgraded;
+#[cfg(feature = "stream")]
+pub use self::spill::SpilledBody;
+pub use self::upgrade::{UpgradeHandle, Upgraded};
 
 #[cfg(feature = "blocking")]
 pub(crate) use self::decoder::Decoder;
@@ -10,9 +16,23 @@ pub(crate) use self::decoder::Decoder;
 pub mod body;
 pub mod client;
 pub mod decoder;
+#[cfg(feature = "stream")]
+mod download;
+#[cfg(feature = "http2")]
+pub(crate) mod h2_fallback;
 pub mod h3_client;
+mod hedge;
+#[cfg(feature = "http2")]
+mod lanes;
+mod limit;
+#[cfg(feature = "http3")]
+mod race;
 #[cfg(feature = "multipart")]
 pub mod multipart;
 pub(crate) mod request;
 mod response;
+pub(crate) mod shutdown;
+#[cfg(feature = "stream")]
+mod spill;
+mod throttle;
 mod upgrade;
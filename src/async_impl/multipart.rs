@@ -1,4 +1,4 @@
-//! multipart/form-data
+//! multipart/form-data requests, and multipart responses
 use std::borrow::Cow;
 use std::fmt;
 use std::pin::Pin;
@@ -20,6 +20,10 @@ use futures_util::{future, stream, StreamExt};
 use super::Body;
 use crate::header::HeaderMap;
 
+mod parts;
+
+pub use self::parts::{Parts, ReceivedPart};
+
 /// An async multipart/form-data request.
 pub struct Form {
     inner: FormParts<Part>,
@@ -205,6 +209,30 @@ impl Form {
             .chain(stream::once(future::ready(Ok("\r\n".into()))))
     }
 
+    /// Returns the exact length of this form's body, if it is known.
+    ///
+    /// The length includes the boundaries and part headers, and is known when
+    /// every part has a known length, such as text, bytes, files, and streams
+    /// created with [`Part::stream_with_length()`]. Some servers, like S3's
+    /// pre-signed POST uploads, reject chunked request bodies, so all parts
+    /// must have known lengths for them.
+    ///
+    /// When the length is known, [`RequestBuilder::multipart()`] sets the
+    /// `Content-Length` header automatically.
+    ///
+    /// [`RequestBuilder::multipart()`]: crate::RequestBuilder::multipart
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let form = reqwest::multipart::Form::new().text("key", "value");
+    /// let length = form.length().unwrap();
+    /// assert!(length > "value".len() as u64);
+    /// ```
+    pub fn length(&self) -> Option<u64> {
+        self.inner.length()
+    }
+
     pub(crate) fn compute_length(&mut self) -> Option<u64> {
         self.inner.compute_length()
     }
@@ -406,34 +434,43 @@ impl<P: PartProps> FormParts<P> {
     // but not if a generic reader has been added;
     pub(crate) fn compute_length(&mut self) -> Option<u64> {
         let mut length = 0u64;
-        for &(ref name, ref field) in self.fields.iter() {
-            match field.value_len() {
-                Some(value_length) => {
-                    // We are constructing the header just to get its length. To not have to
-                    // construct it again when the request is sent we cache these headers.
-                    let header = self.percent_encoding.encode_headers(name, field.metadata());
-                    let header_length = header.len();
-                    self.computed_headers.push(header);
-                    // The additions mimic the format string out of which the field is constructed
-                    // in Reader. Not the cleanest solution because if that format string is
-                    // ever changed then this formula needs to be changed too which is not an
-                    // obvious dependency in the code.
-                    length += 2
-                        + self.boundary().len() as u64
-                        + 2
-                        + header_length as u64
-                        + 4
-                        + value_length
-                        + 2
-                }
-                _ => return None,
-            }
+        for (name, field) in self.fields.iter() {
+            let value_length = field.value_len()?;
+            // We are constructing the header just to get its length. To not have to
+            // construct it again when the request is sent we cache these headers.
+            let header = self.percent_encoding.encode_headers(name, field.metadata());
+            length += self.field_length(header.len(), value_length);
+            self.computed_headers.push(header);
+        }
+        Some(length + self.end_length())
+    }
+
+    /// Like `compute_length`, without caching the encoded headers.
+    pub(crate) fn length(&self) -> Option<u64> {
+        let mut length = 0u64;
+        for (name, field) in self.fields.iter() {
+            let value_length = field.value_len()?;
+            let header = self.percent_encoding.encode_headers(name, field.metadata());
+            length += self.field_length(header.len(), value_length);
         }
+        Some(length + self.end_length())
+    }
+
+    fn field_length(&self, header_length: usize, value_length: u64) -> u64 {
+        // The additions mimic the format string out of which the field is constructed
+        // in Reader. Not the cleanest solution because if that format string is
+        // ever changed then this formula needs to be changed too which is not an
+        // obvious dependency in the code.
+        2 + self.boundary().len() as u64 + 2 + header_length as u64 + 4 + value_length + 2
+    }
+
+    fn end_length(&self) -> u64 {
         // If there is at least one field there is a special boundary for the very last field.
-        if !self.fields.is_empty() {
-            length += 2 + self.boundary().len() as u64 + 4
+        if self.fields.is_empty() {
+            0
+        } else {
+            2 + self.boundary().len() as u64 + 4
         }
-        Some(length)
     }
 
     /// Take the fields vector of this instance, replacing with an empty vector.
@@ -731,6 +768,32 @@ mod tests {
         assert_eq!(body_part.value_len().unwrap(), bytes_len as u64);
     }
 
+    #[test]
+    fn length_matches_body() {
+        let form = Form::new()
+            .text("key1", "value1")
+            .part("key2", Part::bytes(&b"value2"[..]).file_name("file.bin"))
+            .part(
+                "key3",
+                Part::stream_with_length(Body::from("value3"), 6).mime_str("text/plain").unwrap(),
+            );
+        let length = form.length().unwrap();
+
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("new rt");
+        let body = form.stream().into_stream();
+        let out = rt.block_on(body.map_ok(|c| c.to_vec()).try_concat()).unwrap();
+        assert_eq!(length, out.len() as u64);
+
+        let unknown = stream::once(future::ready(Ok::<_, std::io::Error>("unknown")));
+        let form = Form::new()
+            .text("key1", "value1")
+            .part("key2", Part::stream(Body::stream(unknown)));
+        assert_eq!(form.length(), None);
+    }
+
     #[test]
     fn header_percent_encoding() {
         let name = "start%'\"\r\nßend";
@@ -746,4 +809,21 @@ mod tests {
             &b"Content-Disposition: form-data; name*=utf-8''start%25%27%22%0D%0A%C3%9Fend"[..]
         );
     }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn file_part_metadata() {
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("new rt");
+        let part = rt.block_on(Part::file("README.md")).unwrap();
+
+        assert_eq!(part.meta.file_name.as_deref(), Some("README.md"));
+        assert_eq!(part.meta.mime.as_ref().unwrap().essence_str(), "text/markdown");
+        assert_eq!(
+            part.value_len(),
+            Some(std::fs::metadata("README.md").unwrap().len())
+        );
+    }
 }
@@ -0,0 +1,340 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures_core::Stream;
+use http::header::{HeaderName, HeaderValue, CONTENT_RANGE, CONTENT_TYPE};
+
+use crate::header::HeaderMap;
+
+/// Part heads larger than this are an error.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+type BodyStream = Pin<Box<dyn Stream<Item = crate::Result<Bytes>> + Send + Sync>>;
+
+/// The parts of a `multipart/*` response, like `multipart/mixed` or
+/// `multipart/byteranges`.
+///
+/// This is a `Stream` of [`ReceivedPart`]s, returned by
+/// [`Response::multipart()`](crate::Response::multipart). Each part is
+/// yielded as soon as all of it was received.
+pub struct Parts {
+    body: BodyStream,
+    /// `\r\n--boundary`
+    delimiter: Vec<u8>,
+    buf: BytesMut,
+    state: State,
+}
+
+/// A part of a multipart response.
+#[derive(Clone, Debug)]
+pub struct ReceivedPart {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+enum State {
+    /// Skipping the preamble, before the first delimiter.
+    Preamble,
+    /// After a delimiter, before the headers of a part or the end.
+    Delimiter,
+    Headers,
+    Body(HeaderMap),
+    Done,
+}
+
+// ===== impl Parts =====
+
+impl Parts {
+    pub(crate) fn new(
+        content_type: Option<&HeaderValue>,
+        body: BodyStream,
+    ) -> crate::Result<Parts> {
+        let boundary = content_type
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+            .filter(|mime| mime.type_() == mime::MULTIPART)
+            .and_then(|mime| Some(mime.get_param(mime::BOUNDARY)?.as_str().to_owned()))
+            .ok_or_else(|| crate::error::decode("response isn't multipart with a boundary"))?;
+
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        // the first delimiter may be at the very start of the body
+        let buf = BytesMut::from(&b"\r\n"[..]);
+        Ok(Parts {
+            body,
+            delimiter,
+            buf,
+            state: State::Preamble,
+        })
+    }
+
+    /// Parses the next part from the buffer, if all of it was received.
+    fn parse(&mut self) -> crate::Result<Option<ReceivedPart>> {
+        loop {
+            match self.state {
+                State::Preamble => match find(&self.buf, &self.delimiter) {
+                    Some(i) => {
+                        self.buf.advance(i + self.delimiter.len());
+                        self.state = State::Delimiter;
+                    }
+                    None => {
+                        // keep what may be the start of a delimiter
+                        let keep = std::cmp::min(self.buf.len(), self.delimiter.len() - 1);
+                        self.buf.advance(self.buf.len() - keep);
+                        return Ok(None);
+                    }
+                },
+                State::Delimiter => {
+                    if self.buf.starts_with(b"--") {
+                        self.state = State::Done;
+                        return Ok(None);
+                    }
+                    match find(&self.buf, b"\r\n") {
+                        Some(i) => {
+                            // skip transport padding
+                            if self.buf[..i].iter().any(|&b| b != b' ' && b != b'\t') {
+                                return Err(crate::error::decode("invalid multipart delimiter"));
+                            }
+                            self.buf.advance(i + 2);
+                            self.state = State::Headers;
+                        }
+                        None => {
+                            if self.buf.len() > MAX_HEAD_SIZE {
+                                return Err(crate::error::decode("invalid multipart delimiter"));
+                            }
+                            return Ok(None);
+                        }
+                    }
+                }
+                State::Headers => {
+                    let end = if self.buf.starts_with(b"\r\n") {
+                        Some(0)
+                    } else {
+                        find(&self.buf, b"\r\n\r\n").map(|i| i + 2)
+                    };
+                    match end {
+                        Some(end) => {
+                            let head = self.buf.split_to(end + 2);
+                            let headers = parse_headers(&head[..end])?;
+                            self.state = State::Body(headers);
+                        }
+                        None => {
+                            if self.buf.len() > MAX_HEAD_SIZE {
+                                return Err(crate::error::decode("multipart headers too large"));
+                            }
+                            return Ok(None);
+                        }
+                    }
+                }
+                State::Body(_) => match find(&self.buf, &self.delimiter) {
+                    Some(i) => {
+                        let body = self.buf.split_to(i).freeze();
+                        self.buf.advance(self.delimiter.len());
+                        let headers = match std::mem::replace(&mut self.state, State::Delimiter) {
+                            State::Body(headers) => headers,
+                            _ => unreachable!("state was body"),
+                        };
+                        return Ok(Some(ReceivedPart { headers, body }));
+                    }
+                    None => return Ok(None),
+                },
+                State::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Stream for Parts {
+    type Item = crate::Result<ReceivedPart>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.parse() {
+                Ok(Some(part)) => return Poll::Ready(Some(Ok(part))),
+                Ok(None) => (),
+                Err(err) => {
+                    self.state = State::Done;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+            if let State::Done = self.state {
+                return Poll::Ready(None);
+            }
+
+            match futures_core::ready!(self.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    self.state = State::Done;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => {
+                    self.state = State::Done;
+                    return Poll::Ready(Some(Err(crate::error::decode(
+                        "multipart body ended before the closing delimiter",
+                    ))));
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Parts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parts").finish()
+    }
+}
+
+// ===== impl ReceivedPart =====
+
+impl ReceivedPart {
+    /// The headers of the part.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The `Content-Type` of the part, if it has one.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get(CONTENT_TYPE)?.to_str().ok()
+    }
+
+    /// The range of bytes in the `Content-Range` header of a part of a
+    /// `multipart/byteranges` response, like `0-499` for `bytes 0-499/1234`.
+    pub fn content_range(&self) -> Option<RangeInclusive<u64>> {
+        let value = self.headers.get(CONTENT_RANGE)?.to_str().ok()?;
+        let range = value.strip_prefix("bytes ")?.split('/').next()?;
+        let (first, last) = range.trim().split_once('-')?;
+        let first = first.parse().ok()?;
+        let last = last.parse().ok()?;
+        if first <= last {
+            Some(first..=last)
+        } else {
+            None
+        }
+    }
+
+    /// The body of the part.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Consumes the part, returning its body.
+    pub fn into_bytes(self) -> Bytes {
+        self.body
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn parse_headers(head: &[u8]) -> crate::Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for line in head.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+        let colon = line
+            .iter()
+            .position(|&b| b == b':')
+            .ok_or_else(|| crate::error::decode("invalid multipart header"))?;
+        let name = HeaderName::from_bytes(&line[..colon]).map_err(crate::error::decode)?;
+        let value = std::str::from_utf8(&line[colon + 1..])
+            .map(str::trim)
+            .map_err(crate::error::decode)?;
+        let value = HeaderValue::from_str(value).map_err(crate::error::decode)?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{stream, StreamExt, TryStreamExt};
+    use tokio::runtime;
+
+    fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+        runtime::Builder::new_current_thread()
+            .build()
+            .expect("new rt")
+            .block_on(fut)
+    }
+
+    fn parts(content_type: &'static str, chunks: &[&'static str]) -> crate::Result<Parts> {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+            .collect::<Vec<_>>();
+        Parts::new(
+            Some(&HeaderValue::from_static(content_type)),
+            Box::pin(stream::iter(chunks)),
+        )
+    }
+
+    fn collect(parts: Parts) -> crate::Result<Vec<ReceivedPart>> {
+        block_on(parts.try_collect())
+    }
+
+    #[test]
+    fn byteranges() {
+        let body = "--THIS\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Range: bytes 0-4/20\r\n\
+            \r\n\
+            hello\r\n\
+            --THIS\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Range: bytes 15-19/20\r\n\
+            \r\n\
+            world\r\n\
+            --THIS--\r\n";
+        // split in every possible place
+        for i in 0..body.len() {
+            let (a, b) = body.split_at(i);
+            let parts = collect(parts("multipart/byteranges; boundary=THIS", &[a, b]).unwrap())
+                .unwrap();
+            assert_eq!(parts.len(), 2, "split at {i}");
+            assert_eq!(parts[0].content_type(), Some("text/plain"));
+            assert_eq!(parts[0].content_range(), Some(0..=4));
+            assert_eq!(parts[0].body(), b"hello");
+            assert_eq!(parts[1].content_range(), Some(15..=19));
+            assert_eq!(parts[1].body(), b"world");
+        }
+    }
+
+    #[test]
+    fn mixed_with_preamble_and_epilogue() {
+        let parts = collect(
+            parts(
+                "multipart/mixed; boundary=\"b\"",
+                &["preamble\r\n--b  \r\n\r\nno headers\r\n--b\r\nX-A: 1\r\n\r\n\r\n--b--epilogue"],
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].headers().is_empty());
+        assert_eq!(parts[0].body(), b"no headers");
+        assert_eq!(parts[1].headers()["x-a"], "1");
+        assert_eq!(parts[1].body(), b"");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parts("text/plain", &[]).unwrap_err().is_decode());
+        assert!(parts("multipart/mixed", &[]).unwrap_err().is_decode());
+
+        let mut truncated = parts("multipart/mixed; boundary=b", &["--b\r\n\r\nhello"]).unwrap();
+        let err = block_on(truncated.next())
+            .unwrap()
+            .unwrap_err();
+        assert!(err.is_decode());
+        assert!(block_on(truncated.next()).is_none());
+    }
+}
@@ -0,0 +1,98 @@
+//! Racing HTTP/3 against TCP, see `ClientBuilder::http3_race_tcp()`.
+//!
+//! The first request to an origin opens a QUIC connection and a TCP (and
+//! TLS) connection at once, and is sent over whichever protocol completes
+//! its handshake first. The winner is remembered for the origin, so later
+//! requests skip the race. The QUIC connection stays in the HTTP/3 pool;
+//! the TCP connection only serves as a probe, and is closed.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::{self, Either};
+use http::Uri;
+use tower::ServiceExt;
+use url::Url;
+
+use super::h3_client::H3Client;
+use crate::connect::Connector;
+
+/// The protocol that won the race to an origin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Winner {
+    Http3,
+    Tcp,
+}
+
+pub(crate) struct Race {
+    connector: Connector,
+    winners: Mutex<HashMap<String, Winner>>,
+}
+
+impl Race {
+    pub(crate) fn new(connector: Connector) -> Race {
+        Race {
+            connector,
+            winners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the protocol that won the race to the origin of `url`, if it
+    /// was raced.
+    pub(crate) fn winner(&self, url: &Url) -> Option<Winner> {
+        let key = url_key(url)?;
+        self.winners.lock().unwrap().get(&key).copied()
+    }
+
+    /// Forgets the winner of the origin of `url`, so the next request races
+    /// again.
+    pub(crate) fn forget(&self, url: &Url) {
+        if let Some(key) = url_key(url) {
+            self.winners.lock().unwrap().remove(&key);
+        }
+    }
+
+    fn record(&self, url: &Url, winner: Winner) {
+        if let Some(key) = url_key(url) {
+            self.winners.lock().unwrap().insert(key, winner);
+        }
+    }
+
+    /// Races the handshakes to the origin of `url`, and records the winner.
+    ///
+    /// If neither handshake completes, the request goes over TCP to report
+    /// its error, and nothing is recorded.
+    pub(crate) fn run(
+        self: &Arc<Self>,
+        h3: &H3Client,
+        url: &Url,
+        uri: Uri,
+    ) -> impl Future<Output = Winner> + Send + 'static {
+        let race = self.clone();
+        let url = url.clone();
+        let quic = h3.connect(uri.clone());
+        let tcp = self.connector.clone().oneshot(uri);
+        async move {
+            futures_util::pin_mut!(quic, tcp);
+            let winner = match future::select(quic, tcp).await {
+                Either::Left((Ok(()), _)) => Some(Winner::Http3),
+                Either::Right((Ok(_), _)) => Some(Winner::Tcp),
+                Either::Left((Err(_), tcp)) => tcp.await.ok().map(|_| Winner::Tcp),
+                Either::Right((Err(_), quic)) => quic.await.ok().map(|()| Winner::Http3),
+            };
+            match winner {
+                Some(winner) => {
+                    log::debug!("{winner:?} won the race to {url}");
+                    race.record(&url, winner);
+                    winner
+                }
+                None => Winner::Tcp,
+            }
+        }
+    }
+}
+
+fn url_key(url: &Url) -> Option<String> {
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
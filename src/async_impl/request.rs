@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::future::Future;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use serde::Serialize;
 #[cfg(feature = "json")]
@@ -12,10 +15,10 @@ use super::client::{Client, Pending};
 #[cfg(feature = "multipart")]
 use super::multipart;
 use super::response::Response;
-#[cfg(feature = "multipart")]
-use crate::header::CONTENT_LENGTH;
-use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
-use crate::{Method, Url};
+use crate::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, TRAILER,
+};
+use crate::{IntoInferredBody, Method, StatusCode, Url};
 use http::{request::Parts, Request as HttpRequest, Version};
 
 /// A request which can be executed with `Client::execute()`.
@@ -25,9 +28,24 @@ pub struct Request {
     headers: HeaderMap,
     body: Option<Body>,
     timeout: Option<Duration>,
+    max_redirects: Option<usize>,
+    hedge_after: Option<Duration>,
+    expect_continue: Option<Duration>,
+    accept_encoding: bool,
+    max_upload_rate: Option<u64>,
+    max_download_rate: Option<u64>,
+    #[cfg(feature = "http2")]
+    protocol: Option<String>,
     version: Version,
+    resolve_map: Option<HashMap<String, Vec<SocketAddr>>>,
+    #[cfg(feature = "cookies")]
+    cookie_store: Option<Arc<dyn crate::cookie::CookieStore>>,
+    on_informational: Option<OnInformational>,
 }
 
+/// A function called with the informational responses to a request.
+pub(crate) type OnInformational = Arc<dyn Fn(StatusCode, &HeaderMap) + Send + Sync>;
+
 /// A builder to construct the properties of a `Request`.
 ///
 /// To construct a `RequestBuilder`, refer to the `Client` documentation.
@@ -47,7 +65,19 @@ impl Request {
             headers: HeaderMap::new(),
             body: None,
             timeout: None,
+            max_redirects: None,
+            hedge_after: None,
+            expect_continue: None,
+            accept_encoding: true,
+            max_upload_rate: None,
+            max_download_rate: None,
+            #[cfg(feature = "http2")]
+            protocol: None,
             version: Version::default(),
+            resolve_map: None,
+            #[cfg(feature = "cookies")]
+            cookie_store: None,
+            on_informational: None,
         }
     }
 
@@ -111,6 +141,131 @@ impl Request {
         &mut self.timeout
     }
 
+    /// Get the maximum number of redirects to follow.
+    #[inline]
+    pub fn max_redirects(&self) -> Option<usize> {
+        self.max_redirects
+    }
+
+    /// Get a mutable reference to the maximum number of redirects to follow.
+    #[inline]
+    pub fn max_redirects_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_redirects
+    }
+
+    /// Get the delay after which a hedged request is sent.
+    #[inline]
+    pub fn hedge_after(&self) -> Option<Duration> {
+        self.hedge_after
+    }
+
+    /// Get a mutable reference to the delay after which a hedged request is
+    /// sent.
+    #[inline]
+    pub fn hedge_after_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.hedge_after
+    }
+
+    /// Get how long the body waits for a `100 Continue` response.
+    #[inline]
+    pub fn expect_continue(&self) -> Option<Duration> {
+        self.expect_continue
+    }
+
+    /// Get a mutable reference to how long the body waits for a
+    /// `100 Continue` response.
+    #[inline]
+    pub fn expect_continue_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.expect_continue
+    }
+
+    /// Get the maximum rate, in bytes per second, at which the body is sent.
+    #[inline]
+    pub fn max_upload_rate(&self) -> Option<u64> {
+        self.max_upload_rate
+    }
+
+    /// Get a mutable reference to the maximum rate, in bytes per second, at
+    /// which the body is sent.
+    #[inline]
+    pub fn max_upload_rate_mut(&mut self) -> &mut Option<u64> {
+        &mut self.max_upload_rate
+    }
+
+    /// Get the maximum rate, in bytes per second, at which the response body
+    /// is received.
+    #[inline]
+    pub fn max_download_rate(&self) -> Option<u64> {
+        self.max_download_rate
+    }
+
+    /// Get a mutable reference to the maximum rate, in bytes per second, at
+    /// which the response body is received.
+    #[inline]
+    pub fn max_download_rate_mut(&mut self) -> &mut Option<u64> {
+        &mut self.max_download_rate
+    }
+
+    /// Get whether the client's `Accept-Encoding` header is added to this
+    /// request.
+    #[inline]
+    pub fn accept_encoding(&self) -> bool {
+        self.accept_encoding
+    }
+
+    /// Get a mutable reference to whether the client's `Accept-Encoding`
+    /// header is added to this request.
+    #[inline]
+    pub fn accept_encoding_mut(&mut self) -> &mut bool {
+        &mut self.accept_encoding
+    }
+
+    /// Get the `:protocol` of an HTTP/2 extended CONNECT request.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    #[inline]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Get a mutable reference to the `:protocol` of an HTTP/2 extended
+    /// CONNECT request.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    #[inline]
+    pub fn protocol_mut(&mut self) -> &mut Option<String> {
+        &mut self.protocol
+    }
+
+    /// Get the DNS overrides of this request.
+    #[inline]
+    pub fn resolve_map(&self) -> Option<&HashMap<String, Vec<SocketAddr>>> {
+        self.resolve_map.as_ref()
+    }
+
+    /// Get a mutable reference to the DNS overrides of this request.
+    #[inline]
+    pub fn resolve_map_mut(&mut self) -> &mut Option<HashMap<String, Vec<SocketAddr>>> {
+        &mut self.resolve_map
+    }
+
+    /// Get the cookie store of this request, used instead of the one of the
+    /// `Client`.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    #[inline]
+    pub fn cookie_store(&self) -> Option<&Arc<dyn crate::cookie::CookieStore>> {
+        self.cookie_store.as_ref()
+    }
+
+    /// Get a mutable reference to the cookie store of this request.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    #[inline]
+    pub fn cookie_store_mut(&mut self) -> &mut Option<Arc<dyn crate::cookie::CookieStore>> {
+        &mut self.cookie_store
+    }
+
     /// Get the http version.
     #[inline]
     pub fn version(&self) -> Version {
@@ -123,6 +278,123 @@ impl Request {
         &mut self.version
     }
 
+    /// Constructs a request from a curl command line.
+    ///
+    /// This understands the common curl options used to describe a request:
+    /// `-X`/`--request`, `-H`/`--header`, `-d`/`--data` (and its `-raw`,
+    /// `-binary` and `-urlencode` variants), `-F`/`--form`, `-u`/`--user`,
+    /// `-A`, `-e`, `-b`, `-G` and `-I`. Options that only affect curl's output
+    /// or connection handling, such as `-s` or `-L`, are ignored; configure
+    /// the `Client` the request is executed with instead. `--compressed` is
+    /// rejected, since decompression is a `Client` option as well.
+    ///
+    /// Commands that reference local files, like `-d @file` or
+    /// `-F name=@file`, are rejected, since a command from an untrusted
+    /// source could send any file readable by the process. Use
+    /// [`Request::from_curl_with_files()`] to read them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let req = reqwest::Request::from_curl(
+    ///     r#"curl -X POST https://httpbin.org/post -H 'Accept: application/json' -d 'a=1'"#,
+    /// )?;
+    /// assert_eq!(req.method(), reqwest::Method::POST);
+    ///
+    /// let client = reqwest::Client::new();
+    /// # if false {
+    /// let res = client.execute(req).await?;
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the command contains an unsupported option, has
+    /// no URL or an invalid one, or references a local file.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `curl-import` feature to be enabled.
+    #[cfg(feature = "curl-import")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "curl-import")))]
+    pub fn from_curl(command: &str) -> crate::Result<Request> {
+        crate::curl::parse(command, false)
+    }
+
+    /// Constructs a request from a curl command line, reading the local
+    /// files it references.
+    ///
+    /// This is like [`Request::from_curl()`], but files referenced with `@`
+    /// or `<` are read when the command is parsed. Only use this with
+    /// trusted commands: any file readable by the process can be sent.
+    ///
+    /// The files are read whole, with blocking `std::fs` calls, so avoid
+    /// large files and calling this from an async task that can't block.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the command contains an unsupported option, has
+    /// no URL or an invalid one, or references a file that can't be read.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `curl-import` feature to be enabled.
+    #[cfg(feature = "curl-import")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "curl-import")))]
+    pub fn from_curl_with_files(command: &str) -> crate::Result<Request> {
+        crate::curl::parse(command, true)
+    }
+
+    /// Format the request as an equivalent curl command.
+    ///
+    /// This is meant for bug reports and support tickets. The command can
+    /// be pasted into a POSIX shell, and includes the method, URL, headers,
+    /// timeout and body of the request.
+    ///
+    /// The values of sensitive headers, such as `Authorization` and
+    /// `Cookie` or any marked sensitive, and the password of the URL are
+    /// replaced with `[REDACTED]`. Text bodies are inlined, while binary
+    /// and streaming bodies are read from a `body.bin` file, which has to
+    /// be written separately.
+    ///
+    /// Headers added by the `Client`, such as default headers, aren't
+    /// included.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "rustls-tls-no-provider"))]
+    /// # {
+    /// let req = reqwest::Client::new()
+    ///     .post("https://httpbin.org/post")
+    ///     .bearer_auth("secret")
+    ///     .body("it's here")
+    ///     .build()?;
+    /// assert_eq!(
+    ///     req.to_curl(),
+    ///     r"curl -X POST https://httpbin.org/post -H 'authorization: [REDACTED]' --data-raw 'it'\''s here'",
+    /// );
+    /// # }
+    /// # Ok::<(), reqwest::Error>(())
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `curl-debug` feature to be enabled.
+    #[cfg(feature = "curl-debug")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "curl-debug")))]
+    pub fn to_curl(&self) -> String {
+        crate::curl_debug::format(crate::curl_debug::Parts {
+            method: self.method(),
+            url: self.url(),
+            version: self.version(),
+            headers: self.headers(),
+            body: self.body().map(|body| body.as_bytes()),
+            timeout: self.timeout().copied(),
+        })
+    }
+
     /// Attempt to clone the request.
     ///
     /// `None` is returned if the request can not be cloned, i.e. if the body is a stream.
@@ -133,12 +405,36 @@ impl Request {
         };
         let mut req = Request::new(self.method().clone(), self.url().clone());
         *req.timeout_mut() = self.timeout().copied();
+        *req.max_redirects_mut() = self.max_redirects();
+        *req.hedge_after_mut() = self.hedge_after();
+        *req.expect_continue_mut() = self.expect_continue();
+        *req.accept_encoding_mut() = self.accept_encoding();
+        *req.max_upload_rate_mut() = self.max_upload_rate();
+        *req.max_download_rate_mut() = self.max_download_rate();
+        #[cfg(feature = "http2")]
+        {
+            *req.protocol_mut() = self.protocol.clone();
+        }
         *req.headers_mut() = self.headers().clone();
         *req.version_mut() = self.version();
+        *req.resolve_map_mut() = self.resolve_map.clone();
+        #[cfg(feature = "cookies")]
+        {
+            *req.cookie_store_mut() = self.cookie_store.clone();
+        }
+        *req.on_informational_mut() = self.on_informational().cloned();
         req.body = body;
         Some(req)
     }
 
+    pub(crate) fn on_informational(&self) -> Option<&OnInformational> {
+        self.on_informational.as_ref()
+    }
+
+    pub(crate) fn on_informational_mut(&mut self) -> &mut Option<OnInformational> {
+        &mut self.on_informational
+    }
+
     pub(super) fn pieces(
         self,
     ) -> (
@@ -147,6 +443,7 @@ impl Request {
         HeaderMap,
         Option<Body>,
         Option<Duration>,
+        Option<usize>,
         Version,
     ) {
         (
@@ -155,6 +452,7 @@ impl Request {
             self.headers,
             self.body,
             self.timeout,
+            self.max_redirects,
             self.version,
         )
     }
@@ -217,7 +515,7 @@ impl RequestBuilder {
                         }
                         req.headers_mut().append(key, value);
                     }
-                    Err(e) => error = Some(crate::error::builder(e.into())),
+                    Err(e) => error = Some(crate::error::invalid_header_value(key, e.into())),
                 },
                 Err(e) => error = Some(crate::error::builder(e.into())),
             };
@@ -270,6 +568,134 @@ impl RequestBuilder {
         self.header_sensitive(crate::header::AUTHORIZATION, header_value, true)
     }
 
+    /// Set the `Host` header, independently of the URL.
+    ///
+    /// The request is still sent to the host of the URL, but servers see
+    /// `host` instead. This is useful for gateways that route requests by a
+    /// virtual host name different from the address they're reached at,
+    /// such as a shared ingress reached by IP address.
+    ///
+    /// `host` must be a host name or IP address, with an optional port.
+    /// The header isn't sent to another host after a redirect.
+    ///
+    /// This only affects HTTP/1 requests: HTTP/2 and HTTP/3 send the host
+    /// of the URL as the `:authority` pseudo-header.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `host` isn't a valid host and port.
+    pub fn host_header(mut self, host: &str) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match crate::util::host_header_value(host) {
+                Ok(value) => {
+                    req.headers_mut().insert(crate::header::HOST, value);
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
+    /// Set the priority of the request (RFC 9218).
+    ///
+    /// This sets the `Priority` header, so that a server multiplexing many
+    /// requests on one connection can send the responses in order of
+    /// `urgency`, from 0, the highest, to 7, the lowest. The default urgency
+    /// is 3. With `incremental`, the server may interleave the response with
+    /// other ones of the same urgency, which suits bulk transfers that are
+    /// processed as they arrive.
+    ///
+    /// HTTP/2 `PRIORITY_UPDATE` frames aren't sent, so the priority can't be
+    /// changed once the request was sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let page = client
+    ///     .get("https://example.com/")
+    ///     .priority(0, false)
+    ///     .send();
+    /// let video = client
+    ///     .get("https://example.com/intro.mp4")
+    ///     .priority(5, true)
+    ///     .send();
+    /// # let _ = (page.await?, video.await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `urgency` is greater than 7.
+    pub fn priority(mut self, urgency: u8, incremental: bool) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match crate::util::priority_header_value(urgency, incremental) {
+                Ok(value) => {
+                    req.headers_mut()
+                        .insert(HeaderName::from_static("priority"), value);
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
+    /// Send the request only if the resource doesn't match `etag` anymore.
+    ///
+    /// This sets the `If-None-Match` header, to an entity tag like
+    /// `"xyzzy"` or `W/"xyzzy"` that was received in the `ETag` header of a
+    /// previous response, or to `*`. If the resource still matches, the
+    /// server answers with `304 Not Modified` and no body, see
+    /// [`Response::is_not_modified()`].
+    ///
+    /// Calling this more than once matches any of the entity tags.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::Client::new()
+    ///     .get("https://example.com/feed.xml")
+    ///     .if_none_match("\"xyzzy\"")
+    ///     .send()
+    ///     .await?;
+    /// if res.is_not_modified() {
+    ///     // use the stored copy
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn if_none_match<V>(self, etag: V) -> RequestBuilder
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.header(crate::header::IF_NONE_MATCH, etag)
+    }
+
+    /// Send the request only if the resource was modified after `time`.
+    ///
+    /// This sets the `If-Modified-Since` header, usually to the time in the
+    /// `Last-Modified` header of a previous response. Otherwise, the server
+    /// answers with `304 Not Modified` and no body. Servers ignore this
+    /// header when the request has an `If-None-Match` header.
+    pub fn if_modified_since(self, time: SystemTime) -> RequestBuilder {
+        self.header(
+            crate::header::IF_MODIFIED_SINCE,
+            httpdate::fmt_http_date(time),
+        )
+    }
+
     /// Set the request body.
     pub fn body<T: Into<Body>>(mut self, body: T) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -278,6 +704,142 @@ impl RequestBuilder {
         self
     }
 
+    /// Set the request body, and a `Content-Type` inferred from it.
+    ///
+    /// Bytes and strings are sniffed for common types, such as JSON, XML
+    /// and PNG. Paths are read from the filesystem, and their type is
+    /// inferred from the file extension. Anything unrecognized is sent as
+    /// `application/octet-stream`.
+    ///
+    /// The `Content-Type` is only set if the request doesn't have one yet.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// let res = client.post("http://httpbin.org/post")
+    ///     .body_with_inferred_type(r#"{"lang": "rust"}"#)
+    ///     .send().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a path can't be read.
+    pub fn body_with_inferred_type<T: IntoInferredBody>(mut self, body: T) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match body.into_inferred() {
+                Ok((body, mime)) => {
+                    if !req.headers().contains_key(CONTENT_TYPE) {
+                        req.headers_mut()
+                            .insert(CONTENT_TYPE, HeaderValue::from_static(mime));
+                    }
+                    *req.body_mut() = Some(body.into());
+                }
+                Err(err) => error = Some(crate::error::builder(err)),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
+    /// Send trailers after the request body.
+    ///
+    /// Trailers are header fields sent after the body, such as checksums
+    /// computed while streaming it. The `Trailer` header announcing their
+    /// names is added, unless it was already set. With HTTP/1.1, this also
+    /// means the body is sent with chunked transfer encoding.
+    ///
+    /// This wraps the current body, so it must be called after setting the
+    /// body. If no body was set, the trailers follow an empty body.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use reqwest::Error;
+    /// use reqwest::header::{HeaderMap, HeaderValue};
+    ///
+    /// # async fn run() -> Result<(), Error> {
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("x-checksum", HeaderValue::from_static("abc123"));
+    ///
+    /// let client = reqwest::Client::new();
+    /// let res = client.post("http://httpbin.org/post")
+    ///     .body("the body")
+    ///     .trailers(trailers)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn trailers(mut self, trailers: HeaderMap) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            if !trailers.is_empty() && !req.headers().contains_key(TRAILER) {
+                let mut names = Vec::new();
+                for name in trailers.keys() {
+                    names.push(name.as_str());
+                }
+                if let Ok(value) = HeaderValue::from_str(&names.join(", ")) {
+                    req.headers_mut().insert(TRAILER, value);
+                }
+            }
+            let body = req.body_mut().take().unwrap_or_else(Body::empty);
+            *req.body_mut() = Some(body.with_trailers(trailers));
+        }
+        self
+    }
+
+    /// Encode the request body with a custom content coding.
+    ///
+    /// The coding must have been registered with
+    /// [`ClientBuilder::content_codec()`](crate::ClientBuilder::content_codec),
+    /// and its codec must provide an encoder. The `Content-Encoding` header
+    /// is set, and `Content-Length` is removed.
+    ///
+    /// This encodes the current body, so it must be called after setting the
+    /// body. A body of bytes is encoded right away, and can still be sent
+    /// again when following redirects, while a streaming body is encoded as
+    /// it is sent.
+    ///
+    /// # Errors
+    ///
+    /// Sending the request fails with a builder error if no codec can encode
+    /// `encoding`, or if encoding a body of bytes fails.
+    pub fn encode_body(mut self, encoding: &str) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            let encoder = self.client.codec(encoding).and_then(|codec| codec.encoder());
+            match (encoder, HeaderValue::from_str(encoding)) {
+                (Some(encoder), Ok(value)) => {
+                    if let Some(body) = req.body_mut().take() {
+                        match body.transform(encoder) {
+                            Ok(body) => {
+                                *req.body_mut() = Some(body);
+                                req.headers_mut().insert(CONTENT_ENCODING, value);
+                                req.headers_mut().remove(CONTENT_LENGTH);
+                            }
+                            Err(err) => error = Some(crate::error::builder(err)),
+                        }
+                    }
+                }
+                _ => {
+                    error = Some(crate::error::builder(format!(
+                        "no content codec can encode {encoding:?}"
+                    )))
+                }
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
     /// Enables a request timeout.
     ///
     /// The timeout is applied from when the request starts connecting until the
@@ -290,6 +852,302 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets the maximum number of redirects to follow for this request.
+    ///
+    /// This behaves like [`redirect::Policy::limited()`], and replaces the
+    /// maximum of the `Client`'s policy. A custom policy still decides
+    /// whether to follow each redirect, but is stopped at this maximum.
+    ///
+    /// [`redirect::Policy::limited()`]: crate::redirect::Policy::limited
+    pub fn max_redirects(mut self, max: usize) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.max_redirects_mut() = Some(max);
+        }
+        self
+    }
+
+    /// Hedge this request: if no response arrived after `delay`, send a
+    /// second, identical request, and use whichever response arrives first.
+    /// The other request is cancelled.
+    ///
+    /// The second request may reach the server even if the first one
+    /// succeeds, so this must only be used for requests that are safe to
+    /// send twice. Unlike [`ClientBuilder::hedge_after()`], which only hedges
+    /// `GET` and `HEAD` requests, this applies to any method.
+    ///
+    /// Requests with a streaming body are never hedged, since the body can't
+    /// be sent twice.
+    ///
+    /// [`ClientBuilder::hedge_after()`]: crate::ClientBuilder::hedge_after
+    pub fn hedge_after(mut self, delay: Duration) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.hedge_after_mut() = Some(delay);
+        }
+        self
+    }
+
+    /// Send `Expect: 100-continue`, and hold back the body until the server
+    /// answers `100 Continue`, or `timeout` elapses.
+    ///
+    /// This lets a server reject a large upload based on its headers, like
+    /// with `401 Unauthorized` or `413 Content Too Large`, before the body is
+    /// sent. If a final response arrives first, the body is never sent, and
+    /// that response is returned. Since servers that don't know about
+    /// `Expect` never answer `100 Continue`, the body is sent anyway once
+    /// `timeout` elapses.
+    ///
+    /// Requests without a body are sent as usual. Only HTTP/1 servers can
+    /// answer `100 Continue`, with HTTP/2 and HTTP/3 the body waits for the
+    /// timeout, or a final response.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # async fn run(video: Vec<u8>) -> Result<(), reqwest::Error> {
+    /// let res = reqwest::Client::new()
+    ///     .put("https://example.com/upload")
+    ///     .body(video)
+    ///     .expect_continue(Duration::from_secs(1))
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn expect_continue(mut self, timeout: Duration) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.expect_continue_mut() = Some(timeout);
+        }
+        self
+    }
+
+    /// Limits the rate at which the body of this request is sent, in bytes
+    /// per second.
+    ///
+    /// This overrides the
+    /// [`ClientBuilder::max_upload_rate()`](crate::ClientBuilder::max_upload_rate)
+    /// of the client, and a rate of 0 removes its limit for this request.
+    pub fn max_upload_rate(mut self, bytes_per_sec: u64) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.max_upload_rate_mut() = Some(bytes_per_sec);
+        }
+        self
+    }
+
+    /// Limits the rate at which the response body is received, in bytes
+    /// per second.
+    ///
+    /// This overrides the
+    /// [`ClientBuilder::max_download_rate()`](crate::ClientBuilder::max_download_rate)
+    /// of the client, and a rate of 0 removes its limit for this request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// // at most 1 MiB/s
+    /// let backup = reqwest::Client::new()
+    ///     .get("https://example.com/backup.tar")
+    ///     .max_download_rate(1024 * 1024)
+    ///     .send()
+    ///     .await?
+    ///     .bytes()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_download_rate(mut self, bytes_per_sec: u64) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.max_download_rate_mut() = Some(bytes_per_sec);
+        }
+        self
+    }
+
+    /// Don't add the `Accept-Encoding` header of the client to this request.
+    ///
+    /// The server then sends the response without a content coding, unless
+    /// the request has its own `Accept-Encoding` header. This is useful for
+    /// resources that are already compressed, like archives, or when the
+    /// encoded length of a response matters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let archive = reqwest::Client::new()
+    ///     .get("https://example.com/release.tar.gz")
+    ///     .no_accept_encoding()
+    ///     .send()
+    ///     .await?
+    ///     .bytes()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn no_accept_encoding(mut self) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.accept_encoding_mut() = false;
+        }
+        self
+    }
+
+    /// Send an HTTP/2 extended CONNECT request for `protocol` (RFC 8441).
+    ///
+    /// This sets the `:protocol` pseudo-header of a `CONNECT` request, such
+    /// as `websocket` for WebSockets over HTTP/2. Once the server answers
+    /// with `200 OK`, [`Response::upgrade()`](crate::Response::upgrade)
+    /// returns the stream, which shares its connection with other requests.
+    ///
+    /// The request must be sent over HTTP/2, and the server must have enabled
+    /// extended CONNECT in its settings, otherwise it fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::Client::builder()
+    ///     .http2_prior_knowledge()
+    ///     .build()?
+    ///     .request(reqwest::Method::CONNECT, "http://localhost:8080/chat")
+    ///     .protocol("websocket")
+    ///     .header("sec-websocket-version", "13")
+    ///     .send()
+    ///     .await?;
+    /// let stream = res.upgrade().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn protocol(mut self, protocol: impl Into<String>) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.protocol_mut() = Some(protocol.into());
+        }
+        self
+    }
+
+    /// Override DNS resolution for specific domains, for this request only.
+    ///
+    /// The overrides take precedence over those of
+    /// [`ClientBuilder::resolve_to_addrs()`](crate::ClientBuilder::resolve_to_addrs),
+    /// and also apply to redirects. As with those, the port of the URL is
+    /// used instead of the port of the addresses. Requests sent through a
+    /// proxy aren't affected.
+    ///
+    /// The connections to the overridden addresses are kept in a separate
+    /// pool of the `Client`, one for each distinct map, so that they're
+    /// never used for other requests.
+    ///
+    /// ```
+    /// # use reqwest::Error;
+    /// # use std::collections::HashMap;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let client = reqwest::Client::new();
+    /// let mut green = HashMap::new();
+    /// green.insert("api.example.com".to_owned(), vec!["10.0.0.2:0".parse().unwrap()]);
+    /// let res = client
+    ///     .get("https://api.example.com/status")
+    ///     .resolve_map(green)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_map(mut self, overrides: HashMap<String, Vec<SocketAddr>>) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            let overrides = overrides
+                .into_iter()
+                .map(|(domain, addrs)| (domain.to_ascii_lowercase(), addrs))
+                .collect();
+            *req.resolve_map_mut() = Some(overrides);
+        }
+        self
+    }
+
+    /// Call a function with each informational (`1xx`) response that is
+    /// received before the final response, such as `103 Early Hints`.
+    ///
+    /// The function gets the status and headers of the interim response.
+    /// With `103 Early Hints`, a client can start fetching the resources in
+    /// its `Link` headers while the server is still preparing the final
+    /// response. `101 Switching Protocols` is a final response, and isn't
+    /// passed to the function.
+    ///
+    /// Informational responses are only reported for HTTP/1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let res = client
+    ///     .get("https://example.com/")
+    ///     .on_informational(|status, headers| {
+    ///         if status.as_u16() == 103 {
+    ///             for link in headers.get_all(reqwest::header::LINK) {
+    ///                 println!("preload: {link:?}");
+    ///             }
+    ///         }
+    ///     })
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_informational<F>(mut self, f: F) -> RequestBuilder
+    where
+        F: Fn(StatusCode, &HeaderMap) + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.on_informational_mut() = Some(Arc::new(f));
+        }
+        self
+    }
+
+    /// Use a cookie store for this request, instead of the one of the
+    /// `Client`.
+    ///
+    /// The cookies of the store are sent with the request and its
+    /// redirects, and the cookies set by the responses are saved in it. This
+    /// lets one `Client`, and its connection pool, serve several sessions
+    /// concurrently, each with its own [`Jar`](crate::cookie::Jar).
+    ///
+    /// A store can be used for a request even if the `Client` has none.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use reqwest::cookie::Jar;
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::Client::new();
+    /// let alice = Arc::new(Jar::default());
+    /// let res = client
+    ///     .get("https://example.com/account")
+    ///     .cookie_jar(alice.clone())
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cookies` feature to be enabled.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    pub fn cookie_jar<C: crate::cookie::CookieStore + 'static>(
+        mut self,
+        cookie_store: Arc<C>,
+    ) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.cookie_store_mut() = Some(cookie_store as _);
+        }
+        self
+    }
+
     /// Sends a multipart/form-data body.
     ///
     /// ```
@@ -435,13 +1293,20 @@ impl RequestBuilder {
     /// # Errors
     ///
     /// Serialization can fail if `T`'s implementation of `Serialize` decides to
-    /// fail, or if `T` contains a map with non-string keys.
+    /// fail, or if `T` contains a map with non-string keys. With
+    /// [`ClientBuilder::canonical_json()`](crate::ClientBuilder::canonical_json),
+    /// it also fails if `T` contains an integer beyond ±2^53.
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> RequestBuilder {
         let mut error = None;
         if let Ok(ref mut req) = self.request {
-            match serde_json::to_vec(json) {
+            let body = if self.client.canonical_json() {
+                crate::json_canonical::to_vec(json)
+            } else {
+                serde_json::to_vec(json).map_err(crate::error::builder)
+            };
+            match body {
                 Ok(body) => {
                     if !req.headers().contains_key(CONTENT_TYPE) {
                         req.headers_mut()
@@ -449,7 +1314,7 @@ impl RequestBuilder {
                     }
                     *req.body_mut() = Some(body.into());
                 }
-                Err(err) => error = Some(crate::error::builder(err)),
+                Err(err) => error = Some(err),
             }
         }
         if let Some(err) = error {
@@ -514,6 +1379,43 @@ impl RequestBuilder {
         }
     }
 
+    /// Constructs the Request and returns a `Stream` of `Response`s that
+    /// follows `rel="next"` links in the `Link` header of each response.
+    ///
+    /// Each page is requested with the same method, headers and body as the
+    /// first, only the URL changes. The stream ends when a response has no
+    /// `next` link, or the link points to an already fetched page. Use
+    /// [`Pages::max_pages()`](crate::link::Pages::max_pages) to limit the number
+    /// of requests.
+    ///
+    /// # Errors
+    ///
+    /// Each item fails as `send()` would. After an error the stream ends. The
+    /// request body must be reusable (not a stream), since it is sent for
+    /// every page.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let mut pages = reqwest::Client::new()
+    ///     .get("https://api.github.com/repos/seanmonstar/reqwest/issues")
+    ///     .paginate()
+    ///     .max_pages(5);
+    ///
+    /// while let Some(page) = pages.next().await {
+    ///     let page = page?;
+    ///     println!("{}", page.url());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn paginate(self) -> crate::link::Pages {
+        crate::link::Pages::new(self.client, self.request)
+    }
+
     /// Attempt to clone the RequestBuilder.
     ///
     /// `None` is returned if the RequestBuilder can not be cloned,
@@ -620,7 +1522,19 @@ where
             headers,
             body: Some(body.into()),
             timeout: None,
+            max_redirects: None,
+            hedge_after: None,
+            expect_continue: None,
+            accept_encoding: true,
+            max_upload_rate: None,
+            max_download_rate: None,
+            #[cfg(feature = "http2")]
+            protocol: None,
             version,
+            resolve_map: None,
+            #[cfg(feature = "cookies")]
+            cookie_store: None,
+            on_informational: None,
         })
     }
 }
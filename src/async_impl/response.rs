@@ -1,10 +1,13 @@
+use std::borrow::Cow;
 use std::fmt;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::Bytes;
 use http_body_util::BodyExt;
+use hyper::header::HeaderName;
 use hyper::{HeaderMap, StatusCode, Version};
 use hyper_util::client::legacy::connect::HttpInfo;
 #[cfg(feature = "json")]
@@ -22,7 +25,6 @@ use crate::cookie;
 
 #[cfg(feature = "charset")]
 use encoding_rs::{Encoding, UTF_8};
-#[cfg(feature = "charset")]
 use mime::Mime;
 
 /// A Response to a submitted `Request`.
@@ -31,6 +33,8 @@ pub struct Response {
     // Boxed to save space (11 words to 1 word), and it's not accessed
     // frequently internally.
     url: Box<Url>,
+    // Trailers seen while reading the body with `chunk()`.
+    trailers: Option<Box<HeaderMap>>,
 }
 
 impl Response {
@@ -42,8 +46,8 @@ impl Response {
         read_timeout: Option<Duration>,
     ) -> Response {
         let (mut parts, body) = res.into_parts();
-        let decoder = Decoder::detect(
-            &mut parts.headers,
+        let decoder = decode(
+            &mut parts,
             super::body::response(body, total_timeout, read_timeout),
             accepts,
         );
@@ -52,6 +56,7 @@ impl Response {
         Response {
             res,
             url: Box::new(url),
+            trailers: None,
         }
     }
 
@@ -61,6 +66,16 @@ impl Response {
         self.res.status()
     }
 
+    /// Returns true if the status is `304 Not Modified`.
+    ///
+    /// This is the answer to a conditional request, like one sent with
+    /// [`RequestBuilder::if_none_match()`](crate::RequestBuilder::if_none_match),
+    /// when the resource hasn't changed. The response has no body, and the
+    /// previously received content is still current.
+    pub fn is_not_modified(&self) -> bool {
+        self.res.status() == StatusCode::NOT_MODIFIED
+    }
+
     /// Get the HTTP `Version` of this `Response`.
     #[inline]
     pub fn version(&self) -> Version {
@@ -73,6 +88,30 @@ impl Response {
         self.res.headers()
     }
 
+    /// Get the `Headers` of this `Response`, with values decoded as strings.
+    ///
+    /// Header values are usually ASCII, but some servers send other bytes,
+    /// for which `HeaderValue::to_str()` fails. This decodes each value as
+    /// UTF-8, with invalid sequences replaced by the REPLACEMENT CHARACTER.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::get("http://httpbin.org/get").await?;
+    /// for (name, value) in res.headers_lossy() {
+    ///     println!("{name}: {value}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn headers_lossy(&self) -> impl Iterator<Item = (&HeaderName, Cow<'_, str>)> + '_ {
+        self.res
+            .headers()
+            .iter()
+            .map(|(name, value)| (name, String::from_utf8_lossy(value.as_bytes())))
+    }
+
     /// Get a mutable reference to the `Headers` of this `Response`.
     #[inline]
     pub fn headers_mut(&mut self) -> &mut HeaderMap {
@@ -85,13 +124,41 @@ impl Response {
     ///
     /// - The server didn't send a `content-length` header.
     /// - The response is compressed and automatically decoded (thus changing
-    ///   the actual decoded length).
+    ///   the actual decoded length). See [`compressed_length()`] for the
+    ///   length the server sent.
+    ///
+    /// [`compressed_length()`]: Response::compressed_length
     pub fn content_length(&self) -> Option<u64> {
         use hyper::body::Body;
 
         Body::size_hint(self.res.body()).exact()
     }
 
+    /// Get the content coding of the response, like `gzip`, if the body is
+    /// automatically decoded.
+    ///
+    /// The `Content-Encoding` header is removed from the response when the
+    /// body is decoded, so this is the only way to tell what it was.
+    pub fn content_encoding(&self) -> Option<&str> {
+        self.res
+            .extensions()
+            .get::<DecodedContent>()
+            .map(|decoded| &*decoded.encoding)
+    }
+
+    /// Get the `Content-Length` of the response before it was automatically
+    /// decoded, if the server sent one.
+    ///
+    /// This is the number of bytes actually transferred, which is useful for
+    /// progress bars and bandwidth accounting when the decoded length isn't
+    /// known.
+    pub fn compressed_length(&self) -> Option<u64> {
+        self.res
+            .extensions()
+            .get::<DecodedContent>()
+            .and_then(|decoded| decoded.length)
+    }
+
     /// Retrieve the cookies contained in the response.
     ///
     /// Note that invalid 'Set-Cookie' headers will be ignored.
@@ -111,6 +178,81 @@ impl Response {
         &self.url
     }
 
+    /// Parse the `Link` headers of this `Response`.
+    ///
+    /// Relative link targets are resolved against the response `Url`.
+    /// Malformed links are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::get("https://api.github.com/repos/seanmonstar/reqwest/issues").await?;
+    /// if let Some(next) = res.links().iter().find(|link| link.has_rel("next")) {
+    ///     println!("next page: {}", next.url());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn links(&self) -> Vec<crate::link::Link> {
+        crate::link::parse(self.res.headers(), &self.url)
+    }
+
+    /// Get the header lines of this `Response`, in the order and casing they
+    /// were received.
+    ///
+    /// This is only available for HTTP/1 responses, when the `Client` was
+    /// built with
+    /// [`http1_preserve_raw_headers()`](crate::ClientBuilder::http1_preserve_raw_headers).
+    pub fn raw_headers(&self) -> Option<&crate::raw_headers::RawHeaders> {
+        self.res.extensions().get()
+    }
+
+    /// Parse the `Server-Timing` headers of this `Response`.
+    ///
+    /// Malformed metrics are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::get("https://example.com/").await?;
+    /// for metric in res.server_timing() {
+    ///     println!("{}: {:?}", metric.name(), metric.duration());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn server_timing(&self) -> Vec<crate::server_timing::Metric> {
+        crate::server_timing::parse(self.res.headers())
+    }
+
+    /// Parse the `Deprecation` and `Sunset` headers of this `Response`.
+    ///
+    /// Returns `None` if the endpoint didn't announce a deprecation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::get("https://api.example.com/v1/items").await?;
+    /// if let Some(sunset) = res.deprecation().and_then(|d| d.sunset()) {
+    ///     eprintln!("/v1/items goes away at {sunset:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deprecation(&self) -> Option<crate::notice::Deprecation> {
+        crate::notice::Deprecation::from_headers(self.res.headers())
+    }
+
+    /// Parse the `Warning` headers of this `Response`.
+    ///
+    /// Malformed warnings are ignored.
+    pub fn warnings(&self) -> Vec<crate::notice::Warning> {
+        crate::notice::Warning::from_headers(self.res.headers())
+    }
+
     /// Get the remote address used to get this `Response`.
     pub fn remote_addr(&self) -> Option<SocketAddr> {
         self.res
@@ -119,6 +261,68 @@ impl Response {
             .map(|info| info.remote_addr())
     }
 
+    /// Get the local address used to get this `Response`.
+    ///
+    /// This is the address of the local end of the connection the response
+    /// was received on, such as the ephemeral port chosen by the OS.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.res
+            .extensions()
+            .get::<HttpInfo>()
+            .map(|info| info.local_addr())
+    }
+
+    /// Get the number of attempts made to get this `Response`.
+    ///
+    /// This is `1`, plus the number of times a request was automatically
+    /// retried, such as after an HTTP/2 `REFUSED_STREAM`. Retries of any of
+    /// the requests in a redirect chain are counted.
+    pub fn attempts(&self) -> usize {
+        self.res
+            .extensions()
+            .get::<RequestCounts>()
+            .map(|counts| counts.retries + 1)
+            .unwrap_or(1)
+    }
+
+    /// Get the number of redirects followed to get this `Response`.
+    pub fn redirects_followed(&self) -> usize {
+        self.res
+            .extensions()
+            .get::<RequestCounts>()
+            .map(|counts| counts.redirects)
+            .unwrap_or(0)
+    }
+
+    /// Get the `Retry-After` delays the client waited for, in order.
+    ///
+    /// Waits are only taken when enabled with
+    /// [`ClientBuilder::retry_after()`](crate::ClientBuilder::retry_after),
+    /// and add to the latency of the request.
+    pub fn retry_after_waits(&self) -> &[crate::redirect::Wait] {
+        self.res
+            .extensions()
+            .get::<RetryAfterWaits>()
+            .map(|waits| &waits.0[..])
+            .unwrap_or(&[])
+    }
+
+    /// Get the headers sent with the request that got this `Response`.
+    ///
+    /// After redirects, these are the headers of the last request. They
+    /// include the default headers of the `Client`, cookies, and proxy
+    /// authorization, as well as the `Host`, `Content-Length` and
+    /// `Transfer-Encoding` headers added when sending an HTTP/1 request.
+    ///
+    /// This is useful to debug signature mismatches, or to audit what was
+    /// sent.
+    pub fn request_headers(&self) -> Option<&HeaderMap> {
+        self.res
+            .extensions()
+            .get::<RequestHeaders>()
+            .map(|headers| &headers.0)
+    }
+
     /// Returns a reference to the associated extensions.
     pub fn extensions(&self) -> &http::Extensions {
         self.res.extensions()
@@ -166,6 +370,7 @@ impl Response {
 
         #[cfg(not(feature = "charset"))]
         {
+            self.check_expected_content_type()?;
             let full = self.bytes().await?;
             let text = String::from_utf8_lossy(&full);
             Ok(text.into_owned())
@@ -204,6 +409,7 @@ impl Response {
     #[cfg(feature = "charset")]
     #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
     pub async fn text_with_charset(self, default_encoding: &str) -> crate::Result<String> {
+        self.check_expected_content_type()?;
         let content_type = self
             .headers()
             .get(crate::header::CONTENT_TYPE)
@@ -265,11 +471,57 @@ impl Response {
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub async fn json<T: DeserializeOwned>(self) -> crate::Result<T> {
+        self.check_expected_content_type()?;
         let full = self.bytes().await?;
 
         serde_json::from_slice(&full).map_err(crate::error::decode)
     }
 
+    /// Try to deserialize the response body as JSON, reporting where in the
+    /// document deserialization failed.
+    ///
+    /// This is like [`json()`](Response::json), but if the body cannot be
+    /// deserialized to `T`, the message of the error's source starts with
+    /// the path of the value that didn't match, such as `data.items[3].id`,
+    /// which makes schema mismatches in large documents much easier to find.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Ip {
+    ///     origin: String,
+    /// }
+    ///
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let ip = reqwest::get("http://httpbin.org/ip")
+    ///     .await?
+    ///     .json_with_path::<Ip>()
+    ///     .await?;
+    ///
+    /// println!("ip: {}", ip.origin);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not in JSON format,
+    /// or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn json_with_path<T: DeserializeOwned>(self) -> crate::Result<T> {
+        self.check_expected_content_type()?;
+        let full = self.bytes().await?;
+
+        crate::util::json_with_path(&full)
+    }
+
     /// Get the full response body as `Bytes`.
     ///
     /// # Example
@@ -316,8 +568,13 @@ impl Response {
         loop {
             if let Some(res) = self.res.body_mut().frame().await {
                 let frame = res?;
-                if let Ok(buf) = frame.into_data() {
-                    return Ok(Some(buf));
+                match frame.into_data() {
+                    Ok(buf) => return Ok(Some(buf)),
+                    Err(frame) => {
+                        if let Ok(trailers) = frame.into_trailers() {
+                            self.trailers = Some(Box::new(trailers));
+                        }
+                    }
                 }
                 // else continue
             } else {
@@ -326,6 +583,42 @@ impl Response {
         }
     }
 
+    /// Get the trailers of this `Response`.
+    ///
+    /// Trailers are header fields sent after the body. This waits until the
+    /// whole body has been received, discarding any of it that hasn't been
+    /// read yet with [`Response::chunk()`], and returns the trailers, if the
+    /// server sent any.
+    ///
+    /// Note that HTTP/1.1 servers usually only send trailers if the request
+    /// included a `TE: trailers` header. Trailers are not available if the
+    /// body was decompressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut res = reqwest::Client::new()
+    ///     .get("https://hyper.rs")
+    ///     .header("te", "trailers")
+    ///     .send()
+    ///     .await?;
+    ///
+    /// while let Some(chunk) = res.chunk().await? {
+    ///     println!("Chunk: {chunk:?}");
+    /// }
+    ///
+    /// if let Some(trailers) = res.trailers().await? {
+    ///     println!("Trailers: {trailers:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn trailers(&mut self) -> crate::Result<Option<&HeaderMap>> {
+        while self.chunk().await?.is_some() {}
+        Ok(self.trailers.as_deref())
+    }
+
     /// Convert the response into a `Stream` of `Bytes` from the body.
     ///
     /// # Example
@@ -354,6 +647,137 @@ impl Response {
         super::body::DataStream(self.res.into_body())
     }
 
+    /// Split a `multipart/*` response body into its parts.
+    ///
+    /// Servers answer a `GET` for several ranges with a
+    /// `multipart/byteranges` body, and some APIs send `multipart/mixed`
+    /// bodies. The parts are yielded as they are received, each with its own
+    /// headers.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the `Content-Type` of the response isn't
+    /// multipart with a boundary. The stream yields an error if the body
+    /// isn't valid multipart, or ends before its closing delimiter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use futures_util::StreamExt;
+    ///
+    /// let res = reqwest::Client::new()
+    ///     .get("http://httpbin.org/range/100")
+    ///     .header("range", "bytes=0-9, 50-59")
+    ///     .send()
+    ///     .await?;
+    /// let mut parts = res.multipart()?;
+    /// while let Some(part) = parts.next().await {
+    ///     let part = part?;
+    ///     println!("{:?}: {:?}", part.content_range(), part.body());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `multipart` feature to be enabled.
+    #[cfg(feature = "multipart")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    pub fn multipart(self) -> crate::Result<crate::multipart::Parts> {
+        let content_type = self.headers().get(crate::header::CONTENT_TYPE).cloned();
+        crate::multipart::Parts::new(
+            content_type.as_ref(),
+            Box::pin(super::body::DataStream(self.res.into_body())),
+        )
+    }
+
+    /// Verify the body against a digest while it's read.
+    ///
+    /// `expected` is the digest of the body as it's returned, after it was
+    /// decompressed. Reading the body fails at its end if the digest doesn't
+    /// match, with an error for which
+    /// [`Error::is_digest_mismatch()`](crate::Error::is_digest_mismatch)
+    /// returns true. Any part of the body that was read before can't be
+    /// trusted then.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use reqwest::digest::Algorithm;
+    ///
+    /// # async fn run(expected: &[u8]) -> Result<(), reqwest::Error> {
+    /// let mut res = reqwest::get("https://example.com/release.tar.gz")
+    ///     .await?
+    ///     .verify_digest(Algorithm::Sha256, expected);
+    /// while let Some(chunk) = res.chunk().await? {
+    ///     // write the chunk to a temporary file
+    /// }
+    /// // the temporary file has the expected content
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `digest` feature to be enabled.
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    pub fn verify_digest(
+        self,
+        algorithm: crate::digest::Algorithm,
+        expected: impl AsRef<[u8]>,
+    ) -> Response {
+        let expected = expected.as_ref().to_vec();
+        let res = self.res.map(|body| {
+            Decoder::plain_text(super::body::boxed(crate::digest::Verify::new(
+                body, algorithm, expected,
+            )))
+        });
+        Response { res, ..self }
+    }
+
+    /// Get the full response body, keeping it in memory if it's at most
+    /// `max_in_memory` bytes, or writing it to a temporary file otherwise.
+    ///
+    /// This lets services with little memory handle the occasional huge
+    /// response. The returned [`SpilledBody`](crate::SpilledBody) can be
+    /// read as `Bytes` or as a stream either way, and removes its temporary
+    /// file when dropped.
+    ///
+    /// The file is created in [`std::env::temp_dir()`], as soon as the
+    /// `Content-Length`, or the body read so far, exceeds `max_in_memory`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let body = reqwest::get("http://httpbin.org/bytes/4096")
+    ///     .await?
+    ///     .bytes_spilled(64 * 1024)
+    ///     .await?;
+    ///
+    /// println!("spilled to disk: {}", !body.is_in_memory());
+    /// let bytes = body.bytes().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if reading the body fails, or if the temporary
+    /// file can't be written.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub async fn bytes_spilled(self, max_in_memory: usize) -> crate::Result<crate::SpilledBody> {
+        super::spill::read(self, max_in_memory).await
+    }
+
     // util methods
 
     /// Turn a response into an error if the server returned an error.
@@ -386,6 +810,51 @@ impl Response {
         }
     }
 
+    /// Turn a response into an error if the server returned an error,
+    /// keeping up to `limit` bytes of its body in the error.
+    ///
+    /// APIs often explain an error in the body, which is lost with
+    /// [`error_for_status()`](Response::error_for_status). The start of the
+    /// body is available from
+    /// [`Error::status_body()`](crate::Error::status_body), and is shown
+    /// when displaying the source of the error. A body that fails to be
+    /// read is kept up to the failure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::get("http://httpbin.org/status/404").await?;
+    /// if let Err(err) = res.error_for_status_with_body(1024).await {
+    ///     let body = err.status_body().unwrap_or_default();
+    ///     eprintln!("{err}: {}", String::from_utf8_lossy(body));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn error_for_status_with_body(mut self, limit: usize) -> crate::Result<Self> {
+        let status = self.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(self);
+        }
+
+        let mut body = Vec::new();
+        while body.len() < limit {
+            match self.chunk().await {
+                Ok(Some(chunk)) => {
+                    let n = chunk.len().min(limit - body.len());
+                    body.extend_from_slice(&chunk[..n]);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+        Err(crate::error::status_code_with_body(
+            *self.url,
+            status,
+            body.into(),
+        ))
+    }
+
     /// Turn a reference to a response into an error if the server returned an error.
     ///
     /// # Example
@@ -416,8 +885,66 @@ impl Response {
         }
     }
 
+    /// Turn a response into an error if its `Content-Type` doesn't match
+    /// `expected`.
+    ///
+    /// Only the type and subtype are compared, ignoring case and parameters
+    /// like `charset`. The expected type may use a `*` wildcard, like
+    /// `text/*`, and a structured syntax suffix matches too: `application/json`
+    /// accepts `application/problem+json`. A response without a
+    /// `Content-Type` never matches.
+    ///
+    /// The error is a decode error, for which
+    /// [`Error::is_unexpected_content_type()`](crate::Error::is_unexpected_content_type)
+    /// returns true, and whose message includes the actual content type and
+    /// the URL. An `expected` type that isn't valid is a builder error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data: serde_json::Value = reqwest::get("http://httpbin.org/json")
+    ///     .await?
+    ///     .error_for_status()?
+    ///     .ensure_content_type("application/json")?
+    ///     .json()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ensure_content_type(self, expected: &str) -> crate::Result<Self> {
+        let expected = expected.parse::<Mime>().map_err(crate::error::builder)?;
+        self.check_content_type(&expected)?;
+        Ok(self)
+    }
+
     // private
 
+    fn check_content_type(&self, expected: &Mime) -> crate::Result<()> {
+        let actual = self.headers().get(crate::header::CONTENT_TYPE);
+        let matches = actual
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .map_or(false, |actual| content_type_matches(expected, &actual));
+        if matches {
+            Ok(())
+        } else {
+            Err(crate::error::unexpected_content_type(
+                expected,
+                actual.cloned(),
+                *self.url.clone(),
+            ))
+        }
+    }
+
+    /// Checks the content type set with `ClientBuilder::expect_content_type()`.
+    fn check_expected_content_type(&self) -> crate::Result<()> {
+        match self.res.extensions().get::<ExpectedContentType>() {
+            Some(ExpectedContentType(expected)) => self.check_content_type(expected),
+            None => Ok(()),
+        }
+    }
+
     // The Response's body is an implementation detail.
     // You no longer need to get a reference to it, there are async methods
     // on the `Response` itself.
@@ -446,6 +973,68 @@ impl From<Response> for Body {
     }
 }
 
+/// How often the client retried and redirected, stored in the extensions.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RequestCounts {
+    pub(crate) retries: usize,
+    pub(crate) redirects: usize,
+}
+
+/// The `Retry-After` delays waited for, stored in the extensions.
+#[derive(Clone, Debug)]
+pub(crate) struct RetryAfterWaits(pub(crate) Vec<crate::redirect::Wait>);
+
+/// The headers sent with the last request, stored in the extensions.
+#[derive(Clone, Debug)]
+pub(crate) struct RequestHeaders(pub(crate) HeaderMap);
+
+/// The content coding and length of an automatically decoded body, whose
+/// headers were removed, stored in the extensions.
+#[derive(Clone, Debug)]
+struct DecodedContent {
+    encoding: String,
+    length: Option<u64>,
+}
+
+/// Wraps `body` in the decoder of its content coding, and remembers what
+/// the removed headers were.
+fn decode(parts: &mut http::response::Parts, body: ResponseBody, accepts: Accepts) -> Decoder {
+    use http::header::{CONTENT_ENCODING, CONTENT_LENGTH};
+
+    let encoding = parts
+        .headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let length = parts
+        .headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    let decoder = Decoder::detect(&mut parts.headers, body, accepts);
+    if let Some(encoding) = encoding {
+        if !parts.headers.contains_key(CONTENT_ENCODING) {
+            parts
+                .extensions
+                .insert(DecodedContent { encoding, length });
+        }
+    }
+    decoder
+}
+
+/// The content type expected by the `Client`, checked by `json()` and `text()`.
+#[derive(Clone)]
+pub(crate) struct ExpectedContentType(pub(crate) Arc<Mime>);
+
+fn content_type_matches(expected: &Mime, actual: &Mime) -> bool {
+    let type_matches = expected.type_() == mime::STAR || expected.type_() == actual.type_();
+    let subtype_matches = expected.subtype() == mime::STAR
+        || expected.subtype() == actual.subtype()
+        || actual.suffix() == Some(expected.subtype());
+    type_matches && subtype_matches
+}
+
 // I'm not sure this conversion is that useful... People should be encouraged
 // to use `http::Response`, not `reqwest::Response`.
 impl<T: Into<Body>> From<http::Response<T>> for Response {
@@ -454,8 +1043,8 @@ impl<T: Into<Body>> From<http::Response<T>> for Response {
 
         let (mut parts, body) = r.into_parts();
         let body: crate::async_impl::body::Body = body.into();
-        let decoder = Decoder::detect(
-            &mut parts.headers,
+        let decoder = decode(
+            &mut parts,
             ResponseBody::new(body.map_err(Into::into)),
             Accepts::none(),
         );
@@ -468,6 +1057,7 @@ impl<T: Into<Body>> From<http::Response<T>> for Response {
         Response {
             res,
             url: Box::new(url),
+            trailers: None,
         }
     }
 }
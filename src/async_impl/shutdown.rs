@@ -0,0 +1,162 @@
+//! State for `Client::shutdown()`.
+//!
+//! Requests hold an `InFlight` guard until their response body is done. With
+//! `close_connections_on_shutdown`, every connection is also wrapped with a
+//! `ConnWatch`, which makes it report EOF once the client has closed its
+//! connections.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::task::AtomicWaker;
+use http_body::Body as HttpBody;
+use pin_project_lite::pin_project;
+
+#[derive(Default)]
+pub(crate) struct Shutdown {
+    closed: AtomicBool,
+    conns_closed: AtomicBool,
+    in_flight: AtomicUsize,
+    idle_waiters: Mutex<Vec<Waker>>,
+    conns: Mutex<Vec<Weak<AtomicWaker>>>,
+}
+
+/// Marks a request as in flight until dropped.
+pub(crate) struct InFlight(Arc<Shutdown>);
+
+/// Lets a connection know when the client has closed its connections.
+pub(crate) struct ConnWatch {
+    shutdown: Arc<Shutdown>,
+    waker: Arc<AtomicWaker>,
+}
+
+pin_project! {
+    /// A response body that keeps its request in flight until it ends.
+    pub(crate) struct GuardedBody<B> {
+        #[pin]
+        inner: B,
+        guard: Option<InFlight>,
+    }
+}
+
+// ===== impl Shutdown =====
+
+impl Shutdown {
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Returns `None` if the client no longer accepts requests.
+    pub(crate) fn start_request(self: &Arc<Self>) -> Option<InFlight> {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        let guard = InFlight(self.clone());
+        if self.is_closed() {
+            // dropping the guard wakes `shutdown()` if this was the last one
+            return None;
+        }
+        Some(guard)
+    }
+
+    pub(crate) fn watch_conn(self: &Arc<Self>) -> ConnWatch {
+        let waker = Arc::new(AtomicWaker::new());
+        let mut conns = self.conns.lock().expect("lock poisoned");
+        conns.retain(|conn| conn.strong_count() > 0);
+        conns.push(Arc::downgrade(&waker));
+        ConnWatch {
+            shutdown: self.clone(),
+            waker,
+        }
+    }
+
+    pub(crate) async fn shutdown(&self, deadline: Duration) {
+        self.closed.store(true, Ordering::Release);
+
+        let idle = std::future::poll_fn(|cx| {
+            if self.in_flight.load(Ordering::Acquire) == 0 {
+                return Poll::Ready(());
+            }
+            self.idle_waiters
+                .lock()
+                .expect("lock poisoned")
+                .push(cx.waker().clone());
+            // check again, in case the last request finished meanwhile
+            if self.in_flight.load(Ordering::Acquire) == 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        });
+        if tokio::time::timeout(deadline, idle).await.is_err() {
+            log::debug!("shutdown deadline elapsed with requests in flight");
+        }
+
+        self.conns_closed.store(true, Ordering::Release);
+        let conns = std::mem::take(&mut *self.conns.lock().expect("lock poisoned"));
+        for conn in conns.iter().filter_map(Weak::upgrade) {
+            conn.wake();
+        }
+    }
+}
+
+// ===== impl InFlight =====
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let waiters = std::mem::take(&mut *self.0.idle_waiters.lock().expect("lock poisoned"));
+            for waker in waiters {
+                waker.wake();
+            }
+        }
+    }
+}
+
+// ===== impl ConnWatch =====
+
+impl ConnWatch {
+    /// Returns true if the connection should be closed.
+    ///
+    /// Otherwise, the task is woken when that changes.
+    pub(crate) fn poll_closed(&self, cx: &mut Context<'_>) -> bool {
+        self.waker.register(cx.waker());
+        self.shutdown.conns_closed.load(Ordering::Acquire)
+    }
+}
+
+// ===== impl GuardedBody =====
+
+pub(crate) fn guarded<B>(inner: B, guard: Option<InFlight>) -> GuardedBody<B> {
+    GuardedBody { inner, guard }
+}
+
+impl<B> HttpBody for GuardedBody<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper::body::Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = futures_core::ready!(this.inner.poll_frame(cx));
+        if !matches!(frame, Some(Ok(_))) {
+            this.guard.take();
+        }
+        Poll::Ready(frame)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
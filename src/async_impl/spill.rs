@@ -0,0 +1,166 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{StreamExt, TryStreamExt};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+use super::response::Response;
+
+/// A response body read by
+/// [`Response::bytes_spilled()`](crate::Response::bytes_spilled), either in
+/// memory or in a temporary file.
+///
+/// The temporary file is removed when the `SpilledBody`, or the stream it
+/// was turned into, is dropped.
+pub struct SpilledBody {
+    inner: Inner,
+}
+
+enum Inner {
+    Memory(Bytes),
+    File { path: TempPath, len: u64 },
+}
+
+/// Removes the file when dropped.
+struct TempPath(PathBuf);
+
+impl SpilledBody {
+    /// Returns true if the body is kept in memory.
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.inner, Inner::Memory(_))
+    }
+
+    /// Returns the path of the temporary file, if the body was spilled.
+    pub fn path(&self) -> Option<&Path> {
+        match self.inner {
+            Inner::Memory(_) => None,
+            Inner::File { ref path, .. } => Some(&path.0),
+        }
+    }
+
+    /// Returns the length of the body.
+    pub fn len(&self) -> u64 {
+        match self.inner {
+            Inner::Memory(ref bytes) => bytes.len() as u64,
+            Inner::File { len, .. } => len,
+        }
+    }
+
+    /// Returns true if the body is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the full body as `Bytes`, reading the temporary file if the body
+    /// was spilled.
+    pub async fn bytes(self) -> crate::Result<Bytes> {
+        match self.inner {
+            Inner::Memory(bytes) => Ok(bytes),
+            Inner::File { path, .. } => tokio::fs::read(&path.0)
+                .await
+                .map(Bytes::from)
+                .map_err(crate::error::body),
+        }
+    }
+
+    /// Convert the body into a `Stream` of `Bytes`.
+    pub fn into_stream(self) -> impl futures_core::Stream<Item = crate::Result<Bytes>> {
+        match self.inner {
+            Inner::Memory(bytes) => {
+                let chunk = if bytes.is_empty() { None } else { Some(Ok(bytes)) };
+                futures_util::stream::iter(chunk).left_stream()
+            }
+            Inner::File { path, .. } => {
+                let file = futures_util::stream::once(File::open(path.0.clone()));
+                file.map_ok(tokio_util::io::ReaderStream::new)
+                    .try_flatten()
+                    .map(move |chunk| {
+                        // removes the file once the stream is dropped
+                        let _path = &path;
+                        chunk.map_err(crate::error::body)
+                    })
+                    .right_stream()
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SpilledBody {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = f.debug_struct("SpilledBody");
+        f.field("len", &self.len());
+        if let Some(path) = self.path() {
+            f.field("path", &path);
+        }
+        f.finish()
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Reads the body of `res`, writing it to a temporary file once it's larger
+/// than `max_in_memory`.
+pub(super) async fn read(mut res: Response, max_in_memory: usize) -> crate::Result<SpilledBody> {
+    let mut buf = BytesMut::new();
+    let mut spilled = None;
+
+    if res.content_length().map_or(false, |len| len > max_in_memory as u64) {
+        spilled = Some(create().await.map_err(crate::error::body)?);
+    }
+
+    while let Some(chunk) = res.chunk().await? {
+        if spilled.is_none() && buf.len() + chunk.len() > max_in_memory {
+            let (path, mut file) = create().await.map_err(crate::error::body)?;
+            file.write_all(&buf).await.map_err(crate::error::body)?;
+            buf = BytesMut::new();
+            spilled = Some((path, file));
+        }
+        match spilled {
+            Some((_, ref mut file)) => file.write_all(&chunk).await.map_err(crate::error::body)?,
+            None => buf.extend_from_slice(&chunk),
+        }
+    }
+
+    let inner = match spilled {
+        Some((path, mut file)) => {
+            file.flush().await.map_err(crate::error::body)?;
+            let len = file.metadata().await.map_err(crate::error::body)?.len();
+            Inner::File { path, len }
+        }
+        None => Inner::Memory(buf.freeze()),
+    };
+    Ok(SpilledBody { inner })
+}
+
+/// Creates a new temporary file.
+async fn create() -> io::Result<(TempPath, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("reqwest-{}-{nanos:x}-{n}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        match tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(file) => return Ok((TempPath(path), file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use tokio::time::Sleep;
+
+use crate::error::BoxError;
+
+pin_project_lite::pin_project! {
+    /// A body that is read no faster than a rate of bytes per second.
+    ///
+    /// Chunks are split to what the budget allows, and the rest waits until
+    /// enough of the budget is back. The first second's worth of bytes is
+    /// sent without waiting.
+    pub(crate) struct Throttled<B> {
+        #[pin]
+        inner: B,
+        bucket: Bucket,
+        // the rest of a chunk that went over the budget
+        pending: Bytes,
+        sleep: Option<Pin<Box<Sleep>>>,
+    }
+}
+
+/// A token bucket, holding up to a second's worth of bytes.
+///
+/// Chunks are only split to a twentieth of the rate or more, so that a
+/// body isn't sent in tiny pieces.
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl<B> Throttled<B> {
+    /// Throttles `inner` to `rate` bytes per second, which must not be zero.
+    pub(crate) fn new(inner: B, rate: u64) -> Throttled<B> {
+        debug_assert!(rate > 0);
+        let rate = rate as f64;
+        Throttled {
+            inner,
+            bucket: Bucket {
+                rate,
+                tokens: rate,
+                refilled: Instant::now(),
+            },
+            pending: Bytes::new(),
+            sleep: None,
+        }
+    }
+}
+
+impl<B> HttpBody for Throttled<B>
+where
+    B: HttpBody<Data = Bytes>,
+    B::Error: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            if let Some(sleep) = this.sleep.as_mut() {
+                futures_core::ready!(sleep.as_mut().poll(cx));
+                *this.sleep = None;
+            }
+
+            if this.pending.is_empty() {
+                match futures_core::ready!(this.inner.as_mut().poll_frame(cx)) {
+                    Some(Ok(frame)) => match frame.into_data() {
+                        Ok(data) => *this.pending = data,
+                        // trailers aren't throttled
+                        Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                    },
+                    Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                    None => return Poll::Ready(None),
+                }
+                if this.pending.is_empty() {
+                    continue;
+                }
+            }
+
+            match this.bucket.take(this.pending.len()) {
+                Ok(len) => return Poll::Ready(Some(Ok(Frame::data(this.pending.split_to(len))))),
+                Err(wait) => *this.sleep = Some(Box::pin(tokio::time::sleep(wait))),
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_empty() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let pending = self.pending.len() as u64;
+        let hint = self.inner.size_hint();
+        let mut size_hint = SizeHint::new();
+        size_hint.set_lower(hint.lower() + pending);
+        if let Some(upper) = hint.upper() {
+            size_hint.set_upper(upper + pending);
+        }
+        size_hint
+    }
+}
+
+impl Bucket {
+    /// Takes tokens for up to `len` bytes, and returns how many bytes may be
+    /// sent, or how long to wait until enough tokens are back.
+    fn take(&mut self, len: usize) -> Result<usize, Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled = now;
+
+        let wanted = (len as f64).min((self.rate / 20.0).max(1.0));
+        if self.tokens < wanted {
+            return Err(Duration::from_secs_f64((wanted - self.tokens) / self.rate));
+        }
+        let len = len.min(self.tokens as usize);
+        self.tokens -= len as f64;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_splits_and_waits() {
+        let mut bucket = Bucket {
+            rate: 1000.0,
+            tokens: 1000.0,
+            refilled: Instant::now(),
+        };
+        assert_eq!(bucket.take(600), Ok(600));
+        assert_eq!(bucket.take(900), Ok(400));
+        let wait = bucket.take(500).unwrap_err();
+        assert!(wait > Duration::from_millis(45), "{wait:?}");
+        assert!(wait <= Duration::from_millis(50), "{wait:?}");
+        let wait = bucket.take(10).unwrap_err();
+        assert!(wait <= Duration::from_millis(10), "{wait:?}");
+
+        // refills no more than a second's worth
+        bucket.refilled -= Duration::from_secs(10);
+        assert_eq!(bucket.take(5000), Ok(1000));
+    }
+}
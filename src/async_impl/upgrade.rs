@@ -1,14 +1,79 @@
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 use std::{fmt, io};
 
+use futures_util::task::AtomicWaker;
 use futures_util::TryFutureExt;
 use hyper_util::rt::TokioIo;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
+use crate::Url;
+
 /// An upgraded HTTP connection.
 pub struct Upgraded {
     inner: TokioIo<hyper::upgrade::Upgraded>,
+    entry: Option<Arc<Entry>>,
+}
+
+/// A handle to an open upgraded connection of a `Client`.
+///
+/// Returned by [`Client::upgrades()`](crate::Client::upgrades), when
+/// [`ClientBuilder::track_upgrades()`](crate::ClientBuilder::track_upgrades)
+/// is enabled.
+#[derive(Clone)]
+pub struct UpgradeHandle {
+    entry: Arc<Entry>,
+}
+
+/// The open upgraded connections of a `Client`.
+#[derive(Default)]
+pub(crate) struct Registry {
+    next_id: AtomicU64,
+    entries: Mutex<Vec<Arc<Entry>>>,
+}
+
+/// Inserted in the extensions of responses, so that upgrades can be
+/// registered.
+#[derive(Clone)]
+pub(crate) struct Tracker(pub(crate) Arc<Registry>);
+
+struct Entry {
+    id: u64,
+    label: Option<String>,
+    url: Url,
+    opened: Instant,
+    // milliseconds since `opened`
+    last_active: AtomicU64,
+    closed: AtomicBool,
+    aborted: AtomicBool,
+    read_waker: AtomicWaker,
+    write_waker: AtomicWaker,
+}
+
+impl Upgraded {
+    /// Fails with `ConnectionAborted` once aborted through an `UpgradeHandle`,
+    /// or registers `waker` to be woken if it is.
+    fn check_aborted(&self, waker: &task::Waker, which: fn(&Entry) -> &AtomicWaker) -> io::Result<()> {
+        if let Some(ref entry) = self.entry {
+            which(entry).register(waker);
+            if entry.aborted.load(Ordering::Acquire) {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "upgraded connection aborted",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn touch<T>(&self, poll: &Poll<io::Result<T>>) {
+        if let (Some(entry), Poll::Ready(Ok(_))) = (&self.entry, poll) {
+            entry.touch();
+        }
+    }
 }
 
 impl AsyncRead for Upgraded {
@@ -17,7 +82,10 @@ impl AsyncRead for Upgraded {
         cx: &mut task::Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.inner).poll_read(cx, buf)
+        self.check_aborted(cx.waker(), |entry| &entry.read_waker)?;
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        self.touch(&poll);
+        poll
     }
 }
 
@@ -27,7 +95,10 @@ impl AsyncWrite for Upgraded {
         cx: &mut task::Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.inner).poll_write(cx, buf)
+        self.check_aborted(cx.waker(), |entry| &entry.write_waker)?;
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        self.touch(&poll);
+        poll
     }
 
     fn poll_write_vectored(
@@ -35,10 +106,14 @@ impl AsyncWrite for Upgraded {
         cx: &mut task::Context<'_>,
         bufs: &[io::IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        self.check_aborted(cx.waker(), |entry| &entry.write_waker)?;
+        let poll = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        self.touch(&poll);
+        poll
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.check_aborted(cx.waker(), |entry| &entry.write_waker)?;
         Pin::new(&mut self.inner).poll_flush(cx)
     }
 
@@ -53,7 +128,19 @@ impl AsyncWrite for Upgraded {
 
 impl fmt::Debug for Upgraded {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Upgraded").finish()
+        let mut f = f.debug_struct("Upgraded");
+        if let Some(ref entry) = self.entry {
+            f.field("id", &entry.id);
+        }
+        f.finish()
+    }
+}
+
+impl Drop for Upgraded {
+    fn drop(&mut self) {
+        if let Some(ref entry) = self.entry {
+            entry.closed.store(true, Ordering::Release);
+        }
     }
 }
 
@@ -61,6 +148,7 @@ impl From<hyper::upgrade::Upgraded> for Upgraded {
     fn from(inner: hyper::upgrade::Upgraded) -> Self {
         Upgraded {
             inner: TokioIo::new(inner),
+            entry: None,
         }
     }
 }
@@ -68,9 +156,122 @@ impl From<hyper::upgrade::Upgraded> for Upgraded {
 impl super::response::Response {
     /// Consumes the response and returns a future for a possible HTTP upgrade.
     pub async fn upgrade(self) -> crate::Result<Upgraded> {
-        hyper::upgrade::on(self.res)
+        self.upgrade_with_label(None).await
+    }
+
+    /// Like [`Response::upgrade()`](super::response::Response::upgrade), but
+    /// labels the connection in [`Client::upgrades()`](crate::Client::upgrades).
+    pub async fn upgrade_labeled(self, label: impl Into<String>) -> crate::Result<Upgraded> {
+        self.upgrade_with_label(Some(label.into())).await
+    }
+
+    async fn upgrade_with_label(self, label: Option<String>) -> crate::Result<Upgraded> {
+        let tracker = self.res.extensions().get::<Tracker>().cloned();
+        let url = self.url().clone();
+        let mut upgraded = hyper::upgrade::on(self.res)
             .map_ok(Upgraded::from)
             .map_err(crate::error::upgrade)
-            .await
+            .await?;
+        if let Some(Tracker(registry)) = tracker {
+            upgraded.entry = Some(registry.register(label, url));
+        }
+        Ok(upgraded)
+    }
+}
+
+// ===== impl UpgradeHandle =====
+
+impl UpgradeHandle {
+    /// A number identifying the connection among those of its `Client`.
+    pub fn id(&self) -> u64 {
+        self.entry.id
+    }
+
+    /// The label given with
+    /// [`Response::upgrade_labeled()`](crate::Response::upgrade_labeled).
+    pub fn label(&self) -> Option<&str> {
+        self.entry.label.as_deref()
+    }
+
+    /// The URL of the request that was upgraded.
+    pub fn url(&self) -> &Url {
+        &self.entry.url
+    }
+
+    /// How long the connection has been open.
+    pub fn age(&self) -> Duration {
+        self.entry.opened.elapsed()
+    }
+
+    /// How long it has been since data was last read or written.
+    pub fn idle(&self) -> Duration {
+        let active = Duration::from_millis(self.entry.last_active.load(Ordering::Relaxed));
+        self.age().saturating_sub(active)
+    }
+
+    /// Returns true once the `Upgraded` connection was dropped.
+    pub fn is_closed(&self) -> bool {
+        self.entry.closed.load(Ordering::Acquire)
+    }
+
+    /// Abort the connection.
+    ///
+    /// Pending and future reads and writes of the `Upgraded` connection fail
+    /// with [`io::ErrorKind::ConnectionAborted`], so that the task using it
+    /// can clean up and drop it.
+    pub fn abort(&self) {
+        self.entry.aborted.store(true, Ordering::Release);
+        self.entry.read_waker.wake();
+        self.entry.write_waker.wake();
+    }
+}
+
+impl fmt::Debug for UpgradeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpgradeHandle")
+            .field("id", &self.entry.id)
+            .field("label", &self.entry.label)
+            .field("url", &self.entry.url.as_str())
+            .finish()
+    }
+}
+
+// ===== impl Registry =====
+
+impl Registry {
+    fn register(&self, label: Option<String>, url: Url) -> Arc<Entry> {
+        let entry = Arc::new(Entry {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            label,
+            url,
+            opened: Instant::now(),
+            last_active: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            aborted: AtomicBool::new(false),
+            read_waker: AtomicWaker::new(),
+            write_waker: AtomicWaker::new(),
+        });
+        let mut entries = self.entries.lock().expect("lock poisoned");
+        entries.retain(|entry| !entry.closed.load(Ordering::Acquire));
+        entries.push(entry.clone());
+        entry
+    }
+
+    pub(crate) fn list(&self) -> Vec<UpgradeHandle> {
+        let mut entries = self.entries.lock().expect("lock poisoned");
+        entries.retain(|entry| !entry.closed.load(Ordering::Acquire));
+        entries
+            .iter()
+            .map(|entry| UpgradeHandle {
+                entry: entry.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Entry {
+    fn touch(&self) {
+        let millis = self.opened.elapsed().as_millis() as u64;
+        self.last_active.store(millis, Ordering::Relaxed);
     }
 }
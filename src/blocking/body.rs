@@ -12,6 +12,7 @@ use bytes::Bytes;
 use futures_channel::mpsc;
 
 use crate::async_impl;
+use crate::error::BoxError;
 
 /// The body of a `Request`.
 ///
@@ -84,6 +85,46 @@ impl Body {
         }
     }
 
+    /// Create a streaming `Body` from an iterator of chunks.
+    ///
+    /// The chunks are generated as the request body is sent, which avoids
+    /// holding all of it in memory, for instance when serializing the rows
+    /// of a large CSV file. The iterator is only advanced when the
+    /// connection is ready for more data.
+    ///
+    /// If the iterator yields an error, sending the request fails.
+    ///
+    /// Like the bodies created with [`Body::new`], these bodies can't be
+    /// reused for 307 and 308 redirects.
+    ///
+    /// ```rust
+    /// # use reqwest::blocking::Body;
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let rows = vec![("a", 1), ("b", 2)];
+    /// let body = Body::from_iter(
+    ///     rows.into_iter()
+    ///         .map(|(name, n)| Ok::<_, std::io::Error>(format!("{name},{n}\n").into())),
+    /// );
+    /// let res = reqwest::blocking::Client::new()
+    ///     .post("http://httpbin.org/post")
+    ///     .body(body)
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<I, E>(iter: I) -> Body
+    where
+        I: IntoIterator<Item = Result<Bytes, E>>,
+        I::IntoIter: Send + 'static,
+        E: Into<BoxError>,
+    {
+        Body::new(IterReader {
+            iter: iter.into_iter(),
+            chunk: Bytes::new(),
+        })
+    }
+
     /// Returns the body as a byte slice if the body is already buffered in
     /// memory. For streamed requests this method returns `None`.
     pub fn as_bytes(&self) -> Option<&[u8]> {
@@ -256,6 +297,31 @@ impl Read for Reader {
     }
 }
 
+/// Reads the chunks of an iterator.
+struct IterReader<I> {
+    iter: I,
+    chunk: Bytes,
+}
+
+impl<I, E> Read for IterReader<I>
+where
+    I: Iterator<Item = Result<Bytes, E>>,
+    E: Into<BoxError>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.iter.next() {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(err)) => return Err(io::Error::new(io::ErrorKind::Other, err.into())),
+                None => return Ok(0),
+            }
+        }
+        let n = std::cmp::min(buf.len(), self.chunk.len());
+        buf[..n].copy_from_slice(&self.chunk.split_to(n));
+        Ok(n)
+    }
+}
+
 pub(crate) struct Sender {
     body: (Box<dyn Read + Send>, Option<u64>),
     tx: mpsc::Sender<Result<Bytes, Abort>>,
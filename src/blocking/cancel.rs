@@ -0,0 +1,125 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+use futures_util::task::AtomicWaker;
+
+use super::request::Request;
+use super::response::Response;
+use super::Client;
+
+/// A request sent with
+/// [`RequestBuilder::send_cancellable()`](super::RequestBuilder::send_cancellable),
+/// whose response can be waited for.
+#[must_use = "PendingResponse does nothing until you 'wait' for it"]
+pub struct PendingResponse {
+    client: Client,
+    request: crate::Result<Request>,
+    cancel: CancelHandle,
+}
+
+/// A handle to cancel a request from another thread.
+///
+/// Cancelling a request makes [`PendingResponse::wait()`] return an error,
+/// for which [`Error::is_canceled()`](crate::Error::is_canceled) returns
+/// true, and aborts the request. A request that already completed isn't
+/// affected.
+#[derive(Clone)]
+pub struct CancelHandle {
+    inner: Arc<Cancel>,
+}
+
+#[derive(Default)]
+pub(super) struct Cancel {
+    canceled: AtomicBool,
+    waker: AtomicWaker,
+}
+
+// ===== impl PendingResponse =====
+
+impl PendingResponse {
+    pub(super) fn new(client: Client, request: crate::Result<Request>) -> PendingResponse {
+        PendingResponse {
+            client,
+            request,
+            cancel: CancelHandle {
+                inner: Arc::new(Cancel::default()),
+            },
+        }
+    }
+
+    /// Sends the request and blocks until its response is received, or the
+    /// request is canceled.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of
+    /// [`RequestBuilder::send()`](super::RequestBuilder::send), this fails
+    /// if the request was canceled, even before calling `wait()`.
+    pub fn wait(self) -> crate::Result<Response> {
+        self.client
+            .execute_cancellable(self.request?, &self.cancel.inner)
+    }
+
+    /// Returns a handle to cancel this request.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+}
+
+impl fmt::Debug for PendingResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PendingResponse")
+            .field("request", &self.request)
+            .field("canceled", &self.cancel.is_canceled())
+            .finish()
+    }
+}
+
+// ===== impl CancelHandle =====
+
+impl CancelHandle {
+    /// Cancels the request.
+    pub fn cancel(&self) {
+        self.inner.canceled.store(true, Ordering::Release);
+        self.inner.waker.wake();
+    }
+
+    /// Returns true if [`cancel()`](CancelHandle::cancel) was called.
+    pub fn is_canceled(&self) -> bool {
+        self.inner.is_canceled()
+    }
+}
+
+impl fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancelHandle")
+            .field("canceled", &self.is_canceled())
+            .finish()
+    }
+}
+
+// ===== impl Cancel =====
+
+impl Cancel {
+    pub(super) fn is_canceled(&self) -> bool {
+        self.canceled.load(Ordering::Acquire)
+    }
+
+    /// Completes once the request is canceled.
+    pub(super) fn canceled(&self) -> impl Future<Output = ()> + '_ {
+        std::future::poll_fn(move |cx| {
+            if self.is_canceled() {
+                return Poll::Ready(());
+            }
+            self.waker.register(cx.waker());
+            if self.is_canceled() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
@@ -9,12 +9,14 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use futures_util::future::Either;
 use http::header::HeaderValue;
 use log::{error, trace};
 use tokio::sync::{mpsc, oneshot};
 use tower::Layer;
 use tower::Service;
 
+use super::cancel::Cancel;
 use super::request::{Request, RequestBuilder};
 use super::response::Response;
 use super::wait;
@@ -26,10 +28,12 @@ use crate::error::BoxError;
 use crate::tls;
 #[cfg(feature = "__rustls")]
 use crate::tls::CertificateRevocationList;
+use crate::trace_context::ContextSource;
 #[cfg(feature = "__tls")]
 use crate::Certificate;
 #[cfg(any(feature = "native-tls", feature = "__rustls"))]
 use crate::Identity;
+use crate::async_impl::client::MethodTimeouts;
 use crate::{async_impl, header, redirect, IntoUrl, Method, Proxy};
 
 /// A `Client` to make Requests with.
@@ -77,6 +81,7 @@ pub struct Client {
 pub struct ClientBuilder {
     inner: async_impl::ClientBuilder,
     timeout: Timeout,
+    method_timeouts: MethodTimeouts,
 }
 
 impl Default for ClientBuilder {
@@ -93,6 +98,7 @@ impl ClientBuilder {
         ClientBuilder {
             inner: async_impl::ClientBuilder::new(),
             timeout: Timeout::default(),
+            method_timeouts: MethodTimeouts::default(),
         }
     }
 }
@@ -171,6 +177,39 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.default_headers(headers))
     }
 
+    /// Don't send the default `Accept: */*` header.
+    ///
+    /// By default, requests without an `Accept` header are sent with
+    /// `Accept: */*`.
+    pub fn no_default_accept(self) -> ClientBuilder {
+        self.with_inner(|inner| inner.no_default_accept())
+    }
+
+    /// Removes all the default headers, including the default `Accept`
+    /// header, and any set with `default_headers()` or `user_agent()` so
+    /// far.
+    pub fn clear_default_headers(self) -> ClientBuilder {
+        self.with_inner(|inner| inner.clear_default_headers())
+    }
+
+    /// Reject requests with header values that aren't visible ASCII.
+    ///
+    /// Sending such a request fails with a builder error, whose
+    /// [`header_name()`](crate::Error::header_name) identifies the offending
+    /// header.
+    ///
+    /// By default, such header values are sent as they are.
+    pub fn strict_header_values(self) -> ClientBuilder {
+        self.with_inner(|inner| inner.strict_header_values())
+    }
+
+    /// Replace characters in header values that aren't visible ASCII with `?`.
+    ///
+    /// By default, such header values are sent as they are.
+    pub fn sanitize_header_values(self) -> ClientBuilder {
+        self.with_inner(|inner| inner.sanitize_header_values())
+    }
+
     /// Enable a persistent cookie store for the client.
     ///
     /// Cookies received in responses will be preserved and included in
@@ -206,6 +245,42 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.cookie_provider(cookie_store))
     }
 
+    /// Set a function to call when a response changes the cookie store.
+    ///
+    /// The function is told when a cookie is stored, updated, or expired by
+    /// a `Set-Cookie` header.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cookies` feature to be enabled.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    pub fn cookie_observer<F>(self, f: F) -> ClientBuilder
+    where
+        F: Fn(&crate::cookie::CookieEvent<'_>) + Send + Sync + 'static,
+    {
+        self.with_inner(|inner| inner.cookie_observer(f))
+    }
+
+    /// Enable HTTP Strict Transport Security for the client.
+    ///
+    /// Hosts sending a `Strict-Transport-Security` header over HTTPS are
+    /// remembered, and later `http://` requests and redirects to them are
+    /// upgraded to `https://` before connecting.
+    ///
+    /// By default, HSTS isn't used.
+    pub fn hsts(self, enable: bool) -> ClientBuilder {
+        self.with_inner(|inner| inner.hsts(enable))
+    }
+
+    /// Set the HSTS store for the client.
+    ///
+    /// Policies received in responses are passed to this store, and the
+    /// URL of each request and redirect is checked against it.
+    pub fn hsts_provider<S: crate::hsts::HstsStore + 'static>(self, store: Arc<S>) -> ClientBuilder {
+        self.with_inner(|inner| inner.hsts_provider(store))
+    }
+
     /// Enable auto gzip decompression by checking the `Content-Encoding` response header.
     ///
     /// If auto gzip decompression is turned on:
@@ -312,6 +387,28 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.no_brotli())
     }
 
+    /// Set the largest zstd window size accepted when decompressing, as a
+    /// power of 2.
+    ///
+    /// Default is zstd's limit, 27 (128 MiB).
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `zstd` feature to be enabled
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    pub fn zstd_window_log_max(self, log: u32) -> ClientBuilder {
+        self.with_inner(|inner| inner.zstd_window_log_max(log))
+    }
+
+    /// Decompress response bodies on the blocking thread pool, if they're
+    /// larger than `threshold` bytes.
+    ///
+    /// Default is to decompress on the runtime thread reading the body.
+    pub fn decompression_offload_threshold(self, threshold: u64) -> ClientBuilder {
+        self.with_inner(|inner| inner.decompression_offload_threshold(threshold))
+    }
+
     /// Disable auto response body zstd decompression.
     ///
     /// This method exists even if the optional `zstd` feature is not enabled.
@@ -330,6 +427,29 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.no_deflate())
     }
 
+    /// Sets the encodings advertised in the `Accept-Encoding` header, in order
+    /// of preference.
+    ///
+    /// See [`async_impl::ClientBuilder::accept_encoding`] for details.
+    pub fn accept_encoding<I>(self, encodings: I) -> ClientBuilder
+    where
+        I: IntoIterator,
+        I::Item: Into<crate::codec::Preference>,
+    {
+        self.with_inner(|inner| inner.accept_encoding(encodings))
+    }
+
+    /// Register a custom content coding.
+    ///
+    /// The coding is advertised in the `Accept-Encoding` header, after the
+    /// enabled built-in encodings, and responses with a `Content-Encoding` of
+    /// `name` are decoded with the codec.
+    ///
+    /// See the [`codec`](crate::codec) module for an example.
+    pub fn content_codec<C: crate::codec::Codec>(self, name: &str, codec: C) -> ClientBuilder {
+        self.with_inner(|inner| inner.content_codec(name, codec))
+    }
+
     // Redirect options
 
     /// Set a `redirect::Policy` for this client.
@@ -346,6 +466,46 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.referer(enable))
     }
 
+    /// Buffer streaming request bodies, so that they can be sent again when
+    /// following a `307 Temporary Redirect` or `308 Permanent Redirect`.
+    ///
+    /// A streaming body, such as one created from a reader, can't be
+    /// replayed, so by default the 307 or 308 response is returned instead of
+    /// following it. With a limit, the bytes of a streaming body are kept as
+    /// they are sent, and the redirect is followed if the whole body was sent
+    /// before the redirect response arrived, and was no larger than `limit`
+    /// bytes.
+    ///
+    /// Default is no buffering.
+    pub fn redirect_body_buffer_limit(self, limit: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.redirect_body_buffer_limit(limit))
+    }
+
+    /// Buffer streaming request bodies, so that they can be sent again when
+    /// a request is retried.
+    ///
+    /// Requests are retried automatically when the server refuses an HTTP/2
+    /// stream, or closes the connection, before processing them. With a
+    /// limit, the bytes of a streaming body are kept as they are sent, and
+    /// the request is retried if the whole body was sent, and was no larger
+    /// than `limit` bytes.
+    ///
+    /// Default is no buffering.
+    pub fn retry_body_buffer_limit(self, limit: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.retry_body_buffer_limit(limit))
+    }
+
+    /// Honor `Retry-After` headers, waiting up to `max` each time.
+    ///
+    /// Redirects are followed after the delay, and `429 Too Many Requests`
+    /// or `503 Service Unavailable` responses are retried at most twice.
+    /// A response asking to wait longer than `max` is returned as is.
+    ///
+    /// Default is to ignore `Retry-After`.
+    pub fn retry_after(self, max: Duration) -> ClientBuilder {
+        self.with_inner(|inner| inner.retry_after(max))
+    }
+
     // Proxy options
 
     /// Add a `Proxy` to the list of proxies the `Client` will use.
@@ -383,6 +543,47 @@ impl ClientBuilder {
         self
     }
 
+    /// Set a timeout for requests with `method`.
+    ///
+    /// This takes precedence over the timeouts of
+    /// [`safe_methods_timeout()`](ClientBuilder::safe_methods_timeout),
+    /// [`unsafe_methods_timeout()`](ClientBuilder::unsafe_methods_timeout) and
+    /// [`timeout()`](ClientBuilder::timeout), while
+    /// [`RequestBuilder::timeout()`](super::RequestBuilder::timeout) takes
+    /// precedence over it.
+    pub fn timeout_for(mut self, method: Method, timeout: Duration) -> ClientBuilder {
+        self.method_timeouts.methods.insert(method, timeout);
+        self
+    }
+
+    /// Set a timeout for requests with a safe method: `GET`, `HEAD`,
+    /// `OPTIONS` and `TRACE`.
+    ///
+    /// This takes precedence over [`timeout()`](ClientBuilder::timeout).
+    pub fn safe_methods_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.method_timeouts.safe = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for requests with a method that isn't safe, such as
+    /// `POST`, `PUT` or `DELETE`.
+    ///
+    /// This takes precedence over [`timeout()`](ClientBuilder::timeout).
+    pub fn unsafe_methods_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.method_timeouts.unsafe_ = Some(timeout);
+        self
+    }
+
+    /// Only connect to the given destination ports.
+    ///
+    /// Default is all ports.
+    pub fn allowed_ports<I>(self, ports: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        self.with_inner(move |inner| inner.allowed_ports(ports))
+    }
+
     /// Set a timeout for only the connect phase of a `Client`.
     ///
     /// Default is `None`.
@@ -398,6 +599,129 @@ impl ClientBuilder {
         }
     }
 
+    /// Limits the rate at which request bodies are sent, in bytes per
+    /// second.
+    ///
+    /// See [`async_impl::ClientBuilder::max_upload_rate`] for details.
+    pub fn max_upload_rate(self, bytes_per_sec: u64) -> ClientBuilder {
+        self.with_inner(|inner| inner.max_upload_rate(bytes_per_sec))
+    }
+
+    /// Limits the rate at which response bodies are received, in bytes per
+    /// second.
+    ///
+    /// See [`async_impl::ClientBuilder::max_download_rate`] for details.
+    pub fn max_download_rate(self, bytes_per_sec: u64) -> ClientBuilder {
+        self.with_inner(|inner| inner.max_download_rate(bytes_per_sec))
+    }
+
+    /// Hedge `GET` and `HEAD` requests: if no response arrived after
+    /// `delay`, send a second, identical request, and use whichever response
+    /// arrives first. The other request is cancelled.
+    ///
+    /// Only `GET` and `HEAD` requests are hedged, since sending other
+    /// requests twice may not be safe; use
+    /// [`RequestBuilder::hedge_after()`](crate::blocking::RequestBuilder::hedge_after)
+    /// to opt in for other requests that are idempotent.
+    ///
+    /// Default is no hedging.
+    pub fn hedge_after(self, delay: Duration) -> ClientBuilder {
+        self.with_inner(|inner| inner.hedge_after(delay))
+    }
+
+    /// Limits how many requests of the client are in flight at once.
+    ///
+    /// See [`async_impl::ClientBuilder::max_concurrent_requests`] for details.
+    pub fn max_concurrent_requests(self, max: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.max_concurrent_requests(max))
+    }
+
+    /// Limits how many requests of the client to each host are in flight
+    /// at once.
+    ///
+    /// See [`async_impl::ClientBuilder::max_concurrent_requests_per_host`]
+    /// for details.
+    pub fn max_concurrent_requests_per_host(self, max: usize) -> ClientBuilder {
+        self.with_inner(|inner| inner.max_concurrent_requests_per_host(max))
+    }
+
+    /// Sets how long a request waits in line for the concurrency limits,
+    /// before it fails with a timeout error.
+    ///
+    /// Default is to wait as long as it takes.
+    pub fn concurrency_queue_timeout(self, timeout: Duration) -> ClientBuilder {
+        self.with_inner(|inner| inner.concurrency_queue_timeout(timeout))
+    }
+
+    /// Enables a circuit breaker for each origin.
+    ///
+    /// See the [`circuit`](crate::circuit) module for details.
+    ///
+    /// Default is no circuit breaker.
+    pub fn circuit_breaker(self, breaker: crate::circuit::CircuitBreaker) -> ClientBuilder {
+        self.with_inner(|inner| inner.circuit_breaker(breaker))
+    }
+
+    /// Serialize the bodies set with
+    /// [`RequestBuilder::json()`](super::RequestBuilder::json) in the
+    /// canonical form of the JSON Canonicalization Scheme (RFC 8785).
+    ///
+    /// See [`async_impl::ClientBuilder::canonical_json`] for details.
+    ///
+    /// Default is `false`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn canonical_json(self, enable: bool) -> ClientBuilder {
+        self.with_inner(|inner| inner.canonical_json(enable))
+    }
+
+    /// Verify response bodies against their `Content-Digest` or
+    /// `Repr-Digest` header, while they're read.
+    ///
+    /// See [`async_impl::ClientBuilder::verify_content_digest`] for details.
+    ///
+    /// Default is `false`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `digest` feature enabled.
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    pub fn verify_content_digest(self, enable: bool) -> ClientBuilder {
+        self.with_inner(|inner| inner.verify_content_digest(enable))
+    }
+
+    /// Propagates the current trace context with requests.
+    ///
+    /// `source` is asked for the current trace context on the thread that
+    /// sends the request. See the [`trace_context`](crate::trace_context)
+    /// module for details.
+    ///
+    /// Default is no propagation.
+    pub fn trace_context<S>(self, source: S) -> ClientBuilder
+    where
+        S: ContextSource,
+    {
+        self.with_inner(|inner| inner.trace_context(source))
+    }
+
+    /// Adds an interceptor, told about the requests, responses and errors of
+    /// the `Client`.
+    ///
+    /// Interceptors are called on the thread of the runtime that drives
+    /// the requests, not the calling thread. See the
+    /// [`interceptor`](crate::interceptor) module for details.
+    pub fn interceptor<I>(self, interceptor: I) -> ClientBuilder
+    where
+        I: crate::interceptor::Interceptor,
+    {
+        self.with_inner(|inner| inner.interceptor(interceptor))
+    }
+
     /// Set whether connections should emit verbose logs.
     ///
     /// Enabling this option will emit [log][] messages at the `TRACE` level
@@ -427,6 +751,33 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.pool_max_idle_per_host(max))
     }
 
+    /// Limits how many connections to each host are open at once, whether
+    /// they're in use or idle.
+    ///
+    /// See [`async_impl::ClientBuilder::max_connections_per_host`] for details.
+    pub fn max_connections_per_host(self, max: usize) -> ClientBuilder {
+        self.with_inner(move |inner| inner.max_connections_per_host(max))
+    }
+
+    /// Sets how long a request waits for a connection when the pool of its
+    /// host is saturated.
+    ///
+    /// See [`async_impl::ClientBuilder::pool_acquire_timeout`] for details.
+    pub fn pool_acquire_timeout(self, timeout: Duration) -> ClientBuilder {
+        self.with_inner(move |inner| inner.pool_acquire_timeout(timeout))
+    }
+
+    /// Set a function to call when a connection of the pool is created,
+    /// reused or closed.
+    ///
+    /// See the [`pool`](crate::pool) module for details.
+    pub fn pool_events<F>(self, f: F) -> ClientBuilder
+    where
+        F: Fn(&crate::pool::PoolEvent<'_>) + Send + Sync + 'static,
+    {
+        self.with_inner(|inner| inner.pool_events(f))
+    }
+
     /// Send headers as title case instead of lowercase.
     pub fn http1_title_case_headers(self) -> ClientBuilder {
         self.with_inner(|inner| inner.http1_title_case_headers())
@@ -455,6 +806,25 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.http1_allow_spaces_after_header_name_in_responses(value))
     }
 
+    /// Set whether to check if the server closed an idle HTTP/1 connection
+    /// before reusing it.
+    ///
+    /// Default is `false`.
+    pub fn http1_probe_idle_connections(self, enabled: bool) -> ClientBuilder {
+        self.with_inner(|inner| inner.http1_probe_idle_connections(enabled))
+    }
+
+    /// Set whether to record the header lines of HTTP/1 responses in the
+    /// order and casing they were received.
+    ///
+    /// They are available from
+    /// [`Response::raw_headers()`](crate::blocking::Response::raw_headers).
+    ///
+    /// Default is `false`.
+    pub fn http1_preserve_raw_headers(self, enabled: bool) -> ClientBuilder {
+        self.with_inner(|inner| inner.http1_preserve_raw_headers(enabled))
+    }
+
     /// Only use HTTP/1.
     pub fn http1_only(self) -> ClientBuilder {
         self.with_inner(|inner| inner.http1_only())
@@ -472,6 +842,15 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.http2_prior_knowledge())
     }
 
+    /// Use HTTP/2 with prior knowledge for `http://` URLs only.
+    ///
+    /// See [`async_impl::ClientBuilder::http2_cleartext_prior_knowledge`] for details.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_cleartext_prior_knowledge(self) -> ClientBuilder {
+        self.with_inner(|inner| inner.http2_cleartext_prior_knowledge())
+    }
+
     /// Sets the `SETTINGS_INITIAL_WINDOW_SIZE` option for HTTP2 stream-level flow control.
     ///
     /// Default is currently 65,535 but may change internally to optimize for common uses.
@@ -500,6 +879,31 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.http2_adaptive_window(enabled))
     }
 
+    /// Falls back to HTTP/1.1 for origins whose HTTP/2 connections keep
+    /// failing with protocol errors.
+    ///
+    /// See [`async_impl::ClientBuilder::http2_fallback`] for details.
+    ///
+    /// Default is disabled.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_fallback(self, ttl: Duration) -> ClientBuilder {
+        self.with_inner(|inner| inner.http2_fallback(ttl))
+    }
+
+    /// Set a function to call when an HTTP/2 connection receives a `GOAWAY`,
+    /// a stream reset, or the acknowledgement of a ping.
+    ///
+    /// See the [`http2`](crate::http2) module for details.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_events<F>(self, f: F) -> ClientBuilder
+    where
+        F: Fn(&crate::http2::Http2Event<'_>) + Send + Sync + 'static,
+    {
+        self.with_inner(|inner| inner.http2_events(f))
+    }
+
     /// Sets the maximum frame size to use for HTTP2.
     ///
     /// Default is currently 16,384 but may change internally to optimize for common uses.
@@ -518,6 +922,16 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.http2_max_header_list_size(max_header_size_bytes))
     }
 
+    /// Sets the maximum number of requests in flight on each HTTP/2
+    /// connection.
+    ///
+    /// See [`async_impl::ClientBuilder::http2_max_concurrent_streams`] for details.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_concurrent_streams(self, max: u32) -> ClientBuilder {
+        self.with_inner(|inner| inner.http2_max_concurrent_streams(max))
+    }
+
     /// This requires the optional `http3` feature to be
     /// enabled.
     #[cfg(feature = "http3")]
@@ -651,6 +1065,25 @@ impl ClientBuilder {
         self.with_inner(move |inner| inner.add_crls(crls))
     }
 
+    /// Checks the revocation status of server certificates with OCSP.
+    ///
+    /// See [`async_impl::ClientBuilder::tls_ocsp_check`] for details.
+    #[cfg(feature = "rustls-tls-ocsp")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls-ocsp")))]
+    pub fn tls_ocsp_check(self, policy: tls::OcspPolicy) -> ClientBuilder {
+        self.with_inner(|inner| inner.tls_ocsp_check(policy))
+    }
+
+    /// Requires server certificates to satisfy a Certificate Transparency
+    /// policy.
+    ///
+    /// See [`async_impl::ClientBuilder::tls_ct_policy`] for details.
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn tls_ct_policy(self, policy: tls::CtPolicy) -> ClientBuilder {
+        self.with_inner(|inner| inner.tls_ct_policy(policy))
+    }
+
     /// Controls the use of built-in system certificates during certificate validation.
     ///
     /// Defaults to `true` -- built-in system certs will be used.
@@ -754,6 +1187,22 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.danger_accept_invalid_certs(accept_invalid_certs))
     }
 
+    /// Overrides the TLS options for connections to a single host.
+    ///
+    /// See [`async_impl::ClientBuilder::tls_config_for_host`] for details.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_config_for_host(self, host: &str, options: tls::TlsOptions) -> ClientBuilder {
+        self.with_inner(|inner| inner.tls_config_for_host(host, options))
+    }
+
     /// Controls the use of TLS server name indication.
     ///
     /// Defaults to `true`.
@@ -770,6 +1219,23 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.tls_sni(tls_sni))
     }
 
+    /// Sets the name sent in the TLS server name indication to a host,
+    /// in place of the host itself.
+    ///
+    /// See [`async_impl::ClientBuilder::tls_sni_hostname`] for details.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_sni_hostname(self, host: &str, name: &str) -> ClientBuilder {
+        self.with_inner(|inner| inner.tls_sni_hostname(host, name))
+    }
+
     /// Set the minimum required TLS version for connections.
     ///
     /// By default, the TLS backend's own default is used.
@@ -826,6 +1292,39 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.max_tls_version(version))
     }
 
+    /// Restrict the TLS cipher suites of connections, in order of preference.
+    ///
+    /// See [`async_impl::ClientBuilder::tls_cipher_suites`] for details.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_cipher_suites(self, suites: &[tls::CipherSuite]) -> ClientBuilder {
+        self.with_inner(|inner| inner.tls_cipher_suites(suites))
+    }
+
+    /// Restrict the TLS key exchange groups of connections, in order of
+    /// preference.
+    ///
+    /// See [`async_impl::ClientBuilder::tls_key_exchange_groups`] for details.
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "rustls-tls"
+        )))
+    )]
+    pub fn tls_key_exchange_groups(self, groups: &[tls::KeyExchangeGroup]) -> ClientBuilder {
+        self.with_inner(|inner| inner.tls_key_exchange_groups(groups))
+    }
+
     /// Force using the native TLS backend.
     ///
     /// Since multiple TLS backends can be optionally enabled, this option will
@@ -943,6 +1442,30 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.no_hickory_dns())
     }
 
+    /// Set a function to call when a response announces a deprecation with
+    /// the `Deprecation` or `Sunset` headers.
+    ///
+    /// The function is called with the URL of the response, before the
+    /// response is returned.
+    pub fn on_deprecation<F>(self, f: F) -> ClientBuilder
+    where
+        F: Fn(&crate::Url, &crate::notice::Deprecation) + Send + Sync + 'static,
+    {
+        self.with_inner(|inner| inner.on_deprecation(f))
+    }
+
+    /// Set the content type that [`Response::json()`](super::Response::json)
+    /// and [`Response::text()`](super::Response::text) expect responses to
+    /// have.
+    ///
+    /// When set, these methods fail without reading the body if a response
+    /// has another `Content-Type`.
+    ///
+    /// Default is no expectation.
+    pub fn expect_content_type(self, content_type: &str) -> ClientBuilder {
+        self.with_inner(|inner| inner.expect_content_type(content_type))
+    }
+
     /// Restrict the Client to be used with HTTPS only requests.
     ///
     /// Defaults to false.
@@ -975,6 +1498,20 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.dns_resolver(resolver))
     }
 
+    /// Set a timeout for resolving the name of a host.
+    ///
+    /// Default is no timeout.
+    pub fn dns_timeout(self, timeout: Duration) -> ClientBuilder {
+        self.with_inner(|inner| inner.dns_timeout(timeout))
+    }
+
+    /// Set a resolver used when the DNS resolver fails or times out.
+    ///
+    /// Default is no fallback.
+    pub fn dns_fallback<R: Resolve + 'static>(self, resolver: Arc<R>) -> ClientBuilder {
+        self.with_inner(|inner| inner.dns_fallback(resolver))
+    }
+
     /// Adds a new Tower [`Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html) to the
     /// base connector [`Service`](https://docs.rs/tower/latest/tower/trait.Service.html) which
     /// is responsible for connection establishment.
@@ -1004,6 +1541,24 @@ impl ClientBuilder {
         self.with_inner(|inner| inner.connector_layer(layer))
     }
 
+    /// Adds a new Tower [`Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html) to the
+    /// [`RequestService`](crate::RequestService) which sends requests.
+    ///
+    /// See [`reqwest::ClientBuilder::request_layer()`](crate::ClientBuilder::request_layer)
+    /// for where the layers are placed.
+    pub fn request_layer<L>(self, layer: L) -> ClientBuilder
+    where
+        L: Layer<crate::RequestService> + Clone + Send + Sync + 'static,
+        L::Service: Service<http::Request<crate::Body>, Response = http::Response<crate::Body>, Error = BoxError>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as Service<http::Request<crate::Body>>>::Future: Send + 'static,
+    {
+        self.with_inner(|inner| inner.request_layer(layer))
+    }
+
     // private
 
     fn with_inner<F>(mut self, func: F) -> ClientBuilder
@@ -1020,6 +1575,7 @@ impl From<async_impl::ClientBuilder> for ClientBuilder {
         Self {
             inner: builder,
             timeout: Timeout::default(),
+            method_timeouts: MethodTimeouts::default(),
         }
     }
 }
@@ -1134,7 +1690,29 @@ impl Client {
     /// This method fails if there was an error while sending request,
     /// or redirect limit was exhausted.
     pub fn execute(&self, request: Request) -> crate::Result<Response> {
-        self.inner.execute_request(request)
+        self.inner.execute_request(request, None)
+    }
+
+    #[cfg(feature = "json")]
+    pub(super) fn canonical_json(&self) -> bool {
+        self.inner.canonical_json
+    }
+
+    /// Reads the system proxy settings again.
+    ///
+    /// See [`async_impl::Client::reload_system_proxy`] for details.
+    pub fn reload_system_proxy(&self) {
+        for proxy in self.inner.proxies.iter() {
+            proxy.reload_system();
+        }
+    }
+
+    pub(super) fn execute_cancellable(
+        &self,
+        request: Request,
+        cancel: &Cancel,
+    ) -> crate::Result<Response> {
+        self.inner.execute_request(request, Some(cancel))
     }
 }
 
@@ -1157,6 +1735,11 @@ impl fmt::Debug for ClientBuilder {
 #[derive(Clone)]
 struct ClientHandle {
     timeout: Timeout,
+    method_timeouts: Arc<MethodTimeouts>,
+    trace_context: Option<Arc<dyn ContextSource>>,
+    #[cfg(feature = "json")]
+    canonical_json: bool,
+    proxies: Arc<Vec<Proxy>>,
     inner: Arc<InnerClientHandle>,
 }
 
@@ -1187,9 +1770,13 @@ impl Drop for InnerClientHandle {
 impl ClientHandle {
     fn new(builder: ClientBuilder) -> crate::Result<ClientHandle> {
         let timeout = builder.timeout;
+        let method_timeouts = Arc::new(builder.method_timeouts);
+        let trace_context = builder.inner.trace_context_source();
+        #[cfg(feature = "json")]
+        let canonical_json = builder.inner.is_canonical_json();
         let builder = builder.inner;
         let (tx, rx) = mpsc::unbounded_channel::<(async_impl::Request, OneshotResponse)>();
-        let (spawn_tx, spawn_rx) = oneshot::channel::<crate::Result<()>>();
+        let (spawn_tx, spawn_rx) = oneshot::channel::<crate::Result<Arc<Vec<Proxy>>>>();
         let handle = thread::Builder::new()
             .name("reqwest-internal-sync-runtime".into())
             .spawn(move || {
@@ -1218,7 +1805,7 @@ impl ClientHandle {
                         }
                         Ok(v) => v,
                     };
-                    if let Err(e) = spawn_tx.send(Ok(())) {
+                    if let Err(e) = spawn_tx.send(Ok(client.proxies())) {
                         error!("Failed to communicate successful startup: {e:?}");
                         return;
                     }
@@ -1242,11 +1829,11 @@ impl ClientHandle {
             .map_err(crate::error::builder)?;
 
         // Wait for the runtime thread to start up...
-        match wait::timeout(spawn_rx, None) {
-            Ok(Ok(())) => (),
+        let proxies = match wait::timeout(spawn_rx, None) {
+            Ok(Ok(proxies)) => proxies,
             Ok(Err(err)) => return Err(err),
             Err(_canceled) => event_loop_panicked(),
-        }
+        };
 
         let inner_handle = Arc::new(InnerClientHandle {
             tx: Some(tx),
@@ -1255,15 +1842,35 @@ impl ClientHandle {
 
         Ok(ClientHandle {
             timeout,
+            method_timeouts,
+            trace_context,
+            #[cfg(feature = "json")]
+            canonical_json,
+            proxies,
             inner: inner_handle,
         })
     }
 
-    fn execute_request(&self, req: Request) -> crate::Result<Response> {
+    fn execute_request(&self, mut req: Request, cancel: Option<&Cancel>) -> crate::Result<Response> {
+        if cancel.map_or(false, Cancel::is_canceled) {
+            return Err(crate::error::request(crate::error::Canceled).with_url(req.url().clone()));
+        }
+
+        // the current context of the calling thread
+        if let Some(ref source) = self.trace_context {
+            if let Some(context) = source.current() {
+                context.inject(req.headers_mut());
+            }
+        }
+
         let (tx, rx) = oneshot::channel();
         let (req, body) = req.into_async();
         let url = req.url().clone();
-        let timeout = req.timeout().copied().or(self.timeout.0);
+        let timeout = req
+            .timeout()
+            .copied()
+            .or_else(|| self.method_timeouts.get(req.method()))
+            .or(self.timeout.0);
 
         self.inner
             .tx
@@ -1272,16 +1879,30 @@ impl ClientHandle {
             .send((req, tx))
             .expect("core thread panicked");
 
-        let result: Result<crate::Result<async_impl::Response>, wait::Waited<crate::Error>> =
+        let f = async move {
             if let Some(body) = body {
-                let f = async move {
-                    body.send().await?;
-                    rx.await.map_err(|_canceled| event_loop_panicked())
-                };
-                wait::timeout(f, timeout)
-            } else {
-                let f = async move { rx.await.map_err(|_canceled| event_loop_panicked()) };
-                wait::timeout(f, timeout)
+                body.send().await?;
+            }
+            rx.await.map_err(|_canceled| event_loop_panicked())
+        };
+        let result: Result<crate::Result<async_impl::Response>, wait::Waited<crate::Error>> =
+            match cancel {
+                Some(cancel) => {
+                    // dropping the receiver aborts the request
+                    let f = async move {
+                        futures_util::pin_mut!(f);
+                        let canceled = cancel.canceled();
+                        futures_util::pin_mut!(canceled);
+                        match futures_util::future::select(f, canceled).await {
+                            Either::Left((res, _)) => res,
+                            Either::Right(((), _)) => {
+                                Err(crate::error::request(crate::error::Canceled))
+                            }
+                        }
+                    };
+                    wait::timeout(f, timeout)
+                }
+                None => wait::timeout(f, timeout),
             };
 
         match result {
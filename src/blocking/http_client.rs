@@ -0,0 +1,74 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::request::Request;
+use super::response::Response;
+use super::Client;
+
+/// Something that executes blocking HTTP requests.
+///
+/// This is implemented by [`Client`], and can be implemented by other
+/// clients, such as mocks in tests. Libraries can accept a
+/// `&dyn BlockingHttpClient`, or a `Box<dyn BlockingHttpClient + Send + Sync>`,
+/// instead of a `Client`, so that their users can provide any of them.
+///
+/// # Example
+///
+/// ```
+/// use reqwest::blocking::{BlockingHttpClient, Request, Response};
+///
+/// fn fetch_motd(client: &dyn BlockingHttpClient) -> reqwest::Result<String> {
+///     let url = "https://example.com/motd".parse().unwrap();
+///     let req = Request::new(reqwest::Method::GET, url);
+///     client.execute(req)?.error_for_status()?.text()
+/// }
+///
+/// struct Mock;
+///
+/// impl BlockingHttpClient for Mock {
+///     fn execute(&self, _req: Request) -> reqwest::Result<Response> {
+///         Ok(http::Response::new("hello").into())
+///     }
+/// }
+///
+/// assert_eq!(fetch_motd(&Mock).unwrap(), "hello");
+/// ```
+pub trait BlockingHttpClient {
+    /// Executes a `Request`, and returns its `Response`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if there was an error while sending the request,
+    /// or receiving the response.
+    fn execute(&self, request: Request) -> crate::Result<Response>;
+}
+
+impl BlockingHttpClient for Client {
+    fn execute(&self, request: Request) -> crate::Result<Response> {
+        Client::execute(self, request)
+    }
+}
+
+impl<C: BlockingHttpClient + ?Sized> BlockingHttpClient for &C {
+    fn execute(&self, request: Request) -> crate::Result<Response> {
+        (**self).execute(request)
+    }
+}
+
+impl<C: BlockingHttpClient + ?Sized> BlockingHttpClient for Box<C> {
+    fn execute(&self, request: Request) -> crate::Result<Response> {
+        (**self).execute(request)
+    }
+}
+
+impl<C: BlockingHttpClient + ?Sized> BlockingHttpClient for Rc<C> {
+    fn execute(&self, request: Request) -> crate::Result<Response> {
+        (**self).execute(request)
+    }
+}
+
+impl<C: BlockingHttpClient + ?Sized> BlockingHttpClient for Arc<C> {
+    fn execute(&self, request: Request) -> crate::Result<Response> {
+        (**self).execute(request)
+    }
+}
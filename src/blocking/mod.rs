@@ -64,7 +64,9 @@
 //! on the blocking `Client`, see those docs for more.
 
 mod body;
+mod cancel;
 mod client;
+mod http_client;
 #[cfg(feature = "multipart")]
 pub mod multipart;
 mod request;
@@ -72,7 +74,9 @@ mod response;
 mod wait;
 
 pub use self::body::Body;
+pub use self::cancel::{CancelHandle, PendingResponse};
 pub use self::client::{Client, ClientBuilder};
+pub use self::http_client::BlockingHttpClient;
 pub use self::request::{Request, RequestBuilder};
 pub use self::response::Response;
 
@@ -1,4 +1,4 @@
-//! multipart/form-data
+//! multipart/form-data requests, and multipart responses
 //!
 //! To send a `multipart/form-data` body, a [`Form`] is built up, adding
 //! fields or customized [`Part`]s, and then calling the
@@ -36,18 +36,27 @@
 //! ```
 //!
 //! [builder]: ../struct.RequestBuilder.html#method.multipart
+//!
+//! The parts of a `multipart/*` response, like `multipart/byteranges`, are
+//! read with [`Response::multipart()`](super::Response::multipart).
 use std::borrow::Cow;
 use std::fmt;
 use std::fs::File;
 use std::io::{self, Cursor, Read};
 use std::path::Path;
+use std::time::Duration;
 
+use futures_util::{FutureExt, StreamExt};
 use mime_guess::{self, Mime};
 
+use super::client::KeepCoreThreadAlive;
+use super::wait;
 use super::Body;
 use crate::async_impl::multipart::{FormParts, PartMetadata, PartProps};
 use crate::header::HeaderMap;
 
+pub use crate::async_impl::multipart::ReceivedPart;
+
 /// A multipart/form-data request.
 pub struct Form {
     inner: FormParts<Part>,
@@ -59,6 +68,16 @@ pub struct Part {
     value: Body,
 }
 
+/// The parts of a `multipart/*` response.
+///
+/// This is an `Iterator` of [`ReceivedPart`]s, returned by
+/// [`Response::multipart()`](super::Response::multipart).
+pub struct Parts {
+    inner: crate::async_impl::multipart::Parts,
+    timeout: Option<Duration>,
+    _thread_handle: KeepCoreThreadAlive,
+}
+
 impl Default for Form {
     fn default() -> Self {
         Self::new()
@@ -153,6 +172,33 @@ impl Form {
         self.reader()
     }
 
+    /// Returns the exact length of this form's body, if it is known.
+    ///
+    /// The length includes the boundaries and part headers, and is known when
+    /// every part has a known length. Parts created with [`Part::reader()`]
+    /// have an unknown length, unless created with
+    /// [`Part::reader_with_length()`].
+    ///
+    /// When the length is known, [`RequestBuilder::multipart()`] sets the
+    /// `Content-Length` header automatically.
+    ///
+    /// [`RequestBuilder::multipart()`]: crate::blocking::RequestBuilder::multipart
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use reqwest::blocking::multipart::{Form, Part};
+    ///
+    /// let form = Form::new().text("key", "value");
+    /// assert!(form.length().is_some());
+    ///
+    /// let form = form.part("data", Part::reader(std::io::empty()));
+    /// assert_eq!(form.length(), None);
+    /// ```
+    pub fn length(&self) -> Option<u64> {
+        self.inner.length()
+    }
+
     // If predictable, computes the length the request will have
     // The length should be predictable if only String and file fields have been added,
     // but not if a generic reader has been added;
@@ -378,6 +424,39 @@ impl Read for Reader {
     }
 }
 
+impl Parts {
+    pub(crate) fn new(
+        inner: crate::async_impl::multipart::Parts,
+        timeout: Option<Duration>,
+        thread: KeepCoreThreadAlive,
+    ) -> Parts {
+        Parts {
+            inner,
+            timeout,
+            _thread_handle: thread,
+        }
+    }
+}
+
+impl Iterator for Parts {
+    type Item = crate::Result<ReceivedPart>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let timeout = self.timeout;
+        match wait::timeout(self.inner.next().map(Ok), timeout) {
+            Ok(item) => item,
+            Err(wait::Waited::TimedOut(e)) => Some(Err(crate::error::decode(e))),
+            Err(wait::Waited::Inner(e)) => Some(Err(e)),
+        }
+    }
+}
+
+impl fmt::Debug for Parts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parts").finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
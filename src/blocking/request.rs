@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use http::{request::Parts, Request as HttpRequest, Version};
 use serde::Serialize;
@@ -13,7 +16,7 @@ use super::body::{self, Body};
 use super::multipart;
 use super::Client;
 use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
-use crate::{async_impl, Method, Url};
+use crate::{async_impl, IntoInferredBody, Method, StatusCode, Url};
 
 /// A request which can be executed with `Client::execute()`.
 pub struct Request {
@@ -113,6 +116,161 @@ impl Request {
         self.inner.timeout_mut()
     }
 
+    /// Get the maximum number of redirects to follow.
+    #[inline]
+    pub fn max_redirects(&self) -> Option<usize> {
+        self.inner.max_redirects()
+    }
+
+    /// Get a mutable reference to the maximum number of redirects to follow.
+    #[inline]
+    pub fn max_redirects_mut(&mut self) -> &mut Option<usize> {
+        self.inner.max_redirects_mut()
+    }
+
+    /// Get the delay after which a hedged request is sent.
+    #[inline]
+    pub fn hedge_after(&self) -> Option<Duration> {
+        self.inner.hedge_after()
+    }
+
+    /// Get a mutable reference to the delay after which a hedged request is
+    /// sent.
+    #[inline]
+    pub fn hedge_after_mut(&mut self) -> &mut Option<Duration> {
+        self.inner.hedge_after_mut()
+    }
+
+    /// Get how long the body waits for a `100 Continue` response.
+    #[inline]
+    pub fn expect_continue(&self) -> Option<Duration> {
+        self.inner.expect_continue()
+    }
+
+    /// Get a mutable reference to how long the body waits for a
+    /// `100 Continue` response.
+    #[inline]
+    pub fn expect_continue_mut(&mut self) -> &mut Option<Duration> {
+        self.inner.expect_continue_mut()
+    }
+
+    /// Get the maximum rate, in bytes per second, at which the body is sent.
+    #[inline]
+    pub fn max_upload_rate(&self) -> Option<u64> {
+        self.inner.max_upload_rate()
+    }
+
+    /// Get a mutable reference to the maximum rate, in bytes per second, at
+    /// which the body is sent.
+    #[inline]
+    pub fn max_upload_rate_mut(&mut self) -> &mut Option<u64> {
+        self.inner.max_upload_rate_mut()
+    }
+
+    /// Get the maximum rate, in bytes per second, at which the response body
+    /// is received.
+    #[inline]
+    pub fn max_download_rate(&self) -> Option<u64> {
+        self.inner.max_download_rate()
+    }
+
+    /// Get a mutable reference to the maximum rate, in bytes per second, at
+    /// which the response body is received.
+    #[inline]
+    pub fn max_download_rate_mut(&mut self) -> &mut Option<u64> {
+        self.inner.max_download_rate_mut()
+    }
+
+    /// Get whether the client's `Accept-Encoding` header is added to this
+    /// request.
+    #[inline]
+    pub fn accept_encoding(&self) -> bool {
+        self.inner.accept_encoding()
+    }
+
+    /// Get a mutable reference to whether the client's `Accept-Encoding`
+    /// header is added to this request.
+    #[inline]
+    pub fn accept_encoding_mut(&mut self) -> &mut bool {
+        self.inner.accept_encoding_mut()
+    }
+
+    /// Get the DNS overrides of this request.
+    #[inline]
+    pub fn resolve_map(&self) -> Option<&HashMap<String, Vec<SocketAddr>>> {
+        self.inner.resolve_map()
+    }
+
+    /// Get a mutable reference to the DNS overrides of this request.
+    #[inline]
+    pub fn resolve_map_mut(&mut self) -> &mut Option<HashMap<String, Vec<SocketAddr>>> {
+        self.inner.resolve_map_mut()
+    }
+
+    /// Get the cookie store of this request, used instead of the one of the
+    /// `Client`.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    #[inline]
+    pub fn cookie_store(&self) -> Option<&Arc<dyn crate::cookie::CookieStore>> {
+        self.inner.cookie_store()
+    }
+
+    /// Get a mutable reference to the cookie store of this request.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    #[inline]
+    pub fn cookie_store_mut(&mut self) -> &mut Option<Arc<dyn crate::cookie::CookieStore>> {
+        self.inner.cookie_store_mut()
+    }
+
+    /// Format the request as an equivalent curl command.
+    ///
+    /// This is meant for bug reports and support tickets. The command can
+    /// be pasted into a POSIX shell, and includes the method, URL, headers,
+    /// timeout and body of the request.
+    ///
+    /// The values of sensitive headers, such as `Authorization` and
+    /// `Cookie` or any marked sensitive, and the password of the URL are
+    /// replaced with `[REDACTED]`. Text bodies are inlined, while binary
+    /// and streaming bodies are read from a `body.bin` file, which has to
+    /// be written separately.
+    ///
+    /// Headers added by the `Client`, such as default headers, aren't
+    /// included.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "rustls-tls-no-provider"))]
+    /// # {
+    /// let req = reqwest::blocking::Client::new()
+    ///     .post("https://httpbin.org/post")
+    ///     .bearer_auth("secret")
+    ///     .body("it's here")
+    ///     .build()?;
+    /// assert_eq!(
+    ///     req.to_curl(),
+    ///     r"curl -X POST https://httpbin.org/post -H 'authorization: [REDACTED]' --data-raw 'it'\''s here'",
+    /// );
+    /// # }
+    /// # Ok::<(), reqwest::Error>(())
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `curl-debug` feature to be enabled.
+    #[cfg(feature = "curl-debug")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "curl-debug")))]
+    pub fn to_curl(&self) -> String {
+        crate::curl_debug::format(crate::curl_debug::Parts {
+            method: self.method(),
+            url: self.url(),
+            version: self.version(),
+            headers: self.headers(),
+            body: self.body().map(|body| body.as_bytes()),
+            timeout: self.timeout().copied(),
+        })
+    }
+
     /// Attempts to clone the `Request`.
     ///
     /// None is returned if a body is which can not be cloned. This can be because the body is a
@@ -130,6 +288,18 @@ impl Request {
         let mut req = Request::new(self.method().clone(), self.url().clone());
         *req.headers_mut() = self.headers().clone();
         *req.version_mut() = self.version().clone();
+        *req.max_redirects_mut() = self.max_redirects();
+        *req.hedge_after_mut() = self.hedge_after();
+        *req.expect_continue_mut() = self.expect_continue();
+        *req.accept_encoding_mut() = self.accept_encoding();
+        *req.max_upload_rate_mut() = self.max_upload_rate();
+        *req.max_download_rate_mut() = self.max_download_rate();
+        *req.resolve_map_mut() = self.resolve_map().cloned();
+        #[cfg(feature = "cookies")]
+        {
+            *req.cookie_store_mut() = self.cookie_store().cloned();
+        }
+        *req.inner.on_informational_mut() = self.inner.on_informational().cloned();
         req.body = body;
         Some(req)
     }
@@ -219,7 +389,7 @@ impl RequestBuilder {
                         }
                         req.headers_mut().append(key, value);
                     }
-                    Err(e) => error = Some(crate::error::builder(e.into())),
+                    Err(e) => error = Some(crate::error::invalid_header_value(key, e.into())),
                 },
                 Err(e) => error = Some(crate::error::builder(e.into())),
             };
@@ -301,6 +471,106 @@ impl RequestBuilder {
         self.header_sensitive(crate::header::AUTHORIZATION, &*header_value, true)
     }
 
+    /// Set the `Host` header, independently of the URL.
+    ///
+    /// The request is still sent to the host of the URL, but servers see
+    /// `host` instead. This is useful for gateways that route requests by a
+    /// virtual host name different from the address they're reached at,
+    /// such as a shared ingress reached by IP address.
+    ///
+    /// `host` must be a host name or IP address, with an optional port.
+    /// The header isn't sent to another host after a redirect.
+    ///
+    /// This only affects HTTP/1 requests: HTTP/2 and HTTP/3 send the host
+    /// of the URL as the `:authority` pseudo-header.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `host` isn't a valid host and port.
+    pub fn host_header(mut self, host: &str) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match crate::util::host_header_value(host) {
+                Ok(value) => {
+                    req.headers_mut().insert(crate::header::HOST, value);
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
+    /// Set the priority of the request (RFC 9218).
+    ///
+    /// This sets the `Priority` header, with an `urgency` from 0, the
+    /// highest, to 7, the lowest. See
+    /// [`RequestBuilder::priority()`](crate::RequestBuilder::priority) for
+    /// details.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `urgency` is greater than 7.
+    pub fn priority(mut self, urgency: u8, incremental: bool) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match crate::util::priority_header_value(urgency, incremental) {
+                Ok(value) => {
+                    req.headers_mut()
+                        .insert(HeaderName::from_static("priority"), value);
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
+    /// Send the request only if the resource doesn't match `etag` anymore.
+    ///
+    /// This sets the `If-None-Match` header, to an entity tag that was
+    /// received in the `ETag` header of a previous response, or to `*`. If
+    /// the resource still matches, the server answers with
+    /// `304 Not Modified` and no body, see
+    /// [`Response::is_not_modified()`](super::Response::is_not_modified).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::blocking::Client::new()
+    ///     .get("https://example.com/feed.xml")
+    ///     .if_none_match("\"xyzzy\"")
+    ///     .send()?;
+    /// if res.is_not_modified() {
+    ///     // use the stored copy
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn if_none_match<V>(self, etag: V) -> RequestBuilder
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.header(crate::header::IF_NONE_MATCH, etag)
+    }
+
+    /// Send the request only if the resource was modified after `time`.
+    ///
+    /// This sets the `If-Modified-Since` header. Otherwise, the server
+    /// answers with `304 Not Modified` and no body.
+    pub fn if_modified_since(self, time: SystemTime) -> RequestBuilder {
+        self.header(
+            crate::header::IF_MODIFIED_SINCE,
+            httpdate::fmt_http_date(time),
+        )
+    }
+
     /// Set the request body.
     ///
     /// # Examples
@@ -351,6 +621,50 @@ impl RequestBuilder {
         self
     }
 
+    /// Set the request body, and a `Content-Type` inferred from it.
+    ///
+    /// Bytes and strings are sniffed for common types, such as JSON, XML
+    /// and PNG. Paths are read from the filesystem, and their type is
+    /// inferred from the file extension. Anything unrecognized is sent as
+    /// `application/octet-stream`.
+    ///
+    /// The `Content-Type` is only set if the request doesn't have one yet.
+    ///
+    /// ```rust
+    /// # use reqwest::Error;
+    /// #
+    /// # fn run() -> Result<(), Error> {
+    /// let client = reqwest::blocking::Client::new();
+    /// let res = client.post("http://httpbin.org/post")
+    ///     .body_with_inferred_type(r#"{"lang": "rust"}"#)
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a path can't be read.
+    pub fn body_with_inferred_type<T: IntoInferredBody>(mut self, body: T) -> RequestBuilder {
+        let mut error = None;
+        if let Ok(ref mut req) = self.request {
+            match body.into_inferred() {
+                Ok((body, mime)) => {
+                    if !req.headers().contains_key(CONTENT_TYPE) {
+                        req.headers_mut()
+                            .insert(CONTENT_TYPE, HeaderValue::from_static(mime));
+                    }
+                    *req.body_mut() = Some(body.into());
+                }
+                Err(err) => error = Some(crate::error::builder(err)),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
+        }
+        self
+    }
+
     /// Enables a request timeout.
     ///
     /// The timeout is applied from when the request starts connecting until the
@@ -363,6 +677,140 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets the maximum number of redirects to follow for this request.
+    ///
+    /// This behaves like [`redirect::Policy::limited()`], and replaces the
+    /// maximum of the `Client`'s policy.
+    ///
+    /// [`redirect::Policy::limited()`]: crate::redirect::Policy::limited
+    pub fn max_redirects(mut self, max: usize) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.max_redirects_mut() = Some(max);
+        }
+        self
+    }
+
+    /// Hedge this request: if no response arrived after `delay`, send a
+    /// second, identical request, and use whichever response arrives first.
+    /// The other request is cancelled.
+    ///
+    /// The second request may reach the server even if the first one
+    /// succeeds, so this must only be used for requests that are safe to
+    /// send twice. Requests with a body that can't be cloned, such as one
+    /// created from a reader, are never hedged.
+    pub fn hedge_after(mut self, delay: Duration) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.hedge_after_mut() = Some(delay);
+        }
+        self
+    }
+
+    /// Send `Expect: 100-continue`, and hold back the body until the server
+    /// answers `100 Continue`, or `timeout` elapses.
+    ///
+    /// If a final response arrives first, the body is never sent, and that
+    /// response is returned. Requests without a body are sent as usual.
+    pub fn expect_continue(mut self, timeout: Duration) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.expect_continue_mut() = Some(timeout);
+        }
+        self
+    }
+
+    /// Limits the rate at which the body of this request is sent, in bytes
+    /// per second.
+    ///
+    /// See [`async_impl::RequestBuilder::max_upload_rate`](crate::RequestBuilder::max_upload_rate)
+    /// for details.
+    pub fn max_upload_rate(mut self, bytes_per_sec: u64) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.max_upload_rate_mut() = Some(bytes_per_sec);
+        }
+        self
+    }
+
+    /// Limits the rate at which the response body is received, in bytes
+    /// per second.
+    ///
+    /// See [`async_impl::RequestBuilder::max_download_rate`](crate::RequestBuilder::max_download_rate)
+    /// for details.
+    pub fn max_download_rate(mut self, bytes_per_sec: u64) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.max_download_rate_mut() = Some(bytes_per_sec);
+        }
+        self
+    }
+
+    /// Don't add the `Accept-Encoding` header of the client to this request.
+    ///
+    /// The server then sends the response without a content coding, unless
+    /// the request has its own `Accept-Encoding` header.
+    pub fn no_accept_encoding(mut self) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.accept_encoding_mut() = false;
+        }
+        self
+    }
+
+    /// Override DNS resolution for specific domains, for this request only.
+    ///
+    /// The overrides take precedence over those of the `Client`, and also
+    /// apply to redirects. The port of the URL is used instead of the port
+    /// of the addresses. Requests sent through a proxy aren't affected.
+    ///
+    /// The connections to the overridden addresses are kept in a separate
+    /// pool of the `Client`, one for each distinct map.
+    pub fn resolve_map(mut self, overrides: HashMap<String, Vec<SocketAddr>>) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            let overrides = overrides
+                .into_iter()
+                .map(|(domain, addrs)| (domain.to_ascii_lowercase(), addrs))
+                .collect();
+            *req.resolve_map_mut() = Some(overrides);
+        }
+        self
+    }
+
+    /// Call a function with each informational (`1xx`) response that is
+    /// received before the final response, such as `103 Early Hints`.
+    ///
+    /// The function gets the status and headers of the interim response.
+    /// `101 Switching Protocols` is a final response, and isn't passed to
+    /// the function. Informational responses are only reported for HTTP/1.
+    ///
+    /// The function is called from the runtime thread of the `Client`, not
+    /// the thread that sends the request.
+    pub fn on_informational<F>(mut self, f: F) -> RequestBuilder
+    where
+        F: Fn(StatusCode, &HeaderMap) + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            *req.inner.on_informational_mut() = Some(Arc::new(f));
+        }
+        self
+    }
+
+    /// Use a cookie store for this request, instead of the one of the
+    /// `Client`.
+    ///
+    /// The cookies of the store are sent with the request and its
+    /// redirects, and the cookies set by the responses are saved in it.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cookies` feature to be enabled.
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    pub fn cookie_jar<C: crate::cookie::CookieStore + 'static>(
+        mut self,
+        cookie_store: Arc<C>,
+    ) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            *req.cookie_store_mut() = Some(cookie_store as _);
+        }
+        self
+    }
+
     /// Modify the query string of the URL.
     ///
     /// Modifies the URL of this request, adding the parameters provided.
@@ -499,13 +947,20 @@ impl RequestBuilder {
     /// # Errors
     ///
     /// Serialization can fail if `T`'s implementation of `Serialize` decides to
-    /// fail, or if `T` contains a map with non-string keys.
+    /// fail, or if `T` contains a map with non-string keys. With
+    /// [`ClientBuilder::canonical_json()`](super::ClientBuilder::canonical_json),
+    /// it also fails if `T` contains an integer beyond ±2^53.
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> RequestBuilder {
         let mut error = None;
         if let Ok(ref mut req) = self.request {
-            match serde_json::to_vec(json) {
+            let body = if self.client.canonical_json() {
+                crate::json_canonical::to_vec(json)
+            } else {
+                serde_json::to_vec(json).map_err(crate::error::builder)
+            };
+            match body {
                 Ok(body) => {
                     if !req.headers().contains_key(CONTENT_TYPE) {
                         req.headers_mut()
@@ -513,7 +968,7 @@ impl RequestBuilder {
                     }
                     *req.body_mut() = Some(body.into());
                 }
-                Err(err) => error = Some(crate::error::builder(err)),
+                Err(err) => error = Some(err),
             }
         }
         if let Some(err) = error {
@@ -582,6 +1037,35 @@ impl RequestBuilder {
         self.client.execute(self.request?)
     }
 
+    /// Constructs the Request, returning a handle to send it and wait for
+    /// the Response, and a handle to cancel it from another thread.
+    ///
+    /// The request is sent when [`PendingResponse::wait()`](super::PendingResponse::wait)
+    /// is called.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let client = reqwest::blocking::Client::new();
+    /// let (pending, cancel) = client.get("http://httpbin.org/delay/10").send_cancellable();
+    ///
+    /// std::thread::spawn(move || {
+    ///     std::thread::sleep(std::time::Duration::from_secs(1));
+    ///     cancel.cancel();
+    /// });
+    ///
+    /// let err = pending.wait().unwrap_err();
+    /// assert!(err.is_canceled());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_cancellable(self) -> (super::PendingResponse, super::CancelHandle) {
+        let pending = super::PendingResponse::new(self.client, self.request);
+        let cancel = pending.cancel_handle();
+        (pending, cancel)
+    }
+
     /// Attempts to clone the `RequestBuilder`.
     ///
     /// None is returned if a body is which can not be cloned. This can be because the body is a
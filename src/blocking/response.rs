@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fmt;
 use std::io::{self, Read};
 use std::mem;
@@ -7,7 +8,7 @@ use std::time::Duration;
 
 use bytes::Bytes;
 use http;
-use hyper::header::HeaderMap;
+use hyper::header::{HeaderMap, HeaderName};
 #[cfg(feature = "json")]
 use serde::de::DeserializeOwned;
 
@@ -93,6 +94,13 @@ impl Response {
         self.inner.status()
     }
 
+    /// Returns true if the status is `304 Not Modified`.
+    ///
+    /// See [`async_impl::Response::is_not_modified`] for details.
+    pub fn is_not_modified(&self) -> bool {
+        self.inner.is_not_modified()
+    }
+
     /// Get the `Headers` of this `Response`.
     ///
     /// # Example
@@ -122,6 +130,13 @@ impl Response {
         self.inner.headers()
     }
 
+    /// Get the `Headers` of this `Response`, with values decoded as strings.
+    ///
+    /// Invalid UTF-8 sequences are replaced by the REPLACEMENT CHARACTER.
+    pub fn headers_lossy(&self) -> impl Iterator<Item = (&HeaderName, Cow<'_, str>)> + '_ {
+        self.inner.headers_lossy()
+    }
+
     /// Get a mutable reference to the `Headers` of this `Response`.
     #[inline]
     pub fn headers_mut(&mut self) -> &mut HeaderMap {
@@ -178,6 +193,77 @@ impl Response {
         self.inner.remote_addr()
     }
 
+    /// Get the local address used to get this `Response`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let resp = reqwest::blocking::get("http://httpbin.org/ip")?;
+    /// println!("local address: {:?}", resp.local_addr());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Get the number of attempts made to get this `Response`.
+    ///
+    /// This is `1`, plus the number of times a request was automatically
+    /// retried.
+    pub fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+
+    /// Get the number of redirects followed to get this `Response`.
+    pub fn redirects_followed(&self) -> usize {
+        self.inner.redirects_followed()
+    }
+
+    /// Get the `Retry-After` delays the client waited for, in order.
+    pub fn retry_after_waits(&self) -> &[crate::redirect::Wait] {
+        self.inner.retry_after_waits()
+    }
+
+    /// Get the headers sent with the request that got this `Response`.
+    ///
+    /// After redirects, these are the headers of the last request.
+    pub fn request_headers(&self) -> Option<&HeaderMap> {
+        self.inner.request_headers()
+    }
+
+    /// Get the header lines of this `Response`, in the order and casing they
+    /// were received.
+    ///
+    /// This is only available for HTTP/1 responses, when the `Client` was
+    /// built with
+    /// [`http1_preserve_raw_headers()`](crate::blocking::ClientBuilder::http1_preserve_raw_headers).
+    pub fn raw_headers(&self) -> Option<&crate::raw_headers::RawHeaders> {
+        self.inner.raw_headers()
+    }
+
+    /// Parse the `Server-Timing` headers of this `Response`.
+    ///
+    /// Malformed metrics are ignored.
+    pub fn server_timing(&self) -> Vec<crate::server_timing::Metric> {
+        self.inner.server_timing()
+    }
+
+    /// Parse the `Deprecation` and `Sunset` headers of this `Response`.
+    ///
+    /// Returns `None` if the endpoint didn't announce a deprecation.
+    pub fn deprecation(&self) -> Option<crate::notice::Deprecation> {
+        self.inner.deprecation()
+    }
+
+    /// Parse the `Warning` headers of this `Response`.
+    ///
+    /// Malformed warnings are ignored.
+    pub fn warnings(&self) -> Vec<crate::notice::Warning> {
+        self.inner.warnings()
+    }
+
     /// Returns a reference to the associated extensions.
     pub fn extensions(&self) -> &http::Extensions {
         self.inner.extensions()
@@ -199,6 +285,18 @@ impl Response {
         self.inner.content_length()
     }
 
+    /// Get the content coding of the response, like `gzip`, if the body is
+    /// automatically decoded.
+    pub fn content_encoding(&self) -> Option<&str> {
+        self.inner.content_encoding()
+    }
+
+    /// Get the `Content-Length` of the response before it was automatically
+    /// decoded, if the server sent one.
+    pub fn compressed_length(&self) -> Option<u64> {
+        self.inner.compressed_length()
+    }
+
     /// Try and deserialize the response body as JSON using `serde`.
     ///
     /// # Optional
@@ -244,6 +342,30 @@ impl Response {
         })
     }
 
+    /// Try to deserialize the response body as JSON, reporting where in the
+    /// document deserialization failed.
+    ///
+    /// This is like [`json()`](Response::json), but if the body cannot be
+    /// deserialized to `T`, the message of the error's source starts with
+    /// the path of the value that didn't match, such as `data.items[3].id`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not in JSON format,
+    /// or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_with_path<T: DeserializeOwned>(self) -> crate::Result<T> {
+        wait::timeout(self.inner.json_with_path(), self.timeout).map_err(|e| match e {
+            wait::Waited::TimedOut(e) => crate::error::decode(e),
+            wait::Waited::Inner(e) => e,
+        })
+    }
+
     /// Get the full response body as `Bytes`.
     ///
     /// # Example
@@ -263,6 +385,76 @@ impl Response {
         })
     }
 
+    /// Split a `multipart/*` response body into its parts.
+    ///
+    /// The parts, each with its own headers, are read one at a time from the
+    /// returned iterator. Servers answer a `GET` for several ranges with a
+    /// `multipart/byteranges` body.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the `Content-Type` of the response isn't
+    /// multipart with a boundary. The iterator yields an error if the body
+    /// isn't valid multipart, or ends before its closing delimiter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = reqwest::blocking::Client::new()
+    ///     .get("http://httpbin.org/range/100")
+    ///     .header("range", "bytes=0-9, 50-59")
+    ///     .send()?;
+    /// for part in res.multipart()? {
+    ///     let part = part?;
+    ///     println!("{:?}: {:?}", part.content_range(), part.body());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `multipart` feature to be enabled.
+    #[cfg(feature = "multipart")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    pub fn multipart(self) -> crate::Result<super::multipart::Parts> {
+        let Response {
+            inner,
+            timeout,
+            _thread_handle,
+            ..
+        } = self;
+        Ok(super::multipart::Parts::new(
+            inner.multipart()?,
+            timeout,
+            _thread_handle,
+        ))
+    }
+
+    /// Verify the body against a digest while it's read.
+    ///
+    /// Reading the body fails at its end if the digest doesn't match. This
+    /// has to be called before the body is read.
+    ///
+    /// See [`async_impl::Response::verify_digest`] for details.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `digest` feature to be enabled.
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    pub fn verify_digest(
+        self,
+        algorithm: crate::digest::Algorithm,
+        expected: impl AsRef<[u8]>,
+    ) -> Response {
+        Response {
+            inner: self.inner.verify_digest(algorithm, expected),
+            ..self
+        }
+    }
+
     /// Get the response text.
     ///
     /// This method decodes the response body with BOM sniffing
@@ -384,6 +576,44 @@ impl Response {
         })
     }
 
+    /// Turn a response into an error if the server returned an error,
+    /// keeping up to `limit` bytes of its body in the error.
+    ///
+    /// The start of the body is available from
+    /// [`Error::status_body()`](crate::Error::status_body).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let res = reqwest::blocking::get("http://httpbin.org/status/404")?;
+    /// if let Err(err) = res.error_for_status_with_body(1024) {
+    ///     let body = err.status_body().unwrap_or_default();
+    ///     eprintln!("{err}: {}", String::from_utf8_lossy(body));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn error_for_status_with_body(self, limit: usize) -> crate::Result<Self> {
+        let Response {
+            body,
+            inner,
+            timeout,
+            _thread_handle,
+        } = self;
+        wait::timeout(inner.error_for_status_with_body(limit), timeout)
+            .map_err(|e| match e {
+                wait::Waited::TimedOut(e) => crate::error::decode(e),
+                wait::Waited::Inner(e) => e,
+            })
+            .map(move |inner| Response {
+                inner,
+                body,
+                timeout,
+                _thread_handle,
+            })
+    }
+
     /// Turn a reference to a response into an error if the server returned an error.
     ///
     /// # Example
@@ -404,6 +634,37 @@ impl Response {
         self.inner.error_for_status_ref().and_then(|_| Ok(self))
     }
 
+    /// Turn a response into an error if its `Content-Type` doesn't match
+    /// `expected`.
+    ///
+    /// See [`reqwest::Response::ensure_content_type()`](crate::Response::ensure_content_type)
+    /// for how the content type is matched.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let text = reqwest::blocking::get("http://httpbin.org/html")?
+    ///     .ensure_content_type("text/html")?
+    ///     .text()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ensure_content_type(self, expected: &str) -> crate::Result<Self> {
+        let Response {
+            body,
+            inner,
+            timeout,
+            _thread_handle,
+        } = self;
+        inner.ensure_content_type(expected).map(move |inner| Response {
+            inner,
+            body,
+            timeout,
+            _thread_handle,
+        })
+    }
+
     // private
 
     fn body_mut(&mut self) -> Pin<&mut dyn futures_util::io::AsyncRead> {
@@ -0,0 +1,197 @@
+//! Capability probing
+//!
+//! REST tooling often needs to know what a server supports before deciding
+//! how to talk to it, for instance whether to update a resource with `PATCH`
+//! or `PUT`. An `OPTIONS` request answers that with the `Allow`,
+//! `Accept-Patch` and `Accept-Ranges` headers.
+//!
+//! [`Client::capabilities()`](crate::Client::capabilities) sends that
+//! request, parses those headers into [`Capabilities`], and caches the result
+//! per origin, so that it can be asked before every request.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use http::header::{HeaderMap, HeaderName, ACCEPT_RANGES, ALLOW};
+use http::{Method, StatusCode};
+
+use crate::{Client, IntoUrl};
+
+/// The capabilities an origin announced in response to an `OPTIONS` request.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    allow: Vec<Method>,
+    accept_patch: Vec<String>,
+    accept_ranges: Vec<String>,
+}
+
+/// The capabilities of each origin probed by a `Client`.
+pub(crate) struct Cache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Capabilities)>>,
+}
+
+// ===== impl Capabilities =====
+
+impl Capabilities {
+    /// Parses the capabilities from the headers of a response.
+    pub fn from_headers(headers: &HeaderMap) -> Capabilities {
+        Capabilities {
+            allow: list(headers, &ALLOW)
+                .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+                .collect(),
+            accept_patch: list(headers, &HeaderName::from_static("accept-patch"))
+                .map(str::to_owned)
+                .collect(),
+            accept_ranges: list(headers, &ACCEPT_RANGES)
+                .map(str::to_ascii_lowercase)
+                .collect(),
+        }
+    }
+
+    /// The methods listed in the `Allow` header.
+    pub fn allowed_methods(&self) -> &[Method] {
+        &self.allow
+    }
+
+    /// Returns true if `method` is listed in the `Allow` header.
+    pub fn allows(&self, method: &Method) -> bool {
+        self.allow.contains(method)
+    }
+
+    /// The media types listed in the `Accept-Patch` header.
+    pub fn accept_patch(&self) -> &[String] {
+        &self.accept_patch
+    }
+
+    /// Returns true if `PATCH` is allowed, and accepts documents of
+    /// `media_type`, such as `application/merge-patch+json`.
+    ///
+    /// Parameters of the listed media types are ignored.
+    pub fn accepts_patch(&self, media_type: &str) -> bool {
+        self.allows(&Method::PATCH)
+            && self.accept_patch.iter().any(|accepted| {
+                let essence = accepted.split(';').next().unwrap_or_default().trim();
+                essence.eq_ignore_ascii_case(media_type)
+            })
+    }
+
+    /// The range units listed in the `Accept-Ranges` header.
+    pub fn accept_ranges(&self) -> &[String] {
+        &self.accept_ranges
+    }
+
+    /// Returns true if the origin announced support for byte ranges.
+    pub fn accepts_byte_ranges(&self) -> bool {
+        self.accept_ranges.iter().any(|unit| unit == "bytes")
+    }
+}
+
+impl fmt::Debug for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Capabilities")
+            .field("allow", &self.allow)
+            .field("accept_patch", &self.accept_patch)
+            .field("accept_ranges", &self.accept_ranges)
+            .finish()
+    }
+}
+
+/// Iterates the comma separated elements of all `name` headers.
+fn list<'a>(headers: &'a HeaderMap, name: &HeaderName) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|element| !element.is_empty())
+}
+
+// ===== impl Cache =====
+
+impl Cache {
+    pub(crate) fn new(ttl: Duration) -> Cache {
+        Cache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, origin: &str) -> Option<Capabilities> {
+        let entries = self.entries.lock().expect("lock poisoned");
+        match entries.get(origin) {
+            Some((at, caps)) if at.elapsed() < self.ttl => Some(caps.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert(&self, origin: String, caps: Capabilities) {
+        let mut entries = self.entries.lock().expect("lock poisoned");
+        entries.retain(|_, (at, _)| at.elapsed() < self.ttl);
+        entries.insert(origin, (Instant::now(), caps));
+    }
+}
+
+pub(crate) async fn capabilities<U: IntoUrl>(
+    client: &Client,
+    origin: U,
+) -> crate::Result<Capabilities> {
+    let mut url = origin.into_url()?;
+    let origin = url.origin().ascii_serialization();
+    if let Some(caps) = client.capabilities_cache().get(&origin) {
+        return Ok(caps);
+    }
+
+    url.set_path("/");
+    url.set_query(None);
+    url.set_fragment(None);
+    let res = client.request(Method::OPTIONS, url).send().await?;
+    // a `405 Method Not Allowed` still lists the allowed methods
+    let res = if res.status() == StatusCode::METHOD_NOT_ALLOWED {
+        res
+    } else {
+        res.error_for_status()?
+    };
+
+    let caps = Capabilities::from_headers(res.headers());
+    client.capabilities_cache().insert(origin, caps.clone());
+    Ok(caps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ALLOW, "GET, HEAD,PATCH".parse().unwrap());
+        headers.append(ALLOW, "PUT".parse().unwrap());
+        headers.insert(
+            "accept-patch",
+            "application/merge-patch+json;charset=utf-8, application/json-patch+json"
+                .parse()
+                .unwrap(),
+        );
+        headers.insert(ACCEPT_RANGES, "Bytes".parse().unwrap());
+
+        let caps = Capabilities::from_headers(&headers);
+        assert_eq!(
+            caps.allowed_methods(),
+            [Method::GET, Method::HEAD, Method::PATCH, Method::PUT]
+        );
+        assert!(caps.allows(&Method::PUT));
+        assert!(!caps.allows(&Method::DELETE));
+        assert!(caps.accepts_patch("application/merge-patch+json"));
+        assert!(!caps.accepts_patch("text/plain"));
+        assert!(caps.accepts_byte_ranges());
+
+        assert_eq!(
+            Capabilities::from_headers(&HeaderMap::new()),
+            Capabilities::default()
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Circuit breaking
+//!
+//! When an origin starts failing, sending it more requests usually only
+//! adds load to a server that is already struggling, and makes callers
+//! wait for errors. A circuit breaker, configured with
+//! [`ClientBuilder::circuit_breaker()`](crate::ClientBuilder::circuit_breaker),
+//! counts the consecutive failures of each origin. Once the
+//! [failure threshold](CircuitBreaker::failure_threshold) is reached, the
+//! breaker "opens", and requests to that origin fail immediately with an
+//! error for which [`Error::is_circuit_open()`](crate::Error::is_circuit_open)
+//! returns true.
+//!
+//! After the [open duration](CircuitBreaker::open_duration), the breaker is
+//! "half-open": a few [probe](CircuitBreaker::half_open_probes) requests are
+//! let through. If they all succeed the breaker closes again, and if any
+//! fails it opens for another open duration.
+//!
+//! Errors sending a request, including timeouts, and responses with a `5xx`
+//! status count as failures. Only the first response of a request counts,
+//! for the origin of the request URL, and redirects aren't tracked.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use reqwest::circuit::CircuitBreaker;
+//!
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder()
+//!     .circuit_breaker(
+//!         CircuitBreaker::new()
+//!             .failure_threshold(5)
+//!             .open_duration(Duration::from_secs(30)),
+//!     )
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::StatusCode;
+use url::Url;
+
+/// The configuration of a circuit breaker.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    half_open_probes: u32,
+}
+
+/// The circuit breakers of each origin a `Client` talks to.
+pub(crate) struct Breakers {
+    config: CircuitBreaker,
+    origins: Mutex<HashMap<String, State>>,
+}
+
+/// Permission to send a request while a breaker isn't open, which records
+/// the outcome of the request.
+pub(crate) struct Permit {
+    breakers: Arc<Breakers>,
+    origin: String,
+    probe: bool,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { in_flight: u32, successes: u32 },
+}
+
+// ===== impl CircuitBreaker =====
+
+impl CircuitBreaker {
+    /// Creates a circuit breaker with the default configuration.
+    ///
+    /// By default, a breaker opens after 5 consecutive failures, stays open
+    /// for 30 seconds, and then lets 1 probe request through.
+    pub fn new() -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+            half_open_probes: 1,
+        }
+    }
+
+    /// Sets how many consecutive failures open the breaker of an origin.
+    ///
+    /// Default is 5.
+    pub fn failure_threshold(mut self, failures: u32) -> CircuitBreaker {
+        self.failure_threshold = failures.max(1);
+        self
+    }
+
+    /// Sets how long a breaker stays open before letting probe requests
+    /// through.
+    ///
+    /// Default is 30 seconds.
+    pub fn open_duration(mut self, duration: Duration) -> CircuitBreaker {
+        self.open_duration = duration;
+        self
+    }
+
+    /// Sets how many probe requests a half-open breaker lets through at
+    /// once, which must all succeed to close it.
+    ///
+    /// Default is 1.
+    pub fn half_open_probes(mut self, probes: u32) -> CircuitBreaker {
+        self.half_open_probes = probes.max(1);
+        self
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> CircuitBreaker {
+        CircuitBreaker::new()
+    }
+}
+
+// ===== impl Breakers =====
+
+impl Breakers {
+    pub(crate) fn new(config: CircuitBreaker) -> Breakers {
+        Breakers {
+            config,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns a permit to send a request to `url`, or `None` if the
+    /// breaker of its origin is open.
+    pub(crate) fn acquire(self: &Arc<Self>, url: &Url) -> Option<Permit> {
+        let origin = url.origin().ascii_serialization();
+        let mut origins = self.origins.lock().unwrap();
+        let state = origins
+            .entry(origin.clone())
+            .or_insert(State::Closed { failures: 0 });
+
+        if let State::Open { until } = *state {
+            if Instant::now() < until {
+                return None;
+            }
+            *state = State::HalfOpen {
+                in_flight: 0,
+                successes: 0,
+            };
+        }
+
+        let probe = match state {
+            State::Closed { .. } => false,
+            State::HalfOpen {
+                in_flight,
+                successes,
+            } => {
+                if *in_flight + *successes >= self.config.half_open_probes {
+                    return None;
+                }
+                *in_flight += 1;
+                true
+            }
+            State::Open { .. } => unreachable!("checked above"),
+        };
+
+        Some(Permit {
+            breakers: self.clone(),
+            origin,
+            probe,
+        })
+    }
+
+    fn record(&self, origin: &str, probe: bool, success: bool) {
+        let mut origins = self.origins.lock().unwrap();
+        let state = match origins.get_mut(origin) {
+            Some(state) => state,
+            None => return,
+        };
+
+        match state {
+            State::Closed { failures } if success => *failures = 0,
+            State::Closed { failures } => {
+                *failures += 1;
+                if *failures >= self.config.failure_threshold {
+                    log::debug!("circuit breaker for {origin} opened");
+                    *state = self.open();
+                }
+            }
+            State::HalfOpen { .. } if !probe => (),
+            State::HalfOpen { .. } if !success => {
+                log::debug!("circuit breaker for {origin} opened again");
+                *state = self.open();
+            }
+            State::HalfOpen {
+                in_flight,
+                successes,
+            } => {
+                *in_flight -= 1;
+                *successes += 1;
+                if *successes >= self.config.half_open_probes {
+                    log::debug!("circuit breaker for {origin} closed");
+                    *state = State::Closed { failures: 0 };
+                }
+            }
+            State::Open { .. } => (),
+        }
+    }
+
+    /// Frees the slot of a probe that was dropped without an outcome.
+    fn release(&self, origin: &str) {
+        let mut origins = self.origins.lock().unwrap();
+        if let Some(State::HalfOpen { in_flight, .. }) = origins.get_mut(origin) {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+
+    fn open(&self) -> State {
+        State::Open {
+            until: Instant::now() + self.config.open_duration,
+        }
+    }
+}
+
+impl fmt::Debug for Breakers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.config.fmt(f)
+    }
+}
+
+// ===== impl Permit =====
+
+impl Permit {
+    /// Records the status of the response.
+    pub(crate) fn response(self, status: StatusCode) {
+        self.finish(!status.is_server_error());
+    }
+
+    /// Records an error sending the request.
+    pub(crate) fn error(self) {
+        self.finish(false);
+    }
+
+    fn finish(mut self, success: bool) {
+        self.breakers.record(&self.origin, self.probe, success);
+        // recorded, nothing to release
+        self.probe = false;
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if self.probe {
+            self.breakers.release(&self.origin);
+        }
+    }
+}
+
+impl fmt::Debug for Permit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Permit")
+            .field("origin", &self.origin)
+            .field("probe", &self.probe)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breakers(config: CircuitBreaker) -> Arc<Breakers> {
+        Arc::new(Breakers::new(config))
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breakers = breakers(CircuitBreaker::new().failure_threshold(2));
+        let url = Url::parse("http://example.com/a").unwrap();
+        let other = Url::parse("http://example.com:8080/").unwrap();
+
+        breakers.acquire(&url).unwrap().error();
+        breakers.acquire(&url).unwrap().response(StatusCode::OK);
+        breakers.acquire(&url).unwrap().error();
+        assert!(breakers.acquire(&url).is_some());
+
+        breakers
+            .acquire(&url)
+            .unwrap()
+            .response(StatusCode::SERVICE_UNAVAILABLE);
+        assert!(breakers.acquire(&url).is_none());
+        assert!(breakers.acquire(&url.join("/b").unwrap()).is_none());
+        assert!(breakers.acquire(&other).is_some());
+    }
+
+    #[test]
+    fn half_open_probes() {
+        let config = CircuitBreaker::new()
+            .failure_threshold(1)
+            .open_duration(Duration::ZERO)
+            .half_open_probes(2);
+        let breakers = breakers(config);
+        let url = Url::parse("https://example.com/").unwrap();
+
+        breakers.acquire(&url).unwrap().error();
+
+        // a failed probe opens it again
+        let probe = breakers.acquire(&url).unwrap();
+        probe.error();
+
+        let first = breakers.acquire(&url).unwrap();
+        let second = breakers.acquire(&url).unwrap();
+        assert!(breakers.acquire(&url).is_none());
+
+        // a dropped probe frees its slot
+        drop(second);
+        let second = breakers.acquire(&url).unwrap();
+
+        first.response(StatusCode::OK);
+        assert!(breakers.acquire(&url).is_none());
+        second.response(StatusCode::NOT_FOUND);
+
+        // closed
+        let permits = (0..3).map(|_| breakers.acquire(&url).unwrap());
+        assert_eq!(permits.count(), 3);
+    }
+}
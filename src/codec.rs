@@ -0,0 +1,442 @@
+//! Custom content codings
+//!
+//! Besides the built-in `gzip`, `br`, `zstd` and `deflate` decoders, a
+//! `Client` can be taught other content codings, such as the `lz4` or
+//! `snappy` compression used by some internal services. A [`Codec`]
+//! registered with
+//! [`ClientBuilder::content_codec()`](crate::ClientBuilder::content_codec)
+//! is advertised in the `Accept-Encoding` header, decodes response bodies
+//! with a matching `Content-Encoding`, and can encode request bodies with
+//! [`RequestBuilder::encode_body()`](crate::RequestBuilder::encode_body).
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use bytes::BytesMut;
+//! use reqwest::codec::{Codec, Transform};
+//!
+//! /// A "coding" that reverses every byte, to keep the example short.
+//! struct Invert;
+//!
+//! impl Transform for Invert {
+//!     fn transform(&mut self, input: &[u8], output: &mut BytesMut) -> io::Result<()> {
+//!         output.extend(input.iter().map(|b| !b));
+//!         Ok(())
+//!     }
+//!
+//!     fn finish(&mut self, _output: &mut BytesMut) -> io::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! struct InvertCodec;
+//!
+//! impl Codec for InvertCodec {
+//!     fn decoder(&self) -> Box<dyn Transform> {
+//!         Box::new(Invert)
+//!     }
+//!
+//!     fn encoder(&self) -> Option<Box<dyn Transform>> {
+//!         Some(Box::new(Invert))
+//!     }
+//! }
+//!
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder()
+//!     .content_codec("x-invert", InvertCodec)
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+//!
+//! A codec that only decodes responses can also be a closure creating the
+//! decoder:
+//!
+//! ```
+//! # use std::io;
+//! # use bytes::BytesMut;
+//! # use reqwest::codec::Transform;
+//! # struct Invert;
+//! # impl Transform for Invert {
+//! #     fn transform(&mut self, input: &[u8], output: &mut BytesMut) -> io::Result<()> {
+//! #         output.extend(input.iter().map(|b| !b));
+//! #         Ok(())
+//! #     }
+//! #     fn finish(&mut self, _output: &mut BytesMut) -> io::Result<()> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder()
+//!     .content_codec("x-invert", || Box::new(Invert) as Box<dyn Transform>)
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use http::HeaderValue;
+use http_body::{Body as HttpBody, Frame};
+use pin_project_lite::pin_project;
+
+use crate::error::BoxError;
+
+/// A content coding, which creates a [`Transform`] for each body.
+pub trait Codec: Send + Sync + 'static {
+    /// Creates a transform that decodes a body.
+    fn decoder(&self) -> Box<dyn Transform>;
+
+    /// Creates a transform that encodes a body.
+    ///
+    /// Codecs that only decode responses can keep the default, which
+    /// returns `None`.
+    fn encoder(&self) -> Option<Box<dyn Transform>> {
+        None
+    }
+}
+
+impl<F> Codec for F
+where
+    F: Fn() -> Box<dyn Transform> + Send + Sync + 'static,
+{
+    fn decoder(&self) -> Box<dyn Transform> {
+        self()
+    }
+}
+
+/// Encodes or decodes a body, one chunk at a time.
+pub trait Transform: Send + Sync {
+    /// Transforms the next chunk of the body, appending the result to
+    /// `output`.
+    ///
+    /// It's fine to buffer input, and to append nothing.
+    fn transform(&mut self, input: &[u8], output: &mut BytesMut) -> io::Result<()>;
+
+    /// Called once at the end of the body, to append anything that is still
+    /// buffered.
+    ///
+    /// Decoders should return an error if the body was truncated.
+    fn finish(&mut self, output: &mut BytesMut) -> io::Result<()>;
+}
+
+/// A built-in content coding, to list in the `Accept-Encoding` header with
+/// [`ClientBuilder::accept_encoding()`](crate::ClientBuilder::accept_encoding).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// `gzip`, which requires the `gzip` feature.
+    Gzip,
+    /// `br`, which requires the `brotli` feature.
+    Brotli,
+    /// `zstd`, which requires the `zstd` feature.
+    Zstd,
+    /// `deflate`, which requires the `deflate` feature.
+    Deflate,
+    /// `identity`, no encoding.
+    Identity,
+}
+
+/// An [`Encoding`] with a quality value, like `gzip;q=0.5`.
+///
+/// Created with [`Encoding::quality()`], or from an `Encoding` without a
+/// quality value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Preference {
+    encoding: Encoding,
+    quality: Option<f32>,
+}
+
+/// The custom codecs of a `Client`.
+pub(crate) struct Registry {
+    codecs: Vec<(String, Arc<dyn Codec>)>,
+    accept_encoding: HeaderValue,
+}
+
+pin_project! {
+    /// A body transformed by a `Transform`.
+    pub(crate) struct Transformed<B> {
+        #[pin]
+        inner: B,
+        transform: Box<dyn Transform>,
+        done: bool,
+    }
+}
+
+// ===== impl Encoding =====
+
+impl Encoding {
+    /// The name of the coding in the `Accept-Encoding` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Deflate => "deflate",
+            Encoding::Identity => "identity",
+        }
+    }
+
+    /// Sets the quality value of the coding, between 0 and 1.
+    ///
+    /// Servers prefer codings with higher values, and a value of 0 means
+    /// that the coding isn't acceptable, like `identity;q=0`.
+    pub fn quality(self, quality: f32) -> Preference {
+        Preference {
+            encoding: self,
+            quality: Some(quality),
+        }
+    }
+}
+
+impl From<Encoding> for Preference {
+    fn from(encoding: Encoding) -> Preference {
+        Preference {
+            encoding,
+            quality: None,
+        }
+    }
+}
+
+impl From<&Encoding> for Preference {
+    fn from(encoding: &Encoding) -> Preference {
+        Preference::from(*encoding)
+    }
+}
+
+// ===== impl Preference =====
+
+impl Preference {
+    /// The coding.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The quality value, if one was set.
+    pub fn quality_value(&self) -> Option<f32> {
+        self.quality
+    }
+}
+
+/// Formats a list of preferences for the `Accept-Encoding` header, like
+/// `zstd, gzip;q=0.5`.
+pub(crate) fn format_preferences(preferences: &[Preference]) -> crate::Result<String> {
+    let mut value = String::new();
+    for (i, preference) in preferences.iter().enumerate() {
+        if i > 0 {
+            value.push_str(", ");
+        }
+        value.push_str(preference.encoding.as_str());
+        if let Some(quality) = preference.quality {
+            if !(0.0..=1.0).contains(&quality) {
+                return Err(crate::error::builder(format!(
+                    "quality value {quality} of `{}` isn't between 0 and 1",
+                    preference.encoding.as_str()
+                )));
+            }
+            // at most three decimals, without trailing zeros
+            let quality = format!("{:.3}", quality);
+            let quality = quality.trim_end_matches('0').trim_end_matches('.');
+            value.push_str(";q=");
+            value.push_str(quality);
+        }
+    }
+    Ok(value)
+}
+
+// ===== impl Registry =====
+
+impl Registry {
+    /// Creates a registry, which advertises its codecs after the built-in
+    /// encodings in `builtin`.
+    pub(crate) fn new(
+        codecs: Vec<(String, Arc<dyn Codec>)>,
+        builtin: Option<&str>,
+    ) -> crate::Result<Registry> {
+        let names = codecs.iter().map(|(name, _)| name.as_str());
+        let accept_encoding = builtin
+            .into_iter()
+            .chain(names)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let accept_encoding =
+            HeaderValue::try_from(accept_encoding).map_err(crate::error::builder)?;
+        Ok(Registry {
+            codecs,
+            accept_encoding,
+        })
+    }
+
+    pub(crate) fn get(&self, encoding: &str) -> Option<&dyn Codec> {
+        self.codecs
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(encoding))
+            .map(|(_, codec)| &**codec)
+    }
+
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.codecs.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub(crate) fn accept_encoding(&self) -> &HeaderValue {
+        &self.accept_encoding
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+// ===== impl Transformed =====
+
+pub(crate) fn transformed<B>(inner: B, transform: Box<dyn Transform>) -> Transformed<B> {
+    Transformed {
+        inner,
+        transform,
+        done: false,
+    }
+}
+
+/// Transforms a complete body at once.
+pub(crate) fn transform_bytes(mut transform: Box<dyn Transform>, input: &[u8]) -> io::Result<Bytes> {
+    let mut output = BytesMut::new();
+    transform.transform(input, &mut output)?;
+    transform.finish(&mut output)?;
+    Ok(output.freeze())
+}
+
+impl<B> HttpBody for Transformed<B>
+where
+    B: HttpBody<Data = Bytes>,
+    B::Error: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            let mut output = BytesMut::new();
+            match futures_core::ready!(this.inner.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => {
+                    let frame = match frame.into_data() {
+                        Ok(data) => data,
+                        // trailers pass through untouched
+                        Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                    };
+                    this.transform.transform(&frame, &mut output)?;
+                    if output.is_empty() {
+                        continue;
+                    }
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                None => {
+                    *this.done = true;
+                    this.transform.finish(&mut output)?;
+                    if output.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                }
+            }
+            return Poll::Ready(Some(Ok(Frame::data(output.freeze()))));
+        }
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        // the transformed length is unknown
+        http_body::SizeHint::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    /// Emits the input in pairs of bytes, which exercises buffering.
+    struct Pairs(Option<u8>);
+
+    impl Transform for Pairs {
+        fn transform(&mut self, input: &[u8], output: &mut BytesMut) -> io::Result<()> {
+            for &b in input {
+                match self.0.take() {
+                    Some(first) => output.extend_from_slice(&[first, b]),
+                    None => self.0 = Some(b),
+                }
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self, output: &mut BytesMut) -> io::Result<()> {
+            output.extend(self.0.take());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn transformed_body_buffers_and_finishes() {
+        let chunks = ["a", "bc", "", "d", "e"].map(|s| Ok::<_, BoxError>(Frame::data(s.into())));
+        let body = http_body_util::StreamBody::new(futures_util::stream::iter(chunks));
+
+        let mut body = transformed(body, Box::new(Pairs(None)));
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(frames, ["ab", "cd", "e"]);
+
+        let bytes = transform_bytes(Box::new(Pairs(None)), b"abc").unwrap();
+        assert_eq!(bytes, "abc");
+    }
+
+    #[test]
+    fn registry_accept_encoding() {
+        fn noop() -> Arc<dyn Codec> {
+            Arc::new(|| Box::new(Pairs(None)) as Box<dyn Transform>)
+        }
+
+        let registry = Registry::new(
+            vec![("lz4".into(), noop()), ("snappy".into(), noop())],
+            Some("gzip, br"),
+        )
+        .unwrap();
+        assert_eq!(registry.accept_encoding(), "gzip, br, lz4, snappy");
+        assert!(registry.get("LZ4").is_some());
+        assert!(registry.get("zstd").is_none());
+        assert!(registry.get("lz4").unwrap().encoder().is_none());
+
+        let registry = Registry::new(vec![("lz4".into(), noop())], None).unwrap();
+        assert_eq!(registry.accept_encoding(), "lz4");
+    }
+
+    #[test]
+    fn preferences() {
+        let value = format_preferences(&[
+            Encoding::Zstd.into(),
+            Encoding::Gzip.quality(0.5),
+            Encoding::Brotli.quality(1.0),
+            Encoding::Deflate.quality(0.1234),
+            Encoding::Identity.quality(0.0),
+        ])
+        .unwrap();
+        assert_eq!(value, "zstd, gzip;q=0.5, br;q=1, deflate;q=0.123, identity;q=0");
+
+        assert_eq!(format_preferences(&[]).unwrap(), "");
+        assert!(format_preferences(&[Encoding::Gzip.quality(1.5)])
+            .unwrap_err()
+            .is_builder());
+        assert!(format_preferences(&[Encoding::Gzip.quality(f32::NAN)]).is_err());
+    }
+}
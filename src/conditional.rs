@@ -0,0 +1,162 @@
+//! Conditional downloads
+//!
+//! Updaters and package managers often fetch the same resource repeatedly,
+//! and only care about it when it has changed. HTTP supports this with
+//! validators: a server sends an `ETag` and/or `Last-Modified` header with a
+//! response, and a client echoes them back in `If-None-Match` and
+//! `If-Modified-Since` on the next request. If the resource is unchanged, the
+//! server responds with `304 Not Modified` and no body.
+//!
+//! [`Client::download_if_changed()`](crate::Client::download_if_changed)
+//! takes care of sending those headers from a stored [`CacheMeta`], and of
+//! interpreting the response.
+
+use std::fmt;
+
+use http::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, LAST_MODIFIED};
+
+use crate::{Client, IntoUrl, Response};
+
+/// The validators of a previously downloaded resource.
+///
+/// Store the values of [`CacheMeta::etag()`] and
+/// [`CacheMeta::last_modified()`] alongside the downloaded content, and
+/// recreate the `CacheMeta` with [`CacheMeta::new()`] for the next download.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct CacheMeta {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// The result of [`Client::download_if_changed()`](crate::Client::download_if_changed).
+pub enum Download {
+    /// The resource hasn't changed since the stored download.
+    NotModified,
+    /// The resource has changed, or there was no stored download.
+    Modified {
+        /// The response, whose body is the new content.
+        response: Response,
+        /// The validators to store with the new content.
+        meta: CacheMeta,
+    },
+}
+
+// ===== impl CacheMeta =====
+
+impl CacheMeta {
+    /// Creates a `CacheMeta` from stored `ETag` and `Last-Modified` values.
+    ///
+    /// Use `CacheMeta::default()` when nothing has been downloaded yet.
+    pub fn new(etag: Option<String>, last_modified: Option<String>) -> CacheMeta {
+        CacheMeta {
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Reads the validators from the headers of a response.
+    pub fn from_headers(headers: &HeaderMap) -> CacheMeta {
+        let get = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        CacheMeta {
+            etag: get(ETAG),
+            last_modified: get(LAST_MODIFIED),
+        }
+    }
+
+    /// The `ETag` of the stored download, if any.
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// The `Last-Modified` date of the stored download, if any.
+    pub fn last_modified(&self) -> Option<&str> {
+        self.last_modified.as_deref()
+    }
+
+    /// Returns true if there are no validators to send.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+impl fmt::Debug for CacheMeta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CacheMeta")
+            .field("etag", &self.etag)
+            .field("last_modified", &self.last_modified)
+            .finish()
+    }
+}
+
+// ===== impl Download =====
+
+impl Download {
+    /// Returns true if the resource hasn't changed.
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self, Download::NotModified)
+    }
+}
+
+impl fmt::Debug for Download {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Download::NotModified => f.write_str("NotModified"),
+            Download::Modified { response, meta } => f
+                .debug_struct("Modified")
+                .field("response", response)
+                .field("meta", meta)
+                .finish(),
+        }
+    }
+}
+
+pub(crate) async fn download_if_changed<U: IntoUrl>(
+    client: &Client,
+    url: U,
+    meta: &CacheMeta,
+) -> crate::Result<Download> {
+    let mut req = client.get(url);
+    // `If-Modified-Since` is ignored by servers when `If-None-Match` is
+    // present, but sending both is harmless and helps servers that only
+    // support dates.
+    if let Some(ref etag) = meta.etag {
+        req = req.if_none_match(etag.as_str());
+    }
+    if let Some(ref last_modified) = meta.last_modified {
+        req = req.header(IF_MODIFIED_SINCE, last_modified.as_str());
+    }
+
+    let res = req.send().await?;
+    if res.is_not_modified() {
+        return Ok(Download::NotModified);
+    }
+
+    let res = res.error_for_status()?;
+    let meta = CacheMeta::from_headers(res.headers());
+    Ok(Download::Modified {
+        response: res,
+        meta,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, "W/\"abc\"".parse().unwrap());
+        let meta = CacheMeta::from_headers(&headers);
+        assert_eq!(meta.etag(), Some("W/\"abc\""));
+        assert_eq!(meta.last_modified(), None);
+        assert!(!meta.is_empty());
+
+        assert!(CacheMeta::from_headers(&HeaderMap::new()).is_empty());
+    }
+}
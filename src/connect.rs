@@ -1,10 +1,8 @@
-#[cfg(feature = "__tls")]
 use http::header::HeaderValue;
 use http::uri::{Authority, Scheme};
 use http::Uri;
 use hyper::rt::{Read, ReadBufCursor, Write};
 use hyper_util::client::legacy::connect::{Connected, Connection};
-#[cfg(any(feature = "socks", feature = "__tls"))]
 use hyper_util::rt::TokioIo;
 #[cfg(feature = "default-tls")]
 use native_tls_crate::{TlsConnector, TlsConnectorBuilder};
@@ -13,25 +11,36 @@ use tower::util::{BoxCloneSyncServiceLayer, MapRequestLayer};
 use tower::{timeout::TimeoutLayer, util::BoxCloneSyncService, ServiceBuilder};
 use tower_service::Service;
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::io::{self, IoSlice};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
+use self::stream_conn::{StreamConn, BoxStream};
 #[cfg(feature = "default-tls")]
 use self::native_tls_conn::NativeTlsConn;
 #[cfg(feature = "__rustls")]
 use self::rustls_tls_conn::RustlsTlsConn;
+#[cfg(all(feature = "http2", feature = "__tls"))]
+use crate::async_impl::h2_fallback::H2Fallback;
+use crate::async_impl::shutdown::Shutdown;
 use crate::dns::DynResolver;
 use crate::error::{cast_to_internal_error, BoxError};
+#[cfg(feature = "http2")]
+use crate::http2::{Http2EventCallback, Watcher};
+use crate::pool::{PoolEventCallback, Slots, Tracked};
 use crate::proxy::{Proxy, ProxyScheme};
+use crate::raw_headers::Recorder;
 use sealed::{Conn, Unnameable};
 
 pub(crate) type HttpConnector = hyper_util::client::legacy::connect::HttpConnector<DynResolver>;
 
+// the common variant is the large one, boxing it wouldn't save anything
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
 pub(crate) enum Connector {
     // base service, with or without an embedded timeout
@@ -66,17 +75,32 @@ pub(crate) type BoxedConnectorService = BoxCloneSyncService<Unnameable, Conn, Bo
 pub(crate) type BoxedConnectorLayer =
     BoxCloneSyncServiceLayer<BoxedConnectorService, Unnameable, Conn, BoxError>;
 
+#[derive(Clone)]
 pub(crate) struct ConnectorBuilder {
     inner: Inner,
     proxies: Arc<Vec<Proxy>>,
+    dns_overrides: Option<Arc<HashMap<String, Vec<SocketAddr>>>>,
     verbose: verbose::Wrapper,
     timeout: Option<Duration>,
+    shutdown: Option<Arc<Shutdown>>,
+    probe_idle: bool,
+    raw_headers: bool,
+    h2c: bool,
+    pool_events: Option<PoolEvents>,
+    #[cfg(feature = "http2")]
+    http2_events: Option<Http2Events>,
+    slots: Option<Arc<Slots>>,
+    allowed_ports: Option<Arc<[u16]>>,
     #[cfg(feature = "__tls")]
     nodelay: bool,
     #[cfg(feature = "__tls")]
     tls_info: bool,
     #[cfg(feature = "__tls")]
     user_agent: Option<HeaderValue>,
+    #[cfg(all(feature = "http2", feature = "__tls"))]
+    http1_only: Option<Http1Only>,
+    #[cfg(feature = "__tls")]
+    host_tls: HostTls,
 }
 
 impl ConnectorBuilder {
@@ -86,13 +110,27 @@ where {
         let mut base_service = ConnectorService {
             inner: self.inner,
             proxies: self.proxies,
+            dns_overrides: self.dns_overrides,
             verbose: self.verbose,
+            shutdown: self.shutdown,
+            probe_idle: self.probe_idle,
+            raw_headers: self.raw_headers,
+            h2c: self.h2c,
+            pool_events: self.pool_events,
+            #[cfg(feature = "http2")]
+            http2_events: self.http2_events,
+            slots: self.slots,
+            allowed_ports: self.allowed_ports,
             #[cfg(feature = "__tls")]
             nodelay: self.nodelay,
             #[cfg(feature = "__tls")]
             tls_info: self.tls_info,
             #[cfg(feature = "__tls")]
             user_agent: self.user_agent,
+            #[cfg(all(feature = "http2", feature = "__tls"))]
+            http1_only: self.http1_only,
+            #[cfg(feature = "__tls")]
+            host_tls: self.host_tls,
             simple_timeout: None,
         };
 
@@ -165,8 +203,18 @@ where {
         ConnectorBuilder {
             inner: Inner::Http(http),
             proxies,
+            dns_overrides: None,
             verbose: verbose::OFF,
             timeout: None,
+            shutdown: None,
+            probe_idle: false,
+            raw_headers: false,
+            h2c: false,
+            pool_events: None,
+            #[cfg(feature = "http2")]
+            http2_events: None,
+            slots: None,
+            allowed_ports: None,
         }
     }
 
@@ -223,13 +271,26 @@ where {
         http.enforce_http(false);
 
         ConnectorBuilder {
-            inner: Inner::DefaultTls(http, tls),
+            inner: Inner::DefaultTls(http, tls, Arc::default()),
             proxies,
+            dns_overrides: None,
             verbose: verbose::OFF,
             nodelay,
             tls_info,
             user_agent,
             timeout: None,
+            shutdown: None,
+            probe_idle: false,
+            raw_headers: false,
+            h2c: false,
+            pool_events: None,
+            #[cfg(feature = "http2")]
+            http2_events: None,
+            slots: None,
+            allowed_ports: None,
+            #[cfg(feature = "http2")]
+            http1_only: None,
+            host_tls: HostTls::default(),
         }
     }
 
@@ -270,13 +331,27 @@ where {
                 http,
                 tls,
                 tls_proxy,
+                proxy_tls: Arc::default(),
             },
             proxies,
+            dns_overrides: None,
             verbose: verbose::OFF,
             nodelay,
             tls_info,
             user_agent,
             timeout: None,
+            shutdown: None,
+            probe_idle: false,
+            raw_headers: false,
+            h2c: false,
+            pool_events: None,
+            #[cfg(feature = "http2")]
+            http2_events: None,
+            slots: None,
+            allowed_ports: None,
+            #[cfg(feature = "http2")]
+            http1_only: None,
+            host_tls: HostTls::default(),
         }
     }
 
@@ -284,6 +359,133 @@ where {
         self.timeout = timeout;
     }
 
+    pub(crate) fn set_shutdown(&mut self, shutdown: Arc<Shutdown>) {
+        self.shutdown = Some(shutdown);
+    }
+
+    pub(crate) fn set_probe_idle(&mut self, enabled: bool) {
+        self.probe_idle = enabled;
+    }
+
+    pub(crate) fn set_raw_headers(&mut self, enabled: bool) {
+        self.raw_headers = enabled;
+    }
+
+    /// Speaks HTTP/2 with prior knowledge on connections to `http://` URLs.
+    #[cfg(feature = "http2")]
+    pub(crate) fn set_h2c(&mut self, enabled: bool) {
+        self.h2c = enabled;
+    }
+
+    /// Bounds how many connections to each host are open at once.
+    pub(crate) fn set_slots(&mut self, slots: Option<Arc<Slots>>) {
+        self.slots = slots;
+    }
+
+    pub(crate) fn set_pool_events(&mut self, callback: PoolEventCallback, http2_only: bool) {
+        self.pool_events = Some(PoolEvents {
+            callback,
+            http2_only,
+        });
+    }
+
+    #[cfg(feature = "http2")]
+    pub(crate) fn set_http2_events(&mut self, callback: Http2EventCallback, http2_only: bool) {
+        self.http2_events = Some(Http2Events {
+            callback,
+            http2_only,
+        });
+    }
+
+    pub(crate) fn set_dns_overrides(&mut self, overrides: Arc<HashMap<String, Vec<SocketAddr>>>) {
+        self.dns_overrides = Some(overrides);
+    }
+
+    pub(crate) fn set_allowed_ports(&mut self, ports: Option<Vec<u16>>) {
+        self.allowed_ports = ports.map(Arc::from);
+    }
+
+    /// Sets the TLS connectors of the HTTPS proxies with their own identity.
+    #[cfg(feature = "default-tls")]
+    pub(crate) fn set_native_tls_proxies(&mut self, proxies: HashMap<Authority, TlsConnector>) {
+        match &mut self.inner {
+            Inner::DefaultTls(_, _, proxy_tls) => *proxy_tls = Arc::new(proxies),
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { .. } => (),
+        }
+    }
+
+    /// Connects with TLS configs that only offer HTTP/1.1 to the origins
+    /// downgraded by `fallback`.
+    ///
+    /// The native TLS connector that only offers HTTP/1.1 has to be built
+    /// by the caller.
+    #[cfg(all(feature = "http2", feature = "__tls"))]
+    pub(crate) fn set_h2_fallback(
+        &mut self,
+        fallback: Arc<H2Fallback>,
+        #[cfg(feature = "default-tls")] native: Option<TlsConnector>,
+    ) {
+        #[cfg(feature = "__rustls")]
+        let rustls = match &self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(..) => None,
+            Inner::RustlsTls { tls, .. } => {
+                let mut tls = (**tls).clone();
+                tls.alpn_protocols = vec![b"http/1.1".to_vec()];
+                Some(Arc::new(tls))
+            }
+        };
+        self.http1_only = Some(Http1Only {
+            origins: fallback,
+            #[cfg(feature = "default-tls")]
+            native,
+            #[cfg(feature = "__rustls")]
+            rustls,
+        });
+    }
+
+    /// Sets the TLS connectors of the hosts with their own TLS options.
+    #[cfg(feature = "default-tls")]
+    pub(crate) fn set_native_tls_hosts(&mut self, hosts: HashMap<String, TlsConnector>) {
+        self.host_tls.native = Arc::new(hosts);
+    }
+
+    /// Sets the server names to send in the TLS handshakes with hosts,
+    /// instead of the hosts themselves.
+    #[cfg(feature = "__tls")]
+    pub(crate) fn set_tls_server_names(&mut self, names: HashMap<String, String>) {
+        self.host_tls.server_names = Arc::new(names);
+    }
+
+    /// Sets the TLS configs of the hosts with their own TLS options.
+    #[cfg(feature = "__rustls")]
+    pub(crate) fn set_rustls_hosts(&mut self, hosts: HashMap<String, rustls::ClientConfig>) {
+        self.host_tls.rustls = Arc::new(
+            hosts
+                .into_iter()
+                .map(|(host, config)| (host, Arc::new(config)))
+                .collect(),
+        );
+    }
+
+    /// Sets the TLS configs of the HTTPS proxies with their own identity.
+    #[cfg(feature = "__rustls")]
+    pub(crate) fn set_rustls_proxies(&mut self, proxies: HashMap<Authority, rustls::ClientConfig>) {
+        match &mut self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(..) => (),
+            Inner::RustlsTls { proxy_tls, .. } => {
+                *proxy_tls = Arc::new(
+                    proxies
+                        .into_iter()
+                        .map(|(host, config)| (host, Arc::new(config)))
+                        .collect(),
+                );
+            }
+        }
+    }
+
     pub(crate) fn set_verbose(&mut self, enabled: bool) {
         self.verbose.0 = enabled;
     }
@@ -291,7 +493,7 @@ where {
     pub(crate) fn set_keepalive(&mut self, dur: Option<Duration>) {
         match &mut self.inner {
             #[cfg(feature = "default-tls")]
-            Inner::DefaultTls(http, _tls) => http.set_keepalive(dur),
+            Inner::DefaultTls(http, ..) => http.set_keepalive(dur),
             #[cfg(feature = "__rustls")]
             Inner::RustlsTls { http, .. } => http.set_keepalive(dur),
             #[cfg(not(feature = "__tls"))]
@@ -300,23 +502,82 @@ where {
     }
 }
 
+/// The origins downgraded to HTTP/1.1, and the TLS configs to connect to
+/// them without offering h2.
+#[cfg(all(feature = "http2", feature = "__tls"))]
+#[derive(Clone)]
+struct Http1Only {
+    origins: Arc<H2Fallback>,
+    #[cfg(feature = "default-tls")]
+    native: Option<TlsConnector>,
+    #[cfg(feature = "__rustls")]
+    rustls: Option<Arc<rustls::ClientConfig>>,
+}
+
+/// Where to report the events of new connections.
+#[derive(Clone)]
+struct PoolEvents {
+    callback: PoolEventCallback,
+    /// HTTP/2 is spoken without negotiating it.
+    http2_only: bool,
+}
+
+/// Reports the frames of HTTP/2 connections.
+#[cfg(feature = "http2")]
+#[derive(Clone)]
+struct Http2Events {
+    callback: Http2EventCallback,
+    /// HTTP/2 is spoken without negotiating it.
+    http2_only: bool,
+}
+
 #[allow(missing_debug_implementations)]
 #[derive(Clone)]
 pub(crate) struct ConnectorService {
     inner: Inner,
     proxies: Arc<Vec<Proxy>>,
+    /// The addresses to connect to for some hosts, for the requests of a
+    /// `resolve_map()`.
+    dns_overrides: Option<Arc<HashMap<String, Vec<SocketAddr>>>>,
     verbose: verbose::Wrapper,
     /// When there is a single timeout layer and no other layers,
     /// we embed it directly inside our base Service::call().
     /// This lets us avoid an extra `Box::pin` indirection layer
     /// since `tokio::time::Timeout` is `Unpin`
     simple_timeout: Option<Duration>,
+    shutdown: Option<Arc<Shutdown>>,
+    probe_idle: bool,
+    raw_headers: bool,
+    h2c: bool,
+    pool_events: Option<PoolEvents>,
+    #[cfg(feature = "http2")]
+    http2_events: Option<Http2Events>,
+    slots: Option<Arc<Slots>>,
+    allowed_ports: Option<Arc<[u16]>>,
     #[cfg(feature = "__tls")]
     nodelay: bool,
     #[cfg(feature = "__tls")]
     tls_info: bool,
     #[cfg(feature = "__tls")]
     user_agent: Option<HeaderValue>,
+    #[cfg(all(feature = "http2", feature = "__tls"))]
+    http1_only: Option<Http1Only>,
+    #[cfg(feature = "__tls")]
+    host_tls: HostTls,
+}
+
+/// The TLS connectors and configs of the hosts with their own TLS options,
+/// from `ClientBuilder::tls_config_for_host()`.
+#[cfg(feature = "__tls")]
+#[derive(Clone, Default)]
+struct HostTls {
+    #[cfg(feature = "default-tls")]
+    native: Arc<HashMap<String, TlsConnector>>,
+    #[cfg(feature = "__rustls")]
+    rustls: Arc<HashMap<String, Arc<rustls::ClientConfig>>>,
+    /// The server names sent in place of the hosts, from
+    /// `ClientBuilder::tls_sni_hostname()`.
+    server_names: Arc<HashMap<String, String>>,
 }
 
 #[derive(Clone)]
@@ -324,15 +585,32 @@ enum Inner {
     #[cfg(not(feature = "__tls"))]
     Http(HttpConnector),
     #[cfg(feature = "default-tls")]
-    DefaultTls(HttpConnector, TlsConnector),
+    DefaultTls(
+        HttpConnector,
+        TlsConnector,
+        Arc<HashMap<Authority, TlsConnector>>,
+    ),
     #[cfg(feature = "__rustls")]
     RustlsTls {
         http: HttpConnector,
         tls: Arc<rustls::ClientConfig>,
         tls_proxy: Arc<rustls::ClientConfig>,
+        /// Configs of the HTTPS proxies with their own identity.
+        proxy_tls: Arc<HashMap<Authority, Arc<rustls::ClientConfig>>>,
     },
 }
 
+/// Returns the authority of `dst` if it's an HTTPS proxy, to look up its
+/// own TLS config.
+#[cfg(feature = "__tls")]
+fn proxy_tls_key(dst: &Uri) -> Option<&Authority> {
+    if dst.scheme() == Some(&Scheme::HTTPS) {
+        dst.authority()
+    } else {
+        None
+    }
+}
+
 impl ConnectorService {
     #[cfg(feature = "socks")]
     async fn connect_socks(&self, dst: Uri, proxy: ProxyScheme) -> Result<Conn, BoxError> {
@@ -351,14 +629,15 @@ impl ConnectorService {
 
         match &self.inner {
             #[cfg(feature = "default-tls")]
-            Inner::DefaultTls(_http, tls) => {
+            Inner::DefaultTls(_http, tls, _) => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
                     let host = dst.host().ok_or("no host in url")?.to_string();
                     let conn = socks::connect(proxy, dst, dns).await?;
                     let conn = TokioIo::new(conn);
                     let conn = TokioIo::new(conn);
                     let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
-                    let io = tls_connector.connect(&host, conn).await?;
+                    let handshake = tls_connector.connect(self.tls_server_name(&host), conn);
+                    let io = tls_handshake(handshake, &host).await?;
                     let io = TokioIo::new(io);
                     return Ok(Conn {
                         inner: self.verbose.wrap(NativeTlsConn { inner: io }),
@@ -370,7 +649,6 @@ impl ConnectorService {
             #[cfg(feature = "__rustls")]
             Inner::RustlsTls { tls, .. } => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
-                    use std::convert::TryFrom;
                     use tokio_rustls::TlsConnector as RustlsConnector;
 
                     let tls = tls.clone();
@@ -378,12 +656,9 @@ impl ConnectorService {
                     let conn = socks::connect(proxy, dst, dns).await?;
                     let conn = TokioIo::new(conn);
                     let conn = TokioIo::new(conn);
-                    let server_name =
-                        rustls_pki_types::ServerName::try_from(host.as_str().to_owned())
-                            .map_err(|_| "Invalid Server Name")?;
-                    let io = RustlsConnector::from(tls)
-                        .connect(server_name, conn)
-                        .await?;
+                    let server_name = rustls_server_name(self.tls_server_name(&host))?;
+                    let handshake = RustlsConnector::from(tls).connect(server_name, conn);
+                    let io = tls_handshake(handshake, &host).await?;
                     let io = TokioIo::new(io);
                     return Ok(Conn {
                         inner: self.verbose.wrap(RustlsTlsConn { inner: io }),
@@ -404,6 +679,13 @@ impl ConnectorService {
     }
 
     async fn connect_with_maybe_proxy(self, dst: Uri, is_proxy: bool) -> Result<Conn, BoxError> {
+        #[cfg(all(feature = "http2", feature = "__tls"))]
+        let http1_only = self
+            .http1_only
+            .as_ref()
+            .filter(|http1_only| http1_only.origins.is_downgraded(&dst));
+        #[cfg(feature = "__tls")]
+        let server_name = self.tls_server_name_override(&dst).map(str::to_owned);
         match self.inner {
             #[cfg(not(feature = "__tls"))]
             Inner::Http(mut http) => {
@@ -415,7 +697,7 @@ impl ConnectorService {
                 })
             }
             #[cfg(feature = "default-tls")]
-            Inner::DefaultTls(http, tls) => {
+            Inner::DefaultTls(http, tls, _) => {
                 let mut http = http.clone();
 
                 // Disable Nagle's algorithm for TLS handshake
@@ -425,9 +707,23 @@ impl ConnectorService {
                     http.set_nodelay(true);
                 }
 
+                #[cfg(feature = "http2")]
+                let tls = http1_only
+                    .and_then(|http1_only| http1_only.native.as_ref())
+                    .unwrap_or(&tls);
                 let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
-                let mut http = hyper_tls::HttpsConnector::from((http, tls_connector));
-                let io = http.call(dst).await?;
+                let io = match server_name.as_deref() {
+                    Some(name) => {
+                        let tcp = http.call(dst).await?;
+                        let handshake = tls_connector.connect(name, TokioIo::new(tcp));
+                        let tls = tls_handshake(handshake, name).await?;
+                        hyper_tls::MaybeHttpsStream::Https(TokioIo::new(tls))
+                    }
+                    None => {
+                        let mut http = hyper_tls::HttpsConnector::from((http, tls_connector));
+                        http.call(dst).await?
+                    }
+                };
 
                 if let hyper_tls::MaybeHttpsStream::Https(stream) = io {
                     if !self.nodelay {
@@ -464,8 +760,26 @@ impl ConnectorService {
                     http.set_nodelay(true);
                 }
 
-                let mut http = hyper_rustls::HttpsConnector::from((http, tls.clone()));
-                let io = http.call(dst).await?;
+                #[cfg(feature = "http2")]
+                let tls = http1_only
+                    .and_then(|http1_only| http1_only.rustls.as_ref())
+                    .unwrap_or(&tls);
+                let io = match server_name.as_deref() {
+                    Some(name) => {
+                        use tokio_rustls::TlsConnector as RustlsConnector;
+
+                        let server_name = rustls_server_name(name)?;
+                        let tcp = http.call(dst).await?;
+                        let handshake =
+                            RustlsConnector::from(tls.clone()).connect(server_name, TokioIo::new(tcp));
+                        let tls = tls_handshake(handshake, name).await?;
+                        hyper_rustls::MaybeHttpsStream::Https(TokioIo::new(tls))
+                    }
+                    None => {
+                        let mut http = hyper_rustls::HttpsConnector::from((http, tls.clone()));
+                        http.call(dst).await?
+                    }
+                };
 
                 if let hyper_rustls::MaybeHttpsStream::Https(stream) = io {
                     if !self.nodelay {
@@ -509,12 +823,15 @@ impl ConnectorService {
 
         match &self.inner {
             #[cfg(feature = "default-tls")]
-            Inner::DefaultTls(http, tls) => {
+            Inner::DefaultTls(http, tls, proxy_tls) => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
                     let host = dst.host().to_owned();
                     let port = dst.port().map(|p| p.as_u16()).unwrap_or(443);
                     let http = http.clone();
-                    let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
+                    let proxy_connector = proxy_tls_key(&proxy_dst)
+                        .and_then(|key| proxy_tls.get(key))
+                        .unwrap_or(tls);
+                    let tls_connector = tokio_native_tls::TlsConnector::from(proxy_connector.clone());
                     let mut http = hyper_tls::HttpsConnector::from((http, tls_connector));
                     let conn = http.call(proxy_dst).await?;
                     log::trace!("tunneling HTTPS over proxy");
@@ -527,9 +844,10 @@ impl ConnectorService {
                     )
                     .await?;
                     let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
-                    let io = tls_connector
-                        .connect(host.ok_or("no host in url")?, TokioIo::new(tunneled))
-                        .await?;
+                    let host = host.ok_or("no host in url")?;
+                    let handshake =
+                        tls_connector.connect(self.tls_server_name(host), TokioIo::new(tunneled));
+                    let io = tls_handshake(handshake, host).await?;
                     return Ok(Conn {
                         inner: self.verbose.wrap(NativeTlsConn {
                             inner: TokioIo::new(io),
@@ -544,26 +862,27 @@ impl ConnectorService {
                 http,
                 tls,
                 tls_proxy,
+                proxy_tls,
             } => {
                 if dst.scheme() == Some(&Scheme::HTTPS) {
-                    use rustls_pki_types::ServerName;
-                    use std::convert::TryFrom;
                     use tokio_rustls::TlsConnector as RustlsConnector;
 
                     let host = dst.host().ok_or("no host in url")?.to_string();
                     let port = dst.port().map(|r| r.as_u16()).unwrap_or(443);
                     let http = http.clone();
-                    let mut http = hyper_rustls::HttpsConnector::from((http, tls_proxy.clone()));
+                    let proxy_config = proxy_tls_key(&proxy_dst)
+                        .and_then(|key| proxy_tls.get(key))
+                        .unwrap_or(tls_proxy);
+                    let mut http = hyper_rustls::HttpsConnector::from((http, proxy_config.clone()));
                     let tls = tls.clone();
                     let conn = http.call(proxy_dst).await?;
                     log::trace!("tunneling HTTPS over proxy");
-                    let maybe_server_name = ServerName::try_from(host.as_str().to_owned())
-                        .map_err(|_| "Invalid Server Name");
-                    let tunneled = tunnel(conn, host, port, self.user_agent.clone(), auth).await?;
+                    let maybe_server_name = rustls_server_name(self.tls_server_name(&host));
+                    let tunneled = tunnel(conn, host.clone(), port, self.user_agent.clone(), auth).await?;
                     let server_name = maybe_server_name?;
-                    let io = RustlsConnector::from(tls)
-                        .connect(server_name, TokioIo::new(tunneled))
-                        .await?;
+                    let handshake =
+                        RustlsConnector::from(tls).connect(server_name, TokioIo::new(tunneled));
+                    let io = tls_handshake(handshake, &host).await?;
 
                     return Ok(Conn {
                         inner: self.verbose.wrap(RustlsTlsConn {
@@ -578,7 +897,316 @@ impl ConnectorService {
             Inner::Http(_) => (),
         }
 
-        self.connect_with_maybe_proxy(proxy_dst, true).await
+        #[cfg(feature = "__tls")]
+        let this = self.with_proxy_tls(&proxy_dst);
+        #[cfg(not(feature = "__tls"))]
+        let this = self;
+        this.connect_with_maybe_proxy(proxy_dst, true).await
+    }
+
+    /// Uses the TLS options of the host of `dst`, if it has its own, for the
+    /// TLS connection to it.
+    #[cfg(feature = "__tls")]
+    fn with_host_tls(mut self, dst: &Uri) -> ConnectorService {
+        let host = match dst.host() {
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+            None => return self,
+        };
+        match &mut self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(_, tls, _) => {
+                if let Some(host_connector) = self.host_tls.native.get(host) {
+                    *tls = host_connector.clone();
+                }
+            }
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { tls, .. } => {
+                if let Some(host_config) = self.host_tls.rustls.get(host) {
+                    *tls = host_config.clone();
+                }
+            }
+        }
+        self
+    }
+
+    /// The name to present and verify in the TLS handshake with `host`.
+    #[cfg(feature = "__tls")]
+    fn tls_server_name<'a>(&'a self, host: &'a str) -> &'a str {
+        let host = tls_host(host);
+        self.host_tls
+            .server_names
+            .get(host)
+            .map(String::as_str)
+            .unwrap_or(host)
+    }
+
+    /// The name to present in place of the host of `dst`, if it has one.
+    #[cfg(feature = "__tls")]
+    fn tls_server_name_override(&self, dst: &Uri) -> Option<&str> {
+        if dst.scheme() != Some(&Scheme::HTTPS) {
+            return None;
+        }
+        let host = tls_host(dst.host()?);
+        self.host_tls.server_names.get(host).map(String::as_str)
+    }
+
+    /// Uses the TLS config of the proxy `dst`, if it has its own identity,
+    /// to connect to it.
+    #[cfg(feature = "__tls")]
+    fn with_proxy_tls(mut self, dst: &Uri) -> ConnectorService {
+        let key = match proxy_tls_key(dst) {
+            Some(key) => key,
+            None => return self,
+        };
+        match &mut self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(_, tls, proxy_tls) => {
+                if let Some(proxy_connector) = proxy_tls.get(key) {
+                    *tls = proxy_connector.clone();
+                }
+            }
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { tls, proxy_tls, .. } => {
+                if let Some(proxy_config) = proxy_tls.get(key) {
+                    *tls = proxy_config.clone();
+                }
+            }
+        }
+        self
+    }
+}
+
+impl ConnectorService {
+    /// Connects to `dst` through each of the `hops` in turn, and then
+    /// through `proxy`.
+    async fn connect_via_chain(
+        self,
+        dst: Uri,
+        hops: Vec<ProxyScheme>,
+        proxy: ProxyScheme,
+    ) -> Result<Conn, BoxError> {
+        log::debug!("proxy chain({hops:?}, {proxy:?}) intercepts '{dst:?}'");
+
+        let https = dst.scheme() == Some(&Scheme::HTTPS);
+        let dst_host = dst.host().ok_or("no host in url")?.to_owned();
+        let dst_port = dst
+            .port_u16()
+            .unwrap_or(if https { 443 } else { 80 });
+        #[cfg(feature = "__tls")]
+        let user_agent = self.user_agent.clone();
+        #[cfg(not(feature = "__tls"))]
+        let user_agent = None;
+
+        let mut hops = hops;
+        hops.push(proxy);
+
+        let (host, port) = hop_addr(&hops[0]);
+        let first: Uri = format!("http://{host}:{port}").parse()?;
+        let mut stream: BoxStream = Box::new(self.http_connector().call(first).await?.into_inner());
+
+        for (i, hop) in hops.iter().enumerate() {
+            let next = hops.get(i + 1).map(hop_addr);
+            match hop {
+                ProxyScheme::Http { host, auth } | ProxyScheme::Https { host, auth } => {
+                    if let ProxyScheme::Https { .. } = hop {
+                        stream = self
+                            .stream_tls(stream, host.host(), Some(host))
+                            .await?
+                            .into_inner();
+                    }
+                    let (host, port) = match next {
+                        Some(next) => next,
+                        None if https => (dst_host.clone(), dst_port),
+                        // the last proxy forwards plain text requests
+                        None => {
+                            return Ok(Conn {
+                                inner: self.verbose.wrap(StreamConn::new(stream)),
+                                is_proxy: true,
+                                tls_info: false,
+                            });
+                        }
+                    };
+                    log::trace!("tunneling to {host}:{port} over proxy chain");
+                    let tunneled = tunnel(
+                        TokioIo::new(stream),
+                        host,
+                        port,
+                        user_agent.clone(),
+                        auth.clone(),
+                    )
+                    .await?;
+                    stream = tunneled.into_inner();
+                }
+                #[cfg(feature = "socks")]
+                ProxyScheme::Socks4 { .. } | ProxyScheme::Socks5 { .. } => {
+                    let (host, port) = next.unwrap_or_else(|| (dst_host.clone(), dst_port));
+                    log::trace!("tunneling to {host}:{port} over proxy chain");
+                    stream = socks::tunnel(hop, stream, &host, port).await?;
+                }
+            }
+        }
+
+        if https {
+            let conn = self.stream_tls(stream, &dst_host, None).await?;
+            return Ok(Conn {
+                inner: self.verbose.wrap(conn),
+                is_proxy: false,
+                #[cfg(feature = "__tls")]
+                tls_info: self.tls_info,
+                #[cfg(not(feature = "__tls"))]
+                tls_info: false,
+            });
+        }
+
+        Ok(Conn {
+            inner: self.verbose.wrap(StreamConn::new(stream)),
+            is_proxy: false,
+            tls_info: false,
+        })
+    }
+
+    fn http_connector(&self) -> HttpConnector {
+        match &self.inner {
+            #[cfg(not(feature = "__tls"))]
+            Inner::Http(http) => http.clone(),
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(http, _, _) => http.clone(),
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { http, .. } => http.clone(),
+        }
+    }
+
+    /// Starts TLS with `host` over a stream, such as a tunnel of a proxy
+    /// chain, using the TLS config of the HTTPS `proxy`, or of the
+    /// destination.
+    #[cfg(feature = "__tls")]
+    async fn stream_tls(
+        &self,
+        stream: BoxStream,
+        host: &str,
+        proxy: Option<&Authority>,
+    ) -> Result<StreamConn, BoxError> {
+        match &self.inner {
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(_, tls, proxy_tls) => {
+                let tls = proxy.and_then(|key| proxy_tls.get(key)).unwrap_or(tls);
+                let tls_connector = tokio_native_tls::TlsConnector::from(tls.clone());
+                let name = match proxy {
+                    Some(_) => tls_host(host),
+                    None => self.tls_server_name(host),
+                };
+                let io = tls_handshake(tls_connector.connect(name, stream), host).await?;
+                #[cfg(feature = "native-tls-alpn")]
+                let h2 = matches!(io.get_ref().negotiated_alpn(), Ok(Some(alpn)) if alpn == b"h2");
+                #[cfg(not(feature = "native-tls-alpn"))]
+                let h2 = false;
+                let peer_certificate = io
+                    .get_ref()
+                    .peer_certificate()
+                    .ok()
+                    .flatten()
+                    .and_then(|c| c.to_der().ok());
+                let tls_info = crate::tls::TlsInfo { peer_certificate };
+                Ok(StreamConn::with_tls(Box::new(io), h2, tls_info))
+            }
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls {
+                tls,
+                tls_proxy,
+                proxy_tls,
+                ..
+            } => {
+                use tokio_rustls::TlsConnector as RustlsConnector;
+
+                let tls = match proxy {
+                    Some(key) => proxy_tls.get(key).unwrap_or(tls_proxy),
+                    None => tls,
+                };
+                let server_name = match proxy {
+                    Some(_) => rustls_server_name(host)?,
+                    None => rustls_server_name(self.tls_server_name(host))?,
+                };
+                let handshake = RustlsConnector::from(tls.clone()).connect(server_name, stream);
+                let io = tls_handshake(handshake, host).await?;
+                let h2 = io.get_ref().1.alpn_protocol() == Some(b"h2");
+                let peer_certificate = io
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certs| certs.first())
+                    .map(|c| c.to_vec());
+                let tls_info = crate::tls::TlsInfo { peer_certificate };
+                Ok(StreamConn::with_tls(Box::new(io), h2, tls_info))
+            }
+        }
+    }
+
+    #[cfg(not(feature = "__tls"))]
+    async fn stream_tls(
+        &self,
+        _stream: BoxStream,
+        _host: &str,
+        _proxy: Option<&Authority>,
+    ) -> Result<StreamConn, BoxError> {
+        Err("a TLS backend is required for HTTPS".into())
+    }
+
+    /// Connects to `dst` at the first of `addrs` that accepts a connection,
+    /// instead of resolving its host.
+    async fn connect_to_addrs(self, dst: Uri, addrs: Vec<SocketAddr>) -> Result<Conn, BoxError> {
+        let https = dst.scheme() == Some(&Scheme::HTTPS);
+        let host = dst.host().ok_or("no host in url")?;
+        // like for resolved addresses, the port of the URL is used
+        let port = dst.port_u16().unwrap_or(if https { 443 } else { 80 });
+        log::debug!("connecting to {host} at overridden addresses {addrs:?}");
+
+        let mut http = self.http_connector();
+        let mut last_err: BoxError = "no addresses to connect to".into();
+        let mut tcp = None;
+        for addr in addrs {
+            let uri: Uri = format!("http://{}", SocketAddr::new(addr.ip(), port)).parse()?;
+            match http.call(uri).await {
+                Ok(io) => {
+                    tcp = Some(io);
+                    break;
+                }
+                Err(err) => last_err = err.into(),
+            }
+        }
+        let tcp = tcp.ok_or(last_err)?;
+
+        if https {
+            let conn = self.stream_tls(Box::new(tcp.into_inner()), host, None).await?;
+            return Ok(Conn {
+                inner: self.verbose.wrap(conn),
+                is_proxy: false,
+                #[cfg(feature = "__tls")]
+                tls_info: self.tls_info,
+                #[cfg(not(feature = "__tls"))]
+                tls_info: false,
+            });
+        }
+
+        Ok(Conn {
+            inner: self.verbose.wrap(tcp),
+            is_proxy: false,
+            tls_info: false,
+        })
+    }
+}
+
+/// Returns the host and port to connect to a proxy of a chain.
+fn hop_addr(hop: &ProxyScheme) -> (String, u16) {
+    match hop {
+        ProxyScheme::Http { host, .. } => (host.host().to_owned(), host.port_u16().unwrap_or(80)),
+        ProxyScheme::Https { host, .. } => {
+            (host.host().to_owned(), host.port_u16().unwrap_or(443))
+        }
+        #[cfg(feature = "socks")]
+        ProxyScheme::Socks4 { addr } | ProxyScheme::Socks5 { addr, .. } => match addr {
+            std::net::SocketAddr::V4(addr) => (addr.ip().to_string(), addr.port()),
+            std::net::SocketAddr::V6(addr) => (format!("[{}]", addr.ip()), addr.port()),
+        },
     }
 }
 
@@ -592,6 +1220,45 @@ fn into_uri(scheme: Scheme, host: Authority) -> Uri {
         .expect("scheme and authority is valid Uri")
 }
 
+/// The TLS server name of `host`, without the brackets of an IPv6 address,
+/// so that certificates for IP addresses (RFC 8738) are verified against
+/// the address.
+#[cfg(feature = "__tls")]
+fn tls_host(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+/// The rustls server name of `host`, which is an IP address for hosts like
+/// `127.0.0.1` or `[::1]`.
+#[cfg(feature = "__rustls")]
+fn rustls_server_name(
+    host: &str,
+) -> Result<rustls_pki_types::ServerName<'static>, crate::error::InvalidServerName> {
+    use std::convert::TryFrom;
+
+    let name = tls_host(host);
+    rustls_pki_types::ServerName::try_from(name.to_owned()).map_err(|_| {
+        crate::error::InvalidServerName {
+            name: name.to_owned(),
+        }
+    })
+}
+
+/// Instruments a TLS handshake driven by reqwest, instead of by the
+/// `HttpsConnector` of a direct connection.
+#[cfg(feature = "__tls")]
+fn tls_handshake<F: Future>(handshake: F, _host: &str) -> impl Future<Output = F::Output> {
+    #[cfg(feature = "tracing")]
+    return tracing::Instrument::instrument(
+        handshake,
+        tracing::debug_span!("tls_handshake", host = _host),
+    );
+    #[cfg(not(feature = "tracing"))]
+    handshake
+}
+
 async fn with_timeout<T, F>(f: F, timeout: Option<Duration>) -> Result<T, BoxError>
 where
     F: Future<Output = Result<T, BoxError>>,
@@ -607,6 +1274,98 @@ where
     }
 }
 
+/// How new connections are wrapped, see `with_wrappers()`.
+struct Wrappers {
+    dst: Uri,
+    shutdown: Option<Arc<Shutdown>>,
+    probe_idle: bool,
+    raw_headers: bool,
+    h2c: bool,
+    pool_events: Option<PoolEvents>,
+    #[cfg(feature = "http2")]
+    http2_events: Option<Http2Events>,
+    slots: Option<Arc<Slots>>,
+}
+
+async fn with_wrappers<F>(f: F, wrappers: Wrappers) -> Result<Conn, BoxError>
+where
+    F: Future<Output = Result<Conn, BoxError>>,
+{
+    let dst = wrappers.dst;
+    let host = dst.host().unwrap_or_default();
+    let port = dst_port(&dst);
+    // wait for a slot before connecting
+    let slot = match wrappers.slots {
+        Some(slots) => Some(slots.acquire(host, port).await?),
+        None => None,
+    };
+    let mut conn = f.await?;
+    // requests through an HTTP proxy are forwarded to it with HTTP/1
+    if wrappers.h2c && !conn.is_proxy {
+        conn.inner = Box::new(prior_knowledge::PriorKnowledge { inner: conn.inner });
+    }
+    if let Some(slot) = slot {
+        conn.inner = Box::new(slotted::Slotted {
+            inner: conn.inner,
+            _slot: slot,
+        });
+    }
+    if wrappers.probe_idle && !conn.inner.connected().is_negotiated_h2() {
+        if let Some(socket) = conn.inner.peek_socket() {
+            conn.inner = Box::new(idle_probe::IdleProbe {
+                inner: conn.inner,
+                socket,
+            });
+        }
+    }
+    if wrappers.raw_headers && !conn.inner.connected().is_negotiated_h2() {
+        conn.inner = Box::new(raw_head::RawHeadConn {
+            inner: conn.inner,
+            recorder: Recorder::new(),
+        });
+    }
+    #[cfg(feature = "http2")]
+    if let Some(events) = wrappers.http2_events {
+        if events.http2_only || conn.inner.connected().is_negotiated_h2() {
+            conn.inner = Box::new(watched::Watched {
+                inner: conn.inner,
+                watcher: Watcher::new(events.callback, host, port),
+            });
+        }
+    }
+    if let Some(shutdown) = wrappers.shutdown {
+        conn.inner = Box::new(closable::Closable {
+            inner: conn.inner,
+            watch: shutdown.watch_conn(),
+        });
+    }
+    if let Some(events) = wrappers.pool_events {
+        let version = if events.http2_only || conn.inner.connected().is_negotiated_h2() {
+            http::Version::HTTP_2
+        } else {
+            http::Version::HTTP_11
+        };
+        let tracked = Tracked::created(events.callback, host, port, version);
+        conn.inner = Box::new(tracked::TrackedConn {
+            inner: conn.inner,
+            tracked,
+            closed: None,
+        });
+    }
+    Ok(conn)
+}
+
+/// The port of `dst`, or the default one of its scheme.
+fn dst_port(dst: &Uri) -> u16 {
+    dst.port_u16().unwrap_or_else(|| {
+        if dst.scheme() == Some(&Scheme::HTTPS) {
+            443
+        } else {
+            80
+        }
+    })
+}
+
 impl Service<Uri> for ConnectorService {
     type Response = Conn;
     type Error = BoxError;
@@ -617,21 +1376,66 @@ impl Service<Uri> for ConnectorService {
     }
 
     fn call(&mut self, dst: Uri) -> Self::Future {
+        if let Some(ref allowed) = self.allowed_ports {
+            let port = dst_port(&dst);
+            if !allowed.contains(&port) {
+                log::debug!("port {port} of {dst:?} is not allowed");
+                let err = crate::error::PortNotAllowed { port };
+                return Box::pin(futures_util::future::err(err.into()));
+            }
+        }
+
         log::debug!("starting new connection: {dst:?}");
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("connect", uri = %dst, proxy = tracing::field::Empty);
         let timeout = self.simple_timeout;
+        let wrappers = Wrappers {
+            dst: dst.clone(),
+            shutdown: self.shutdown.clone(),
+            probe_idle: self.probe_idle,
+            raw_headers: self.raw_headers,
+            h2c: self.h2c && dst.scheme() == Some(&Scheme::HTTP),
+            pool_events: self.pool_events.clone(),
+            #[cfg(feature = "http2")]
+            http2_events: self.http2_events.clone(),
+            slots: self.slots.clone(),
+        };
+        #[cfg(feature = "__tls")]
+        let this = self.clone().with_host_tls(&dst);
+        #[cfg(not(feature = "__tls"))]
+        let this = self.clone();
         for prox in self.proxies.iter() {
             if let Some(proxy_scheme) = prox.intercept(&dst) {
-                return Box::pin(with_timeout(
-                    self.clone().connect_via_proxy(dst, proxy_scheme),
-                    timeout,
-                ));
+                #[cfg(feature = "tracing")]
+                span.record("proxy", tracing::field::debug(&proxy_scheme));
+                let connecting: Connecting = if prox.hops().is_empty() {
+                    Box::pin(this.connect_via_proxy(dst, proxy_scheme))
+                } else {
+                    Box::pin(this.connect_via_chain(dst, prox.hops().to_vec(), proxy_scheme))
+                };
+                let connecting = futures_util::TryFutureExt::map_err(connecting, |err| {
+                    Box::new(crate::error::ProxyFailed(err)) as BoxError
+                });
+                let connecting = with_wrappers(with_timeout(connecting, timeout), wrappers);
+                #[cfg(feature = "tracing")]
+                let connecting = tracing::Instrument::instrument(connecting, span);
+                return Box::pin(connecting);
             }
         }
 
-        Box::pin(with_timeout(
-            self.clone().connect_with_maybe_proxy(dst, false),
-            timeout,
-        ))
+        let addrs = self
+            .dns_overrides
+            .as_ref()
+            .and_then(|overrides| overrides.get(dst.host()?))
+            .cloned();
+        let connecting: Connecting = match addrs {
+            Some(addrs) => Box::pin(this.connect_to_addrs(dst, addrs)),
+            None => Box::pin(this.connect_with_maybe_proxy(dst, false)),
+        };
+        let connecting = with_wrappers(with_timeout(connecting, timeout), wrappers);
+        #[cfg(feature = "tracing")]
+        let connecting = tracing::Instrument::instrument(connecting, span);
+        Box::pin(connecting)
     }
 }
 
@@ -734,29 +1538,96 @@ impl TlsInfoFactory for hyper_rustls::MaybeHttpsStream<TokioIo<tokio::net::TcpSt
     }
 }
 
-pub(crate) trait AsyncConn:
-    Read + Write + Connection + Send + Sync + Unpin + 'static
-{
+/// Duplicates the handle of the underlying TCP socket, so it can be peeked
+/// at without going through the TLS layer or the I/O driver.
+trait PeekSocket {
+    fn peek_socket(&self) -> Option<std::net::TcpStream>;
 }
 
-impl<T: Read + Write + Connection + Send + Sync + Unpin + 'static> AsyncConn for T {}
+impl PeekSocket for tokio::net::TcpStream {
+    #[cfg(unix)]
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        use std::os::unix::io::AsFd;
+        let fd = self.as_fd().try_clone_to_owned().ok()?;
+        Some(std::net::TcpStream::from(fd))
+    }
 
-#[cfg(feature = "__tls")]
-trait AsyncConnWithInfo: AsyncConn + TlsInfoFactory {}
-#[cfg(not(feature = "__tls"))]
-trait AsyncConnWithInfo: AsyncConn {}
+    #[cfg(windows)]
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        use std::os::windows::io::AsSocket;
+        let socket = self.as_socket().try_clone_to_owned().ok()?;
+        Some(std::net::TcpStream::from(socket))
+    }
 
-#[cfg(feature = "__tls")]
-impl<T: AsyncConn + TlsInfoFactory> AsyncConnWithInfo for T {}
-#[cfg(not(feature = "__tls"))]
-impl<T: AsyncConn> AsyncConnWithInfo for T {}
+    #[cfg(not(any(unix, windows)))]
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        None
+    }
+}
 
-type BoxConn = Box<dyn AsyncConnWithInfo>;
+impl<T: PeekSocket> PeekSocket for TokioIo<T> {
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        self.inner().peek_socket()
+    }
+}
 
-pub(crate) mod sealed {
-    use super::*;
-    #[derive(Debug)]
-    pub struct Unnameable(pub(super) Uri);
+#[cfg(feature = "default-tls")]
+impl<S: PeekSocket> PeekSocket for tokio_native_tls::TlsStream<S> {
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        self.get_ref().get_ref().get_ref().peek_socket()
+    }
+}
+
+#[cfg(feature = "default-tls")]
+impl<T: PeekSocket> PeekSocket for hyper_tls::MaybeHttpsStream<T> {
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        match self {
+            hyper_tls::MaybeHttpsStream::Https(tls) => tls.peek_socket(),
+            hyper_tls::MaybeHttpsStream::Http(tcp) => tcp.peek_socket(),
+        }
+    }
+}
+
+#[cfg(feature = "__rustls")]
+impl<T: PeekSocket> PeekSocket for tokio_rustls::client::TlsStream<T> {
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        self.get_ref().0.peek_socket()
+    }
+}
+
+#[cfg(feature = "__rustls")]
+impl<T: PeekSocket> PeekSocket for hyper_rustls::MaybeHttpsStream<T> {
+    fn peek_socket(&self) -> Option<std::net::TcpStream> {
+        match self {
+            hyper_rustls::MaybeHttpsStream::Https(tls) => tls.peek_socket(),
+            hyper_rustls::MaybeHttpsStream::Http(tcp) => tcp.peek_socket(),
+        }
+    }
+}
+
+pub(crate) trait AsyncConn:
+    Read + Write + Connection + Send + Sync + Unpin + 'static
+{
+}
+
+impl<T: Read + Write + Connection + Send + Sync + Unpin + 'static> AsyncConn for T {}
+
+#[cfg(feature = "__tls")]
+trait AsyncConnWithInfo: AsyncConn + TlsInfoFactory + PeekSocket {}
+#[cfg(not(feature = "__tls"))]
+trait AsyncConnWithInfo: AsyncConn + PeekSocket {}
+
+#[cfg(feature = "__tls")]
+impl<T: AsyncConn + TlsInfoFactory + PeekSocket> AsyncConnWithInfo for T {}
+#[cfg(not(feature = "__tls"))]
+impl<T: AsyncConn + PeekSocket> AsyncConnWithInfo for T {}
+
+type BoxConn = Box<dyn AsyncConnWithInfo>;
+
+pub(crate) mod sealed {
+    use super::*;
+    #[derive(Debug)]
+    pub struct Unnameable(pub(super) Uri);
 
     pin_project! {
         /// Note: the `is_proxy` member means *is plain text HTTP proxy*.
@@ -839,7 +1710,6 @@ pub(crate) mod sealed {
 
 pub(crate) type Connecting = Pin<Box<dyn Future<Output = Result<Conn, BoxError>> + Send>>;
 
-#[cfg(feature = "__tls")]
 async fn tunnel<T>(
     mut conn: T,
     host: String,
@@ -911,7 +1781,6 @@ where
     }
 }
 
-#[cfg(feature = "__tls")]
 fn tunnel_eof() -> BoxError {
     "unexpected eof while tunneling".into()
 }
@@ -1038,6 +1907,12 @@ mod native_tls_conn {
             self.inner.tls_info()
         }
     }
+
+    impl<T: super::PeekSocket> super::PeekSocket for NativeTlsConn<T> {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
 }
 
 #[cfg(feature = "__rustls")]
@@ -1152,6 +2027,132 @@ mod rustls_tls_conn {
             self.inner.tls_info()
         }
     }
+
+    impl<T: super::PeekSocket> super::PeekSocket for RustlsTlsConn<T> {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+mod stream_conn {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use hyper_util::rt::TokioIo;
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncWrite};
+
+    pub(super) trait StreamIo: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static {}
+
+    impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static> StreamIo for T {}
+
+    /// A stream that was set up by hand, such as a tunnel through some of the
+    /// proxies of a chain.
+    pub(super) type BoxStream = Box<dyn StreamIo>;
+
+    /// A connection over a `BoxStream`.
+    pub(super) struct StreamConn {
+        inner: TokioIo<BoxStream>,
+        h2: bool,
+        #[cfg(feature = "__tls")]
+        tls_info: Option<crate::tls::TlsInfo>,
+    }
+
+    impl StreamConn {
+        pub(super) fn new(stream: BoxStream) -> StreamConn {
+            StreamConn {
+                inner: TokioIo::new(stream),
+                h2: false,
+                #[cfg(feature = "__tls")]
+                tls_info: None,
+            }
+        }
+
+        #[cfg(feature = "__tls")]
+        pub(super) fn with_tls(
+            stream: BoxStream,
+            h2: bool,
+            tls_info: crate::tls::TlsInfo,
+        ) -> StreamConn {
+            StreamConn {
+                inner: TokioIo::new(stream),
+                h2,
+                tls_info: Some(tls_info),
+            }
+        }
+
+        pub(super) fn into_inner(self) -> BoxStream {
+            self.inner.into_inner()
+        }
+    }
+
+    impl Connection for StreamConn {
+        fn connected(&self) -> Connected {
+            if self.h2 {
+                Connected::new().negotiated_h2()
+            } else {
+                Connected::new()
+            }
+        }
+    }
+
+    impl Read for StreamConn {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl Write for StreamConn {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for StreamConn {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.tls_info.clone()
+        }
+    }
+
+    impl super::PeekSocket for StreamConn {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            None
+        }
+    }
 }
 
 #[cfg(feature = "socks")]
@@ -1160,6 +2161,7 @@ mod socks {
     use std::net::ToSocketAddrs;
 
     use http::Uri;
+    use tokio::io::{AsyncRead, AsyncWrite};
     use tokio::net::TcpStream;
     use tokio_socks::tcp::{Socks4Stream, Socks5Stream};
 
@@ -1222,88 +2224,101 @@ mod socks {
             _ => unreachable!(),
         }
     }
+
+    /// Opens a tunnel to `host:port` through a SOCKS proxy of a chain,
+    /// reached over `stream`.
+    pub(super) async fn tunnel<S>(
+        proxy: &ProxyScheme,
+        stream: S,
+        host: &str,
+        port: u16,
+    ) -> Result<S, BoxError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut host = host.trim_start_matches('[').trim_end_matches(']').to_owned();
+        if let ProxyScheme::Socks4 { .. } | ProxyScheme::Socks5 { remote_dns: false, .. } = proxy {
+            let maybe_new_target = (host.as_str(), port).to_socket_addrs()?.next();
+            if let Some(new_target) = maybe_new_target {
+                host = new_target.ip().to_string();
+            }
+        }
+
+        let stream = match proxy {
+            ProxyScheme::Socks4 { .. } => Socks4Stream::connect_with_socket(stream, (host.as_str(), port))
+                .await
+                .map_err(|e| format!("socks connect error: {e}"))?
+                .into_inner(),
+            ProxyScheme::Socks5 {
+                auth: Some((username, password)),
+                ..
+            } => Socks5Stream::connect_with_password_and_socket(
+                stream,
+                (host.as_str(), port),
+                username,
+                password,
+            )
+            .await
+            .map_err(|e| format!("socks connect error: {e}"))?
+            .into_inner(),
+            ProxyScheme::Socks5 { .. } => Socks5Stream::connect_with_socket(stream, (host.as_str(), port))
+                .await
+                .map_err(|e| format!("socks connect error: {e}"))?
+                .into_inner(),
+            _ => unreachable!("socks::tunnel is only called for socks proxies"),
+        };
+        Ok(stream)
+    }
 }
 
-mod verbose {
+mod closable {
     use hyper::rt::{Read, ReadBufCursor, Write};
     use hyper_util::client::legacy::connect::{Connected, Connection};
-    use std::cmp::min;
-    use std::fmt;
     use std::io::{self, IoSlice};
     use std::pin::Pin;
     use std::task::{Context, Poll};
 
-    pub(super) const OFF: Wrapper = Wrapper(false);
-
-    #[derive(Clone, Copy)]
-    pub(super) struct Wrapper(pub(super) bool);
+    use crate::async_impl::shutdown::ConnWatch;
 
-    impl Wrapper {
-        pub(super) fn wrap<T: super::AsyncConnWithInfo>(&self, conn: T) -> super::BoxConn {
-            if self.0 && log::log_enabled!(log::Level::Trace) {
-                Box::new(Verbose {
-                    // truncate is fine
-                    id: crate::util::fast_random() as u32,
-                    inner: conn,
-                })
-            } else {
-                Box::new(conn)
-            }
-        }
+    /// A connection that reports EOF once the client shuts down.
+    pub(super) struct Closable {
+        pub(super) inner: super::BoxConn,
+        pub(super) watch: ConnWatch,
     }
 
-    struct Verbose<T> {
-        id: u32,
-        inner: T,
+    fn closed() -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "client has been shut down")
     }
 
-    impl<T: Connection + Read + Write + Unpin> Connection for Verbose<T> {
+    impl Connection for Closable {
         fn connected(&self) -> Connected {
             self.inner.connected()
         }
     }
 
-    impl<T: Read + Write + Unpin> Read for Verbose<T> {
+    impl Read for Closable {
         fn poll_read(
             mut self: Pin<&mut Self>,
             cx: &mut Context,
-            mut buf: ReadBufCursor<'_>,
-        ) -> Poll<std::io::Result<()>> {
-            // TODO: This _does_ forget the `init` len, so it could result in
-            // re-initializing twice. Needs upstream support, perhaps.
-            // SAFETY: Passing to a ReadBuf will never de-initialize any bytes.
-            let mut vbuf = hyper::rt::ReadBuf::uninit(unsafe { buf.as_mut() });
-            match Pin::new(&mut self.inner).poll_read(cx, vbuf.unfilled()) {
-                Poll::Ready(Ok(())) => {
-                    log::trace!("{:08x} read: {:?}", self.id, Escape(vbuf.filled()));
-                    let len = vbuf.filled().len();
-                    // SAFETY: The two cursors were for the same buffer. What was
-                    // filled in one is safe in the other.
-                    unsafe {
-                        buf.advance(len);
-                    }
-                    Poll::Ready(Ok(()))
-                }
-                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
-                Poll::Pending => Poll::Pending,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            if self.watch.poll_closed(cx) {
+                return Poll::Ready(Ok(()));
             }
+            Pin::new(&mut self.inner).poll_read(cx, buf)
         }
     }
 
-    impl<T: Read + Write + Unpin> Write for Verbose<T> {
+    impl Write for Closable {
         fn poll_write(
             mut self: Pin<&mut Self>,
             cx: &mut Context,
             buf: &[u8],
-        ) -> Poll<Result<usize, std::io::Error>> {
-            match Pin::new(&mut self.inner).poll_write(cx, buf) {
-                Poll::Ready(Ok(n)) => {
-                    log::trace!("{:08x} write: {:?}", self.id, Escape(&buf[..n]));
-                    Poll::Ready(Ok(n))
-                }
-                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
-                Poll::Pending => Poll::Pending,
+        ) -> Poll<Result<usize, io::Error>> {
+            if self.watch.poll_closed(cx) {
+                return Poll::Ready(Err(closed()));
             }
+            Pin::new(&mut self.inner).poll_write(cx, buf)
         }
 
         fn poll_write_vectored(
@@ -1311,90 +2326,794 @@ mod verbose {
             cx: &mut Context<'_>,
             bufs: &[IoSlice<'_>],
         ) -> Poll<Result<usize, io::Error>> {
-            match Pin::new(&mut self.inner).poll_write_vectored(cx, bufs) {
-                Poll::Ready(Ok(nwritten)) => {
-                    log::trace!(
-                        "{:08x} write (vectored): {:?}",
-                        self.id,
-                        Vectored { bufs, nwritten }
-                    );
-                    Poll::Ready(Ok(nwritten))
-                }
-                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
-                Poll::Pending => Poll::Pending,
+            if self.watch.poll_closed(cx) {
+                return Poll::Ready(Err(closed()));
             }
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
         }
 
         fn is_write_vectored(&self) -> bool {
             self.inner.is_write_vectored()
         }
 
-        fn poll_flush(
-            mut self: Pin<&mut Self>,
-            cx: &mut Context,
-        ) -> Poll<Result<(), std::io::Error>> {
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
             Pin::new(&mut self.inner).poll_flush(cx)
         }
 
         fn poll_shutdown(
             mut self: Pin<&mut Self>,
             cx: &mut Context,
-        ) -> Poll<Result<(), std::io::Error>> {
+        ) -> Poll<Result<(), io::Error>> {
             Pin::new(&mut self.inner).poll_shutdown(cx)
         }
     }
 
     #[cfg(feature = "__tls")]
-    impl<T: super::TlsInfoFactory> super::TlsInfoFactory for Verbose<T> {
+    impl super::TlsInfoFactory for Closable {
         fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
             self.inner.tls_info()
         }
     }
 
-    struct Escape<'a>(&'a [u8]);
-
-    impl fmt::Debug for Escape<'_> {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "b\"")?;
-            for &c in self.0 {
-                // https://doc.rust-lang.org/reference.html#byte-escapes
-                if c == b'\n' {
-                    write!(f, "\\n")?;
-                } else if c == b'\r' {
-                    write!(f, "\\r")?;
-                } else if c == b'\t' {
-                    write!(f, "\\t")?;
-                } else if c == b'\\' || c == b'"' {
-                    write!(f, "\\{}", c as char)?;
-                } else if c == b'\0' {
-                    write!(f, "\\0")?;
-                // ASCII printable
-                } else if c >= 0x20 && c < 0x7f {
-                    write!(f, "{}", c as char)?;
-                } else {
-                    write!(f, "\\x{c:02x}")?;
-                }
-            }
-            write!(f, "\"")?;
-            Ok(())
+    impl super::PeekSocket for Closable {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            self.inner.peek_socket()
         }
     }
+}
 
-    struct Vectored<'a, 'b> {
-        bufs: &'a [IoSlice<'b>],
-        nwritten: usize,
+mod slotted {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use crate::pool::Slot;
+
+    /// A connection that frees its slot of the pool when dropped.
+    pub(super) struct Slotted {
+        pub(super) inner: super::BoxConn,
+        pub(super) _slot: Slot,
     }
 
-    impl fmt::Debug for Vectored<'_, '_> {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let mut left = self.nwritten;
-            for buf in self.bufs.iter() {
-                if left == 0 {
-                    break;
-                }
-                let n = min(left, buf.len());
-                Escape(&buf[..n]).fmt(f)?;
-                left -= n;
+    impl Connection for Slotted {
+        fn connected(&self) -> Connected {
+            self.inner.connected()
+        }
+    }
+
+    impl Read for Slotted {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl Write for Slotted {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for Slotted {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl super::PeekSocket for Slotted {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+mod prior_knowledge {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// A cleartext connection that speaks HTTP/2 from the start.
+    pub(super) struct PriorKnowledge {
+        pub(super) inner: super::BoxConn,
+    }
+
+    impl Connection for PriorKnowledge {
+        fn connected(&self) -> Connected {
+            self.inner.connected().negotiated_h2()
+        }
+    }
+
+    impl Read for PriorKnowledge {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl Write for PriorKnowledge {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for PriorKnowledge {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl super::PeekSocket for PriorKnowledge {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+mod idle_probe {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::io::{self, IoSlice};
+    use std::net::TcpStream;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// A connection that peeks at the socket whenever a read would block.
+    ///
+    /// The I/O driver may not have seen yet that the server closed an idle
+    /// connection, and the pool would then hand it out for a request that is
+    /// bound to fail. Reporting EOF right away lets hyper retry the request on
+    /// a new connection instead.
+    pub(super) struct IdleProbe {
+        pub(super) inner: super::BoxConn,
+        pub(super) socket: TcpStream,
+    }
+
+    fn peer_closed(socket: &TcpStream) -> bool {
+        // the socket is non-blocking, so this is `WouldBlock` unless the
+        // server sent something
+        matches!(socket.peek(&mut [0]), Ok(0))
+    }
+
+    impl Connection for IdleProbe {
+        fn connected(&self) -> Connected {
+            self.inner.connected()
+        }
+    }
+
+    impl Read for IdleProbe {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            match Pin::new(&mut self.inner).poll_read(cx, buf) {
+                Poll::Pending if peer_closed(&self.socket) => {
+                    log::trace!("idle probe found connection closed by peer");
+                    Poll::Ready(Ok(()))
+                }
+                res => res,
+            }
+        }
+    }
+
+    impl Write for IdleProbe {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for IdleProbe {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl super::PeekSocket for IdleProbe {
+        fn peek_socket(&self) -> Option<TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+#[cfg(feature = "http2")]
+mod watched {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::io::{self, IoSlice};
+    use std::net::TcpStream;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use crate::http2::Watcher;
+
+    /// An HTTP/2 connection whose frames are reported.
+    pub(super) struct Watched {
+        pub(super) inner: super::BoxConn,
+        pub(super) watcher: Watcher,
+    }
+
+    impl Connection for Watched {
+        fn connected(&self) -> Connected {
+            self.inner.connected()
+        }
+    }
+
+    impl Read for Watched {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            mut buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            // SAFETY: Passing to a ReadBuf will never de-initialize any bytes.
+            let mut tbuf = hyper::rt::ReadBuf::uninit(unsafe { buf.as_mut() });
+            let res = Pin::new(&mut self.inner).poll_read(cx, tbuf.unfilled());
+            if let Poll::Ready(Ok(())) = res {
+                let filled = tbuf.filled();
+                let len = filled.len();
+                self.watcher.received(filled);
+                // SAFETY: The two cursors were for the same buffer. What was
+                // filled in one is safe in the other.
+                unsafe {
+                    buf.advance(len);
+                }
+            }
+            res
+        }
+    }
+
+    impl Write for Watched {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+            if let Poll::Ready(Ok(n)) = res {
+                self.watcher.sent(&buf[..n]);
+            }
+            res
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+            if let Poll::Ready(Ok(n)) = res {
+                let mut left = n;
+                for buf in bufs {
+                    let len = left.min(buf.len());
+                    self.watcher.sent(&buf[..len]);
+                    left -= len;
+                    if left == 0 {
+                        break;
+                    }
+                }
+            }
+            res
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for Watched {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl super::PeekSocket for Watched {
+        fn peek_socket(&self) -> Option<TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+mod tracked {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::io::{self, IoSlice};
+    use std::net::TcpStream;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use crate::pool::{PoolEventKind, Tracked};
+
+    /// A connection that reports how it was closed when dropped.
+    pub(super) struct TrackedConn {
+        pub(super) inner: super::BoxConn,
+        pub(super) tracked: Arc<Tracked>,
+        pub(super) closed: Option<PoolEventKind>,
+    }
+
+    impl TrackedConn {
+        fn check<T>(&mut self, res: Poll<io::Result<T>>) -> Poll<io::Result<T>> {
+            if let Poll::Ready(Err(_)) = res {
+                self.closed = Some(PoolEventKind::ClosedWithError);
+            }
+            res
+        }
+    }
+
+    impl Drop for TrackedConn {
+        fn drop(&mut self) {
+            self.tracked
+                .emit(self.closed.unwrap_or(PoolEventKind::Evicted));
+        }
+    }
+
+    impl Connection for TrackedConn {
+        fn connected(&self) -> Connected {
+            self.inner.connected().extra(self.tracked.conn_use())
+        }
+    }
+
+    impl Read for TrackedConn {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            mut buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            // SAFETY: Passing to a ReadBuf will never de-initialize any bytes.
+            let mut tbuf = hyper::rt::ReadBuf::uninit(unsafe { buf.as_mut() });
+            let res = Pin::new(&mut self.inner).poll_read(cx, tbuf.unfilled());
+            if let Poll::Ready(Ok(())) = res {
+                let len = tbuf.filled().len();
+                if len == 0 && buf.remaining() > 0 && self.closed.is_none() {
+                    self.closed = Some(PoolEventKind::Closed);
+                }
+                // SAFETY: The two cursors were for the same buffer. What was
+                // filled in one is safe in the other.
+                unsafe {
+                    buf.advance(len);
+                }
+            }
+            self.check(res)
+        }
+    }
+
+    impl Write for TrackedConn {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+            self.check(res)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+            self.check(res)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_flush(cx);
+            self.check(res)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_shutdown(cx);
+            self.check(res)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for TrackedConn {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl super::PeekSocket for TrackedConn {
+        fn peek_socket(&self) -> Option<TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+mod raw_head {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::io::{self, IoSlice};
+    use std::net::TcpStream;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use crate::raw_headers::Recorder;
+
+    /// A connection that records the raw headers of the responses read
+    /// from it.
+    pub(super) struct RawHeadConn {
+        pub(super) inner: super::BoxConn,
+        pub(super) recorder: Recorder,
+    }
+
+    impl Connection for RawHeadConn {
+        fn connected(&self) -> Connected {
+            self.inner.connected().extra(self.recorder.recorded())
+        }
+    }
+
+    impl Read for RawHeadConn {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            mut buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            // SAFETY: Passing to a ReadBuf will never de-initialize any bytes.
+            let mut rbuf = hyper::rt::ReadBuf::uninit(unsafe { buf.as_mut() });
+            let res = Pin::new(&mut self.inner).poll_read(cx, rbuf.unfilled());
+            if let Poll::Ready(Ok(())) = res {
+                self.recorder.read(rbuf.filled());
+                let len = rbuf.filled().len();
+                // SAFETY: The two cursors were for the same buffer. What was
+                // filled in one is safe in the other.
+                unsafe {
+                    buf.advance(len);
+                }
+            }
+            res
+        }
+    }
+
+    impl Write for RawHeadConn {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+            if let Poll::Ready(Ok(n)) = res {
+                if n > 0 {
+                    self.recorder.write();
+                }
+            }
+            res
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            let res = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+            if let Poll::Ready(Ok(n)) = res {
+                if n > 0 {
+                    self.recorder.write();
+                }
+            }
+            res
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl super::TlsInfoFactory for RawHeadConn {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl super::PeekSocket for RawHeadConn {
+        fn peek_socket(&self) -> Option<TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+}
+
+mod verbose {
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use std::cmp::min;
+    use std::fmt;
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    pub(super) const OFF: Wrapper = Wrapper(false);
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Wrapper(pub(super) bool);
+
+    impl Wrapper {
+        pub(super) fn wrap<T: super::AsyncConnWithInfo>(&self, conn: T) -> super::BoxConn {
+            if self.0 && log::log_enabled!(log::Level::Trace) {
+                Box::new(Verbose {
+                    // truncate is fine
+                    id: crate::util::fast_random() as u32,
+                    inner: conn,
+                })
+            } else {
+                Box::new(conn)
+            }
+        }
+    }
+
+    struct Verbose<T> {
+        id: u32,
+        inner: T,
+    }
+
+    impl<T: Connection + Read + Write + Unpin> Connection for Verbose<T> {
+        fn connected(&self) -> Connected {
+            self.inner.connected()
+        }
+    }
+
+    impl<T: Read + Write + Unpin> Read for Verbose<T> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            mut buf: ReadBufCursor<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            // TODO: This _does_ forget the `init` len, so it could result in
+            // re-initializing twice. Needs upstream support, perhaps.
+            // SAFETY: Passing to a ReadBuf will never de-initialize any bytes.
+            let mut vbuf = hyper::rt::ReadBuf::uninit(unsafe { buf.as_mut() });
+            match Pin::new(&mut self.inner).poll_read(cx, vbuf.unfilled()) {
+                Poll::Ready(Ok(())) => {
+                    log::trace!("{:08x} read: {:?}", self.id, Escape(vbuf.filled()));
+                    let len = vbuf.filled().len();
+                    // SAFETY: The two cursors were for the same buffer. What was
+                    // filled in one is safe in the other.
+                    unsafe {
+                        buf.advance(len);
+                    }
+                    Poll::Ready(Ok(()))
+                }
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    impl<T: Read + Write + Unpin> Write for Verbose<T> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, std::io::Error>> {
+            match Pin::new(&mut self.inner).poll_write(cx, buf) {
+                Poll::Ready(Ok(n)) => {
+                    log::trace!("{:08x} write: {:?}", self.id, Escape(&buf[..n]));
+                    Poll::Ready(Ok(n))
+                }
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => Poll::Pending,
+            }
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            match Pin::new(&mut self.inner).poll_write_vectored(cx, bufs) {
+                Poll::Ready(Ok(nwritten)) => {
+                    log::trace!(
+                        "{:08x} write (vectored): {:?}",
+                        self.id,
+                        Vectored { bufs, nwritten }
+                    );
+                    Poll::Ready(Ok(nwritten))
+                }
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => Poll::Pending,
+            }
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), std::io::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+        ) -> Poll<Result<(), std::io::Error>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[cfg(feature = "__tls")]
+    impl<T: super::TlsInfoFactory> super::TlsInfoFactory for Verbose<T> {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+    }
+
+    impl<T: super::PeekSocket> super::PeekSocket for Verbose<T> {
+        fn peek_socket(&self) -> Option<std::net::TcpStream> {
+            self.inner.peek_socket()
+        }
+    }
+
+    struct Escape<'a>(&'a [u8]);
+
+    impl fmt::Debug for Escape<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "b\"")?;
+            for &c in self.0 {
+                // https://doc.rust-lang.org/reference.html#byte-escapes
+                if c == b'\n' {
+                    write!(f, "\\n")?;
+                } else if c == b'\r' {
+                    write!(f, "\\r")?;
+                } else if c == b'\t' {
+                    write!(f, "\\t")?;
+                } else if c == b'\\' || c == b'"' {
+                    write!(f, "\\{}", c as char)?;
+                } else if c == b'\0' {
+                    write!(f, "\\0")?;
+                // ASCII printable
+                } else if c >= 0x20 && c < 0x7f {
+                    write!(f, "{}", c as char)?;
+                } else {
+                    write!(f, "\\x{c:02x}")?;
+                }
+            }
+            write!(f, "\"")?;
+            Ok(())
+        }
+    }
+
+    struct Vectored<'a, 'b> {
+        bufs: &'a [IoSlice<'b>],
+        nwritten: usize,
+    }
+
+    impl fmt::Debug for Vectored<'_, '_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let mut left = self.nwritten;
+            for buf in self.bufs.iter() {
+                if left == 0 {
+                    break;
+                }
+                let n = min(left, buf.len());
+                Escape(&buf[..n]).fmt(f)?;
+                left -= n;
             }
             Ok(())
         }
@@ -1566,4 +3285,29 @@ mod tests {
 
         rt.block_on(f).unwrap();
     }
+
+    #[cfg(feature = "__tls")]
+    #[test]
+    fn test_tls_host() {
+        assert_eq!(super::tls_host("example.com"), "example.com");
+        assert_eq!(super::tls_host("127.0.0.1"), "127.0.0.1");
+        assert_eq!(super::tls_host("[::1]"), "::1");
+    }
+
+    #[cfg(feature = "__rustls")]
+    #[test]
+    fn test_rustls_server_name() {
+        use rustls_pki_types::ServerName;
+        use std::net::Ipv6Addr;
+
+        let name = super::rustls_server_name("[::1]").unwrap();
+        assert_eq!(name, ServerName::from(Ipv6Addr::LOCALHOST));
+        let name = super::rustls_server_name("127.0.0.1").unwrap();
+        assert!(matches!(name, ServerName::IpAddress(_)));
+        let name = super::rustls_server_name("example.com").unwrap();
+        assert!(matches!(name, ServerName::DnsName(_)));
+
+        let err = super::rustls_server_name("bad_name!").unwrap_err();
+        assert_eq!(err.to_string(), "invalid TLS server name: bad_name!");
+    }
 }
@@ -2,11 +2,12 @@
 
 use std::convert::TryInto;
 use std::fmt;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 
-use crate::header::{HeaderValue, SET_COOKIE};
+use crate::header::{HeaderMap, HeaderValue, SET_COOKIE};
 use bytes::Bytes;
+use url::Url;
 
 /// Actions for a persistent cookie store providing session support.
 pub trait CookieStore: Send + Sync {
@@ -19,14 +20,40 @@ pub trait CookieStore: Send + Sync {
 /// A single HTTP cookie.
 pub struct Cookie<'a>(cookie_crate::Cookie<'a>);
 
+/// A change of a cookie store caused by a response, reported to the
+/// function set with
+/// [`ClientBuilder::cookie_observer()`](crate::ClientBuilder::cookie_observer).
+pub struct CookieEvent<'a> {
+    kind: CookieEventKind,
+    cookie: &'a Cookie<'a>,
+    url: &'a Url,
+}
+
+/// The kinds of [`CookieEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CookieEventKind {
+    /// A new cookie was stored.
+    Stored,
+    /// A cookie that was already stored was set again, usually with a new
+    /// value or expiration time.
+    Updated,
+    /// A stored cookie was removed, because the response set it with an
+    /// expiration time in the past.
+    Expired,
+}
+
+pub(crate) type CookieObserver = Arc<dyn Fn(&CookieEvent<'_>) + Send + Sync>;
+
 /// A good default `CookieStore` implementation.
 ///
 /// This is the implementation used when simply calling `cookie_store(true)`.
 /// This type is exposed to allow creating one and filling it with some
 /// existing cookies more easily, before creating a `Client`.
 ///
-/// For more advanced scenarios, such as needing to serialize the store or
-/// manipulate it between requests, you may refer to the
+/// The stored cookies can be inspected and modified between requests. For
+/// more advanced scenarios, such as needing to serialize the store, you may
+/// refer to the
 /// [reqwest_cookie_store crate](https://crates.io/crates/reqwest_cookie_store).
 #[derive(Debug, Default)]
 pub struct Jar(RwLock<cookie_store::CookieStore>);
@@ -42,6 +69,18 @@ impl<'a> Cookie<'a> {
             .map(Cookie)
     }
 
+    /// Copies a cookie out of the store, with the domain and path it is
+    /// stored for.
+    fn stored(cookie: &cookie_store::Cookie<'static>) -> Cookie<'static> {
+        let mut raw = cookie_crate::Cookie::clone(cookie);
+        let domain = String::from(&cookie.domain);
+        if !domain.is_empty() {
+            raw.set_domain(domain);
+        }
+        raw.set_path(String::from(cookie.path.as_ref()));
+        Cookie(raw)
+    }
+
     /// The name of the cookie.
     pub fn name(&self) -> &str {
         self.0.name()
@@ -105,6 +144,35 @@ impl<'a> fmt::Debug for Cookie<'a> {
     }
 }
 
+// ===== impl CookieEvent =====
+
+impl<'a> CookieEvent<'a> {
+    /// What happened to the cookie.
+    pub fn kind(&self) -> CookieEventKind {
+        self.kind
+    }
+
+    /// The cookie, as set by the `Set-Cookie` header of the response.
+    pub fn cookie(&self) -> &Cookie<'a> {
+        self.cookie
+    }
+
+    /// The URL of the response that set the cookie.
+    pub fn url(&self) -> &'a Url {
+        self.url
+    }
+}
+
+impl fmt::Debug for CookieEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CookieEvent")
+            .field("kind", &self.kind)
+            .field("cookie", &self.cookie)
+            .field("url", &self.url.as_str())
+            .finish()
+    }
+}
+
 pub(crate) fn extract_response_cookie_headers<'a>(
     headers: &'a hyper::HeaderMap,
 ) -> impl Iterator<Item = &'a HeaderValue> + 'a {
@@ -120,6 +188,80 @@ pub(crate) fn extract_response_cookies<'a>(
         .map(|value| Cookie::parse(value))
 }
 
+/// Stores the cookies set by a response from `url`.
+///
+/// With an `observer`, each cookie is stored on its own, and the values
+/// the store sends back for it are compared before and after, to find out
+/// what changed whatever the `CookieStore` implementation.
+pub(crate) fn store_response_cookies(
+    store: &dyn CookieStore,
+    headers: &HeaderMap,
+    url: &Url,
+    observer: Option<&CookieObserver>,
+) {
+    let observer = match observer {
+        Some(observer) => observer,
+        None => {
+            let mut cookies = extract_response_cookie_headers(headers).peekable();
+            if cookies.peek().is_some() {
+                store.set_cookies(&mut cookies, url);
+            }
+            return;
+        }
+    };
+
+    for value in headers.get_all(SET_COOKIE) {
+        let cookie = match Cookie::parse(value) {
+            Ok(cookie) => cookie,
+            Err(_) => {
+                store.set_cookies(&mut std::iter::once(value), url);
+                continue;
+            }
+        };
+        let scope = cookie_scope(&cookie, url);
+        let before = has_cookie(store, &scope, cookie.name());
+        store.set_cookies(&mut std::iter::once(value), url);
+        let after = has_cookie(store, &scope, cookie.name());
+        let kind = match (before, after) {
+            (false, true) => CookieEventKind::Stored,
+            (true, true) => CookieEventKind::Updated,
+            (true, false) => CookieEventKind::Expired,
+            (false, false) => continue,
+        };
+        observer(&CookieEvent {
+            kind,
+            cookie: &cookie,
+            url,
+        });
+    }
+}
+
+/// Returns a URL the cookie would be sent to, if stored.
+fn cookie_scope(cookie: &Cookie<'_>, url: &Url) -> Url {
+    let mut scope = url.clone();
+    if let Some(path) = cookie.path().filter(|path| path.starts_with('/')) {
+        scope.set_path(path);
+        scope.set_query(None);
+    }
+    scope
+}
+
+fn has_cookie(store: &dyn CookieStore, url: &Url, name: &str) -> bool {
+    let header = match store.cookies(url) {
+        Some(header) => header,
+        None => return false,
+    };
+    header
+        .to_str()
+        .map(|cookies| {
+            cookies
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .any(|(n, _)| n == name)
+        })
+        .unwrap_or(false)
+}
+
 /// Error representing a parse failure of a 'Set-Cookie' header.
 pub(crate) struct CookieParseError(cookie_crate::ParseError);
 
@@ -162,6 +304,140 @@ impl Jar {
             .into_iter();
         self.0.write().unwrap().store_response_cookies(cookies, url);
     }
+
+    /// Create an empty jar which rejects cookies set for public suffixes,
+    /// like browsers do.
+    ///
+    /// `list` is the content of the
+    /// [Public Suffix List](https://publicsuffix.org/list/public_suffix_list.dat).
+    /// With it, a cookie with a `Domain` attribute of `co.uk` is ignored,
+    /// instead of being sent to every site under `co.uk`. If the public
+    /// suffix is the host that sent the cookie, the cookie is kept, but only
+    /// sent back to that host.
+    ///
+    /// Only the suffixes in the ICANN and private sections of the list are
+    /// taken into account. The list isn't updated while the jar is used.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `list` can't be parsed.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `psl` feature to be enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use reqwest::cookie::Jar;
+    ///
+    /// # fn run() -> Result<(), reqwest::Error> {
+    /// let list = "// ===BEGIN ICANN DOMAINS===\nuk\nco.uk\n";
+    /// let jar = Jar::with_public_suffix_list(list)?;
+    ///
+    /// # #[cfg(not(feature = "rustls-tls-no-provider"))]
+    /// let client = reqwest::Client::builder()
+    ///     .cookie_provider(Arc::new(jar))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "psl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "psl")))]
+    pub fn with_public_suffix_list(list: &str) -> crate::Result<Jar> {
+        let list = list
+            .parse::<publicsuffix::List>()
+            .map_err(|e| crate::error::builder(e.to_string()))?;
+        let store = cookie_store::CookieStore::default().with_suffix_list(list);
+        Ok(Jar(RwLock::new(store)))
+    }
+
+    /// Returns the cookies that would be sent with a request to `url`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reqwest::{cookie::Jar, Url};
+    ///
+    /// let url = "https://yolo.local/a".parse::<Url>().unwrap();
+    ///
+    /// let jar = Jar::default();
+    /// jar.add_cookie_str("foo=bar", &url);
+    ///
+    /// let cookies = jar.cookies_for(&url);
+    /// assert_eq!(cookies.len(), 1);
+    /// assert_eq!(cookies[0].value(), "bar");
+    /// ```
+    pub fn cookies_for(&self, url: &url::Url) -> Vec<Cookie<'static>> {
+        self.0
+            .read()
+            .unwrap()
+            .matches(url)
+            .into_iter()
+            .map(Cookie::stored)
+            .collect()
+    }
+
+    /// Returns an iterator over all unexpired cookies in this jar.
+    ///
+    /// The iterator works on a snapshot of the jar, so the jar may be modified
+    /// while iterating.
+    ///
+    /// The [`domain()`](Cookie::domain) and [`path()`](Cookie::path) of the
+    /// returned cookies are those the cookies are stored for, even if they
+    /// weren't set by the `Set-Cookie` header.
+    pub fn iter(&self) -> impl Iterator<Item = Cookie<'static>> {
+        self.0
+            .read()
+            .unwrap()
+            .iter_unexpired()
+            .map(Cookie::stored)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Removes the cookies named `name` stored for `domain`, whatever their
+    /// path.
+    ///
+    /// `domain` is matched exactly, so removing the cookies of `example.com`
+    /// doesn't remove those of `www.example.com`. Returns true if a cookie
+    /// was removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reqwest::{cookie::Jar, Url};
+    ///
+    /// let url = "https://yolo.local".parse::<Url>().unwrap();
+    ///
+    /// let jar = Jar::default();
+    /// jar.add_cookie_str("foo=bar", &url);
+    /// jar.add_cookie_str("foo=baz; Path=/other", &url);
+    ///
+    /// assert!(jar.remove("yolo.local", "foo"));
+    /// assert_eq!(jar.iter().count(), 0);
+    /// ```
+    pub fn remove(&self, domain: &str, name: &str) -> bool {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        let mut store = self.0.write().unwrap();
+        let paths = store
+            .iter_any()
+            .filter(|c| c.name() == name && String::from(&c.domain) == domain)
+            .map(|c| String::from(c.path.as_ref()))
+            .collect::<Vec<_>>();
+
+        let mut removed = false;
+        for path in paths {
+            removed |= store.remove(&domain, &path, name).is_some();
+        }
+        removed
+    }
+
+    /// Removes all cookies from this jar.
+    pub fn clear(&self) {
+        self.0.write().unwrap().clear();
+    }
 }
 
 impl CookieStore for Jar {
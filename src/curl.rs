@@ -0,0 +1,556 @@
+//! Parsing of curl command lines into `Request`s.
+
+use std::fs;
+
+use http::header::{
+    HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, REFERER,
+    USER_AGENT,
+};
+use http::Method;
+use url::form_urlencoded;
+
+use crate::async_impl::multipart::{Form, Part};
+use crate::{Body, Request, Url};
+
+/// Options that don't affect the request itself, and take no argument.
+const IGNORED_FLAGS: &[&str] = &[
+    "-L",
+    "--location",
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-k",
+    "--insecure",
+    "-f",
+    "--fail",
+    "-g",
+    "--globoff",
+];
+
+#[derive(Default)]
+struct Command {
+    method: Option<Method>,
+    url: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    data: Vec<Vec<u8>>,
+    form: Option<Form>,
+    basic_auth: Option<String>,
+    get: bool,
+    head: bool,
+}
+
+/// Parses a curl command, reading the files it references if `read_files`
+/// is set, or failing otherwise.
+pub(crate) fn parse(command: &str, read_files: bool) -> crate::Result<Request> {
+    let args = split(command)?;
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("curl") {
+        args.next();
+    }
+
+    let mut cmd = Command::default();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            set_url(&mut cmd, arg)?;
+            continue;
+        }
+
+        if IGNORED_FLAGS.contains(&arg.as_str()) {
+            continue;
+        }
+
+        let (flag, attached) = split_short_flag(&arg);
+        if attached.is_none() && is_flag_cluster(&arg) {
+            for c in arg[1..].chars() {
+                match c {
+                    'G' => cmd.get = true,
+                    'I' => cmd.head = true,
+                    _ => (),
+                }
+            }
+            continue;
+        }
+
+        match flag {
+            "-G" | "--get" => cmd.get = true,
+            "-I" | "--head" => cmd.head = true,
+            "--url" => {
+                let url = value(flag, attached, &mut args)?;
+                set_url(&mut cmd, url)?;
+            }
+            "-X" | "--request" => {
+                let method = value(flag, attached, &mut args)?;
+                cmd.method =
+                    Some(Method::from_bytes(method.as_bytes()).map_err(crate::error::builder)?);
+            }
+            "-H" | "--header" => {
+                let header = value(flag, attached, &mut args)?;
+                cmd.headers.push(parse_header(&header)?);
+            }
+            "-A" | "--user-agent" => {
+                let agent = value(flag, attached, &mut args)?;
+                cmd.headers.push((USER_AGENT, header_value(&agent)?));
+            }
+            "-e" | "--referer" => {
+                let referer = value(flag, attached, &mut args)?;
+                cmd.headers.push((REFERER, header_value(&referer)?));
+            }
+            "-b" | "--cookie" => {
+                let cookie = value(flag, attached, &mut args)?;
+                cmd.headers.push((COOKIE, header_value(&cookie)?));
+            }
+            "-u" | "--user" => {
+                cmd.basic_auth = Some(value(flag, attached, &mut args)?);
+            }
+            "-d" | "--data" | "--data-ascii" => {
+                let data = value(flag, attached, &mut args)?;
+                cmd.data.push(read_if_file(data, true, read_files)?);
+            }
+            "--data-binary" => {
+                let data = value(flag, attached, &mut args)?;
+                cmd.data.push(read_if_file(data, false, read_files)?);
+            }
+            "--data-raw" => {
+                cmd.data.push(value(flag, attached, &mut args)?.into_bytes());
+            }
+            "--data-urlencode" => {
+                let data = value(flag, attached, &mut args)?;
+                cmd.data.push(url_encode_data(&data, read_files)?.into_bytes());
+            }
+            "-F" | "--form" => {
+                let field = value(flag, attached, &mut args)?;
+                let form = cmd.form.take().unwrap_or_default();
+                cmd.form = Some(add_form_field(form, &field, read_files)?);
+            }
+            // Decompression is configured on the `Client`, not per request.
+            "--compressed" => {
+                return Err(crate::error::builder(
+                    "unsupported curl option: --compressed, enable decompression on the Client instead",
+                ))
+            }
+            _ => {
+                return Err(crate::error::builder(format!(
+                    "unsupported curl option: {arg}"
+                )))
+            }
+        }
+    }
+
+    build(cmd)
+}
+
+fn build(cmd: Command) -> crate::Result<Request> {
+    let url = cmd
+        .url
+        .ok_or_else(|| crate::error::builder("curl command has no URL"))?;
+    let mut url = match Url::parse(&url) {
+        Ok(url) => url,
+        // curl assumes `http://` when the scheme is missing
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Url::parse(&format!("http://{url}")).map_err(crate::error::builder)?
+        }
+        Err(err) => return Err(crate::error::builder(err)),
+    };
+
+    if !cmd.data.is_empty() && cmd.form.is_some() {
+        return Err(crate::error::builder(
+            "curl command can't use both --data and --form",
+        ));
+    }
+
+    let data = if cmd.data.is_empty() {
+        None
+    } else {
+        Some(cmd.data.join(&b'&'))
+    };
+
+    let mut body = None;
+    let mut content_type = None;
+    let mut content_length = None;
+    let default_method = match (data, cmd.form) {
+        (Some(data), _) if cmd.get => {
+            let data = String::from_utf8(data).map_err(crate::error::builder)?;
+            let query = match url.query() {
+                Some(query) if !query.is_empty() => format!("{query}&{data}"),
+                _ => data,
+            };
+            url.set_query(Some(&query));
+            Method::GET
+        }
+        (Some(data), _) => {
+            content_type = Some(HeaderValue::from_static(
+                "application/x-www-form-urlencoded",
+            ));
+            body = Some(Body::from(data));
+            Method::POST
+        }
+        (None, Some(mut form)) => {
+            content_type = Some(
+                HeaderValue::from_str(&format!(
+                    "multipart/form-data; boundary={}",
+                    form.boundary()
+                ))
+                .expect("boundary is a valid header value"),
+            );
+            content_length = form.compute_length().map(HeaderValue::from);
+            body = Some(form.stream());
+            Method::POST
+        }
+        (None, None) if cmd.head => Method::HEAD,
+        (None, None) => Method::GET,
+    };
+
+    let mut req = Request::new(cmd.method.unwrap_or(default_method), url);
+    if let Some(user) = cmd.basic_auth {
+        let (username, password) = match user.split_once(':') {
+            Some((username, password)) => (username, Some(password)),
+            None => (user.as_str(), None),
+        };
+        req.headers_mut().insert(
+            AUTHORIZATION,
+            crate::util::basic_auth(username, password),
+        );
+    }
+    for (name, value) in cmd.headers {
+        req.headers_mut().append(name, value);
+    }
+    if let Some(content_type) = content_type {
+        // a `Content-Type` from `-H` wins, like in curl
+        req.headers_mut()
+            .entry(CONTENT_TYPE)
+            .or_insert(content_type);
+    }
+    if let Some(content_length) = content_length {
+        req.headers_mut().insert(CONTENT_LENGTH, content_length);
+    }
+    *req.body_mut() = body;
+    Ok(req)
+}
+
+fn set_url(cmd: &mut Command, url: String) -> crate::Result<()> {
+    if cmd.url.is_some() {
+        return Err(crate::error::builder(
+            "curl command with several URLs is not supported",
+        ));
+    }
+    cmd.url = Some(url);
+    Ok(())
+}
+
+/// Splits `-XPOST` into `("-X", Some("POST"))`.
+fn split_short_flag(arg: &str) -> (&str, Option<String>) {
+    if arg.starts_with("--") || arg.len() <= 2 {
+        return (arg, None);
+    }
+    let (flag, rest) = arg.split_at(2);
+    if matches!(flag, "-X" | "-H" | "-A" | "-e" | "-b" | "-u" | "-d" | "-F") {
+        (flag, Some(rest.to_owned()))
+    } else {
+        (arg, None)
+    }
+}
+
+/// Returns true for clusters of short flags without arguments, like `-sSL`.
+fn is_flag_cluster(arg: &str) -> bool {
+    arg.len() > 2
+        && !arg.starts_with("--")
+        && arg[1..]
+            .chars()
+            .all(|c| matches!(c, 'L' | 's' | 'S' | 'v' | 'i' | 'k' | 'f' | 'g' | 'G' | 'I'))
+}
+
+fn value(
+    flag: &str,
+    attached: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> crate::Result<String> {
+    attached
+        .or_else(|| args.next())
+        .ok_or_else(|| crate::error::builder(format!("curl option {flag} requires a value")))
+}
+
+fn parse_header(header: &str) -> crate::Result<(HeaderName, HeaderValue)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| crate::error::builder(format!("invalid curl header: {header}")))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(crate::error::builder)?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| crate::error::invalid_header_value(name.clone(), e))?;
+    Ok((name, value))
+}
+
+fn header_value(value: &str) -> crate::Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(crate::error::builder)
+}
+
+/// Reads a file referenced by the command, whole and with blocking
+/// `std::fs` calls.
+fn read_file(path: &str, read_files: bool) -> crate::Result<Vec<u8>> {
+    if !read_files {
+        return Err(crate::error::builder(format!(
+            "curl command reads the local file {path}, use Request::from_curl_with_files"
+        )));
+    }
+    fs::read(path).map_err(crate::error::builder)
+}
+
+/// Reads `@file` arguments, stripping newlines from the file like `-d`
+/// does if `strip_newlines` is set.
+fn read_if_file(data: String, strip_newlines: bool, read_files: bool) -> crate::Result<Vec<u8>> {
+    match data.strip_prefix('@') {
+        Some(path) => {
+            let mut contents = read_file(path, read_files)?;
+            if strip_newlines {
+                contents.retain(|&b| b != b'\r' && b != b'\n');
+            }
+            Ok(contents)
+        }
+        None => Ok(data.into_bytes()),
+    }
+}
+
+/// Implements the `--data-urlencode` forms `content`, `=content`,
+/// `name=content`, `@file` and `name@file`.
+fn url_encode_data(data: &str, read_files: bool) -> crate::Result<String> {
+    let encode = |s: &[u8]| form_urlencoded::byte_serialize(s).collect::<String>();
+
+    if let Some((name, content)) = data.split_once('=') {
+        return Ok(if name.is_empty() {
+            encode(content.as_bytes())
+        } else {
+            format!("{name}={}", encode(content.as_bytes()))
+        });
+    }
+    if let Some((name, path)) = data.split_once('@') {
+        let content = read_file(path, read_files)?;
+        return Ok(if name.is_empty() {
+            encode(&content)
+        } else {
+            format!("{name}={}", encode(&content))
+        });
+    }
+    Ok(encode(data.as_bytes()))
+}
+
+/// Implements `-F name=value`, `-F name=@file` and `-F name=<file`, with
+/// optional `;type=` and `;filename=` parameters.
+fn add_form_field(form: Form, field: &str, read_files: bool) -> crate::Result<Form> {
+    let (name, value) = field
+        .split_once('=')
+        .ok_or_else(|| crate::error::builder(format!("invalid curl form field: {field}")))?;
+    let name = name.to_owned();
+
+    let mut params = value.split(';');
+    let value = params.next().unwrap_or_default();
+    let mut mime = None;
+    let mut file_name = None;
+    for param in params {
+        match param.trim().split_once('=') {
+            Some(("type", ty)) => mime = Some(ty.to_owned()),
+            Some(("filename", name)) => file_name = Some(name.to_owned()),
+            _ => (),
+        }
+    }
+
+    let mut part = if let Some(path) = value.strip_prefix('@') {
+        let contents = read_file(path, read_files)?;
+        let guessed = mime_guess::from_path(path).first_or_octet_stream();
+        let default_name = std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        let part = Part::bytes(contents).mime_str(guessed.as_ref())?;
+        match file_name.take().or(default_name) {
+            Some(file_name) => part.file_name(file_name),
+            None => part,
+        }
+    } else if let Some(path) = value.strip_prefix('<') {
+        Part::bytes(read_file(path, read_files)?)
+    } else {
+        Part::text(value.to_owned())
+    };
+
+    if let Some(mime) = mime {
+        part = part.mime_str(&mime)?;
+    }
+    if let Some(file_name) = file_name {
+        part = part.file_name(file_name);
+    }
+    Ok(form.part(name, part))
+}
+
+/// Splits a command line into arguments, like a POSIX shell would.
+///
+/// Supports single and double quotes, backslash escapes and line
+/// continuations, but no variables or other expansions.
+fn split(command: &str) -> crate::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(crate::error::builder("unterminated quote")),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => (),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(crate::error::builder("unterminated quote")),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(crate::error::builder("unterminated quote")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // line continuation
+                Some('\n') => (),
+                Some(c) => {
+                    in_arg = true;
+                    current.push(c);
+                }
+                None => (),
+            },
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_files(command: &str) -> crate::Result<Request> {
+        parse(command, true)
+    }
+
+    #[test]
+    fn split_quotes_and_continuations() {
+        let args = split("curl 'https://example.com/a b' \\\n  -H \"X-Quote: \\\"hi\\\"\"").unwrap();
+        assert_eq!(
+            args,
+            ["curl", "https://example.com/a b", "-H", "X-Quote: \"hi\""]
+        );
+
+        assert!(split("curl 'unterminated").is_err());
+    }
+
+    #[test]
+    fn get_with_headers() {
+        let req = parse_files("curl -sSL -H 'Accept: application/json' -A agent example.com/items").unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.url().as_str(), "http://example.com/items");
+        assert_eq!(req.headers()["accept"], "application/json");
+        assert_eq!(req.headers()[USER_AGENT], "agent");
+        assert!(req.body().is_none());
+    }
+
+    #[test]
+    fn post_data() {
+        let req = parse_files(
+            "curl -XPUT https://example.com -d a=1 --data-urlencode 'q=x y&z' -u user:pass",
+        )
+        .unwrap();
+        assert_eq!(req.method(), Method::PUT);
+        assert_eq!(
+            req.headers()[CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(req.headers()[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+        assert_eq!(
+            req.body().unwrap().as_bytes(),
+            Some(&b"a=1&q=x+y%26z"[..])
+        );
+    }
+
+    #[test]
+    fn data_keeps_inline_newlines() {
+        let req = parse_files("curl https://example.com -d 'a\nb'").unwrap();
+        assert_eq!(req.body().unwrap().as_bytes(), Some(&b"a\nb"[..]));
+
+        let req = parse_files("curl https://example.com -d @Cargo.toml").unwrap();
+        let body = req.body().unwrap().as_bytes().unwrap();
+        assert!(!body.contains(&b'\n'));
+    }
+
+    #[test]
+    fn data_binary_reads_bytes() {
+        let path = std::env::temp_dir().join("reqwest-curl-data-binary");
+        fs::write(&path, b"\xff\r\n\x00").unwrap();
+        let command = format!("curl https://example.com --data-binary @{}", path.display());
+        let req = parse_files(&command).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(req.body().unwrap().as_bytes(), Some(&b"\xff\r\n\x00"[..]));
+    }
+
+    #[test]
+    fn files_are_opt_in() {
+        assert!(parse("curl https://example.com -d @Cargo.toml", false).is_err());
+        assert!(parse("curl https://example.com --data-urlencode a@Cargo.toml", false).is_err());
+        assert!(parse("curl https://example.com -F file=@Cargo.toml", false).is_err());
+        assert!(parse("curl https://example.com -F 'text=<Cargo.toml'", false).is_err());
+        assert!(parse("curl https://example.com --data-raw @Cargo.toml", false).is_ok());
+    }
+
+    #[test]
+    fn get_data_goes_to_query() {
+        let req = parse_files("curl -G 'https://example.com/?a=1' -d b=2").unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.url().query(), Some("a=1&b=2"));
+        assert!(req.body().is_none());
+    }
+
+    #[test]
+    fn multipart_form() {
+        let req = parse_files("curl https://example.com -F name=value -F 'file=@Cargo.toml;type=text/plain'")
+            .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        let content_type = req.headers()[CONTENT_TYPE].to_str().unwrap();
+        assert!(content_type.starts_with("multipart/form-data; boundary="));
+        assert!(req.headers().contains_key(CONTENT_LENGTH));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse_files("curl -H").is_err());
+        assert!(parse_files("curl --proxy http://proxy example.com").is_err());
+        assert!(parse_files("curl --compressed example.com").is_err());
+        assert!(parse_files("curl -X GET").is_err());
+        assert!(parse_files("curl a.com b.com").is_err());
+    }
+}
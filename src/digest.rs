@@ -0,0 +1,334 @@
+//! Response digests
+//!
+//! A response body can be checked against a digest while it's read, so that
+//! corrupted or tampered downloads are detected without buffering the whole
+//! body first. If the digest of the body doesn't match once it was fully
+//! read, reading the body fails with an error for which
+//! [`Error::is_digest_mismatch()`](crate::Error::is_digest_mismatch) returns
+//! true.
+//!
+//! A known digest is checked with
+//! [`Response::verify_digest()`](crate::Response::verify_digest). A `Client`
+//! built with
+//! [`ClientBuilder::verify_content_digest()`](crate::ClientBuilder::verify_content_digest)
+//! also checks the `Content-Digest` and `Repr-Digest` headers of
+//! [RFC 9530] that servers send.
+//!
+//! [RFC 9530]: https://www.rfc-editor.org/rfc/rfc9530
+//!
+//! # Example
+//!
+//! ```no_run
+//! use reqwest::digest::Algorithm;
+//!
+//! # async fn run(expected: &[u8]) -> Result<(), reqwest::Error> {
+//! let bytes = reqwest::get("https://example.com/release.tar.gz")
+//!     .await?
+//!     .verify_digest(Algorithm::Sha256, expected)
+//!     .bytes()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use base64::Engine;
+use bytes::Bytes;
+use http::header::{HeaderMap, HeaderName};
+use http::StatusCode;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+
+use crate::async_impl::body::ResponseBody;
+
+const CONTENT_DIGEST: HeaderName = HeaderName::from_static("content-digest");
+const REPR_DIGEST: HeaderName = HeaderName::from_static("repr-digest");
+
+/// A hash algorithm of a digest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Algorithm {
+    /// SHA-256
+    Sha256,
+    /// SHA-512
+    Sha512,
+}
+
+pin_project_lite::pin_project! {
+    /// Hashes a body as it's read, and fails at its end if the digest
+    /// doesn't match.
+    pub(crate) struct Verify<B> {
+        #[pin]
+        inner: B,
+        context: Option<ring::digest::Context>,
+        algorithm: Algorithm,
+        expected: Vec<u8>,
+    }
+}
+
+/// The error of a body whose digest doesn't match.
+#[derive(Debug)]
+pub(crate) struct DigestMismatch {
+    algorithm: Algorithm,
+}
+
+// ===== impl Algorithm =====
+
+impl Algorithm {
+    /// The name of the algorithm in the Hash Algorithms for HTTP Digest
+    /// Fields registry, like `sha-256`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha-256",
+            Algorithm::Sha512 => "sha-512",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "sha-256" => Some(Algorithm::Sha256),
+            "sha-512" => Some(Algorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn ring(&self) -> &'static ring::digest::Algorithm {
+        match self {
+            Algorithm::Sha256 => &ring::digest::SHA256,
+            Algorithm::Sha512 => &ring::digest::SHA512,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// ===== impl Verify =====
+
+impl<B> Verify<B> {
+    pub(crate) fn new(inner: B, algorithm: Algorithm, expected: Vec<u8>) -> Verify<B> {
+        Verify {
+            inner,
+            context: Some(ring::digest::Context::new(algorithm.ring())),
+            algorithm,
+            expected,
+        }
+    }
+}
+
+impl<B> HttpBody for Verify<B>
+where
+    B: HttpBody<Data = Bytes>,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        match futures_core::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => {
+                if let (Some(data), Some(context)) = (frame.data_ref(), this.context.as_mut()) {
+                    context.update(data);
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+            None => {
+                let digest = this.context.take().map(ring::digest::Context::finish);
+                match digest {
+                    Some(digest) if digest.as_ref() != &this.expected[..] => {
+                        Poll::Ready(Some(Err(Box::new(DigestMismatch {
+                            algorithm: *this.algorithm,
+                        }))))
+                    }
+                    _ => Poll::Ready(None),
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        // the digest is checked when polled at the end
+        self.context.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// ===== impl DigestMismatch =====
+
+impl fmt::Display for DigestMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "response body doesn't match its {} digest", self.algorithm)
+    }
+}
+
+impl std::error::Error for DigestMismatch {}
+
+/// Verifies the body of `res` against its `Content-Digest` or `Repr-Digest`
+/// header, if it has one with a supported algorithm.
+///
+/// This has to be done before the content coding is decoded, since the
+/// digests are computed over the encoded content.
+pub(crate) fn verify_headers(
+    res: http::Response<ResponseBody>,
+    is_head: bool,
+) -> http::Response<ResponseBody> {
+    let status = res.status();
+    if is_head || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+        return res;
+    }
+
+    let mut expected = strongest(res.headers(), &CONTENT_DIGEST);
+    // the representation digest of a partial response is the digest of the
+    // whole representation
+    if expected.is_none() && status != StatusCode::PARTIAL_CONTENT {
+        expected = strongest(res.headers(), &REPR_DIGEST);
+    }
+
+    match expected {
+        Some((algorithm, expected)) => res.map(|body| {
+            crate::async_impl::body::boxed(Verify::new(body, algorithm, expected))
+        }),
+        None => res,
+    }
+}
+
+/// The digest of the strongest supported algorithm in the `name` headers.
+///
+/// These headers are Structured Field dictionaries, like
+/// `sha-256=:base64:, sha-512=:base64:`. Malformed members are skipped.
+fn strongest(headers: &HeaderMap, name: &HeaderName) -> Option<(Algorithm, Vec<u8>)> {
+    let mut strongest = None;
+    for value in headers.get_all(name) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for member in value.split(',') {
+            let (key, value) = match member.split_once('=') {
+                Some(member) => member,
+                None => continue,
+            };
+            let algorithm = match Algorithm::from_name(key.trim()) {
+                Some(algorithm) => algorithm,
+                None => continue,
+            };
+            // a byte sequence, maybe followed by parameters
+            let value = value.trim_start().strip_prefix(':');
+            let digest = match value.and_then(|value| value.split(':').next()) {
+                Some(digest) => digest,
+                None => continue,
+            };
+            let digest = match base64::engine::general_purpose::STANDARD.decode(digest) {
+                Ok(digest) => digest,
+                Err(_) => continue,
+            };
+            if strongest.as_ref().map_or(true, |(strongest, _)| {
+                algorithm == Algorithm::Sha512 && *strongest == Algorithm::Sha256
+            }) {
+                strongest = Some((algorithm, digest));
+            }
+        }
+    }
+    strongest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    const HELLO_SHA256: &str = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+
+    fn body(chunks: &'static [&'static str]) -> ResponseBody {
+        let stream = futures_util::stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok::<_, std::io::Error>(Frame::data(Bytes::from(*chunk)))),
+        );
+        crate::async_impl::body::boxed(http_body_util::StreamBody::new(stream))
+    }
+
+    fn collect<B: HttpBody>(body: B) -> Result<Bytes, B::Error> {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("new rt");
+        rt.block_on(body.collect()).map(|body| body.to_bytes())
+    }
+
+    fn sha256(digest: &str) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD
+            .decode(digest)
+            .unwrap()
+    }
+
+    #[test]
+    fn verify() {
+        let ok = Verify::new(body(&["hel", "lo"]), Algorithm::Sha256, sha256(HELLO_SHA256));
+        assert_eq!(collect(ok).unwrap(), "hello");
+
+        let bad = Verify::new(body(&["hell", "o!"]), Algorithm::Sha256, sha256(HELLO_SHA256));
+        let err = collect(bad).unwrap_err();
+        assert!(err.is::<DigestMismatch>());
+        assert_eq!(
+            err.to_string(),
+            "response body doesn't match its sha-256 digest"
+        );
+    }
+
+    #[test]
+    fn parse_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            CONTENT_DIGEST,
+            format!("md5=:AAAA:, sha-256=:{HELLO_SHA256}:;p=1, x").parse().unwrap(),
+        );
+        assert_eq!(
+            strongest(&headers, &CONTENT_DIGEST),
+            Some((Algorithm::Sha256, sha256(HELLO_SHA256)))
+        );
+
+        headers.append(CONTENT_DIGEST, "sha-512=:AAAA:".parse().unwrap());
+        assert_eq!(
+            strongest(&headers, &CONTENT_DIGEST),
+            Some((Algorithm::Sha512, vec![0; 3]))
+        );
+
+        assert_eq!(strongest(&headers, &REPR_DIGEST), None);
+        headers.insert(REPR_DIGEST, "sha-256=:not base64:".parse().unwrap());
+        assert_eq!(strongest(&headers, &REPR_DIGEST), None);
+    }
+
+    #[test]
+    fn verify_headers_skips_partial_representations() {
+        let res = |status, header: &HeaderName| {
+            http::Response::builder()
+                .status(status)
+                .header(header, format!("sha-256=:{HELLO_SHA256}:"))
+                .body(body(&["hello, world"]))
+                .unwrap()
+        };
+
+        let err = collect(verify_headers(res(200, &REPR_DIGEST), false).into_body()).unwrap_err();
+        assert!(err.is::<DigestMismatch>());
+        let err = collect(verify_headers(res(206, &CONTENT_DIGEST), false).into_body()).unwrap_err();
+        assert!(err.is::<DigestMismatch>());
+
+        let body = collect(verify_headers(res(206, &REPR_DIGEST), false).into_body()).unwrap();
+        assert_eq!(body, "hello, world");
+        let body = collect(verify_headers(res(200, &CONTENT_DIGEST), true).into_body()).unwrap();
+        assert_eq!(body, "hello, world");
+    }
+}
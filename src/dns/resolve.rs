@@ -2,12 +2,15 @@ use hyper_util::client::legacy::connect::dns::Name as HyperName;
 use tower_service::Service;
 
 use std::collections::HashMap;
+use std::fmt;
 use std::future::Future;
+use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use crate::error::BoxError;
 
@@ -75,7 +78,18 @@ impl Service<HyperName> for DynResolver {
     }
 
     fn call(&mut self, name: HyperName) -> Self::Future {
-        self.resolver.resolve(Name(name))
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("dns", host = name.as_str());
+        let resolving = self.resolver.resolve(Name(name));
+        let resolving: Resolving = Box::pin(async move {
+            resolving
+                .await
+                .map_err(|err| Box::new(crate::error::DnsFailed(err)) as BoxError)
+        });
+        #[cfg(feature = "tracing")]
+        return Box::pin(tracing::Instrument::instrument(resolving, span));
+        #[cfg(not(feature = "tracing"))]
+        resolving
     }
 }
 
@@ -108,6 +122,99 @@ impl Resolve for DnsResolverWithOverrides {
     }
 }
 
+/// Bounds the time a resolver may take, and falls back to a second
+/// resolver when the first one fails or times out.
+pub(crate) struct DnsResolverWithFallback {
+    primary: Arc<dyn Resolve>,
+    fallback: Option<Arc<dyn Resolve>>,
+    timeout: Option<Duration>,
+}
+
+impl DnsResolverWithFallback {
+    pub(crate) fn new(
+        primary: Arc<dyn Resolve>,
+        fallback: Option<Arc<dyn Resolve>>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        DnsResolverWithFallback {
+            primary,
+            fallback,
+            timeout,
+        }
+    }
+}
+
+impl Resolve for DnsResolverWithFallback {
+    fn resolve(&self, name: Name) -> Resolving {
+        let primary = self.primary.clone();
+        let fallback = self.fallback.clone();
+        let timeout = self.timeout;
+        Box::pin(async move {
+            let host = name.as_str().to_owned();
+            let err = match resolve_within(&*primary, name, timeout).await {
+                Ok(addrs) => return Ok(addrs),
+                Err(err) => err,
+            };
+            let fallback = match fallback {
+                Some(fallback) => fallback,
+                None => return Err(err),
+            };
+            log::debug!("resolving {host} failed, using the fallback resolver: {err}");
+            let name = Name::from_str(&host).expect("name was already valid");
+            resolve_within(&*fallback, name, timeout)
+                .await
+                .map_err(|fallback| {
+                    Box::new(FallbackFailed {
+                        primary: err,
+                        fallback,
+                    }) as BoxError
+                })
+        })
+    }
+}
+
+async fn resolve_within(
+    resolver: &dyn Resolve,
+    name: Name,
+    timeout: Option<Duration>,
+) -> Result<Addrs, BoxError> {
+    let resolving = resolver.resolve(name);
+    match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, resolving).await {
+            Ok(result) => result,
+            Err(_) => Err(Box::new(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "dns resolution timed out",
+            ))),
+        },
+        None => resolving.await,
+    }
+}
+
+/// The error of a fallback resolver, which keeps the reason the primary
+/// resolver failed.
+#[derive(Debug)]
+struct FallbackFailed {
+    primary: BoxError,
+    fallback: BoxError,
+}
+
+impl fmt::Display for FallbackFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fallback resolver failed, after the primary resolver failed: {}",
+            self.primary
+        )
+    }
+}
+
+impl std::error::Error for FallbackFailed {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.fallback)
+    }
+}
+
 mod sealed {
     use std::fmt;
 
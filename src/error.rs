@@ -3,6 +3,7 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::io;
 
+use crate::header::HeaderName;
 use crate::{StatusCode, Url};
 
 /// A `Result` alias where the `Err` case is `reqwest::Error`.
@@ -99,9 +100,7 @@ impl Error {
 
     /// Returns true if the error is related to a timeout.
     pub fn is_timeout(&self) -> bool {
-        let mut source = self.source();
-
-        while let Some(err) = source {
+        self.sources().any(|err| {
             if err.is::<TimedOut>() {
                 return true;
             }
@@ -110,9 +109,171 @@ impl Error {
                     return true;
                 }
             }
-            source = err.source();
+            false
+        })
+    }
+
+    /// Returns the class of the error.
+    ///
+    /// This allows to branch on the reason a request failed, for instance to
+    /// decide whether to retry it. When several kinds apply, the most
+    /// specific one is returned: a request that failed because of a DNS
+    /// timeout is a [`Timeout`](ErrorKind::Timeout), and one that failed
+    /// because the name of a proxy couldn't be resolved is a
+    /// [`Dns`](ErrorKind::Dns) error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reqwest::ErrorKind;
+    ///
+    /// fn is_transient(err: &reqwest::Error) -> bool {
+    ///     match err.kind() {
+    ///         ErrorKind::Timeout | ErrorKind::Connect => true,
+    ///         ErrorKind::Status => err.status().map_or(false, |s| s.is_server_error()),
+    ///         _ => false,
+    ///     }
+    /// }
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self.inner.kind {
+            Kind::Builder => return ErrorKind::Builder,
+            Kind::Redirect => return ErrorKind::Redirect,
+            Kind::Status(_) => return ErrorKind::Status,
+            Kind::Upgrade => return ErrorKind::Upgrade,
+            Kind::CircuitOpen => return ErrorKind::CircuitOpen,
+            Kind::Request | Kind::Body | Kind::Decode => (),
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.is_pool_timeout() {
+            return ErrorKind::PoolTimeout;
+        }
+
+        if self.is_timeout() {
+            return ErrorKind::Timeout;
+        }
+
+        match self.inner.kind {
+            Kind::Body => return ErrorKind::Body,
+            Kind::Decode => return ErrorKind::Decode,
+            _ => (),
+        }
+
+        let (mut dns, mut tls, mut proxy) = (false, false, false);
+        for err in self.sources() {
+            dns |= err.is::<DnsFailed>();
+            tls |= is_tls_error(err);
+            proxy |= err.is::<ProxyFailed>();
         }
+        if dns {
+            ErrorKind::Dns
+        } else if tls {
+            ErrorKind::Tls
+        } else if proxy {
+            ErrorKind::Proxy
+        } else if self.is_connect_error() {
+            ErrorKind::Connect
+        } else {
+            ErrorKind::Request
+        }
+    }
+
+    /// Returns the kind of the I/O error that caused this error, if any.
+    ///
+    /// When the cause is a failed read or write, such as a connection that
+    /// was refused or reset by the server, this is the kind of the innermost
+    /// `io::Error` of the source chain, like
+    /// [`ConnectionRefused`](io::ErrorKind::ConnectionRefused) or
+    /// [`ConnectionReset`](io::ErrorKind::ConnectionReset).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// fn should_retry(err: &reqwest::Error) -> bool {
+    ///     matches!(
+    ///         err.io_error_kind(),
+    ///         Some(io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted)
+    ///     )
+    /// }
+    /// ```
+    pub fn io_error_kind(&self) -> Option<io::ErrorKind> {
+        self.sources()
+            .filter_map(|err| err.downcast_ref::<io::Error>())
+            .last()
+            .map(io::Error::kind)
+    }
+
+    /// Returns the description of the TLS alert sent by the server, if the
+    /// error was caused by one.
+    ///
+    /// This is the code of the alert, as registered in the
+    /// [TLS Alerts registry], such as 40 for `handshake_failure`, 42 for
+    /// `bad_certificate` or 116 for `certificate_required`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the `rustls-tls(-...)` Cargo feature enabled.
+    ///
+    /// [TLS Alerts registry]: https://www.iana.org/assignments/tls-parameters/tls-parameters.xhtml#tls-parameters-6
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn tls_alert(&self) -> Option<u8> {
+        self.sources()
+            .find_map(|err| match err.downcast_ref::<rustls::Error>()? {
+                rustls::Error::AlertReceived(alert) => Some(u8::from(*alert)),
+                _ => None,
+            })
+    }
+
+    /// Returns true if the error is from a server certificate that is
+    /// revoked, according to a certificate revocation list or OCSP.
+    ///
+    /// # Optional
+    ///
+    /// This requires the `rustls-tls(-...)` Cargo feature enabled.
+    #[cfg(feature = "__rustls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn is_certificate_revoked(&self) -> bool {
+        self.sources().any(|err| {
+            matches!(
+                err.downcast_ref::<rustls::Error>(),
+                Some(rustls::Error::InvalidCertificate(
+                    rustls::CertificateError::Revoked
+                ))
+            )
+        })
+    }
+
+    /// Walks the source chain, also looking into the errors wrapped by an
+    /// `io::Error`, and through the internal wrappers that hide themselves.
+    fn sources(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        let mut next = self.source();
+        std::iter::from_fn(move || {
+            let err = next?;
+            next = if let Some(dns) = err.downcast_ref::<DnsFailed>() {
+                Some(&*dns.0)
+            } else if let Some(proxy) = err.downcast_ref::<ProxyFailed>() {
+                Some(&*proxy.0)
+            } else if let Some(inner) = err.downcast_ref::<io::Error>().and_then(|io| io.get_ref())
+            {
+                Some(inner as _)
+            } else {
+                err.source()
+            };
+            Some(err)
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_connect_error(&self) -> bool {
+        self.is_connect()
+    }
 
+    #[cfg(target_arch = "wasm32")]
+    fn is_connect_error(&self) -> bool {
         false
     }
 
@@ -139,6 +300,51 @@ impl Error {
         false
     }
 
+    /// Returns true if the error is from connecting to a port that isn't
+    /// allowed.
+    ///
+    /// See [`ClientBuilder::allowed_ports()`](crate::ClientBuilder::allowed_ports).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_port_not_allowed(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<PortNotAllowed>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error is from a request that waited too long
+    /// for a connection, because the pool of its host was saturated.
+    ///
+    /// See [`ClientBuilder::pool_acquire_timeout()`](crate::ClientBuilder::pool_acquire_timeout).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_pool_timeout(&self) -> bool {
+        self.sources().any(|err| err.is::<PoolTimedOut>())
+    }
+
+    /// Returns true if the error is from a request that was canceled.
+    ///
+    /// See [`blocking::RequestBuilder::send_cancellable()`](crate::blocking::RequestBuilder::send_cancellable).
+    #[cfg(feature = "blocking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+    pub fn is_canceled(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<Canceled>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
     /// Returns true if the error is related to the request or response body
     pub fn is_body(&self) -> bool {
         matches!(self.inner.kind, Kind::Body)
@@ -149,6 +355,47 @@ impl Error {
         matches!(self.inner.kind, Kind::Decode)
     }
 
+    /// Returns true if the request wasn't sent because the circuit breaker
+    /// of its origin is open.
+    ///
+    /// See [`ClientBuilder::circuit_breaker()`](crate::ClientBuilder::circuit_breaker).
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self.inner.kind, Kind::CircuitOpen)
+    }
+
+    /// Returns true if the error is from a response whose `Content-Type`
+    /// wasn't the expected one.
+    ///
+    /// See [`Response::ensure_content_type()`](crate::Response::ensure_content_type).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_unexpected_content_type(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<UnexpectedContentType>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if the error is from a response body that doesn't match
+    /// its digest.
+    ///
+    /// See the [`digest`](crate::digest) module.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `digest` feature to be enabled.
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    pub fn is_digest_mismatch(&self) -> bool {
+        self.sources()
+            .any(|err| err.is::<crate::digest::DigestMismatch>())
+    }
+
     /// Returns the status code, if the error was generated from a response.
     pub fn status(&self) -> Option<StatusCode> {
         match self.inner.kind {
@@ -157,6 +404,58 @@ impl Error {
         }
     }
 
+    /// Returns the start of the response body, if the error was generated
+    /// by [`Response::error_for_status_with_body()`](crate::Response::error_for_status_with_body).
+    pub fn status_body(&self) -> Option<&[u8]> {
+        match self.inner.kind {
+            Kind::Status(_) => self
+                .source()
+                .and_then(|err| err.downcast_ref::<StatusBody>())
+                .map(|body| &body.0[..]),
+            _ => None,
+        }
+    }
+
+    /// Returns the options that can't be used together, if the error was
+    /// caused by conflicting options of a
+    /// [`ClientBuilder`](crate::ClientBuilder).
+    ///
+    /// Each conflict is described in a sentence, to be shown to users.
+    pub fn conflicts(&self) -> Option<&[&'static str]> {
+        match self.inner.kind {
+            Kind::Builder => self
+                .source()
+                .and_then(|err| err.downcast_ref::<Conflicts>())
+                .map(|conflicts| &conflicts.0[..]),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the header, if the error was caused by an invalid
+    /// header value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() {
+    /// let err = reqwest::Client::new()
+    ///     .get("http://example.com")
+    ///     .header("x-api-key", "line\nbreak")
+    ///     .build()
+    ///     .unwrap_err();
+    ///
+    /// assert!(err.is_builder());
+    /// assert_eq!(err.header_name().unwrap(), "x-api-key");
+    /// # }
+    /// ```
+    pub fn header_name(&self) -> Option<&HeaderName> {
+        self.inner
+            .source
+            .as_ref()?
+            .downcast_ref::<InvalidHeaderValue>()
+            .map(|err| &err.name)
+    }
+
     // private
 
     #[allow(unused)]
@@ -204,6 +503,7 @@ impl fmt::Display for Error {
             Kind::Decode => f.write_str("error decoding response body")?,
             Kind::Redirect => f.write_str("error following redirect")?,
             Kind::Upgrade => f.write_str("error upgrading connection")?,
+            Kind::CircuitOpen => f.write_str("circuit breaker is open")?,
             Kind::Status(ref code) => {
                 let prefix = if code.is_client_error() {
                     "HTTP status client error"
@@ -243,6 +543,53 @@ impl From<crate::error::Error> for js_sys::Error {
     }
 }
 
+/// The class of an [`Error`], returned by [`Error::kind()`].
+///
+/// More kinds may be added in the future, so matching on it must include a
+/// wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The client or the request couldn't be built, for instance because of
+    /// an invalid URL or header.
+    Builder,
+    /// The name of the host, or of a proxy, couldn't be resolved.
+    Dns,
+    /// The connection to the host, or to a proxy, couldn't be established.
+    Connect,
+    /// The TLS handshake failed, for instance because the certificate of
+    /// the server wasn't trusted.
+    Tls,
+    /// The proxy couldn't be used, for instance because it refused to open
+    /// a tunnel.
+    Proxy,
+    /// A timeout elapsed, while connecting, sending the request or reading
+    /// the response.
+    Timeout,
+    /// No connection became available in time, because the pool of the
+    /// host was saturated.
+    PoolTimeout,
+    /// Sending the request or receiving the response failed for another
+    /// reason, for instance because the connection was closed by the
+    /// server.
+    Request,
+    /// A redirect couldn't be followed, for instance because there were too
+    /// many of them.
+    Redirect,
+    /// The response had an error status, from
+    /// [`Response::error_for_status()`](crate::Response::error_for_status).
+    Status,
+    /// The body of the request or response failed.
+    Body,
+    /// The body of the response couldn't be decoded.
+    Decode,
+    /// The connection couldn't be upgraded.
+    Upgrade,
+    /// The request wasn't sent because the circuit breaker of its origin is
+    /// open.
+    CircuitOpen,
+}
+
 #[derive(Debug)]
 pub(crate) enum Kind {
     Builder,
@@ -252,6 +599,7 @@ pub(crate) enum Kind {
     Body,
     Decode,
     Upgrade,
+    CircuitOpen,
 }
 
 // constructors
@@ -260,6 +608,11 @@ pub(crate) fn builder<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Builder, Some(e))
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn conflicts(conflicts: Vec<&'static str>) -> Error {
+    Error::new(Kind::Builder, Some(Conflicts(conflicts)))
+}
+
 pub(crate) fn body<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Body, Some(e))
 }
@@ -280,6 +633,25 @@ pub(crate) fn status_code(url: Url, status: StatusCode) -> Error {
     Error::new(Kind::Status(status), None::<Error>).with_url(url)
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn status_code_with_body(url: Url, status: StatusCode, body: bytes::Bytes) -> Error {
+    Error::new(Kind::Status(status), Some(StatusBody(body))).with_url(url)
+}
+
+pub(crate) fn circuit_open(url: Url) -> Error {
+    Error::new(Kind::CircuitOpen, None::<Error>).with_url(url)
+}
+
+pub(crate) fn invalid_header_value<E: Into<BoxError>>(name: HeaderName, e: E) -> Error {
+    Error::new(
+        Kind::Builder,
+        Some(InvalidHeaderValue {
+            name,
+            source: Some(e.into()),
+        }),
+    )
+}
+
 pub(crate) fn url_bad_scheme(url: Url) -> Error {
     Error::new(Kind::Builder, Some(BadScheme)).with_url(url)
 }
@@ -294,6 +666,19 @@ if_wasm! {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn unexpected_content_type(
+    expected: &mime::Mime,
+    actual: Option<http::HeaderValue>,
+    url: Url,
+) -> Error {
+    decode(UnexpectedContentType {
+        expected: expected.to_string(),
+        actual,
+    })
+    .with_url(url)
+}
+
 pub(crate) fn upgrade<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Upgrade, Some(e))
 }
@@ -336,6 +721,157 @@ impl fmt::Display for TimedOut {
 
 impl StdError for TimedOut {}
 
+/// Wraps the error of a DNS resolver, to tell it apart from other connect
+/// errors. It displays as the wrapped error.
+#[derive(Debug)]
+pub(crate) struct DnsFailed(pub(crate) BoxError);
+
+impl fmt::Display for DnsFailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl StdError for DnsFailed {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Wraps an error of connecting through a proxy. It displays as the wrapped
+/// error.
+#[derive(Debug)]
+pub(crate) struct ProxyFailed(pub(crate) BoxError);
+
+impl fmt::Display for ProxyFailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl StdError for ProxyFailed {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
+
+fn is_tls_error(err: &(dyn StdError + 'static)) -> bool {
+    if err.is::<InvalidServerName>() {
+        return true;
+    }
+    #[cfg(feature = "default-tls")]
+    if err.is::<native_tls_crate::Error>() {
+        return true;
+    }
+    #[cfg(feature = "__rustls")]
+    if err.is::<rustls::Error>() || err.is::<rustls_pki_types::InvalidDnsNameError>() {
+        return true;
+    }
+    let _ = err;
+    false
+}
+
+#[derive(Debug)]
+pub(crate) struct InvalidServerName {
+    pub(crate) name: String,
+}
+
+impl fmt::Display for InvalidServerName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid TLS server name: {}", self.name)
+    }
+}
+
+impl StdError for InvalidServerName {}
+
+#[derive(Debug)]
+pub(crate) struct PortNotAllowed {
+    pub(crate) port: u16,
+}
+
+impl fmt::Display for PortNotAllowed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "port {} is not allowed", self.port)
+    }
+}
+
+impl StdError for PortNotAllowed {}
+
+#[derive(Debug)]
+pub(crate) struct PoolTimedOut;
+
+impl fmt::Display for PoolTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("timed out waiting for a connection from the pool")
+    }
+}
+
+impl StdError for PoolTimedOut {}
+
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+pub(crate) struct Canceled;
+
+#[cfg(feature = "blocking")]
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("request was canceled")
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl StdError for Canceled {}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub(crate) struct UnexpectedContentType {
+    expected: String,
+    actual: Option<http::HeaderValue>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl fmt::Display for UnexpectedContentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected content type `{}`, ", self.expected)?;
+        match self.actual {
+            Some(ref actual) => write!(f, "got `{}`", String::from_utf8_lossy(actual.as_bytes())),
+            None => f.write_str("got none"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StdError for UnexpectedContentType {}
+
+#[derive(Debug)]
+pub(crate) struct StatusBody(bytes::Bytes);
+
+impl fmt::Display for StatusBody {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "response body: {}", String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl StdError for StatusBody {}
+
+#[derive(Debug)]
+pub(crate) struct Conflicts(Vec<&'static str>);
+
+impl fmt::Display for Conflicts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("conflicting options: ")?;
+        for (i, conflict) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            f.write_str(conflict)?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for Conflicts {}
+
 #[derive(Debug)]
 pub(crate) struct BadScheme;
 
@@ -347,6 +883,24 @@ impl fmt::Display for BadScheme {
 
 impl StdError for BadScheme {}
 
+#[derive(Debug)]
+pub(crate) struct InvalidHeaderValue {
+    pub(crate) name: HeaderName,
+    pub(crate) source: Option<BoxError>,
+}
+
+impl fmt::Display for InvalidHeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid value for header `{}`", self.name)
+    }
+}
+
+impl StdError for InvalidHeaderValue {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source.as_ref().map(|e| &**e as _)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,6 +919,56 @@ mod tests {
         assert_sync::<Error>();
     }
 
+    #[test]
+    fn io_error_kind() {
+        let err = super::request(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+        assert_eq!(err.io_error_kind(), Some(io::ErrorKind::ConnectionReset));
+
+        // the innermost one
+        let inner = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        let err = super::body(io::Error::new(io::ErrorKind::Other, inner));
+        assert_eq!(err.io_error_kind(), Some(io::ErrorKind::ConnectionRefused));
+
+        assert_eq!(super::request("orly").io_error_kind(), None);
+    }
+
+    #[cfg(feature = "__rustls")]
+    #[test]
+    fn tls_alert() {
+        let alert = rustls::Error::AlertReceived(rustls::AlertDescription::HandshakeFailure);
+        let err = super::request(io::Error::new(io::ErrorKind::InvalidData, alert));
+        assert_eq!(err.tls_alert(), Some(40));
+        assert_eq!(err.io_error_kind(), Some(io::ErrorKind::InvalidData));
+
+        let err = super::request(rustls::Error::DecryptError);
+        assert_eq!(err.tls_alert(), None);
+    }
+
+    #[cfg(feature = "__rustls")]
+    #[test]
+    fn is_certificate_revoked() {
+        let revoked = rustls::Error::InvalidCertificate(rustls::CertificateError::Revoked);
+        let err = super::request(io::Error::new(io::ErrorKind::InvalidData, revoked));
+        assert!(err.is_certificate_revoked());
+
+        let unknown =
+            rustls::Error::InvalidCertificate(rustls::CertificateError::UnknownRevocationStatus);
+        assert!(!super::request(unknown).is_certificate_revoked());
+    }
+
+    #[test]
+    fn conflicts() {
+        let err = super::conflicts(vec!["a can't be used with b", "c requires d"]);
+        assert!(err.is_builder());
+        assert_eq!(err.conflicts().unwrap().len(), 2);
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "conflicting options: a can't be used with b; c requires d"
+        );
+
+        assert!(super::builder("orly").conflicts().is_none());
+    }
+
     #[test]
     fn mem_size_of() {
         use std::mem::size_of;
@@ -395,6 +999,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn is_pool_timeout() {
+        let io = io::Error::new(io::ErrorKind::Other, super::PoolTimedOut);
+        let err = super::request(io);
+        assert!(err.is_pool_timeout());
+        assert!(!err.is_timeout());
+        assert_eq!(err.kind(), super::ErrorKind::PoolTimeout);
+
+        assert!(!super::request(super::TimedOut).is_pool_timeout());
+    }
+
     #[test]
     fn is_timeout() {
         let err = super::request(super::TimedOut);
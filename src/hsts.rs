@@ -0,0 +1,335 @@
+//! HTTP Strict Transport Security
+//!
+//! When enabled with [`ClientBuilder::hsts()`](crate::ClientBuilder::hsts),
+//! a `Client` remembers the hosts that sent a `Strict-Transport-Security`
+//! header over HTTPS, and upgrades later `http://` requests and redirects to
+//! those hosts, or their subdomains if `includeSubDomains` was set, to
+//! `https://` before connecting. The header is ignored on plain HTTP
+//! responses, and for IP addresses.
+//!
+//! A [`HstsCache`] keeps the known hosts in memory, and can be filled with
+//! preloaded hosts before creating a `Client`. Other storage, for instance
+//! to persist hosts between runs, can be provided by implementing
+//! [`HstsStore`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use http::header::STRICT_TRANSPORT_SECURITY;
+use http::{HeaderMap, HeaderValue};
+use url::{Host, Url};
+
+/// Actions for a store of known HSTS hosts.
+pub trait HstsStore: Send + Sync {
+    /// Store a policy received from `host`.
+    ///
+    /// A policy with a `max_age` of zero means the host should be forgotten.
+    fn set_policy(&self, host: &str, policy: &Policy);
+    /// Returns true if requests to `host` must use HTTPS.
+    fn is_known_host(&self, host: &str) -> bool;
+}
+
+/// A parsed `Strict-Transport-Security` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    max_age: Duration,
+    include_subdomains: bool,
+}
+
+/// A good default `HstsStore` implementation, keeping hosts in memory.
+///
+/// This is the implementation used when simply calling `hsts(true)`.
+#[derive(Default)]
+pub struct HstsCache(RwLock<HashMap<String, Entry>>);
+
+struct Entry {
+    /// `None` if the expiry doesn't fit in an `Instant`.
+    expires: Option<Instant>,
+    include_subdomains: bool,
+}
+
+// ===== impl Policy =====
+
+impl Policy {
+    /// Create a policy, like for a preloaded host.
+    pub fn new(max_age: Duration, include_subdomains: bool) -> Policy {
+        Policy {
+            max_age,
+            include_subdomains,
+        }
+    }
+
+    /// Parse the value of a `Strict-Transport-Security` header.
+    ///
+    /// Returns `None` if the value isn't valid, for instance if it has no
+    /// `max-age` directive, or repeats a directive.
+    pub fn parse(value: &HeaderValue) -> Option<Policy> {
+        let value = value.to_str().ok()?;
+        let mut max_age = None;
+        let mut include_subdomains = false;
+
+        for directive in value.split(';') {
+            let directive = directive.trim();
+            if directive.is_empty() {
+                continue;
+            }
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim())),
+                None => (directive, None),
+            };
+            if name.eq_ignore_ascii_case("max-age") {
+                let value = value?;
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                if max_age.is_some() || value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit())
+                {
+                    return None;
+                }
+                // saturate huge values, they mean forever anyways
+                max_age = Some(value.parse().unwrap_or(u64::MAX));
+            } else if name.eq_ignore_ascii_case("includeSubDomains") {
+                if include_subdomains || value.is_some() {
+                    return None;
+                }
+                include_subdomains = true;
+            }
+        }
+
+        Some(Policy {
+            max_age: Duration::from_secs(max_age?),
+            include_subdomains,
+        })
+    }
+
+    /// How long the host should be known for.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns true if the policy also applies to subdomains of the host.
+    pub fn include_subdomains(&self) -> bool {
+        self.include_subdomains
+    }
+}
+
+// ===== impl HstsCache =====
+
+impl HstsCache {
+    /// Create an empty cache.
+    pub fn new() -> HstsCache {
+        HstsCache::default()
+    }
+
+    /// Add a host to the cache, like a preloaded one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use reqwest::hsts::{HstsCache, Policy};
+    ///
+    /// let cache = HstsCache::new();
+    /// cache.insert("example.com", Policy::new(Duration::from_secs(31536000), true));
+    /// ```
+    pub fn insert(&self, host: &str, policy: Policy) {
+        self.set_policy(host, &policy);
+    }
+
+    /// Remove a host from the cache.
+    pub fn remove(&self, host: &str) {
+        self.0.write().unwrap().remove(&normalize(host));
+    }
+
+    /// Remove all hosts from the cache.
+    pub fn clear(&self) {
+        self.0.write().unwrap().clear();
+    }
+}
+
+impl HstsStore for HstsCache {
+    fn set_policy(&self, host: &str, policy: &Policy) {
+        let host = normalize(host);
+        let mut hosts = self.0.write().unwrap();
+        if policy.max_age.is_zero() {
+            hosts.remove(&host);
+            return;
+        }
+        let now = Instant::now();
+        hosts.retain(|_, entry| !entry.is_expired(now));
+        hosts.insert(
+            host,
+            Entry {
+                expires: now.checked_add(policy.max_age),
+                include_subdomains: policy.include_subdomains,
+            },
+        );
+    }
+
+    fn is_known_host(&self, host: &str) -> bool {
+        let host = normalize(host);
+        let hosts = self.0.read().unwrap();
+        let now = Instant::now();
+
+        if let Some(entry) = hosts.get(&host) {
+            if !entry.is_expired(now) {
+                return true;
+            }
+        }
+
+        let mut domain = &host[..];
+        while let Some((_, parent)) = domain.split_once('.') {
+            domain = parent;
+            if let Some(entry) = hosts.get(domain) {
+                if entry.include_subdomains && !entry.is_expired(now) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl fmt::Debug for HstsCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hosts = self.0.read().unwrap();
+        f.debug_set().entries(hosts.keys()).finish()
+    }
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+}
+
+fn normalize(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Rewrites `url` to `https` if its host is known to the store.
+pub(crate) fn upgrade(store: &dyn HstsStore, url: &mut Url) {
+    if url.scheme() != "http" {
+        return;
+    }
+    let host = match url.host() {
+        Some(Host::Domain(host)) => host,
+        _ => return,
+    };
+    if !store.is_known_host(host) {
+        return;
+    }
+    let port = url.port();
+    if url.set_scheme("https").is_err() {
+        return;
+    }
+    if port == Some(80) {
+        let _ = url.set_port(None);
+    }
+}
+
+/// Stores the `Strict-Transport-Security` policy of a response from `url`.
+pub(crate) fn record(store: &dyn HstsStore, headers: &HeaderMap, url: &Url) {
+    if url.scheme() != "https" {
+        return;
+    }
+    let host = match url.host() {
+        Some(Host::Domain(host)) => host,
+        _ => return,
+    };
+    // only the first header is processed
+    if let Some(policy) = headers.get(STRICT_TRANSPORT_SECURITY).and_then(Policy::parse) {
+        store.set_policy(host, &policy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(s: &'static str) -> Option<Policy> {
+        Policy::parse(&HeaderValue::from_static(s))
+    }
+
+    #[test]
+    fn parse_policy() {
+        let year = Duration::from_secs(31536000);
+        assert_eq!(policy("max-age=31536000"), Some(Policy::new(year, false)));
+        assert_eq!(
+            policy("Max-Age=\"31536000\"; includeSubDomains; preload"),
+            Some(Policy::new(year, true))
+        );
+        assert_eq!(policy("max-age=0"), Some(Policy::new(Duration::ZERO, false)));
+        assert_eq!(
+            policy("max-age=99999999999999999999999").map(|p| p.max_age()),
+            Some(Duration::from_secs(u64::MAX))
+        );
+
+        assert_eq!(policy("includeSubDomains"), None);
+        assert_eq!(policy("max-age=1; max-age=2"), None);
+        assert_eq!(policy("max-age=-1"), None);
+        assert_eq!(policy("max-age"), None);
+        assert_eq!(policy("max-age=1; includeSubDomains; includeSubDomains"), None);
+    }
+
+    #[test]
+    fn known_hosts() {
+        let cache = HstsCache::new();
+        let hour = Duration::from_secs(3600);
+        cache.insert("example.com", Policy::new(hour, false));
+        cache.insert("sub.example.org.", Policy::new(hour, true));
+
+        assert!(cache.is_known_host("example.com"));
+        assert!(cache.is_known_host("EXAMPLE.com."));
+        assert!(!cache.is_known_host("www.example.com"));
+        assert!(cache.is_known_host("sub.example.org"));
+        assert!(cache.is_known_host("a.b.sub.example.org"));
+        assert!(!cache.is_known_host("example.org"));
+
+        cache.set_policy("example.com", &Policy::new(Duration::ZERO, false));
+        assert!(!cache.is_known_host("example.com"));
+    }
+
+    #[test]
+    fn upgrades_urls() {
+        let cache = HstsCache::new();
+        cache.insert("example.com", Policy::new(Duration::from_secs(60), true));
+
+        let mut url = Url::parse("http://www.example.com/a?b").unwrap();
+        upgrade(&cache, &mut url);
+        assert_eq!(url.as_str(), "https://www.example.com/a?b");
+
+        let mut url = Url::parse("http://example.com:80/").unwrap();
+        upgrade(&cache, &mut url);
+        assert_eq!(url.as_str(), "https://example.com/");
+
+        let mut url = Url::parse("http://example.com:8080/").unwrap();
+        upgrade(&cache, &mut url);
+        assert_eq!(url.as_str(), "https://example.com:8080/");
+
+        let mut url = Url::parse("http://example.net/").unwrap();
+        upgrade(&cache, &mut url);
+        assert_eq!(url.as_str(), "http://example.net/");
+    }
+
+    #[test]
+    fn records_only_secure_responses() {
+        let cache = HstsCache::new();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            STRICT_TRANSPORT_SECURITY,
+            HeaderValue::from_static("max-age=60"),
+        );
+
+        record(&cache, &headers, &Url::parse("http://plain.example/").unwrap());
+        record(&cache, &headers, &Url::parse("https://127.0.0.1/").unwrap());
+        assert!(!cache.is_known_host("plain.example"));
+        assert!(!cache.is_known_host("127.0.0.1"));
+
+        record(&cache, &headers, &Url::parse("https://secure.example/").unwrap());
+        assert!(cache.is_known_host("secure.example"));
+    }
+}
@@ -0,0 +1,387 @@
+//! HTTP/2 connection events
+//!
+//! A function set with
+//! [`ClientBuilder::http2_events()`](crate::ClientBuilder::http2_events) is
+//! told about the connection-level frames that the HTTP/2 connections of a
+//! `Client` receive: `GOAWAY` frames with their debug data, streams reset by
+//! the server, and the acknowledgements of the client's pings, with their
+//! round-trip time. This helps to find out why long-lived connections keep
+//! being replaced.
+//!
+//! Like [pool events](crate::pool), each [`Http2Event`] is labeled with the
+//! host and port the connection is for, and the function is called
+//! synchronously from the task that drives the connection, so it should be
+//! quick.
+//!
+//! # Example
+//!
+//! ```
+//! use reqwest::http2::Http2EventKind;
+//!
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder()
+//!     .http2_events(|event| {
+//!         if event.kind() == Http2EventKind::GoAway {
+//!             eprintln!(
+//!                 "{}:{} sent GOAWAY with error code {:?}: {}",
+//!                 event.host(),
+//!                 event.port(),
+//!                 event.error_code(),
+//!                 String::from_utf8_lossy(event.debug_data()),
+//!             );
+//!         }
+//!     })
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Something that happened on an HTTP/2 connection.
+pub struct Http2Event<'a> {
+    kind: Http2EventKind,
+    host: &'a str,
+    port: u16,
+    stream_id: u32,
+    error_code: Option<u32>,
+    debug_data: &'a [u8],
+    rtt: Option<Duration>,
+}
+
+/// The kinds of [`Http2Event`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Http2EventKind {
+    /// The server sent a `GOAWAY` frame, so no new requests are sent on the
+    /// connection.
+    GoAway,
+    /// The server reset a stream with a `RST_STREAM` frame.
+    StreamReset,
+    /// The server acknowledged a ping of the client, for instance one sent
+    /// for the
+    /// [`http2_keep_alive_interval`](crate::ClientBuilder::http2_keep_alive_interval).
+    PingAcknowledged,
+}
+
+pub(crate) type Http2EventCallback = Arc<dyn Fn(&Http2Event<'_>) + Send + Sync>;
+
+/// Follows the frames sent and received on a connection, and reports them.
+pub(crate) struct Watcher {
+    callback: Http2EventCallback,
+    host: String,
+    port: u16,
+    received: Frames,
+    sent: Frames,
+    // the payloads of the pings waiting for an acknowledgement
+    pings: Vec<([u8; 8], Instant)>,
+}
+
+/// Splits one direction of a connection into frames.
+#[derive(Default)]
+struct Frames {
+    // bytes before the first frame, like the connection preface
+    skip: usize,
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    // bytes left of the payload of the current frame
+    remaining: usize,
+    // the payload of the current frame, if it's one of those reported
+    payload: Option<Vec<u8>>,
+}
+
+/// A frame that's reported.
+struct Frame<'a> {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: &'a [u8],
+}
+
+const FRAME_HEADER_LEN: usize = 9;
+const PREFACE_LEN: usize = 24;
+// the debug data of a GOAWAY can be long, only the start of it is kept
+const MAX_PAYLOAD_LEN: usize = 16 * 1024;
+
+const RST_STREAM: u8 = 0x3;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const ACK: u8 = 0x1;
+
+// ===== impl Http2Event =====
+
+impl<'a> Http2Event<'a> {
+    /// What happened on the connection.
+    pub fn kind(&self) -> Http2EventKind {
+        self.kind
+    }
+
+    /// The host the connection is for.
+    pub fn host(&self) -> &'a str {
+        self.host
+    }
+
+    /// The port the connection is for.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The stream that was reset, or for a `GOAWAY`, the last stream the
+    /// server may have processed. Streams after it can be retried on another
+    /// connection.
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    /// The error code of a `GOAWAY` or `RST_STREAM` frame, like `0` for
+    /// `NO_ERROR` or `8` for `CANCEL` (RFC 9113, section 7).
+    pub fn error_code(&self) -> Option<u32> {
+        self.error_code
+    }
+
+    /// The debug data of a `GOAWAY` frame, which is often a message
+    /// explaining why the server closes the connection.
+    ///
+    /// Only the first 16 KiB of it are kept.
+    pub fn debug_data(&self) -> &'a [u8] {
+        self.debug_data
+    }
+
+    /// The round-trip time of an acknowledged ping.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+}
+
+impl fmt::Debug for Http2Event<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = f.debug_struct("Http2Event");
+        f.field("kind", &self.kind)
+            .field("host", &self.host)
+            .field("port", &self.port);
+        match self.kind {
+            Http2EventKind::GoAway => {
+                f.field("last_stream_id", &self.stream_id)
+                    .field("error_code", &self.error_code)
+                    .field("debug_data", &String::from_utf8_lossy(self.debug_data));
+            }
+            Http2EventKind::StreamReset => {
+                f.field("stream_id", &self.stream_id)
+                    .field("error_code", &self.error_code);
+            }
+            Http2EventKind::PingAcknowledged => {
+                f.field("rtt", &self.rtt);
+            }
+        }
+        f.finish()
+    }
+}
+
+// ===== impl Watcher =====
+
+impl Watcher {
+    pub(crate) fn new(callback: Http2EventCallback, host: &str, port: u16) -> Watcher {
+        Watcher {
+            callback,
+            host: host.to_owned(),
+            port,
+            received: Frames::default(),
+            sent: Frames {
+                skip: PREFACE_LEN,
+                ..Frames::default()
+            },
+            pings: Vec::new(),
+        }
+    }
+
+    /// Follows bytes read from the connection.
+    pub(crate) fn received(&mut self, bytes: &[u8]) {
+        let Watcher {
+            ref mut received,
+            ref mut pings,
+            ref callback,
+            ref host,
+            port,
+            ..
+        } = *self;
+        received.feed(bytes, |frame| {
+            let mut event = Http2Event {
+                kind: Http2EventKind::GoAway,
+                host,
+                port,
+                stream_id: 0,
+                error_code: None,
+                debug_data: &[],
+                rtt: None,
+            };
+            match frame.kind {
+                GOAWAY if frame.payload.len() >= 8 => {
+                    event.stream_id = read_u32(&frame.payload[..4]) & 0x7fff_ffff;
+                    event.error_code = Some(read_u32(&frame.payload[4..8]));
+                    event.debug_data = &frame.payload[8..];
+                }
+                RST_STREAM if frame.payload.len() >= 4 => {
+                    event.kind = Http2EventKind::StreamReset;
+                    event.stream_id = frame.stream_id;
+                    event.error_code = Some(read_u32(frame.payload));
+                }
+                PING if frame.flags & ACK != 0 => {
+                    let pos = pings
+                        .iter()
+                        .position(|(payload, _)| payload[..] == *frame.payload);
+                    let sent = match pos {
+                        Some(pos) => pings.remove(pos).1,
+                        None => return,
+                    };
+                    event.kind = Http2EventKind::PingAcknowledged;
+                    event.rtt = Some(sent.elapsed());
+                }
+                _ => return,
+            }
+            callback(&event);
+        });
+    }
+
+    /// Follows bytes written to the connection.
+    pub(crate) fn sent(&mut self, bytes: &[u8]) {
+        let pings = &mut self.pings;
+        self.sent.feed(bytes, |frame| {
+            if frame.kind == PING && frame.flags & ACK == 0 {
+                if let Ok(payload) = <[u8; 8]>::try_from(frame.payload) {
+                    // a ping that's never acknowledged is forgotten
+                    if pings.len() == 8 {
+                        pings.remove(0);
+                    }
+                    pings.push((payload, Instant::now()));
+                }
+            }
+        });
+    }
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .finish()
+    }
+}
+
+// ===== impl Frames =====
+
+impl Frames {
+    fn feed(&mut self, mut bytes: &[u8], mut on_frame: impl FnMut(Frame<'_>)) {
+        while !bytes.is_empty() {
+            if self.skip > 0 {
+                let n = self.skip.min(bytes.len());
+                self.skip -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+
+            if self.header_len < FRAME_HEADER_LEN {
+                let n = (FRAME_HEADER_LEN - self.header_len).min(bytes.len());
+                self.header[self.header_len..self.header_len + n].copy_from_slice(&bytes[..n]);
+                self.header_len += n;
+                bytes = &bytes[n..];
+                if self.header_len == FRAME_HEADER_LEN {
+                    let header = &self.header;
+                    self.remaining =
+                        (header[0] as usize) << 16 | (header[1] as usize) << 8 | header[2] as usize;
+                    self.payload = match header[3] {
+                        RST_STREAM | PING | GOAWAY => Some(Vec::new()),
+                        _ => None,
+                    };
+                }
+            } else {
+                let n = self.remaining.min(bytes.len());
+                if let Some(ref mut payload) = self.payload {
+                    let keep = n.min(MAX_PAYLOAD_LEN.saturating_sub(payload.len()));
+                    payload.extend_from_slice(&bytes[..keep]);
+                }
+                self.remaining -= n;
+                bytes = &bytes[n..];
+            }
+
+            if self.header_len == FRAME_HEADER_LEN && self.remaining == 0 {
+                self.header_len = 0;
+                if let Some(payload) = self.payload.take() {
+                    on_frame(Frame {
+                        kind: self.header[3],
+                        flags: self.header[4],
+                        stream_id: read_u32(&self.header[5..]) & 0x7fff_ffff,
+                        payload: &payload,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let len = payload.len() as u32;
+        let mut frame = len.to_be_bytes()[1..].to_vec();
+        frame.extend_from_slice(&[kind, flags]);
+        frame.extend_from_slice(&stream_id.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn watcher_reports_frames() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let callback: Http2EventCallback = {
+            let events = events.clone();
+            Arc::new(move |event: &Http2Event<'_>| {
+                events.lock().unwrap().push(format!("{event:?}"));
+            })
+        };
+        let mut watcher = Watcher::new(callback, "example.com", 443);
+
+        // the preface, the settings and a ping of the client
+        let mut sent = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+        sent.extend(frame(0x4, 0, 0, &[0; 6]));
+        sent.extend(frame(PING, 0, 0, b"12345678"));
+        for chunk in sent.chunks(5) {
+            watcher.sent(chunk);
+        }
+
+        let mut received = frame(0x4, 0, 0, &[]);
+        received.extend(frame(0x0, 0, 1, b"ignored data"));
+        received.extend(frame(PING, ACK, 0, b"12345678"));
+        // an acknowledgement of an unknown ping isn't reported
+        received.extend(frame(PING, ACK, 0, b"87654321"));
+        received.extend(frame(RST_STREAM, 0, 3, &8u32.to_be_bytes()));
+        let mut goaway = 5u32.to_be_bytes().to_vec();
+        goaway.extend_from_slice(&0u32.to_be_bytes());
+        goaway.extend_from_slice(b"shutting down");
+        received.extend(frame(GOAWAY, 0, 0, &goaway));
+        for chunk in received.chunks(3) {
+            watcher.received(chunk);
+        }
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3, "{events:?}");
+        assert!(events[0].contains("PingAcknowledged"), "{}", events[0]);
+        assert!(events[0].contains("rtt: Some("), "{}", events[0]);
+        assert_eq!(
+            events[1],
+            "Http2Event { kind: StreamReset, host: \"example.com\", port: 443, stream_id: 3, error_code: Some(8) }"
+        );
+        assert_eq!(
+            events[2],
+            "Http2Event { kind: GoAway, host: \"example.com\", port: 443, last_stream_id: 5, error_code: Some(0), debug_data: \"shutting down\" }"
+        );
+    }
+}
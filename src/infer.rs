@@ -0,0 +1,158 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+
+/// A trait for request bodies whose `Content-Type` can be inferred, used
+/// by `RequestBuilder::body_with_inferred_type()`.
+///
+/// Bytes and strings are sniffed from their content. Paths are read from
+/// the filesystem, and their type is inferred from the file extension,
+/// falling back to sniffing the content.
+///
+/// This trait is "sealed", such that only types within reqwest can
+/// implement it.
+pub trait IntoInferredBody: IntoInferredBodySealed {}
+
+impl IntoInferredBody for Bytes {}
+impl IntoInferredBody for Vec<u8> {}
+impl IntoInferredBody for &'static [u8] {}
+impl IntoInferredBody for String {}
+impl IntoInferredBody for &'static str {}
+impl IntoInferredBody for PathBuf {}
+impl IntoInferredBody for &Path {}
+
+pub trait IntoInferredBodySealed {
+    // Returns the body, and the inferred `Content-Type`.
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)>;
+}
+
+impl IntoInferredBodySealed for Bytes {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        let mime = sniff(&self);
+        Ok((self, mime))
+    }
+}
+
+impl IntoInferredBodySealed for Vec<u8> {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        Bytes::from(self).into_inferred()
+    }
+}
+
+impl IntoInferredBodySealed for &'static [u8] {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        Bytes::from_static(self).into_inferred()
+    }
+}
+
+impl IntoInferredBodySealed for String {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        Bytes::from(self).into_inferred()
+    }
+}
+
+impl IntoInferredBodySealed for &'static str {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        Bytes::from_static(self.as_bytes()).into_inferred()
+    }
+}
+
+impl IntoInferredBodySealed for PathBuf {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        self.as_path().into_inferred()
+    }
+}
+
+impl IntoInferredBodySealed for &Path {
+    fn into_inferred(self) -> io::Result<(Bytes, &'static str)> {
+        let bytes = Bytes::from(fs::read(self)?);
+        let mime = self
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(from_extension)
+            .unwrap_or_else(|| sniff(&bytes));
+        Ok((bytes, mime))
+    }
+}
+
+fn from_extension(ext: &str) -> Option<&'static str> {
+    let mime = match &*ext.to_ascii_lowercase() {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Infers the `Content-Type` of a body from its content.
+fn sniff(bytes: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| bytes.starts_with(sig)) {
+        return mime;
+    }
+
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace());
+    let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace());
+    let text = match (start, end) {
+        (Some(start), Some(end)) => &bytes[start..=end],
+        _ => &[],
+    };
+    let delimited = |open, close| text.first() == Some(&open) && text.last() == Some(&close);
+    if (delimited(b'{', b'}') || delimited(b'[', b']')) && std::str::from_utf8(text).is_ok() {
+        "application/json"
+    } else if text.starts_with(b"<?xml") {
+        "application/xml"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_content() {
+        assert_eq!(sniff(b" {\"a\": 1}\n"), "application/json");
+        assert_eq!(sniff(b"[1, 2]"), "application/json");
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?><a/>"), "application/xml");
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), "image/png");
+        assert_eq!(sniff(b"{ not closed"), "application/octet-stream");
+        assert_eq!(sniff(b""), "application/octet-stream");
+    }
+
+    #[test]
+    fn path_extension_wins_over_content() {
+        let dir = std::env::temp_dir().join("reqwest-infer-test");
+        fs::create_dir_all(&dir).unwrap();
+
+        let csv = dir.join("data.CSV");
+        fs::write(&csv, "[a],[b]").unwrap();
+        let (bytes, mime) = csv.into_inferred().unwrap();
+        assert_eq!(bytes, "[a],[b]");
+        assert_eq!(mime, "text/csv; charset=utf-8");
+
+        let unknown = dir.join("data.bin");
+        fs::write(&unknown, "{}").unwrap();
+        assert_eq!(unknown.into_inferred().unwrap().1, "application/json");
+
+        let missing = dir.join("missing.json");
+        assert!(missing.into_inferred().is_err());
+    }
+}
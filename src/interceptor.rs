@@ -0,0 +1,188 @@
+//! Request and response interceptors
+//!
+//! An [`Interceptor`], registered with
+//! [`ClientBuilder::interceptor()`](crate::ClientBuilder::interceptor), is
+//! told about every request a `Client` sends, every response it returns,
+//! and every error that ends a request. It's meant for logging, auditing
+//! and metrics, which don't need a full tower layer.
+//!
+//! The callbacks are synchronous, and are called while the request is
+//! polled, so they should be quick. Interceptors can't change requests or
+//! responses.
+//!
+//! - [`on_request`](Interceptor::on_request) is called before a request is
+//!   sent, and before the request of each redirect. Retries of a request
+//!   on a new connection aren't reported again.
+//! - [`on_response`](Interceptor::on_response) is called with the final
+//!   response, after any redirects were followed.
+//! - [`on_error`](Interceptor::on_error) is called when a request that was
+//!   sent fails, including timeouts. Requests that fail before being sent,
+//!   for instance because of an invalid URL, aren't reported at all.
+//!
+//! # Example
+//!
+//! ```
+//! use reqwest::interceptor::{Interceptor, RequestParts, ResponseParts};
+//!
+//! struct Audit;
+//!
+//! impl Interceptor for Audit {
+//!     fn on_request(&self, req: &RequestParts<'_>) {
+//!         eprintln!("--> {} {}", req.method(), req.url());
+//!     }
+//!
+//!     fn on_response(&self, res: &ResponseParts<'_>) {
+//!         eprintln!("<-- {} {}", res.status(), res.url());
+//!     }
+//!
+//!     fn on_error(&self, err: &reqwest::Error) {
+//!         eprintln!("<-- error: {err}");
+//!     }
+//! }
+//!
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder().interceptor(Audit).build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::fmt;
+
+use http::{HeaderMap, Method, StatusCode, Version};
+use url::Url;
+
+/// Callbacks for the requests, responses and errors of a `Client`.
+///
+/// All methods have empty default implementations.
+pub trait Interceptor: Send + Sync + 'static {
+    /// Called before a request, or the request of a redirect, is sent.
+    fn on_request(&self, request: &RequestParts<'_>) {
+        let _ = request;
+    }
+
+    /// Called when the final response of a request is received.
+    fn on_response(&self, response: &ResponseParts<'_>) {
+        let _ = response;
+    }
+
+    /// Called when a request that was sent fails.
+    fn on_error(&self, error: &crate::Error) {
+        let _ = error;
+    }
+}
+
+/// The parts of a request that is about to be sent.
+pub struct RequestParts<'a> {
+    method: &'a Method,
+    url: &'a Url,
+    version: Version,
+    headers: &'a HeaderMap,
+}
+
+/// The parts of a response, without its body.
+pub struct ResponseParts<'a> {
+    url: &'a Url,
+    status: StatusCode,
+    version: Version,
+    headers: &'a HeaderMap,
+}
+
+// ===== impl RequestParts =====
+
+impl<'a> RequestParts<'a> {
+    pub(crate) fn new(
+        method: &'a Method,
+        url: &'a Url,
+        version: Version,
+        headers: &'a HeaderMap,
+    ) -> RequestParts<'a> {
+        RequestParts {
+            method,
+            url,
+            version,
+            headers,
+        }
+    }
+
+    /// Get the method of the request.
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    /// Get the URL of the request.
+    pub fn url(&self) -> &Url {
+        self.url
+    }
+
+    /// Get the HTTP version requested.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Get the headers of the request.
+    ///
+    /// Headers added when the request is written, such as `Host` and
+    /// `Content-Length`, and proxy credentials aren't included.
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+}
+
+impl fmt::Debug for RequestParts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RequestParts")
+            .field("method", self.method)
+            .field("url", &self.url.as_str())
+            .field("version", &self.version)
+            .field("headers", self.headers)
+            .finish()
+    }
+}
+
+// ===== impl ResponseParts =====
+
+impl<'a> ResponseParts<'a> {
+    pub(crate) fn new(
+        url: &'a Url,
+        status: StatusCode,
+        version: Version,
+        headers: &'a HeaderMap,
+    ) -> ResponseParts<'a> {
+        ResponseParts {
+            url,
+            status,
+            version,
+            headers,
+        }
+    }
+
+    /// Get the final URL of the response, after any redirects.
+    pub fn url(&self) -> &Url {
+        self.url
+    }
+
+    /// Get the status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the HTTP version of the response.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Get the headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+}
+
+impl fmt::Debug for ResponseParts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResponseParts")
+            .field("url", &self.url.as_str())
+            .field("status", &self.status)
+            .field("version", &self.version)
+            .field("headers", self.headers)
+            .finish()
+    }
+}
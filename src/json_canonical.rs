@@ -0,0 +1,182 @@
+//! Canonical JSON, as in the JSON Canonicalization Scheme (RFC 8785).
+
+use std::io::Write;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// The largest integer whose value is exact as a double.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Serializes `value` in the canonical form.
+///
+/// Object members are sorted by the UTF-16 code units of their names,
+/// there's no whitespace, and numbers are written like ECMAScript does.
+/// Integers that can't be exact as doubles are rejected.
+pub(crate) fn to_vec<T: Serialize + ?Sized>(value: &T) -> crate::Result<Vec<u8>> {
+    let value = serde_json::to_value(value).map_err(crate::error::builder)?;
+    let mut out = Vec::new();
+    write_value(&mut out, &value)?;
+    Ok(out)
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) -> crate::Result<()> {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        Value::Number(n) => write_number(out, n)?,
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            out.push(b'[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(out, value)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(map) => {
+            let mut members = map.iter().collect::<Vec<_>>();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push(b'{');
+            for (i, (name, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_string(out, name);
+                out.push(b':');
+                write_value(out, value)?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    // serde_json escapes strings the same way
+    serde_json::to_writer(out, s).expect("writing to a Vec doesn't fail");
+}
+
+fn write_number(out: &mut Vec<u8>, n: &serde_json::Number) -> crate::Result<()> {
+    if let Some(u) = n.as_u64() {
+        if u > MAX_SAFE_INTEGER {
+            return Err(crate::error::builder(format!(
+                "integer {u} can't be represented in canonical JSON"
+            )));
+        }
+        write!(out, "{u}").expect("writing to a Vec doesn't fail");
+    } else if let Some(i) = n.as_i64() {
+        if i.unsigned_abs() > MAX_SAFE_INTEGER {
+            return Err(crate::error::builder(format!(
+                "integer {i} can't be represented in canonical JSON"
+            )));
+        }
+        write!(out, "{i}").expect("writing to a Vec doesn't fail");
+    } else if let Some(f) = n.as_f64() {
+        write_double(out, f);
+    }
+    Ok(())
+}
+
+/// Writes a finite double like ECMAScript's `Number.prototype.toString()`.
+fn write_double(out: &mut Vec<u8>, f: f64) {
+    if f == 0.0 {
+        // including -0
+        out.push(b'0');
+        return;
+    }
+    if f < 0.0 {
+        out.push(b'-');
+    }
+
+    // the shortest digits that round-trip, as `d.ddde±x`
+    let exp = format!("{:e}", f.abs());
+    let (mantissa, e) = exp.split_once('e').expect("exponent format");
+    let digits = mantissa.bytes().filter(|&b| b != b'.').collect::<Vec<_>>();
+    let k = digits.len() as i32;
+    // the position of the decimal point, relative to the first digit
+    let n = e.parse::<i32>().expect("exponent format") + 1;
+
+    if k <= n && n <= 21 {
+        out.extend_from_slice(&digits);
+        out.extend(std::iter::repeat(b'0').take((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.extend_from_slice(&digits[..n as usize]);
+        out.push(b'.');
+        out.extend_from_slice(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.extend_from_slice(b"0.");
+        out.extend(std::iter::repeat(b'0').take(-n as usize));
+        out.extend_from_slice(&digits);
+    } else {
+        out.extend_from_slice(&digits[..1]);
+        if k > 1 {
+            out.push(b'.');
+            out.extend_from_slice(&digits[1..]);
+        }
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        write!(out, "e{sign}{}", (n - 1).abs()).expect("writing to a Vec doesn't fail");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(value: Value) -> String {
+        String::from_utf8(to_vec(&value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sorts_members() {
+        let value = serde_json::json!({
+            "b": [1, {"z": null, "a": true}],
+            "a": "x",
+            "\u{fb33}": 1,
+            "\u{1f600}": 2,
+        });
+        // U+1F600 is a surrogate pair, sorting before U+FB33 in UTF-16
+        assert_eq!(
+            canonical(value),
+            "{\"a\":\"x\",\"b\":[1,{\"a\":true,\"z\":null}],\"\u{1f600}\":2,\"\u{fb33}\":1}"
+        );
+    }
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(
+            canonical(Value::from("\u{1}\n\"\\/\u{e9}")),
+            "\"\\u0001\\n\\\"\\\\/\u{e9}\""
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        // examples from RFC 8785, appendix B
+        let cases: &[(f64, &str)] = &[
+            (0.0, "0"),
+            (-0.0, "0"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (1e21, "1e+21"),
+            (1e-6, "0.000001"),
+            (1e-7, "1e-7"),
+            (0.1, "0.1"),
+            (333333333.3333333, "333333333.3333333"),
+            (-1.5, "-1.5"),
+            (100.0, "100"),
+        ];
+        for &(f, expected) in cases {
+            let mut out = Vec::new();
+            write_double(&mut out, f);
+            assert_eq!(String::from_utf8(out).unwrap(), expected, "{f:e}");
+        }
+
+        assert_eq!(canonical(serde_json::json!([1, -2, 3.0])), "[1,-2,3]");
+        assert!(to_vec(&u64::MAX).unwrap_err().is_builder());
+    }
+}
@@ -195,9 +195,13 @@
 //!   while using root certificates from the `webpki-roots` crate.
 //! - **rustls-tls-native-roots**: Enables TLS functionality provided by `rustls`,
 //!   while using root certificates from the `rustls-native-certs` crate.
+//! - **rustls-tls-ocsp**: Provides [`ClientBuilder::tls_ocsp_check()`] to check
+//!   the revocation status of server certificates with the rustls backend.
 //! - **blocking**: Provides the [blocking][] client API.
 //! - **charset** *(enabled by default)*: Improved support for decoding text.
 //! - **cookies**: Provides cookie session support.
+//! - **psl**: Lets the cookie [`Jar`](cookie::Jar) reject cookies set for
+//!   public suffixes.
 //! - **gzip**: Provides response body gzip decompression.
 //! - **brotli**: Provides response body brotli decompression.
 //! - **zstd**: Provides response body zstd decompression.
@@ -206,6 +210,15 @@
 //! - **multipart**: Provides functionality for multipart forms.
 //! - **stream**: Adds support for `futures::Stream`.
 //! - **socks**: Provides SOCKS5 proxy support.
+//! - **proxy-util**: Provides [helpers](proxy_util) for building a reverse proxy.
+//! - **curl-import**: Provides [`Request::from_curl()`] to build requests from curl commands.
+//! - **curl-debug**: Provides [`Request::to_curl()`] to format requests as curl commands.
+//! - **digest**: Provides [verification](digest) of response bodies against
+//!   SHA-256 and SHA-512 digests.
+//! - **test-util**: Provides [utilities](test_util) for testing applications,
+//!   such as simulating slow networks.
+//! - **tracing**: Emits [`tracing`](https://docs.rs/tracing) spans for
+//!   requests, DNS lookups, connects, TLS handshakes, redirects and retries.
 //! - **hickory-dns**: Enables a hickory-dns async resolver instead of default
 //!   threadpool using `getaddrinfo`.
 //!
@@ -275,7 +288,7 @@ mod error;
 mod into_url;
 mod response;
 
-pub use self::error::{Error, Result};
+pub use self::error::{Error, ErrorKind, Result};
 pub use self::into_url::IntoUrl;
 pub use self::response::ResponseBuilderExt;
 
@@ -343,9 +356,15 @@ if_hyper! {
     doctest!("../README.md");
 
     pub use self::async_impl::{
-        Body, Client, ClientBuilder, Request, RequestBuilder, Response, Upgraded,
+        Body, Client, ClientBuilder, HttpPending, Request, RequestBuilder, RequestService,
+        Response, UpgradeHandle, Upgraded,
     };
-    pub use self::proxy::{Proxy,NoProxy};
+    #[cfg(feature = "stream")]
+    pub use self::async_impl::{Downloader, SpilledBody};
+    #[cfg(feature = "http3")]
+    pub use self::async_impl::Http3ZeroRtt;
+    pub use self::infer::IntoInferredBody;
+    pub use self::proxy::{NoProxy, NoProxyBuilder, Proxy, ProxyMatcher};
     #[cfg(feature = "__tls")]
     // Re-exports, to be removed in a future release
     pub use tls::{Certificate, Identity};
@@ -356,14 +375,41 @@ if_hyper! {
     mod async_impl;
     #[cfg(feature = "blocking")]
     pub mod blocking;
+    pub mod capabilities;
+    pub mod circuit;
+    pub mod codec;
+    pub mod conditional;
     mod connect;
     #[cfg(feature = "cookies")]
     pub mod cookie;
+    #[cfg(feature = "curl-import")]
+    mod curl;
+    #[cfg(feature = "curl-debug")]
+    mod curl_debug;
+    #[cfg(feature = "digest")]
+    pub mod digest;
     pub mod dns;
+    pub mod hsts;
+    #[cfg(feature = "http2")]
+    pub mod http2;
+    mod infer;
+    pub mod interceptor;
+    #[cfg(feature = "json")]
+    mod json_canonical;
+    pub mod link;
+    pub mod notice;
+    pub mod pool;
     mod proxy;
+    #[cfg(feature = "proxy-util")]
+    pub mod proxy_util;
+    pub mod raw_headers;
     pub mod redirect;
+    pub mod server_timing;
+    #[cfg(feature = "test-util")]
+    pub mod test_util;
     #[cfg(feature = "__tls")]
     pub mod tls;
+    pub mod trace_context;
     mod util;
 }
 
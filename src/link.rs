@@ -0,0 +1,363 @@
+//! Web Linking
+//!
+//! Many APIs paginate their results using the `Link` header described in
+//! [RFC 8288](https://www.rfc-editor.org/rfc/rfc8288) (formerly RFC 5988),
+//! such as:
+//!
+//! ```text
+//! Link: <https://api.example.com/items?page=2>; rel="next",
+//!       <https://api.example.com/items?page=5>; rel="last"
+//! ```
+//!
+//! [`Response::links()`](crate::Response::links) parses these headers, and
+//! [`RequestBuilder::paginate()`](crate::RequestBuilder::paginate) can be used
+//! to follow the `rel="next"` links until there are no more pages.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use http::header::{HeaderMap, LINK};
+
+use crate::async_impl::client::Pending;
+use crate::{Client, Request, Response, Url};
+
+/// A single link parsed from a `Link` header.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Link {
+    url: Url,
+    params: Vec<(String, String)>,
+}
+
+/// A `Stream` of `Response`s, following `rel="next"` links.
+///
+/// Created by [`RequestBuilder::paginate()`](crate::RequestBuilder::paginate).
+#[must_use = "streams do nothing unless polled"]
+pub struct Pages {
+    client: Client,
+    request: Option<crate::Result<Request>>,
+    next: Option<Url>,
+    in_flight: Option<Pending>,
+    visited: Vec<Url>,
+    max_pages: Option<usize>,
+}
+
+// ===== impl Link =====
+
+impl Link {
+    /// The target of this link, resolved against the response URL.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The value of the `rel` parameter, if present.
+    ///
+    /// Note that this may contain several space-separated relation types.
+    /// Use [`Link::has_rel()`] to check for a specific one.
+    pub fn rel(&self) -> Option<&str> {
+        self.param("rel")
+    }
+
+    /// Returns true if this link has the given relation type.
+    ///
+    /// Relation types are compared case-insensitively.
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rel()
+            .map(|rels| {
+                rels.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case(rel))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Get the value of a link parameter, such as `title` or `type`.
+    ///
+    /// Parameter names are compared case-insensitively. If a parameter
+    /// occurs more than once, the first occurrence is returned.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Link")
+            .field("url", &self.url.as_str())
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// Parse all `Link` headers, resolving each target against `base`.
+///
+/// Malformed links are skipped.
+pub(crate) fn parse(headers: &HeaderMap, base: &Url) -> Vec<Link> {
+    let mut links = Vec::new();
+    for value in headers.get_all(LINK) {
+        // Link targets are URI-references, which are ASCII.
+        if let Ok(value) = value.to_str() {
+            parse_value(value, base, &mut links);
+        }
+    }
+    links
+}
+
+fn parse_value(mut s: &str, base: &Url, links: &mut Vec<Link>) {
+    loop {
+        s = s.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        if s.is_empty() {
+            return;
+        }
+
+        let parsed = parse_link(s, base);
+        let rest = match parsed {
+            Some((link, rest)) => {
+                if let Some(link) = link {
+                    links.push(link);
+                }
+                rest
+            }
+            None => skip_link(s),
+        };
+        s = rest;
+    }
+}
+
+/// Parses one `<uri>; param=value` link-value, returning the rest of input.
+///
+/// The returned link is `None` if the target couldn't be resolved.
+fn parse_link<'a>(s: &'a str, base: &Url) -> Option<(Option<Link>, &'a str)> {
+    let s = s.strip_prefix('<')?;
+    let end = s.find('>')?;
+    let target = s[..end].trim();
+    let mut s = &s[end + 1..];
+
+    let mut params = Vec::new();
+    loop {
+        s = s.trim_start();
+        match s.strip_prefix(';') {
+            Some(rest) => s = rest.trim_start(),
+            None => break,
+        }
+
+        let name_end = s
+            .find(|c: char| c == '=' || c == ';' || c == ',' || c.is_ascii_whitespace())
+            .unwrap_or(s.len());
+        let name = &s[..name_end];
+        s = s[name_end..].trim_start();
+
+        let value = if let Some(rest) = s.strip_prefix('=') {
+            let (value, rest) = parse_param_value(rest.trim_start())?;
+            s = rest;
+            value
+        } else {
+            String::new()
+        };
+
+        if !name.is_empty() {
+            params.push((name.to_ascii_lowercase(), value));
+        }
+    }
+
+    if !s.is_empty() && !s.starts_with(',') {
+        return None;
+    }
+
+    let link = base.join(target).ok().map(|url| Link { url, params });
+    Some((link, s))
+}
+
+fn parse_param_value(s: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((value, &quoted[i + 1..])),
+                '\\' => value.push(chars.next()?.1),
+                c => value.push(c),
+            }
+        }
+        // unterminated quoted-string
+        None
+    } else {
+        let end = s
+            .find(|c: char| c == ';' || c == ',' || c.is_ascii_whitespace())
+            .unwrap_or(s.len());
+        Some((s[..end].to_owned(), &s[end..]))
+    }
+}
+
+/// Skip past a malformed link-value, to the next top-level comma.
+fn skip_link(s: &str) -> &str {
+    let mut in_uri = false;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"' if !in_uri => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_uri = true,
+            '>' if !in_quotes => in_uri = false,
+            ',' if !in_quotes && !in_uri => return &s[i..],
+            _ => (),
+        }
+    }
+    ""
+}
+
+// ===== impl Pages =====
+
+impl Pages {
+    pub(crate) fn new(client: Client, request: crate::Result<Request>) -> Pages {
+        Pages {
+            client,
+            request: Some(request),
+            next: None,
+            in_flight: None,
+            visited: Vec::new(),
+            max_pages: None,
+        }
+    }
+
+    /// Stop after fetching at most `max` pages.
+    ///
+    /// By default, pages are fetched until a response no longer includes a
+    /// `rel="next"` link, or the link points to a page already fetched.
+    pub fn max_pages(mut self, max: usize) -> Pages {
+        self.max_pages = Some(max);
+        self
+    }
+
+    fn next_request(&mut self) -> Option<crate::Result<Request>> {
+        let template = match self.request {
+            Some(Ok(ref template)) => template,
+            Some(Err(_)) => return self.request.take(),
+            None => return None,
+        };
+
+        if let Some(max) = self.max_pages {
+            if self.visited.len() >= max {
+                return None;
+            }
+        }
+
+        let url = match self.next.take() {
+            Some(url) => url,
+            None if self.visited.is_empty() => template.url().clone(),
+            None => return None,
+        };
+
+        let mut req = match template.try_clone() {
+            Some(req) => req,
+            None => {
+                self.request = None;
+                return Some(Err(crate::error::builder(
+                    "paginated request body must be reusable",
+                )
+                .with_url(url)));
+            }
+        };
+        *req.url_mut() = url.clone();
+        self.visited.push(url);
+        Some(Ok(req))
+    }
+}
+
+impl Stream for Pages {
+    type Item = crate::Result<Response>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(ref mut in_flight) = self.in_flight {
+                let res = futures_core::ready!(Pin::new(in_flight).poll(cx));
+                self.in_flight = None;
+
+                return match res {
+                    Ok(res) => {
+                        self.next = res
+                            .links()
+                            .into_iter()
+                            .find(|link| link.has_rel("next"))
+                            .map(|link| link.url)
+                            .filter(|url| !self.visited.contains(url));
+                        Poll::Ready(Some(Ok(res)))
+                    }
+                    Err(err) => {
+                        self.request = None;
+                        Poll::Ready(Some(Err(err)))
+                    }
+                };
+            }
+
+            match self.next_request() {
+                Some(Ok(req)) => {
+                    self.in_flight = Some(self.client.execute_request(req));
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Pages {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pages")
+            .field("next", &self.next)
+            .field("pages", &self.visited.len())
+            .field("max_pages", &self.max_pages)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(value: &'static str) -> Vec<Link> {
+        let mut headers = HeaderMap::new();
+        headers.insert(LINK, value.parse().unwrap());
+        let base = Url::parse("https://api.example.com/items?page=1").unwrap();
+        parse(&headers, &base)
+    }
+
+    #[test]
+    fn parse_github_style() {
+        let links = links(
+            r#"<https://api.example.com/items?page=2>; rel="next", <https://api.example.com/items?page=5>; rel="last""#,
+        );
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].url().as_str(), "https://api.example.com/items?page=2");
+        assert!(links[0].has_rel("next"));
+        assert_eq!(links[1].rel(), Some("last"));
+    }
+
+    #[test]
+    fn parse_relative_and_params() {
+        let links = links(r#"</items?page=2>; rel="next prefetch"; title="a, \"b\"", <other>; REL=prev"#);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].url().as_str(), "https://api.example.com/items?page=2");
+        assert!(links[0].has_rel("prefetch"));
+        assert!(links[0].has_rel("NEXT"));
+        assert_eq!(links[0].param("title"), Some(r#"a, "b""#));
+        assert_eq!(links[1].url().as_str(), "https://api.example.com/other");
+        assert!(links[1].has_rel("prev"));
+    }
+
+    #[test]
+    fn parse_skips_malformed() {
+        let links = links(r#"garbage; rel="x,y", <https://example.com/ok>; rel=next"#);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].url().as_str(), "https://example.com/ok");
+    }
+}
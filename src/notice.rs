@@ -0,0 +1,300 @@
+//! Deprecation and warning notices
+//!
+//! APIs announce upcoming breaking changes with response headers:
+//!
+//! - `Deprecation` ([RFC 9745](https://www.rfc-editor.org/rfc/rfc9745))
+//!   signals that an endpoint is, or will be, deprecated.
+//! - `Sunset` ([RFC 8594](https://www.rfc-editor.org/rfc/rfc8594)) signals
+//!   when it will stop responding.
+//! - `Warning` ([RFC 7234](https://www.rfc-editor.org/rfc/rfc7234#section-5.5))
+//!   carries free-form warnings, and is still used by some APIs despite being
+//!   obsoleted.
+//!
+//! [`Response::deprecation()`](crate::Response::deprecation) and
+//! [`Response::warnings()`](crate::Response::warnings) parse these headers,
+//! and [`ClientBuilder::on_deprecation()`](crate::ClientBuilder::on_deprecation)
+//! can be used to notice deprecations of any endpoint a `Client` talks to.
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::{HeaderMap, HeaderName, WARNING};
+
+/// A deprecation announced by the `Deprecation` or `Sunset` headers of a
+/// response.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    deprecated: bool,
+    date: Option<SystemTime>,
+    sunset: Option<SystemTime>,
+}
+
+/// A warning from a `Warning` header.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Warning {
+    code: u16,
+    agent: String,
+    text: String,
+    date: Option<SystemTime>,
+}
+
+// ===== impl Deprecation =====
+
+impl Deprecation {
+    /// Parses the `Deprecation` and `Sunset` headers.
+    ///
+    /// Returns `None` if neither is present.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Deprecation> {
+        let deprecation = headers
+            .get(HeaderName::from_static("deprecation"))
+            .and_then(|value| value.to_str().ok());
+        let sunset = headers
+            .get(HeaderName::from_static("sunset"))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value.trim()).ok());
+        if deprecation.is_none() && sunset.is_none() {
+            return None;
+        }
+
+        Some(Deprecation {
+            deprecated: deprecation.is_some(),
+            date: deprecation.and_then(parse_deprecation_date),
+            sunset,
+        })
+    }
+
+    /// Returns true if the response had a `Deprecation` header.
+    ///
+    /// A response can announce a `Sunset` without deprecating the endpoint
+    /// first.
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated
+    }
+
+    /// When the endpoint was, or will be, deprecated.
+    ///
+    /// This is `None` if the `Deprecation` header doesn't have a date, such
+    /// as the `Deprecation: true` of earlier drafts of the specification.
+    pub fn date(&self) -> Option<SystemTime> {
+        self.date
+    }
+
+    /// When the endpoint will stop responding, from the `Sunset` header.
+    pub fn sunset(&self) -> Option<SystemTime> {
+        self.sunset
+    }
+}
+
+impl fmt::Debug for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Deprecation")
+            .field("deprecated", &self.deprecated)
+            .field("date", &self.date)
+            .field("sunset", &self.sunset)
+            .finish()
+    }
+}
+
+/// Parses the date of a `Deprecation` header, either a structured field
+/// date such as `@1688169599`, or the HTTP-date of earlier drafts.
+fn parse_deprecation_date(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    match value.strip_prefix('@') {
+        Some(secs) => {
+            let secs = secs.parse::<i64>().ok()?;
+            let offset = Duration::from_secs(secs.unsigned_abs());
+            if secs < 0 {
+                UNIX_EPOCH.checked_sub(offset)
+            } else {
+                UNIX_EPOCH.checked_add(offset)
+            }
+        }
+        None => httpdate::parse_http_date(value).ok(),
+    }
+}
+
+// ===== impl Warning =====
+
+impl Warning {
+    /// Parses all the `Warning` headers.
+    ///
+    /// Malformed warnings are ignored.
+    pub fn from_headers(headers: &HeaderMap) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for value in headers.get_all(WARNING) {
+            if let Ok(value) = value.to_str() {
+                parse_warnings(value, &mut warnings);
+            }
+        }
+        warnings
+    }
+
+    /// The 3-digit warning code, such as `299` for a miscellaneous
+    /// persistent warning.
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// The host, or pseudonym, of the agent that added the warning.
+    pub fn agent(&self) -> &str {
+        &self.agent
+    }
+
+    /// The warning text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The date of the warning, if present.
+    pub fn date(&self) -> Option<SystemTime> {
+        self.date
+    }
+}
+
+impl fmt::Debug for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Warning")
+            .field("code", &self.code)
+            .field("agent", &self.agent)
+            .field("text", &self.text)
+            .field("date", &self.date)
+            .finish()
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {:?}", self.code, self.agent, self.text)
+    }
+}
+
+/// Parses a comma separated list of
+/// `warn-code SP warn-agent SP warn-text [ SP warn-date ]`.
+fn parse_warnings(mut s: &str, warnings: &mut Vec<Warning>) {
+    loop {
+        s = s.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        if s.is_empty() {
+            return;
+        }
+        match parse_warning(s) {
+            Some((warning, rest)) => {
+                warnings.push(warning);
+                s = rest;
+            }
+            // skip to the next element
+            None => match s.find(',') {
+                Some(comma) => s = &s[comma..],
+                None => return,
+            },
+        }
+    }
+}
+
+fn parse_warning(s: &str) -> Option<(Warning, &str)> {
+    let (code, s) = s.split_once(' ')?;
+    if code.len() != 3 {
+        return None;
+    }
+    let code = code.parse().ok()?;
+    let (agent, s) = s.trim_start().split_once(' ')?;
+    let (text, s) = parse_quoted(s.trim_start())?;
+
+    let trimmed = s.trim_start();
+    let (date, s) = match parse_quoted(trimmed) {
+        Some((date, rest)) => (httpdate::parse_http_date(&date).ok(), rest),
+        None => (None, s),
+    };
+    let s = s.trim_start();
+    if !(s.is_empty() || s.starts_with(',')) {
+        return None;
+    }
+
+    let warning = Warning {
+        code,
+        agent: agent.to_owned(),
+        text,
+        date,
+    };
+    Some((warning, s))
+}
+
+/// Parses a quoted-string, returning it unescaped with the rest of `s`.
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut unescaped = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((unescaped, &s[i + 2..])),
+            '\\' => unescaped.push(chars.next()?.1),
+            c => unescaped.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deprecation_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Deprecation::from_headers(&headers), None);
+
+        headers.insert("deprecation", "@1688169599".parse().unwrap());
+        headers.insert("sunset", "Wed, 11 Nov 2026 23:59:59 GMT".parse().unwrap());
+        let deprecation = Deprecation::from_headers(&headers).unwrap();
+        assert!(deprecation.is_deprecated());
+        assert_eq!(
+            deprecation.date(),
+            Some(UNIX_EPOCH + Duration::from_secs(1688169599))
+        );
+        assert_eq!(
+            deprecation.sunset(),
+            Some(UNIX_EPOCH + Duration::from_secs(1794441599))
+        );
+
+        headers.insert("deprecation", "true".parse().unwrap());
+        headers.remove("sunset");
+        let deprecation = Deprecation::from_headers(&headers).unwrap();
+        assert!(deprecation.is_deprecated());
+        assert_eq!(deprecation.date(), None);
+        assert_eq!(deprecation.sunset(), None);
+
+        headers.remove("deprecation");
+        headers.insert("sunset", "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap());
+        let deprecation = Deprecation::from_headers(&headers).unwrap();
+        assert!(!deprecation.is_deprecated());
+        assert_eq!(
+            deprecation.sunset(),
+            Some(UNIX_EPOCH + Duration::from_secs(784111777))
+        );
+    }
+
+    #[test]
+    fn warnings_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            WARNING,
+            r#"299 api.example.com "Deprecated, use \"v2\"" "Sun, 06 Nov 1994 08:49:37 GMT", bogus, 110 - "Response is Stale""#
+                .parse()
+                .unwrap(),
+        );
+        headers.append(WARNING, "214 proxy:8080 \"Transformation Applied\"".parse().unwrap());
+
+        let warnings = Warning::from_headers(&headers);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[0].code(), 299);
+        assert_eq!(warnings[0].agent(), "api.example.com");
+        assert_eq!(warnings[0].text(), "Deprecated, use \"v2\"");
+        assert_eq!(
+            warnings[0].date(),
+            Some(UNIX_EPOCH + Duration::from_secs(784111777))
+        );
+        assert_eq!(warnings[1].code(), 110);
+        assert_eq!(warnings[1].agent(), "-");
+        assert_eq!(warnings[1].date(), None);
+        assert_eq!(warnings[2].agent(), "proxy:8080");
+        assert_eq!(warnings[2].text(), "Transformation Applied");
+    }
+}
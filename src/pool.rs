@@ -0,0 +1,254 @@
+//! Connection pool events
+//!
+//! A function set with
+//! [`ClientBuilder::pool_events()`](crate::ClientBuilder::pool_events) is
+//! told when the connections of a `Client` are created, reused and closed.
+//! This gives some visibility into connection churn, for instance to notice
+//! that connections are rarely reused, without a full metrics integration.
+//!
+//! Each [`PoolEvent`] is labeled with the host and port the connection is
+//! for, and the HTTP version it speaks. When a proxy is used, these are
+//! still those of the target, not of the proxy.
+//!
+//! The function is called synchronously, from the task that drives the
+//! connection or the request, so it should be quick.
+//!
+//! # Example
+//!
+//! ```
+//! use reqwest::pool::PoolEventKind;
+//!
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder()
+//!     .pool_events(|event| {
+//!         if event.kind() == PoolEventKind::ClosedWithError {
+//!             eprintln!("connection to {}:{} failed", event.host(), event.port());
+//!         }
+//!     })
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::{Response, Version};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::BoxError;
+
+/// Something that happened to a pooled connection.
+pub struct PoolEvent<'a> {
+    kind: PoolEventKind,
+    host: &'a str,
+    port: u16,
+    version: Version,
+}
+
+/// The kinds of [`PoolEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PoolEventKind {
+    /// A new connection was established.
+    Created,
+    /// A response was received on a connection that already had been used
+    /// for an earlier request.
+    ///
+    /// The first request of a connection isn't reported.
+    Reused,
+    /// A connection was dropped while it was still usable, usually because
+    /// it was idle for longer than the
+    /// [`pool_idle_timeout`](crate::ClientBuilder::pool_idle_timeout), there
+    /// were already enough idle connections to the host, or the `Client`
+    /// was dropped.
+    Evicted,
+    /// A connection was dropped after the server closed it.
+    Closed,
+    /// A connection was dropped after a read or write on it failed.
+    ClosedWithError,
+}
+
+pub(crate) type PoolEventCallback = Arc<dyn Fn(&PoolEvent<'_>) + Send + Sync>;
+
+/// The state of a connection whose events are reported.
+pub(crate) struct Tracked {
+    callback: PoolEventCallback,
+    host: String,
+    port: u16,
+    version: Version,
+    used: AtomicBool,
+}
+
+/// Added to the extras of a tracked connection, to find out when it is
+/// reused.
+#[derive(Clone)]
+pub(crate) struct ConnUse(Arc<Tracked>);
+
+/// The connection slots of each host, which bound how many connections to
+/// it are open at once.
+pub(crate) struct Slots {
+    max: usize,
+    timeout: Option<Duration>,
+    hosts: Mutex<HashMap<(String, u16), Arc<Semaphore>>>,
+}
+
+/// A slot, held by a connection for as long as it's open.
+pub(crate) struct Slot {
+    _permit: OwnedSemaphorePermit,
+}
+
+// ===== impl PoolEvent =====
+
+impl<'a> PoolEvent<'a> {
+    /// What happened to the connection.
+    pub fn kind(&self) -> PoolEventKind {
+        self.kind
+    }
+
+    /// The host the connection is for.
+    pub fn host(&self) -> &'a str {
+        self.host
+    }
+
+    /// The port the connection is for.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The HTTP version of the connection.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+}
+
+impl fmt::Debug for PoolEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoolEvent")
+            .field("kind", &self.kind)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+// ===== impl Tracked =====
+
+impl Tracked {
+    /// Reports a new connection.
+    pub(crate) fn created(
+        callback: PoolEventCallback,
+        host: &str,
+        port: u16,
+        version: Version,
+    ) -> Arc<Tracked> {
+        let tracked = Arc::new(Tracked {
+            callback,
+            host: host.to_owned(),
+            port,
+            version,
+            used: AtomicBool::new(false),
+        });
+        tracked.emit(PoolEventKind::Created);
+        tracked
+    }
+
+    pub(crate) fn emit(&self, kind: PoolEventKind) {
+        (self.callback)(&PoolEvent {
+            kind,
+            host: &self.host,
+            port: self.port,
+            version: self.version,
+        });
+    }
+
+    pub(crate) fn conn_use(self: &Arc<Self>) -> ConnUse {
+        ConnUse(self.clone())
+    }
+}
+
+// ===== impl Slots =====
+
+impl Slots {
+    /// Creates slots for up to `max` connections to each host, which new
+    /// connections wait for up to `timeout`.
+    pub(crate) fn new(max: usize, timeout: Option<Duration>) -> Slots {
+        Slots {
+            max,
+            timeout,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits for a free slot of a host.
+    ///
+    /// Meanwhile, the pool may hand out a connection that became idle to
+    /// the request that's waiting, and then the new connection is opened
+    /// later, for the pool.
+    pub(crate) async fn acquire(&self, host: &str, port: u16) -> Result<Slot, BoxError> {
+        let semaphore = {
+            let mut hosts = self.hosts.lock().unwrap();
+            // forget the hosts without connections
+            hosts.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+            hosts
+                .entry((host.to_owned(), port))
+                .or_insert_with(|| Arc::new(Semaphore::new(self.max)))
+                .clone()
+        };
+        let acquire = semaphore.acquire_owned();
+        let permit = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, acquire).await {
+                Ok(permit) => permit,
+                Err(_) => {
+                    log::debug!("no connection to {host}:{port} became available in time");
+                    return Err(Box::new(crate::error::PoolTimedOut));
+                }
+            },
+            None => acquire.await,
+        };
+        Ok(Slot {
+            _permit: permit.expect("slots are never closed"),
+        })
+    }
+}
+
+impl fmt::Debug for Slots {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Slots")
+            .field("max", &self.max)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Reports a reused connection, if `res` was received on one.
+pub(crate) fn record_response<B>(res: &mut Response<B>) {
+    if let Some(ConnUse(tracked)) = res.extensions_mut().remove::<ConnUse>() {
+        if tracked.used.swap(true, Ordering::AcqRel) {
+            tracked.emit(PoolEventKind::Reused);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slots_wait_and_time_out() {
+        let slots = Slots::new(1, Some(Duration::from_millis(20)));
+        let slot = slots.acquire("example.com", 443).await.unwrap();
+
+        // other hosts have their own slots
+        slots.acquire("example.com", 80).await.unwrap();
+
+        let err = slots.acquire("example.com", 443).await.err().unwrap();
+        assert!(err.is::<crate::error::PoolTimedOut>());
+
+        drop(slot);
+        slots.acquire("example.com", 443).await.unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+//! Reverse proxy helpers
+//!
+//! A common use of reqwest is to forward requests received by a hyper server
+//! on to some upstream service. Doing that correctly means more than copying
+//! the method, URI and headers: hop-by-hop headers must be stripped in both
+//! directions, bodies should be streamed instead of buffered, and protocol
+//! upgrades (such as WebSockets) need both connections spliced together.
+//!
+//! [`forward`] takes care of those details.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn run(
+//! #     req: http::Request<hyper::body::Incoming>,
+//! #     client: reqwest::Client,
+//! # ) -> Result<http::Response<reqwest::Body>, reqwest::Error> {
+//! // Point the incoming request at the upstream service...
+//! let (mut parts, body) = req.into_parts();
+//! let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+//! parts.uri = format!("http://upstream.local{path}").parse().unwrap();
+//!
+//! // ...and forward it.
+//! let res = reqwest::proxy_util::forward(http::Request::from_parts(parts, body), &client).await?;
+//! # Ok(res)
+//! # }
+//! ```
+//!
+//! # Optional
+//!
+//! This requires the optional `proxy-util` feature to be enabled.
+
+use bytes::Bytes;
+use http::header::{
+    HeaderMap, HeaderName, CONNECTION, HOST, TE, TRAILER, TRANSFER_ENCODING, UPGRADE,
+};
+use http::{Request as HttpRequest, Response as HttpResponse, StatusCode};
+use log::debug;
+
+use crate::error::BoxError;
+use crate::{Body, Client, Request};
+
+static HOP_BY_HOP: [HeaderName; 8] = [
+    CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    HeaderName::from_static("proxy-connection"),
+    http::header::PROXY_AUTHENTICATE,
+    http::header::PROXY_AUTHORIZATION,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+];
+
+/// Forward an incoming request to its upstream, and return the upstream
+/// response so it can be sent back to the original client.
+///
+/// The URI of `req` must be absolute, and is where the request is sent. A
+/// server usually receives only a path, so rewrite the URI before calling
+/// this.
+///
+/// Both the request and response bodies are streamed. Hop-by-hop headers
+/// (`Connection`, any header named by `Connection`, `Keep-Alive`, `TE`,
+/// `Trailer`, `Transfer-Encoding`, and the proxy headers) are removed in both
+/// directions, as is the incoming `Host` header so the upstream receives its
+/// own.
+///
+/// If the request asks for an `Upgrade` and the upstream agrees with a
+/// `101 Switching Protocols`, a task is spawned that copies bytes between the
+/// two upgraded connections until either side closes. The incoming request
+/// must therefore come from a hyper server connection that supports upgrades.
+///
+/// # Note
+///
+/// The passed `Client` is used as is. For a transparent proxy, it should
+/// usually be built with [`redirect::Policy::none()`](crate::redirect::Policy::none)
+/// and automatic decompression disabled, so that redirects and encoded
+/// bodies are passed through untouched.
+///
+/// # Errors
+///
+/// This method fails if the URI of `req` is not an absolute `http` or `https`
+/// URL, or if there was an error while sending the request upstream.
+pub async fn forward<B>(
+    mut req: HttpRequest<B>,
+    client: &Client,
+) -> crate::Result<HttpResponse<Body>>
+where
+    B: http_body::Body + Send + Sync + 'static,
+    B::Data: Into<Bytes>,
+    B::Error: Into<BoxError>,
+{
+    let upgrade = upgrade_requested(req.headers());
+    let on_upgrade = if upgrade.is_some() {
+        Some(hyper::upgrade::on(&mut req))
+    } else {
+        None
+    };
+
+    let (mut parts, body) = req.into_parts();
+    remove_hop_by_hop_headers(&mut parts.headers);
+    parts.headers.remove(HOST);
+    if let Some(ref protocol) = upgrade {
+        parts
+            .headers
+            .insert(CONNECTION, http::HeaderValue::from_static("upgrade"));
+        parts.headers.insert(UPGRADE, protocol.clone());
+    }
+
+    let url = crate::Url::parse(&parts.uri.to_string()).map_err(crate::error::builder)?;
+    let mut outbound = Request::new(parts.method, url);
+    *outbound.headers_mut() = parts.headers;
+    *outbound.body_mut() = Some(Body::wrap(body));
+
+    let res = client.execute(outbound).await?;
+
+    if res.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(on_upgrade) = on_upgrade {
+            let mut headers = res.headers().clone();
+            let protocol = headers.remove(UPGRADE);
+            remove_hop_by_hop_headers(&mut headers);
+
+            let mut builder = HttpResponse::builder()
+                .status(StatusCode::SWITCHING_PROTOCOLS)
+                .header(CONNECTION, "upgrade");
+            if let Some(protocol) = protocol {
+                builder = builder.header(UPGRADE, protocol);
+            }
+            let mut downstream = builder.body(Body::empty()).expect("valid response parts");
+            downstream.headers_mut().extend(headers);
+
+            tokio::spawn(async move {
+                let mut upstream = match res.upgrade().await {
+                    Ok(upgraded) => upgraded,
+                    Err(err) => {
+                        debug!("proxy upstream upgrade failed: {err}");
+                        return;
+                    }
+                };
+                let mut incoming = match on_upgrade.await {
+                    Ok(upgraded) => hyper_util::rt::TokioIo::new(upgraded),
+                    Err(err) => {
+                        debug!("proxy downstream upgrade failed: {err}");
+                        return;
+                    }
+                };
+                if let Err(err) = tokio::io::copy_bidirectional(&mut incoming, &mut upstream).await
+                {
+                    debug!("proxy upgraded connection error: {err}");
+                }
+            });
+
+            return Ok(downstream);
+        }
+    }
+
+    let mut res: HttpResponse<Body> = res.into();
+    remove_hop_by_hop_headers(res.headers_mut());
+    Ok(res)
+}
+
+/// Returns the requested `Upgrade` protocol, if `Connection: upgrade` was sent.
+fn upgrade_requested(headers: &HeaderMap) -> Option<http::HeaderValue> {
+    let wants_upgrade = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+
+    if wants_upgrade {
+        headers.get(UPGRADE).cloned()
+    } else {
+        None
+    }
+}
+
+fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    // Any header named in `Connection` is also hop-by-hop.
+    let named = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|token| HeaderName::from_bytes(token.trim().as_bytes()).ok())
+        .collect::<Vec<_>>();
+
+    for name in named {
+        headers.remove(name);
+    }
+
+    for name in HOP_BY_HOP.iter() {
+        headers.remove(name);
+    }
+    headers.remove(UPGRADE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_hop_by_hop_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, "keep-alive, x-private".parse().unwrap());
+        headers.insert("keep-alive", "timeout=5".parse().unwrap());
+        headers.insert("x-private", "secret".parse().unwrap());
+        headers.insert(TE, "trailers".parse().unwrap());
+        headers.insert(TRANSFER_ENCODING, "chunked".parse().unwrap());
+        headers.insert("x-keep", "yes".parse().unwrap());
+
+        remove_hop_by_hop_headers(&mut headers);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-keep"], "yes");
+    }
+
+    #[test]
+    fn upgrade_requires_connection_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert(UPGRADE, "websocket".parse().unwrap());
+        assert_eq!(upgrade_requested(&headers), None);
+
+        headers.insert(CONNECTION, "keep-alive, Upgrade".parse().unwrap());
+        assert_eq!(upgrade_requested(&headers).unwrap(), "websocket");
+    }
+}
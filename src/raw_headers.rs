@@ -0,0 +1,276 @@
+//! Raw response headers
+//!
+//! A [`HeaderMap`](crate::header::HeaderMap) lowercases header names, and
+//! groups the values of each name together, so the order and casing that a
+//! server used are lost. For debugging protocols, analyzing fingerprints, or
+//! proxies that forward headers unchanged, a `Client` built with
+//! [`ClientBuilder::http1_preserve_raw_headers()`](crate::ClientBuilder::http1_preserve_raw_headers)
+//! also records the header lines of each HTTP/1 response as they were
+//! received. They are available from
+//! [`Response::raw_headers()`](crate::Response::raw_headers).
+//!
+//! HTTP/2 and HTTP/3 header names are always lowercase, and their responses
+//! don't have raw headers.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), reqwest::Error> {
+//! let client = reqwest::Client::builder()
+//!     .http1_preserve_raw_headers(true)
+//!     .build()?;
+//! let res = client.get("http://httpbin.org/get").send().await?;
+//! if let Some(raw) = res.raw_headers() {
+//!     for (name, value) in raw.iter() {
+//!         println!("{name}: {value:?}");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use http::header::HeaderValue;
+
+/// Response heads larger than this aren't recorded.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// The header lines of a response, in the order and casing they were
+/// received.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RawHeaders {
+    lines: Vec<(String, HeaderValue)>,
+}
+
+/// Where a connection puts the raw headers of its latest response. Added to
+/// the extras of the connection, so that the response can take them.
+#[derive(Clone, Default)]
+pub(crate) struct Recorded(Arc<Mutex<Option<RawHeaders>>>);
+
+/// Finds the response heads in the bytes read from a connection.
+pub(crate) struct Recorder {
+    recorded: Recorded,
+    state: State,
+}
+
+enum State {
+    /// Reading a response head.
+    Head(Vec<u8>),
+    /// Reading a response body, or bytes that aren't HTTP/1.
+    Body,
+}
+
+// ===== impl RawHeaders =====
+
+impl RawHeaders {
+    /// An iterator over the header lines, as names and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &HeaderValue)> + '_ {
+        self.lines.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// The number of header lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns true if there are no header lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+// ===== impl Recorder =====
+
+impl Recorder {
+    pub(crate) fn new() -> Recorder {
+        Recorder {
+            recorded: Recorded::default(),
+            state: State::Head(Vec::new()),
+        }
+    }
+
+    pub(crate) fn recorded(&self) -> Recorded {
+        self.recorded.clone()
+    }
+
+    /// Bytes were read from the connection.
+    pub(crate) fn read(&mut self, bytes: &[u8]) {
+        match self.state {
+            State::Head(ref mut buf) => buf.extend_from_slice(bytes),
+            State::Body => return,
+        }
+
+        while let State::Head(ref mut buf) = self.state {
+            let is_http = if buf.len() < 5 {
+                b"HTTP/".starts_with(buf)
+            } else {
+                buf.starts_with(b"HTTP/")
+            };
+            if !is_http {
+                self.state = State::Body;
+                return;
+            }
+
+            let end = match find_head_end(buf) {
+                Some(end) => end,
+                None => {
+                    if buf.len() > MAX_HEAD_SIZE {
+                        self.state = State::Body;
+                    }
+                    return;
+                }
+            };
+            let rest = buf.split_off(end);
+            match parse_head(buf) {
+                // an informational response, the final one follows
+                Some((status, _)) if (100..200).contains(&status) && status != 101 => {
+                    self.state = State::Head(rest);
+                }
+                Some((_, headers)) => {
+                    *self.recorded.0.lock().unwrap() = Some(headers);
+                    self.state = State::Body;
+                }
+                None => self.state = State::Body,
+            }
+        }
+    }
+
+    /// Bytes were written to the connection, so the next bytes read are the
+    /// head of the response to a new request.
+    pub(crate) fn write(&mut self) {
+        if let State::Body = self.state {
+            self.state = State::Head(Vec::new());
+        }
+    }
+}
+
+/// Moves the raw headers recorded by the connection of `res` into its
+/// extensions.
+pub(crate) fn record_response<B>(res: &mut http::Response<B>) {
+    if let Some(Recorded(recorded)) = res.extensions_mut().remove::<Recorded>() {
+        if let Some(headers) = recorded.lock().unwrap().take() {
+            res.extensions_mut().insert(headers);
+        }
+    }
+}
+
+/// The length of the head at the start of `buf`, up to the empty line.
+fn find_head_end(buf: &[u8]) -> Option<usize> {
+    for (i, &b) in buf.iter().enumerate() {
+        if b == b'\n' {
+            match buf[i + 1..] {
+                [b'\n', ..] => return Some(i + 2),
+                [b'\r', b'\n', ..] => return Some(i + 3),
+                _ => (),
+            }
+        }
+    }
+    None
+}
+
+/// Parses the status code and header lines of a response head.
+///
+/// Malformed header lines are skipped.
+fn parse_head(head: &[u8]) -> Option<(u16, RawHeaders)> {
+    let mut lines = head
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+
+    let status = lines.next()?.split(|&b| b == b' ').nth(1)?;
+    let status = std::str::from_utf8(status).ok()?.parse().ok()?;
+
+    let mut headers = RawHeaders::default();
+    for line in lines {
+        let colon = match line.iter().position(|&b| b == b':') {
+            Some(colon) => colon,
+            None => continue,
+        };
+        let name = match std::str::from_utf8(&line[..colon]) {
+            Ok(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+        let value = trim_whitespace(&line[colon + 1..]);
+        if let Ok(value) = HeaderValue::from_bytes(value) {
+            headers.lines.push((name.to_owned(), value));
+        }
+    }
+    Some((status, headers))
+}
+
+fn trim_whitespace(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(recorder: &Recorder) -> Option<Vec<(String, String)>> {
+        let headers = recorder.recorded.0.lock().unwrap().take()?;
+        Some(
+            headers
+                .iter()
+                .map(|(name, value)| (name.to_owned(), value.to_str().unwrap().to_owned()))
+                .collect(),
+        )
+    }
+
+    fn lines(lines: &[(&str, &str)]) -> Vec<(String, String)> {
+        lines
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn records_order_and_case() {
+        let mut recorder = Recorder::new();
+        recorder.read(b"HTTP/1.1 200 OK\r\nX-B: 1\r\ncontent-Length:  2 \r\n");
+        assert_eq!(recorded(&recorder), None);
+        recorder.read(b"X-a: 2\r\nX-B: 3\r\n\r\nhi");
+        assert_eq!(
+            recorded(&recorder),
+            Some(lines(&[
+                ("X-B", "1"),
+                ("content-Length", "2"),
+                ("X-a", "2"),
+                ("X-B", "3")
+            ]))
+        );
+
+        // the body isn't parsed, until the next request
+        recorder.read(b"HTTP/1.1 200 OK\r\nX: 1\r\n\r\n");
+        assert_eq!(recorded(&recorder), None);
+        recorder.write();
+        recorder.read(b"HTTP/1.1 204 No Content\nServer: a\n\n");
+        assert_eq!(recorded(&recorder), Some(lines(&[("Server", "a")])));
+    }
+
+    #[test]
+    fn skips_informational() {
+        let mut recorder = Recorder::new();
+        recorder.read(b"HTTP/1.1 100 Continue\r\nX-Early: 1\r\n\r\nHTTP/1.1 200 OK\r\n");
+        assert_eq!(recorded(&recorder), None);
+        recorder.read(b"X-Final: 1\r\n\r\n");
+        assert_eq!(recorded(&recorder), Some(lines(&[("X-Final", "1")])));
+    }
+
+    #[test]
+    fn ignores_other_bytes() {
+        let mut recorder = Recorder::new();
+        recorder.read(b"\x00\x00\x12\x04\x00\x00\x00\x00\x00\r\n\r\n");
+        assert_eq!(recorded(&recorder), None);
+        recorder.write();
+        recorder.read(b"HTTP/1.1 200 OK\r\nX: ");
+        recorder.read(&[b'a'; MAX_HEAD_SIZE]);
+        recorder.read(b"\r\n\r\n");
+        assert_eq!(recorded(&recorder), None);
+    }
+}
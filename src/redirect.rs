@@ -6,9 +6,14 @@
 
 use std::error::Error as StdError;
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
 
-use crate::header::{HeaderMap, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, WWW_AUTHENTICATE};
-use hyper::StatusCode;
+use crate::header::{
+    HeaderMap, HeaderValue, AUTHORIZATION, COOKIE, HOST, PROXY_AUTHORIZATION, WWW_AUTHENTICATE,
+};
+use hyper::{Method, StatusCode};
 
 use crate::Url;
 
@@ -17,10 +22,16 @@ use crate::Url;
 /// The default value will catch redirect loops, and has a maximum of 10
 /// redirects it will follow in a chain before returning an error.
 ///
+/// A loop is a redirect to a request that was already made in the chain: the
+/// same method and URL, sent with the same cookies. Revisiting a URL after a
+/// cookie was set, as login flows do, is not a loop.
+///
 /// - `limited` can be used have the same as the default behavior, but adjust
 ///   the allowed maximum redirect hops in a chain.
 /// - `none` can be used to disable all redirect behavior.
 /// - `custom` can be used to create a customized policy.
+/// - `custom_async` can be used to create a policy that inspects the
+///   response headers, or changes the headers of the next request.
 pub struct Policy {
     inner: PolicyKind,
 }
@@ -32,12 +43,81 @@ pub struct Attempt<'a> {
     status: StatusCode,
     next: &'a Url,
     previous: &'a [Url],
+    chain: Option<Chain<'a>>,
+}
+
+/// The information given to a policy created with [`Policy::custom_async()`].
+///
+/// Unlike [`Attempt`], it owns its data, so that it can be moved into the
+/// returned future, and gives access to the headers of the redirect response
+/// and of the next request.
+pub struct AsyncAttempt {
+    status: StatusCode,
+    next: Url,
+    previous: Vec<Url>,
+    method: Method,
+    response_headers: HeaderMap,
+    headers: HeaderMap,
+}
+
+/// A request that was made while following a redirect chain.
+#[derive(Clone)]
+pub struct Hop {
+    method: Method,
+    url: Url,
+    status: StatusCode,
+    // A hash of the `Cookie` header that was sent, so that loops can be told
+    // apart from flows that revisit a URL once a cookie is set.
+    cookie: Option<u64>,
+}
+
+/// A `Retry-After` delay that was waited for before sending a request.
+///
+/// See [`ClientBuilder::retry_after()`](crate::ClientBuilder::retry_after).
+#[derive(Clone)]
+pub struct Wait {
+    url: Url,
+    status: StatusCode,
+    duration: Duration,
+}
+
+/// The error returned when the default `Policy` stops following redirects.
+///
+/// It is the source of the [`Error`](crate::Error) returned for the request,
+/// and records every request made in the chain, which helps to debug
+/// misconfigured redirects, such as those of authentication flows.
+///
+/// # Example
+///
+/// ```
+/// # async fn run() {
+/// use std::error::Error as _;
+///
+/// let res = reqwest::get("https://example.com/loop").await;
+/// if let Err(err) = res {
+///     if let Some(chain) = err
+///         .source()
+///         .and_then(|e| e.downcast_ref::<reqwest::redirect::ChainError>())
+///     {
+///         for hop in chain.hops() {
+///             eprintln!("{} {} -> {}", hop.method(), hop.url(), hop.status());
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct ChainError {
+    kind: ChainErrorKind,
+    hops: Vec<Hop>,
+    next: Url,
 }
 
 /// An action to perform when a redirect status code is found.
 #[derive(Debug)]
 pub struct Action {
     inner: ActionKind,
+    // The headers of the next request, set by an `AsyncAttempt`.
+    headers: Option<HeaderMap>,
 }
 
 impl Policy {
@@ -104,6 +184,61 @@ impl Policy {
         }
     }
 
+    /// Create a custom `Policy` using the passed async function.
+    ///
+    /// This is like [`Policy::custom()`], but the function can wait, for
+    /// instance to fetch a new token, and is given an [`AsyncAttempt`],
+    /// which has access to the headers of the redirect response, and can
+    /// add or remove headers of the next request.
+    ///
+    /// Sensitive headers, such as `Authorization`, have already been
+    /// removed from the next request if it goes to a different host. The
+    /// policy can add them back, for instance after signing the request for
+    /// the new host.
+    ///
+    /// # Note
+    ///
+    /// Like the custom variant, this doesn't limit the length of the redirect
+    /// chain, or catch loops. The function should have some way of handling
+    /// those.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use reqwest::{Error, redirect};
+    /// #
+    /// # async fn sign(url: &reqwest::Url) -> reqwest::header::HeaderValue {
+    /// #     reqwest::header::HeaderValue::from_static("Bearer token")
+    /// # }
+    /// # fn run() -> Result<(), Error> {
+    /// let custom = redirect::Policy::custom_async(|mut attempt| async move {
+    ///     if attempt.previous().len() > 5 {
+    ///         return attempt.error("too many redirects");
+    ///     }
+    ///     if attempt.url().host_str() == Some("files.example.domain") {
+    ///         let auth = sign(attempt.url()).await;
+    ///         attempt
+    ///             .headers_mut()
+    ///             .insert(reqwest::header::AUTHORIZATION, auth);
+    ///     }
+    ///     attempt.follow()
+    /// });
+    /// let client = reqwest::Client::builder()
+    ///     .redirect(custom)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn custom_async<T, F>(policy: T) -> Self
+    where
+        T: Fn(AsyncAttempt) -> F + Send + Sync + 'static,
+        F: Future<Output = Action> + Send + 'static,
+    {
+        Self {
+            inner: PolicyKind::Async(Box::new(move |attempt| Box::pin(policy(attempt)))),
+        }
+    }
+
     /// Apply this policy to a given [`Attempt`] to produce a [`Action`].
     ///
     /// # Note
@@ -127,24 +262,49 @@ impl Policy {
     pub fn redirect(&self, attempt: Attempt) -> Action {
         match self.inner {
             PolicyKind::Custom(ref custom) => custom(attempt),
-            PolicyKind::Limit(max) => {
-                if attempt.previous.len() >= max {
-                    attempt.error(TooManyRedirects)
-                } else {
-                    attempt.follow()
-                }
-            }
+            PolicyKind::Async(_) => attempt.error("an async redirect policy can't be applied to an Attempt"),
+            PolicyKind::Limit(max) => attempt.limited(max),
             PolicyKind::None => attempt.stop(),
         }
     }
 
-    pub(crate) fn check(&self, status: StatusCode, next: &Url, previous: &[Url]) -> ActionKind {
-        self.redirect(Attempt {
+    pub(crate) fn check(
+        &self,
+        status: StatusCode,
+        next: &Url,
+        previous: &[Url],
+        chain: Option<Chain<'_>>,
+    ) -> ActionKind {
+        let max = chain.as_ref().and_then(|chain| chain.max);
+        let attempt = Attempt {
             status,
             next,
             previous,
-        })
-        .inner
+            chain,
+        };
+        // a maximum set on the request replaces the one of the policy, and
+        // caps custom policies
+        match (&self.inner, max) {
+            (PolicyKind::Limit(_), Some(max)) => attempt.limited(max).inner,
+            (PolicyKind::Custom(_) | PolicyKind::Async(_), Some(max)) if previous.len() >= max => {
+                let err = attempt.chain_error(ChainErrorKind::TooManyRedirects);
+                attempt.error(err).inner
+            }
+            _ => self.redirect(attempt).inner,
+        }
+    }
+
+    /// Starts an async policy, if this is one.
+    ///
+    /// The request's maximum number of redirects is checked by `check`.
+    pub(crate) fn check_async<F>(&self, attempt: F) -> Option<PendingAction>
+    where
+        F: FnOnce() -> AsyncAttempt,
+    {
+        match self.inner {
+            PolicyKind::Async(ref policy) => Some(policy(attempt())),
+            _ => None,
+        }
     }
 
     pub(crate) fn is_default(&self) -> bool {
@@ -178,6 +338,119 @@ impl<'a> Attempt<'a> {
     pub fn follow(self) -> Action {
         Action {
             inner: ActionKind::Follow,
+            headers: None,
+        }
+    }
+
+    /// Returns an action meaning reqwest should not follow the next URL.
+    ///
+    /// The 30x response will be returned as the `Ok` result.
+    pub fn stop(self) -> Action {
+        Action {
+            inner: ActionKind::Stop,
+            headers: None,
+        }
+    }
+
+    /// Returns an action failing the redirect with an error.
+    ///
+    /// The `Error` will be returned for the result of the sent request.
+    pub fn error<E: Into<Box<dyn StdError + Send + Sync>>>(self, error: E) -> Action {
+        Action {
+            inner: ActionKind::Error(error.into()),
+            headers: None,
+        }
+    }
+
+    fn limited(self, max: usize) -> Action {
+        if self.previous.len() >= max {
+            let err = self.chain_error(ChainErrorKind::TooManyRedirects);
+            self.error(err)
+        } else if self.is_loop() {
+            let err = self.chain_error(ChainErrorKind::Loop);
+            self.error(err)
+        } else {
+            self.follow()
+        }
+    }
+
+    fn is_loop(&self) -> bool {
+        self.chain.as_ref().map_or(false, |chain| {
+            chain.hops.iter().any(|hop| {
+                hop.method == *chain.method && hop.url == *self.next && hop.cookie == chain.cookie
+            })
+        })
+    }
+
+    fn chain_error(&self, kind: ChainErrorKind) -> ChainError {
+        ChainError {
+            kind,
+            hops: self.chain.as_ref().map_or(Vec::new(), |c| c.hops.to_vec()),
+            next: self.next.clone(),
+        }
+    }
+}
+
+impl AsyncAttempt {
+    pub(crate) fn new(
+        status: StatusCode,
+        next: Url,
+        previous: Vec<Url>,
+        method: Method,
+        response_headers: HeaderMap,
+        headers: HeaderMap,
+    ) -> AsyncAttempt {
+        AsyncAttempt {
+            status,
+            next,
+            previous,
+            method,
+            response_headers,
+            headers,
+        }
+    }
+
+    /// Get the type of redirect.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the next URL to redirect to.
+    pub fn url(&self) -> &Url {
+        &self.next
+    }
+
+    /// Get the list of previous URLs that have already been requested in this chain.
+    pub fn previous(&self) -> &[Url] {
+        &self.previous
+    }
+
+    /// Get the method of the next request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Get the headers of the redirect response.
+    pub fn response_headers(&self) -> &HeaderMap {
+        &self.response_headers
+    }
+
+    /// Get the headers of the next request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get a mutable reference to the headers of the next request.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Returns an action meaning reqwest should follow the next URL, with
+    /// the headers of this attempt.
+    pub fn follow(self) -> Action {
+        Action {
+            inner: ActionKind::Follow,
+            headers: Some(self.headers),
         }
     }
 
@@ -187,6 +460,7 @@ impl<'a> Attempt<'a> {
     pub fn stop(self) -> Action {
         Action {
             inner: ActionKind::Stop,
+            headers: None,
         }
     }
 
@@ -196,12 +470,171 @@ impl<'a> Attempt<'a> {
     pub fn error<E: Into<Box<dyn StdError + Send + Sync>>>(self, error: E) -> Action {
         Action {
             inner: ActionKind::Error(error.into()),
+            headers: None,
+        }
+    }
+}
+
+impl fmt::Debug for AsyncAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncAttempt")
+            .field("status", &self.status)
+            .field("next", &self.next.as_str())
+            .field("previous", &self.previous)
+            .field("method", &self.method)
+            .field("response_headers", &self.response_headers)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl Action {
+    pub(crate) fn into_parts(self) -> (ActionKind, Option<HeaderMap>) {
+        (self.inner, self.headers)
+    }
+}
+
+impl Hop {
+    pub(crate) fn new(method: Method, url: Url, status: StatusCode, headers: &HeaderMap) -> Hop {
+        Hop {
+            method,
+            url,
+            status,
+            cookie: cookie_fingerprint(headers),
         }
     }
+
+    /// Get the method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Get the URL of the request.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the redirect status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl fmt::Debug for Hop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hop")
+            .field("method", &self.method)
+            .field("url", &self.url.as_str())
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+impl Wait {
+    pub(crate) fn new(url: Url, status: StatusCode, duration: Duration) -> Wait {
+        Wait {
+            url,
+            status,
+            duration,
+        }
+    }
+
+    /// Get the URL of the response that asked to wait.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the status of the response that asked to wait.
+    ///
+    /// A redirect status if the redirect was followed after waiting, or
+    /// `429` or `503` if the request was sent again.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get how long the client waited.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl fmt::Debug for Wait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Wait")
+            .field("url", &self.url.as_str())
+            .field("status", &self.status)
+            .field("duration", &self.duration)
+            .finish()
+    }
+}
+
+/// Parses a `Retry-After` header, either a number of seconds or a date.
+///
+/// A date in the past means no delay.
+pub(crate) fn parse_retry_after(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        return Some(Duration::from_secs(value.parse().unwrap_or(u64::MAX)));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+impl ChainError {
+    /// Returns true if a redirect loop was detected.
+    pub fn is_loop(&self) -> bool {
+        matches!(self.kind, ChainErrorKind::Loop)
+    }
+
+    /// Returns true if the maximum number of redirects was reached.
+    pub fn is_too_many_redirects(&self) -> bool {
+        matches!(self.kind, ChainErrorKind::TooManyRedirects)
+    }
+
+    /// Get the requests made in the chain, in order.
+    pub fn hops(&self) -> &[Hop] {
+        &self.hops
+    }
+
+    /// Get the URL of the redirect that was not followed.
+    pub fn next(&self) -> &Url {
+        &self.next
+    }
+}
+
+impl fmt::Debug for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChainError")
+            .field("kind", &self.kind)
+            .field("hops", &self.hops)
+            .field("next", &self.next.as_str())
+            .finish()
+    }
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ChainErrorKind::TooManyRedirects => f.write_str("too many redirects"),
+            ChainErrorKind::Loop => write!(f, "redirect loop to {}", self.next),
+        }
+    }
+}
+
+impl StdError for ChainError {}
+
+#[derive(Debug)]
+enum ChainErrorKind {
+    TooManyRedirects,
+    Loop,
 }
 
 enum PolicyKind {
     Custom(Box<dyn Fn(Attempt) -> Action + Send + Sync + 'static>),
+    Async(Box<dyn Fn(AsyncAttempt) -> PendingAction + Send + Sync + 'static>),
     Limit(usize),
     None,
 }
@@ -216,6 +649,7 @@ impl fmt::Debug for PolicyKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             PolicyKind::Custom(..) => f.pad("Custom"),
+            PolicyKind::Async(..) => f.pad("Async"),
             PolicyKind::Limit(max) => f.debug_tuple("Limit").field(&max).finish(),
             PolicyKind::None => f.pad("None"),
         }
@@ -224,6 +658,22 @@ impl fmt::Debug for PolicyKind {
 
 // pub(crate)
 
+/// The state of the redirect chain a `Policy` is applied to.
+#[derive(Debug)]
+pub(crate) struct Chain<'a> {
+    /// The requests made so far.
+    pub(crate) hops: &'a [Hop],
+    /// The method of the next request.
+    pub(crate) method: &'a Method,
+    /// The cookie fingerprint of the next request.
+    pub(crate) cookie: Option<u64>,
+    /// The maximum set with `RequestBuilder::max_redirects()`.
+    pub(crate) max: Option<usize>,
+}
+
+/// The future returned by an async policy.
+pub(crate) type PendingAction = Pin<Box<dyn Future<Output = Action> + Send>>;
+
 #[derive(Debug)]
 pub(crate) enum ActionKind {
     Follow,
@@ -241,20 +691,29 @@ pub(crate) fn remove_sensitive_headers(headers: &mut HeaderMap, next: &Url, prev
             headers.remove("cookie2");
             headers.remove(PROXY_AUTHORIZATION);
             headers.remove(WWW_AUTHENTICATE);
+            // set for the previous host, with `RequestBuilder::host_header()`
+            headers.remove(HOST);
         }
     }
 }
 
-#[derive(Debug)]
-struct TooManyRedirects;
+pub(crate) fn cookie_fingerprint(headers: &HeaderMap) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
 
-impl fmt::Display for TooManyRedirects {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("too many redirects")
+    let mut cookies = headers.get_all(COOKIE).iter().peekable();
+    cookies.peek()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for cookie in cookies {
+        cookie.as_bytes().hash(&mut hasher);
     }
+    Some(hasher.finish())
 }
 
-impl StdError for TooManyRedirects {}
+#[cfg(test)]
+fn is_too_many_redirects(err: &(dyn StdError + Send + Sync + 'static)) -> bool {
+    err.downcast_ref::<ChainError>()
+        .map_or(false, ChainError::is_too_many_redirects)
+}
 
 #[test]
 fn test_redirect_policy_limit() {
@@ -264,15 +723,15 @@ fn test_redirect_policy_limit() {
         .map(|i| Url::parse(&format!("http://a.b/c/{i}")).unwrap())
         .collect::<Vec<_>>();
 
-    match policy.check(StatusCode::FOUND, &next, &previous) {
+    match policy.check(StatusCode::FOUND, &next, &previous, None) {
         ActionKind::Follow => (),
         other => panic!("unexpected {other:?}"),
     }
 
     previous.push(Url::parse("http://a.b.d/e/33").unwrap());
 
-    match policy.check(StatusCode::FOUND, &next, &previous) {
-        ActionKind::Error(err) if err.is::<TooManyRedirects>() => (),
+    match policy.check(StatusCode::FOUND, &next, &previous, None) {
+        ActionKind::Error(err) if is_too_many_redirects(&*err) => (),
         other => panic!("unexpected {other:?}"),
     }
 }
@@ -283,8 +742,8 @@ fn test_redirect_policy_limit_to_0() {
     let next = Url::parse("http://x.y/z").unwrap();
     let previous = vec![Url::parse("http://a.b/c").unwrap()];
 
-    match policy.check(StatusCode::FOUND, &next, &previous) {
-        ActionKind::Error(err) if err.is::<TooManyRedirects>() => (),
+    match policy.check(StatusCode::FOUND, &next, &previous, None) {
+        ActionKind::Error(err) if is_too_many_redirects(&*err) => (),
         other => panic!("unexpected {other:?}"),
     }
 }
@@ -300,26 +759,85 @@ fn test_redirect_policy_custom() {
     });
 
     let next = Url::parse("http://bar/baz").unwrap();
-    match policy.check(StatusCode::FOUND, &next, &[]) {
+    match policy.check(StatusCode::FOUND, &next, &[], None) {
         ActionKind::Follow => (),
         other => panic!("unexpected {other:?}"),
     }
 
     let next = Url::parse("http://foo/baz").unwrap();
-    match policy.check(StatusCode::FOUND, &next, &[]) {
+    match policy.check(StatusCode::FOUND, &next, &[], None) {
         ActionKind::Stop => (),
         other => panic!("unexpected {other:?}"),
     }
 }
 
+#[test]
+fn test_redirect_policy_loop() {
+    use hyper::header::HeaderValue;
+
+    let policy = Policy::default();
+    let login = Url::parse("http://a.b/login").unwrap();
+    let callback = Url::parse("http://a.b/callback").unwrap();
+    let previous = vec![login.clone(), callback.clone()];
+
+    let mut headers = HeaderMap::new();
+    let hops = vec![
+        Hop::new(Method::GET, login.clone(), StatusCode::FOUND, &headers),
+        Hop::new(Method::GET, callback, StatusCode::FOUND, &headers),
+    ];
+
+    // the same request again
+    let chain = Chain {
+        hops: &hops,
+        method: &Method::GET,
+        cookie: None,
+        max: None,
+    };
+    match policy.check(StatusCode::FOUND, &login, &previous, Some(chain)) {
+        ActionKind::Error(err) => {
+            let err = err.downcast_ref::<ChainError>().unwrap();
+            assert!(err.is_loop());
+            assert_eq!(err.hops().len(), 2);
+            assert_eq!(err.next(), &login);
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+
+    // a cookie was set in between
+    headers.insert(COOKIE, HeaderValue::from_static("session=1"));
+    let chain = Chain {
+        hops: &hops,
+        method: &Method::GET,
+        cookie: cookie_fingerprint(&headers),
+        max: None,
+    };
+    match policy.check(StatusCode::FOUND, &login, &previous, Some(chain)) {
+        ActionKind::Follow => (),
+        other => panic!("unexpected {other:?}"),
+    }
+
+    // a different method
+    let chain = Chain {
+        hops: &hops,
+        method: &Method::POST,
+        cookie: None,
+        max: None,
+    };
+    match policy.check(StatusCode::TEMPORARY_REDIRECT, &login, &previous, Some(chain)) {
+        ActionKind::Follow => (),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
 #[test]
 fn test_remove_sensitive_headers() {
-    use hyper::header::{HeaderValue, ACCEPT, AUTHORIZATION, COOKIE};
+    use hyper::header::{HeaderValue, ACCEPT, AUTHORIZATION, COOKIE, HOST};
 
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
     headers.insert(AUTHORIZATION, HeaderValue::from_static("let me in"));
     headers.insert(COOKIE, HeaderValue::from_static("foo=bar"));
+    headers.insert(HOST, HeaderValue::from_static("internal.example"));
 
     let next = Url::parse("http://initial-domain.com/path").unwrap();
     let mut prev = vec![Url::parse("http://initial-domain.com/new_path").unwrap()];
@@ -331,7 +849,23 @@ fn test_remove_sensitive_headers() {
     prev.push(Url::parse("http://new-domain.com/path").unwrap());
     filtered_headers.remove(AUTHORIZATION);
     filtered_headers.remove(COOKIE);
+    filtered_headers.remove(HOST);
 
     remove_sensitive_headers(&mut headers, &next, &prev);
     assert_eq!(headers, filtered_headers);
 }
+
+#[test]
+fn test_parse_retry_after() {
+    let parse = |s: &'static str| parse_retry_after(&HeaderValue::from_static(s));
+
+    assert_eq!(parse("120"), Some(Duration::from_secs(120)));
+    assert_eq!(parse(" 0 "), Some(Duration::ZERO));
+    assert_eq!(parse("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    assert_eq!(parse("-1"), None);
+    assert_eq!(parse("soon"), None);
+
+    let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600));
+    let delay = parse_retry_after(&HeaderValue::from_str(&later).unwrap()).unwrap();
+    assert!(delay > Duration::from_secs(3500) && delay <= Duration::from_secs(3600));
+}
@@ -0,0 +1,228 @@
+//! Server Timing
+//!
+//! Servers can report metrics about how a request was handled, like the time
+//! spent in a database or a cache hit, in the `Server-Timing` header
+//! described by the [W3C Server Timing] specification:
+//!
+//! ```text
+//! Server-Timing: db;dur=53.2, cache;desc="Cache Read";dur=23.2, miss
+//! ```
+//!
+//! [`Response::server_timing()`](crate::Response::server_timing) parses these
+//! headers into [`Metric`]s.
+//!
+//! [W3C Server Timing]: https://www.w3.org/TR/server-timing/
+
+use std::time::Duration;
+
+use http::header::{HeaderMap, HeaderName};
+
+/// The `Server-Timing` header name.
+const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+/// A single metric parsed from a `Server-Timing` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metric {
+    name: String,
+    params: Vec<(String, String)>,
+}
+
+// ===== impl Metric =====
+
+impl Metric {
+    /// The name of the metric.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The duration of the metric, from its `dur` parameter in milliseconds.
+    ///
+    /// Returns `None` if there's no duration, or it isn't a valid number.
+    pub fn duration(&self) -> Option<Duration> {
+        let ms = self.param("dur")?.parse::<f64>().ok()?;
+        let secs = ms / 1000.0;
+        if secs.is_finite() && secs >= 0.0 && secs < u64::MAX as f64 {
+            Some(Duration::from_secs_f64(secs))
+        } else {
+            None
+        }
+    }
+
+    /// The value of the `desc` parameter, if present.
+    pub fn description(&self) -> Option<&str> {
+        self.param("desc")
+    }
+
+    /// Get the value of a parameter of the metric.
+    ///
+    /// Parameter names are compared case-insensitively. If a parameter
+    /// occurs more than once, the first occurrence is returned.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parse all `Server-Timing` headers.
+///
+/// Malformed metrics are skipped.
+pub(crate) fn parse(headers: &HeaderMap) -> Vec<Metric> {
+    let mut metrics = Vec::new();
+    for value in headers.get_all(SERVER_TIMING) {
+        if let Ok(value) = value.to_str() {
+            parse_value(value, &mut metrics);
+        }
+    }
+    metrics
+}
+
+fn parse_value(mut s: &str, metrics: &mut Vec<Metric>) {
+    loop {
+        s = s.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        if s.is_empty() {
+            return;
+        }
+
+        s = match parse_metric(s) {
+            Some((metric, rest)) => {
+                metrics.push(metric);
+                rest
+            }
+            None => skip_metric(s),
+        };
+    }
+}
+
+/// Parses one `name;param=value` metric, returning the rest of input.
+fn parse_metric(s: &str) -> Option<(Metric, &str)> {
+    let name_end = s.find(is_delimiter).unwrap_or(s.len());
+    if name_end == 0 {
+        return None;
+    }
+    let name = s[..name_end].to_owned();
+    let mut s = &s[name_end..];
+
+    let mut params = Vec::new();
+    loop {
+        s = s.trim_start();
+        match s.strip_prefix(';') {
+            Some(rest) => s = rest.trim_start(),
+            None => break,
+        }
+
+        let name_end = s.find(is_delimiter).unwrap_or(s.len());
+        let name = &s[..name_end];
+        s = s[name_end..].trim_start();
+
+        let value = if let Some(rest) = s.strip_prefix('=') {
+            let (value, rest) = parse_param_value(rest.trim_start())?;
+            s = rest;
+            value
+        } else {
+            String::new()
+        };
+
+        if !name.is_empty() {
+            params.push((name.to_ascii_lowercase(), value));
+        }
+    }
+
+    if !s.is_empty() && !s.starts_with(',') {
+        return None;
+    }
+
+    Some((Metric { name, params }, s))
+}
+
+fn parse_param_value(s: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((value, &quoted[i + 1..])),
+                '\\' => value.push(chars.next()?.1),
+                c => value.push(c),
+            }
+        }
+        // unterminated quoted-string
+        None
+    } else {
+        let end = s.find(is_delimiter).unwrap_or(s.len());
+        Some((s[..end].to_owned(), &s[end..]))
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c == '=' || c == ';' || c == ',' || c == '"' || c.is_ascii_whitespace()
+}
+
+/// Skip past a malformed metric, to the next comma outside of quotes.
+fn skip_metric(s: &str) -> &str {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => return &s[i..],
+            _ => (),
+        }
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(value: &'static str) -> Vec<Metric> {
+        let mut headers = HeaderMap::new();
+        headers.insert(SERVER_TIMING, value.parse().unwrap());
+        parse(&headers)
+    }
+
+    #[test]
+    fn parse_metrics() {
+        let metrics = metrics(r#"db;dur=53.2, cache;desc="Cache Read";dur=23.2, miss"#);
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[0].name(), "db");
+        assert_eq!(metrics[0].duration(), Some(Duration::from_micros(53200)));
+        assert_eq!(metrics[0].description(), None);
+        assert_eq!(metrics[1].name(), "cache");
+        assert_eq!(metrics[1].description(), Some("Cache Read"));
+        assert_eq!(metrics[1].duration(), Some(Duration::from_micros(23200)));
+        assert_eq!(metrics[2].name(), "miss");
+        assert_eq!(metrics[2].duration(), None);
+    }
+
+    #[test]
+    fn parse_quoting_and_params() {
+        let metrics = metrics(
+            r#"total ; DUR = 12 ; desc="a, \"quoted\"; value"; dur=99, edge;region=eu;dur=abc"#,
+        );
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].duration(), Some(Duration::from_millis(12)));
+        assert_eq!(metrics[0].description(), Some(r#"a, "quoted"; value"#));
+        assert_eq!(metrics[1].param("Region"), Some("eu"));
+        assert_eq!(metrics[1].duration(), None);
+    }
+
+    #[test]
+    fn parse_skips_malformed() {
+        let none = metrics(r#";dur=1, bad;desc="unterminated, ok;dur=-1, next;dur=2"#);
+        assert_eq!(none.len(), 0);
+
+        let metrics = metrics(r#"bad x;desc="a,b", ok;dur=-1, next;dur=2"#);
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].name(), "ok");
+        assert_eq!(metrics[0].duration(), None);
+        assert_eq!(metrics[1].duration(), Some(Duration::from_millis(2)));
+    }
+}
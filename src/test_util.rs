@@ -0,0 +1,192 @@
+//! Utilities for testing applications that use reqwest
+//!
+//! Timeouts and retries are hard to exercise against a fast local server.
+//! [`LatencyLayer`] simulates a slow network by delaying connection
+//! establishment, and can be added to a `Client` with
+//! [`ClientBuilder::connector_layer()`](crate::ClientBuilder::connector_layer).
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use reqwest::test_util::LatencyLayer;
+//!
+//! # #[cfg(not(feature = "rustls-tls-no-provider"))]
+//! let client = reqwest::Client::builder()
+//!     .connect_timeout(Duration::from_millis(500))
+//!     .connector_layer(
+//!         LatencyLayer::new(Duration::from_millis(200)).jitter(Duration::from_millis(100)),
+//!     )
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+//!
+//! # Optional
+//!
+//! This requires the optional `test-util` feature to be enabled.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use pin_project_lite::pin_project;
+use tokio::time::Sleep;
+use tower::Layer;
+use tower_service::Service;
+
+use crate::error::BoxError;
+
+/// A `Layer` that delays every call to the wrapped service.
+///
+/// Each call is delayed by the base latency plus a random amount of jitter,
+/// chosen uniformly between zero and the configured maximum.
+#[derive(Clone)]
+pub struct LatencyLayer {
+    latency: Duration,
+    jitter: Duration,
+}
+
+/// A `Service` that delays every call to the inner service.
+///
+/// Created by [`LatencyLayer`].
+#[derive(Clone)]
+pub struct Latency<S> {
+    inner: S,
+    latency: Duration,
+    jitter: Duration,
+}
+
+pin_project! {
+    /// The response future of [`Latency`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        response: F,
+        #[pin]
+        sleep: Sleep,
+    }
+}
+
+// ===== impl LatencyLayer =====
+
+impl LatencyLayer {
+    /// Creates a layer that delays each call by `latency`.
+    pub const fn new(latency: Duration) -> LatencyLayer {
+        LatencyLayer {
+            latency,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Adds up to `max` of random extra delay to each call.
+    pub const fn jitter(mut self, max: Duration) -> LatencyLayer {
+        self.jitter = max;
+        self
+    }
+}
+
+impl<S> Layer<S> for LatencyLayer {
+    type Service = Latency<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Latency {
+            inner,
+            latency: self.latency,
+            jitter: self.jitter,
+        }
+    }
+}
+
+impl fmt::Debug for LatencyLayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LatencyLayer")
+            .field("latency", &self.latency)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+// ===== impl Latency =====
+
+impl<S> Latency<S> {
+    fn delay(&self) -> Duration {
+        let jitter = self.jitter.as_nanos() as u64;
+        if jitter == 0 {
+            return self.latency;
+        }
+        self.latency + Duration::from_nanos(crate::util::fast_random() % (jitter + 1))
+    }
+}
+
+impl<S, Request> Service<Request> for Latency<S>
+where
+    S: Service<Request>,
+    S::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        ResponseFuture {
+            sleep: tokio::time::sleep(self.delay()),
+            response: self.inner.call(req),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Latency<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Latency")
+            .field("latency", &self.latency)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<F, T, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<BoxError>,
+{
+    type Output = Result<T, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        futures_core::ready!(this.sleep.poll(cx));
+        this.response.poll(cx).map_err(Into::into)
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_within_jitter() {
+        let svc = LatencyLayer::new(Duration::from_millis(10))
+            .jitter(Duration::from_millis(5))
+            .layer(());
+        for _ in 0..100 {
+            let delay = svc.delay();
+            assert!(delay >= Duration::from_millis(10));
+            assert!(delay <= Duration::from_millis(15));
+        }
+
+        let svc = LatencyLayer::new(Duration::from_millis(10)).layer(());
+        assert_eq!(svc.delay(), Duration::from_millis(10));
+    }
+}
@@ -53,11 +53,20 @@ use rustls::{
 };
 #[cfg(feature = "__rustls")]
 use rustls_pki_types::{ServerName, UnixTime};
+#[cfg(feature = "__rustls")]
+use std::sync::Arc;
 use std::{
     fmt,
     io::{BufRead, BufReader},
 };
 
+#[cfg(feature = "__rustls")]
+mod ct;
+#[cfg(feature = "rustls-tls-ocsp")]
+mod ocsp;
+#[cfg(feature = "__rustls")]
+mod x509;
+
 /// Represents a X509 certificate revocation list.
 #[cfg(feature = "__rustls")]
 pub struct CertificateRevocationList {
@@ -98,6 +107,11 @@ enum ClientCert {
         key: rustls_pki_types::PrivateKeyDer<'static>,
         certs: Vec<rustls_pki_types::CertificateDer<'static>>,
     },
+    #[cfg(feature = "__rustls")]
+    Signer {
+        key: Arc<dyn rustls::sign::SigningKey>,
+        certs: Vec<rustls_pki_types::CertificateDer<'static>>,
+    },
 }
 
 impl Clone for ClientCert {
@@ -112,6 +126,11 @@ impl Clone for ClientCert {
                 key: key.clone_key(),
                 certs: certs.clone(),
             },
+            #[cfg(feature = "__rustls")]
+            ClientCert::Signer { key, certs } => ClientCert::Signer {
+                key: key.clone(),
+                certs: certs.clone(),
+            },
             #[cfg_attr(
                 any(feature = "native-tls", feature = "__rustls"),
                 allow(unreachable_patterns)
@@ -380,6 +399,54 @@ impl Identity {
         })
     }
 
+    /// Creates an identity whose private key is kept by a signer, such as an
+    /// HSM, a TPM or a cloud KMS.
+    ///
+    /// `cert_chain` is a chain of PEM encoded X509 certificates, with the
+    /// leaf certificate first. The handshake signatures are made by `key`,
+    /// a rustls [`SigningKey`], so the private key is never loaded into
+    /// memory.
+    ///
+    /// [`SigningKey`]: https://docs.rs/rustls/latest/rustls/sign/trait.SigningKey.html
+    ///
+    /// # Optional
+    ///
+    /// This requires the `rustls-tls(-...)` Cargo feature enabled.
+    #[cfg(feature = "__rustls")]
+    pub fn from_signer(
+        cert_chain: &[u8],
+        key: Arc<dyn rustls::sign::SigningKey>,
+    ) -> crate::Result<Identity> {
+        let mut reader = BufReader::new(cert_chain);
+        let certs = rustls_pemfile::certs(&mut reader)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                crate::error::builder(TLSError::General(String::from(
+                    "Invalid identity PEM file",
+                )))
+            })?;
+        if certs.is_empty() {
+            return Err(crate::error::builder(TLSError::General(String::from(
+                "certificate not found",
+            ))));
+        }
+
+        Ok(Identity {
+            inner: ClientCert::Signer { key, certs },
+        })
+    }
+
+    /// Returns true if this identity can only be used with native-tls.
+    #[cfg(any(feature = "native-tls", feature = "__rustls"))]
+    pub(crate) fn is_native_tls(&self) -> bool {
+        match self.inner {
+            #[cfg(feature = "native-tls")]
+            ClientCert::Pkcs12(_) | ClientCert::Pkcs8(_) => true,
+            #[cfg(feature = "__rustls")]
+            ClientCert::Pem { .. } | ClientCert::Signer { .. } => false,
+        }
+    }
+
     #[cfg(feature = "native-tls")]
     pub(crate) fn add_to_native_tls(
         self,
@@ -391,7 +458,9 @@ impl Identity {
                 Ok(())
             }
             #[cfg(feature = "__rustls")]
-            ClientCert::Pem { .. } => Err(crate::error::builder("incompatible TLS identity type")),
+            ClientCert::Pem { .. } | ClientCert::Signer { .. } => {
+                Err(crate::error::builder("incompatible TLS identity type"))
+            }
         }
     }
 
@@ -408,6 +477,39 @@ impl Identity {
             ClientCert::Pem { key, certs } => config_builder
                 .with_client_auth_cert(certs, key)
                 .map_err(crate::error::builder),
+            ClientCert::Signer { key, certs } => {
+                let certified = rustls::sign::CertifiedKey::new(certs, key);
+                Ok(config_builder
+                    .with_client_cert_resolver(Arc::new(ClientCertResolver(Arc::new(certified)))))
+            }
+            #[cfg(feature = "native-tls")]
+            ClientCert::Pkcs12(..) | ClientCert::Pkcs8(..) => {
+                Err(crate::error::builder("incompatible TLS identity type"))
+            }
+        }
+    }
+
+    /// Sets this identity as the client certificate of an existing config.
+    #[cfg(feature = "__rustls")]
+    pub(crate) fn replace_in_rustls(
+        self,
+        config: &mut rustls::ClientConfig,
+        key_provider: &dyn rustls::crypto::KeyProvider,
+    ) -> crate::Result<()> {
+        match self.inner {
+            ClientCert::Pem { key, certs } => {
+                let key = key_provider
+                    .load_private_key(key)
+                    .map_err(crate::error::builder)?;
+                let certified = rustls::sign::CertifiedKey::new(certs, key);
+                config.client_auth_cert_resolver = Arc::new(ClientCertResolver(Arc::new(certified)));
+                Ok(())
+            }
+            ClientCert::Signer { key, certs } => {
+                let certified = rustls::sign::CertifiedKey::new(certs, key);
+                config.client_auth_cert_resolver = Arc::new(ClientCertResolver(Arc::new(certified)));
+                Ok(())
+            }
             #[cfg(feature = "native-tls")]
             ClientCert::Pkcs12(..) | ClientCert::Pkcs8(..) => {
                 Err(crate::error::builder("incompatible TLS identity type"))
@@ -416,6 +518,26 @@ impl Identity {
     }
 }
 
+/// Always presents the same client certificate.
+#[cfg(feature = "__rustls")]
+#[derive(Debug)]
+struct ClientCertResolver(Arc<rustls::sign::CertifiedKey>);
+
+#[cfg(feature = "__rustls")]
+impl rustls::client::ResolvesClientCert for ClientCertResolver {
+    fn resolve(
+        &self,
+        _root_hint_subjects: &[&[u8]],
+        _sigschemes: &[rustls::SignatureScheme],
+    ) -> Option<Arc<rustls::sign::CertifiedKey>> {
+        Some(self.0.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(feature = "__rustls")]
 impl CertificateRevocationList {
     /// Parses a PEM encoded CRL.
@@ -553,6 +675,370 @@ impl Version {
     }
 }
 
+/// The TLS options of connections to a host, see
+/// [`ClientBuilder::tls_config_for_host()`](crate::ClientBuilder::tls_config_for_host).
+///
+/// The options of the `Client` apply to what isn't set here.
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    root_certs: Vec<Certificate>,
+    identity: Option<Identity>,
+    accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    /// Creates options that change nothing.
+    pub fn new() -> TlsOptions {
+        TlsOptions::default()
+    }
+
+    /// Adds a root certificate for the host.
+    ///
+    /// Once a root certificate is added, the host's certificate is only
+    /// verified against the root certificates of the host, instead of those
+    /// of the `Client`.
+    pub fn add_root_certificate(mut self, cert: Certificate) -> TlsOptions {
+        self.root_certs.push(cert);
+        self
+    }
+
+    /// Sets the client certificate presented to the host.
+    pub fn identity(mut self, identity: Identity) -> TlsOptions {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Controls the use of certificate validation for the host.
+    ///
+    /// # Warning
+    ///
+    /// You should think very carefully before using this method. If
+    /// invalid certificates are trusted, *any* certificate for *any* site
+    /// will be trusted for use with this host.
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> TlsOptions {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    pub(crate) fn take_identity(&mut self) -> Option<Identity> {
+        self.identity.take()
+    }
+
+    #[cfg(feature = "default-tls")]
+    pub(crate) fn add_to_native_tls(self, tls: &mut native_tls_crate::TlsConnectorBuilder) {
+        if !self.root_certs.is_empty() {
+            tls.disable_built_in_roots(true);
+        }
+        for cert in self.root_certs {
+            cert.add_to_native_tls(tls);
+        }
+        if self.accept_invalid_certs {
+            tls.danger_accept_invalid_certs(true);
+        }
+    }
+
+    #[cfg(feature = "__rustls")]
+    pub(crate) fn add_to_rustls(
+        self,
+        config: &mut rustls::ClientConfig,
+        provider: &Arc<rustls::crypto::CryptoProvider>,
+        checks: &CertChecks,
+    ) -> crate::Result<()> {
+        if self.accept_invalid_certs {
+            config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(NoVerifier));
+        } else if !self.root_certs.is_empty() {
+            let mut root_cert_store = RootCertStore::empty();
+            for cert in self.root_certs {
+                cert.add_to_rustls(&mut root_cert_store)?;
+            }
+            let roots = Arc::new(root_cert_store);
+            let verifier = rustls::client::WebPkiServerVerifier::builder_with_provider(
+                roots.clone(),
+                provider.clone(),
+            )
+            .build()
+            .map_err(|_| crate::error::builder("invalid TLS verification settings"))?;
+            let verifier = add_checks(verifier, roots, checks, provider)?;
+            config.dangerous().set_certificate_verifier(verifier);
+        }
+        Ok(())
+    }
+}
+
+/// How the revocation status of server certificates is checked with OCSP,
+/// see [`ClientBuilder::tls_ocsp_check()`](crate::ClientBuilder::tls_ocsp_check).
+///
+/// A certificate whose status is revoked is always rejected. The policy
+/// decides what happens when its status can't be confirmed, because the
+/// server doesn't staple an OCSP response, or the response is invalid, and
+/// whether the responder of the certificate is queried then.
+#[cfg(feature = "rustls-tls-ocsp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls-ocsp")))]
+#[derive(Clone, Debug)]
+pub struct OcspPolicy {
+    pub(crate) hard_fail: bool,
+    pub(crate) fetch_timeout: Option<std::time::Duration>,
+}
+
+#[cfg(feature = "rustls-tls-ocsp")]
+impl OcspPolicy {
+    /// Accepts certificates whose status is unknown.
+    pub fn soft_fail() -> OcspPolicy {
+        OcspPolicy {
+            hard_fail: false,
+            fetch_timeout: None,
+        }
+    }
+
+    /// Rejects certificates whose status is unknown.
+    pub fn hard_fail() -> OcspPolicy {
+        OcspPolicy {
+            hard_fail: true,
+            fetch_timeout: None,
+        }
+    }
+
+    /// Queries the OCSP responder named in certificates that come without a
+    /// valid stapled response, waiting up to `timeout` for its answer.
+    ///
+    /// Responders are queried over plain HTTP, through the proxies of the
+    /// `Client`, with its resolver and allowed ports. Their answers are
+    /// cached until the responder's next update.
+    ///
+    /// The handshake waits for the responder, which blocks the thread
+    /// driving the connection.
+    pub fn fetch_responses(mut self, timeout: std::time::Duration) -> OcspPolicy {
+        self.fetch_timeout = Some(timeout);
+        self
+    }
+}
+
+/// A Certificate Transparency log, by its public key.
+#[cfg(feature = "__rustls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+#[derive(Clone, Debug)]
+pub struct CtLog {
+    key: Vec<u8>,
+}
+
+#[cfg(feature = "__rustls")]
+impl CtLog {
+    /// Creates a log from its DER encoded `SubjectPublicKeyInfo`, as
+    /// published in the log lists of browsers.
+    pub fn from_der(key: &[u8]) -> crate::Result<CtLog> {
+        let mut reader = x509::Reader::new(key);
+        reader
+            .expect(x509::SEQUENCE)
+            .and_then(|spki| x509::Spki::parse(spki.value))
+            .filter(|_| reader.is_empty())
+            .ok_or_else(|| crate::error::builder("invalid CT log key"))?;
+        Ok(CtLog { key: key.to_vec() })
+    }
+}
+
+/// The Certificate Transparency policy that server certificates must
+/// satisfy, see [`ClientBuilder::tls_ct_policy()`](crate::ClientBuilder::tls_ct_policy).
+///
+/// A certificate satisfies it when it embeds valid signed certificate
+/// timestamps (SCTs) from enough distinct logs of the policy.
+#[cfg(feature = "__rustls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+#[derive(Clone, Debug)]
+pub struct CtPolicy {
+    logs: Vec<CtLog>,
+    min_scts: usize,
+}
+
+#[cfg(feature = "__rustls")]
+impl CtPolicy {
+    /// Creates a policy trusting `logs`, which requires SCTs from 2 of them.
+    pub fn new(logs: impl IntoIterator<Item = CtLog>) -> CtPolicy {
+        CtPolicy {
+            logs: logs.into_iter().collect(),
+            min_scts: 2,
+        }
+    }
+
+    /// Sets how many distinct logs must have issued SCTs for a certificate.
+    pub fn min_scts(mut self, min_scts: usize) -> CtPolicy {
+        self.min_scts = min_scts;
+        self
+    }
+}
+
+/// The checks that reqwest adds to the verification of server certificates
+/// by rustls.
+#[cfg(feature = "__rustls")]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CertChecks {
+    #[cfg(feature = "rustls-tls-ocsp")]
+    pub(crate) ocsp: Option<OcspPolicy>,
+    /// The client that queries OCSP responders, if the policy allows it.
+    #[cfg(feature = "rustls-tls-ocsp")]
+    pub(crate) ocsp_client: Option<crate::Client>,
+    pub(crate) ct: Option<CtPolicy>,
+}
+
+#[cfg(feature = "__rustls")]
+impl CertChecks {
+    pub(crate) fn is_empty(&self) -> bool {
+        #[cfg(feature = "rustls-tls-ocsp")]
+        if self.ocsp.is_some() {
+            return false;
+        }
+        self.ct.is_none()
+    }
+}
+
+/// Adds the OCSP and CT checks to a verifier of server certificates.
+#[cfg(feature = "__rustls")]
+pub(crate) fn add_checks(
+    verifier: Arc<dyn ServerCertVerifier>,
+    roots: Arc<RootCertStore>,
+    checks: &CertChecks,
+    provider: &rustls::crypto::CryptoProvider,
+) -> crate::Result<Arc<dyn ServerCertVerifier>> {
+    let mut verifier = verifier;
+    #[cfg(feature = "rustls-tls-ocsp")]
+    if let Some(ref policy) = checks.ocsp {
+        verifier = Arc::new(ocsp::OcspVerifier::new(
+            verifier,
+            roots.clone(),
+            policy.clone(),
+            provider,
+            checks.ocsp_client.clone(),
+        ));
+    }
+    if let Some(ref policy) = checks.ct {
+        verifier = Arc::new(ct::CtVerifier::new(verifier, roots, policy, provider)?);
+    }
+    Ok(verifier)
+}
+
+/// A TLS cipher suite, by its IANA code point.
+///
+/// Only the rustls backend can restrict the cipher suites, see
+/// [`ClientBuilder::tls_cipher_suites()`](crate::ClientBuilder::tls_cipher_suites).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CipherSuite(u16);
+
+impl CipherSuite {
+    /// `TLS13_AES_128_GCM_SHA256`
+    pub const TLS13_AES_128_GCM_SHA256: CipherSuite = CipherSuite(0x1301);
+    /// `TLS13_AES_256_GCM_SHA384`
+    pub const TLS13_AES_256_GCM_SHA384: CipherSuite = CipherSuite(0x1302);
+    /// `TLS13_CHACHA20_POLY1305_SHA256`
+    pub const TLS13_CHACHA20_POLY1305_SHA256: CipherSuite = CipherSuite(0x1303);
+    /// `TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256`
+    pub const TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256: CipherSuite = CipherSuite(0xc02b);
+    /// `TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384`
+    pub const TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384: CipherSuite = CipherSuite(0xc02c);
+    /// `TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256`
+    pub const TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256: CipherSuite = CipherSuite(0xcca9);
+    /// `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`
+    pub const TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256: CipherSuite = CipherSuite(0xc02f);
+    /// `TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384`
+    pub const TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384: CipherSuite = CipherSuite(0xc030);
+    /// `TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256`
+    pub const TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256: CipherSuite = CipherSuite(0xcca8);
+
+    /// A cipher suite by its IANA code point, for the suites without a
+    /// constant here.
+    pub const fn from_u16(value: u16) -> CipherSuite {
+        CipherSuite(value)
+    }
+
+    /// The IANA code point of the cipher suite.
+    pub const fn as_u16(self) -> u16 {
+        self.0
+    }
+}
+
+/// A TLS key exchange group, by its IANA code point.
+///
+/// Only the rustls backend can restrict the key exchange groups, see
+/// [`ClientBuilder::tls_key_exchange_groups()`](crate::ClientBuilder::tls_key_exchange_groups).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyExchangeGroup(u16);
+
+impl KeyExchangeGroup {
+    /// The `secp256r1` elliptic curve, also known as P-256.
+    pub const SECP256R1: KeyExchangeGroup = KeyExchangeGroup(0x0017);
+    /// The `secp384r1` elliptic curve, also known as P-384.
+    pub const SECP384R1: KeyExchangeGroup = KeyExchangeGroup(0x0018);
+    /// The `X25519` elliptic curve.
+    pub const X25519: KeyExchangeGroup = KeyExchangeGroup(0x001d);
+    /// The `X25519MLKEM768` post-quantum hybrid group.
+    pub const X25519MLKEM768: KeyExchangeGroup = KeyExchangeGroup(0x11ec);
+
+    /// A key exchange group by its IANA code point, for the groups without
+    /// a constant here.
+    pub const fn from_u16(value: u16) -> KeyExchangeGroup {
+        KeyExchangeGroup(value)
+    }
+
+    /// The IANA code point of the key exchange group.
+    pub const fn as_u16(self) -> u16 {
+        self.0
+    }
+}
+
+/// Restricts the cipher suites and key exchange groups of `provider` to the
+/// given ones, in their order of preference.
+#[cfg(feature = "__rustls")]
+pub(crate) fn restrict_provider(
+    provider: &rustls::crypto::CryptoProvider,
+    cipher_suites: Option<&[CipherSuite]>,
+    kx_groups: Option<&[KeyExchangeGroup]>,
+) -> crate::Result<rustls::crypto::CryptoProvider> {
+    let mut provider = provider.clone();
+    if let Some(cipher_suites) = cipher_suites {
+        provider.cipher_suites = cipher_suites
+            .iter()
+            .map(|wanted| {
+                provider
+                    .cipher_suites
+                    .iter()
+                    .find(|suite| u16::from(suite.suite()) == wanted.0)
+                    .copied()
+                    .ok_or_else(|| {
+                        crate::error::builder(format!(
+                            "TLS cipher suite {:#06x} isn't supported by the crypto provider",
+                            wanted.0
+                        ))
+                    })
+            })
+            .collect::<crate::Result<_>>()?;
+    }
+    if let Some(kx_groups) = kx_groups {
+        provider.kx_groups = kx_groups
+            .iter()
+            .map(|wanted| {
+                provider
+                    .kx_groups
+                    .iter()
+                    .find(|group| u16::from(group.name()) == wanted.0)
+                    .copied()
+                    .ok_or_else(|| {
+                        crate::error::builder(format!(
+                            "TLS key exchange group {:#06x} isn't supported by the crypto provider",
+                            wanted.0
+                        ))
+                    })
+            })
+            .collect::<crate::Result<_>>()?;
+    }
+    if provider.cipher_suites.is_empty() {
+        return Err(crate::error::builder("empty supported tls cipher suites"));
+    }
+    if provider.kx_groups.is_empty() {
+        return Err(crate::error::builder("empty supported tls key exchange groups"));
+    }
+    Ok(provider)
+}
+
 pub(crate) enum TlsBackend {
     // This is the default and HTTP/3 feature does not use it so suppress it.
     #[allow(dead_code)]
@@ -660,14 +1146,14 @@ impl ServerCertVerifier for NoVerifier {
 #[cfg(feature = "__rustls")]
 #[derive(Debug)]
 pub(crate) struct IgnoreHostname {
-    roots: RootCertStore,
+    roots: Arc<RootCertStore>,
     signature_algorithms: WebPkiSupportedAlgorithms,
 }
 
 #[cfg(feature = "__rustls")]
 impl IgnoreHostname {
     pub(crate) fn new(
-        roots: RootCertStore,
+        roots: Arc<RootCertStore>,
         signature_algorithms: WebPkiSupportedAlgorithms,
     ) -> Self {
         Self {
@@ -787,6 +1273,27 @@ mod tests {
         Identity::from_pem(pem).unwrap();
     }
 
+    #[cfg(feature = "__rustls-ring")]
+    #[test]
+    fn identity_from_signer() {
+        use base64::Engine;
+
+        let key = std::fs::read("tests/support/server.key").unwrap();
+        let key = rustls::crypto::ring::default_provider()
+            .key_provider
+            .load_private_key(key.try_into().unwrap())
+            .unwrap();
+        let cert = std::fs::read("tests/support/server.cert").unwrap();
+        let certs = format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+            base64::engine::general_purpose::STANDARD.encode(cert)
+        );
+
+        let id = Identity::from_signer(certs.as_bytes(), key.clone()).unwrap();
+        assert!(!id.is_native_tls());
+        Identity::from_signer(b"not pem", key).unwrap_err();
+    }
+
     #[test]
     fn certificates_from_pem_bundle() {
         const PEM_BUNDLE: &[u8] = b"
@@ -840,4 +1347,27 @@ mod tests {
         let result = result.unwrap();
         assert_eq!(result.len(), 1);
     }
+
+    #[cfg(feature = "__rustls-ring")]
+    #[test]
+    fn restrict_provider_keeps_order() {
+        let provider = rustls::crypto::ring::default_provider();
+        let suites = [
+            CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+            CipherSuite::TLS13_AES_128_GCM_SHA256,
+        ];
+        let restricted =
+            restrict_provider(&provider, Some(&suites), Some(&[KeyExchangeGroup::X25519])).unwrap();
+        let restricted_suites = restricted
+            .cipher_suites
+            .iter()
+            .map(|suite| u16::from(suite.suite()))
+            .collect::<Vec<_>>();
+        assert_eq!(restricted_suites, [0x1303, 0x1301]);
+        assert_eq!(restricted.kx_groups.len(), 1);
+
+        let unknown = CipherSuite::from_u16(0x0000);
+        assert!(restrict_provider(&provider, Some(&[unknown]), None).is_err());
+        assert!(restrict_provider(&provider, Some(&[]), None).is_err());
+    }
 }
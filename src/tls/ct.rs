@@ -0,0 +1,596 @@
+//! Enforcing certificate transparency, see `ClientBuilder::tls_ct_policy()`.
+//!
+//! Server certificates must embed signed certificate timestamps (SCTs) from
+//! enough of the known logs. Each SCT is verified against the key of its
+//! log, over the precertificate the log signed, as described in RFC 6962.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::hash::Hash;
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::{
+    CertificateError, DigitallySignedStruct, DistinguishedName, Error as TLSError, OtherError,
+    RootCertStore, SignatureScheme,
+};
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+
+use super::x509::{
+    encode, find_issuer, sha256, verify_signature, Cert, Reader, Spki, CONTEXT_CONSTRUCTED,
+    OCTET_STRING, OID, SEQUENCE,
+};
+use super::CtPolicy;
+
+// 1.3.6.1.4.1.11129.2.4.2
+const ID_SCT_LIST: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x04, 0x02];
+// ecdsa-with-SHA256
+const ECDSA_WITH_SHA256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+// sha256WithRSAEncryption
+const SHA256_WITH_RSA: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b, 0x05, 0x00,
+];
+
+/// A log trusted by the policy.
+struct Log {
+    /// The SHA-256 hash of its key.
+    id: Vec<u8>,
+    key: Vec<u8>,
+}
+
+/// Rejects certificates the inner verifier accepted, unless they embed
+/// enough valid SCTs.
+pub(crate) struct CtVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    roots: Arc<RootCertStore>,
+    logs: Vec<Log>,
+    min_scts: usize,
+    algorithms: WebPkiSupportedAlgorithms,
+    sha256: &'static dyn Hash,
+}
+
+impl CtVerifier {
+    pub(crate) fn new(
+        inner: Arc<dyn ServerCertVerifier>,
+        roots: Arc<RootCertStore>,
+        policy: &CtPolicy,
+        provider: &CryptoProvider,
+    ) -> crate::Result<CtVerifier> {
+        let sha256 = sha256(provider).ok_or_else(|| {
+            crate::error::builder("a CT policy requires a crypto provider with SHA-256")
+        })?;
+        let logs = policy
+            .logs
+            .iter()
+            .map(|log| Log {
+                id: sha256.hash(&log.key).as_ref().to_vec(),
+                key: log.key.clone(),
+            })
+            .collect();
+        Ok(CtVerifier {
+            inner,
+            roots,
+            logs,
+            min_scts: policy.min_scts,
+            algorithms: provider.signature_verification_algorithms,
+            sha256,
+        })
+    }
+
+    /// Returns how many distinct logs issued valid SCTs embedded in
+    /// `end_entity`.
+    fn logged(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: u64,
+    ) -> usize {
+        let cert = match Cert::parse(end_entity) {
+            Some(cert) => cert,
+            None => return 0,
+        };
+        let list = match cert
+            .extension(ID_SCT_LIST)
+            .and_then(|list| Reader::new(list).expect(OCTET_STRING))
+        {
+            Some(list) => list.value,
+            None => return 0,
+        };
+        let (issuer, tbs) = match (
+            find_issuer(&cert, intermediates, &self.roots, &self.algorithms),
+            precert_tbs(cert.tbs),
+        ) {
+            (Some(issuer), Some(tbs)) => (issuer, tbs),
+            _ => return 0,
+        };
+        let precert = PreCert {
+            issuer_key_hash: self.sha256.hash(&issuer.to_der()).as_ref().to_vec(),
+            tbs,
+        };
+
+        let mut logs = Vec::new();
+        let mut list = TlsReader::new(list);
+        let mut scts = match list.vec16() {
+            Some(scts) => TlsReader::new(scts),
+            None => return 0,
+        };
+        while let Some(sct) = scts.vec16() {
+            if let Some(log) = self.verify_sct(sct, &precert, now) {
+                if !logs.contains(&log) {
+                    logs.push(log);
+                }
+            }
+        }
+        logs.len()
+    }
+
+    /// Returns the index of the log that issued `sct`, if it's valid.
+    fn verify_sct(&self, sct: &[u8], precert: &PreCert, now: u64) -> Option<usize> {
+        let mut sct = TlsReader::new(sct);
+        // v1 (0)
+        if sct.take(1)? != [0] {
+            return None;
+        }
+        let log_id = sct.take(32)?;
+        let timestamp = sct.take(8)?;
+        let extensions = sct.vec16()?;
+        let algorithm = match sct.take(2)? {
+            // sha256, ecdsa
+            [4, 3] => ECDSA_WITH_SHA256,
+            // sha256, rsa
+            [4, 1] => SHA256_WITH_RSA,
+            _ => return None,
+        };
+        let signature = sct.vec16()?;
+        if !sct.input.is_empty() {
+            return None;
+        }
+
+        let index = self.logs.iter().position(|log| log.id == log_id)?;
+        let mut millis = [0; 8];
+        millis.copy_from_slice(timestamp);
+        if u64::from_be_bytes(millis) > now {
+            return None;
+        }
+
+        let message = signed_data(timestamp, precert, extensions);
+        let mut key = Reader::new(&self.logs[index].key);
+        let key = Spki::parse(key.expect(SEQUENCE)?.value)?;
+        if verify_signature(&self.algorithms, &key, algorithm, &message, signature) {
+            Some(index)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for CtVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CtVerifier")
+            .field("inner", &self.inner)
+            .field("logs", &self.logs.len())
+            .field("min_scts", &self.min_scts)
+            .finish()
+    }
+}
+
+impl ServerCertVerifier for CtVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TLSError> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let logged = self.logged(end_entity, intermediates, now.as_secs() * 1000);
+        if logged < self.min_scts {
+            return Err(TLSError::InvalidCertificate(CertificateError::Other(
+                OtherError(Arc::new(CtPolicyFailed {
+                    logged,
+                    required: self.min_scts,
+                })),
+            )));
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+
+    fn root_hint_subjects(&self) -> Option<&[DistinguishedName]> {
+        self.inner.root_hint_subjects()
+    }
+}
+
+#[derive(Debug)]
+struct CtPolicyFailed {
+    logged: usize,
+    required: usize,
+}
+
+impl fmt::Display for CtPolicyFailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "certificate has valid SCTs from {} known logs, the CT policy requires {}",
+            self.logged, self.required
+        )
+    }
+}
+
+impl StdError for CtPolicyFailed {}
+
+/// What a log signs for a certificate whose SCTs are embedded.
+struct PreCert {
+    /// The SHA-256 hash of the issuer's key.
+    issuer_key_hash: Vec<u8>,
+    tbs: Vec<u8>,
+}
+
+/// Encodes what the log of an SCT signed.
+fn signed_data(timestamp: &[u8], precert: &PreCert, extensions: &[u8]) -> Vec<u8> {
+    // v1, certificate_timestamp
+    let mut data = vec![0, 0];
+    data.extend_from_slice(timestamp);
+    // precert_entry
+    data.extend_from_slice(&[0, 1]);
+    data.extend_from_slice(&precert.issuer_key_hash);
+    data.extend_from_slice(&(precert.tbs.len() as u32).to_be_bytes()[1..]);
+    data.extend_from_slice(&precert.tbs);
+    data.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    data.extend_from_slice(extensions);
+    data
+}
+
+/// Encodes a `TBSCertificate` again, without its SCT list extension, as it
+/// was in the precertificate.
+fn precert_tbs(tbs: &[u8]) -> Option<Vec<u8>> {
+    let mut outer = Reader::new(tbs);
+    let mut fields = Reader::new(outer.expect(SEQUENCE)?.value);
+    let mut parts = Vec::new();
+    while let Some(field) = fields.read() {
+        if field.tag != CONTEXT_CONSTRUCTED | 3 {
+            parts.push(field.raw.to_vec());
+            continue;
+        }
+        let mut outer = Reader::new(field.value);
+        let mut extensions = Reader::new(outer.expect(SEQUENCE)?.value);
+        let mut kept = Vec::new();
+        while let Some(extension) = extensions.read() {
+            if Reader::new(extension.value).expect(OID)?.value != ID_SCT_LIST {
+                kept.push(extension.raw);
+            }
+        }
+        parts.push(encode(CONTEXT_CONSTRUCTED | 3, &[&encode(SEQUENCE, &kept)]));
+    }
+    let parts = parts.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    Some(encode(SEQUENCE, &parts))
+}
+
+/// Reads the TLS encoded structures of SCTs.
+struct TlsReader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> TlsReader<'a> {
+    fn new(input: &'a [u8]) -> TlsReader<'a> {
+        TlsReader { input }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.input.len() < n {
+            return None;
+        }
+        let (taken, rest) = self.input.split_at(n);
+        self.input = rest;
+        Some(taken)
+    }
+
+    /// Reads a vector with a 16-bit length.
+    fn vec16(&mut self) -> Option<&'a [u8]> {
+        let len = self.take(2)?;
+        self.take(usize::from(u16::from_be_bytes([len[0], len[1]])))
+    }
+}
+
+#[cfg(all(test, feature = "__rustls-ring"))]
+mod tests {
+    use super::super::x509::BIT_STRING;
+    use super::*;
+    use crate::tls::{CtLog, NoVerifier};
+    use rustls_pki_types::{Der, TrustAnchor};
+
+    // 2024-01-01T00:00:00Z
+    const NOW: u64 = 1_704_067_200;
+
+    fn server_cert() -> Vec<u8> {
+        std::fs::read("tests/support/server.cert").unwrap()
+    }
+
+    /// A verifier trusting the key of the test server as a log, and as the
+    /// root that issued the test server certificate.
+    fn ct_verifier(min_scts: usize) -> CtVerifier {
+        let der = server_cert();
+        let cert = Cert::parse(&der).unwrap();
+        let key = cert.spki.to_der();
+        let mut roots = RootCertStore::empty();
+        roots.roots.push(TrustAnchor {
+            subject: Der::from(cert.issuer.to_vec()),
+            subject_public_key_info: Der::from(
+                Reader::new(&key).expect(SEQUENCE).unwrap().value.to_vec(),
+            ),
+            name_constraints: None,
+        });
+        let policy = CtPolicy::new(vec![CtLog::from_der(&key).unwrap()]).min_scts(min_scts);
+        CtVerifier::new(
+            Arc::new(NoVerifier),
+            Arc::new(roots),
+            &policy,
+            &rustls::crypto::ring::default_provider(),
+        )
+        .unwrap()
+    }
+
+    /// Signs `data` with the key of the test server.
+    fn sign(data: &[u8]) -> Vec<u8> {
+        let key = std::fs::read("tests/support/server.key").unwrap();
+        let key = rustls::crypto::ring::sign::any_supported_type(&key.try_into().unwrap()).unwrap();
+        let signer = key
+            .choose_scheme(&[SignatureScheme::RSA_PKCS1_SHA256])
+            .unwrap();
+        signer.sign(data).unwrap()
+    }
+
+    /// Signs an SCT for the test server certificate, with its own key.
+    fn signed_sct(verifier: &CtVerifier, timestamp: u64) -> Vec<u8> {
+        let der = server_cert();
+        let cert = Cert::parse(&der).unwrap();
+        let precert = PreCert {
+            issuer_key_hash: verifier.sha256.hash(&cert.spki.to_der()).as_ref().to_vec(),
+            tbs: cert.tbs.to_vec(),
+        };
+        let timestamp = (timestamp * 1000).to_be_bytes();
+        let signature = sign(&signed_data(&timestamp, &precert, &[]));
+
+        let mut sct = vec![0];
+        sct.extend_from_slice(&verifier.logs[0].id);
+        sct.extend_from_slice(&timestamp);
+        sct.extend_from_slice(&[0, 0, 4, 1]);
+        sct.extend_from_slice(&(signature.len() as u16).to_be_bytes());
+        sct.extend_from_slice(&signature);
+        sct
+    }
+
+    /// Embeds an SCT list in the test server certificate, which is signed
+    /// again with its own key.
+    fn cert_with_scts(scts: &[Vec<u8>]) -> Vec<u8> {
+        let mut list = Vec::new();
+        for sct in scts {
+            list.extend_from_slice(&(sct.len() as u16).to_be_bytes());
+            list.extend_from_slice(sct);
+        }
+        let mut value = (list.len() as u16).to_be_bytes().to_vec();
+        value.extend_from_slice(&list);
+        let extension = encode(
+            SEQUENCE,
+            &[
+                &encode(OID, &[ID_SCT_LIST]),
+                &encode(OCTET_STRING, &[&encode(OCTET_STRING, &[&value])]),
+            ],
+        );
+
+        let der = server_cert();
+        let mut outer = Reader::new(&der);
+        let mut cert = Reader::new(outer.expect(SEQUENCE).unwrap().value);
+        let tbs = cert.expect(SEQUENCE).unwrap();
+        let mut fields = Reader::new(tbs.value);
+        let mut parts = Vec::new();
+        while let Some(field) = fields.read() {
+            if field.tag == CONTEXT_CONSTRUCTED | 3 {
+                let mut outer = Reader::new(field.value);
+                let extensions = outer.expect(SEQUENCE).unwrap().value;
+                parts.push(encode(
+                    CONTEXT_CONSTRUCTED | 3,
+                    &[&encode(SEQUENCE, &[extensions, &extension])],
+                ));
+            } else {
+                parts.push(field.raw.to_vec());
+            }
+        }
+        let parts = parts.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let tbs = encode(SEQUENCE, &parts);
+        let algorithm = cert.read().unwrap().raw.to_vec();
+        let signature = encode(BIT_STRING, &[&[0], &sign(&tbs)]);
+        encode(SEQUENCE, &[&tbs, &algorithm, &signature])
+    }
+
+    fn verify(verifier: &CtVerifier, cert: Vec<u8>) -> Result<ServerCertVerified, TLSError> {
+        verifier.verify_server_cert(
+            &CertificateDer::from(cert),
+            &[],
+            &ServerName::try_from("testserver.com").unwrap(),
+            &[],
+            UnixTime::since_unix_epoch(std::time::Duration::from_secs(NOW)),
+        )
+    }
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("tests/support/ct/{name}")).unwrap()
+    }
+
+    /// A verifier trusting the `logs` of the fixtures made with OpenSSL.
+    fn fixture_verifier(logs: &[&str]) -> CtVerifier {
+        let logs = logs
+            .iter()
+            .map(|name| CtLog::from_der(&fixture(name)).unwrap());
+        CtVerifier::new(
+            Arc::new(NoVerifier),
+            Arc::new(RootCertStore::empty()),
+            &CtPolicy::new(logs),
+            &rustls::crypto::ring::default_provider(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn openssl_scts() {
+        // an ECDSA and an RSA log signed the certificate at `NOW`
+        let leaf = CertificateDer::from(fixture("leaf.cert"));
+        let root = [CertificateDer::from(fixture("root.cert"))];
+        let now = NOW * 1000;
+        let verifier = fixture_verifier(&["ecdsa-log.key", "rsa-log.key"]);
+        assert_eq!(verifier.logged(&leaf, &root, now), 2);
+        assert_eq!(
+            fixture_verifier(&["ecdsa-log.key"]).logged(&leaf, &root, now),
+            1
+        );
+        assert_eq!(
+            fixture_verifier(&["rsa-log.key"]).logged(&leaf, &root, now),
+            1
+        );
+
+        assert_eq!(verifier.logged(&leaf, &root, now - 1), 0);
+        // without the issuer, whose key the logs signed too
+        assert_eq!(verifier.logged(&leaf, &[], now), 0);
+    }
+
+    #[test]
+    fn precert_tbs_without_scts() {
+        let verifier = ct_verifier(1);
+        let cert = cert_with_scts(&[signed_sct(&verifier, NOW)]);
+        let der = server_cert();
+        assert_eq!(
+            precert_tbs(Cert::parse(&cert).unwrap().tbs).unwrap(),
+            Cert::parse(&der).unwrap().tbs
+        );
+    }
+
+    #[test]
+    fn embedded_scts() {
+        let verifier = ct_verifier(1);
+        let sct = signed_sct(&verifier, NOW - 60);
+        assert_eq!(
+            verifier.logged(
+                &cert_with_scts(std::slice::from_ref(&sct)).into(),
+                &[],
+                NOW * 1000
+            ),
+            1
+        );
+        assert!(verify(&verifier, cert_with_scts(std::slice::from_ref(&sct))).is_ok());
+
+        // SCTs of the same log count once
+        let verifier = ct_verifier(2);
+        let err = verify(&verifier, cert_with_scts(&[sct.clone(), sct])).unwrap_err();
+        assert!(matches!(
+            err,
+            TLSError::InvalidCertificate(CertificateError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_scts() {
+        let verifier = ct_verifier(1);
+        assert!(verify(&verifier, server_cert()).is_err());
+
+        // issued after now
+        let sct = signed_sct(&verifier, NOW + 60);
+        assert!(verify(&verifier, cert_with_scts(&[sct])).is_err());
+
+        // tampered
+        let mut sct = signed_sct(&verifier, NOW - 60);
+        let last = sct.len() - 1;
+        sct[last] ^= 1;
+        assert!(verify(&verifier, cert_with_scts(&[sct])).is_err());
+
+        // unknown log
+        let mut sct = signed_sct(&verifier, NOW - 60);
+        sct[1] ^= 1;
+        assert!(verify(&verifier, cert_with_scts(&[sct])).is_err());
+    }
+
+    #[test]
+    fn malformed_scts() {
+        let verifier = ct_verifier(1);
+        let logged =
+            |scts: &[Vec<u8>]| verifier.logged(&cert_with_scts(scts).into(), &[], NOW * 1000);
+        let sct = signed_sct(&verifier, NOW - 60);
+        assert_eq!(logged(std::slice::from_ref(&sct)), 1);
+
+        let mut version = sct.clone();
+        version[0] = 1;
+        // sha384
+        let mut algorithm = sct.clone();
+        algorithm[43] = 5;
+        for malformed in [
+            sct[..sct.len() - 1].to_vec(),
+            [&sct[..], &[0]].concat(),
+            sct[..41].to_vec(),
+            version,
+            algorithm,
+        ] {
+            assert_eq!(logged(std::slice::from_ref(&malformed)), 0);
+            // the other SCTs are still checked
+            assert_eq!(logged(&[malformed, sct.clone()]), 1);
+        }
+    }
+
+    #[test]
+    fn malformed_precert_tbs() {
+        let extensions = |extensions: &[u8]| {
+            encode(SEQUENCE, &[&encode(CONTEXT_CONSTRUCTED | 3, &[extensions])])
+        };
+        assert!(precert_tbs(&[]).is_none());
+        assert!(precert_tbs(&encode(OCTET_STRING, &[])).is_none());
+        assert!(precert_tbs(&extensions(&encode(OCTET_STRING, &[]))).is_none());
+        // an extension without its OID
+        let extension = encode(SEQUENCE, &[&encode(OCTET_STRING, &[])]);
+        assert!(precert_tbs(&extensions(&encode(SEQUENCE, &[&extension]))).is_none());
+
+        // other extensions are kept, in order
+        let extension = |oid: &[u8]| {
+            encode(
+                SEQUENCE,
+                &[&encode(OID, &[oid]), &encode(OCTET_STRING, &[])],
+            )
+        };
+        let (first, last) = (
+            extension(&[0x55, 0x1d, 0x0f]),
+            extension(&[0x55, 0x1d, 0x11]),
+        );
+        let tbs = extensions(&encode(SEQUENCE, &[&first, &extension(ID_SCT_LIST), &last]));
+        assert_eq!(
+            precert_tbs(&tbs).unwrap(),
+            extensions(&encode(SEQUENCE, &[&first, &last]))
+        );
+    }
+}
@@ -0,0 +1,594 @@
+//! Checking the revocation status of server certificates with OCSP, see
+//! `ClientBuilder::tls_ocsp_check()`.
+//!
+//! The status comes from the response stapled by the server or, if the
+//! policy allows it, from the responder named in the certificate. Responses
+//! must be signed by the issuer of the certificate, or by a responder it
+//! delegated with the `id-kp-OCSPSigning` key usage, as described in
+//! RFC 6960.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use ring::digest;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::{
+    CertificateError, DigitallySignedStruct, DistinguishedName, Error as TLSError, RootCertStore,
+    SignatureScheme,
+};
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+
+use super::x509::{
+    bit_string, encode, find_issuer, time, verify_signature, Cert, Reader, Spki, BIT_STRING,
+    CONTEXT, CONTEXT_CONSTRUCTED, ENUMERATED, GENERALIZED_TIME, INTEGER, OCTET_STRING, OID,
+    SEQUENCE,
+};
+use super::OcspPolicy;
+
+// 1.3.6.1.5.5.7.48.1.1
+const ID_PKIX_OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+// 1.3.6.1.5.5.7.3.9
+const ID_KP_OCSP_SIGNING: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x09];
+// 1.3.6.1.5.5.7.1.1
+const ID_PE_AUTHORITY_INFO_ACCESS: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x01];
+// 1.3.6.1.5.5.7.48.1
+const ID_AD_OCSP: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01];
+// 2.5.29.37
+const ID_CE_EXT_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+// 1.3.14.3.2.26
+const SHA1: &[u8] = &[0x2b, 0x0e, 0x03, 0x02, 0x1a];
+// 2.16.840.1.101.3.4.2.1
+const SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+/// How far the clocks of responders may be off, in seconds.
+const CLOCK_SKEW: u64 = 5 * 60;
+
+/// The revocation status of a certificate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Good,
+    Revoked,
+    Unknown,
+}
+
+/// The status of a certificate in a valid response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Single {
+    status: Status,
+    /// When newer information will be available, if the responder says.
+    next_update: Option<u64>,
+}
+
+/// Checks the OCSP status of certificates the inner verifier accepted.
+pub(crate) struct OcspVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    roots: Arc<RootCertStore>,
+    policy: OcspPolicy,
+    algorithms: WebPkiSupportedAlgorithms,
+    fetcher: Option<Fetcher>,
+}
+
+impl OcspVerifier {
+    /// Creates a verifier that queries responders with `client` if the
+    /// policy allows it.
+    pub(crate) fn new(
+        inner: Arc<dyn ServerCertVerifier>,
+        roots: Arc<RootCertStore>,
+        policy: OcspPolicy,
+        provider: &CryptoProvider,
+        client: Option<crate::Client>,
+    ) -> OcspVerifier {
+        let fetcher = match (policy.fetch_timeout, client) {
+            (Some(timeout), Some(client)) => Some(Fetcher::new(client, timeout)),
+            _ => None,
+        };
+        OcspVerifier {
+            inner,
+            roots,
+            policy,
+            algorithms: provider.signature_verification_algorithms,
+            fetcher,
+        }
+    }
+
+    /// Returns the status of `end_entity`, from the stapled response, then
+    /// from its responder.
+    fn status(
+        &self,
+        end_entity: &[u8],
+        intermediates: &[CertificateDer<'_>],
+        stapled: &[u8],
+        now: u64,
+    ) -> Option<Status> {
+        let cert = Cert::parse(end_entity)?;
+        // The inner verifier checked the chain, but the server may send
+        // other certificates with the name of the issuer too.
+        let issuer = find_issuer(&cert, intermediates, &self.roots, &self.algorithms)?;
+        if !stapled.is_empty() {
+            match response_status(&cert, &issuer, stapled, &self.algorithms, now) {
+                Some(single) => return Some(single.status),
+                None => log::debug!("invalid OCSP response stapled to the server certificate"),
+            }
+        }
+        self.fetcher
+            .as_ref()?
+            .status(&cert, &issuer, &self.algorithms, now)
+    }
+}
+
+impl fmt::Debug for OcspVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OcspVerifier")
+            .field("inner", &self.inner)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl ServerCertVerifier for OcspVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TLSError> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let status = self
+            .status(end_entity, intermediates, ocsp_response, now.as_secs())
+            .unwrap_or(Status::Unknown);
+        match status {
+            Status::Good => Ok(verified),
+            Status::Revoked => Err(TLSError::InvalidCertificate(CertificateError::Revoked)),
+            Status::Unknown if self.policy.hard_fail => Err(TLSError::InvalidCertificate(
+                CertificateError::UnknownRevocationStatus,
+            )),
+            Status::Unknown => Ok(verified),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+
+    fn root_hint_subjects(&self) -> Option<&[DistinguishedName]> {
+        self.inner.root_hint_subjects()
+    }
+}
+
+/// A request to a responder, answered with its response.
+struct Job {
+    url: String,
+    request: Vec<u8>,
+    reply: mpsc::SyncSender<Option<Vec<u8>>>,
+}
+
+/// Queries OCSP responders over HTTP.
+///
+/// Verifiers can't wait for futures, so the queries run on a runtime of
+/// their own, in a thread started by the first one, while the handshake
+/// waits for them.
+struct Fetcher {
+    client: crate::Client,
+    timeout: Duration,
+    jobs: Mutex<Option<tokio::sync::mpsc::UnboundedSender<Job>>>,
+    // statuses by request, until their next update
+    cache: Mutex<HashMap<Vec<u8>, Single>>,
+}
+
+impl Fetcher {
+    fn new(client: crate::Client, timeout: Duration) -> Fetcher {
+        Fetcher {
+            client,
+            timeout,
+            jobs: Mutex::new(None),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the status of `cert` from its responder.
+    fn status(
+        &self,
+        cert: &Cert<'_>,
+        issuer: &Spki<'_>,
+        algorithms: &WebPkiSupportedAlgorithms,
+        now: u64,
+    ) -> Option<Status> {
+        let url = responder(cert)?;
+        let request = request(cert, issuer);
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, single| single.next_update.map_or(false, |next| next >= now));
+        if let Some(single) = cache.get(&request) {
+            return Some(single.status);
+        }
+        drop(cache);
+
+        log::debug!("querying the OCSP responder {url}");
+        let response = self.fetch(url, request.clone())?;
+        let single = response_status(cert, issuer, &response, algorithms, now)?;
+        if single.next_update.is_some() {
+            self.cache.lock().unwrap().insert(request, single);
+        }
+        Some(single.status)
+    }
+
+    /// Sends `request` to the responder at `url`, and waits for its response.
+    fn fetch(&self, url: String, request: Vec<u8>) -> Option<Vec<u8>> {
+        let (reply, response) = mpsc::sync_channel(1);
+        let job = Job {
+            url,
+            request,
+            reply,
+        };
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = match *jobs {
+            Some(ref jobs) => jobs.send(job).err().map(|err| err.0),
+            None => Some(job),
+        };
+        if let Some(job) = job {
+            let tx = spawn(self.client.clone())?;
+            tx.send(job).ok()?;
+            *jobs = Some(tx);
+        }
+        drop(jobs);
+
+        match response.recv_timeout(self.timeout) {
+            Ok(response) => response,
+            Err(_) => {
+                log::debug!("the OCSP responder didn't answer in time");
+                None
+            }
+        }
+    }
+}
+
+/// Starts the thread that queries responders until the returned sender is
+/// dropped.
+fn spawn(client: crate::Client) -> Option<tokio::sync::mpsc::UnboundedSender<Job>> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .ok()?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Job>();
+    thread::Builder::new()
+        .name("reqwest-ocsp".into())
+        .spawn(move || {
+            rt.block_on(async move {
+                while let Some(job) = rx.recv().await {
+                    let client = client.clone();
+                    tokio::spawn(async move {
+                        let response = query(&client, job.url, job.request).await;
+                        if let Err(ref err) = response {
+                            log::debug!("OCSP query failed: {err}");
+                        }
+                        let _ = job.reply.send(response.ok());
+                    });
+                }
+            })
+        })
+        .ok()?;
+    Some(tx)
+}
+
+async fn query(client: &crate::Client, url: String, request: Vec<u8>) -> crate::Result<Vec<u8>> {
+    let response = client
+        .post(url)
+        .header(http::header::CONTENT_TYPE, "application/ocsp-request")
+        .body(request)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Returns the URL of the OCSP responder of `cert`, from its authority
+/// information access extension.
+///
+/// Only plain HTTP responders are queried, as usual: their responses are
+/// signed, and checking an HTTPS responder would need OCSP itself.
+fn responder(cert: &Cert<'_>) -> Option<String> {
+    let mut outer = Reader::new(cert.extension(ID_PE_AUTHORITY_INFO_ACCESS)?);
+    let mut descriptions = Reader::new(outer.expect(SEQUENCE)?.value);
+    while let Some(description) = descriptions.read() {
+        let mut description = Reader::new(description.value);
+        let method = description.expect(OID)?;
+        // uniformResourceIdentifier
+        match description.read() {
+            Some(location) if method.value == ID_AD_OCSP && location.tag == CONTEXT | 6 => {
+                let url = std::str::from_utf8(location.value).ok()?;
+                if url.starts_with("http://") {
+                    return Some(url.to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the DER encoding of an `OCSPRequest` for `cert`.
+fn request(cert: &Cert<'_>, issuer: &Spki<'_>) -> Vec<u8> {
+    let name_hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &issuer_name(cert));
+    let key_hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, issuer.key);
+    let cert_id = encode(
+        SEQUENCE,
+        &[
+            &encode(SEQUENCE, &[&encode(OID, &[SHA1]), &[0x05, 0x00]]),
+            &encode(OCTET_STRING, &[name_hash.as_ref()]),
+            &encode(OCTET_STRING, &[key_hash.as_ref()]),
+            &encode(INTEGER, &[cert.serial]),
+        ],
+    );
+    // OCSPRequest, TBSRequest, requestList, Request
+    let request = encode(SEQUENCE, &[&cert_id]);
+    let request_list = encode(SEQUENCE, &[&request]);
+    encode(SEQUENCE, &[&encode(SEQUENCE, &[&request_list])])
+}
+
+/// Returns the DER encoding of the name of the issuer of `cert`.
+fn issuer_name(cert: &Cert<'_>) -> Vec<u8> {
+    encode(SEQUENCE, &[cert.issuer])
+}
+
+/// Returns whether a `CertID` identifies `cert`.
+///
+/// Responders usually identify certificates with SHA-1 hashes.
+fn identifies(cert_id: &[u8], cert: &Cert<'_>, issuer: &Spki<'_>) -> Option<bool> {
+    let mut cert_id = Reader::new(cert_id);
+    let algorithm = Reader::new(cert_id.expect(SEQUENCE)?.value).expect(OID)?;
+    let algorithm = if algorithm.value == SHA1 {
+        &digest::SHA1_FOR_LEGACY_USE_ONLY
+    } else if algorithm.value == SHA256 {
+        &digest::SHA256
+    } else {
+        return None;
+    };
+    let name_hash = cert_id.expect(OCTET_STRING)?.value;
+    let key_hash = cert_id.expect(OCTET_STRING)?.value;
+    let serial = cert_id.expect(INTEGER)?.value;
+    Some(
+        serial == cert.serial
+            && digest::digest(algorithm, &issuer_name(cert)).as_ref() == name_hash
+            && digest::digest(algorithm, issuer.key).as_ref() == key_hash,
+    )
+}
+
+/// Returns whether `responder` is a valid OCSP responder delegated by the
+/// issuer of `cert`.
+fn is_delegated(
+    responder: &Cert<'_>,
+    cert: &Cert<'_>,
+    issuer: &Spki<'_>,
+    algorithms: &WebPkiSupportedAlgorithms,
+    now: u64,
+) -> bool {
+    let signing = || -> Option<bool> {
+        let mut outer = Reader::new(responder.extension(ID_CE_EXT_KEY_USAGE)?);
+        let mut usages = Reader::new(outer.expect(SEQUENCE)?.value);
+        while let Some(usage) = usages.read() {
+            if usage.tag == OID && usage.value == ID_KP_OCSP_SIGNING {
+                return Some(true);
+            }
+        }
+        Some(false)
+    };
+    responder.issuer == cert.issuer
+        && responder.validity.0 <= now
+        && now <= responder.validity.1
+        && signing() == Some(true)
+        && verify_signature(
+            algorithms,
+            issuer,
+            responder.signature_algorithm,
+            responder.tbs,
+            responder.signature,
+        )
+}
+
+/// Returns the status of `cert` in a DER encoded `OCSPResponse`.
+///
+/// Returns `None` if the response is malformed, isn't signed by `issuer` or
+/// a responder it delegated, doesn't cover `cert`, or isn't current.
+fn response_status(
+    cert: &Cert<'_>,
+    issuer: &Spki<'_>,
+    response: &[u8],
+    algorithms: &WebPkiSupportedAlgorithms,
+    now: u64,
+) -> Option<Single> {
+    let mut outer = Reader::new(response);
+    let mut response = Reader::new(outer.expect(SEQUENCE)?.value);
+    // successful
+    if response.expect(ENUMERATED)?.value != [0] {
+        return None;
+    }
+    let mut bytes = Reader::new(response.expect(CONTEXT_CONSTRUCTED)?.value);
+    let mut bytes = Reader::new(bytes.expect(SEQUENCE)?.value);
+    if bytes.expect(OID)?.value != ID_PKIX_OCSP_BASIC {
+        return None;
+    }
+    let mut outer = Reader::new(bytes.expect(OCTET_STRING)?.value);
+    let mut basic = Reader::new(outer.expect(SEQUENCE)?.value);
+    let data = basic.expect(SEQUENCE)?;
+    let signature_algorithm = basic.expect(SEQUENCE)?.value;
+    let signature = bit_string(basic.expect(BIT_STRING)?)?;
+    let certs = basic.optional(CONTEXT_CONSTRUCTED);
+
+    let signed = |spki: &Spki<'_>| {
+        verify_signature(algorithms, spki, signature_algorithm, data.raw, signature)
+    };
+    let signed_by_responder = || -> Option<bool> {
+        let mut outer = Reader::new(certs?.value);
+        let mut certs = Reader::new(outer.expect(SEQUENCE)?.value);
+        while let Some(der) = certs.read() {
+            let responder = match Cert::parse(der.raw) {
+                Some(responder) => responder,
+                None => continue,
+            };
+            if is_delegated(&responder, cert, issuer, algorithms, now) && signed(&responder.spki) {
+                return Some(true);
+            }
+        }
+        Some(false)
+    };
+    if !signed(issuer) && signed_by_responder() != Some(true) {
+        return None;
+    }
+
+    let mut data = Reader::new(data.value);
+    // version, responder ID, production time
+    data.optional(CONTEXT_CONSTRUCTED);
+    data.read()?;
+    data.expect(GENERALIZED_TIME)?;
+    let mut responses = Reader::new(data.expect(SEQUENCE)?.value);
+    while let Some(single) = responses.read() {
+        let mut single = Reader::new(single.value);
+        if identifies(single.expect(SEQUENCE)?.value, cert, issuer) != Some(true) {
+            continue;
+        }
+        let status = match single.read()?.tag {
+            tag if tag == CONTEXT => Status::Good,
+            tag if tag == CONTEXT_CONSTRUCTED | 1 => Status::Revoked,
+            tag if tag == CONTEXT | 2 => Status::Unknown,
+            _ => return None,
+        };
+        let this_update = time(single.expect(GENERALIZED_TIME)?)?;
+        let next_update = match single.optional(CONTEXT_CONSTRUCTED) {
+            Some(next) => Some(time(Reader::new(next.value).expect(GENERALIZED_TIME)?)?),
+            None => None,
+        };
+        if this_update > now + CLOCK_SKEW {
+            return None;
+        }
+        if let Some(next_update) = next_update {
+            if next_update < this_update || next_update + CLOCK_SKEW < now {
+                return None;
+            }
+        }
+        return Some(Single {
+            status,
+            next_update,
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("tests/support/ocsp/{name}")).unwrap()
+    }
+
+    fn algorithms() -> WebPkiSupportedAlgorithms {
+        rustls::crypto::ring::default_provider().signature_verification_algorithms
+    }
+
+    fn status(intermediates: &[&str], response: &str) -> Option<Status> {
+        let intermediates = intermediates
+            .iter()
+            .map(|name| CertificateDer::from(fixture(name)))
+            .collect::<Vec<_>>();
+        let leaf = fixture("leaf.cert");
+        let cert = Cert::parse(&leaf).unwrap();
+        let roots = RootCertStore::empty();
+        let issuer = find_issuer(&cert, &intermediates, &roots, &algorithms())?;
+        // in an hour, when the stale response is past its next update even
+        // if it was just generated
+        let now = UnixTime::now().as_secs() + 3600;
+        response_status(&cert, &issuer, &fixture(response), &algorithms(), now)
+            .map(|single| single.status)
+    }
+
+    #[test]
+    fn signed_by_issuer() {
+        assert_eq!(
+            status(&["intermediate.cert"], "good.ocsp"),
+            Some(Status::Good)
+        );
+        assert_eq!(
+            status(&["intermediate.cert"], "revoked.ocsp"),
+            Some(Status::Revoked)
+        );
+    }
+
+    #[test]
+    fn signed_by_delegated_responder() {
+        assert_eq!(
+            status(&["intermediate.cert"], "delegated.ocsp"),
+            Some(Status::Revoked)
+        );
+    }
+
+    #[test]
+    fn invalid_responses() {
+        // signed by another key, with the name of the issuer
+        assert_eq!(
+            status(&["forged.cert", "intermediate.cert"], "forged.ocsp"),
+            None
+        );
+        // the issuer isn't sent
+        assert_eq!(status(&[], "good.ocsp"), None);
+        assert_eq!(status(&["forged.cert"], "good.ocsp"), None);
+        // about another certificate
+        assert_eq!(status(&["intermediate.cert"], "other.ocsp"), None);
+        // past its next update
+        assert_eq!(status(&["intermediate.cert"], "stale.ocsp"), None);
+        assert_eq!(status(&["intermediate.cert"], "leaf.cert"), None);
+    }
+
+    #[test]
+    fn responder_and_request() {
+        let leaf = fixture("leaf.cert");
+        let cert = Cert::parse(&leaf).unwrap();
+        assert_eq!(responder(&cert).as_deref(), Some("http://ocsp.test/"));
+        let intermediate = fixture("intermediate.cert");
+        assert!(responder(&Cert::parse(&intermediate).unwrap()).is_none());
+
+        // the request identifies the certificate like the responses do
+        let issuer = Cert::parse(&intermediate).unwrap().spki;
+        let request = request(&cert, &issuer);
+        let mut reader = Reader::new(&request);
+        let cert_id = [SEQUENCE; 4]
+            .iter()
+            .fold(reader.expect(SEQUENCE), |tlv, &tag| {
+                Reader::new(tlv?.value).expect(tag)
+            });
+        assert_eq!(
+            identifies(cert_id.unwrap().value, &cert, &issuer),
+            Some(true)
+        );
+    }
+}
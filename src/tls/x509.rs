@@ -0,0 +1,450 @@
+//! Just enough DER and X.509 parsing to check the certificate transparency
+//! and the OCSP status of server certificates, which rustls leaves to its
+//! verifiers.
+
+use rustls::crypto::hash::{Hash, HashAlgorithm};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::RootCertStore;
+use rustls_pki_types::CertificateDer;
+
+// DER tags
+pub(super) const BOOLEAN: u8 = 0x01;
+pub(super) const INTEGER: u8 = 0x02;
+pub(super) const BIT_STRING: u8 = 0x03;
+pub(super) const OCTET_STRING: u8 = 0x04;
+pub(super) const OID: u8 = 0x06;
+#[cfg(feature = "rustls-tls-ocsp")]
+pub(super) const ENUMERATED: u8 = 0x0a;
+pub(super) const UTC_TIME: u8 = 0x17;
+pub(super) const GENERALIZED_TIME: u8 = 0x18;
+pub(super) const SEQUENCE: u8 = 0x30;
+pub(super) const CONTEXT: u8 = 0x80;
+pub(super) const CONTEXT_CONSTRUCTED: u8 = 0xa0;
+
+/// A subject public key info.
+#[derive(Clone, Copy)]
+pub(super) struct Spki<'a> {
+    /// The contents of its algorithm identifier.
+    pub(super) algorithm: &'a [u8],
+    pub(super) key: &'a [u8],
+}
+
+impl<'a> Spki<'a> {
+    /// Parses the contents of a `SubjectPublicKeyInfo`.
+    pub(super) fn parse(contents: &'a [u8]) -> Option<Spki<'a>> {
+        let mut spki = Reader::new(contents);
+        let algorithm = spki.expect(SEQUENCE)?.value;
+        let key = bit_string(spki.expect(BIT_STRING)?)?;
+        Some(Spki { algorithm, key })
+    }
+
+    /// Encodes the whole `SubjectPublicKeyInfo` again.
+    pub(super) fn to_der(self) -> Vec<u8> {
+        encode(
+            SEQUENCE,
+            &[
+                &encode(SEQUENCE, &[self.algorithm]),
+                &encode(BIT_STRING, &[&[0], self.key]),
+            ],
+        )
+    }
+}
+
+/// The parts of an X.509 certificate that reqwest checks itself.
+pub(super) struct Cert<'a> {
+    pub(super) tbs: &'a [u8],
+    pub(super) signature_algorithm: &'a [u8],
+    pub(super) signature: &'a [u8],
+    /// The contents of the serial number.
+    #[cfg_attr(not(feature = "rustls-tls-ocsp"), allow(dead_code))]
+    pub(super) serial: &'a [u8],
+    /// The contents of the issuer name.
+    pub(super) issuer: &'a [u8],
+    /// The start and end of the validity period, in seconds since the Unix
+    /// epoch.
+    #[cfg_attr(not(feature = "rustls-tls-ocsp"), allow(dead_code))]
+    pub(super) validity: (u64, u64),
+    /// The contents of the subject name.
+    pub(super) subject: &'a [u8],
+    pub(super) spki: Spki<'a>,
+    pub(super) extensions: Option<&'a [u8]>,
+}
+
+impl<'a> Cert<'a> {
+    pub(super) fn parse(der: &'a [u8]) -> Option<Cert<'a>> {
+        let mut outer = Reader::new(der);
+        let mut cert = Reader::new(outer.expect(SEQUENCE)?.value);
+        let tbs = cert.expect(SEQUENCE)?;
+        let signature_algorithm = cert.expect(SEQUENCE)?.value;
+        let signature = bit_string(cert.expect(BIT_STRING)?)?;
+
+        let mut fields = Reader::new(tbs.value);
+        fields.optional(CONTEXT_CONSTRUCTED);
+        let serial = fields.expect(INTEGER)?.value;
+        // signature algorithm
+        fields.expect(SEQUENCE)?;
+        let issuer = fields.expect(SEQUENCE)?.value;
+        let mut validity = Reader::new(fields.expect(SEQUENCE)?.value);
+        let validity = (time(validity.read()?)?, time(validity.read()?)?);
+        let subject = fields.expect(SEQUENCE)?.value;
+        let spki = Spki::parse(fields.expect(SEQUENCE)?.value)?;
+        fields.optional(CONTEXT | 1);
+        fields.optional(CONTEXT | 2);
+        let extensions = fields
+            .optional(CONTEXT_CONSTRUCTED | 3)
+            .map(|ext| ext.value);
+
+        Some(Cert {
+            tbs: tbs.raw,
+            signature_algorithm,
+            signature,
+            serial,
+            issuer,
+            validity,
+            subject,
+            spki,
+            extensions,
+        })
+    }
+
+    /// Returns the contents of the extension `oid`.
+    pub(super) fn extension(&self, oid: &[u8]) -> Option<&'a [u8]> {
+        let mut outer = Reader::new(self.extensions?);
+        let mut extensions = Reader::new(outer.expect(SEQUENCE)?.value);
+        while let Some(extension) = extensions.read() {
+            let mut extension = Reader::new(extension.value);
+            let id = extension.expect(OID)?;
+            extension.optional(BOOLEAN);
+            let value = extension.expect(OCTET_STRING)?;
+            if id.value == oid {
+                return Some(value.value);
+            }
+        }
+        None
+    }
+}
+
+/// Finds the key of the issuer of `cert` among the intermediates, then the
+/// roots.
+///
+/// Candidates are matched by name, and must have signed `cert`.
+pub(super) fn find_issuer<'a>(
+    cert: &Cert<'_>,
+    intermediates: &'a [CertificateDer<'_>],
+    roots: &'a RootCertStore,
+    algorithms: &WebPkiSupportedAlgorithms,
+) -> Option<Spki<'a>> {
+    let signed = |spki: &Spki<'_>| {
+        verify_signature(
+            algorithms,
+            spki,
+            cert.signature_algorithm,
+            cert.tbs,
+            cert.signature,
+        )
+    };
+    let intermediates = intermediates
+        .iter()
+        .filter_map(|der| Cert::parse(der))
+        .filter(|candidate| candidate.subject == cert.issuer)
+        .map(|issuer| issuer.spki);
+    let roots = roots
+        .roots
+        .iter()
+        .filter(|anchor| anchor.subject.as_ref() == cert.issuer)
+        .filter_map(|anchor| Spki::parse(anchor.subject_public_key_info.as_ref()));
+    intermediates.chain(roots).find(|spki| signed(spki))
+}
+
+/// Returns the SHA-256 implementation of `provider`, from its cipher
+/// suites.
+pub(super) fn sha256(provider: &CryptoProvider) -> Option<&'static dyn Hash> {
+    provider
+        .cipher_suites
+        .iter()
+        .filter_map(|suite| Some(suite.tls13()?.common.hash_provider))
+        .find(|hash| hash.algorithm() == HashAlgorithm::SHA256)
+}
+
+/// Returns whether `signature` over `message` is valid for the key of
+/// `spki`, with one of the supported algorithms.
+pub(super) fn verify_signature(
+    algorithms: &WebPkiSupportedAlgorithms,
+    spki: &Spki<'_>,
+    signature_algorithm: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    algorithms
+        .all
+        .iter()
+        .filter(|alg| {
+            alg.public_key_alg_id().as_ref() == spki.algorithm
+                && alg.signature_alg_id().as_ref() == signature_algorithm
+        })
+        .any(|alg| alg.verify_signature(spki.key, message, signature).is_ok())
+}
+
+/// A DER encoded value.
+#[derive(Clone, Copy)]
+pub(super) struct Tlv<'a> {
+    pub(super) tag: u8,
+    pub(super) value: &'a [u8],
+    /// The whole encoding, with the tag and length.
+    pub(super) raw: &'a [u8],
+}
+
+/// Reads DER encoded values one after the other.
+pub(super) struct Reader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn new(input: &'a [u8]) -> Reader<'a> {
+        Reader { input }
+    }
+
+    pub(super) fn read(&mut self) -> Option<Tlv<'a>> {
+        let (&tag, rest) = self.input.split_first()?;
+        // high tag numbers aren't used by the structures read here
+        if tag & 0x1f == 0x1f {
+            return None;
+        }
+        let (&first, mut rest) = rest.split_first()?;
+        let len = if first < 0x80 {
+            usize::from(first)
+        } else {
+            let n = usize::from(first & 0x7f);
+            if n == 0 || n > 4 || rest.len() < n {
+                return None;
+            }
+            let len = rest[..n]
+                .iter()
+                .fold(0usize, |len, &b| (len << 8) | usize::from(b));
+            rest = &rest[n..];
+            len
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let header = self.input.len() - rest.len();
+        let tlv = Tlv {
+            tag,
+            value: &rest[..len],
+            raw: &self.input[..header + len],
+        };
+        self.input = &rest[len..];
+        Some(tlv)
+    }
+
+    pub(super) fn expect(&mut self, tag: u8) -> Option<Tlv<'a>> {
+        self.read().filter(|tlv| tlv.tag == tag)
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    pub(super) fn optional(&mut self, tag: u8) -> Option<Tlv<'a>> {
+        if self.input.first() == Some(&tag) {
+            self.read()
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) fn encode(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let len: usize = parts.iter().map(|part| part.len()).sum();
+    let mut out = vec![tag];
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    for part in parts {
+        out.extend_from_slice(part);
+    }
+    out
+}
+
+/// Returns the seconds since the Unix epoch of a UTCTime or GeneralizedTime,
+/// in UTC and without fractions of seconds, as DER requires.
+pub(super) fn time(tlv: Tlv<'_>) -> Option<u64> {
+    fn digits(input: &[u8], n: usize) -> Option<(u64, &[u8])> {
+        if input.len() < n || !input[..n].iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let value = input[..n]
+            .iter()
+            .fold(0, |value, &d| value * 10 + u64::from(d - b'0'));
+        Some((value, &input[n..]))
+    }
+
+    let (year, mut rest) = match tlv.tag {
+        UTC_TIME => {
+            let (year, rest) = digits(tlv.value, 2)?;
+            (if year < 50 { 2000 + year } else { 1900 + year }, rest)
+        }
+        GENERALIZED_TIME => digits(tlv.value, 4)?,
+        _ => return None,
+    };
+    // month, day, hour, minute, second
+    let mut fields = [0; 5];
+    for field in &mut fields {
+        let (value, next) = digits(rest, 2)?;
+        *field = value;
+        rest = next;
+    }
+    let [month, day, hour, minute, second] = fields;
+    if rest != b"Z"
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    // days since 1970-01-01 in the proleptic Gregorian calendar, counting
+    // years from March so that leap days end them
+    let year = if month <= 2 { year - 1 } else { year };
+    let (era, year_of_era) = (year / 400, year % 400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Returns the contents of a BIT STRING without unused bits.
+pub(super) fn bit_string(tlv: Tlv<'_>) -> Option<&[u8]> {
+    match tlv.value.split_first() {
+        Some((0, bits)) => Some(bits),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cert() {
+        let der = std::fs::read("tests/support/server.cert").unwrap();
+        let cert = Cert::parse(&der).unwrap();
+        assert!(cert.subject.ends_with(b"testserver.com"));
+        assert!(cert.issuer.ends_with(b"hyperium level 2 intermediate"));
+        assert!(cert.extension(&[0x55, 0x1d, 0x11]).is_some());
+        assert!(cert.extension(&[0x55, 0x1d, 0x12]).is_none());
+        assert!(cert.validity.0 < cert.validity.1);
+    }
+
+    #[test]
+    fn times() {
+        let time = |tag, value: &[u8]| {
+            time(Tlv {
+                tag,
+                value,
+                raw: &[],
+            })
+        };
+        assert_eq!(time(UTC_TIME, b"700101000000Z"), Some(0));
+        assert_eq!(time(UTC_TIME, b"491231235959Z"), Some(2_524_607_999));
+        assert_eq!(
+            time(GENERALIZED_TIME, b"20000229120000Z"),
+            Some(951_825_600)
+        );
+        assert_eq!(
+            time(GENERALIZED_TIME, b"21240101000000Z"),
+            Some(4_859_740_800)
+        );
+        for value in [
+            &b"20240101000000"[..],
+            b"20240101000000.5Z",
+            b"20240101000000+0100",
+            b"20241301000000Z",
+            b"19691231235959Z",
+            b"2024010100000aZ",
+        ] {
+            assert_eq!(time(GENERALIZED_TIME, value), None, "{value:?}");
+        }
+        assert_eq!(time(OCTET_STRING, b"20240101000000Z"), None);
+    }
+
+    #[test]
+    fn read_lengths() {
+        let long = vec![7; 300];
+        let der = [encode(OCTET_STRING, &[&[1, 2]]), encode(SEQUENCE, &[&long])].concat();
+        let mut reader = Reader::new(&der);
+        let short = reader.expect(OCTET_STRING).unwrap();
+        assert_eq!(short.value, [1, 2]);
+        assert_eq!(short.raw, [OCTET_STRING, 2, 1, 2]);
+        let tlv = reader.expect(SEQUENCE).unwrap();
+        assert_eq!(tlv.value, &long[..]);
+        assert_eq!(&tlv.raw[..4], [SEQUENCE, 0x82, 0x01, 0x2c]);
+        assert!(reader.is_empty());
+        assert!(reader.read().is_none());
+    }
+
+    #[test]
+    fn read_malformed() {
+        for der in [
+            // truncated
+            &[OCTET_STRING, 3, 1, 2][..],
+            &[OCTET_STRING, 0x82, 0x01][..],
+            &[OCTET_STRING][..],
+            // indefinite length
+            &[SEQUENCE, 0x80, 0, 0][..],
+            // lengths over 4 bytes
+            &[OCTET_STRING, 0x85, 0, 0, 0, 0, 1, 0][..],
+            // high tag number
+            &[0x1f, 0x81, 0x01, 0][..],
+        ] {
+            assert!(Reader::new(der).read().is_none(), "{der:?}");
+        }
+        assert!(Reader::new(&[OID, 0]).expect(SEQUENCE).is_none());
+        assert!(Reader::new(&[OID, 0]).optional(SEQUENCE).is_none());
+
+        let unused_bits = Reader::new(&[BIT_STRING, 2, 1, 0]).read().unwrap();
+        assert!(bit_string(unused_bits).is_none());
+    }
+
+    #[cfg(feature = "__rustls-ring")]
+    #[test]
+    fn find_issuer_by_signature() {
+        let fixture = |name: &str| std::fs::read(format!("tests/support/ct/{name}")).unwrap();
+        let der = fixture("leaf.cert");
+        let cert = Cert::parse(&der).unwrap();
+        let root = fixture("root.cert");
+        let issuer_key = Cert::parse(&root).unwrap().spki.to_der();
+        let algorithms = rustls::crypto::ring::default_provider().signature_verification_algorithms;
+
+        let intermediates = [CertificateDer::from(root.clone())];
+        let roots = RootCertStore::empty();
+        let issuer = find_issuer(&cert, &intermediates, &roots, &algorithms).unwrap();
+        assert_eq!(issuer.to_der(), issuer_key);
+
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from(root)).unwrap();
+        let issuer = find_issuer(&cert, &[], &roots, &algorithms).unwrap();
+        assert_eq!(issuer.to_der(), issuer_key);
+
+        // another certificate with the name of the issuer
+        let mut forged = RootCertStore::empty();
+        forged.roots.push(rustls_pki_types::TrustAnchor {
+            subject: cert.issuer.to_vec().into(),
+            subject_public_key_info: Reader::new(&cert.spki.to_der())
+                .expect(SEQUENCE)
+                .unwrap()
+                .value
+                .to_vec()
+                .into(),
+            name_constraints: None,
+        });
+        assert!(find_issuer(&cert, &[], &forged, &algorithms).is_none());
+    }
+}
@@ -0,0 +1,212 @@
+//! Trace context propagation
+//!
+//! Distributed tracing follows a request across services by sending the
+//! current trace context along with it, in the `traceparent` and
+//! `tracestate` headers of [W3C Trace Context](https://www.w3.org/TR/trace-context/),
+//! and optionally the `baggage` header of [W3C Baggage](https://www.w3.org/TR/baggage/).
+//!
+//! reqwest doesn't depend on a tracing library. Instead,
+//! [`ClientBuilder::trace_context()`](crate::ClientBuilder::trace_context)
+//! takes a [`ContextSource`], usually a closure, which returns the current
+//! [`TraceContext`] when a request is sent. Its headers are added to the
+//! request, and to the requests of any redirects and retries, unless the
+//! request already has them.
+//!
+//! # Example
+//!
+//! With OpenTelemetry, the source reads the span context of the current
+//! `opentelemetry::Context`:
+//!
+//! ```ignore
+//! use opentelemetry::trace::TraceContextExt;
+//! use reqwest::trace_context::TraceContext;
+//!
+//! let client = reqwest::Client::builder()
+//!     .trace_context(|| {
+//!         let cx = opentelemetry::Context::current();
+//!         let span = cx.span().span_context().clone();
+//!         if !span.is_valid() {
+//!             return None;
+//!         }
+//!         let trace_id = u128::from_be_bytes(span.trace_id().to_bytes());
+//!         let span_id = u64::from_be_bytes(span.span_id().to_bytes());
+//!         Some(
+//!             TraceContext::new(trace_id, span_id)
+//!                 .sampled(span.is_sampled())
+//!                 .trace_state(span.trace_state().header()),
+//!         )
+//!     })
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::fmt;
+
+use http::header::{Entry, HeaderMap, HeaderName, HeaderValue};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// The characters that are percent-encoded in baggage values.
+const BAGGAGE_VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b',')
+    .add(b';')
+    .add(b'\\')
+    .add(b'%');
+
+/// The trace context of the current span, sent with requests.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    sampled: bool,
+    trace_state: Option<String>,
+    baggage: Vec<(String, String)>,
+}
+
+/// A source of the current trace context.
+///
+/// This is implemented for closures returning an `Option<TraceContext>`.
+pub trait ContextSource: Send + Sync + 'static {
+    /// Returns the trace context of the current span, or `None` if there
+    /// is none.
+    fn current(&self) -> Option<TraceContext>;
+}
+
+impl<F> ContextSource for F
+where
+    F: Fn() -> Option<TraceContext> + Send + Sync + 'static,
+{
+    fn current(&self) -> Option<TraceContext> {
+        self()
+    }
+}
+
+// ===== impl TraceContext =====
+
+impl TraceContext {
+    /// Creates a trace context for the span `span_id` of the trace
+    /// `trace_id`.
+    ///
+    /// The span is sampled by default.
+    pub fn new(trace_id: u128, span_id: u64) -> TraceContext {
+        TraceContext {
+            trace_id,
+            span_id,
+            sampled: true,
+            trace_state: None,
+            baggage: Vec::new(),
+        }
+    }
+
+    /// Sets whether the span is sampled.
+    pub fn sampled(mut self, sampled: bool) -> TraceContext {
+        self.sampled = sampled;
+        self
+    }
+
+    /// Sets the vendor-specific `tracestate`, such as `"vendor=value"`.
+    ///
+    /// An empty trace state isn't sent.
+    pub fn trace_state(mut self, trace_state: impl Into<String>) -> TraceContext {
+        let trace_state = trace_state.into();
+        self.trace_state = if trace_state.is_empty() {
+            None
+        } else {
+            Some(trace_state)
+        };
+        self
+    }
+
+    /// Adds an entry to the `baggage`.
+    pub fn baggage(mut self, key: impl Into<String>, value: impl Into<String>) -> TraceContext {
+        self.baggage.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the value of the `traceparent` header.
+    pub fn traceparent(&self) -> String {
+        let flags = if self.sampled { 1 } else { 0 };
+        format!("00-{:032x}-{:016x}-{flags:02x}", self.trace_id, self.span_id)
+    }
+
+    /// Adds the headers of the context to `headers`, keeping the ones
+    /// already present.
+    pub(crate) fn inject(&self, headers: &mut HeaderMap) {
+        // all-zero ids are invalid
+        if self.trace_id == 0 || self.span_id == 0 {
+            return;
+        }
+        let traceparent = HeaderValue::try_from(self.traceparent());
+        insert(headers, "traceparent", traceparent.ok());
+
+        let trace_state = self.trace_state.as_deref().map(HeaderValue::try_from);
+        insert(headers, "tracestate", trace_state.and_then(Result::ok));
+
+        if !self.baggage.is_empty() {
+            let baggage = self
+                .baggage
+                .iter()
+                .map(|(key, value)| format!("{key}={}", utf8_percent_encode(value, BAGGAGE_VALUE)))
+                .collect::<Vec<_>>()
+                .join(",");
+            insert(headers, "baggage", HeaderValue::try_from(baggage).ok());
+        }
+    }
+}
+
+fn insert(headers: &mut HeaderMap, name: &'static str, value: Option<HeaderValue>) {
+    if let (Entry::Vacant(entry), Some(value)) =
+        (headers.entry(HeaderName::from_static(name)), value)
+    {
+        entry.insert(value);
+    }
+}
+
+impl fmt::Debug for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TraceContext")
+            .field("traceparent", &self.traceparent())
+            .field("trace_state", &self.trace_state)
+            .field("baggage", &self.baggage)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inject_headers() {
+        let cx = TraceContext::new(0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7)
+            .trace_state("congo=t61rcWkgMzE")
+            .baggage("user", "jane doe")
+            .baggage("tier", "1");
+        let mut headers = HeaderMap::new();
+        cx.inject(&mut headers);
+        assert_eq!(
+            headers["traceparent"],
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(headers["tracestate"], "congo=t61rcWkgMzE");
+        assert_eq!(headers["baggage"], "user=jane%20doe,tier=1");
+
+        // existing headers are kept
+        let cx = TraceContext::new(1, 2).sampled(false);
+        assert_eq!(cx.traceparent(), format!("00-{:032x}-{:016x}-00", 1, 2));
+        cx.inject(&mut headers);
+        assert_eq!(
+            headers["traceparent"],
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+    }
+
+    #[test]
+    fn invalid_ids_are_not_sent() {
+        let mut headers = HeaderMap::new();
+        TraceContext::new(0, 1).inject(&mut headers);
+        TraceContext::new(1, 0).inject(&mut headers);
+        assert!(headers.is_empty());
+    }
+}
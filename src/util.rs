@@ -1,4 +1,4 @@
-use crate::header::{Entry, HeaderMap, HeaderValue, OccupiedEntry};
+use crate::header::{Entry, HeaderMap, HeaderName, HeaderValue, OccupiedEntry};
 
 pub fn basic_auth<U, P>(username: U, password: Option<P>) -> HeaderValue
 where
@@ -87,3 +87,83 @@ pub(crate) fn replace_headers(dst: &mut HeaderMap, src: HeaderMap) {
         }
     }
 }
+
+/// Returns the name of the first header whose value isn't visible ASCII
+/// (or space and horizontal tab).
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn find_non_visible_ascii_header(headers: &HeaderMap) -> Option<&HeaderName> {
+    headers
+        .iter()
+        .find(|(_, value)| !is_visible_ascii(value.as_bytes()))
+        .map(|(name, _)| name)
+}
+
+/// Replace every character of each header value that isn't visible ASCII
+/// with `?`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn sanitize_header_values(headers: &mut HeaderMap) {
+    for value in headers.values_mut() {
+        if is_visible_ascii(value.as_bytes()) {
+            continue;
+        }
+
+        let sanitized = String::from_utf8_lossy(value.as_bytes())
+            .chars()
+            .map(|c| match c {
+                '\t' | ' '..='~' => c,
+                _ => '?',
+            })
+            .collect::<String>();
+        let mut sanitized =
+            HeaderValue::from_str(&sanitized).expect("visible ASCII is always a valid HeaderValue");
+        sanitized.set_sensitive(value.is_sensitive());
+        *value = sanitized;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_visible_ascii(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .all(|&b| b == b'\t' || (b' '..=b'~').contains(&b))
+}
+
+/// Validates a `Host` header value, which is a host with an optional port.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn host_header_value(host: &str) -> crate::Result<HeaderValue> {
+    let valid = !host.is_empty()
+        && !host.contains('@')
+        && host.parse::<http::uri::Authority>().is_ok();
+    if !valid {
+        return Err(crate::error::builder(format!("invalid Host header: {host:?}")));
+    }
+    HeaderValue::from_str(host).map_err(crate::error::builder)
+}
+
+/// Builds a `Priority` header value (RFC 9218), with an urgency from 0, the
+/// highest, to 7.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn priority_header_value(urgency: u8, incremental: bool) -> crate::Result<HeaderValue> {
+    if urgency > 7 {
+        return Err(crate::error::builder(format!(
+            "priority urgency must be from 0 to 7, got {urgency}"
+        )));
+    }
+    let value = if incremental {
+        format!("u={urgency}, i")
+    } else {
+        format!("u={urgency}")
+    };
+    HeaderValue::from_str(&value).map_err(crate::error::builder)
+}
+
+/// Deserializes a JSON document, with the path of the failing value in the
+/// error.
+#[cfg(feature = "json")]
+pub(crate) fn json_with_path<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> crate::Result<T> {
+    let mut de = serde_json::Deserializer::from_slice(bytes);
+    let value = serde_path_to_error::deserialize(&mut de).map_err(crate::error::decode)?;
+    // like `serde_json::from_slice`, reject trailing characters
+    de.end().map_err(crate::error::decode)?;
+    Ok(value)
+}
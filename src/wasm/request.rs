@@ -266,7 +266,7 @@ impl RequestBuilder {
                     Ok(value) => {
                         req.headers_mut().append(key, value);
                     }
-                    Err(e) => error = Some(crate::error::builder(e.into())),
+                    Err(e) => error = Some(crate::error::invalid_header_value(key, e.into())),
                 },
                 Err(e) => error = Some(crate::error::builder(e.into())),
             };
@@ -95,6 +95,16 @@ impl Response {
         serde_json::from_slice(&full).map_err(crate::error::decode)
     }
 
+    /// Try to deserialize the response body as JSON, reporting where in the
+    /// document deserialization failed.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn json_with_path<T: DeserializeOwned>(self) -> crate::Result<T> {
+        let full = self.bytes().await?;
+
+        crate::util::json_with_path(&full)
+    }
+
     /// Get the response text.
     pub async fn text(self) -> crate::Result<String> {
         let p = self
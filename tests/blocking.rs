@@ -102,6 +102,7 @@ fn test_get() {
     assert_eq!(res.url().as_str(), &url);
     assert_eq!(res.status(), reqwest::StatusCode::OK);
     assert_eq!(res.remote_addr(), Some(server.addr()));
+    assert!(res.local_addr().expect("local_addr").ip().is_loopback());
 
     assert_eq!(res.text().unwrap().len(), 0)
 }
@@ -199,6 +200,37 @@ fn test_error_for_status_5xx() {
     );
 }
 
+/// Calling `Response::error_for_status_with_body` keeps the start of the
+/// body in the error.
+#[test]
+fn test_error_for_status_with_body() {
+    let server = server::http(move |req| async move {
+        let status = if req.uri() == "/ok" { 200 } else { 422 };
+        http::Response::builder()
+            .status(status)
+            .body(r#"{"error":"invalid name"}"#.into())
+            .unwrap()
+    });
+
+    let url = format!("http://{}/1", server.addr());
+    let res = reqwest::blocking::get(&url).unwrap();
+
+    let err = res.error_for_status_with_body(16).unwrap_err();
+    assert!(err.is_status());
+    assert_eq!(
+        err.status(),
+        Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY)
+    );
+    assert_eq!(err.status_body(), Some(&br#"{"error":"invali"#[..]));
+
+    let url = format!("http://{}/ok", server.addr());
+    let res = reqwest::blocking::get(&url)
+        .unwrap()
+        .error_for_status_with_body(16)
+        .unwrap();
+    assert_eq!(res.text().unwrap(), r#"{"error":"invalid name"}"#);
+}
+
 #[test]
 fn test_default_headers() {
     let server = server::http(move |req| async move {
@@ -357,6 +389,44 @@ fn test_body_from_bytes() {
     assert_eq!(request.body().unwrap().as_bytes(), Some(body.as_bytes()));
 }
 
+#[test]
+fn test_body_from_iter() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/error" {
+            return http::Response::default();
+        }
+        assert_eq!(req.headers()["transfer-encoding"], "chunked");
+
+        let data = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&*data, b"a,1\nb,2\nc,3\n");
+
+        http::Response::default()
+    });
+
+    let rows = (1..=3)
+        .zip('a'..)
+        .map(|(n, name)| Ok::<_, std::io::Error>(format!("{name},{n}\n").into()));
+    let url = format!("http://{}/csv", server.addr());
+    let res = reqwest::blocking::Client::new()
+        .post(&url)
+        .body(reqwest::blocking::Body::from_iter(rows))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let chunks = vec![
+        Ok(bytes::Bytes::from("a")),
+        Err(std::io::Error::new(std::io::ErrorKind::Other, "row")),
+    ];
+    let url = format!("http://{}/error", server.addr());
+    let err = reqwest::blocking::Client::new()
+        .post(&url)
+        .body(reqwest::blocking::Body::from_iter(chunks))
+        .send()
+        .unwrap_err();
+    assert!(err.is_body());
+}
+
 #[test]
 #[cfg(feature = "json")]
 fn blocking_add_json_default_content_type_if_not_set_manually() {
@@ -411,3 +481,68 @@ fn test_response_no_tls_info_for_http() {
     let body = res.text().unwrap();
     assert_eq!(b"Hello", body.as_bytes());
 }
+
+#[test]
+fn send_cancellable() {
+    use std::time::{Duration, Instant};
+
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/slow" {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+        http::Response::default()
+    });
+
+    let client = reqwest::blocking::Client::new();
+
+    let url = format!("http://{}/slow", server.addr());
+    let (pending, cancel) = client.get(&url).send_cancellable();
+    let start = Instant::now();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        cancel.cancel();
+    });
+    let err = pending.wait().unwrap_err();
+    assert!(err.is_canceled(), "{err:?}");
+    assert_eq!(err.url().map(|u| u.as_str()), Some(&*url));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // canceled before waiting, it isn't sent at all
+    let (pending, cancel) = client.get(&url).send_cancellable();
+    cancel.cancel();
+    assert!(pending.wait().unwrap_err().is_canceled());
+
+    let url = format!("http://{}/fast", server.addr());
+    let (pending, cancel) = client.get(&url).send_cancellable();
+    let res = pending.wait().unwrap();
+    cancel.cancel();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[test]
+fn test_blocking_http_client_trait() {
+    use reqwest::blocking::{BlockingHttpClient, Request, Response};
+
+    fn get_text(client: &dyn BlockingHttpClient, url: &str) -> reqwest::Result<String> {
+        let req = Request::new(reqwest::Method::GET, url.parse().unwrap());
+        client.execute(req)?.text()
+    }
+
+    struct Mock;
+
+    impl BlockingHttpClient for Mock {
+        fn execute(&self, req: Request) -> reqwest::Result<Response> {
+            Ok(http::Response::new(format!("mocked {}", req.url().path())).into())
+        }
+    }
+
+    let server = server::http(move |_req| async { http::Response::new("real".into()) });
+    let url = format!("http://{}/text", server.addr());
+
+    let client = reqwest::blocking::Client::new();
+    assert_eq!(get_text(&client, &url).unwrap(), "real");
+    assert_eq!(get_text(&Mock, &url).unwrap(), "mocked /text");
+
+    let boxed: Box<dyn BlockingHttpClient + Send + Sync> = Box::new(std::sync::Arc::new(client));
+    assert_eq!(get_text(&boxed, &url).unwrap(), "real");
+}
@@ -4,7 +4,7 @@ mod support;
 
 use support::server;
 
-use http::header::{CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING};
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING};
 #[cfg(feature = "json")]
 use std::collections::HashMap;
 
@@ -58,6 +58,40 @@ async fn auto_headers() {
     assert_eq!(res.url().as_str(), &url);
     assert_eq!(res.status(), reqwest::StatusCode::OK);
     assert_eq!(res.remote_addr(), Some(server.addr()));
+    let local_addr = res.local_addr().expect("local_addr");
+    assert!(local_addr.ip().is_loopback());
+    assert_ne!(local_addr.port(), server.addr().port());
+}
+
+#[tokio::test]
+async fn no_default_accept() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers().get("accept"), None);
+        assert_eq!(req.headers().get("user-agent"), None);
+        http::Response::default()
+    });
+    let url = format!("http://{}/", server.addr());
+
+    let res = reqwest::Client::builder()
+        .no_default_accept()
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let res = reqwest::Client::builder()
+        .user_agent("test")
+        .clear_default_headers()
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
 
 #[tokio::test]
@@ -147,6 +181,8 @@ async fn response_text() {
         .await
         .expect("Failed to get");
     assert_eq!(res.content_length(), Some(5));
+    assert_eq!(res.content_encoding(), None);
+    assert_eq!(res.compressed_length(), None);
     let text = res.text().await.expect("Failed to get text");
     assert_eq!("Hello", text);
 }
@@ -169,6 +205,59 @@ async fn response_bytes() {
     assert_eq!("Hello", bytes);
 }
 
+#[tokio::test]
+async fn error_for_status_with_body() {
+    let server = server::http(move |_req| async {
+        http::Response::builder()
+            .status(503)
+            .body("try again later".into())
+            .unwrap()
+    });
+
+    let url = format!("http://{}/busy", server.addr());
+    let res = reqwest::get(&url).await.unwrap();
+
+    let err = res.error_for_status_with_body(1024).await.unwrap_err();
+    assert!(err.is_status());
+    assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(err.status_body(), Some(&b"try again later"[..]));
+    assert_eq!(
+        std::error::Error::source(&err).unwrap().to_string(),
+        "response body: try again later"
+    );
+
+    let err = reqwest::get(&url)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap_err();
+    assert_eq!(err.status_body(), None);
+}
+
+#[tokio::test]
+#[cfg(feature = "json")]
+async fn canonical_json_body() {
+    let server = server::http(move |req| async move {
+        let body = http_body_util::BodyExt::collect(req.into_body())
+            .await
+            .unwrap()
+            .to_bytes();
+        assert_eq!(body, r#"{"a":[1.5,1e+21],"b":"\u0001","c":null}"#);
+        http::Response::default()
+    });
+
+    let client = Client::builder().canonical_json(true).build().unwrap();
+    let body = serde_json::json!({
+        "c": null,
+        "a": [1.5, 1e21],
+        "b": "\u{1}",
+    });
+
+    let url = format!("http://{}/json", server.addr());
+    let res = client.post(&url).json(&body).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
 #[tokio::test]
 #[cfg(feature = "json")]
 async fn response_json() {
@@ -187,6 +276,95 @@ async fn response_json() {
     assert_eq!("Hello", text);
 }
 
+#[tokio::test]
+#[cfg(feature = "json")]
+async fn response_json_with_path() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| async {
+        http::Response::new(r#"{"items": [{"id": 1}, {"id": "two"}]}"#.into())
+    });
+
+    let client = Client::new();
+    let url = format!("http://{}/json", server.addr());
+
+    let res = client.get(&url).send().await.unwrap();
+    let value = res.json_with_path::<serde_json::Value>().await.unwrap();
+    assert_eq!(value["items"][1]["id"], "two");
+
+    let res = client.get(&url).send().await.unwrap();
+    let err = res
+        .json_with_path::<HashMap<String, Vec<HashMap<String, u32>>>>()
+        .await
+        .unwrap_err();
+    assert!(err.is_decode());
+    let source = std::error::Error::source(&err).unwrap().to_string();
+    assert!(
+        source.starts_with("items[1].id"),
+        "unexpected error: {source}"
+    );
+}
+
+#[tokio::test]
+#[cfg(feature = "json")]
+async fn response_ensure_content_type() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        let content_type = if req.uri() == "/json" {
+            "application/problem+json; charset=utf-8"
+        } else {
+            "text/html"
+        };
+        http::Response::builder()
+            .header("content-type", content_type)
+            .body("\"Hello\"".into())
+            .unwrap()
+    });
+
+    let json_url = format!("http://{}/json", server.addr());
+    let html_url = format!("http://{}/html", server.addr());
+
+    let res = reqwest::get(&json_url).await.unwrap();
+    let res = res.ensure_content_type("Application/JSON").unwrap();
+    let res = res.ensure_content_type("application/*").unwrap();
+    let err = res.ensure_content_type("text/*").unwrap_err();
+    assert!(err.is_decode());
+    assert!(err.is_unexpected_content_type());
+    assert_eq!(err.url().map(|u| u.as_str()), Some(&*json_url));
+
+    let res = reqwest::get(&html_url).await.unwrap();
+    let err = res.ensure_content_type("not a type").unwrap_err();
+    assert!(err.is_builder());
+
+    let client = Client::builder()
+        .expect_content_type("application/json")
+        .build()
+        .unwrap();
+
+    let text = client.get(&json_url).send().await.unwrap().text().await;
+    assert_eq!(text.unwrap(), "\"Hello\"");
+
+    let err = client
+        .get(&html_url)
+        .send()
+        .await
+        .unwrap()
+        .json::<String>()
+        .await
+        .unwrap_err();
+    assert!(err.is_unexpected_content_type());
+    let source = std::error::Error::source(&err).unwrap().to_string();
+    assert_eq!(
+        source,
+        "expected content type `application/json`, got `text/html`"
+    );
+
+    // bytes() isn't checked
+    let bytes = client.get(&html_url).send().await.unwrap().bytes().await;
+    assert_eq!(bytes.unwrap(), "\"Hello\"");
+}
+
 #[tokio::test]
 async fn body_pipe_response() {
     use http_body_util::BodyExt;
@@ -292,6 +470,82 @@ async fn overridden_dns_resolution_with_gai_multiple() {
     assert_eq!("Hello", text);
 }
 
+#[tokio::test]
+async fn overridden_dns_resolution_for_one_request() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    let overridden_domain = "blue.test";
+    let url = format!(
+        "http://{overridden_domain}:{}/domain_override",
+        server.addr().port()
+    );
+    // the server only listens on IPv4
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .resolve(overridden_domain, "[::1]:0".parse().unwrap())
+        .build()
+        .expect("client builder");
+
+    let mut overrides = std::collections::HashMap::new();
+    overrides.insert("Blue.test".to_owned(), vec!["127.0.0.1:0".parse().unwrap()]);
+    let res = client
+        .get(&url)
+        .resolve_map(overrides)
+        .send()
+        .await
+        .expect("request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let text = res.text().await.expect("Failed to get text");
+    assert_eq!("Hello", text);
+
+    // the pooled connection isn't used for other requests
+    let err = client.get(&url).send().await.unwrap_err();
+    assert!(err.is_connect());
+}
+
+#[tokio::test]
+async fn pool_events_report_connection_churn() {
+    use reqwest::pool::PoolEventKind;
+    use std::sync::{Arc, Mutex};
+
+    let _ = env_logger::builder().is_test(true).try_init();
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .pool_events(move |event| {
+            assert_eq!(event.host(), "127.0.0.1");
+            assert_eq!(event.version(), http::Version::HTTP_11);
+            events2.lock().unwrap().push(event.kind());
+        })
+        .build()
+        .expect("client builder");
+
+    let url = format!("http://{}/churn", server.addr());
+    for _ in 0..2 {
+        let res = client.get(&url).send().await.expect("request");
+        assert_eq!(res.text().await.expect("text"), "Hello");
+    }
+    assert_eq!(
+        *events.lock().unwrap(),
+        [PoolEventKind::Created, PoolEventKind::Reused]
+    );
+
+    drop(client);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            PoolEventKind::Created,
+            PoolEventKind::Reused,
+            PoolEventKind::Evicted
+        ]
+    );
+}
+
 #[cfg(feature = "hickory-dns")]
 #[tokio::test]
 async fn overridden_dns_resolution_with_hickory_dns() {
@@ -395,6 +649,345 @@ fn use_preconfigured_rustls_default() {
         .expect("preconfigured rustls tls");
 }
 
+#[cfg(feature = "__rustls-ring")]
+#[tokio::test]
+async fn tls_config_for_host() {
+    use reqwest::tls::TlsOptions;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let cert = std::fs::read("tests/support/server.cert").unwrap();
+    let key = std::fs::read("tests/support/server.key").unwrap();
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.into()], key.try_into().unwrap())
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(mut tls) = acceptor.accept(tcp).await {
+                    let mut buf = [0; 1024];
+                    let _ = tls.read(&mut buf).await;
+                    let _ = tls
+                        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                        .await;
+                }
+            });
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .resolve("trusted.test", addr)
+        .resolve("other.test", addr)
+        .tls_config_for_host(
+            "trusted.test",
+            TlsOptions::new().danger_accept_invalid_certs(true),
+        )
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("https://trusted.test:{}/", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let err = client
+        .get(format!("https://other.test:{}/", addr.port()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect());
+}
+
+#[cfg(feature = "__rustls-ring")]
+#[tokio::test]
+async fn tls_sni_hostname() {
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let cert = std::fs::read("tests/support/server.cert").unwrap();
+    let key = std::fs::read("tests/support/server.key").unwrap();
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.into()], key.try_into().unwrap())
+        .unwrap();
+    let config = Arc::new(config);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let acceptor =
+            tokio_rustls::LazyConfigAcceptor::new(rustls::server::Acceptor::default(), tcp);
+        let start = acceptor.await.unwrap();
+        let sni = start.client_hello().server_name().map(str::to_owned);
+        let mut tls = start.into_stream(config).await.unwrap();
+        let mut buf = vec![0; 1024];
+        let n = tls.read(&mut buf).await.unwrap();
+        buf.truncate(n);
+        tx.send((sni, String::from_utf8(buf).unwrap())).unwrap();
+        tls.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .unwrap();
+    });
+
+    let client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(true)
+        .resolve("origin.test", addr)
+        .tls_sni_hostname("origin.test", "front.test")
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("https://origin.test:{}/", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let (sni, head) = rx.recv().await.unwrap();
+    assert_eq!(sni.as_deref(), Some("front.test"));
+    assert!(head.contains(&format!("host: origin.test:{}\r\n", addr.port())));
+}
+
+#[cfg(all(feature = "__rustls-ring", feature = "rustls-tls-ocsp"))]
+#[tokio::test]
+async fn tls_ocsp_check() {
+    use reqwest::tls::{Certificate, OcspPolicy};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let fixture = |name: &str| std::fs::read(format!("tests/support/ocsp/{name}")).unwrap();
+
+    // serves the test certificate, with `ocsp` stapled
+    let serve = |ocsp: Vec<u8>| {
+        let chain = vec![
+            fixture("leaf.cert").into(),
+            fixture("intermediate.cert").into(),
+        ];
+        let config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert_with_ocsp(chain, fixture("leaf.key").try_into().unwrap(), ocsp)
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut tls) = acceptor.accept(tcp).await {
+                        let mut buf = [0; 1024];
+                        let _ = tls.read(&mut buf).await;
+                        let _ = tls
+                            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                            .await;
+                    }
+                });
+            }
+        });
+        addr
+    };
+    let client = |policy: OcspPolicy| {
+        Client::builder()
+            .use_rustls_tls()
+            .add_root_certificate(Certificate::from_der(&fixture("root.cert")).unwrap())
+            .tls_ocsp_check(policy)
+    };
+
+    let addr = serve(fixture("good.ocsp"));
+    let res = client(OcspPolicy::hard_fail())
+        .build()
+        .unwrap()
+        .get(format!("https://localhost:{}/", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let addr = serve(fixture("revoked.ocsp"));
+    let err = client(OcspPolicy::soft_fail())
+        .build()
+        .unwrap()
+        .get(format!("https://localhost:{}/", addr.port()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_certificate_revoked(), "{err:?}");
+
+    // the status is checked without hostname verification too
+    let addr = serve(Vec::new());
+    let url = format!("https://127.0.0.1:{}/", addr.port());
+    let err = client(OcspPolicy::hard_fail())
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect(), "{err:?}");
+    let res = client(OcspPolicy::soft_fail())
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // without a stapled response, the responder named in the certificate is
+    // queried, through the proxy of the client
+    let responder = |response: &'static str| {
+        let queries = Arc::new(AtomicUsize::new(0));
+        let counter = queries.clone();
+        let server = server::http(move |req| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(req.method(), "POST");
+            assert_eq!(req.uri(), "http://ocsp.test/");
+            assert_eq!(req.headers()["content-type"], "application/ocsp-request");
+            async move { http::Response::new(fixture(response).into()) }
+        });
+        let proxy = reqwest::Proxy::http(format!("http://{}", server.addr())).unwrap();
+        (server, proxy, queries)
+    };
+    let policy = || OcspPolicy::hard_fail().fetch_responses(Duration::from_secs(5));
+    let url = format!("https://localhost:{}/", addr.port());
+
+    let (_server, proxy, queries) = responder("good.ocsp");
+    let fetching = client(policy())
+        .proxy(proxy)
+        .pool_max_idle_per_host(0)
+        .build()
+        .unwrap();
+    for _ in 0..2 {
+        let res = fetching.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+    }
+    // the status is cached until the responder's next update
+    assert_eq!(queries.load(Ordering::SeqCst), 1);
+
+    let (_server, proxy, _) = responder("revoked.ocsp");
+    let err = client(policy())
+        .proxy(proxy)
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_certificate_revoked(), "{err:?}");
+
+    let err = client(OcspPolicy::soft_fail())
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.conflicts().unwrap(),
+        ["`tls_ocsp_check()` can't be combined with `danger_accept_invalid_certs()`"]
+    );
+}
+
+#[cfg(feature = "__rustls-ring")]
+#[tokio::test]
+async fn tls_ct_policy() {
+    use reqwest::tls::{Certificate, CtLog, CtPolicy};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let fixture = |name: &str| std::fs::read(format!("tests/support/ct/{name}")).unwrap();
+
+    // serves the test certificate, which embeds SCTs from both test logs
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![fixture("leaf.cert").into()],
+            fixture("leaf.key").try_into().unwrap(),
+        )
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(mut tls) = acceptor.accept(tcp).await {
+                    let mut buf = [0; 1024];
+                    let _ = tls.read(&mut buf).await;
+                    let _ = tls
+                        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                        .await;
+                }
+            });
+        }
+    });
+
+    let client = |min_scts: usize| {
+        let logs = ["ecdsa-log.key", "rsa-log.key"]
+            .iter()
+            .map(|name| CtLog::from_der(&fixture(name)).unwrap());
+        Client::builder()
+            .use_rustls_tls()
+            .add_root_certificate(Certificate::from_der(&fixture("root.cert")).unwrap())
+            .tls_ct_policy(CtPolicy::new(logs).min_scts(min_scts))
+    };
+
+    let res = client(2)
+        .build()
+        .unwrap()
+        .get(format!("https://localhost:{port}/"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let err = client(3)
+        .build()
+        .unwrap()
+        .get(format!("https://localhost:{port}/"))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect(), "{err:?}");
+
+    // the policy applies without hostname verification too
+    let err = client(3)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .unwrap()
+        .get(format!("https://127.0.0.1:{port}/"))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect(), "{err:?}");
+
+    let err = client(2)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.conflicts().unwrap(),
+        ["`tls_ct_policy()` can't be combined with `danger_accept_invalid_certs()`"]
+    );
+}
+
 #[cfg(feature = "__rustls")]
 #[tokio::test]
 #[ignore = "Needs TLS support in the test server"]
@@ -593,3 +1186,1976 @@ async fn close_connection_after_idle_timeout() {
         .iter()
         .any(|e| matches!(e, server::Event::ConnectionClosed)));
 }
+
+#[tokio::test]
+async fn paginate_follows_next_links() {
+    use futures_util::StreamExt;
+
+    let server = server::http(move |req| async move {
+        let page: usize = req
+            .uri()
+            .query()
+            .and_then(|q| q.strip_prefix("page="))
+            .unwrap_or("1")
+            .parse()
+            .unwrap();
+        assert_eq!(req.headers()["x-token"], "abc");
+
+        let mut res = http::Response::builder();
+        if page < 3 {
+            res = res.header(
+                "link",
+                format!(
+                    "</items?page={}>; rel=\"next\", </items?page=3>; rel=\"last\"",
+                    page + 1
+                ),
+            );
+        }
+        res.body(reqwest::Body::from(page.to_string())).unwrap()
+    });
+
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    let url = format!("http://{}/items", server.addr());
+
+    let pages = client
+        .get(&url)
+        .header("x-token", "abc")
+        .paginate()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(pages.len(), 3);
+    let mut bodies = Vec::new();
+    for page in pages {
+        bodies.push(page.unwrap().text().await.unwrap());
+    }
+    assert_eq!(bodies, ["1", "2", "3"]);
+
+    let pages = client
+        .get(&url)
+        .header("x-token", "abc")
+        .paginate()
+        .max_pages(2)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(pages.len(), 2);
+    let last = pages.into_iter().last().unwrap().unwrap();
+    assert_eq!(last.url().query(), Some("page=2"));
+    assert!(last.links()[0].has_rel("next"));
+}
+
+#[tokio::test]
+async fn strict_header_values_rejects_non_ascii() {
+    let server = server::http(move |_req| async { http::Response::default() });
+
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .strict_header_values()
+        .build()
+        .unwrap();
+
+    let err = client
+        .get(format!("http://{}/", server.addr()))
+        .header(
+            "x-name",
+            HeaderValue::from_bytes("café".as_bytes()).unwrap(),
+        )
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_builder());
+    assert_eq!(err.header_name().unwrap(), "x-name");
+}
+
+#[tokio::test]
+async fn sanitize_header_values_replaces_non_ascii() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["x-name"], "caf?");
+        http::Response::builder()
+            .header("x-raw", HeaderValue::from_bytes(b"ok\xff").unwrap())
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .sanitize_header_values()
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/", server.addr()))
+        .header(
+            "x-name",
+            HeaderValue::from_bytes("café".as_bytes()).unwrap(),
+        )
+        .send()
+        .await
+        .unwrap();
+
+    let raw = res
+        .headers_lossy()
+        .find(|(name, _)| *name == "x-raw")
+        .map(|(_, value)| value.into_owned());
+    assert_eq!(raw.as_deref(), Some("ok\u{FFFD}"));
+}
+
+#[tokio::test]
+async fn download_if_changed() {
+    use reqwest::conditional::{CacheMeta, Download};
+
+    let server = server::http(move |req| async move {
+        let etag = "\"v1\"";
+        if req.headers().get("if-none-match").map(|v| v == etag) == Some(true) {
+            assert_eq!(
+                req.headers()["if-modified-since"],
+                "Wed, 21 Oct 2015 07:28:00 GMT"
+            );
+            return http::Response::builder()
+                .status(304)
+                .body(Default::default())
+                .unwrap();
+        }
+        assert_eq!(req.headers().get("if-modified-since"), None);
+        http::Response::builder()
+            .header("etag", etag)
+            .header("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")
+            .body("content".into())
+            .unwrap()
+    });
+
+    let url = format!("http://{}/file", server.addr());
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    let meta = match client
+        .download_if_changed(&url, &CacheMeta::default())
+        .await
+        .unwrap()
+    {
+        Download::Modified { response, meta } => {
+            assert_eq!(response.text().await.unwrap(), "content");
+            meta
+        }
+        Download::NotModified => panic!("expected content"),
+    };
+    assert_eq!(meta.etag(), Some("\"v1\""));
+
+    let res = client.download_if_changed(&url, &meta).await.unwrap();
+    assert!(res.is_not_modified());
+}
+
+#[tokio::test]
+async fn conditional_request_helpers() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let server = server::http(move |req| async move {
+        let etags = req
+            .headers()
+            .get_all("if-none-match")
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(etags, ["\"v1\"", "W/\"v0\""]);
+        assert_eq!(
+            req.headers()["if-modified-since"],
+            "Wed, 21 Oct 2015 07:28:00 GMT"
+        );
+        http::Response::builder()
+            .status(304)
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let res = Client::new()
+        .get(format!("http://{}/file", server.addr()))
+        .if_none_match("\"v1\"")
+        .if_none_match("W/\"v0\"")
+        .if_modified_since(UNIX_EPOCH + Duration::from_secs(1_445_412_480))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.is_not_modified());
+    assert_eq!(res.text().await.unwrap(), "");
+}
+
+#[tokio::test]
+async fn request_trailers() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["trailer"], "x-checksum");
+        assert_eq!(req.headers()["transfer-encoding"], "chunked");
+        let body = req.into_body().collect().await.unwrap();
+        assert_eq!(body.trailers().unwrap()["x-checksum"], "abc123");
+        assert_eq!(body.to_bytes(), "hello");
+        http::Response::default()
+    });
+
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("x-checksum", HeaderValue::from_static("abc123"));
+
+    let res = Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .post(format!("http://{}/", server.addr()))
+        .body("hello")
+        .trailers(trailers)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn response_trailers() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["te"], "trailers");
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("x-checksum", HeaderValue::from_static("abc123"));
+        let frames = futures_util::stream::iter(vec![
+            Ok::<_, std::convert::Infallible>(http_body::Frame::data(bytes::Bytes::from("hello"))),
+            Ok(http_body::Frame::trailers(trailers)),
+        ]);
+        http::Response::builder()
+            .header("trailer", "x-checksum")
+            .body(reqwest::Body::wrap(http_body_util::StreamBody::new(frames)))
+            .unwrap()
+    });
+
+    let mut res = Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .get(format!("http://{}/", server.addr()))
+        .header("te", "trailers")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.chunk().await.unwrap().unwrap(), "hello");
+    let trailers = res.trailers().await.unwrap().unwrap();
+    assert_eq!(trailers["x-checksum"], "abc123");
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn http_request_service_preserves_frames_and_trailers() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.version(), http::Version::HTTP_2);
+        assert_eq!(req.headers()["te"], "trailers");
+        let body = req.into_body().collect().await.unwrap();
+        assert_eq!(body.trailers().unwrap()["x-request-trailer"], "yes");
+        assert_eq!(body.to_bytes(), "ping");
+
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from_static("0"));
+        let frames = futures_util::stream::iter(vec![
+            Ok::<_, std::convert::Infallible>(http_body::Frame::data(bytes::Bytes::from("po"))),
+            Ok(http_body::Frame::data(bytes::Bytes::from("ng"))),
+            Ok(http_body::Frame::trailers(trailers)),
+        ]);
+        http::Response::new(reqwest::Body::wrap(http_body_util::StreamBody::new(frames)))
+    });
+
+    let client = Client::builder()
+        .http2_prior_knowledge()
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("x-request-trailer", HeaderValue::from_static("yes"));
+    let frames = futures_util::stream::iter(vec![
+        Ok::<_, std::convert::Infallible>(http_body::Frame::data(bytes::Bytes::from("ping"))),
+        Ok(http_body::Frame::trailers(trailers)),
+    ]);
+    let req = http::Request::builder()
+        .method("POST")
+        .uri(format!("http://{}/echo.Echo/Ping", server.addr()))
+        .header("te", "trailers")
+        .body(http_body_util::StreamBody::new(frames))
+        .unwrap();
+
+    let res: http::Response<reqwest::Body> = client.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let mut body = res.into_body();
+    let mut data = Vec::new();
+    let mut trailers = None;
+    while let Some(frame) = body.frame().await {
+        match frame.unwrap().into_data() {
+            Ok(chunk) => data.push(chunk),
+            Err(frame) => trailers = frame.into_trailers().ok(),
+        }
+    }
+    assert_eq!(data.concat(), b"pong");
+    assert_eq!(trailers.unwrap()["grpc-status"], "0");
+}
+
+#[tokio::test]
+async fn http_request_service_accepts_unsync_body() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["content-length"], "5");
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello");
+        http::Response::default()
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    // tonic's request bodies are `Send` but not `Sync`
+    let body = http_body_util::Full::new(bytes::Bytes::from("hello"))
+        .map_err(|never| match never {})
+        .boxed_unsync();
+    let req = http::Request::builder()
+        .method("POST")
+        .uri(format!("http://{}/upload", server.addr()))
+        .body::<http_body_util::combinators::UnsyncBoxBody<_, std::io::Error>>(body)
+        .unwrap();
+
+    let res = client.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_in_flight_and_closes_connections() {
+    let mut server = server::http(move |req| async move {
+        if req.uri() == "/slow" {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+        http::Response::new("done".into())
+    });
+
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .close_connections_on_shutdown(true)
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}", server.addr());
+
+    // leave an idle connection in the pool
+    client.get(&url).send().await.unwrap();
+
+    let slow = tokio::spawn({
+        let req = client.get(format!("{url}/slow"));
+        async move { req.send().await?.text().await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    client.shutdown(std::time::Duration::from_secs(5)).await;
+
+    assert_eq!(slow.await.unwrap().unwrap(), "done");
+
+    let err = client.get(&url).send().await.unwrap_err();
+    assert!(err.is_request());
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server
+        .events()
+        .iter()
+        .any(|e| matches!(e, server::Event::ConnectionClosed)));
+}
+
+#[test]
+fn http1_probe_idle_connections_retries_closed_connection() {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        // every connection answers one request and then closes while the
+        // client thinks it is still idle in the pool
+        for _ in 0..2 {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = sock.read(&mut buf).unwrap();
+            sock.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    });
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .http1_probe_idle_connections(true)
+            .build()
+            .unwrap();
+        let url = format!("http://{addr}/");
+
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "ok");
+
+        // block the runtime, so the FIN isn't noticed by the I/O driver
+        // before the connection is checked out again
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "ok");
+    });
+
+    server.join().unwrap();
+}
+
+#[tokio::test]
+async fn capabilities_are_probed_and_cached() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let probes = Arc::new(AtomicUsize::new(0));
+    let counter = probes.clone();
+    let server = server::http(move |req| {
+        let counter = counter.clone();
+        async move {
+            assert_eq!(req.method(), "OPTIONS");
+            assert_eq!(req.uri(), "/");
+            counter.fetch_add(1, Ordering::SeqCst);
+            http::Response::builder()
+                .status(405)
+                .header("allow", "GET, PATCH")
+                .header("accept-patch", "application/merge-patch+json")
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+
+    let client = Client::new();
+    let url = format!("http://{}/items/1?q", server.addr());
+
+    let caps = client.capabilities(&url).await.unwrap();
+    assert!(caps.allows(&reqwest::Method::PATCH));
+    assert!(!caps.allows(&reqwest::Method::PUT));
+    assert!(caps.accepts_patch("application/merge-patch+json"));
+    assert!(!caps.accepts_byte_ranges());
+
+    let cached = client
+        .capabilities(format!("http://{}/other", server.addr()))
+        .await
+        .unwrap();
+    assert_eq!(cached, caps);
+    assert_eq!(probes.load(Ordering::SeqCst), 1);
+
+    let client = Client::builder()
+        .capabilities_ttl(std::time::Duration::ZERO)
+        .build()
+        .unwrap();
+    client.capabilities(&url).await.unwrap();
+    client.capabilities(&url).await.unwrap();
+    assert_eq!(probes.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn content_codec_decodes_responses_and_encodes_bodies() {
+    use bytes::BytesMut;
+    use http_body_util::BodyExt;
+    use reqwest::codec::{Codec, Transform};
+
+    struct Invert;
+
+    impl Transform for Invert {
+        fn transform(&mut self, input: &[u8], output: &mut BytesMut) -> std::io::Result<()> {
+            output.extend(input.iter().map(|b| !b));
+            Ok(())
+        }
+
+        fn finish(&mut self, _output: &mut BytesMut) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct InvertCodec;
+
+    impl Codec for InvertCodec {
+        fn decoder(&self) -> Box<dyn Transform> {
+            Box::new(Invert)
+        }
+
+        fn encoder(&self) -> Option<Box<dyn Transform>> {
+            Some(Box::new(Invert))
+        }
+    }
+
+    fn invert(s: &str) -> Vec<u8> {
+        s.bytes().map(|b| !b).collect()
+    }
+
+    let server = server::http(move |req| async move {
+        let accept = req.headers()["accept-encoding"].to_str().unwrap();
+        assert!(accept.ends_with("x-invert"), "{accept}");
+
+        if req.method() == "POST" {
+            assert_eq!(req.headers()["content-encoding"], "x-invert");
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, invert("hello"));
+        }
+        http::Response::builder()
+            .header("content-encoding", "x-invert")
+            .body(invert("world").into())
+            .unwrap()
+    });
+
+    let client = Client::builder()
+        .content_codec("x-invert", InvertCodec)
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", server.addr());
+
+    let text = client.get(&url).send().await.unwrap().text().await.unwrap();
+    assert_eq!(text, "world");
+
+    let text = client
+        .post(&url)
+        .body("hello")
+        .encode_body("x-invert")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(text, "world");
+
+    let err = client
+        .post(&url)
+        .body("hello")
+        .encode_body("x-unknown")
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn accept_encoding_preferences() {
+    use reqwest::codec::Encoding;
+
+    let server = server::http(move |req| async move {
+        let accept = req.headers().get("accept-encoding");
+        if req.headers().contains_key("x-none") {
+            assert_eq!(accept, None);
+        } else {
+            assert_eq!(accept.unwrap(), "identity;q=0.5");
+        }
+        http::Response::default()
+    });
+
+    let client = Client::builder()
+        .accept_encoding([Encoding::Identity.quality(0.5)])
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", server.addr());
+
+    client.get(&url).send().await.unwrap();
+    client
+        .get(&url)
+        .header("x-none", "1")
+        .no_accept_encoding()
+        .send()
+        .await
+        .unwrap();
+
+    let err = Client::builder()
+        .accept_encoding([Encoding::Identity.quality(1.5)])
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+
+    #[cfg(not(feature = "gzip"))]
+    {
+        let err = Client::builder()
+            .accept_encoding([Encoding::Gzip])
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.conflicts().unwrap(),
+            ["`accept_encoding()` lists an encoding that isn't enabled"]
+        );
+        assert!(Client::builder()
+            .accept_encoding([Encoding::Gzip.quality(0.0)])
+            .build()
+            .is_ok());
+    }
+}
+
+#[tokio::test]
+async fn throttled_bodies() {
+    use http_body_util::BodyExt;
+    use std::time::{Duration, Instant};
+
+    let server = server::http(move |req| async move {
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty() || body.len() == 1500);
+        http::Response::new(vec![b'x'; 1500].into())
+    });
+
+    let client = Client::builder()
+        .max_upload_rate(1000)
+        .max_download_rate(1000)
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", server.addr());
+
+    // a second's worth goes through at once, the rest waits
+    let start = Instant::now();
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.bytes().await.unwrap().len(), 1500);
+    assert!(start.elapsed() >= Duration::from_millis(450));
+
+    let start = Instant::now();
+    client
+        .post(&url)
+        .body(vec![b'y'; 1500])
+        .max_download_rate(0)
+        .send()
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(450));
+
+    let start = Instant::now();
+    let res = client
+        .post(&url)
+        .body(vec![b'y'; 1500])
+        .max_upload_rate(0)
+        .max_download_rate(0)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.content_length(), Some(1500));
+    assert_eq!(res.bytes().await.unwrap().len(), 1500);
+    assert!(start.elapsed() < Duration::from_millis(450));
+}
+
+#[tokio::test]
+async fn concurrency_limits() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let (in_flight2, most2) = (in_flight.clone(), most.clone());
+    let server = server::http(move |_req| {
+        let (in_flight, most) = (in_flight2.clone(), most2.clone());
+        async move {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            most.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            http::Response::default()
+        }
+    });
+    let url = format!("http://{}/", server.addr());
+
+    let client = Client::builder()
+        .max_concurrent_requests(4)
+        .max_concurrent_requests_per_host(2)
+        .build()
+        .unwrap();
+    let requests = (0..6).map(|_| client.get(&url).send());
+    for res in futures_util::future::join_all(requests).await {
+        assert_eq!(res.unwrap().status(), 200);
+    }
+    assert_eq!(most.load(Ordering::SeqCst), 2);
+
+    let client = Client::builder()
+        .max_concurrent_requests(1)
+        .concurrency_queue_timeout(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    let (first, second) = tokio::join!(client.get(&url).send(), client.get(&url).send());
+    assert_eq!(first.unwrap().status(), 200);
+    let err = second.unwrap_err();
+    assert!(err.is_timeout());
+    assert_eq!(err.url().map(|u| u.as_str()), Some(&*url));
+
+    assert!(Client::builder()
+        .max_concurrent_requests(0)
+        .build()
+        .unwrap_err()
+        .is_builder());
+}
+
+#[tokio::test]
+async fn max_connections_per_host() {
+    use reqwest::pool::PoolEventKind;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let server = server::http(move |_req| async move { http::Response::default() });
+    let url = format!("http://{}/", server.addr());
+
+    let created = Arc::new(AtomicUsize::new(0));
+    let created2 = created.clone();
+    let client = Client::builder()
+        .max_connections_per_host(1)
+        .pool_events(move |event| {
+            if event.kind() == PoolEventKind::Created {
+                created2.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build()
+        .unwrap();
+    let requests = (0..3).map(|_| client.get(&url).send());
+    for res in futures_util::future::join_all(requests).await {
+        assert_eq!(res.unwrap().status(), 200);
+    }
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn pool_acquire_timeout() {
+    use std::time::Duration;
+
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/slow" {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        http::Response::default()
+    });
+    let url = format!("http://{}/", server.addr());
+
+    let client = Client::builder()
+        .max_connections_per_host(1)
+        .pool_acquire_timeout(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    let (first, second) = tokio::join!(
+        client.get(format!("{url}slow")).send(),
+        client.get(&url).send()
+    );
+    assert_eq!(first.unwrap().status(), 200);
+    let err = second.unwrap_err();
+    assert!(err.is_pool_timeout(), "{err:?}");
+    assert_eq!(err.kind(), reqwest::ErrorKind::PoolTimeout);
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn http2_cleartext_prior_knowledge() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.version(), http::Version::HTTP_2);
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .http2_cleartext_prior_knowledge()
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/h2c", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.version(), http::Version::HTTP_2);
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let err = reqwest::Client::builder()
+        .http2_cleartext_prior_knowledge()
+        .http1_only()
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn request_priority() {
+    let server = server::http(move |req| async move {
+        let priority = match req.uri().path() {
+            "/urgent" => "u=0",
+            _ => "u=5, i",
+        };
+        assert_eq!(req.headers()["priority"], priority);
+        http::Response::default()
+    });
+
+    let client = Client::new();
+    let res = client
+        .get(format!("http://{}/urgent", server.addr()))
+        .priority(0, false)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let res = client
+        .get(format!("http://{}/bulk", server.addr()))
+        .priority(5, true)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let err = client
+        .get(format!("http://{}/urgent", server.addr()))
+        .priority(8, false)
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn deprecation_and_warning_headers() {
+    use std::sync::{Arc, Mutex};
+
+    let server = server::http(move |req| async move {
+        let mut res = http::Response::builder();
+        if req.uri() == "/old" {
+            res = res
+                .header("deprecation", "@1688169599")
+                .header("sunset", "Wed, 11 Nov 2026 23:59:59 GMT")
+                .header("warning", "299 - \"use /new instead\"");
+        }
+        res.body(Default::default()).unwrap()
+    });
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let client = {
+        let seen = seen.clone();
+        Client::builder()
+            .on_deprecation(move |url, deprecation| {
+                seen.lock()
+                    .unwrap()
+                    .push((url.path().to_owned(), deprecation.clone()));
+            })
+            .build()
+            .unwrap()
+    };
+
+    let res = client
+        .get(format!("http://{}/new", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.deprecation(), None);
+    assert!(res.warnings().is_empty());
+    assert!(seen.lock().unwrap().is_empty());
+
+    let res = client
+        .get(format!("http://{}/old", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    let deprecation = res.deprecation().unwrap();
+    assert!(deprecation.is_deprecated());
+    assert!(deprecation.date().is_some());
+    assert!(deprecation.sunset().is_some());
+    let warnings = res.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code(), 299);
+    assert_eq!(warnings[0].text(), "use /new instead");
+
+    assert_eq!(*seen.lock().unwrap(), [("/old".to_owned(), deprecation)]);
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn retry_replays_buffered_streaming_body() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let mut conn = h2::server::handshake(socket).await.unwrap();
+                while let Some(Ok((req, mut respond))) = conn.accept().await {
+                    let counter = counter.clone();
+                    tokio::spawn(async move {
+                        let mut body = req.into_body();
+                        let mut data = Vec::new();
+                        while let Some(chunk) = body.data().await {
+                            let chunk = chunk.unwrap();
+                            let _ = body.flow_control().release_capacity(chunk.len());
+                            data.extend_from_slice(&chunk);
+                        }
+                        assert_eq!(data, b"Hello");
+
+                        // refuse every other request, which is safe to retry
+                        if counter.fetch_add(1, Ordering::SeqCst) % 2 == 0 {
+                            respond.send_reset(h2::Reason::REFUSED_STREAM);
+                        } else {
+                            let res = http::Response::new(());
+                            respond.send_response(res, true).unwrap();
+                        }
+                    });
+                }
+            });
+        }
+    });
+
+    let url = format!("http://{addr}/");
+    let client = Client::builder()
+        .http2_prior_knowledge()
+        .retry_body_buffer_limit(1024)
+        .build()
+        .unwrap();
+    let res = client
+        .post(&url)
+        .body(reqwest::Body::wrap(String::from("Hello")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.attempts(), 2);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    // without buffering, the streaming body can't be sent again
+    let client = Client::builder().http2_prior_knowledge().build().unwrap();
+    let err = client
+        .post(&url)
+        .body(reqwest::Body::wrap(String::from("Hello")))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_request());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn http2_events() {
+    use reqwest::http2::Http2EventKind;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut conn = h2::server::handshake(socket).await.unwrap();
+        while let Some(Ok((req, mut respond))) = conn.accept().await {
+            if req.uri().path() == "/reset" {
+                respond.send_reset(h2::Reason::INTERNAL_ERROR);
+            } else {
+                let res = http::Response::new(());
+                respond.send_response(res, true).unwrap();
+                conn.graceful_shutdown();
+            }
+        }
+    });
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let seen = events.clone();
+    let client = Client::builder()
+        .http2_prior_knowledge()
+        .http2_keep_alive_interval(Duration::from_millis(10))
+        .http2_keep_alive_while_idle(true)
+        .http2_events(move |event| {
+            assert_eq!(event.host(), "127.0.0.1");
+            seen.lock().unwrap().push((
+                event.kind(),
+                event.stream_id(),
+                event.error_code(),
+                event.rtt(),
+            ));
+        })
+        .build()
+        .unwrap();
+
+    let err = client
+        .get(format!("http://{addr}/reset"))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_request());
+    // give the keep-alive pings some time
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let res = client
+        .get(format!("http://{addr}/ok"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let events = events.lock().unwrap();
+    assert!(
+        events.contains(&(Http2EventKind::StreamReset, 1, Some(2), None)),
+        "{events:?}"
+    );
+    assert!(
+        events
+            .iter()
+            .any(|&(kind, _, _, rtt)| kind == Http2EventKind::PingAcknowledged && rtt.is_some()),
+        "{events:?}"
+    );
+    assert!(
+        events
+            .iter()
+            .any(|&(kind, _, code, _)| kind == Http2EventKind::GoAway && code == Some(0)),
+        "{events:?}"
+    );
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn http2_max_concurrent_streams() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.version(), http::Version::HTTP_2);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        http::Response::default()
+    });
+
+    let created = Arc::new(AtomicUsize::new(0));
+    let counter = created.clone();
+    let client = Client::builder()
+        .http2_prior_knowledge()
+        .http2_max_concurrent_streams(2)
+        .pool_events(move |event| {
+            if event.kind() == reqwest::pool::PoolEventKind::Created {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/", server.addr());
+    let send = |client: Client| {
+        let url = url.clone();
+        async move {
+            let res = client.get(url).send().await.unwrap();
+            assert_eq!(res.status(), reqwest::StatusCode::OK);
+        }
+    };
+
+    // the third request needs a second connection
+    futures_util::future::join_all((0..3).map(|_| send(client.clone()))).await;
+    assert_eq!(created.load(Ordering::SeqCst), 2);
+
+    // once they're done, the connections are reused
+    futures_util::future::join_all((0..4).map(|_| send(client.clone()))).await;
+    assert_eq!(created.load(Ordering::SeqCst), 2);
+
+    let err = Client::builder()
+        .http2_max_concurrent_streams(0)
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn retry_after_resends_unavailable_requests() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let server = server::http(move |req| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            let retry_after = if req.uri() == "/slow" { "60" } else { "0" };
+            let status = if n == 0 || req.uri() == "/slow" {
+                503
+            } else {
+                200
+            };
+            http::Response::builder()
+                .status(status)
+                .header("retry-after", retry_after)
+                .body(reqwest::Body::from("ok"))
+                .unwrap()
+        }
+    });
+
+    let client = Client::builder()
+        .retry_after(Duration::from_secs(1))
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", server.addr());
+    let res = client.post(&url).body("Hello").send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.attempts(), 2);
+    let waits = res.retry_after_waits();
+    assert_eq!(waits.len(), 1);
+    assert_eq!(waits[0].status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(waits[0].duration(), Duration::ZERO);
+    assert_eq!(waits[0].url().as_str(), url);
+
+    // a longer delay than allowed returns the response
+    let url = format!("http://{}/slow", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.attempts(), 1);
+    assert!(res.retry_after_waits().is_empty());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn hedged_request_uses_first_response() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let server = server::http(move |_req| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            // only the hedged requests (1 and 4) respond quickly
+            if matches!(n, 0 | 2 | 3) {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            http::Response::new(format!("{n}").into())
+        }
+    });
+
+    let client = Client::builder()
+        .hedge_after(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", server.addr());
+
+    let text = client.get(&url).send().await.unwrap().text().await.unwrap();
+    assert_eq!(text, "1");
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    // POST isn't hedged, unless asked for
+    let text = client
+        .post(&url)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(text, "2");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    let text = client
+        .post(&url)
+        .hedge_after(Duration::from_millis(50))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(text, "4");
+    assert_eq!(requests.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn body_with_inferred_type() {
+    let server = server::http(move |req| async move {
+        let content_type = req.headers()["content-type"].to_str().unwrap().to_owned();
+        http::Response::new(content_type.into())
+    });
+    let url = format!("http://{}/", server.addr());
+    let client = Client::new();
+
+    let content_type = |req: reqwest::RequestBuilder| async move {
+        req.send().await.unwrap().text().await.unwrap()
+    };
+
+    let json = client.post(&url).body_with_inferred_type(r#"{"a": 1}"#);
+    assert_eq!(content_type(json).await, "application/json");
+
+    let png = client
+        .post(&url)
+        .body_with_inferred_type(&b"\x89PNG\r\n\x1a\n"[..]);
+    assert_eq!(content_type(png).await, "image/png");
+
+    let explicit = client
+        .post(&url)
+        .header("content-type", "text/plain")
+        .body_with_inferred_type(r#"{"a": 1}"#);
+    assert_eq!(content_type(explicit).await, "text/plain");
+
+    let missing = client
+        .post(&url)
+        .body_with_inferred_type(std::path::Path::new("does-not-exist.json"))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(missing.is_builder());
+}
+
+#[tokio::test]
+async fn circuit_breaker_fails_fast_while_open() {
+    use reqwest::circuit::CircuitBreaker;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let server = server::http(move |_req| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            let status = if n < 2 { 503 } else { 200 };
+            http::Response::builder()
+                .status(status)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+
+    let client = Client::builder()
+        .circuit_breaker(
+            CircuitBreaker::new()
+                .failure_threshold(2)
+                .open_duration(Duration::from_millis(100)),
+        )
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", server.addr());
+
+    for _ in 0..2 {
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    let err = client.get(&url).send().await.unwrap_err();
+    assert!(err.is_circuit_open());
+    assert_eq!(err.url().map(|u| u.as_str()), Some(&*url));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    // the probe succeeds, and closes the breaker
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn response_request_headers() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/redirect" {
+            return http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap();
+        }
+        http::Response::default()
+    });
+
+    let client = Client::builder()
+        .user_agent("reqwest-test")
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/", server.addr());
+    let res = client
+        .post(&url)
+        .header("x-signature", "abc")
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    let headers = res.request_headers().unwrap();
+    assert_eq!(headers["user-agent"], "reqwest-test");
+    assert_eq!(headers["accept"], "*/*");
+    assert_eq!(headers["x-signature"], "abc");
+    assert_eq!(headers["content-length"], "5");
+    assert_eq!(headers["host"], server.addr().to_string().as_str());
+
+    let url = format!("http://{}/redirect", server.addr());
+    let res = client
+        .get(&url)
+        .header("x-signature", "abc")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().path(), "/dst");
+    let headers = res.request_headers().unwrap();
+    assert_eq!(headers["x-signature"], "abc");
+    assert!(headers.get("content-length").is_none());
+}
+
+#[tokio::test]
+async fn host_header_override() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["host"], "internal.example:8080");
+        http::Response::default()
+    });
+
+    let url = format!("http://{}/", server.addr());
+    let res = reqwest::Client::new()
+        .get(&url)
+        .host_header("internal.example:8080")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.url().as_str(), url);
+
+    for invalid in ["", "user@internal.example", "internal example", "a/b"] {
+        let err = reqwest::Client::new()
+            .get(&url)
+            .host_header(invalid)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_builder(), "{invalid:?}: {err:?}");
+    }
+}
+
+#[tokio::test]
+async fn interceptor_sees_requests_responses_and_errors() {
+    use reqwest::interceptor::{Interceptor, RequestParts, ResponseParts};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    impl Interceptor for Events {
+        fn on_request(&self, req: &RequestParts<'_>) {
+            let event = format!("request {} {}", req.method(), req.url().path());
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_response(&self, res: &ResponseParts<'_>) {
+            let event = format!("response {} {}", res.status().as_u16(), res.url().path());
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_error(&self, err: &reqwest::Error) {
+            let event = format!("error timeout={}", err.is_timeout());
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    let server = server::http(move |req| async move {
+        match req.uri().path() {
+            "/redirect" => http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap(),
+            "/slow" => {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                http::Response::default()
+            }
+            _ => http::Response::default(),
+        }
+    });
+
+    let events = Events::default();
+    let client = Client::builder()
+        .interceptor(events.clone())
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/redirect", server.addr());
+    let res = client.post(&url).send().await.unwrap();
+    assert_eq!(res.url().path(), "/dst");
+
+    let url = format!("http://{}/slow", server.addr());
+    let err = client
+        .get(&url)
+        .timeout(Duration::from_millis(100))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+
+    // not sent, not reported
+    client.get("ftp://localhost/").send().await.unwrap_err();
+
+    assert_eq!(
+        *events.0.lock().unwrap(),
+        [
+            "request POST /redirect",
+            "request GET /dst",
+            "response 200 /dst",
+            "request GET /slow",
+            "error timeout=true",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn error_kinds() {
+    use reqwest::dns::{Name, Resolve, Resolving};
+    use reqwest::ErrorKind;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Failing;
+
+    impl Resolve for Failing {
+        fn resolve(&self, _: Name) -> Resolving {
+            let err = std::io::Error::new(std::io::ErrorKind::NotFound, "no such host");
+            Box::pin(futures_util::future::ready(Err(err.into())))
+        }
+    }
+
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/slow" {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+        http::Response::builder()
+            .status(404)
+            .body(Default::default())
+            .unwrap()
+    });
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    let err = client.get("http://a b/").build().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Builder);
+
+    let url = format!("http://{}/", server.addr());
+    let err = client
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Status);
+
+    let err = client
+        .get(format!("http://{}/slow", server.addr()))
+        .timeout(Duration::from_millis(50))
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+
+    let err = Client::builder()
+        .dns_resolver(Arc::new(Failing))
+        .no_proxy()
+        .build()
+        .unwrap()
+        .get("http://missing.test/")
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Dns);
+    assert!(err.is_connect());
+
+    // the server doesn't speak TLS
+    #[cfg(feature = "__tls")]
+    {
+        let err = client
+            .get(format!("https://{}/", server.addr()))
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Tls);
+    }
+
+    // find a port nothing listens on
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let err = client
+        .get(format!("http://{addr}/"))
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Connect);
+    assert_eq!(
+        err.io_error_kind(),
+        Some(std::io::ErrorKind::ConnectionRefused)
+    );
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn response_bytes_spilled() {
+    use futures_util::TryStreamExt;
+
+    let server = server::http(move |req| async move {
+        let body = match req.uri().path() {
+            "/small" => "small".to_owned(),
+            _ => "large ".repeat(10_000),
+        };
+        http::Response::new(body.into())
+    });
+
+    let client = Client::new();
+    let small = client
+        .get(format!("http://{}/small", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes_spilled(1024)
+        .await
+        .unwrap();
+    assert!(small.is_in_memory());
+    assert_eq!(small.path(), None);
+    assert_eq!(small.bytes().await.unwrap(), "small");
+
+    let large = client
+        .get(format!("http://{}/large", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes_spilled(1024)
+        .await
+        .unwrap();
+    assert!(!large.is_in_memory());
+    assert_eq!(large.len(), 60_000);
+    let path = large.path().unwrap().to_owned();
+    assert!(path.exists());
+
+    let chunks: Vec<_> = large.into_stream().try_collect().await.unwrap();
+    assert_eq!(chunks.concat(), "large ".repeat(10_000).as_bytes());
+    // the stream was dropped
+    assert!(!path.exists());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn downloader_segments() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let content = (0..1000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let ranged = Arc::new(AtomicUsize::new(0));
+
+    let server = server::http({
+        let content = content.clone();
+        let ranged = ranged.clone();
+        move |req| {
+            let content = content.clone();
+            let ranged = ranged.clone();
+            async move {
+                let path = req.uri().path().to_owned();
+                let etag = if path == "/changing" && req.method() == "GET" {
+                    "\"v2\""
+                } else {
+                    "\"v1\""
+                };
+                let res = http::Response::builder()
+                    .header("etag", etag)
+                    .header("content-length", content.len());
+                let res = if path == "/norange" {
+                    res
+                } else {
+                    res.header("accept-ranges", "bytes")
+                };
+                if req.method() == "HEAD" {
+                    return res.body(reqwest::Body::default()).unwrap();
+                }
+
+                match req.headers().get("range").filter(|_| path != "/norange") {
+                    Some(range) => {
+                        ranged.fetch_add(1, Ordering::SeqCst);
+                        assert_eq!(req.headers()["if-range"], "\"v1\"");
+                        let range = range.to_str().unwrap().strip_prefix("bytes=").unwrap();
+                        let (start, end) = range.split_once('-').unwrap();
+                        let (start, end) = (start.parse().unwrap(), end.parse::<usize>().unwrap());
+                        http::Response::builder()
+                            .status(http::StatusCode::PARTIAL_CONTENT)
+                            .header("etag", etag)
+                            .header("content-range", format!("bytes {start}-{end}/1000"))
+                            .body(content[start..=end].to_vec().into())
+                            .unwrap()
+                    }
+                    None => res.body(content.into()).unwrap(),
+                }
+            }
+        }
+    });
+
+    let client = Client::new();
+    let url = |path| format!("http://{}{path}", server.addr());
+
+    let mut out = Vec::new();
+    let len = reqwest::Downloader::new(&client, url("/file"))
+        .segment_size(300)
+        .concurrency(2)
+        .to_writer(&mut out)
+        .await
+        .unwrap();
+    assert_eq!(len, 1000);
+    assert_eq!(out, content);
+    assert_eq!(ranged.swap(0, Ordering::SeqCst), 4);
+
+    let path = std::env::temp_dir().join(format!("reqwest-download-{}", std::process::id()));
+    let len = reqwest::Downloader::new(&client, url("/file"))
+        .segment_size(128)
+        .to_file(&path)
+        .await
+        .unwrap();
+    assert_eq!(len, 1000);
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+    assert_eq!(ranged.swap(0, Ordering::SeqCst), 8);
+    std::fs::remove_file(&path).unwrap();
+
+    // without ranges, a single request
+    let mut out = Vec::new();
+    let len = reqwest::Downloader::new(&client, url("/norange"))
+        .segment_size(100)
+        .to_writer(&mut out)
+        .await
+        .unwrap();
+    assert_eq!(len, 1000);
+    assert_eq!(out, content);
+    assert_eq!(ranged.load(Ordering::SeqCst), 0);
+
+    let err = reqwest::Downloader::new(&client, url("/changing"))
+        .segment_size(100)
+        .to_writer(&mut Vec::new())
+        .await
+        .unwrap_err();
+    assert!(err.is_decode());
+}
+
+#[tokio::test]
+async fn allowed_ports_are_enforced_before_connecting() {
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/redirect" {
+            return http::Response::builder()
+                .status(302)
+                .header("location", "http://127.0.0.1:1/")
+                .body(Default::default())
+                .unwrap();
+        }
+        http::Response::default()
+    });
+    let port = server.addr().port();
+
+    let client = Client::builder().allowed_ports([port]).build().unwrap();
+    let res = client
+        .get(format!("http://{}/", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let err = client
+        .get(format!("http://{}/redirect", server.addr()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_port_not_allowed(), "{err:?}");
+
+    let client = Client::builder().allowed_ports([80, 443]).build().unwrap();
+    let err = client
+        .get(format!("http://{}/", server.addr()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_port_not_allowed(), "{err:?}");
+    assert!(err.is_connect());
+}
+
+#[tokio::test]
+async fn hsts_upgrades_known_hosts() {
+    use reqwest::hsts::{HstsCache, Policy};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let server = server::http(move |_req| async { http::Response::default() });
+
+    let cache = Arc::new(HstsCache::new());
+    cache.insert("localhost", Policy::new(Duration::from_secs(60), false));
+    let client = reqwest::Client::builder()
+        .hsts_provider(cache.clone())
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let url = format!("http://127.0.0.1:{}/", server.addr().port());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.url().as_str(), url);
+
+    // the plain HTTP server can't complete a TLS handshake
+    let url = format!("http://localhost:{}/", server.addr().port());
+    let err = client.get(&url).send().await.unwrap_err();
+    assert_eq!(
+        err.url().map(|u| u.as_str()),
+        Some(&*format!("https://localhost:{}/", server.addr().port()))
+    );
+
+    cache.remove("localhost");
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.url().as_str(), url);
+}
+
+#[tokio::test]
+async fn request_layer_can_answer() {
+    let _ = env_logger::try_init();
+
+    let client = Client::builder()
+        .request_layer(tower::layer::layer_fn(|_inner: reqwest::RequestService| {
+            tower::service_fn(|req: http::Request<reqwest::Body>| async move {
+                assert_eq!(req.uri(), "http://cached.local/");
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>(http::Response::new(
+                    reqwest::Body::from("cached"),
+                ))
+            })
+        }))
+        .build()
+        .unwrap();
+
+    let res = client.get("http://cached.local").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "cached");
+}
+
+#[tokio::test]
+async fn http1_preserve_raw_headers() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = server::low_level_with_response(|_raw_request, client_socket| {
+        Box::new(async move {
+            client_socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\n\
+                      X-Second: 1\r\n\
+                      content-LENGTH: 2\r\n\
+                      X-First: 2\r\n\
+                      X-Second: 3\r\n\
+                      \r\n\
+                      ok",
+                )
+                .await
+                .expect("response write_all failed");
+        })
+    });
+    let url = format!("http://{}/", server.addr());
+
+    let client = Client::builder()
+        .http1_preserve_raw_headers(true)
+        .build()
+        .unwrap();
+    // the second response is on a reused connection
+    for _ in 0..2 {
+        let res = client.get(&url).send().await.unwrap();
+        let raw = res
+            .raw_headers()
+            .unwrap()
+            .iter()
+            .map(|(name, value)| (name.to_owned(), value.to_str().unwrap().to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            raw,
+            [
+                ("X-Second", "1"),
+                ("content-LENGTH", "2"),
+                ("X-First", "2"),
+                ("X-Second", "3"),
+            ]
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        );
+        assert_eq!(res.text().await.unwrap(), "ok");
+    }
+
+    let res = Client::new().get(&url).send().await.unwrap();
+    assert!(res.raw_headers().is_none());
+}
+
+#[tokio::test]
+async fn on_informational_early_hints() {
+    use std::sync::{Arc, Mutex};
+    use tokio::io::AsyncWriteExt;
+
+    let server = server::low_level_with_response(|_raw_request, client_socket| {
+        Box::new(async move {
+            client_socket
+                .write_all(
+                    b"HTTP/1.1 103 Early Hints\r\n\
+                      Link: </style.css>; rel=preload; as=style\r\n\
+                      \r\n\
+                      HTTP/1.1 200 OK\r\n\
+                      Content-Length: 2\r\n\
+                      \r\n\
+                      ok",
+                )
+                .await
+                .expect("response write_all failed");
+        })
+    });
+
+    let hints = Arc::new(Mutex::new(Vec::new()));
+    let hints2 = hints.clone();
+    let res = Client::new()
+        .get(format!("http://{}/", server.addr()))
+        .on_informational(move |status, headers| {
+            let link = headers.get("link").unwrap().to_str().unwrap().to_owned();
+            hints2.lock().unwrap().push((status.as_u16(), link));
+        })
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "ok");
+    assert_eq!(
+        *hints.lock().unwrap(),
+        [(103, "</style.css>; rel=preload; as=style".to_owned())]
+    );
+}
+
+#[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+#[test]
+fn builder_conflicts() {
+    let builder = Client::builder()
+        .interface("lo")
+        .local_address(std::net::IpAddr::from([127, 0, 0, 1]));
+
+    let err = builder.build().unwrap_err();
+    assert!(err.is_builder());
+    assert_eq!(
+        err.conflicts().unwrap(),
+        ["`interface()` can't be combined with `local_address()`"]
+    );
+
+    assert!(Client::builder().interface("lo").build().is_ok());
+}
+
+#[tokio::test]
+async fn expect_continue() {
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = server::low_level_with_response(|raw_request, client_socket| {
+        Box::new(async move {
+            let request = String::from_utf8_lossy(raw_request).to_ascii_lowercase();
+            if request.contains("x-reject") {
+                assert!(request.contains("expect: 100-continue\r\n"));
+                client_socket
+                    .write_all(b"HTTP/1.1 417 Expectation Failed\r\nContent-Length: 4\r\n\r\nnope")
+                    .await
+                    .expect("response write_all failed");
+                return;
+            }
+            if request.contains("expect: 100-continue\r\n") && !request.contains("x-silent") {
+                client_socket
+                    .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+                    .await
+                    .expect("continue write_all failed");
+            }
+            let mut body = [0; 5];
+            client_socket.read_exact(&mut body).await.unwrap();
+            assert_eq!(&body, b"hello");
+            client_socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .expect("response write_all failed");
+        })
+    });
+    let url = format!("http://{}/", server.addr());
+    let client = Client::new();
+
+    // the server answers `100 Continue`
+    let start = Instant::now();
+    let res = client
+        .put(&url)
+        .body("hello")
+        .expect_continue(Duration::from_secs(10))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "ok");
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // the server doesn't know about `Expect`
+    let res = client
+        .put(&url)
+        .header("x-silent", "1")
+        .body("hello")
+        .expect_continue(Duration::from_millis(100))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "ok");
+
+    // the server rejects the request before the body
+    let start = Instant::now();
+    let res = client
+        .put(&url)
+        .header("x-reject", "1")
+        .body("hello")
+        .expect_continue(Duration::from_secs(10))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::EXPECTATION_FAILED);
+    assert_eq!(res.text().await.unwrap(), "nope");
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(feature = "digest")]
+#[tokio::test]
+async fn verify_content_digest() {
+    use reqwest::digest::Algorithm;
+
+    // sha-256 of "hello"
+    const HELLO: &str = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+
+    let server = server::http(move |req| async move {
+        let body = match req.uri().path() {
+            "/tampered" => "hello!",
+            _ => "hello",
+        };
+        http::Response::builder()
+            .header("content-digest", format!("sha-256=:{HELLO}:"))
+            .body(body.into())
+            .unwrap()
+    });
+
+    let client = Client::builder()
+        .verify_content_digest(true)
+        .build()
+        .unwrap();
+    let url = |path| format!("http://{}{path}", server.addr());
+
+    let body = client.get(url("/")).send().await.unwrap().text().await;
+    assert_eq!(body.unwrap(), "hello");
+
+    let err = client
+        .get(url("/tampered"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap_err();
+    assert!(err.is_decode());
+    assert!(err.is_digest_mismatch());
+
+    // a digest known in advance
+    let expected = [
+        0x2c, 0xf2, 0x4d, 0xba, 0x5f, 0xb0, 0xa3, 0x0e, 0x26, 0xe8, 0x3b, 0x2a, 0xc5, 0xb9, 0xe2,
+        0x9e, 0x1b, 0x16, 0x1e, 0x5c, 0x1f, 0xa7, 0x42, 0x5e, 0x73, 0x04, 0x33, 0x62, 0x93, 0x8b,
+        0x98, 0x24,
+    ];
+    let body = reqwest::get(url("/"))
+        .await
+        .unwrap()
+        .verify_digest(Algorithm::Sha256, expected)
+        .bytes()
+        .await;
+    assert_eq!(body.unwrap(), "hello");
+
+    let err = reqwest::get(url("/tampered"))
+        .await
+        .unwrap()
+        .verify_digest(Algorithm::Sha256, expected)
+        .bytes()
+        .await
+        .unwrap_err();
+    assert!(err.is_digest_mismatch());
+}
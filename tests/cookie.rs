@@ -201,3 +201,181 @@ async fn cookie_store_path() {
     let url = format!("http://{}/subpath", server.addr());
     client.get(&url).send().await.unwrap();
 }
+
+#[tokio::test]
+async fn cookie_jar_management() {
+    use std::sync::Arc;
+
+    let server = server::http(move |req| async move {
+        if req.uri() == "/login" {
+            http::Response::builder()
+                .header("Set-Cookie", "session=abc")
+                .header("Set-Cookie", "theme=dark; Path=/")
+                .header("Set-Cookie", "theme=light; Path=/other")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.headers()["cookie"], "theme=dark");
+            http::Response::default()
+        }
+    });
+
+    let jar = Arc::new(reqwest::cookie::Jar::default());
+    let client = reqwest::Client::builder()
+        .cookie_provider(jar.clone())
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/login", server.addr());
+    client.get(&url).send().await.unwrap();
+
+    let mut cookies = jar
+        .iter()
+        .map(|c| (c.name().to_owned(), c.path().unwrap().to_owned()))
+        .collect::<Vec<_>>();
+    cookies.sort();
+    assert_eq!(
+        cookies,
+        [
+            ("session".to_owned(), "/".to_owned()),
+            ("theme".to_owned(), "/".to_owned()),
+            ("theme".to_owned(), "/other".to_owned()),
+        ]
+    );
+
+    let url = format!("http://{}/", server.addr()).parse().unwrap();
+    let cookies = jar.cookies_for(&url);
+    assert_eq!(cookies.len(), 2);
+    assert_eq!(cookies[0].domain(), Some("127.0.0.1"));
+
+    assert!(jar.remove("127.0.0.1", "session"));
+    assert!(!jar.remove("127.0.0.1", "session"));
+    client.get(url).send().await.unwrap();
+
+    jar.clear();
+    assert_eq!(jar.iter().count(), 0);
+}
+
+#[cfg(feature = "psl")]
+#[test]
+fn cookie_jar_rejects_public_suffixes() {
+    use reqwest::cookie::{CookieStore, Jar};
+
+    let list = "// ===BEGIN ICANN DOMAINS===\nuk\nco.uk\n";
+    let jar = Jar::with_public_suffix_list(list).unwrap();
+
+    let shop = "http://shop.co.uk/".parse().unwrap();
+    jar.add_cookie_str("super=1; Domain=co.uk", &shop);
+    jar.add_cookie_str("scoped=1; Domain=shop.co.uk", &shop);
+    assert_eq!(jar.cookies(&shop).unwrap(), "scoped=1");
+
+    let other = "http://other.co.uk/".parse().unwrap();
+    assert_eq!(jar.cookies(&other), None);
+
+    // a public suffix setting a cookie for itself gets a host-only cookie
+    let registry = "http://co.uk/".parse().unwrap();
+    jar.add_cookie_str("own=1; Domain=co.uk", &registry);
+    assert_eq!(jar.cookies(&registry).unwrap(), "own=1");
+    assert_eq!(jar.cookies(&shop).unwrap(), "scoped=1");
+}
+
+#[tokio::test]
+async fn cookie_jar_per_request() {
+    use std::sync::Arc;
+
+    let server = server::http(move |req| async move {
+        if req.uri() == "/login" {
+            let user = req.headers()["x-user"].to_str().unwrap().to_owned();
+            http::Response::builder()
+                .header("Set-Cookie", format!("user={user}"))
+                .body(Default::default())
+                .unwrap()
+        } else {
+            let user = req.headers()["x-user"].to_str().unwrap();
+            assert_eq!(req.headers()["cookie"], format!("user={user}"));
+            http::Response::default()
+        }
+    });
+
+    let client_jar = Arc::new(reqwest::cookie::Jar::default());
+    let client = reqwest::Client::builder()
+        .cookie_provider(client_jar.clone())
+        .build()
+        .unwrap();
+    let alice = Arc::new(reqwest::cookie::Jar::default());
+    let bob = Arc::new(reqwest::cookie::Jar::default());
+
+    let login = format!("http://{}/login", server.addr());
+    let account = format!("http://{}/account", server.addr());
+    for (user, jar) in [("alice", &alice), ("bob", &bob)] {
+        client
+            .get(&login)
+            .header("x-user", user)
+            .cookie_jar(jar.clone())
+            .send()
+            .await
+            .unwrap();
+    }
+    for (user, jar) in [("bob", &bob), ("alice", &alice)] {
+        client
+            .get(&account)
+            .header("x-user", user)
+            .cookie_jar(jar.clone())
+            .send()
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(alice.iter().next().unwrap().value(), "alice");
+    assert_eq!(bob.iter().next().unwrap().value(), "bob");
+    assert_eq!(client_jar.iter().count(), 0);
+}
+
+#[tokio::test]
+async fn cookie_observer_reports_changes() {
+    use reqwest::cookie::CookieEventKind;
+    use std::sync::{Arc, Mutex};
+
+    let server = server::http(move |req| async move {
+        let set_cookie = match req.uri().path() {
+            "/login" => "session=1; Path=/app",
+            "/refresh" => "session=2; Path=/app",
+            _ => "session=; Path=/app; Max-Age=0",
+        };
+        http::Response::builder()
+            .header("Set-Cookie", set_cookie)
+            .header("Set-Cookie", "gone=; Max-Age=0")
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let seen = events.clone();
+    let client = reqwest::Client::builder()
+        .cookie_store(true)
+        .cookie_observer(move |event| {
+            let cookie = event.cookie();
+            seen.lock().unwrap().push((
+                event.kind(),
+                cookie.name().to_owned(),
+                cookie.value().to_owned(),
+            ));
+        })
+        .build()
+        .unwrap();
+
+    for path in ["login", "refresh", "logout"] {
+        let url = format!("http://{}/{path}", server.addr());
+        client.get(&url).send().await.unwrap();
+    }
+
+    let session = |kind, value: &str| (kind, "session".to_owned(), value.to_owned());
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            session(CookieEventKind::Stored, "1"),
+            session(CookieEventKind::Updated, "2"),
+            session(CookieEventKind::Expired, ""),
+        ]
+    );
+}
@@ -101,6 +101,7 @@ async fn gzip_case(response_size: usize, chunk_size: usize) {
     };
 
     let gzipped_content = encoder.finish().into_result().unwrap();
+    let gzipped_len = gzipped_content.len();
 
     let mut response = format!(
         "\
@@ -148,6 +149,10 @@ async fn gzip_case(response_size: usize, chunk_size: usize) {
         .await
         .expect("response");
 
+    assert_eq!(res.content_length(), None);
+    assert_eq!(res.content_encoding(), Some("gzip"));
+    assert_eq!(res.compressed_length(), Some(gzipped_len as u64));
+
     let body = res.text().await.expect("text");
     assert_eq!(body, content);
 }
@@ -0,0 +1,219 @@
+#![cfg(all(reqwest_unstable, feature = "http3"))]
+#![cfg(not(target_arch = "wasm32"))]
+mod support;
+use support::server;
+
+#[tokio::test]
+async fn http3_race_tcp_quic_wins() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let server = server::http3(move |_req| async move { http::Response::default() });
+
+    // a TCP listener on the same port that never completes a TLS handshake
+    let listener = tokio::net::TcpListener::bind(server.addr()).await.unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    tokio::spawn(async move {
+        let mut stalled = Vec::new();
+        loop {
+            let (tcp, _) = listener.accept().await.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            stalled.push(tcp);
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(true)
+        .http3_race_tcp(true)
+        .build()
+        .unwrap();
+    let url = format!("https://{}/", server.addr());
+    for _ in 0..3 {
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.version(), http::Version::HTTP_3);
+    }
+
+    // only the first request raced, the others reused the winner
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn http3_race_tcp_tcp_wins() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let cert = std::fs::read("tests/support/server.cert").unwrap();
+    let key = std::fs::read("tests/support/server.key").unwrap();
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.into()], key.try_into().unwrap())
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    // a UDP socket on the same port that swallows the QUIC handshake
+    let udp = tokio::net::UdpSocket::bind(addr).await.unwrap();
+    tokio::spawn(async move {
+        let mut buf = [0; 2048];
+        while udp.recv(&mut buf).await.is_ok() {}
+    });
+
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    tokio::spawn(async move {
+        loop {
+            let (tcp, _) = listener.accept().await.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut tls) = acceptor.accept(tcp).await else {
+                    return;
+                };
+                let mut buf = [0; 1024];
+                while matches!(tls.read(&mut buf).await, Ok(n) if n > 0) {
+                    let ok = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+                    if tls.write_all(ok).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(true)
+        .http3_race_tcp(true)
+        .build()
+        .unwrap();
+    let url = format!("https://{addr}/");
+    for _ in 0..3 {
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.version(), http::Version::HTTP_11);
+    }
+
+    // the probe that won the race, and the pooled connection of the requests
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn http3_zero_rtt_rejected() {
+    use http_body_util::BodyExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let serve = |addr| {
+        // wait for the previous server to release the port
+        while std::net::UdpSocket::bind(addr).is_err() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let requests = requests.clone();
+        server::http3_with_addr(addr, move |req| {
+            let requests = requests.clone();
+            async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                let body = req.collect().await.unwrap().to_bytes();
+                http::Response::new(body.into())
+            }
+        })
+    };
+
+    let client = reqwest::Client::builder()
+        .http3_prior_knowledge()
+        .danger_accept_invalid_certs(true)
+        .tls_early_data(true)
+        .http3_zero_rtt(reqwest::Http3ZeroRtt::All)
+        .build()
+        .unwrap();
+
+    // the first connection gets a session ticket for the next ones
+    let mut server = serve("[::1]:0".parse().unwrap());
+    let addr = server.addr();
+    let url = format!("https://{addr}/");
+    let res = client
+        .get(&url)
+        .version(http::Version::HTTP_3)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // a restarted server doesn't know the ticket and rejects the early data,
+    // so the request is sent again on a new connection
+    drop(server);
+    client.flush_http3_pool();
+    server = serve(addr);
+    let res = client
+        .post(&url)
+        .version(http::Version::HTTP_3)
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "hello");
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    let accepted = server
+        .events()
+        .into_iter()
+        .filter(|e| matches!(e, server::Event::ConnectionAccepted))
+        .count();
+    assert_eq!(accepted, 2);
+
+    // a body that can't be sent again isn't sent in early data
+    #[cfg(feature = "stream")]
+    {
+        drop(server);
+        client.flush_http3_pool();
+        server = serve(addr);
+        let body = futures_util::stream::once(async { Ok::<_, std::io::Error>("hello") });
+        let res = client
+            .post(&url)
+            .version(http::Version::HTTP_3)
+            .body(reqwest::Body::wrap_stream(body))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+    drop(server);
+}
+
+#[tokio::test]
+async fn http3_congestion_controller() {
+    use quinn::congestion::{Controller, ControllerFactory, NewRenoConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // NewReno, counting the controllers it builds
+    struct Counting(AtomicUsize);
+
+    impl ControllerFactory for Counting {
+        fn build(self: Arc<Self>, now: std::time::Instant, mtu: u16) -> Box<dyn Controller> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Arc::new(NewRenoConfig::default()).build(now, mtu)
+        }
+    }
+
+    let server = server::http3(move |_req| async move { http::Response::default() });
+    let controller = Arc::new(Counting(AtomicUsize::new(0)));
+    let res = reqwest::Client::builder()
+        .http3_prior_knowledge()
+        .danger_accept_invalid_certs(true)
+        .http3_congestion_controller(controller.clone())
+        .build()
+        .unwrap()
+        .get(format!("https://{}/", server.addr()))
+        .version(http::Version::HTTP_3)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(controller.0.load(Ordering::SeqCst), 1);
+}
@@ -233,3 +233,88 @@ async fn async_impl_file_part() {
     assert_eq!(res.url().as_str(), &url);
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
+
+const BYTERANGES: &str = "\
+    --THIS_STRING_SEPARATES\r\n\
+    Content-Type: text/plain\r\n\
+    Content-Range: bytes 0-4/11\r\n\
+    \r\n\
+    hello\r\n\
+    --THIS_STRING_SEPARATES\r\n\
+    Content-Type: text/plain\r\n\
+    Content-Range: bytes 6-10/11\r\n\
+    \r\n\
+    world\r\n\
+    --THIS_STRING_SEPARATES--\r\n";
+
+fn byteranges_server() -> server::Server {
+    server::http(move |req| async move {
+        assert_eq!(req.headers()["range"], "bytes=0-4, 6-10");
+        http::Response::builder()
+            .status(http::StatusCode::PARTIAL_CONTENT)
+            .header(
+                "content-type",
+                "multipart/byteranges; boundary=THIS_STRING_SEPARATES",
+            )
+            .body(BYTERANGES.into())
+            .unwrap()
+    })
+}
+
+#[tokio::test]
+async fn response_byteranges() {
+    use futures_util::TryStreamExt;
+
+    let _ = env_logger::try_init();
+
+    let server = byteranges_server();
+    let url = format!("http://{}/byteranges", server.addr());
+
+    let parts = reqwest::Client::new()
+        .get(&url)
+        .header("range", "bytes=0-4, 6-10")
+        .send()
+        .await
+        .unwrap()
+        .multipart()
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].content_type(), Some("text/plain"));
+    assert_eq!(parts[0].content_range(), Some(0..=4));
+    assert_eq!(parts[0].body(), b"hello");
+    assert_eq!(parts[1].content_range(), Some(6..=10));
+    assert_eq!(parts[1].body(), b"world");
+
+    // not multipart
+    let server = server::http(move |_req| async move { http::Response::default() });
+    let url = format!("http://{}/plain", server.addr());
+    let err = reqwest::get(&url).await.unwrap().multipart().unwrap_err();
+    assert!(err.is_decode());
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_response_byteranges() {
+    let _ = env_logger::try_init();
+
+    let server = byteranges_server();
+    let url = format!("http://{}/byteranges", server.addr());
+
+    let parts = reqwest::blocking::Client::new()
+        .get(&url)
+        .header("range", "bytes=0-4, 6-10")
+        .send()
+        .unwrap()
+        .multipart()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].body(), b"hello");
+    assert_eq!(parts[1].body(), b"world");
+}
@@ -65,6 +65,47 @@ async fn http_proxy_basic_auth() {
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
 
+#[tokio::test]
+async fn http_proxy_custom_auth_is_refreshed() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let url = "http://hyper.rs/prox";
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let server_seen = seen.clone();
+    let server = server::http(move |req| {
+        let auth = req.headers()["proxy-authorization"].clone();
+        server_seen.lock().unwrap().push(auth);
+        async { http::Response::default() }
+    });
+
+    let proxy = format!("http://{}", server.addr());
+    let tokens = Arc::new(AtomicUsize::new(0));
+    let client = reqwest::Client::builder()
+        .proxy(
+            reqwest::Proxy::http(&proxy)
+                .unwrap()
+                .basic_auth("Aladdin", "open sesame")
+                .custom_auth(move |dst| {
+                    assert_eq!(dst, "http://hyper.rs");
+                    let n = tokens.fetch_add(1, Ordering::SeqCst);
+                    reqwest::header::HeaderValue::try_from(format!("Bearer token-{n}")).ok()
+                }),
+        )
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let res = client.get(url).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+    }
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert!(seen[0].to_str().unwrap().starts_with("Bearer token-"));
+    assert_ne!(seen[0], seen[1]);
+}
+
 #[tokio::test]
 async fn http_proxy_basic_auth_parsed() {
     let url = "http://hyper.rs/prox";
@@ -137,6 +178,34 @@ async fn system_http_proxy_basic_auth_parsed() {
     }
 }
 
+#[cfg(feature = "__tls")]
+#[tokio::test]
+async fn refused_tunnel_is_proxy_error() {
+    let server = server::http(move |req| {
+        assert_eq!(req.method(), "CONNECT");
+
+        async {
+            http::Response::builder()
+                .status(403)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+
+    let proxy = format!("http://{}", server.addr());
+
+    let err = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::https(&proxy).unwrap())
+        .build()
+        .unwrap()
+        .get("https://hyper.rs/prox")
+        .send()
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), reqwest::ErrorKind::Proxy);
+}
+
 #[tokio::test]
 async fn test_no_proxy() {
     let server = server::http(move |req| {
@@ -218,3 +287,52 @@ async fn http_over_http() {
     assert_eq!(res.url().as_str(), url);
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
+
+#[tokio::test]
+async fn http_proxy_via_jump_proxy() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let url = "http://hyper.rs/prox";
+    let server = server::http(move |req| {
+        assert_eq!(req.method(), "GET");
+        assert_eq!(req.uri(), url);
+
+        async { http::Response::default() }
+    });
+    let target = server.addr().to_string();
+
+    // a jump host, which only tunnels to the proxy
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let jump = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut conn, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        while !buf.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            conn.read_exact(&mut byte).await.unwrap();
+            buf.push(byte[0]);
+        }
+        let head = String::from_utf8(buf).unwrap();
+        assert!(head.starts_with(&format!("CONNECT {target} HTTP/1.1\r\n")));
+        assert!(head.contains("\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+
+        let mut tunnel = tokio::net::TcpStream::connect(&target).await.unwrap();
+        conn.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await.unwrap();
+        let _ = tokio::io::copy_bidirectional(&mut conn, &mut tunnel).await;
+    });
+
+    let proxy = format!("http://{}", server.addr());
+    let jump = jump.replace("http://", "http://user:pass@");
+
+    let res = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(&proxy).unwrap().via(jump).unwrap())
+        .build()
+        .unwrap()
+        .get(url)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.url().as_str(), url);
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
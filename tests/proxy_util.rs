@@ -0,0 +1,124 @@
+#![cfg(not(target_arch = "wasm32"))]
+#![cfg(not(feature = "rustls-tls-manual-roots-no-provider"))]
+mod support;
+use support::server;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn forward_strips_hop_by_hop_headers() {
+    let upstream = server::http(move |req| async move {
+        assert_eq!(req.method(), "POST");
+        assert_eq!(req.uri(), "/echo?a=b");
+        assert_eq!(req.headers()["x-end-to-end"], "kept");
+        assert_eq!(req.headers().get("x-private"), None);
+        assert_eq!(req.headers().get("keep-alive"), None);
+
+        let body = http_body_util::BodyExt::collect(req.into_body())
+            .await
+            .unwrap()
+            .to_bytes();
+        assert_eq!(body, "hello upstream");
+
+        http::Response::builder()
+            .header("connection", "x-upstream-private")
+            .header("x-upstream-private", "secret")
+            .header("x-upstream", "yes")
+            .body(reqwest::Body::from("hello downstream"))
+            .unwrap()
+    });
+
+    let upstream_addr = upstream.addr();
+    let front = server::http(move |req| async move {
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let (mut parts, body) = req.into_parts();
+        parts.uri = format!(
+            "http://{upstream_addr}{}",
+            parts.uri.path_and_query().unwrap()
+        )
+        .parse()
+        .unwrap();
+        reqwest::proxy_util::forward(http::Request::from_parts(parts, body), &client)
+            .await
+            .unwrap()
+    });
+
+    let res = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .post(format!("http://{}/echo?a=b", front.addr()))
+        .header("connection", "x-private")
+        .header("x-private", "secret")
+        .header("keep-alive", "timeout=5")
+        .header("x-end-to-end", "kept")
+        .body("hello upstream")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.headers()["x-upstream"], "yes");
+    assert_eq!(res.headers().get("x-upstream-private"), None);
+    assert_eq!(res.text().await.unwrap(), "hello downstream");
+}
+
+#[tokio::test]
+async fn forward_upgrade() {
+    let upstream = server::http(move |req| {
+        assert_eq!(req.headers()["connection"], "upgrade");
+        assert_eq!(req.headers()["upgrade"], "foobar");
+
+        tokio::spawn(async move {
+            let mut upgraded = hyper_util::rt::TokioIo::new(hyper::upgrade::on(req).await.unwrap());
+
+            let mut buf = vec![0; 7];
+            upgraded.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, b"foo=bar");
+
+            upgraded.write_all(b"bar=foo").await.unwrap();
+        });
+
+        async {
+            http::Response::builder()
+                .status(http::StatusCode::SWITCHING_PROTOCOLS)
+                .header(http::header::CONNECTION, "upgrade")
+                .header(http::header::UPGRADE, "foobar")
+                .body(reqwest::Body::default())
+                .unwrap()
+        }
+    });
+
+    let upstream_addr = upstream.addr();
+    let front = server::http(move |req| async move {
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let (mut parts, body) = req.into_parts();
+        parts.uri = format!("http://{upstream_addr}/").parse().unwrap();
+        reqwest::proxy_util::forward(http::Request::from_parts(parts, body), &client)
+            .await
+            .unwrap()
+    });
+
+    let res = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .get(format!("http://{}", front.addr()))
+        .header(http::header::CONNECTION, "upgrade")
+        .header(http::header::UPGRADE, "foobar")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+    let mut upgraded = res.upgrade().await.unwrap();
+
+    upgraded.write_all(b"foo=bar").await.unwrap();
+
+    let mut buf = vec![];
+    upgraded.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"bar=foo");
+}
@@ -39,6 +39,8 @@ async fn test_redirect_301_and_302_and_303_changes_post_to_get() {
             res.headers().get(reqwest::header::SERVER).unwrap(),
             &"test-dst"
         );
+        assert_eq!(res.redirects_followed(), 1);
+        assert_eq!(res.attempts(), 1);
     }
 }
 
@@ -127,6 +129,55 @@ async fn test_redirect_307_and_308_tries_to_post_again() {
     }
 }
 
+#[tokio::test]
+async fn test_redirect_307_replays_buffered_streaming_body() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.method(), "POST");
+        let uri = req.uri().clone();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "Hello");
+
+        if uri == "/307" {
+            http::Response::builder()
+                .status(307)
+                .header("location", "/dst")
+                .body(Body::default())
+                .unwrap()
+        } else {
+            assert_eq!(uri, "/dst");
+            http::Response::new(Body::default())
+        }
+    });
+
+    let url = format!("http://{}/307", server.addr());
+    let client = reqwest::Client::builder()
+        .redirect_body_buffer_limit(1024)
+        .build()
+        .unwrap();
+    let res = client
+        .post(&url)
+        .body(Body::wrap(String::from("Hello")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.url().path(), "/dst");
+
+    // the body doesn't fit, so it can't be replayed
+    let client = reqwest::Client::builder()
+        .redirect_body_buffer_limit(4)
+        .build()
+        .unwrap();
+    let res = client
+        .post(&url)
+        .body(Body::wrap(String::from("Hello")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(res.url().path(), "/307");
+}
+
 #[cfg(feature = "blocking")]
 #[test]
 fn test_redirect_307_does_not_try_if_reader_cannot_reset() {
@@ -230,6 +281,124 @@ async fn test_redirect_policy_can_return_errors() {
     assert!(err.is_redirect());
 }
 
+#[tokio::test]
+async fn test_redirect_loop_error_has_hops() {
+    use std::error::Error as _;
+
+    let server = server::http(move |req| async move {
+        let location = if req.uri() == "/a" { "/b" } else { "/a" };
+        http::Response::builder()
+            .status(302)
+            .header("location", location)
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let url = format!("http://{}/a", server.addr());
+    let err = reqwest::get(&url).await.unwrap_err();
+    assert!(err.is_redirect());
+
+    let chain = err
+        .source()
+        .and_then(|e| e.downcast_ref::<reqwest::redirect::ChainError>())
+        .expect("ChainError");
+    assert!(chain.is_loop());
+    assert_eq!(chain.next().as_str(), url);
+    let hops = chain
+        .hops()
+        .iter()
+        .map(|hop| {
+            (
+                hop.method().as_str(),
+                hop.url().path(),
+                hop.status().as_u16(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(hops, [("GET", "/a", 302), ("GET", "/b", 302)]);
+}
+
+#[tokio::test]
+async fn test_request_max_redirects() {
+    let server = server::http(move |req| async move {
+        let n = req.uri().path()[1..].parse::<u32>().unwrap();
+        http::Response::builder()
+            .status(302)
+            .header("location", format!("/{}", n + 1))
+            .body(Body::default())
+            .unwrap()
+    });
+
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/0", server.addr());
+    let err = client.get(&url).max_redirects(3).send().await.unwrap_err();
+    assert!(err.is_redirect());
+    assert_eq!(err.url().unwrap().path(), "/2");
+
+    // more than the default of the client
+    let err = client.get(&url).max_redirects(15).send().await.unwrap_err();
+    assert_eq!(err.url().unwrap().path(), "/14");
+}
+
+#[tokio::test]
+async fn test_async_redirect_policy_changes_next_request() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/start" {
+            assert_eq!(req.headers()["x-custom"], "start");
+            http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .header("x-next-token", "abc")
+                .body(Body::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.uri(), "/dst");
+            assert_eq!(req.headers()["authorization"], "Bearer abc");
+            assert_eq!(req.headers().get("x-custom"), None);
+            http::Response::new(Body::default())
+        }
+    });
+
+    let policy = reqwest::redirect::Policy::custom_async(|mut attempt| async move {
+        tokio::task::yield_now().await;
+        assert_eq!(attempt.method(), reqwest::Method::GET);
+        let token = attempt.response_headers()["x-next-token"].clone();
+        let mut auth = b"Bearer ".to_vec();
+        auth.extend_from_slice(token.as_bytes());
+        let headers = attempt.headers_mut();
+        headers.insert("authorization", auth.try_into().unwrap());
+        headers.remove("x-custom");
+        attempt.follow()
+    });
+    let client = reqwest::Client::builder().redirect(policy).build().unwrap();
+
+    let url = format!("http://{}/start", server.addr());
+    let res = client
+        .get(&url)
+        .header("x-custom", "start")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.url().path(), "/dst");
+    assert_eq!(res.redirects_followed(), 1);
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::custom_async(
+            |attempt| async move { attempt.stop() },
+        ))
+        .build()
+        .unwrap();
+    let res = client
+        .get(&url)
+        .header("x-custom", "start")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(res.url().path(), "/start");
+}
+
 #[tokio::test]
 async fn test_redirect_policy_can_stop_redirects_without_an_error() {
     let server = server::http(move |req| async move {
@@ -254,6 +423,45 @@ async fn test_redirect_policy_can_stop_redirects_without_an_error() {
 
     assert_eq!(res.url().as_str(), url);
     assert_eq!(res.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(res.redirects_followed(), 0);
+}
+
+#[tokio::test]
+async fn test_redirect_waits_for_retry_after() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/wait" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .header("retry-after", "1")
+                .body(Body::default())
+                .unwrap()
+        } else {
+            http::Response::default()
+        }
+    });
+
+    let url = format!("http://{}/wait", server.addr());
+    let client = reqwest::Client::builder()
+        .retry_after(std::time::Duration::from_secs(1))
+        .build()
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_secs(1));
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.redirects_followed(), 1);
+    let waits = res.retry_after_waits();
+    assert_eq!(waits.len(), 1);
+    assert_eq!(waits[0].url().as_str(), url);
+    assert_eq!(waits[0].status(), reqwest::StatusCode::FOUND);
+    assert_eq!(waits[0].duration(), std::time::Duration::from_secs(1));
+
+    // Retry-After is ignored by default
+    let res = reqwest::get(&url).await.unwrap();
+    assert_eq!(res.redirects_followed(), 1);
+    assert!(res.retry_after_waits().is_empty());
 }
 
 #[tokio::test]
@@ -349,6 +557,40 @@ async fn test_redirect_302_with_set_cookies() {
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
 
+#[cfg(feature = "cookies")]
+#[tokio::test]
+async fn test_redirect_revisit_with_new_cookie_is_not_a_loop() {
+    let server = server::http(move |req| async move {
+        match (req.uri().path(), req.headers().get("cookie")) {
+            ("/login", None) => http::Response::builder()
+                .status(302)
+                .header("location", "/callback")
+                .body(Body::default())
+                .unwrap(),
+            ("/callback", _) => http::Response::builder()
+                .status(302)
+                .header("location", "/login")
+                .header("set-cookie", "session=1")
+                .body(Body::default())
+                .unwrap(),
+            ("/login", Some(_)) => http::Response::default(),
+            _ => unreachable!(),
+        }
+    });
+
+    let url = format!("http://{}/login", server.addr());
+
+    let client = reqwest::ClientBuilder::new()
+        .cookie_store(true)
+        .build()
+        .unwrap();
+    let res = client.get(&url).send().await.unwrap();
+
+    assert_eq!(res.url().as_str(), url);
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.redirects_followed(), 2);
+}
+
 #[cfg(feature = "__rustls")]
 #[tokio::test]
 #[ignore = "Needs TLS support in the test server"]
@@ -376,3 +618,75 @@ async fn test_redirect_https_only_enforced_gh1312() {
     let err = res.unwrap_err();
     assert!(err.is_redirect());
 }
+
+#[tokio::test]
+async fn trace_context_is_propagated_across_redirects() {
+    use reqwest::trace_context::TraceContext;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(
+            req.headers()["traceparent"],
+            "00-0000000000000000000000000000002a-0000000000000007-01"
+        );
+        assert_eq!(req.headers()["baggage"], "tenant=a%2Cb");
+        if req.uri() == "/redirect" {
+            return http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap();
+        }
+        http::Response::default()
+    });
+
+    let client = reqwest::Client::builder()
+        .trace_context(|| Some(TraceContext::new(42, 7).baggage("tenant", "a,b")))
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/redirect", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(res.url().path(), "/dst");
+}
+
+#[tokio::test]
+async fn request_layer_sees_every_hop() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["x-signature"], "signed");
+        if req.uri() == "/redirect" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            http::Response::new("dst".into())
+        }
+    });
+
+    let hops = Arc::new(AtomicUsize::new(0));
+    let counter = hops.clone();
+    let client = reqwest::Client::builder()
+        .request_layer(tower::util::MapRequestLayer::new(
+            move |mut req: http::Request<reqwest::Body>| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                req.headers_mut()
+                    .insert("x-signature", http::HeaderValue::from_static("signed"));
+                req
+            },
+        ))
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/redirect", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.url().path(), "/dst");
+    assert_eq!(res.text().await.unwrap(), "dst");
+    assert_eq!(hops.load(Ordering::SeqCst), 2);
+}
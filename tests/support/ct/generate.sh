@@ -0,0 +1,92 @@
+#!/bin/sh
+# Generates the certificates and logs of the certificate transparency tests
+# with the openssl command line tool.
+#
+# `leaf.cert` is issued by `root.cert` and embeds SCTs from the logs whose
+# keys are `ecdsa-log.key` and `rsa-log.key`, signed over the certificate
+# without its SCT list, as described in RFC 6962. OpenSSL validates them, for
+# instance with `openssl s_client -ct -ctlogfile`.
+set -e
+
+out=$(cd "$(dirname "$0")" && pwd)
+dir=$(mktemp -d)
+trap 'rm -rf "$dir"' EXIT
+cd "$dir"
+
+# 2024-01-01T00:00:00Z, in milliseconds
+timestamp=$(printf '%016x' 1704067200000)
+
+hex() {
+    xxd -p | tr -d '\n'
+}
+# length16 HEX
+length16() {
+    printf '%04x' $((${#1} / 2))
+}
+# length24 HEX
+length24() {
+    printf '%06x' $((${#1} / 2))
+}
+
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out root.key
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out leaf.key
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out ecdsa-log.key
+openssl genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:2048 -out rsa-log.key
+
+openssl req -new -x509 -key root.key -subj "/CN=reqwest test CT root" -days 36500 \
+    -set_serial 1 -addext "basicConstraints=critical,CA:TRUE" \
+    -addext "keyUsage=critical,keyCertSign" -out root.pem
+openssl req -new -key leaf.key -subj "/CN=localhost" -out leaf.csr
+
+# The logs sign the certificate without the SCT list, which goes between
+# the other extensions to check they keep their order when it's removed.
+cat > unlogged.cnf <<'EOF'
+basicConstraints = critical,CA:FALSE
+keyUsage = critical,digitalSignature
+subjectAltName = DNS:localhost
+subjectKeyIdentifier = none
+authorityKeyIdentifier = none
+EOF
+# issue EXTENSIONS OUT
+issue() {
+    openssl x509 -req -in leaf.csr -CA root.pem -CAkey root.key -set_serial 2 \
+        -not_before 20240101000000Z -not_after 21240101000000Z -extfile $1 \
+        -outform der -out $2
+}
+issue unlogged.cnf unlogged.der
+# The TBSCertificate follows the 4 byte header of the certificate.
+tbs_length=$(openssl asn1parse -inform der -in unlogged.der | sed -n 2p |
+    sed -E 's/.*hl= *([0-9]+) +l= *([0-9]+).*/\1 \2/' | awk '{ print $1 + $2 }')
+tbs=$(tail -c +5 unlogged.der | head -c "$tbs_length" | hex)
+issuer_key_hash=$(openssl x509 -in root.pem -pubkey -noout | openssl pkey -pubin -outform der |
+    openssl dgst -sha256 -binary | hex)
+
+# sct LOG ALGORITHM
+sct() {
+    log_id=$(openssl pkey -in $1.key -pubout -outform der | openssl dgst -sha256 -binary | hex)
+    # v1, certificate_timestamp, timestamp, precert_entry, issuer_key_hash,
+    # tbs_certificate, no extensions
+    echo "0000${timestamp}0001${issuer_key_hash}$(length24 "$tbs")${tbs}0000" | xxd -r -p > signed
+    signature=$(openssl dgst -sha256 -sign $1.key signed | hex)
+    sct="00${log_id}${timestamp}0000$2$(length16 "$signature")${signature}"
+    printf '%s%s' "$(length16 "$sct")" "$sct"
+}
+# sha256 with ecdsa, and with rsa
+scts="$(sct ecdsa-log 0403)$(sct rsa-log 0401)"
+scts="$(length16 "$scts")$scts"
+
+cat > cert.cnf <<EOF
+basicConstraints = critical,CA:FALSE
+1.3.6.1.4.1.11129.2.4.2 = ASN1:FORMAT:HEX,OCTETSTRING:$scts
+keyUsage = critical,digitalSignature
+subjectAltName = DNS:localhost
+subjectKeyIdentifier = none
+authorityKeyIdentifier = none
+EOF
+issue cert.cnf leaf.der
+
+cp leaf.der "$out/leaf.cert"
+openssl x509 -in root.pem -outform der -out "$out/root.cert"
+openssl pkcs8 -topk8 -nocrypt -in leaf.key -outform der -out "$out/leaf.key"
+openssl pkey -in ecdsa-log.key -pubout -outform der -out "$out/ecdsa-log.key"
+openssl pkey -in rsa-log.key -pubout -outform der -out "$out/rsa-log.key"
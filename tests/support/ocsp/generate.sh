@@ -0,0 +1,87 @@
+#!/bin/sh
+# Generates the certificates and OCSP responses of the OCSP tests with the
+# openssl command line tool.
+#
+# `leaf.cert` is issued by `intermediate.cert`, issued by `root.cert`, and
+# names the responder `http://ocsp.test/`. `forged.cert` is self-signed with
+# the name of the intermediate.
+# `stale.ocsp` is past its next update a few minutes after it's generated.
+set -e
+
+out=$(cd "$(dirname "$0")" && pwd)
+dir=$(mktemp -d)
+trap 'rm -rf "$dir"' EXIT
+cd "$dir"
+
+cat > ext.cnf <<'EOF'
+[root]
+basicConstraints = critical,CA:TRUE
+keyUsage = critical,keyCertSign,cRLSign
+subjectKeyIdentifier = hash
+[ca]
+basicConstraints = critical,CA:TRUE
+keyUsage = critical,keyCertSign,cRLSign
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid
+[leaf]
+basicConstraints = critical,CA:FALSE
+keyUsage = critical,digitalSignature
+extendedKeyUsage = serverAuth
+subjectAltName = DNS:localhost
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid
+authorityInfoAccess = OCSP;URI:http://ocsp.test/
+[responder]
+basicConstraints = critical,CA:FALSE
+keyUsage = critical,digitalSignature
+extendedKeyUsage = OCSPSigning
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid
+EOF
+
+for name in root intermediate leaf responder forged; do
+    openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out $name.key
+done
+
+# self_sign NAME SUBJECT
+self_sign() {
+    openssl req -new -x509 -key $1.key -subj "$2" -days 36500 -set_serial 1 \
+        -config ext.cnf -extensions root -out $1.pem
+}
+# issue NAME SUBJECT ISSUER EXTENSIONS SERIAL
+issue() {
+    openssl req -new -key $1.key -subj "$2" -out $1.csr
+    openssl x509 -req -in $1.csr -CA $3.pem -CAkey $3.key -set_serial $5 -days 36500 \
+        -extfile ext.cnf -extensions $4 -out $1.pem
+}
+self_sign root "/CN=reqwest test root"
+issue intermediate "/CN=reqwest test intermediate" root ca 2
+issue leaf "/CN=localhost" intermediate leaf 3
+issue responder "/CN=reqwest test OCSP responder" intermediate responder 4
+self_sign forged "/CN=reqwest test intermediate"
+
+printf 'V\t21240101000000Z\t\t03\tunknown\t/CN=localhost\n' > good.txt
+printf 'R\t21240101000000Z\t240101000000Z\t03\tunknown\t/CN=localhost\n' > revoked.txt
+printf 'V\t21240101000000Z\t\t05\tunknown\t/CN=other\n' > other.txt
+openssl ocsp -issuer intermediate.pem -cert leaf.pem -no_nonce -reqout leaf.req
+openssl ocsp -issuer intermediate.pem -serial 5 -no_nonce -reqout other.req
+
+# respond RESPONSE INDEX SIGNER REQUEST OPTIONS...
+respond() {
+    out_file=$1 index=$2 signer=$3 request=$4
+    shift 4
+    openssl ocsp -index $index -CA intermediate.pem -rsigner $signer.pem -rkey $signer.key \
+        -reqin $request -respout $out_file "$@"
+}
+respond good.ocsp good.txt intermediate leaf.req -ndays 36500 -resp_no_certs
+respond revoked.ocsp revoked.txt intermediate leaf.req -ndays 36500 -resp_no_certs
+respond delegated.ocsp revoked.txt responder leaf.req -ndays 36500
+respond forged.ocsp good.txt forged leaf.req -ndays 36500
+respond stale.ocsp good.txt intermediate leaf.req -nmin 1 -resp_no_certs
+respond other.ocsp other.txt intermediate other.req -ndays 36500 -resp_no_certs
+
+for name in root intermediate leaf forged; do
+    openssl x509 -in $name.pem -outform der -out "$out/$name.cert"
+done
+openssl pkcs8 -topk8 -nocrypt -in leaf.key -outform der -out "$out/leaf.key"
+cp good.ocsp revoked.ocsp delegated.ocsp forged.ocsp stale.ocsp other.ocsp "$out"
@@ -21,6 +21,9 @@ pub struct Server {
 #[non_exhaustive]
 pub enum Event {
     ConnectionClosed,
+    /// An HTTP/3 server accepted a QUIC connection.
+    #[cfg(feature = "http3")]
+    ConnectionAccepted,
 }
 
 impl Server {
@@ -134,6 +137,18 @@ where
 
 #[cfg(feature = "http3")]
 pub fn http3<F1, Fut>(func: F1) -> Server
+where
+    F1: Fn(http::Request<http_body_util::combinators::BoxBody<bytes::Bytes, h3::Error>>) -> Fut
+        + Clone
+        + Send
+        + 'static,
+    Fut: Future<Output = http::Response<reqwest::Body>> + Send + 'static,
+{
+    http3_with_addr("[::1]:0".parse().unwrap(), func)
+}
+
+#[cfg(feature = "http3")]
+pub fn http3_with_addr<F1, Fut>(addr: net::SocketAddr, func: F1) -> Server
 where
     F1: Fn(http::Request<http_body_util::combinators::BoxBody<bytes::Bytes, h3::Error>>) -> Fut
         + Clone
@@ -166,7 +181,7 @@ where
 
         let server_config = quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls_config).unwrap()));
         let endpoint = rt.block_on(async move {
-            quinn::Endpoint::server(server_config, "[::1]:0".parse().unwrap()).unwrap()
+            quinn::Endpoint::server(server_config, addr).unwrap()
         });
         let addr = endpoint.local_addr().unwrap();
 
@@ -189,6 +204,7 @@ where
                             }
                             Some(accepted) = endpoint.accept() => {
                                 let conn = accepted.await.expect("accepted");
+                                events_tx.send(Event::ConnectionAccepted).unwrap();
                                 let mut h3_conn = h3::server::Connection::new(h3_quinn::Connection::new(conn)).await.unwrap();
                                 let events_tx = events_tx.clone();
                                 let func = func.clone();
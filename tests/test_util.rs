@@ -0,0 +1,51 @@
+#![cfg(not(target_arch = "wasm32"))]
+#![cfg(not(feature = "rustls-tls-manual-roots-no-provider"))]
+mod support;
+use support::server;
+
+use std::time::{Duration, Instant};
+
+use reqwest::test_util::LatencyLayer;
+
+#[tokio::test]
+async fn latency_layer_delays_connect() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| async { http::Response::default() });
+
+    let url = format!("http://{}", server.addr());
+
+    let client = reqwest::Client::builder()
+        .connector_layer(
+            LatencyLayer::new(Duration::from_millis(100)).jitter(Duration::from_millis(50)),
+        )
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let res = client.get(url).send().await.unwrap();
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn latency_layer_triggers_connect_timeout() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| async { http::Response::default() });
+
+    let url = format!("http://{}", server.addr());
+
+    let client = reqwest::Client::builder()
+        .connector_layer(LatencyLayer::new(Duration::from_millis(500)))
+        .connect_timeout(Duration::from_millis(100))
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let err = client.get(url).send().await.unwrap_err();
+
+    assert!(err.is_connect() && err.is_timeout());
+}
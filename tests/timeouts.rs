@@ -65,6 +65,47 @@ async fn request_timeout() {
     assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
 }
 
+#[tokio::test]
+async fn method_timeouts() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| {
+        async {
+            // delay returning the response
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            http::Response::default()
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(100))
+        .unsafe_methods_timeout(Duration::from_secs(5))
+        .timeout_for(reqwest::Method::DELETE, Duration::from_millis(100))
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/slow", server.addr());
+
+    let err = client.get(&url).send().await.unwrap_err();
+    assert!(err.is_timeout());
+
+    let res = client.post(&url).send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let err = client.delete(&url).send().await.unwrap_err();
+    assert!(err.is_timeout());
+
+    // the timeout of a request takes precedence
+    let res = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::test]
 async fn connect_timeout() {
@@ -337,6 +378,33 @@ fn timeout_blocking_request() {
     assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
 }
 
+#[cfg(feature = "blocking")]
+#[test]
+fn method_timeouts_blocking_request() {
+    let _ = env_logger::try_init();
+
+    let client = reqwest::blocking::Client::builder()
+        .safe_methods_timeout(Duration::from_millis(500))
+        .timeout_for(reqwest::Method::PUT, Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let server = server::http(move |_req| {
+        async {
+            // delay returning the response
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            http::Response::default()
+        }
+    });
+
+    let url = format!("http://{}/slow", server.addr());
+    let err = client.get(&url).send().unwrap_err();
+    assert!(err.is_timeout());
+
+    let res = client.put(&url).send().unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
 #[cfg(feature = "blocking")]
 #[test]
 fn connect_timeout_blocking_request() {
@@ -446,3 +514,78 @@ async fn response_body_timeout_forwards_size_hint() {
 
     assert_eq!(res.content_length(), Some(5));
 }
+
+#[tokio::test]
+async fn dns_timeout_and_fallback() {
+    use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+    use std::error::Error as _;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    struct Hanging;
+
+    impl Resolve for Hanging {
+        fn resolve(&self, _: Name) -> Resolving {
+            Box::pin(futures_util::future::pending())
+        }
+    }
+
+    struct Fixed(SocketAddr);
+
+    impl Resolve for Fixed {
+        fn resolve(&self, _: Name) -> Resolving {
+            let addrs: Addrs = Box::new(std::iter::once(self.0));
+            Box::pin(futures_util::future::ready(Ok(addrs)))
+        }
+    }
+
+    let server = server::http(move |_req| async { http::Response::default() });
+    let url = format!("http://hanging.test:{}/", server.addr().port());
+
+    let err = reqwest::Client::builder()
+        .dns_resolver(Arc::new(Hanging))
+        .dns_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect());
+    assert!(err.is_timeout());
+
+    let res = reqwest::Client::builder()
+        .dns_resolver(Arc::new(Hanging))
+        .dns_timeout(Duration::from_millis(50))
+        .dns_fallback(Arc::new(Fixed(server.addr())))
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    // the timeout of the primary resolver is kept in the chain
+    let err = reqwest::Client::builder()
+        .dns_resolver(Arc::new(Hanging))
+        .dns_timeout(Duration::from_millis(50))
+        .dns_fallback(Arc::new(Hanging))
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap_err();
+    let mut chain = String::new();
+    let mut source = err.source();
+    while let Some(err) = source {
+        chain.push_str(&err.to_string());
+        chain.push('\n');
+        source = err.source();
+    }
+    assert!(
+        chain.contains("primary resolver failed: dns resolution timed out"),
+        "{chain}"
+    );
+}
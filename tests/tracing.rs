@@ -0,0 +1,111 @@
+#![cfg(not(target_arch = "wasm32"))]
+mod support;
+use support::server;
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+type Fields = Vec<(String, String)>;
+
+/// Records the spans that were created, with their fields.
+#[derive(Clone, Default)]
+struct Spans {
+    spans: Arc<Mutex<Vec<(&'static str, Fields)>>>,
+}
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push((field.name().to_owned(), format!("{value:?}")));
+    }
+}
+
+impl Spans {
+    fn names(&self) -> Vec<&'static str> {
+        let spans = self.spans.lock().unwrap();
+        spans.iter().map(|(name, _)| *name).collect()
+    }
+
+    fn field(&self, span: &str, field: &str) -> Option<String> {
+        let spans = self.spans.lock().unwrap();
+        let (_, fields) = spans.iter().find(|(name, _)| *name == span)?;
+        let (_, value) = fields.iter().rev().find(|(name, _)| name == field)?;
+        Some(value.clone())
+    }
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Vec::new();
+        span.record(&mut Visitor(&mut fields));
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((span.metadata().name(), fields));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+        values.record(&mut Visitor(fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn spans_for_request_lifecycle() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/redirect" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "/dst")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            http::Response::default()
+        }
+    });
+
+    let spans = Spans::default();
+    tracing::subscriber::with_default(spans.clone(), || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let url = format!("http://localhost:{}/redirect", server.addr().port());
+            let res = reqwest::Client::builder()
+                .no_proxy()
+                .build()
+                .unwrap()
+                .get(&url)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), reqwest::StatusCode::OK);
+        });
+    });
+
+    let names = spans.names();
+    for name in ["request", "dns", "connect", "redirect"] {
+        assert!(names.contains(&name), "{name} not in {names:?}");
+    }
+    assert_eq!(spans.field("request", "method").unwrap(), "GET");
+    assert_eq!(spans.field("request", "status").unwrap(), "200");
+    assert_eq!(spans.field("redirect", "status").unwrap(), "302");
+}
@@ -50,3 +50,137 @@ async fn http_upgrade() {
     upgraded.read_to_end(&mut buf).await.unwrap();
     assert_eq!(buf, b"bar=foo");
 }
+
+#[tokio::test]
+async fn tracked_upgrades_can_be_listed_and_aborted() {
+    let server = server::http(move |req| {
+        tokio::spawn(async move {
+            let mut upgraded = hyper_util::rt::TokioIo::new(hyper::upgrade::on(req).await.unwrap());
+            // keep the tunnel open until the client goes away
+            let mut buf = vec![];
+            let _ = upgraded.read_to_end(&mut buf).await;
+        });
+
+        async {
+            http::Response::builder()
+                .status(http::StatusCode::SWITCHING_PROTOCOLS)
+                .header(http::header::CONNECTION, "upgrade")
+                .header(http::header::UPGRADE, "foobar")
+                .body(reqwest::Body::default())
+                .unwrap()
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .track_upgrades(true)
+        .build()
+        .unwrap();
+    let url = format!("http://{}/tunnel", server.addr());
+    let upgrade = |label| {
+        let req = client
+            .get(&url)
+            .header(http::header::CONNECTION, "upgrade")
+            .header(http::header::UPGRADE, "foobar");
+        async move {
+            req.send()
+                .await
+                .unwrap()
+                .upgrade_labeled(label)
+                .await
+                .unwrap()
+        }
+    };
+
+    let mut first = upgrade("first").await;
+    let second = upgrade("second").await;
+
+    let upgrades = client.upgrades();
+    assert_eq!(upgrades.len(), 2);
+    assert_eq!(upgrades[0].label(), Some("first"));
+    assert_eq!(upgrades[0].url().path(), "/tunnel");
+    assert_eq!(upgrades[1].label(), Some("second"));
+    assert!(!upgrades[1].is_closed());
+
+    drop(second);
+    assert!(upgrades[1].is_closed());
+    assert_eq!(client.upgrades().len(), 1);
+
+    // a pending read fails once aborted
+    let handle = upgrades[0].clone();
+    let read = tokio::spawn(async move {
+        let mut buf = [0; 1];
+        let err = first.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionAborted);
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    handle.abort();
+    read.await.unwrap();
+    assert!(client.upgrades().is_empty());
+
+    // untracked by default
+    let client = reqwest::Client::new();
+    let _upgraded = client
+        .get(&url)
+        .header(http::header::CONNECTION, "upgrade")
+        .header(http::header::UPGRADE, "foobar")
+        .send()
+        .await
+        .unwrap()
+        .upgrade()
+        .await
+        .unwrap();
+    assert!(client.upgrades().is_empty());
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn http2_extended_connect() {
+    let server = server::http_with_config(
+        move |req| {
+            assert_eq!(req.method(), "CONNECT");
+            assert_eq!(req.version(), http::Version::HTTP_2);
+            assert_eq!(req.uri().path(), "/chat");
+            let protocol = req.extensions().get::<hyper::ext::Protocol>().unwrap();
+            assert_eq!(protocol.as_str(), "websocket");
+
+            tokio::spawn(async move {
+                let mut upgraded =
+                    hyper_util::rt::TokioIo::new(hyper::upgrade::on(req).await.unwrap());
+
+                let mut buf = vec![0; 7];
+                upgraded.read_exact(&mut buf).await.unwrap();
+                assert_eq!(buf, b"foo=bar");
+
+                upgraded.write_all(b"bar=foo").await.unwrap();
+                upgraded.shutdown().await.unwrap();
+            });
+
+            async { http::Response::default() }
+        },
+        |builder| {
+            builder.http2().enable_connect_protocol();
+        },
+    );
+
+    let client = reqwest::Client::builder()
+        .http2_prior_knowledge()
+        .build()
+        .unwrap();
+    let url = format!("http://{}/chat", server.addr());
+
+    let res = client
+        .request(http::Method::CONNECT, &url)
+        .protocol("websocket")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), http::StatusCode::OK);
+    let mut upgraded = res.upgrade().await.unwrap();
+
+    upgraded.write_all(b"foo=bar").await.unwrap();
+
+    let mut buf = vec![];
+    upgraded.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"bar=foo");
+}
@@ -84,6 +84,75 @@ async fn test_accept_encoding_header_is_not_changed_if_set() {
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 }
 
+#[tokio::test]
+async fn zstd_window_log_max() {
+    // a 2 MiB frame needs a window that large
+    let content = "test ".repeat(400_000);
+    let zstded_content = zstd_crate::encode_all(content.as_bytes(), 3).unwrap();
+
+    let server = server::http(move |_req| {
+        let zstded = zstded_content.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "zstd")
+                .body(zstded.into())
+                .unwrap()
+        }
+    });
+    let url = format!("http://{}/zstd", server.addr());
+
+    let client = reqwest::Client::builder()
+        .zstd_window_log_max(20)
+        .build()
+        .unwrap();
+    let err = client
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap_err();
+    assert!(err.is_decode());
+
+    let client = reqwest::Client::builder()
+        .zstd_window_log_max(22)
+        .build()
+        .unwrap();
+    let body = client.get(&url).send().await.unwrap().text().await.unwrap();
+    assert_eq!(body, content);
+}
+
+#[tokio::test]
+async fn zstd_decompression_offloaded() {
+    let content: String = (0..10_000).map(|i| format!("test {i}")).collect();
+    let zstded_content = zstd_crate::encode_all(content.as_bytes(), 3).unwrap();
+
+    let server = server::http(move |_req| {
+        let zstded = zstded_content.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "zstd")
+                .body(zstded.into())
+                .unwrap()
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .decompression_offload_threshold(1024)
+        .build()
+        .unwrap();
+    let body = client
+        .get(format!("http://{}/zstd", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, content);
+}
+
 async fn zstd_case(response_size: usize, chunk_size: usize) {
     use futures_util::stream::StreamExt;
 